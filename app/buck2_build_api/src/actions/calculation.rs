@@ -70,10 +70,14 @@ use crate::actions::error::ActionError;
 use crate::actions::error_handler::ActionErrorHandlerError;
 use crate::actions::error_handler::ActionSubErrorResult;
 use crate::actions::error_handler::StarlarkActionErrorContext;
+use crate::actions::error_handler::action_diagnostics;
 use crate::actions::execute::action_executor::ActionOutputs;
 use crate::actions::execute::action_executor::BuckActionExecutor;
 use crate::actions::execute::action_executor::HasActionExecutor;
+use crate::actions::execute::dice_data::GetCacheProbeTracker;
 use crate::actions::execute::error::ExecuteError;
+use crate::actions::execute::user_cancellation::UserCancellation;
+use crate::actions::execute::user_cancellation::UserCancellationError;
 use crate::artifact_groups::ArtifactGroup;
 use crate::artifact_groups::ArtifactGroupValues;
 use crate::artifact_groups::calculation::ensure_artifact_group_staged;
@@ -308,9 +312,21 @@ async fn build_action_inner(
         }
         None => buck2_data::ExpectedEligibleForDedupe::UnknownEligibility,
     };
-    let (execute_result, command_reports) = executor
-        .execute(waiting_data, ensured_inputs, action, cancellation)
-        .await;
+    let execute = executor.execute(waiting_data, ensured_inputs, action, cancellation);
+    let (execute_result, command_reports) = match UserCancellation::register() {
+        Some(mut user_cancellation) => {
+            tokio::select! {
+                result = execute => result,
+                () = user_cancellation.cancelled() => (
+                    Err(ExecuteError::from(buck2_error::Error::from(
+                        UserCancellationError::Cancelled,
+                    ))),
+                    Vec::new(),
+                ),
+            }
+        }
+        None => execute.await,
+    };
 
     let allow_omit_details = execute_result.is_ok();
 
@@ -437,9 +453,17 @@ async fn build_action_inner(
 
             error = Some(e.as_proto_field());
 
-            ctx.per_transaction_data()
-                .get_dispatcher()
-                .instant_event(e.as_proto_event());
+            // In a dry run, actions that miss the caches fail without running; that is reported
+            // at the end of the build rather than as an action error.
+            if ctx
+                .per_transaction_data()
+                .get_cache_probe_tracker()
+                .is_none()
+            {
+                ctx.per_transaction_data()
+                    .get_dispatcher()
+                    .instant_event(e.as_proto_event());
+            }
 
             action_result = Err(buck2_error::Error::from(e)
                 // Make sure to mark the error as emitted so that it is not printed out to console
@@ -500,6 +524,8 @@ async fn build_action_inner(
         .and_then(|r| r.status.execution_kind())
         .and_then(|k| k.re_platform_name());
 
+    let diagnostics = action_diagnostics(error_diagnostics.as_ref());
+
     (
         ActionExecutionData {
             action_result,
@@ -541,6 +567,7 @@ async fn build_action_inner(
             buck2_build_time,
             hostname,
             error_diagnostics,
+            diagnostics,
             input_files_bytes,
             invalidation_info,
             target_rule_type_name,
@@ -813,7 +840,8 @@ impl Key for BuildKey {
         // error types and try to cache non-transient error types, but practically there
         // are too many unknowns that may cause more harm than good if we cached errors.
         // So, don't cache it for now, until someday we decide to really need to.
-        x.is_ok()
+        // Dry-run outputs were never written to disk, so a later real build must recompute them.
+        x.as_ref().is_ok_and(|outputs| !outputs.is_dry_run())
     }
 
     fn value_serialize() -> impl ValueSerialize<Value = Self::Value> {
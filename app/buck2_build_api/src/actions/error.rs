@@ -15,6 +15,7 @@ use buck2_error::source_location::SourceLocation;
 use buck2_event_observer::display::TargetDisplayOptions;
 use buck2_event_observer::display::display_action_error;
 
+use crate::actions::error_handler::action_diagnostics;
 use crate::actions::execute::error::ExecuteError;
 
 #[derive(Debug)]
@@ -158,6 +159,7 @@ impl ActionError {
             key: Some(self.key.clone()),
             last_command: self.last_command.clone(),
             error_diagnostics: self.error_diagnostics.clone(),
+            diagnostics: action_diagnostics(self.error_diagnostics.as_ref()),
         }
     }
 }
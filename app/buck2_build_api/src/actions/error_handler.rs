@@ -11,8 +11,11 @@
 use std::cell::RefCell;
 
 use allocative::Allocative;
+use buck2_data::ActionDiagnostic;
+use buck2_data::ActionErrorDiagnostics;
 use buck2_data::ActionSubError;
 use buck2_data::CommandExecution;
+use buck2_data::DiagnosticSeverity;
 use console::strip_ansi_codes;
 use derive_more::Display;
 use starlark::environment::GlobalsBuilder;
@@ -561,6 +564,46 @@ impl StarlarkActionSubError {
     }
 }
 
+/// The sub-errors which point at a file, as diagnostics that tools can show against the source.
+pub(crate) fn action_diagnostics(
+    error_diagnostics: Option<&ActionErrorDiagnostics>,
+) -> Vec<ActionDiagnostic> {
+    let Some(buck2_data::action_error_diagnostics::Data::SubErrors(sub_errors)) =
+        error_diagnostics.and_then(|d| d.data.as_ref())
+    else {
+        return Vec::new();
+    };
+    sub_errors
+        .sub_errors
+        .iter()
+        .filter_map(|s| {
+            Some(ActionDiagnostic {
+                file: s.file.clone()?,
+                line: s.lnum,
+                column: s.col,
+                end_line: s.end_lnum,
+                end_column: s.end_col,
+                severity: diagnostic_severity(s.error_type.as_deref()) as i32,
+                message: s.message.clone().unwrap_or_else(|| s.category.clone()),
+                category: s.category.clone(),
+                code: s.error_number,
+            })
+        })
+        .collect()
+}
+
+fn diagnostic_severity(error_type: Option<&str>) -> DiagnosticSeverity {
+    // errorformat's `%t` captures a single letter, handlers using `new_sub_error` tend to
+    // spell it out.
+    match error_type.map(|t| t.trim().to_ascii_lowercase()).as_deref() {
+        Some("e" | "error" | "fatal") => DiagnosticSeverity::Error,
+        Some("w" | "warning" | "warn") => DiagnosticSeverity::Warning,
+        Some("i" | "info") => DiagnosticSeverity::Info,
+        Some("n" | "note" | "hint" | "help") => DiagnosticSeverity::Note,
+        _ => DiagnosticSeverity::Unknown,
+    }
+}
+
 #[starlark_module]
 #[starlark_types(
     StarlarkActionSubError as ActionSubError,
@@ -584,3 +627,57 @@ pub(crate) fn register_action_error_handler_for_testing(builder: &mut GlobalsBui
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use buck2_data::ActionSubErrors;
+    use buck2_data::action_error_diagnostics::Data;
+
+    use super::*;
+
+    fn sub_error(file: Option<&str>, error_type: Option<&str>) -> ActionSubError {
+        ActionSubError {
+            category: "rustc".to_owned(),
+            message: Some("expected `;`".to_owned()),
+            file: file.map(ToOwned::to_owned),
+            lnum: Some(3),
+            col: Some(7),
+            error_type: error_type.map(ToOwned::to_owned),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_action_diagnostics() {
+        let diagnostics = ActionErrorDiagnostics {
+            data: Some(Data::SubErrors(ActionSubErrors {
+                sub_errors: vec![
+                    sub_error(Some("src/lib.rs"), Some("e")),
+                    sub_error(None, Some("e")),
+                    sub_error(Some("src/main.rs"), Some("Warning")),
+                    sub_error(Some("src/main.rs"), Some("x")),
+                ],
+            })),
+        };
+        let res = action_diagnostics(Some(&diagnostics));
+        assert_eq!(
+            vec![
+                ("src/lib.rs", DiagnosticSeverity::Error),
+                ("src/main.rs", DiagnosticSeverity::Warning),
+                ("src/main.rs", DiagnosticSeverity::Unknown),
+            ],
+            res.iter()
+                .map(|d| (d.file.as_str(), d.severity()))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(Some(3), res[0].line);
+        assert_eq!("expected `;`", res[0].message);
+
+        assert_eq!(
+            Vec::<ActionDiagnostic>::new(),
+            action_diagnostics(Some(&ActionErrorDiagnostics {
+                data: Some(Data::HandlerInvocationError("boom".to_owned())),
+            }))
+        );
+    }
+}
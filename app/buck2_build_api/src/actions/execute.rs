@@ -12,3 +12,4 @@ pub mod action_execution_target;
 pub mod action_executor;
 pub mod dice_data;
 pub mod error;
+pub mod user_cancellation;
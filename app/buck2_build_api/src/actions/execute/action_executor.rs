@@ -86,6 +86,7 @@ use crate::actions::artifact::get_artifact_fs::GetArtifactFs;
 use crate::actions::execute::action_execution_target::ActionExecutionTarget;
 use crate::actions::execute::dice_data::CommandExecutorResponse;
 use crate::actions::execute::dice_data::DiceHasCommandExecutor;
+use crate::actions::execute::dice_data::GetCacheProbeTracker;
 use crate::actions::execute::dice_data::GetInvalidationTrackingConfig;
 use crate::actions::execute::dice_data::GetReClient;
 use crate::actions::execute::error::ExecuteError;
@@ -122,6 +123,9 @@ struct ActionOutputsData {
     // This is OK to skip because the hash is stored inline.
     #[allocative(skip)]
     fingerprint: blake3::Hash,
+    /// Whether these outputs were computed by a `--dry-run` build, and so were never written to
+    /// disk.
+    dry_run: bool,
 }
 
 /// Equality compares the fingerprint, making it O(1). That matters because
@@ -135,9 +139,12 @@ struct ActionOutputsData {
 /// equal as unordered maps may compare unequal here. `Key::equality` permits
 /// that direction (it only costs extra invalidation); the opposite direction
 /// rests on blake3 collision resistance.
+///
+/// Dry-run outputs never equal real ones, so that the outputs of a later real
+/// build replace them.
 impl PartialEq for ActionOutputsData {
     fn eq(&self, other: &Self) -> bool {
-        self.fingerprint == other.fingerprint
+        self.fingerprint == other.fingerprint && self.dry_run == other.dry_run
     }
 }
 
@@ -270,9 +277,25 @@ impl ActionOutputs {
         Self(Arc::new(ActionOutputsData {
             outputs,
             fingerprint,
+            dry_run: false,
+        }))
+    }
+
+    /// Mark these outputs as computed by a `--dry-run` build.
+    fn into_dry_run(self) -> Self {
+        Self(Arc::new(ActionOutputsData {
+            outputs: self.0.outputs.clone(),
+            fingerprint: self.0.fingerprint,
+            dry_run: true,
         }))
     }
 
+    /// Whether these outputs were computed by a `--dry-run` build. Such outputs don't exist on
+    /// disk and must not be reused by later builds.
+    pub fn is_dry_run(&self) -> bool {
+        self.0.dry_run
+    }
+
     pub fn from_single(artifact: BuildArtifactPath, value: ArtifactValue) -> Self {
         Self::new(buck_indexmap! {artifact => value})
     }
@@ -331,6 +354,10 @@ impl<'d> HasActionExecutor<'d> for DiceComputations<'d> {
         let http_client = self.per_transaction_data().get_http_client();
         let mergebase = self.per_transaction_data().get_mergebase();
         let invalidation_tracking_enabled = self.get_invalidation_tracking_config().enabled;
+        let dry_run = self
+            .per_transaction_data()
+            .get_cache_probe_tracker()
+            .is_some();
 
         Ok(BuckActionExecutor::new(
             CommandExecutor::new(
@@ -353,6 +380,7 @@ impl<'d> HasActionExecutor<'d> for DiceComputations<'d> {
             mergebase,
             invalidation_tracking_enabled,
             output_trees_download_config,
+            dry_run,
         ))
     }
 }
@@ -370,6 +398,7 @@ pub struct BuckActionExecutor<'d> {
     mergebase: &'d Mergebase,
     invalidation_tracking_enabled: bool,
     output_trees_download_config: OutputTreesDownloadConfig,
+    dry_run: bool,
 }
 
 impl<'d> BuckActionExecutor<'d> {
@@ -386,6 +415,7 @@ impl<'d> BuckActionExecutor<'d> {
         mergebase: &'d Mergebase,
         invalidation_tracking_enabled: bool,
         output_trees_download_config: OutputTreesDownloadConfig,
+        dry_run: bool,
     ) -> Self {
         BuckActionExecutor {
             command_executor,
@@ -400,6 +430,7 @@ impl<'d> BuckActionExecutor<'d> {
             mergebase,
             invalidation_tracking_enabled,
             output_trees_download_config,
+            dry_run,
         }
     }
 
@@ -690,6 +721,11 @@ impl ActionExecutionCtx for BuckActionExecutionContext<'_, '_> {
     }
 
     async fn cleanup_outputs(&self) -> buck2_error::Result<()> {
+        // A dry run must leave whatever a previous build put in buck-out alone.
+        if self.executor.dry_run {
+            return Ok(());
+        }
+
         // Delete all outputs before we start, so things will be clean.
         let output_paths = self
             .outputs
@@ -845,6 +881,8 @@ impl<'d> BuckActionExecutor<'d> {
                 } else {
                     Err(ExecuteError::MismatchedOutputs { declared, real })
                 }
+            } else if self.dry_run {
+                Ok((result.into_dry_run(), metadata))
             } else {
                 Ok((result, metadata))
             }
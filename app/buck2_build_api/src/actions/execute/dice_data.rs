@@ -17,6 +17,7 @@ use buck2_core::execution_types::executor_config::CommandExecutorConfig;
 use buck2_core::fs::artifact_path_resolver::ArtifactFs;
 use buck2_error::BuckErrorContext;
 use buck2_error::conversion::from_any_with_tag;
+use buck2_execute::execute::cache_probe::CacheProbeTracker;
 use buck2_execute::execute::cache_uploader::UploadCache;
 use buck2_execute::execute::prepared::PreparedCommandExecutor;
 use buck2_execute::execute::prepared::PreparedCommandOptionalExecutor;
@@ -129,6 +130,27 @@ impl GetReClient for UserComputationData {
     }
 }
 
+/// Set for `buck2 build --dry-run`, where the command executors only probe the caches.
+pub trait SetCacheProbeTracker {
+    fn set_cache_probe_tracker(&mut self, tracker: Arc<CacheProbeTracker>);
+}
+
+pub trait GetCacheProbeTracker {
+    fn get_cache_probe_tracker(&self) -> Option<Arc<CacheProbeTracker>>;
+}
+
+impl SetCacheProbeTracker for UserComputationData {
+    fn set_cache_probe_tracker(&mut self, tracker: Arc<CacheProbeTracker>) {
+        self.data.set(tracker);
+    }
+}
+
+impl GetCacheProbeTracker for UserComputationData {
+    fn get_cache_probe_tracker(&self) -> Option<Arc<CacheProbeTracker>> {
+        self.data.get::<Arc<CacheProbeTracker>>().ok().cloned()
+    }
+}
+
 #[derive(Debug, Clone, Copy, Dupe)]
 pub struct InvalidationTrackingConfig {
     pub enabled: bool,
@@ -0,0 +1,99 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is dual-licensed under either the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree or the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree. You may select, at your option, one of the
+ * above-listed licenses.
+ */
+
+//! Cancellation of single actions on request of the user, e.g. from the action inspector of the
+//! superconsole. Actions are identified by the span of their execution, which is what clients see
+//! in events.
+//!
+//! Cancelling drops the execution of the action, which then fails. Unlike cancellation by DICE,
+//! this doesn't wait for critical sections of the execution to complete.
+
+use std::sync::LazyLock;
+use std::sync::Mutex;
+
+use buck2_events::dispatch::current_span;
+use buck2_events::span::SpanId;
+use buck2_hash::BuckMutMap;
+use tokio::sync::oneshot;
+
+static RUNNING_ACTIONS: LazyLock<Mutex<BuckMutMap<SpanId, oneshot::Sender<()>>>> =
+    LazyLock::new(|| Mutex::new(BuckMutMap::default()));
+
+#[derive(Debug, buck2_error::Error)]
+#[buck2(tag = Input)]
+pub(crate) enum UserCancellationError {
+    #[error("Action was cancelled by the user")]
+    Cancelled,
+}
+
+/// Registration of an executing action, removed when dropped.
+pub(crate) struct UserCancellation {
+    span: SpanId,
+    receiver: oneshot::Receiver<()>,
+}
+
+impl UserCancellation {
+    /// Makes the action executing in the current span cancellable. Returns `None` outside of a
+    /// span.
+    pub(crate) fn register() -> Option<Self> {
+        let span = current_span()?;
+        let (sender, receiver) = oneshot::channel();
+        RUNNING_ACTIONS.lock().unwrap().insert(span, sender);
+        Some(Self { span, receiver })
+    }
+
+    /// Resolves when the user asks to cancel the action.
+    pub(crate) async fn cancelled(&mut self) {
+        if (&mut self.receiver).await.is_err() {
+            // Only happens if the registration was replaced, in which case nobody can cancel
+            // this action anymore.
+            futures::future::pending().await
+        }
+    }
+}
+
+impl Drop for UserCancellation {
+    fn drop(&mut self) {
+        RUNNING_ACTIONS.lock().unwrap().remove(&self.span);
+    }
+}
+
+/// Cancels the action executing in `span`. Returns `false` if there is no such action, e.g.
+/// because it already finished.
+pub fn cancel_action(span: SpanId) -> bool {
+    match RUNNING_ACTIONS.lock().unwrap().remove(&span) {
+        Some(sender) => sender.send(()).is_ok(),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::FutureExt;
+
+    use super::*;
+
+    #[test]
+    fn test_cancel_action() {
+        let span = SpanId::next();
+        let (sender, receiver) = oneshot::channel();
+        RUNNING_ACTIONS.lock().unwrap().insert(span, sender);
+        let mut registration = UserCancellation { span, receiver };
+
+        assert!(registration.cancelled().now_or_never().is_none());
+        assert!(cancel_action(span));
+        assert!(registration.cancelled().now_or_never().is_some());
+        // Already cancelled.
+        assert!(!cancel_action(span));
+
+        drop(registration);
+        assert!(!RUNNING_ACTIONS.lock().unwrap().contains_key(&span));
+    }
+}
@@ -25,9 +25,12 @@ use buck2_core::configuration::compatibility::MaybeCompatible;
 use buck2_core::pattern::pattern::Modifiers;
 use buck2_core::provider::label::ConfiguredProvidersLabel;
 use buck2_core::provider::label::ProvidersLabel;
+use buck2_core::provider::label::ProvidersName;
 use buck2_core::target::configured_target_label::ConfiguredTargetLabel;
+use buck2_data::ToProtoMessage;
 use buck2_error::BuckErrorOptionContext;
 use buck2_events::dispatch::console_message;
+use buck2_events::dispatch::instant_event;
 use buck2_hash::BuckMutMap;
 use buck2_node::nodes::configured_frontend::ConfiguredTargetNodeCalculation;
 use dice::LinearRecomputeDiceComputations;
@@ -72,6 +75,7 @@ pub mod build_report;
 pub mod detailed_aggregated_metrics;
 pub mod graph_properties;
 pub mod outputs;
+mod sarif;
 pub(crate) mod sketch_impl;
 
 /// The types of provider to build on the configured providers label
@@ -570,6 +574,7 @@ pub async fn build_configured_label(
     )
     .await
     {
+        top_level_target_built(&providers_label, false);
         event_consumer.consume_configured(ConfiguredBuildEvent {
             label: providers_label,
             variant: ConfiguredBuildEventVariant::Error { err: e },
@@ -577,6 +582,18 @@ pub async fn build_configured_label(
     }
 }
 
+/// Notifies subscribers that a top-level target finished building.
+fn top_level_target_built(providers_label: &ConfiguredProvidersLabel, success: bool) {
+    instant_event(buck2_data::TopLevelTargetBuilt {
+        target: Some(providers_label.target().as_proto()),
+        provider: match providers_label.name() {
+            ProvidersName::Default => None,
+            v => Some(v.to_string()),
+        },
+        success,
+    });
+}
+
 async fn build_configured_label_inner(
     event_consumer: &dyn BuildEventConsumer,
     ctx: LinearRecomputeDiceComputations<'_, '_>,
@@ -755,12 +772,24 @@ async fn build_configured_label_inner(
         })
         .collect();
 
+    let mut success = true;
     while let Some(variant) = tokio::task::unconstrained(outputs.next()).await {
+        if matches!(
+            &variant,
+            ConfiguredBuildEventVariant::Timeout
+                | ConfiguredBuildEventVariant::Execution(
+                    ConfiguredBuildEventExecutionVariant::BuildOutput { output: Err(_), .. }
+                        | ConfiguredBuildEventExecutionVariant::Validation { result: Err(_) }
+                )
+        ) {
+            success = false;
+        }
         event_consumer.consume_configured(ConfiguredBuildEvent {
             label: providers_label.dupe(),
             variant,
         })
     }
+    top_level_target_built(&providers_label, success);
 
     if !opts.graph_properties.is_empty() {
         let graph_properties = graph_properties::get_graph_properties(
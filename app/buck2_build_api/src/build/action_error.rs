@@ -34,14 +34,14 @@ pub(crate) struct ActionErrorBuildOptions {
 }
 
 #[derive(Debug, Clone, Serialize, PartialOrd, Ord, PartialEq, Eq)]
-struct BuildReportActionName {
-    category: String,
-    identifier: String,
+pub(super) struct BuildReportActionName {
+    pub(super) category: String,
+    pub(super) identifier: String,
 }
 
 #[derive(Debug, Clone, Serialize, PartialOrd, Ord, PartialEq, Eq)]
-struct BuildReportActionKey {
-    owner: String,
+pub(super) struct BuildReportActionKey {
+    pub(super) owner: String,
 }
 
 #[derive(Debug, Clone, Serialize, PartialOrd, Ord, PartialEq, Eq)]
@@ -76,16 +76,32 @@ struct BuildReportActionSubError {
     remediation: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize, PartialOrd, Ord, PartialEq, Eq)]
+pub(super) struct BuildReportActionDiagnostic {
+    pub(super) file: String,
+    pub(super) line: Option<u64>,
+    pub(super) column: Option<u64>,
+    pub(super) end_line: Option<u64>,
+    pub(super) end_column: Option<u64>,
+    // One of `error`, `warning`, `info`, `note` or `unknown`
+    pub(super) severity: String,
+    pub(super) message_content: String,
+    pub(super) category: String,
+    pub(super) code: Option<u64>,
+}
+
 /// DO NOT UPDATE WITHOUT UPDATING `docs/users/build_observability/build_report.md`!
 #[derive(Debug, Clone, Serialize, PartialOrd, Ord, PartialEq, Eq)]
 pub(crate) struct BuildReportActionError {
-    name: BuildReportActionName,
-    key: BuildReportActionKey,
-    digest: String,
+    pub(super) name: BuildReportActionName,
+    pub(super) key: BuildReportActionKey,
+    pub(super) digest: String,
     error_content: String,
     stderr_content: String,
     stdout_content: String,
     error_diagnostics: Option<BuildReportActionErrorDiagnostics>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(super) diagnostics: Vec<BuildReportActionDiagnostic>,
 }
 
 impl BuildReportActionError {
@@ -155,6 +171,26 @@ impl BuildReportActionError {
             })
         };
 
+        let diagnostics = if opts.exclude_action_error_diagnostics {
+            Vec::new()
+        } else {
+            error
+                .diagnostics
+                .iter()
+                .map(|d| BuildReportActionDiagnostic {
+                    file: d.file.clone(),
+                    line: d.line,
+                    column: d.column,
+                    end_line: d.end_line,
+                    end_column: d.end_column,
+                    severity: d.severity().to_string(),
+                    message_content: collector.update_string_cache(d.message.clone()),
+                    category: d.category.clone(),
+                    code: d.code,
+                })
+                .collect()
+        };
+
         let stderr = command_details.map_or(String::default(), |c| {
             console::strip_ansi_codes(&c.cmd_stderr).to_string()
         });
@@ -183,6 +219,31 @@ impl BuildReportActionError {
             stdout_content,
             digest: get_action_digest(command_details).unwrap_or_default(),
             error_diagnostics,
+            diagnostics,
+        }
+    }
+
+    #[cfg(test)]
+    pub(super) fn testing_new(
+        category: &str,
+        owner: &str,
+        digest: &str,
+        diagnostics: Vec<BuildReportActionDiagnostic>,
+    ) -> Self {
+        Self {
+            name: BuildReportActionName {
+                category: category.to_owned(),
+                identifier: String::new(),
+            },
+            key: BuildReportActionKey {
+                owner: owner.to_owned(),
+            },
+            digest: digest.to_owned(),
+            error_content: String::new(),
+            stderr_content: String::new(),
+            stdout_content: String::new(),
+            error_diagnostics: None,
+            diagnostics,
         }
     }
 }
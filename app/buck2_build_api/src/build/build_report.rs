@@ -81,6 +81,7 @@ use crate::build::detailed_aggregated_metrics::types::ArtifactPathSketchResult;
 use crate::build::detailed_aggregated_metrics::types::DetailedAggregatedMetrics;
 use crate::build::detailed_aggregated_metrics::types::TopLevelTargetAggregatedData;
 use crate::build::graph_properties::GraphPropertiesOptions;
+use crate::build::sarif::SarifLog;
 use crate::build::sketch_impl::DEFAULT_SKETCH_VERSION;
 use crate::build::sketch_impl::VersionedSketcher;
 use crate::bxl::types::BxlFunctionLabel;
@@ -102,14 +103,14 @@ enum BuildOutcome {
 /// iterate in a deterministic order - `BTreeMap`, not `BuckMutMap`.
 #[derive(Debug, Serialize)]
 pub struct BuildReport {
-    trace_id: TraceId,
+    pub(super) trace_id: TraceId,
     success: bool,
-    results: BTreeMap<EntryLabel, BuildReportEntry>,
+    pub(super) results: BTreeMap<EntryLabel, BuildReportEntry>,
     /// filled only when fill-out-failures is passed for Buck1 backcompat only
     failures: BTreeMap<EntryLabel, String>,
-    project_root: AbsNormPathBuf,
+    pub(super) project_root: AbsNormPathBuf,
     truncated: bool,
-    strings: BTreeMap<String, String>,
+    pub(super) strings: BTreeMap<String, String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     /// Build metrics aggregated across all targets.
     build_metrics: Option<AllTargetsBuildMetrics>,
@@ -117,6 +118,27 @@ pub struct BuildReport {
     total_configured_graph_sketch: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     error_category: Option<String>,
+    /// Counts of test results, for `buck2 test` only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tests: Option<BuildReportTestCounts>,
+}
+
+/// DO NOT UPDATE WITHOUT UPDATING `docs/users/build_observability/build_report.md`!
+#[derive(Default, Debug, Serialize)]
+pub struct BuildReportTestCounts {
+    pub passed: u64,
+    pub failed: u64,
+    pub fatal: u64,
+    pub timed_out: u64,
+    pub skipped: u64,
+    pub omitted: u64,
+    pub infra_failure: u64,
+    /// Tests that passed, but only after failing at least once.
+    pub flaky: u64,
+    /// Seconds spent running the tests of each target, or of each test case for targets sharded
+    /// by test case. Used to balance `buck2 test --shard-timings`.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub durations: BTreeMap<String, f64>,
 }
 
 /// The fields that stored in the unconfigured `BuildReportEntry` for buck1 backcompat.
@@ -145,7 +167,7 @@ struct MaybeConfiguredBuildReportEntry {
 #[derive(Default, Debug, Serialize)]
 pub(crate) struct ConfiguredBuildReportEntry {
     /// A list of errors that occurred while building this target
-    errors: Vec<BuildReportError>,
+    pub(super) errors: Vec<BuildReportError>,
     /// Remote artifact information, including hashes, etc.
     #[serde(skip_serializing_if = "BTreeMap::is_empty")]
     artifact_info: BTreeMap<Arc<str>, ArtifactInfo>,
@@ -238,7 +260,7 @@ pub(crate) struct AllTargetsBuildMetrics {
 
 /// DO NOT UPDATE WITHOUT UPDATING `docs/users/build_observability/build_report.md`!
 #[derive(Debug, Serialize)]
-struct BuildReportEntry {
+pub(super) struct BuildReportEntry {
     /// The buck1 build report did not support multiple configurations of the same target. We
     /// do, which is why we have the `configured` field below, which users should ideally use.
     /// This field is kept around for buck1 compatibility only and should ideally be removed.
@@ -250,11 +272,11 @@ struct BuildReportEntry {
     compatible: Option<MaybeConfiguredBuildReportEntry>,
 
     /// the configured entry
-    configured: BTreeMap<ConfigurationData, ConfiguredBuildReportEntry>,
+    pub(super) configured: BTreeMap<ConfigurationData, ConfiguredBuildReportEntry>,
 
     /// Errors that could not be associated with a particular configured version of the target,
     /// typically because they happened before configuration.
-    errors: Vec<BuildReportError>,
+    pub(super) errors: Vec<BuildReportError>,
 
     /// The path to the package where this target is defined, relative to the project root.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -263,23 +285,33 @@ struct BuildReportEntry {
 
 /// DO NOT UPDATE WITHOUT UPDATING `docs/users/build_observability/build_report.md`!
 #[derive(Debug, Clone, Serialize, PartialOrd, Ord, PartialEq, Eq)]
-struct BuildReportError {
-    message_content: String,
-    action_error: Option<BuildReportActionError>,
-    error_tags: Vec<String>,
+pub(super) struct BuildReportError {
+    pub(super) message_content: String,
+    pub(super) action_error: Option<BuildReportActionError>,
+    pub(super) error_tags: Vec<String>,
     /// An opaque index that can be use to de-duplicate errors. Two errors with the same
     /// cause index have the same cause
     ///
     /// For example, two targets in different packages may have the same cause (evaluation of
     /// common bzl file), but error stack will be different.
-    cause_index: usize,
-    error_category: String,
+    pub(super) cause_index: usize,
+    pub(super) error_category: String,
 }
 
-#[derive(Derivative, Serialize, Eq, PartialEq, Ord, PartialOrd, Hash, Clone)]
+#[derive(
+    Derivative,
+    derive_more::Display,
+    Serialize,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+    Hash,
+    Clone
+)]
 #[derivative(Debug)]
 #[serde(untagged)]
-enum EntryLabel {
+pub(super) enum EntryLabel {
     #[derivative(Debug = "transparent")]
     Target(TargetLabelWithModifiers),
     #[derivative(Debug = "transparent")]
@@ -296,6 +328,7 @@ pub struct BuildReportOpts {
     pub unstable_streaming_build_report_filename: String,
     pub unstable_exclude_action_error_diagnostics: bool,
     pub unstable_truncate_error_content: bool,
+    pub unstable_build_report_sarif: bool,
 }
 
 pub struct BuildReportCollector<'a> {
@@ -662,6 +695,7 @@ impl<'a> BuildReportCollector<'a> {
                 .map(|m| Self::convert_all_target_build_metrics(&m.all_targets_build_metrics)),
             total_configured_graph_sketch,
             error_category,
+            tests: None,
         })
     }
 
@@ -1163,6 +1197,7 @@ pub async fn build_report_opts<'a>(
         unstable_exclude_action_error_diagnostics: build_opts
             .unstable_exclude_action_error_diagnostics,
         unstable_truncate_error_content: build_opts.unstable_truncate_error_content,
+        unstable_build_report_sarif: build_opts.unstable_build_report_sarif,
     };
 
     Ok(build_report_opts)
@@ -1170,6 +1205,20 @@ pub async fn build_report_opts<'a>(
 
 fn write_or_serialize_build_report(
     build_report: &BuildReport,
+    sarif: bool,
+    filename: &str,
+    project_root: &ProjectRoot,
+    cwd: &ProjectRelativePath,
+) -> Result<Option<String>, buck2_error::Error> {
+    if sarif {
+        write_or_serialize_json(&SarifLog::new(build_report), filename, project_root, cwd)
+    } else {
+        write_or_serialize_json(build_report, filename, project_root, cwd)
+    }
+}
+
+fn write_or_serialize_json(
+    build_report: &impl Serialize,
     filename: &str,
     project_root: &ProjectRoot,
     cwd: &ProjectRelativePath,
@@ -1206,8 +1255,9 @@ pub fn write_build_report(
     detailed_metrics: Option<DetailedAggregatedMetrics>,
     action_graph_sketch_result: Option<ActionGraphSketchResult>,
     artifact_path_sketch_result: Option<ArtifactPathSketchResult>,
+    test_counts: Option<BuildReportTestCounts>,
 ) -> Result<Option<String>, buck2_error::Error> {
-    let build_report = BuildReportCollector::convert(
+    let mut build_report = BuildReportCollector::convert(
         trace_id,
         artifact_fs,
         cell_resolver,
@@ -1227,8 +1277,11 @@ pub fn write_build_report(
         opts.graph_properties_opts,
     )?;
 
+    build_report.tests = test_counts;
+
     write_or_serialize_build_report(
         &build_report,
+        opts.unstable_build_report_sarif,
         &opts.unstable_build_report_filename,
         project_root,
         cwd,
@@ -1269,6 +1322,7 @@ pub fn write_bxl_build_report(
 
     write_or_serialize_build_report(
         &build_report,
+        opts.unstable_build_report_sarif,
         &opts.unstable_build_report_filename,
         project_root,
         cwd,
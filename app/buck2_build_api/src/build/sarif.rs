@@ -0,0 +1,529 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is dual-licensed under either the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree or the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree. You may select, at your option, one of the
+ * above-listed licenses.
+ */
+
+//! The failures in a build report as a [SARIF 2.1.0](https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html)
+//! log, for code review tools that annotate findings inline.
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use serde::Serialize;
+use serde_json::json;
+use url::Url;
+
+use crate::build::action_error::BuildReportActionDiagnostic;
+use crate::build::action_error::BuildReportActionError;
+use crate::build::build_report::BuildReport;
+use crate::build::build_report::BuildReportError;
+
+/// The rule for errors that have no error tags.
+const BUILD_FAILURE_RULE: &str = "build_failure";
+
+impl SarifLog {
+    pub(crate) fn new(report: &BuildReport) -> Self {
+        let mut errors = Vec::new();
+        for (label, entry) in &report.results {
+            for (configuration, configured) in &entry.configured {
+                let owner = format!("{label} ({configuration})");
+                errors.extend(configured.errors.iter().map(|e| (owner.clone(), e)));
+            }
+            let owner = label.to_string();
+            errors.extend(entry.errors.iter().map(|e| (owner.clone(), e)));
+        }
+        Self::from_errors(
+            report.trace_id.to_string(),
+            report.project_root.as_path(),
+            &report.strings,
+            errors,
+        )
+    }
+
+    /// The log of the errors of a build, each with the target it failed. Relative paths in
+    /// diagnostics are relative to the project root.
+    fn from_errors<'a>(
+        trace_id: String,
+        project_root: &Path,
+        strings: &BTreeMap<String, String>,
+        errors: impl IntoIterator<Item = (String, &'a BuildReportError)>,
+    ) -> Self {
+        let mut collector = SarifCollector {
+            project_root,
+            strings,
+            results: BTreeMap::new(),
+        };
+        for (owner, error) in errors {
+            collector.error(&owner, error);
+        }
+        Self::single_run(
+            "buck2",
+            "https://buck2.build",
+            Some(SarifAutomationDetails { id: trace_id }),
+            project_root,
+            collector.results.into_values().collect(),
+        )
+    }
+}
+
+struct SarifCollector<'a> {
+    project_root: &'a Path,
+    strings: &'a BTreeMap<String, String>,
+    /// Keyed by the error's cause index, and the index of the diagnostic within it, so that
+    /// an error shared by several targets is reported once, against all of them.
+    results: BTreeMap<(usize, usize), SarifResult>,
+}
+
+impl SarifCollector<'_> {
+    fn string(&self, key: &str) -> String {
+        self.strings.get(key).cloned().unwrap_or_default()
+    }
+
+    fn error(&mut self, owner: &str, error: &BuildReportError) {
+        let action_error = error.action_error.as_ref();
+        let owner = SarifLogicalLocation {
+            fully_qualified_name: action_error
+                .map_or_else(|| owner.to_owned(), |a| a.key.owner.clone()),
+            kind: "module",
+        };
+        let diagnostics = action_error.map_or(&[][..], |a| a.diagnostics.as_slice());
+
+        if diagnostics.is_empty() {
+            let key = (error.cause_index, 0);
+            if !self.add_owner(key, owner.clone()) {
+                let result = SarifResult {
+                    rule_id: error
+                        .error_tags
+                        .first()
+                        .map_or_else(|| BUILD_FAILURE_RULE.to_owned(), |t| t.to_lowercase()),
+                    rule_index: 0,
+                    level: "error",
+                    message: SarifMessage {
+                        text: self.string(&error.message_content),
+                    },
+                    locations: vec![SarifLocation {
+                        physical_location: None,
+                        logical_locations: vec![owner],
+                    }],
+                    properties: properties(error, action_error, None),
+                };
+                self.results.insert(key, result);
+            }
+            return;
+        }
+
+        for (i, diagnostic) in diagnostics.iter().enumerate() {
+            let key = (error.cause_index, i);
+            if self.add_owner(key, owner.clone()) {
+                continue;
+            }
+            let result = SarifResult {
+                rule_id: diagnostic.category.clone(),
+                rule_index: 0,
+                level: level(&diagnostic.severity),
+                message: SarifMessage {
+                    text: self.string(&diagnostic.message_content),
+                },
+                locations: vec![SarifLocation {
+                    physical_location: Some(physical_location(self.project_root, diagnostic)),
+                    logical_locations: vec![owner.clone()],
+                }],
+                properties: properties(error, action_error, diagnostic.code),
+            };
+            self.results.insert(key, result);
+        }
+    }
+
+    /// Record that an already reported result also applies to `owner`.
+    fn add_owner(&mut self, key: (usize, usize), owner: SarifLogicalLocation) -> bool {
+        match self.results.get_mut(&key) {
+            Some(result) => {
+                let owners = &mut result.locations[0].logical_locations;
+                if !owners.contains(&owner) {
+                    owners.push(owner);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+fn properties(
+    error: &BuildReportError,
+    action_error: Option<&BuildReportActionError>,
+    code: Option<u64>,
+) -> BTreeMap<&'static str, serde_json::Value> {
+    let mut properties = BTreeMap::from([
+        ("errorCategory", json!(error.error_category)),
+        ("errorTags", json!(error.error_tags)),
+    ]);
+    if let Some(action_error) = action_error {
+        properties.insert("actionCategory", json!(action_error.name.category));
+        if !action_error.name.identifier.is_empty() {
+            properties.insert("actionIdentifier", json!(action_error.name.identifier));
+        }
+        if !action_error.digest.is_empty() {
+            properties.insert("actionDigest", json!(action_error.digest));
+        }
+    }
+    if let Some(code) = code {
+        properties.insert("code", json!(code));
+    }
+    properties
+}
+
+/// SARIF has no equivalent of a diagnostic with an unknown severity. It came from a failed
+/// action, so call it an error.
+fn level(severity: &str) -> &'static str {
+    match severity {
+        "warning" => "warning",
+        "info" | "note" => "note",
+        _ => "error",
+    }
+}
+
+fn physical_location(
+    project_root: &Path,
+    diagnostic: &BuildReportActionDiagnostic,
+) -> SarifPhysicalLocation {
+    SarifPhysicalLocation {
+        artifact_location: SarifArtifactLocation::new(project_root, &diagnostic.file),
+        // SARIF regions must start at a line, so without one the whole file is meant.
+        region: diagnostic.line.map(|start_line| SarifRegion {
+            start_line,
+            start_column: diagnostic.column,
+            end_line: diagnostic.end_line,
+            end_column: diagnostic.end_column,
+        }),
+    }
+}
+
+// The SARIF types and helpers from here to the tests are the same in buck2's build report
+// (`app/buck2_build_api/src/build/sarif.rs`) and in `starlark --sarif`
+// (`starlark-rust/starlark_bin/bin/sarif.rs`). Keep them identical.
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const SARIF_VERSION: &str = "2.1.0";
+
+/// The base id of the paths relative to the root.
+const SRCROOT: &str = "%SRCROOT%";
+
+#[derive(Debug, Serialize)]
+pub(crate) struct SarifLog {
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    version: &'static str,
+    runs: Vec<SarifRun>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifRun {
+    tool: SarifTool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    automation_details: Option<SarifAutomationDetails>,
+    original_uri_base_ids: BTreeMap<&'static str, SarifArtifactLocation>,
+    results: Vec<SarifResult>,
+}
+
+#[derive(Debug, Serialize)]
+struct SarifTool {
+    driver: SarifDriver,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifDriver {
+    name: &'static str,
+    information_uri: &'static str,
+    rules: Vec<SarifRule>,
+}
+
+#[derive(Debug, Serialize)]
+struct SarifRule {
+    id: String,
+}
+
+#[derive(Debug, Serialize)]
+struct SarifAutomationDetails {
+    id: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifResult {
+    rule_id: String,
+    rule_index: usize,
+    level: &'static str,
+    message: SarifMessage,
+    locations: Vec<SarifLocation>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    properties: BTreeMap<&'static str, serde_json::Value>,
+}
+
+#[derive(Debug, Serialize)]
+struct SarifMessage {
+    text: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifLocation {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    physical_location: Option<SarifPhysicalLocation>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    logical_locations: Vec<SarifLogicalLocation>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifPhysicalLocation {
+    artifact_location: SarifArtifactLocation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    region: Option<SarifRegion>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifArtifactLocation {
+    uri: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    uri_base_id: Option<&'static str>,
+}
+
+/// Lines and columns are 1-based, and the end column is exclusive.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifRegion {
+    start_line: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    start_column: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    end_line: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    end_column: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+struct SarifLogicalLocation {
+    fully_qualified_name: String,
+    kind: &'static str,
+}
+
+impl SarifLog {
+    /// A log with a single run of `driver_name`, whose rules are the rules of the results.
+    fn single_run(
+        driver_name: &'static str,
+        information_uri: &'static str,
+        automation_details: Option<SarifAutomationDetails>,
+        root: &Path,
+        mut results: Vec<SarifResult>,
+    ) -> Self {
+        let rules: BTreeSet<&str> = results.iter().map(|r| r.rule_id.as_str()).collect();
+        let rules: Vec<String> = rules.into_iter().map(str::to_owned).collect();
+        for result in &mut results {
+            result.rule_index = rules.binary_search(&result.rule_id).unwrap_or_default();
+        }
+        SarifLog {
+            schema: SARIF_SCHEMA,
+            version: SARIF_VERSION,
+            runs: vec![SarifRun {
+                tool: SarifTool {
+                    driver: SarifDriver {
+                        name: driver_name,
+                        information_uri,
+                        rules: rules.into_iter().map(|id| SarifRule { id }).collect(),
+                    },
+                },
+                automation_details,
+                original_uri_base_ids: BTreeMap::from([(
+                    SRCROOT,
+                    SarifArtifactLocation {
+                        uri: Url::from_directory_path(root)
+                            .map_or_else(|()| root.display().to_string(), String::from),
+                        uri_base_id: None,
+                    },
+                )]),
+                results,
+            }],
+        }
+    }
+}
+
+impl SarifArtifactLocation {
+    /// The location of `path`. Relative paths are resolved against `root`, and are reported
+    /// relative to the `%SRCROOT%` base id.
+    fn new(root: &Path, path: &str) -> Self {
+        if Path::new(path).is_relative() {
+            let relative = match (
+                Url::from_directory_path(root),
+                Url::from_file_path(root.join(path)),
+            ) {
+                (Ok(root), Ok(url)) => root.make_relative(&url),
+                _ => None,
+            };
+            if let Some(uri) = relative {
+                return SarifArtifactLocation {
+                    uri,
+                    uri_base_id: Some(SRCROOT),
+                };
+            }
+        }
+        SarifArtifactLocation {
+            uri: Url::from_file_path(path).map_or_else(|()| path.to_owned(), String::from),
+            uri_base_id: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(
+        cause_index: usize,
+        message_content: &str,
+        action_error: Option<BuildReportActionError>,
+    ) -> BuildReportError {
+        BuildReportError {
+            message_content: message_content.to_owned(),
+            action_error,
+            error_tags: vec!["ANALYSIS".to_owned()],
+            cause_index,
+            error_category: "USER".to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_sarif_log() {
+        let (project_root, project_root_uri) = if cfg!(windows) {
+            ("C:\\repo", "file:///C:/repo/")
+        } else {
+            ("/repo", "file:///repo/")
+        };
+        let strings = BTreeMap::from([
+            ("0".to_owned(), "Analysis failed".to_owned()),
+            ("1".to_owned(), "expected `;`".to_owned()),
+        ]);
+        let action_error = BuildReportActionError::testing_new(
+            "cxx_compile",
+            "root//:main",
+            "abc:10",
+            vec![BuildReportActionDiagnostic {
+                file: "src/my main.cpp".to_owned(),
+                line: Some(3),
+                column: Some(5),
+                end_line: None,
+                end_column: None,
+                severity: "error".to_owned(),
+                message_content: "1".to_owned(),
+                category: "syntax".to_owned(),
+                code: Some(2),
+            }],
+        );
+        let analysis_error = error(0, "0", None);
+        let compile_error = error(1, "", Some(action_error));
+        let log = SarifLog::from_errors(
+            "trace".to_owned(),
+            Path::new(project_root),
+            &strings,
+            [
+                ("root//:a (cfg)".to_owned(), &analysis_error),
+                // The same error is reported once, against both targets.
+                ("root//:b (cfg)".to_owned(), &analysis_error),
+                ("root//:main (cfg)".to_owned(), &compile_error),
+            ],
+        );
+
+        assert_eq!(
+            json!({
+                "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+                "version": "2.1.0",
+                "runs": [{
+                    "tool": {
+                        "driver": {
+                            "name": "buck2",
+                            "informationUri": "https://buck2.build",
+                            "rules": [{"id": "analysis"}, {"id": "syntax"}],
+                        },
+                    },
+                    "automationDetails": {"id": "trace"},
+                    "originalUriBaseIds": {"%SRCROOT%": {"uri": project_root_uri}},
+                    "results": [
+                        {
+                            "ruleId": "analysis",
+                            "ruleIndex": 0,
+                            "level": "error",
+                            "message": {"text": "Analysis failed"},
+                            "locations": [{
+                                "logicalLocations": [
+                                    {"fullyQualifiedName": "root//:a (cfg)", "kind": "module"},
+                                    {"fullyQualifiedName": "root//:b (cfg)", "kind": "module"},
+                                ],
+                            }],
+                            "properties": {"errorCategory": "USER", "errorTags": ["ANALYSIS"]},
+                        },
+                        {
+                            "ruleId": "syntax",
+                            "ruleIndex": 1,
+                            "level": "error",
+                            "message": {"text": "expected `;`"},
+                            "locations": [{
+                                "physicalLocation": {
+                                    "artifactLocation": {
+                                        "uri": "src/my%20main.cpp",
+                                        "uriBaseId": "%SRCROOT%",
+                                    },
+                                    "region": {"startLine": 3, "startColumn": 5},
+                                },
+                                "logicalLocations": [
+                                    {"fullyQualifiedName": "root//:main", "kind": "module"},
+                                ],
+                            }],
+                            "properties": {
+                                "actionCategory": "cxx_compile",
+                                "actionDigest": "abc:10",
+                                "code": 2,
+                                "errorCategory": "USER",
+                                "errorTags": ["ANALYSIS"],
+                            },
+                        },
+                    ],
+                }],
+            }),
+            serde_json::to_value(&log).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_artifact_location() {
+        let root = if cfg!(windows) { "C:\\repo" } else { "/repo" };
+        let location = SarifArtifactLocation::new(Path::new(root), "src/b#1.rs");
+        assert_eq!(Some(SRCROOT), location.uri_base_id);
+        assert_eq!("src/b%231.rs", location.uri);
+
+        let absolute = if cfg!(windows) {
+            "C:\\other\\x y.rs"
+        } else {
+            "/other/x y.rs"
+        };
+        let location = SarifArtifactLocation::new(Path::new(root), absolute);
+        assert_eq!(None, location.uri_base_id);
+        assert_eq!(
+            if cfg!(windows) {
+                "file:///C:/other/x%20y.rs"
+            } else {
+                "file:///other/x%20y.rs"
+            },
+            location.uri
+        );
+    }
+}
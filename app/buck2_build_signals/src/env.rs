@@ -174,6 +174,9 @@ pub struct BuildSignalsContext {
     pub metadata: IntentionallyStdHashMap<String, String>,
     pub isolation_prefix: FileNameBuf,
     pub early_command_timing: EarlyCommandTiming,
+    /// Log the whole build graph along with the critical path, for
+    /// `buck2 log critical-path --what-if`.
+    pub log_build_graph: bool,
 }
 
 /// Created along with the BuildSignalsInstaller (ideally, BuildSignalsInstaller's definition would
@@ -231,10 +234,18 @@ where
     result
 }
 
+#[derive(Copy, Clone, Dupe)]
+struct LogBuildGraph(bool);
+
 pub trait HasCriticalPathBackend {
     fn set_critical_path_backend(&mut self, backend: CriticalPathBackendName);
 
     fn get_critical_path_backend(&self) -> CriticalPathBackendName;
+
+    fn set_log_build_graph(&mut self, log_build_graph: bool);
+
+    /// Whether the build graph should be logged, see `BuildSignalsContext::log_build_graph`.
+    fn get_log_build_graph(&self) -> bool;
 }
 
 impl HasCriticalPathBackend for UserComputationData {
@@ -248,6 +259,16 @@ impl HasCriticalPathBackend for UserComputationData {
             .get::<CriticalPathBackendName>()
             .expect("CriticalPathBackendName should be set")
     }
+
+    fn set_log_build_graph(&mut self, log_build_graph: bool) {
+        self.data.set(LogBuildGraph(log_build_graph));
+    }
+
+    fn get_log_build_graph(&self) -> bool {
+        self.data
+            .get::<LogBuildGraph>()
+            .is_ok_and(|log_build_graph| log_build_graph.0)
+    }
 }
 
 #[cfg(test)]
@@ -99,6 +99,7 @@ impl BuildListenerBackend for LoggingBackend {
             num_nodes: 0,
             num_edges: 0,
             top_level_targets: Default::default(),
+            build_graph: Vec::new(),
         })
     }
 
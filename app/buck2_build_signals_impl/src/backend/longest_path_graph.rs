@@ -9,6 +9,7 @@
  */
 
 use std::time::Duration;
+use std::time::Instant;
 
 use buck2_analysis::analysis::calculation::AnalysisKey;
 use buck2_build_signals::env::CriticalPathBackendName;
@@ -44,12 +45,15 @@ use crate::NodeData;
 use crate::NodeExtraData;
 use crate::NodeKey;
 use crate::backend::backend::BuildListenerBackend;
+use crate::duration_to_proto_saturating;
 
 /// An implementation of critical path that uses a longest-paths graph in order to produce
 /// potential savings in addition to the critical path.
 pub(crate) struct LongestPathGraphBackend {
     builder: Result<GraphBuilder<NodeKey, NodeData>, CriticalPathError>,
     top_level_targets: Vec<TopLevelTarget>,
+    /// If set, the whole graph is logged, with times relative to this instant.
+    log_graph_from: Option<Instant>,
 }
 
 /// Represents nodes that block us "seeing" other parts of the graph until they finish evaluating.
@@ -59,10 +63,11 @@ struct TopLevelTarget {
 }
 
 impl LongestPathGraphBackend {
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(log_graph_from: Option<Instant>) -> Self {
         Self {
             builder: Ok(GraphBuilder::new()),
             top_level_targets: Vec::new(),
+            log_graph_from,
         }
     }
 }
@@ -245,6 +250,10 @@ impl BuildListenerBackend for LongestPathGraphBackend {
 
         let slowest_path = slowest_path?;
         let (critical_path, critical_path_for_top_level_targets) = cp_res?;
+        let build_graph = match self.log_graph_from {
+            Some(start) => build_graph_proto(&graph, &keys, &data, start)?,
+            None => Vec::new(),
+        };
 
         Ok(BuildInfo {
             critical_path,
@@ -252,6 +261,7 @@ impl BuildListenerBackend for LongestPathGraphBackend {
             num_nodes: graph.vertices_count() as _,
             num_edges: graph.edges_count() as _,
             top_level_targets: critical_path_for_top_level_targets,
+            build_graph,
         })
     }
 
@@ -344,6 +354,53 @@ fn compute_critical_paths(
     ))
 }
 
+/// The whole graph, with every node after its dependencies, as logged for
+/// `buck2 log critical-path --what-if`.
+fn build_graph_proto(
+    graph: &Graph,
+    keys: &VertexKeys<NodeKey>,
+    data: &VertexData<NodeData>,
+    start: Instant,
+) -> Result<Vec<buck2_data::BuildGraphNode>, CriticalPathError> {
+    // The topological order puts dependents first.
+    let order = graph
+        .topo_sort()
+        .map_err(|e| format_topo_sort_cycle_error(e, keys))?;
+
+    let mut index = graph.allocate_vertex_data(0u32);
+    for (i, vertex) in order.iter().rev().enumerate() {
+        index[*vertex] = i as u32;
+    }
+
+    Ok(order
+        .iter()
+        .rev()
+        .map(|vertex| {
+            let node = &data[*vertex];
+            buck2_data::BuildGraphNode {
+                span_ids: node
+                    .span_ids
+                    .iter()
+                    .map(|span_id| (*span_id).into())
+                    .collect(),
+                deps: graph.iter_edges(*vertex).map(|dep| index[dep]).collect(),
+                start_offset_ns: node
+                    .duration
+                    .total
+                    .start()
+                    .saturating_duration_since(start)
+                    .as_nanos()
+                    .try_into()
+                    .unwrap_or(u64::MAX),
+                duration: Some(duration_to_proto_saturating(
+                    node.duration.critical_path_duration(),
+                )),
+                queue_duration: node.duration.queue.map(duration_to_proto_saturating),
+            }
+        })
+        .collect())
+}
+
 /// Computes the "slowest path" where each node's predecessor is the dependency that finished last.
 /// This differs from critical path where predecessors have the greatest critical path length.
 /// The slowest path makes waiting time directly attributable to what a node is immediately waiting on.
@@ -770,7 +770,15 @@ impl DeferredBuildSignals for DeferredBuildSignalsImpl {
     ) -> Box<dyn FinishBuildSignals> {
         let handle = match backend {
             CriticalPathBackendName::LongestPathGraph => {
-                start_backend(events, self.receiver, LongestPathGraphBackend::new(), ctx)
+                let log_graph_from = ctx
+                    .log_build_graph
+                    .then_some(ctx.early_command_timing.command_start);
+                start_backend(
+                    events,
+                    self.receiver,
+                    LongestPathGraphBackend::new(log_graph_from),
+                    ctx,
+                )
             }
             CriticalPathBackendName::Logging => start_backend(
                 events.dupe(),
@@ -884,6 +892,7 @@ where
             num_nodes,
             num_edges,
             top_level_targets,
+            build_graph,
         } = self.backend.finish(
             self.first_analysis_for_anon_target
                 .into_iter()
@@ -914,6 +923,7 @@ where
             num_edges,
             backend_name: Some(T::name().to_string()),
             top_level_targets,
+            build_graph,
         });
         Ok(())
     }
@@ -1232,6 +1242,8 @@ pub(crate) struct BuildInfo {
     num_edges: u64,
     /// Critical path for top level targets
     top_level_targets: Vec<(ConfiguredTargetLabel, Duration)>,
+    /// The whole graph, if it should be logged.
+    build_graph: Vec<buck2_data::BuildGraphNode>,
 }
 
 /// Entry in a detailed critical path, including metadata about timing and dependencies.
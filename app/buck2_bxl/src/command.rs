@@ -471,6 +471,7 @@ impl BxlServerCommand {
                     .clone(),
                 unstable_exclude_action_error_diagnostics: false,
                 unstable_truncate_error_content: false,
+                unstable_build_report_sarif: false,
             };
 
             write_bxl_build_report(
@@ -18,6 +18,7 @@ use buck2_cli_proto::TargetCfg;
 use buck2_cli_proto::build_request::BuildProviders;
 use buck2_cli_proto::build_request::ResponseOptions;
 use buck2_cli_proto::build_request::build_providers;
+use buck2_cli_proto::dry_run_report;
 use buck2_client_ctx::client_ctx::ClientCommandContext;
 use buck2_client_ctx::command_outcome::CommandOutcome;
 use buck2_client_ctx::common::BuckArgMatches;
@@ -123,6 +124,11 @@ pub struct BuildCommand {
     )]
     output_path: Option<OutputDestinationArg>,
 
+    /// Look up every action in the action cache instead of running it, and print which actions
+    /// are cached and which would execute. Nothing is executed, materialized or uploaded.
+    #[clap(long, conflicts_with_all = &["output_path", "materializations"])]
+    dry_run: bool,
+
     #[clap(name = "TARGET_PATTERNS", help = "Patterns to build", value_hint = clap::ValueHint::Other)]
     patterns: Vec<String>,
 
@@ -243,6 +249,13 @@ impl StreamingCommand for BuildCommand {
     ) -> ExitResult {
         let context = ctx.client_context(matches, &self)?;
 
+        let mut build_opts = self.build_opts.to_proto();
+        if self.dry_run {
+            build_opts.dry_run = true;
+            // Actions that miss the cache fail, so keep going to look up everything else.
+            build_opts.keep_going = true;
+        }
+
         let result = buckd
             .with_flushing()
             .build(
@@ -260,7 +273,7 @@ impl StreamingCommand for BuildCommand {
                             || self.output_path.is_some(),
                         return_run_args: false,
                     }),
-                    build_opts: Some(self.build_opts.to_proto()),
+                    build_opts: Some(build_opts),
                     final_artifact_materializations: self.materializations.to_proto() as i32,
                     final_artifact_uploads: self.upload_final_artifacts.to_proto() as i32,
                     target_universe: self.target_cfg.target_universe,
@@ -307,6 +320,10 @@ impl StreamingCommand for BuildCommand {
 
         let mut stdout = Vec::new();
 
+        if let Some(dry_run_report) = &response.dry_run_report {
+            print_dry_run_report(&mut stdout, &console, dry_run_report)?;
+        }
+
         if let Some(build_report) = response.serialized_build_report {
             stdout.extend(build_report.as_bytes());
             writeln!(&mut stdout)?;
@@ -400,6 +417,42 @@ pub(crate) fn print_build_failed(console: &FinalConsole) -> buck2_error::Result<
     console.print_error("BUILD FAILED")
 }
 
+/// Prints one `<outcome>\t<action>\t<digest>` line per action looked up, and a summary.
+fn print_dry_run_report(
+    mut out: impl Write,
+    console: &FinalConsole,
+    report: &buck2_cli_proto::DryRunReport,
+) -> buck2_error::Result<()> {
+    let mut cache_hits = 0;
+    let mut would_execute = 0;
+    for action in &report.actions {
+        let outcome = match action.outcome() {
+            dry_run_report::Outcome::ActionCacheHit => {
+                cache_hits += 1;
+                "cache_hit"
+            }
+            dry_run_report::Outcome::RemoteDepFileCacheHit => {
+                cache_hits += 1;
+                "dep_file_cache_hit"
+            }
+            dry_run_report::Outcome::WouldExecute => {
+                would_execute += 1;
+                "would_execute"
+            }
+        };
+        writeln!(out, "{}\t{}\t{}", outcome, action.action, action.digest)?;
+    }
+    console.print_stderr(&format!(
+        "Dry run: {cache_hits} cached, {would_execute} would execute"
+    ))?;
+    if would_execute > 0 {
+        console.print_stderr(
+            "Actions depending on the outputs of actions that would execute were not looked up",
+        )?;
+    }
+    Ok(())
+}
+
 pub(crate) fn print_outputs(
     out: impl Write,
     targets: &[BuildTarget],
@@ -496,6 +549,8 @@ mod tests {
         );
         assert_matches!(parse(&["--build-run-info", "--skip-run-info"]), Err(..));
         assert_matches!(parse(&["--build-test-info", "--skip-test-info"]), Err(..));
+        assert_matches!(parse(&["--dry-run", "--out", "out"]), Err(..));
+        assert_matches!(parse(&["--dry-run", "--materializations", "all"]), Err(..));
 
         // Test args across all groups.
         assert_matches!(
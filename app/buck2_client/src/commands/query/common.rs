@@ -28,6 +28,9 @@ enum QueryOutputFormatArg {
     DotCompact,
     Starlark,
     Html,
+    JsonGraph,
+    Graphml,
+    Mermaid,
 }
 
 /// Args common to all the query commands
@@ -58,9 +61,12 @@ pub(crate) struct CommonQueryOptions {
            dot_compact - compact alternative to dot format. \n
            json - JSON format. \n
            starlark - targets are printed like starlark code that would produce them.
-           html - html file containing interactive target graph.
+           html - html file containing interactive target graph. \n
+           json_graph - JSON object with `nodes` and the `edges` between them. \n
+           graphml - GraphML document. \n
+           mermaid - Mermaid flowchart. \n
          ",
-        value_name = "dot|dot_compact|json|starlark|html",
+        value_name = "dot|dot_compact|json|starlark|html|json_graph|graphml|mermaid",
         value_enum
     )]
     output_format: Option<QueryOutputFormatArg>,
@@ -94,6 +100,9 @@ impl CommonQueryOptions {
             Some(QueryOutputFormatArg::DotCompact) => QueryOutputFormat::DotCompact,
             Some(QueryOutputFormatArg::Starlark) => QueryOutputFormat::Starlark,
             Some(QueryOutputFormatArg::Html) => QueryOutputFormat::Html,
+            Some(QueryOutputFormatArg::JsonGraph) => QueryOutputFormat::JsonGraph,
+            Some(QueryOutputFormatArg::Graphml) => QueryOutputFormat::Graphml,
+            Some(QueryOutputFormatArg::Mermaid) => QueryOutputFormat::Mermaid,
             None => {
                 if self.json {
                     QueryOutputFormat::Json
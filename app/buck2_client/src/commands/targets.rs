@@ -199,6 +199,17 @@ pub struct TargetsCommand {
     #[clap(long, requires = "streaming")]
     imports: bool,
 
+    /// Directory to read and write cached per-package output. A package is not evaluated if its
+    /// directory listing and the contents of its build file, `PACKAGE` files and the files they
+    /// load are unchanged since its output was cached. Packages which fail to load are not cached.
+    #[clap(
+        long,
+        value_name = "PATH",
+        requires = "streaming",
+        conflicts_with = "imports"
+    )]
+    package_cache: Option<PathArg>,
+
     /// Show the package values. Produces an additional attribute representing all the package values
     /// for the package containing the target.
     #[clap(long, conflicts_with = "package_values_regex")]
@@ -350,6 +361,9 @@ impl StreamingCommand for TargetsCommand {
                     cached: !self.no_cache,
                     imports: self.imports,
                     package_values,
+                    package_cache: self
+                        .package_cache
+                        .try_map(|x| x.resolve(&ctx.working_dir).into_string())?,
                 })
             }),
             target_cfg: Some(self.target_cfg.target_cfg()),
@@ -14,6 +14,7 @@ use async_trait::async_trait;
 use buck2_cli_proto::CounterWithExamples;
 use buck2_cli_proto::TestRequest;
 use buck2_cli_proto::TestSessionOptions;
+use buck2_cli_proto::TestShard;
 use buck2_client_ctx::client_ctx::ClientCommandContext;
 use buck2_client_ctx::common::BuckArgMatches;
 use buck2_client_ctx::common::CommonBuildConfigurationOptions;
@@ -38,6 +39,7 @@ use buck2_client_ctx::subscribers::superconsole::test::span_from_build_failure_c
 use buck2_error::BuckErrorContext;
 use buck2_error::BuckErrorOptionContext;
 use buck2_error::ExitCode;
+use buck2_error::conversion::clap::buck_error_clap_parser;
 use buck2_fs::error::IoResultExt;
 use buck2_fs::fs_util;
 use buck2_fs::working_dir::AbsWorkingDir;
@@ -79,6 +81,26 @@ fn print_error_counter(
     Ok(())
 }
 
+/// Flaky tests passed, so they are listed as a warning rather than an error.
+fn print_flaky_counter(
+    console: &FinalConsole,
+    counter: &CounterWithExamples,
+) -> buck2_error::Result<()> {
+    if counter.count > 0 {
+        console.print_warning(&format!("{} TESTS FLAKY", counter.count))?;
+        for test_name in &counter.example_tests {
+            console.print_warning(&format!("  ≈ {test_name}"))?;
+        }
+        if counter.count > counter.max {
+            console.print_warning(&format!(
+                "  ...and {} more not shown...",
+                counter.count - counter.max
+            ))?;
+        }
+    }
+    Ok(())
+}
+
 /// Check if we should warn about potentially misplaced --include/--exclude flags.
 /// Returns Some(suspicious_labels) if warning should be shown, where suspicious_labels
 /// are label values that look like they might be target patterns (contain '/' or ':').
@@ -101,6 +123,33 @@ fn should_warn_about_flag_position(
     }
 }
 
+/// A `K/N` shard, stored 0-based.
+#[derive(Debug, Clone, Copy)]
+struct ShardArg {
+    index: u32,
+    count: u32,
+}
+
+fn parse_shard(s: &str) -> buck2_error::Result<ShardArg> {
+    let parsed = s
+        .split_once('/')
+        .and_then(|(k, n)| Some((k.parse::<u32>().ok()?, n.parse::<u32>().ok()?)));
+    match parsed {
+        Some((k, n)) if k >= 1 && k <= n => Ok(ShardArg {
+            index: k - 1,
+            count: n,
+        }),
+        _ => Err(ShardArgError::Invalid(s.to_owned()).into()),
+    }
+}
+
+#[derive(Debug, buck2_error::Error)]
+#[buck2(tag = Input)]
+enum ShardArgError {
+    #[error("Invalid shard `{0}`, expected `K/N` with 1 <= K <= N, e.g. `3/40`")]
+    Invalid(String),
+}
+
 #[derive(Debug, clap::Parser)]
 #[clap(name = "test", about = "Build and test the specified targets")]
 pub struct TestCommand {
@@ -227,6 +276,30 @@ If include patterns are present, regardless of whether exclude patterns are pres
     #[clap(long, value_name = "PATH")]
     write_test_id: Option<PathArg>,
 
+    /// Write the test results as a JUnit XML report into this file, with a `<testsuite>` per
+    /// target and a `<testcase>` per test case. The report is written even if the run fails.
+    #[clap(long, value_name = "PATH")]
+    junit_xml: Option<PathArg>,
+
+    /// Write the test results as a TAP version 13 report into this file.
+    #[clap(long, value_name = "PATH")]
+    tap: Option<PathArg>,
+
+    /// Only run shard K of N, e.g. `--shard 3/40`, to split a test run across machines. Every
+    /// shard must be given the same target patterns. Shards never overlap, and together they run
+    /// every test.
+    ///
+    /// Tests run by Buck2's internal runner, which lists their test cases, are sharded by test
+    /// case. Other tests are sharded by target, and only the targets in the shard are built.
+    #[clap(long, value_name = "K/N", value_parser = buck_error_clap_parser(parse_shard))]
+    shard: Option<ShardArg>,
+
+    /// Build reports (`--build-report`) of previous test runs, whose test durations are used to
+    /// balance the shards. Every shard must be given the same files. Tests that are not in any of
+    /// them are assigned to shards by hash.
+    #[clap(long, value_name = "PATH", requires = "shard")]
+    shard_timings: Vec<PathArg>,
+
     #[clap(flatten)]
     common_opts: CommonCommandOptions,
 }
@@ -411,6 +484,19 @@ impl StreamingCommand for TestCommand {
             None
         };
 
+        let shard = match self.shard {
+            Some(ShardArg { index, count }) => Some(TestShard {
+                index,
+                count,
+                timings_paths: self
+                    .shard_timings
+                    .iter()
+                    .map(|path| path.resolve(&ctx.working_dir).into_string())
+                    .collect::<buck2_error::Result<_>>()?,
+            }),
+            None => None,
+        };
+
         let response = buckd
             .with_flushing()
             .test(
@@ -436,6 +522,7 @@ impl StreamingCommand for TestCommand {
                     ignore_tests_attribute: self.ignore_tests_attribute,
                     build_default_info,
                     build_run_info,
+                    shard,
                 },
                 events_ctx,
                 ctx.console_interaction_stream(&self.common_opts.console_opts),
@@ -480,6 +567,7 @@ impl StreamingCommand for TestCommand {
             .infra_failure
             .as_ref()
             .internal_error("Missing `infra failure`")?;
+        let flaky = statuses.flaky.as_ref().internal_error("Missing `flaky`")?;
 
         let console = self.common_opts.console_opts.final_console();
         print_build_result(&console, &response.errors)?;
@@ -509,6 +597,10 @@ impl StreamingCommand for TestCommand {
             line.push(column.to_span_from_test_statuses(statuses)?);
             line.push(Span::new_unstyled_lossy(". "));
         }
+        if flaky.count > 0 {
+            line.push(TestCounterColumn::FLAKY.to_span_from_test_statuses(statuses)?);
+            line.push(Span::new_unstyled_lossy(". "));
+        }
         line.push(span_from_build_failure_count(statuses.build_errors)?);
         eprint_line(&line)?;
 
@@ -517,6 +609,7 @@ impl StreamingCommand for TestCommand {
         print_error_counter(&console, timeout, "TESTS TIMED OUT", "⏱")?;
         print_error_counter(&console, fatals, "TESTS FATALS", "⚠")?;
         print_error_counter(&console, infra_failure, "TESTS Infra Failed", "🛠")?;
+        print_flaky_counter(&console, flaky)?;
 
         if passed.count
             + failed.count
@@ -525,6 +618,7 @@ impl StreamingCommand for TestCommand {
             + skipped.count
             + omitted.count
             + infra_failure.count
+            + flaky.count
             == 0
         {
             console.print_warning("NO TESTS RAN")?;
@@ -603,12 +697,31 @@ impl StreamingCommand for TestCommand {
     fn write_test_id(&self) -> &Option<PathArg> {
         &self.write_test_id
     }
+
+    fn write_junit_xml(&self) -> &Option<PathArg> {
+        &self.junit_xml
+    }
+
+    fn write_tap(&self) -> &Option<PathArg> {
+        &self.tap
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_shard() {
+        let shard = parse_shard("3/40").unwrap();
+        assert_eq!((shard.index, shard.count), (2, 40));
+        let shard = parse_shard("1/1").unwrap();
+        assert_eq!((shard.index, shard.count), (0, 1));
+        for invalid in ["0/4", "5/4", "1/0", "3", "a/b", "1/2/3"] {
+            assert!(parse_shard(invalid).is_err(), "{invalid}");
+        }
+    }
+
     #[test]
     fn test_warn_when_no_patterns_with_include() {
         let result = should_warn_about_flag_position(&[], &["some_label".to_owned()], &[])
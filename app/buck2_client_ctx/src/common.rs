@@ -160,6 +160,16 @@ pub struct CommonEventLogOptions {
     /// written to `buck-out/v2/<uuid>/command_report` even without this flag.
     #[clap(long, value_name = "PATH")]
     pub(crate) command_report_path: Option<PathArg>,
+
+    /// Export the spans of the command as OpenTelemetry traces to this OTLP/gRPC endpoint, e.g.
+    /// `http://localhost:4317`. Defaults to `$BUCK2_OTEL_TRACES_ENDPOINT`.
+    #[clap(long, value_name = "URL")]
+    pub(crate) otel_traces_endpoint: Option<String>,
+
+    /// Write the spans of the command as OpenTelemetry traces to this file, in the OTLP JSON
+    /// encoding with one export request per line.
+    #[clap(long, value_name = "PATH")]
+    pub(crate) otel_traces_file: Option<PathArg>,
 }
 
 impl CommonEventLogOptions {
@@ -170,6 +180,8 @@ impl CommonEventLogOptions {
             write_build_id: None,
             command_report_path: None,
             unstable_write_invocation_record: None,
+            otel_traces_endpoint: None,
+            otel_traces_file: None,
         };
         &DEFAULT
     }
@@ -181,6 +193,8 @@ impl CommonEventLogOptions {
             write_build_id: None,
             command_report_path: None,
             unstable_write_invocation_record: None,
+            otel_traces_endpoint: None,
+            otel_traces_file: None,
         };
         &NO_EVENT_LOG
     }
@@ -33,6 +33,9 @@ pub struct BuildReportOption {
 
     /// Truncate error content in the build report to reduce size.
     truncate_error_content: bool,
+
+    /// Write the build report as a SARIF log of the failures.
+    sarif: bool,
 }
 
 fn parse_build_report_option(s: &str) -> buck2_error::Result<BuildReportOption> {
@@ -41,6 +44,7 @@ fn parse_build_report_option(s: &str) -> buck2_error::Result<BuildReportOption>
     let mut include_artifact_hash_information = false;
     let mut exclude_action_error_diagnostics = false;
     let mut truncate_error_content = false;
+    let mut sarif = false;
 
     if s.to_lowercase() == "fill-out-failures" {
         fill_out_failures = true;
@@ -52,9 +56,11 @@ fn parse_build_report_option(s: &str) -> buck2_error::Result<BuildReportOption>
         exclude_action_error_diagnostics = true;
     } else if s.to_lowercase() == "truncate-error-content" {
         truncate_error_content = true;
+    } else if s.to_lowercase() == "sarif" {
+        sarif = true;
     } else {
         warn!(
-            "Incorrect syntax for build report option. Got: `{}` but expected one of `fill-out-failures, package-project-relative-paths, include-artifact-hash-information, exclude-action-error-diagnostics, truncate-error-content`",
+            "Incorrect syntax for build report option. Got: `{}` but expected one of `fill-out-failures, package-project-relative-paths, include-artifact-hash-information, exclude-action-error-diagnostics, truncate-error-content, sarif`",
             s.to_owned()
         )
     }
@@ -64,6 +70,7 @@ fn parse_build_report_option(s: &str) -> buck2_error::Result<BuildReportOption>
         include_artifact_hash_information,
         exclude_action_error_diagnostics,
         truncate_error_content,
+        sarif,
     })
 }
 
@@ -94,10 +101,13 @@ pub struct CommonBuildOptions {
     /// emit the project-relative path of packages for the targets that were built.
     ///
     /// `exclude-action-error-diagnostics`:
-    /// exclude error_diagnostics field from action errors in the build report.
+    /// exclude error_diagnostics and diagnostics fields from action errors in the build report.
     ///
     /// `truncate-error-content`:
     /// truncate error content in the build report to reduce size.
+    ///
+    /// `sarif`:
+    /// write a SARIF 2.1.0 log of the build failures instead of the build report.
     #[clap(
         long = "build-report-options",
         requires = "build_report",
@@ -241,6 +251,8 @@ impl CommonBuildOptions {
             .build_report_options
             .iter()
             .any(|option| option.truncate_error_content);
+        let unstable_build_report_sarif =
+            self.build_report_options.iter().any(|option| option.sarif);
         let concurrency = self
             .num_threads
             .map(|num| buck2_cli_proto::Concurrency { concurrency: num });
@@ -284,6 +296,9 @@ impl CommonBuildOptions {
             unstable_streaming_build_report_filename,
             unstable_exclude_action_error_diagnostics,
             unstable_truncate_error_content,
+            unstable_build_report_sarif,
+            // Only `buck2 build` has `--dry-run`; it sets this itself.
+            dry_run: false,
         }
     }
 }
@@ -204,6 +204,7 @@ pub enum SuperConsoleToggle {
     TargetConfigurations,
     ExpandedProgress,
     Commands,
+    ActionInspector,
     IncrLines,
     DecrLines,
     IncreaseReplaySpeed,
@@ -245,6 +246,7 @@ impl SuperConsoleToggle {
             SuperConsoleToggle::TargetConfigurations => "target configurations",
             SuperConsoleToggle::ExpandedProgress => "expanded progress",
             SuperConsoleToggle::Commands => "commands",
+            SuperConsoleToggle::ActionInspector => "action inspector",
             SuperConsoleToggle::IncrLines => "more lines",
             SuperConsoleToggle::DecrLines => "less lines",
             SuperConsoleToggle::IncreaseReplaySpeed => "increase replay speed",
@@ -265,6 +267,7 @@ impl SuperConsoleToggle {
             SuperConsoleToggle::TargetConfigurations => 'p',
             SuperConsoleToggle::ExpandedProgress => 'x',
             SuperConsoleToggle::Commands => 'c',
+            SuperConsoleToggle::ActionInspector => 'a',
             SuperConsoleToggle::IncrLines => '+',
             SuperConsoleToggle::DecrLines => '-',
             SuperConsoleToggle::IncreaseReplaySpeed => 'k',
@@ -309,6 +312,7 @@ impl SuperConsoleInteraction for ConsoleInteractionStream<'_> {
                             'p' => SuperConsoleToggle::TargetConfigurations,
                             'x' => SuperConsoleToggle::ExpandedProgress,
                             'c' => SuperConsoleToggle::Commands,
+                            'a' => SuperConsoleToggle::ActionInspector,
                             '+' => SuperConsoleToggle::IncrLines,
                             '-' => SuperConsoleToggle::DecrLines,
                             'k' => SuperConsoleToggle::IncreaseReplaySpeed,
@@ -324,6 +324,29 @@ impl BuckdClient {
 
         Ok(())
     }
+
+    pub(crate) fn action_canceller(&self) -> ActionCanceller {
+        ActionCanceller {
+            client: self.client.clone(),
+        }
+    }
+}
+
+/// Cancels actions while a command is streaming, on behalf of subscribers.
+pub(crate) struct ActionCanceller {
+    client: DaemonApiClient<InterceptedService<Channel, BuckAddAuthTokenInterceptor>>,
+}
+
+impl ActionCanceller {
+    /// Returns `false` if the action is not executing anymore.
+    pub(crate) async fn cancel_action(&mut self, span_id: u64) -> buck2_error::Result<bool> {
+        let response = self
+            .client
+            .cancel_action(Request::new(CancelActionRequest { span_id }))
+            .await
+            .map_err(tonic_status_to_error)?;
+        Ok(response.into_inner().cancelled)
+    }
 }
 
 pub struct FlushingBuckdClient<'a> {
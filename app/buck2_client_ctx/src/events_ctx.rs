@@ -23,6 +23,7 @@ use buck2_error::BuckErrorOptionContext;
 use buck2_error::ErrorTag;
 use buck2_event_log::stream_value::StreamValue;
 use buck2_events::BuckEvent;
+use buck2_events::span::SpanId;
 use buck2_fs::paths::abs_norm_path::AbsNormPathBuf;
 use buck2_fs::paths::abs_path::AbsPathBuf;
 use buck2_wrapper_common::invocation_id::TraceId;
@@ -41,6 +42,7 @@ use crate::console_interaction_stream::ConsoleInteraction;
 use crate::console_interaction_stream::ConsoleInteractionStream;
 use crate::console_interaction_stream::NoopSuperConsoleInteraction;
 use crate::console_interaction_stream::SuperConsoleInteraction;
+use crate::daemon::client::ActionCanceller;
 use crate::daemon::client::BuckdClient;
 use crate::daemon::client::NoPartialResultHandler;
 use crate::daemon::client::tonic_status_to_error;
@@ -137,6 +139,8 @@ pub struct DaemonEventsCtx<'a> {
     pub(crate) inner: &'a mut EventsCtx,
     tailers: FileTailers,
     ticker: Ticker,
+    /// Unset if there is no daemon to cancel actions with.
+    action_canceller: Option<ActionCanceller>,
 }
 
 impl<'a> DaemonEventsCtx<'a> {
@@ -160,6 +164,7 @@ impl<'a> DaemonEventsCtx<'a> {
             inner: events_ctx,
             tailers,
             ticker: Ticker::new(TICKS_PER_SECOND),
+            action_canceller: Some(client.action_canceller()),
         })
     }
 
@@ -168,6 +173,7 @@ impl<'a> DaemonEventsCtx<'a> {
             inner: events_ctx,
             tailers: FileTailers::empty(),
             ticker: Ticker::new(TICKS_PER_SECOND),
+            action_canceller: None,
         }
     }
 
@@ -249,6 +255,27 @@ impl<'a> DaemonEventsCtx<'a> {
         }
     }
 
+    /// Cancels the actions subscribers were asked to cancel, and reports those that can't be.
+    async fn cancel_actions(&mut self) -> buck2_error::Result<()> {
+        for span_id in self.inner.take_action_cancellations() {
+            let error = match &mut self.action_canceller {
+                Some(action_canceller) => {
+                    match action_canceller.cancel_action(span_id.into()).await {
+                        // The action failing reports the cancellation.
+                        Ok(true) => continue,
+                        Ok(false) => "the action is not executing anymore".to_owned(),
+                        Err(e) => format!("{e:#}"),
+                    }
+                }
+                None => "not connected to the daemon".to_owned(),
+            };
+            self.inner
+                .eprintln(&format!("Failed to cancel action: {error}"))
+                .await?;
+        }
+        Ok(())
+    }
+
     async fn unpack_stream_inner<S, Handler>(
         &mut self,
         partial_result_handler: &mut Handler,
@@ -293,6 +320,7 @@ impl<'a> DaemonEventsCtx<'a> {
                     }
                     interaction = console_interaction.interaction() => {
                         self.inner.handle_console_interaction(&interaction?).await?;
+                        self.cancel_actions().await?;
                     }
                     tick = self.ticker.tick() => {
                         self.inner.tick(&tick).await?;
@@ -647,6 +675,12 @@ impl EventsCtx {
         }
     }
 
+    fn take_action_cancellations(&mut self) -> Vec<SpanId> {
+        let mut spans = Vec::new();
+        self.for_each_subscriber(|subscriber| spans.extend(subscriber.take_action_cancellations()));
+        spans
+    }
+
     pub(crate) fn handle_stream_end(&mut self) {
         self.for_each_subscriber(|subscriber| subscriber.handle_stream_end());
     }
@@ -16,6 +16,7 @@ use buck2_common::argv::Argv;
 use buck2_common::argv::SanitizedArgv;
 use buck2_common::init::DEFAULT_RETAINED_EVENT_LOGS;
 use buck2_common::invocation_paths::InvocationPaths;
+use buck2_core::buck2_env;
 use buck2_error::ExitCode;
 use buck2_event_observer::span_tracker::EventTimestamp;
 use dupe::Dupe;
@@ -42,11 +43,13 @@ use crate::subscribers::build_graph_stats::BuildGraphStats;
 use crate::subscribers::build_id_writer::BuildIdWriter;
 use crate::subscribers::event_log::EventLog;
 use crate::subscribers::health_check_subscriber::HealthCheckSubscriber;
+use crate::subscribers::otel_traces::OtelTraces;
 use crate::subscribers::re_log::ReLog;
 use crate::subscribers::subscriber::EventSubscriber;
 use crate::subscribers::superconsole::timekeeper::RealtimeClock;
 use crate::subscribers::superconsole::timekeeper::Timekeeper;
 use crate::subscribers::test_id_writer::TestIdWriter;
+use crate::subscribers::test_report_writer::TestReportWriter;
 
 const HEALTH_CHECK_CHANNEL_SIZE: usize = 100;
 
@@ -124,9 +127,17 @@ fn update_events_ctx<T: StreamingCommand>(
     if let Some(test_id_writer) = get_test_id_writer(cmd, ctx) {
         subscribers.push(test_id_writer)
     }
+    if let Some(test_report_writer) = get_test_report_writer(cmd, ctx) {
+        subscribers.push(test_report_writer)
+    }
     if let Some(build_graph_stats) = get_build_graph_stats(cmd, ctx) {
         subscribers.push(build_graph_stats)
     }
+    match get_otel_traces(cmd, ctx) {
+        Ok(Some(otel_traces)) => subscribers.push(otel_traces),
+        Ok(None) => {}
+        Err(e) => tracing::warn!("Not exporting OpenTelemetry traces: {:#}", e),
+    }
     let representative_config_flags = if ctx.paths().is_ok() {
         matches.get_representative_config_flags()
     } else {
@@ -215,6 +226,16 @@ pub trait StreamingCommand: Sized + Send + Sync {
     fn write_test_id(&self) -> &Option<PathArg> {
         &None
     }
+
+    /// Path to write a JUnit XML test report. Currently only for TestCommand.
+    fn write_junit_xml(&self) -> &Option<PathArg> {
+        &None
+    }
+
+    /// Path to write a TAP test report. Currently only for TestCommand.
+    fn write_tap(&self) -> &Option<PathArg> {
+        &None
+    }
 }
 
 impl<T: StreamingCommand> BuckSubcommand for T {
@@ -359,6 +380,48 @@ fn get_test_id_writer<T: StreamingCommand>(
     }
 }
 
+fn get_test_report_writer<T: StreamingCommand>(
+    cmd: &T,
+    ctx: &ClientCommandContext,
+) -> Option<Box<dyn EventSubscriber>> {
+    let junit_xml = cmd
+        .write_junit_xml()
+        .as_ref()
+        .map(|path| path.resolve(&ctx.working_dir));
+    let tap = cmd
+        .write_tap()
+        .as_ref()
+        .map(|path| path.resolve(&ctx.working_dir));
+    if junit_xml.is_none() && tap.is_none() {
+        return None;
+    }
+    Some(Box::new(TestReportWriter::new(junit_xml, tap)))
+}
+
+fn get_otel_traces<T: StreamingCommand>(
+    cmd: &T,
+    ctx: &ClientCommandContext,
+) -> buck2_error::Result<Option<Box<dyn EventSubscriber>>> {
+    let opts = cmd.event_log_opts();
+    let endpoint = match &opts.otel_traces_endpoint {
+        Some(endpoint) => Some(endpoint.clone()),
+        None => buck2_env!("BUCK2_OTEL_TRACES_ENDPOINT")?.map(|endpoint| endpoint.to_owned()),
+    };
+    let file = opts
+        .otel_traces_file
+        .as_ref()
+        .map(|path| path.resolve(&ctx.working_dir));
+    if endpoint.is_none() && file.is_none() {
+        return Ok(None);
+    }
+    Ok(Some(Box::new(OtelTraces::new(
+        ctx.trace_id.dupe(),
+        T::COMMAND_NAME,
+        endpoint,
+        file,
+    ))))
+}
+
 fn get_build_graph_stats<T: StreamingCommand>(
     cmd: &T,
     ctx: &ClientCommandContext,
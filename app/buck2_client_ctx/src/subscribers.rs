@@ -19,6 +19,7 @@ pub(crate) mod health_check_subscriber;
 pub(crate) mod observer;
 #[cfg(target_os = "linux")]
 pub(crate) mod oom;
+pub(crate) mod otel_traces;
 pub mod re_log;
 pub mod recorder;
 pub(crate) mod simpleconsole;
@@ -27,3 +28,4 @@ pub mod subscriber;
 pub mod superconsole;
 pub(crate) mod system_warning;
 pub(crate) mod test_id_writer;
+pub(crate) mod test_report_writer;
@@ -11,6 +11,7 @@
 use std::fmt;
 
 use buck2_event_observer::what_ran::CommandReproducer;
+use buck2_event_observer::what_ran::WhatRanCommandOutcome;
 use buck2_event_observer::what_ran::WhatRanOptions;
 use buck2_event_observer::what_ran::WhatRanOptionsRegex;
 use buck2_event_observer::what_ran::WhatRanOutputWriter;
@@ -40,9 +41,7 @@ pub(crate) fn emit_event_if_relevant(
             repro,
             output,
             &options_regex,
-            None,
-            None,
-            None,
+            WhatRanCommandOutcome::default(),
         )?;
     }
 
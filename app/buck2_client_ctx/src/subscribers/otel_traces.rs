@@ -0,0 +1,1056 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is dual-licensed under either the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree or the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree. You may select, at your option, one of the
+ * above-listed licenses.
+ */
+
+//! Exports the spans of a command as OpenTelemetry traces, over OTLP/gRPC or to a file in the
+//! OTLP JSON encoding, so that builds show up in tracing backends.
+
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use async_trait::async_trait;
+use buck2_error::BuckErrorContext;
+use buck2_event_observer::display;
+use buck2_event_observer::display::TargetDisplayOptions;
+use buck2_event_observer::unpack_event::UnpackedBuckEvent;
+use buck2_event_observer::unpack_event::unpack_event;
+use buck2_events::BuckEvent;
+use buck2_events::span::SpanId;
+use buck2_fs::paths::abs_path::AbsPathBuf;
+use buck2_hash::BuckMutMap;
+use buck2_otel_proto::AnyValue;
+use buck2_otel_proto::ExportTraceServiceRequest;
+use buck2_otel_proto::InstrumentationScope;
+use buck2_otel_proto::KeyValue;
+use buck2_otel_proto::Resource;
+use buck2_otel_proto::ResourceSpans;
+use buck2_otel_proto::ScopeSpans;
+use buck2_otel_proto::Span;
+use buck2_otel_proto::Status;
+use buck2_otel_proto::any_value;
+use buck2_otel_proto::span::SpanKind;
+use buck2_otel_proto::status::StatusCode;
+use buck2_otel_proto::trace_service_client::TraceServiceClient;
+use buck2_wrapper_common::invocation_id::TraceId;
+use serde_json::json;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tonic::transport::Channel;
+use tonic::transport::Endpoint;
+
+use crate::subscribers::subscriber::EventSubscriber;
+
+/// Maximum number of spans sent in a single export request. Finished spans are exported in the
+/// background once that many have built up.
+const BATCH_SIZE: usize = 512;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, buck2_error::Error)]
+#[buck2(tag = Environment)]
+enum OtelTracesError {
+    #[error("OpenTelemetry collector rejected {0} spans: {1}")]
+    Rejected(i64, String),
+}
+
+pub(crate) struct OtelTraces {
+    trace_id: TraceId,
+    command_name: &'static str,
+    endpoint: Option<String>,
+    file: Option<AbsPathBuf>,
+    open: BuckMutMap<SpanId, OpenSpan>,
+    finished: Vec<Span>,
+    command_span: Option<SpanId>,
+    /// The command span once it ended, kept until the end so that the command result can set its
+    /// status.
+    command: Option<Span>,
+    command_error: Option<String>,
+    /// Exports the batches of finished spans during the command, started with the first batch.
+    exporter: Option<Exporter>,
+    /// Timestamp of the latest event, used to end the spans that are still open when the command
+    /// finishes.
+    last_timestamp: u64,
+}
+
+struct OpenSpan {
+    parent: Option<SpanId>,
+    /// The span being built, if this kind of span is exported.
+    span: Option<Span>,
+    /// Digest of the action run under this span, as reported by the first executor stage that
+    /// carries one.
+    action_digest: Option<String>,
+}
+
+impl OtelTraces {
+    pub(crate) fn new(
+        trace_id: TraceId,
+        command_name: &'static str,
+        endpoint: Option<String>,
+        file: Option<AbsPathBuf>,
+    ) -> Self {
+        Self {
+            trace_id,
+            command_name,
+            endpoint,
+            file,
+            open: BuckMutMap::default(),
+            finished: Vec::new(),
+            command_span: None,
+            command: None,
+            command_error: None,
+            exporter: None,
+            last_timestamp: 0,
+        }
+    }
+
+    fn handle_event(&mut self, event: &BuckEvent) {
+        let timestamp = unix_nanos(event.timestamp());
+        self.last_timestamp = self.last_timestamp.max(timestamp);
+        match unpack_event(event) {
+            Ok(UnpackedBuckEvent::SpanStart(event, _, data)) => self.start(event, data, timestamp),
+            Ok(UnpackedBuckEvent::SpanEnd(event, _, data)) => self.end(event, data, timestamp),
+            _ => {}
+        }
+    }
+
+    fn start(
+        &mut self,
+        event: &BuckEvent,
+        data: &buck2_data::span_start_event::Data,
+        timestamp: u64,
+    ) {
+        let Some(span_id) = event.span_id() else {
+            return;
+        };
+        let parent = event.parent_id();
+        let action_digest = match data {
+            buck2_data::span_start_event::Data::ExecutorStage(stage) => {
+                executor_stage_digest(stage)
+            }
+            _ => None,
+        };
+        if let (Some(digest), Some(open)) = (
+            action_digest,
+            parent.and_then(|parent| self.open.get_mut(&parent)),
+        ) {
+            open.action_digest.get_or_insert_with(|| digest.to_owned());
+        }
+        let span = self
+            .name_and_attributes(data, parent)
+            .map(|(name, attributes)| Span {
+                trace_id: self.trace_id.as_bytes().to_vec(),
+                span_id: span_id_bytes(span_id),
+                parent_span_id: self
+                    .exported_ancestor(parent)
+                    .map_or_else(Vec::new, span_id_bytes),
+                name,
+                kind: SpanKind::Internal as i32,
+                start_time_unix_nano: timestamp,
+                end_time_unix_nano: 0,
+                attributes,
+                status: None,
+            });
+        if matches!(data, buck2_data::span_start_event::Data::Command(_)) {
+            self.command_span = Some(span_id);
+        }
+        self.open.insert(
+            span_id,
+            OpenSpan {
+                parent,
+                span,
+                action_digest: None,
+            },
+        );
+    }
+
+    fn end(&mut self, event: &BuckEvent, data: &buck2_data::span_end_event::Data, timestamp: u64) {
+        let Some(span_id) = event.span_id() else {
+            return;
+        };
+        let Some(OpenSpan {
+            span: Some(mut span),
+            ..
+        }) = self.open.remove(&span_id)
+        else {
+            return;
+        };
+        span.end_time_unix_nano = timestamp;
+        if let Some(message) = add_end_attributes(&mut span.attributes, data) {
+            span.status = Some(error_status(message));
+        }
+        if self.command_span == Some(span_id) {
+            self.command = Some(span);
+            return;
+        }
+        self.finished.push(span);
+        if self.finished.len() >= BATCH_SIZE && (self.endpoint.is_some() || self.file.is_some()) {
+            let mut spans = std::mem::take(&mut self.finished);
+            spans.sort_by_key(|span| span.start_time_unix_nano);
+            let request = self.request(spans);
+            self.exporter
+                .get_or_insert_with(|| Exporter::spawn(self.endpoint.clone(), self.file.clone()))
+                .send(request);
+        }
+    }
+
+    /// The closest ancestor that is exported, so that spans are not linked to parents missing
+    /// from the trace. Falls back to the command span.
+    fn exported_ancestor(&self, mut parent: Option<SpanId>) -> Option<SpanId> {
+        while let Some(id) = parent {
+            match self.open.get(&id) {
+                Some(OpenSpan { span: Some(_), .. }) => return Some(id),
+                Some(OpenSpan { parent: next, .. }) => parent = *next,
+                None => break,
+            }
+        }
+        self.command_span
+    }
+
+    /// The action digest recorded by the closest ancestor.
+    fn ancestor_action_digest(&self, mut parent: Option<SpanId>) -> Option<&str> {
+        while let Some(open) = parent.and_then(|id| self.open.get(&id)) {
+            if let Some(digest) = &open.action_digest {
+                return Some(digest);
+            }
+            parent = open.parent;
+        }
+        None
+    }
+
+    fn name_and_attributes(
+        &self,
+        data: &buck2_data::span_start_event::Data,
+        parent: Option<SpanId>,
+    ) -> Option<(String, Vec<KeyValue>)> {
+        use buck2_data::span_start_event::Data;
+
+        let opts = TargetDisplayOptions::for_log();
+        Some(match data {
+            Data::Command(_) => (
+                format!("buck2 {}", self.command_name),
+                vec![string_attribute("buck2.command", self.command_name)],
+            ),
+            Data::Analysis(analysis) => {
+                let mut attributes = vec![string_attribute("buck2.rule", &analysis.rule)];
+                if let Some(Ok(target)) = analysis
+                    .target
+                    .as_ref()
+                    .map(|target| display::display_analysis_target(target, opts))
+                {
+                    attributes.push(string_attribute("buck2.target", target));
+                }
+                ("analysis".to_owned(), attributes)
+            }
+            Data::ActionExecution(action) => {
+                let mut attributes = Vec::new();
+                if let Some(Ok(target)) = action
+                    .key
+                    .as_ref()
+                    .map(|key| display::display_action_key(key, opts))
+                {
+                    attributes.push(string_attribute("buck2.target", target));
+                }
+                if let Some(name) = &action.name {
+                    attributes.push(string_attribute("buck2.action.category", &name.category));
+                    attributes.push(string_attribute(
+                        "buck2.action.identifier",
+                        &name.identifier,
+                    ));
+                }
+                if let Ok(kind) = buck2_data::ActionKind::try_from(action.kind) {
+                    attributes.push(string_attribute("buck2.action.kind", kind.as_str_name()));
+                }
+                ("action".to_owned(), attributes)
+            }
+            Data::ExecutorStage(stage) => {
+                let stage = stage.stage.as_ref()?;
+                // Stages such as the RE download do not carry the digest themselves, so fall back
+                // to the one reported by an earlier stage of the same action.
+                let digest =
+                    executor_stage_digest(stage).or_else(|| self.ancestor_action_digest(parent));
+                (
+                    display::display_executor_stage(stage)?.to_owned(),
+                    digest
+                        .map(|digest| string_attribute("buck2.action.digest", digest))
+                        .into_iter()
+                        .collect(),
+                )
+            }
+            Data::Materialization(materialization) => (
+                "materialization".to_owned(),
+                materialization
+                    .action_digest
+                    .iter()
+                    .map(|digest| string_attribute("buck2.action.digest", digest))
+                    .collect(),
+            ),
+            Data::ReUpload(_) => ("re_upload".to_owned(), Vec::new()),
+            _ => return None,
+        })
+    }
+
+    /// Ends the spans that are still open, and groups the spans not exported yet into export
+    /// requests.
+    fn requests(&mut self) -> Vec<ExportTraceServiceRequest> {
+        for (_, open) in self.open.drain() {
+            if let Some(mut span) = open.span {
+                span.end_time_unix_nano = self.last_timestamp;
+                span.status = Some(error_status(
+                    "Span did not end before the command finished".to_owned(),
+                ));
+                self.finished.push(span);
+            }
+        }
+        self.finished.extend(self.command.take());
+        if let (Some(command_span), Some(error)) = (self.command_span, self.command_error.take()) {
+            let command_span = span_id_bytes(command_span);
+            if let Some(span) = self
+                .finished
+                .iter_mut()
+                .find(|span| span.span_id == command_span)
+            {
+                span.status = Some(error_status(error));
+            }
+        }
+        let mut spans = std::mem::take(&mut self.finished);
+        spans.sort_by_key(|span| span.start_time_unix_nano);
+        spans
+            .chunks(BATCH_SIZE)
+            .map(|spans| self.request(spans.to_vec()))
+            .collect()
+    }
+
+    fn request(&self, spans: Vec<Span>) -> ExportTraceServiceRequest {
+        let resource = Resource {
+            attributes: vec![
+                string_attribute("service.name", "buck2"),
+                string_attribute("buck2.command", self.command_name),
+            ],
+        };
+        let scope = InstrumentationScope {
+            name: "buck2".to_owned(),
+            version: buck2_build_info::revision().unwrap_or_default().to_owned(),
+        };
+        ExportTraceServiceRequest {
+            resource_spans: vec![ResourceSpans {
+                resource: Some(resource),
+                scope_spans: vec![ScopeSpans {
+                    scope: Some(scope),
+                    spans,
+                }],
+            }],
+        }
+    }
+}
+
+/// Exports requests from a background task, so that the spans are not kept until the command
+/// finishes.
+struct Exporter {
+    requests: mpsc::UnboundedSender<ExportTraceServiceRequest>,
+    task: JoinHandle<buck2_error::Result<()>>,
+}
+
+impl Exporter {
+    fn spawn(endpoint: Option<String>, file: Option<AbsPathBuf>) -> Self {
+        let (requests, receiver) = mpsc::unbounded_channel();
+        Self {
+            requests,
+            task: tokio::spawn(export_requests(endpoint, file, receiver)),
+        }
+    }
+
+    fn send(&self, request: ExportTraceServiceRequest) {
+        // The task only stops early on an error, which `finish` reports.
+        let _ignored = self.requests.send(request);
+    }
+
+    /// Waits for all the requests to be exported.
+    async fn finish(self) -> buck2_error::Result<()> {
+        drop(self.requests);
+        self.task.await?
+    }
+}
+
+#[async_trait]
+impl EventSubscriber for OtelTraces {
+    fn name(&self) -> &'static str {
+        "otel_traces"
+    }
+
+    async fn handle_events(&mut self, events: &[Arc<BuckEvent>]) -> buck2_error::Result<()> {
+        for event in events {
+            self.handle_event(event);
+        }
+        Ok(())
+    }
+
+    async fn handle_command_result(
+        &mut self,
+        result: &buck2_cli_proto::CommandResult,
+    ) -> buck2_error::Result<()> {
+        if let Some(buck2_cli_proto::command_result::Result::Error(e)) = &result.result {
+            self.command_error = Some(e.message.clone());
+        }
+        Ok(())
+    }
+
+    async fn finalize(mut self: Box<Self>) -> buck2_error::Result<()> {
+        if self.endpoint.is_none() && self.file.is_none() {
+            return Ok(());
+        }
+        let requests = self.requests();
+        let exporter = match self.exporter.take() {
+            Some(exporter) => exporter,
+            None => Exporter::spawn(self.endpoint.take(), self.file.take()),
+        };
+        for request in requests {
+            exporter.send(request);
+        }
+        exporter.finish().await
+    }
+}
+
+/// Adds the attributes only known when the span ends, and returns the error of the span, if any.
+fn add_end_attributes(
+    attributes: &mut Vec<KeyValue>,
+    data: &buck2_data::span_end_event::Data,
+) -> Option<String> {
+    use buck2_data::span_end_event::Data;
+
+    match data {
+        Data::ActionExecution(action) => {
+            if let Ok(kind) = buck2_data::ActionExecutionKind::try_from(action.execution_kind) {
+                attributes.push(string_attribute(
+                    "buck2.action.execution_kind",
+                    kind.as_str_name(),
+                ));
+            }
+            attributes.push(int_attribute(
+                "buck2.action.output_size",
+                action.output_size,
+            ));
+            if action.failed {
+                return Some(match &action.error {
+                    Some(buck2_data::action_execution_end::Error::Unknown(message)) => {
+                        message.clone()
+                    }
+                    _ => "Action failed".to_owned(),
+                });
+            }
+        }
+        Data::Analysis(analysis) => return analysis.error.clone(),
+        Data::Materialization(materialization) => {
+            attributes.push(string_attribute(
+                "buck2.materialization.path",
+                &materialization.path,
+            ));
+            attributes.push(int_attribute(
+                "buck2.materialization.file_count",
+                materialization.file_count,
+            ));
+            attributes.push(int_attribute(
+                "buck2.materialization.total_bytes",
+                materialization.total_bytes,
+            ));
+            return materialization.error.clone();
+        }
+        Data::ReUpload(upload) => {
+            if let Some(digests) = upload.digests_uploaded {
+                attributes.push(int_attribute("buck2.re_upload.digests", digests));
+            }
+            if let Some(bytes) = upload.bytes_uploaded {
+                attributes.push(int_attribute("buck2.re_upload.bytes", bytes));
+            }
+        }
+        _ => {}
+    }
+    None
+}
+
+/// Writes and exports the requests in the order they are received, stopping at the first error.
+async fn export_requests(
+    endpoint: Option<String>,
+    path: Option<AbsPathBuf>,
+    mut requests: mpsc::UnboundedReceiver<ExportTraceServiceRequest>,
+) -> buck2_error::Result<()> {
+    let mut file = match &path {
+        Some(path) => Some(
+            tokio::fs::File::create(path)
+                .await
+                .with_buck_error_context(|| {
+                    format!("Error writing OpenTelemetry traces to `{path}`")
+                })?,
+        ),
+        None => None,
+    };
+    let mut client = None;
+    while let Some(request) = requests.recv().await {
+        if let (Some(file), Some(path)) = (&mut file, &path) {
+            file.write_all(render_json_lines(std::slice::from_ref(&request)).as_bytes())
+                .await
+                .with_buck_error_context(|| {
+                    format!("Error writing OpenTelemetry traces to `{path}`")
+                })?;
+        }
+        if let Some(endpoint) = &endpoint {
+            let client = match &mut client {
+                Some(client) => client,
+                None => client.insert(connect(endpoint).await.with_buck_error_context(|| {
+                    format!("Error exporting OpenTelemetry traces to `{endpoint}`")
+                })?),
+            };
+            export(client, request).await.with_buck_error_context(|| {
+                format!("Error exporting OpenTelemetry traces to `{endpoint}`")
+            })?;
+        }
+    }
+    if let (Some(file), Some(path)) = (&mut file, &path) {
+        file.flush().await.with_buck_error_context(|| {
+            format!("Error writing OpenTelemetry traces to `{path}`")
+        })?;
+    }
+    Ok(())
+}
+
+async fn connect(endpoint: &str) -> buck2_error::Result<TraceServiceClient<Channel>> {
+    let channel = Endpoint::from_shared(endpoint.to_owned())?
+        .connect_timeout(CONNECT_TIMEOUT)
+        .timeout(EXPORT_TIMEOUT)
+        .connect()
+        .await?;
+    Ok(TraceServiceClient::new(channel))
+}
+
+async fn export(
+    client: &mut TraceServiceClient<Channel>,
+    request: ExportTraceServiceRequest,
+) -> buck2_error::Result<()> {
+    let response = client.export(request).await?.into_inner();
+    if let Some(partial) = response.partial_success
+        && partial.rejected_spans > 0
+    {
+        return Err(
+            OtelTracesError::Rejected(partial.rejected_spans, partial.error_message).into(),
+        );
+    }
+    Ok(())
+}
+
+/// Renders the requests in the OTLP JSON encoding, one request per line.
+fn render_json_lines(requests: &[ExportTraceServiceRequest]) -> String {
+    let mut out = String::new();
+    for request in requests {
+        out.push_str(&request_to_json(request).to_string());
+        out.push('\n');
+    }
+    out
+}
+
+fn request_to_json(request: &ExportTraceServiceRequest) -> serde_json::Value {
+    let resource_spans: Vec<_> = request
+        .resource_spans
+        .iter()
+        .map(|resource_spans| {
+            let scope_spans: Vec<_> = resource_spans
+                .scope_spans
+                .iter()
+                .map(|scope_spans| {
+                    let mut json = json!({
+                        "spans": scope_spans.spans.iter().map(span_to_json).collect::<Vec<_>>(),
+                    });
+                    if let Some(scope) = &scope_spans.scope {
+                        json["scope"] = json!({"name": scope.name, "version": scope.version});
+                    }
+                    json
+                })
+                .collect();
+            let mut json = json!({ "scopeSpans": scope_spans });
+            if let Some(resource) = &resource_spans.resource {
+                json["resource"] = json!({"attributes": attributes_to_json(&resource.attributes)});
+            }
+            json
+        })
+        .collect();
+    json!({ "resourceSpans": resource_spans })
+}
+
+fn span_to_json(span: &Span) -> serde_json::Value {
+    // Following the OTLP JSON encoding, ids are hex strings and 64-bit integers are strings.
+    let mut json = json!({
+        "traceId": hex::encode(&span.trace_id),
+        "spanId": hex::encode(&span.span_id),
+        "name": span.name,
+        "kind": span.kind,
+        "startTimeUnixNano": span.start_time_unix_nano.to_string(),
+        "endTimeUnixNano": span.end_time_unix_nano.to_string(),
+        "attributes": attributes_to_json(&span.attributes),
+    });
+    if !span.parent_span_id.is_empty() {
+        json["parentSpanId"] = json!(hex::encode(&span.parent_span_id));
+    }
+    if let Some(status) = &span.status {
+        json["status"] = json!({"code": status.code, "message": status.message});
+    }
+    json
+}
+
+fn attributes_to_json(attributes: &[KeyValue]) -> serde_json::Value {
+    attributes
+        .iter()
+        .map(|attribute| {
+            let value = match attribute.value.as_ref().and_then(|v| v.value.as_ref()) {
+                Some(any_value::Value::StringValue(v)) => json!({ "stringValue": v }),
+                Some(any_value::Value::BoolValue(v)) => json!({ "boolValue": v }),
+                Some(any_value::Value::IntValue(v)) => json!({ "intValue": v.to_string() }),
+                Some(any_value::Value::DoubleValue(v)) => json!({ "doubleValue": v }),
+                None => json!({}),
+            };
+            json!({"key": attribute.key, "value": value})
+        })
+        .collect()
+}
+
+/// The action digest carried by an executor stage, if any.
+fn executor_stage_digest(stage: &buck2_data::executor_stage_start::Stage) -> Option<&str> {
+    use buck2_data::executor_stage_start::Stage;
+
+    let digest = match stage {
+        Stage::CacheQuery(cache_query) => &cache_query.action_digest,
+        Stage::CacheHit(cache_hit) => &cache_hit.action_digest,
+        Stage::Re(re) => match re.stage.as_ref()? {
+            buck2_data::re_stage::Stage::Execute(execute) => &execute.action_digest,
+            buck2_data::re_stage::Stage::Queue(queue) => &queue.action_digest,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(digest.as_str()).filter(|digest| !digest.is_empty())
+}
+
+fn string_attribute(key: &str, value: impl Into<String>) -> KeyValue {
+    KeyValue {
+        key: key.to_owned(),
+        value: Some(AnyValue {
+            value: Some(any_value::Value::StringValue(value.into())),
+        }),
+    }
+}
+
+fn int_attribute(key: &str, value: u64) -> KeyValue {
+    KeyValue {
+        key: key.to_owned(),
+        value: Some(AnyValue {
+            value: Some(any_value::Value::IntValue(
+                i64::try_from(value).unwrap_or(i64::MAX),
+            )),
+        }),
+    }
+}
+
+fn error_status(message: String) -> Status {
+    Status {
+        message,
+        code: StatusCode::Error as i32,
+    }
+}
+
+fn span_id_bytes(span_id: SpanId) -> Vec<u8> {
+    span_id.0.get().to_be_bytes().to_vec()
+}
+
+fn unix_nanos(timestamp: SystemTime) -> u64 {
+    timestamp
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| u64::try_from(d.as_nanos()).unwrap_or(u64::MAX))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+    use std::sync::Mutex;
+
+    use buck2_data::SpanEndEvent;
+    use buck2_data::SpanStartEvent;
+    use buck2_otel_proto::ExportTraceServiceResponse;
+    use buck2_otel_proto::trace_service_server::TraceService;
+    use buck2_otel_proto::trace_service_server::TraceServiceServer;
+    use dupe::Dupe;
+    use tonic::Request;
+    use tonic::Response;
+    use tonic::transport::Server;
+
+    use super::*;
+
+    fn at(secs: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    fn start(
+        span: SpanId,
+        parent: Option<SpanId>,
+        secs: u64,
+        data: buck2_data::span_start_event::Data,
+    ) -> BuckEvent {
+        BuckEvent::new(
+            at(secs),
+            TraceId::null(),
+            Some(span),
+            parent,
+            buck2_data::buck_event::Data::SpanStart(SpanStartEvent { data: Some(data) }),
+        )
+    }
+
+    fn end(span: SpanId, secs: u64, data: buck2_data::span_end_event::Data) -> BuckEvent {
+        BuckEvent::new(
+            at(secs),
+            TraceId::null(),
+            Some(span),
+            None,
+            buck2_data::buck_event::Data::SpanEnd(SpanEndEvent {
+                data: Some(data),
+                ..Default::default()
+            }),
+        )
+    }
+
+    /// A command running an action, with a span in between that is not exported.
+    fn traces() -> (OtelTraces, SpanId, SpanId) {
+        let mut traces = OtelTraces::new(TraceId::null(), "build", None, None);
+        let command = SpanId::next();
+        let hidden = SpanId::next();
+        let action = SpanId::next();
+        for event in [
+            start(
+                command,
+                None,
+                1,
+                buck2_data::span_start_event::Data::Command(Default::default()),
+            ),
+            start(
+                hidden,
+                Some(command),
+                2,
+                buck2_data::span_start_event::Data::Fake(Default::default()),
+            ),
+            start(
+                action,
+                Some(hidden),
+                3,
+                buck2_data::span_start_event::Data::ActionExecution(
+                    buck2_data::ActionExecutionStart {
+                        name: Some(buck2_data::ActionName {
+                            category: "cxx_compile".to_owned(),
+                            identifier: "main.cpp".to_owned(),
+                        }),
+                        ..Default::default()
+                    },
+                ),
+            ),
+            end(
+                action,
+                4,
+                buck2_data::span_end_event::Data::ActionExecution(Box::new(
+                    buck2_data::ActionExecutionEnd {
+                        failed: true,
+                        error: Some(buck2_data::action_execution_end::Error::Unknown(
+                            "boom".to_owned(),
+                        )),
+                        output_size: 7,
+                        ..Default::default()
+                    },
+                )),
+            ),
+            end(
+                hidden,
+                5,
+                buck2_data::span_end_event::Data::Fake(Default::default()),
+            ),
+            end(
+                command,
+                6,
+                buck2_data::span_end_event::Data::Command(Default::default()),
+            ),
+        ] {
+            traces.handle_event(&event);
+        }
+        (traces, command, action)
+    }
+
+    fn attribute<'a>(span: &'a Span, key: &str) -> Option<&'a any_value::Value> {
+        span.attributes
+            .iter()
+            .find(|attribute| attribute.key == key)
+            .and_then(|attribute| attribute.value.as_ref()?.value.as_ref())
+    }
+
+    #[test]
+    fn test_spans() {
+        let (mut traces, command, action) = traces();
+        traces.command_error = Some("Build failed".to_owned());
+        let requests = traces.requests();
+        assert_eq!(1, requests.len());
+        let spans = &requests[0].resource_spans[0].scope_spans[0].spans;
+        assert_eq!(2, spans.len());
+
+        let command_span = &spans[0];
+        assert_eq!("buck2 build", command_span.name);
+        assert_eq!(span_id_bytes(command), command_span.span_id);
+        assert!(command_span.parent_span_id.is_empty());
+        assert_eq!(
+            Some("Build failed"),
+            command_span.status.as_ref().map(|s| s.message.as_str())
+        );
+
+        let action_span = &spans[1];
+        assert_eq!("action", action_span.name);
+        assert_eq!(span_id_bytes(action), action_span.span_id);
+        // The hidden span in between is skipped.
+        assert_eq!(span_id_bytes(command), action_span.parent_span_id);
+        assert_eq!(3_000_000_000, action_span.start_time_unix_nano);
+        assert_eq!(4_000_000_000, action_span.end_time_unix_nano);
+        assert_eq!(
+            Some(&any_value::Value::StringValue("cxx_compile".to_owned())),
+            attribute(action_span, "buck2.action.category")
+        );
+        assert_eq!(
+            Some(&any_value::Value::IntValue(7)),
+            attribute(action_span, "buck2.action.output_size")
+        );
+        let status = action_span.status.as_ref().unwrap();
+        assert_eq!(StatusCode::Error as i32, status.code);
+        assert_eq!("boom", status.message);
+    }
+
+    #[test]
+    fn test_open_spans_end_with_command() {
+        let mut traces = OtelTraces::new(TraceId::null(), "build", None, None);
+        let command = SpanId::next();
+        traces.handle_event(&start(
+            command,
+            None,
+            1,
+            buck2_data::span_start_event::Data::Command(Default::default()),
+        ));
+        traces.handle_event(&BuckEvent::new(
+            at(9),
+            TraceId::null(),
+            None,
+            None,
+            buck2_data::buck_event::Data::Instant(Default::default()),
+        ));
+        let requests = traces.requests();
+        let span = &requests[0].resource_spans[0].scope_spans[0].spans[0];
+        assert_eq!(9_000_000_000, span.end_time_unix_nano);
+        assert_eq!(
+            Some(StatusCode::Error as i32),
+            span.status.as_ref().map(|s| s.code)
+        );
+    }
+
+    #[test]
+    fn test_executor_stage_digest() {
+        let mut traces = OtelTraces::new(TraceId::null(), "build", None, None);
+        let action = SpanId::next();
+        let execute = SpanId::next();
+        let download = SpanId::next();
+        let re_stage = |stage| {
+            buck2_data::span_start_event::Data::ExecutorStage(buck2_data::ExecutorStageStart {
+                stage: Some(buck2_data::executor_stage_start::Stage::Re(
+                    buck2_data::ReStage { stage: Some(stage) },
+                )),
+            })
+        };
+        for event in [
+            start(
+                action,
+                None,
+                1,
+                buck2_data::span_start_event::Data::ActionExecution(Default::default()),
+            ),
+            start(
+                execute,
+                Some(action),
+                2,
+                re_stage(buck2_data::re_stage::Stage::Execute(
+                    buck2_data::ReExecute {
+                        action_digest: "abc:10".to_owned(),
+                        ..Default::default()
+                    },
+                )),
+            ),
+            start(
+                download,
+                Some(action),
+                3,
+                re_stage(buck2_data::re_stage::Stage::Download(Default::default())),
+            ),
+        ] {
+            traces.handle_event(&event);
+        }
+        let requests = traces.requests();
+        let spans = &requests[0].resource_spans[0].scope_spans[0].spans;
+        assert_eq!(
+            vec!["action", "re_execute", "re_download"],
+            spans
+                .iter()
+                .map(|span| span.name.as_str())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(None, attribute(&spans[0], "buck2.action.digest"));
+        for span in &spans[1..] {
+            // The download stage does not carry the digest and reuses the one of the execution.
+            assert_eq!(
+                Some(&any_value::Value::StringValue("abc:10".to_owned())),
+                attribute(span, "buck2.action.digest")
+            );
+        }
+    }
+
+    #[test]
+    fn test_json() {
+        let (mut traces, command, action) = traces();
+        let json: serde_json::Value =
+            serde_json::from_str(render_json_lines(&traces.requests()).trim_end()).unwrap();
+        let action_span = &json["resourceSpans"][0]["scopeSpans"][0]["spans"][1];
+        assert_eq!(
+            json!(hex::encode(span_id_bytes(action))),
+            action_span["spanId"]
+        );
+        assert_eq!(
+            json!(hex::encode(span_id_bytes(command))),
+            action_span["parentSpanId"]
+        );
+        assert_eq!(json!("3000000000"), action_span["startTimeUnixNano"]);
+        assert_eq!(
+            json!({"key": "buck2.action.output_size", "value": {"intValue": "7"}}),
+            action_span["attributes"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|a| a["key"] == "buck2.action.output_size")
+                .unwrap()
+                .clone()
+        );
+        assert_eq!(json!({"code": 2, "message": "boom"}), action_span["status"]);
+        assert_eq!(
+            json!({"stringValue": "buck2"}),
+            json["resourceSpans"][0]["resource"]["attributes"][0]["value"]
+        );
+    }
+
+    /// Stands in for an OpenTelemetry collector.
+    #[derive(Default)]
+    struct Collector {
+        requests: Arc<Mutex<Vec<ExportTraceServiceRequest>>>,
+    }
+
+    #[tonic::async_trait]
+    impl TraceService for Collector {
+        async fn export(
+            &self,
+            request: Request<ExportTraceServiceRequest>,
+        ) -> Result<Response<ExportTraceServiceResponse>, tonic::Status> {
+            self.requests.lock().unwrap().push(request.into_inner());
+            Ok(Response::new(ExportTraceServiceResponse::default()))
+        }
+    }
+
+    async fn collector(requests: Arc<Mutex<Vec<ExportTraceServiceRequest>>>) -> SocketAddr {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            Server::builder()
+                .add_service(TraceServiceServer::new(Collector { requests }))
+                .serve_with_incoming(tokio_stream::wrappers::TcpListenerStream::new(listener))
+                .await
+                .unwrap();
+        });
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_export_to_collector() {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let addr = collector(requests.dupe()).await;
+
+        let (mut traces, _, _) = traces();
+        traces.endpoint = Some(format!("http://{addr}"));
+        Box::new(traces).finalize().await.unwrap();
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(1, requests.len());
+        let spans = &requests[0].resource_spans[0].scope_spans[0].spans;
+        assert_eq!(
+            vec!["buck2 build", "action"],
+            spans.iter().map(|s| s.name.as_str()).collect::<Vec<_>>()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_export_batches() {
+        let mut traces = OtelTraces::new(TraceId::null(), "build", None, None);
+        for i in 0..(BATCH_SIZE as u64 + 1) {
+            traces.handle_event(&start(
+                SpanId::next(),
+                None,
+                i,
+                buck2_data::span_start_event::Data::ReUpload(Default::default()),
+            ));
+        }
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let addr = collector(requests.dupe()).await;
+        traces.endpoint = Some(format!("http://{addr}"));
+        Box::new(traces).finalize().await.unwrap();
+        assert_eq!(2, requests.lock().unwrap().len());
+    }
+
+    #[tokio::test]
+    async fn test_export_batches_during_command() {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let addr = collector(requests.dupe()).await;
+        let mut traces = OtelTraces::new(
+            TraceId::null(),
+            "build",
+            Some(format!("http://{addr}")),
+            None,
+        );
+        for i in 0..(BATCH_SIZE as u64 + 1) {
+            let span = SpanId::next();
+            traces.handle_event(&start(
+                span,
+                None,
+                i,
+                buck2_data::span_start_event::Data::ReUpload(Default::default()),
+            ));
+            traces.handle_event(&end(
+                span,
+                i,
+                buck2_data::span_end_event::Data::ReUpload(Default::default()),
+            ));
+        }
+        // The first batch is exported without waiting for the command to finish.
+        while requests.lock().unwrap().is_empty() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(
+            BATCH_SIZE,
+            requests.lock().unwrap()[0].resource_spans[0].scope_spans[0]
+                .spans
+                .len()
+        );
+        assert!(traces.finished.len() < BATCH_SIZE);
+
+        Box::new(traces).finalize().await.unwrap();
+        let requests = requests.lock().unwrap();
+        assert_eq!(2, requests.len());
+        assert_eq!(1, requests[1].resource_spans[0].scope_spans[0].spans.len());
+    }
+}
@@ -1787,7 +1787,7 @@ impl InvocationRecorder {
     ) -> buck2_error::Result<()> {
         let duration = duration_since(event.timestamp(), self.start_time);
         match test_result.status() {
-            buck2_data::TestStatus::Pass => {
+            buck2_data::TestStatus::Pass | buck2_data::TestStatus::Flaky => {
                 self.time_to_first_pass_test_result.get_or_insert(duration);
             }
             buck2_data::TestStatus::Fail => {
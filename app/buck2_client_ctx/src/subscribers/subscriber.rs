@@ -12,6 +12,7 @@ use std::sync::Arc;
 
 use async_trait::async_trait;
 use buck2_events::BuckEvent;
+use buck2_events::span::SpanId;
 
 use crate::console_interaction_stream::ConsoleInteraction;
 use crate::exit_result::ExitResult;
@@ -80,6 +81,12 @@ pub trait EventSubscriber: Send {
     fn as_error_observer(&self) -> Option<&dyn ErrorObserver> {
         None
     }
+
+    /// The spans of the executions of the actions the user asked to cancel since the last call.
+    fn take_action_cancellations(&mut self) -> Vec<SpanId> {
+        Vec::new()
+    }
+
     fn handle_stream_end(&mut self) {}
     fn handle_daemon_connection_failure(&mut self) {}
     fn handle_daemon_started(&mut self, _reason: buck2_data::DaemonWasStartedReason) {}
@@ -31,6 +31,7 @@ use buck2_event_observer::verbosity::Verbosity;
 use buck2_event_observer::what_ran::command_to_string;
 use buck2_event_observer::what_ran::worker_command_as_fallback_to_string;
 use buck2_events::BuckEvent;
+use buck2_events::span::SpanId;
 use buck2_health_check::report::DisplayReport;
 use buck2_wrapper_common::invocation_id::TraceId;
 use gazebo::prelude::*;
@@ -57,6 +58,8 @@ use crate::subscribers::console_output_limit::EmitResult;
 use crate::subscribers::emit_event::emit_event_if_relevant;
 use crate::subscribers::simpleconsole::SimpleConsole;
 use crate::subscribers::subscriber::EventSubscriber;
+use crate::subscribers::superconsole::action_inspector::ActionInspector;
+use crate::subscribers::superconsole::action_inspector::ActionInspectorComponent;
 use crate::subscribers::superconsole::commands::CommandsComponent;
 use crate::subscribers::superconsole::debug_events::DebugEventsComponent;
 use crate::subscribers::superconsole::debugger::StarlarkDebuggerComponent;
@@ -72,6 +75,7 @@ use crate::subscribers::superconsole::timed_list::TimedList;
 use crate::subscribers::superconsole::timekeeper::Timekeeper;
 use crate::ticker::Tick;
 
+mod action_inspector;
 mod commands;
 mod common;
 pub(crate) mod debug_events;
@@ -383,6 +387,7 @@ pub struct StatefulSuperConsoleImpl {
     super_console: SuperConsole,
     verbosity: Verbosity,
     games_overlay: GamesOverlay,
+    action_inspector: ActionInspector,
     shown_interactive_console_message: bool,
 }
 
@@ -444,6 +449,7 @@ struct BuckRootComponent<'s> {
     header: &'s str,
     state: &'s SuperConsoleState,
     games_overlay: &'s GamesOverlay,
+    action_inspector: &'s ActionInspector,
 }
 
 /// Adapter that wraps a `Component<Error = anyhow::Error>` to produce
@@ -622,8 +628,21 @@ impl Component for BuckRootComponent<'_> {
             },
             mode,
         )?;
+        draw.draw(
+            &ActionInspectorComponent {
+                inspector: self.action_inspector,
+                timekeeper: &self.state.timekeeper,
+                display_platform: self.state.config.display_platform,
+            },
+            mode,
+        )?;
         draw.draw(&TasksHeader::new(self.header, self.state), mode)?;
-        draw.draw(&TimedList::new(&CUTOFFS, self.state), mode)?;
+        let roots = self.state.simple_console.observer.spans().roots_ongoing();
+        draw.draw(
+            &TimedList::new(&CUTOFFS, self.state)
+                .with_selected(self.action_inspector.selected(roots)),
+            mode,
+        )?;
 
         Ok(draw.finish())
     }
@@ -685,6 +704,7 @@ impl StatefulSuperConsole {
             super_console,
             verbosity,
             games_overlay: GamesOverlay::new(),
+            action_inspector: ActionInspector::new(),
             shown_interactive_console_message: false,
         }))
     }
@@ -828,6 +848,7 @@ impl StatefulSuperConsoleImpl {
 
     async fn handle_event(&mut self, event: &Arc<BuckEvent>) -> buck2_error::Result<()> {
         self.state.update_event_observer(event).await?;
+        self.action_inspector.observe(event);
 
         self.handle_inner_event(event)
             .await
@@ -1147,6 +1168,14 @@ impl StatefulSuperConsoleImpl {
                         ConsoleKey::ShiftRight => self.handle_games_control(Control::ShiftRight),
                         ConsoleKey::Other => {}
                     }
+                } else if self.action_inspector.active {
+                    let roots = self.state.simple_console.observer().spans().roots_ongoing();
+                    match key {
+                        ConsoleKey::Escape => self.action_inspector.escape(),
+                        ConsoleKey::Up => self.action_inspector.move_cursor(false, roots),
+                        ConsoleKey::Down => self.action_inspector.move_cursor(true, roots),
+                        _ => {}
+                    }
                 }
                 return Ok(());
             }
@@ -1155,6 +1184,7 @@ impl StatefulSuperConsoleImpl {
                     header: &self.header,
                     state: &self.state,
                     games_overlay: &self.games_overlay,
+                    action_inspector: &self.action_inspector,
                 })?;
                 return Ok(());
             }
@@ -1234,6 +1264,35 @@ impl StatefulSuperConsoleImpl {
                 })
                 .await?
             }
+            SuperConsoleToggle::ActionInspector => {
+                if self.action_inspector.active {
+                    self.action_inspector.deactivate();
+                } else {
+                    self.action_inspector.activate();
+                }
+                let on_off = if self.action_inspector.active {
+                    "on"
+                } else {
+                    "off"
+                };
+                self.handle_stderr(&format!(
+                    "{}: {on_off}, press `{}` to revert",
+                    c.description(),
+                    c.key()
+                ))
+                .await?
+            }
+            SuperConsoleToggle::Char('\n') if self.action_inspector.active => {
+                let spans = self.state.simple_console.observer().spans();
+                if let Some(selected) = self.action_inspector.selected(spans.roots_ongoing())
+                    && let Some(root) = spans.iter_roots().nth(selected)
+                {
+                    self.action_inspector.open(&root);
+                }
+            }
+            SuperConsoleToggle::Char('C') if self.action_inspector.active => {
+                self.action_inspector.cancel()
+            }
             SuperConsoleToggle::IncrLines => {
                 self.state.config.max_lines = self.state.config.max_lines.saturating_add(1)
             }
@@ -1475,6 +1534,7 @@ impl StatefulSuperConsoleImpl {
             header: &self.header,
             state: &self.state,
             games_overlay: &self.games_overlay,
+            action_inspector: &self.action_inspector,
         })?;
         Ok(())
     }
@@ -1500,6 +1560,7 @@ impl StatefulSuperConsoleImpl {
                 header: &self.header,
                 state: &self.state,
                 games_overlay: &self.games_overlay,
+                action_inspector: &self.action_inspector,
             })
             .err();
         (self.state, err)
@@ -1572,6 +1633,13 @@ impl EventSubscriber for StatefulSuperConsole {
         Ok(())
     }
 
+    fn take_action_cancellations(&mut self) -> Vec<SpanId> {
+        match self {
+            Self::Running(super_console) => super_console.action_inspector.take_cancellations(),
+            Self::Finalized(_) => Vec::new(),
+        }
+    }
+
     async fn tick(&mut self, tick: &Tick) -> buck2_error::Result<()> {
         if let Self::Running(super_console) = self {
             super_console.tick(tick).await?;
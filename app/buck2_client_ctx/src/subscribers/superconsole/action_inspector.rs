@@ -0,0 +1,689 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is dual-licensed under either the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree or the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree. You may select, at your option, one of the
+ * above-listed licenses.
+ */
+
+//! Interactive inspector for the actions in the timed list: a cursor to pick a running action,
+//! and a pane with what is known about it.
+
+use std::sync::Arc;
+
+use buck2_event_observer::display;
+use buck2_event_observer::display::TargetDisplayOptions;
+use buck2_event_observer::fmt_duration;
+use buck2_event_observer::span_tracker::BuckEventSpanHandle;
+use buck2_event_observer::span_tracker::EventTimestamp;
+use buck2_event_observer::span_tracker::is_span_shown;
+use buck2_event_observer::what_ran::CommandReproducer;
+use buck2_event_observer::what_ran::WhatRanOptions;
+use buck2_events::BuckEvent;
+use buck2_events::span::SpanId;
+use buck2_hash::BuckMutMap;
+use dupe::Dupe;
+use superconsole::Component;
+use superconsole::Dimensions;
+use superconsole::DrawMode;
+use superconsole::Line;
+use superconsole::Lines;
+use superconsole::Span;
+use superconsole::style::Stylize;
+
+use crate::subscribers::superconsole::timekeeper::Timekeeper;
+use crate::subscribers::superconsole::timekeeper::duration_between_timestamps;
+
+const MAX_PHASES: usize = 8;
+const MAX_COMMAND_LINES: usize = 4;
+const MAX_ENV_ENTRIES: usize = 5;
+const MAX_STDERR_LINES: usize = 5;
+
+pub(crate) struct ActionInspector {
+    pub(crate) active: bool,
+    /// Index of the selected root in the timed list. Roots come and go, so this is clamped to
+    /// the number of roots whenever it is used.
+    cursor: usize,
+    /// What we know about every ongoing root, collected from its start so that the pane can list
+    /// phases that ended before it was opened. The root in the pane is kept after it finishes.
+    actions: BuckMutMap<SpanId, ActionPane>,
+    /// The root of every ongoing span under a root, and whether the span is shown. Roots are
+    /// picked the same way as in the span tracker.
+    root_of: BuckMutMap<SpanId, (SpanId, bool)>,
+    pane: Option<SpanId>,
+    /// Executions the user asked to cancel, not yet sent to the daemon.
+    cancellations: Vec<SpanId>,
+}
+
+impl ActionInspector {
+    pub(crate) fn new() -> Self {
+        Self {
+            active: false,
+            cursor: 0,
+            actions: BuckMutMap::default(),
+            root_of: BuckMutMap::default(),
+            pane: None,
+            cancellations: Vec::new(),
+        }
+    }
+
+    pub(crate) fn activate(&mut self) {
+        self.active = true;
+        self.cursor = 0;
+    }
+
+    pub(crate) fn deactivate(&mut self) {
+        self.active = false;
+        self.close();
+    }
+
+    /// Close the pane if it is open, otherwise leave the inspector.
+    pub(crate) fn escape(&mut self) {
+        if !self.close() {
+            self.deactivate();
+        }
+    }
+
+    fn close(&mut self) -> bool {
+        let Some(root) = self.pane.take() else {
+            return false;
+        };
+        if self
+            .actions
+            .get(&root)
+            .is_some_and(|pane| pane.end.is_some())
+        {
+            self.actions.remove(&root);
+        }
+        true
+    }
+
+    fn open_pane(&self) -> Option<&ActionPane> {
+        self.pane.and_then(|root| self.actions.get(&root))
+    }
+
+    pub(crate) fn selected(&self, roots: usize) -> Option<usize> {
+        if !self.active || roots == 0 {
+            return None;
+        }
+        Some(self.cursor.min(roots - 1))
+    }
+
+    pub(crate) fn move_cursor(&mut self, down: bool, roots: usize) {
+        let Some(selected) = self.selected(roots) else {
+            return;
+        };
+        self.cursor = if down {
+            (selected + 1).min(roots - 1)
+        } else {
+            selected.saturating_sub(1)
+        };
+    }
+
+    pub(crate) fn open(&mut self, root: &BuckEventSpanHandle) {
+        self.close();
+        let Some(root_id) = root.info().event.span_id() else {
+            return;
+        };
+        if !self.actions.contains_key(&root_id) {
+            // Only if we missed the start of the root, e.g. because it started before the console.
+            self.actions
+                .insert(root_id, ActionPane::seeded(root_id, root));
+        }
+        self.pane = Some(root_id);
+    }
+
+    /// Ask to cancel the action in the pane. Only actions that are still running can be
+    /// cancelled.
+    pub(crate) fn cancel(&mut self) {
+        let Some(pane) = self.pane.and_then(|root| self.actions.get_mut(&root)) else {
+            return;
+        };
+        if pane.is_action() && pane.end.is_none() && !pane.cancel_requested {
+            pane.cancel_requested = true;
+            self.cancellations.push(pane.root);
+        }
+    }
+
+    pub(crate) fn take_cancellations(&mut self) -> Vec<SpanId> {
+        std::mem::take(&mut self.cancellations)
+    }
+
+    pub(crate) fn observe(&mut self, event: &Arc<BuckEvent>) {
+        if let buck2_data::buck_event::Data::Instant(instant) = event.data() {
+            if let Some(buck2_data::instant_event::Data::ActionStderrTail(tail)) = &instant.data
+                && let Some(pane) = event
+                    .parent_id()
+                    .and_then(|parent| self.root_of.get(&parent))
+                    .and_then(|(root, _)| self.actions.get_mut(root))
+            {
+                pane.stderr = Some(tail.stderr.clone());
+            }
+            return;
+        }
+        let Some(span_id) = event.span_id() else {
+            return;
+        };
+        let timestamp = EventTimestamp(event.timestamp().into());
+        if event.span_start_event().is_some() {
+            let shown = is_span_shown(event);
+            let parent = event
+                .parent_id()
+                .and_then(|parent| self.root_of.get(&parent).copied());
+            match parent {
+                // A shown span under a hidden one is a root of its own, as in the span tracker.
+                Some((root, parent_shown)) if parent_shown || !shown => {
+                    self.root_of.insert(span_id, (root, shown));
+                    if let Some(pane) = self.actions.get_mut(&root) {
+                        pane.add_phase(span_id, event, timestamp);
+                    }
+                }
+                _ if shown => {
+                    self.root_of.insert(span_id, (span_id, true));
+                    self.actions
+                        .insert(span_id, ActionPane::new(span_id, event, timestamp));
+                }
+                _ => {}
+            }
+        } else if event.span_end_event().is_some() {
+            let Some((root, _)) = self.root_of.remove(&span_id) else {
+                return;
+            };
+            if root == span_id {
+                if self.pane == Some(root) {
+                    if let Some(pane) = self.actions.get_mut(&root) {
+                        pane.finish(event, timestamp);
+                    }
+                } else {
+                    self.actions.remove(&root);
+                }
+            } else if let Some(pane) = self.actions.get_mut(&root) {
+                pane.end_phase(span_id, timestamp);
+            }
+        }
+    }
+}
+
+struct Phase {
+    span: SpanId,
+    event: Arc<BuckEvent>,
+    start: EventTimestamp,
+    end: Option<EventTimestamp>,
+}
+
+/// What we know about a root and the spans under it.
+struct ActionPane {
+    root: SpanId,
+    root_event: Arc<BuckEvent>,
+    start: EventTimestamp,
+    end: Option<EventTimestamp>,
+    phases: Vec<Phase>,
+    command: Option<CommandReproducer>,
+    /// The end of the stderr of the command running for the action, then the stderr of its last
+    /// command once the action finishes.
+    stderr: Option<String>,
+    cancel_requested: bool,
+}
+
+impl ActionPane {
+    fn new(root: SpanId, event: &Arc<BuckEvent>, start: EventTimestamp) -> Self {
+        Self {
+            root,
+            root_event: event.dupe(),
+            start,
+            end: None,
+            phases: Vec::new(),
+            command: None,
+            stderr: None,
+            cancel_requested: false,
+        }
+    }
+
+    fn seeded(root_id: SpanId, root: &BuckEventSpanHandle) -> Self {
+        let info = root.info();
+        let mut pane = Self::new(root_id, &info.event, info.start);
+        pane.seed(root);
+        pane
+    }
+
+    fn seed(&mut self, span: &BuckEventSpanHandle) {
+        for child in span.children() {
+            let info = child.info();
+            if let Some(span_id) = info.event.span_id() {
+                self.add_phase(span_id, &info.event, info.start);
+            }
+            self.seed(&child);
+        }
+    }
+
+    fn is_action(&self) -> bool {
+        matches!(
+            self.root_event
+                .span_start_event()
+                .and_then(|start| start.data.as_ref()),
+            Some(buck2_data::span_start_event::Data::ActionExecution(..))
+        )
+    }
+
+    fn add_phase(&mut self, span: SpanId, event: &Arc<BuckEvent>, start: EventTimestamp) {
+        if let Some(command) =
+            CommandReproducer::from_buck_data(event.data(), &WhatRanOptions::default())
+        {
+            self.command = Some(command);
+        }
+        if is_span_shown(event) {
+            self.phases.push(Phase {
+                span,
+                event: event.dupe(),
+                start,
+                end: None,
+            });
+        }
+    }
+
+    fn end_phase(&mut self, span: SpanId, end: EventTimestamp) {
+        if let Some(phase) = self.phases.iter_mut().find(|p| p.span == span) {
+            phase.end = Some(end);
+        }
+    }
+
+    fn finish(&mut self, event: &BuckEvent, end: EventTimestamp) {
+        self.end = Some(end);
+        if let Some(buck2_data::span_end_event::Data::ActionExecution(action)) =
+            event.span_end_event().and_then(|end| end.data.as_ref())
+        {
+            self.stderr = action
+                .commands
+                .last()
+                .and_then(|command| command.details.as_ref())
+                .map(|details| details.cmd_stderr.clone());
+        }
+    }
+
+    fn env(&self) -> &[buck2_data::EnvironmentEntry] {
+        let env = match &self.command {
+            Some(CommandReproducer::LocalExecute(execute)) => {
+                execute.command.as_ref().map(|c| &c.env)
+            }
+            Some(CommandReproducer::WorkerExecute(execute)) => {
+                execute.command.as_ref().map(|c| &c.env)
+            }
+            Some(CommandReproducer::WorkerInit(init)) => init.command.as_ref().map(|c| &c.env),
+            _ => None,
+        };
+        env.map_or(&[], |env| env.as_slice())
+    }
+}
+
+/// Renders the inspector: a hint while picking an action, then the pane for the picked one.
+pub(crate) struct ActionInspectorComponent<'a> {
+    pub(crate) inspector: &'a ActionInspector,
+    pub(crate) timekeeper: &'a Timekeeper,
+    pub(crate) display_platform: bool,
+}
+
+impl ActionInspectorComponent<'_> {
+    fn elapsed(&self, start: EventTimestamp, end: Option<EventTimestamp>) -> String {
+        let elapsed = match end {
+            Some(end) => duration_between_timestamps(start.0, end.0),
+            None => self.timekeeper.duration_since(start),
+        };
+        fmt_duration::fmt_duration(elapsed)
+    }
+
+    fn pane_lines(&self, pane: &ActionPane, width: usize) -> buck2_error::Result<Vec<Line>> {
+        let options = TargetDisplayOptions::for_console(self.display_platform);
+        let heading = |text: &str| Span::new_styled_lossy(text.to_owned().bold());
+        let indented = |text: &str| Line::from_iter([Span::padding(2), Span::sanitized(text)]);
+
+        let mut lines = Vec::new();
+        lines.push(Line::from_iter([
+            heading("Action: "),
+            Span::sanitized(display::display_event(&pane.root_event, options)?),
+        ]));
+        let status = match pane.end {
+            Some(_) => "finished in",
+            None if pane.cancel_requested => "cancelling, running for",
+            None => "running for",
+        };
+        lines.push(Line::from_iter([
+            heading("Status: "),
+            Span::sanitized(format!("{status} {}", self.elapsed(pane.start, pane.end))),
+        ]));
+        if let Some(command) = &pane.command {
+            lines.push(Line::from_iter([
+                heading("Executor: "),
+                Span::sanitized(command.executor()),
+            ]));
+        }
+
+        if !pane.phases.is_empty() {
+            lines.push(Line::from_iter([heading("Phases:")]));
+            let hidden = pane.phases.len().saturating_sub(MAX_PHASES);
+            if hidden > 0 {
+                lines.push(indented(&format!("... {hidden} earlier")));
+            }
+            for phase in &pane.phases[hidden..] {
+                let name = display::display_event(&phase.event, options)?.to_string();
+                let running = if phase.end.is_none() {
+                    " (running)"
+                } else {
+                    ""
+                };
+                lines.push(indented(&format!(
+                    "{name} {}{running}",
+                    self.elapsed(phase.start, phase.end)
+                )));
+            }
+        }
+
+        if let Some(command) = &pane.command {
+            let command: Vec<char> = command.to_string().chars().collect();
+            if !command.is_empty() {
+                lines.push(Line::from_iter([heading("Command:")]));
+                let chunk_len = width.saturating_sub(2).max(1);
+                let mut chunks = command.chunks(chunk_len);
+                for chunk in chunks.by_ref().take(MAX_COMMAND_LINES) {
+                    lines.push(indented(&chunk.iter().collect::<String>()));
+                }
+                if chunks.next().is_some() {
+                    lines.push(indented(
+                        "... (run `buck2 log what-ran` for the full command)",
+                    ));
+                }
+            }
+        }
+
+        let env = pane.env();
+        if !env.is_empty() {
+            lines.push(Line::from_iter([heading("Env:")]));
+            for entry in env.iter().take(MAX_ENV_ENTRIES) {
+                lines.push(indented(&format!("{}={}", entry.key, entry.value)));
+            }
+            if env.len() > MAX_ENV_ENTRIES {
+                lines.push(indented(&format!(
+                    "... and {} more",
+                    env.len() - MAX_ENV_ENTRIES
+                )));
+            }
+        }
+
+        if pane.is_action() {
+            lines.push(Line::from_iter([heading("Stderr:")]));
+            match (&pane.stderr, pane.end) {
+                (Some(stderr), _) if !stderr.trim().is_empty() => {
+                    let stderr: Vec<&str> = stderr.lines().collect();
+                    let hidden = stderr.len().saturating_sub(MAX_STDERR_LINES);
+                    if hidden > 0 {
+                        lines.push(indented(&format!("... {hidden} earlier lines")));
+                    }
+                    for line in &stderr[hidden..] {
+                        lines.push(indented(line));
+                    }
+                }
+                (_, Some(_)) => lines.push(indented("(empty)")),
+                (_, None) => lines.push(indented("(nothing yet)")),
+            }
+        }
+
+        let hint = if pane.is_action() && pane.end.is_none() && !pane.cancel_requested {
+            "Shift+C to cancel the action, Esc to close"
+        } else {
+            "Esc to close"
+        };
+        lines.push(Line::from_iter([Span::new_styled_lossy(
+            hint.to_owned().italic(),
+        )]));
+        Ok(lines)
+    }
+}
+
+impl Component for ActionInspectorComponent<'_> {
+    type Error = buck2_error::Error;
+
+    fn draw_unchecked(&self, dimensions: Dimensions, mode: DrawMode) -> buck2_error::Result<Lines> {
+        if !self.inspector.active || mode == DrawMode::Final {
+            return Ok(Lines::new());
+        }
+        match self.inspector.open_pane() {
+            Some(pane) => Ok(Lines(self.pane_lines(pane, dimensions.width)?)),
+            None => Ok(Lines(vec![Line::from_iter([Span::new_styled_lossy(
+                "Action inspector: Up/Down to select an action, Enter to inspect it, Esc to exit"
+                    .to_owned()
+                    .italic(),
+            )])])),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use std::time::SystemTime;
+
+    use buck2_data::FakeStart;
+    use buck2_data::SpanEndEvent;
+    use buck2_data::SpanStartEvent;
+    use buck2_event_observer::span_tracker::BuckEventSpanTracker;
+    use buck2_wrapper_common::invocation_id::TraceId;
+
+    use super::*;
+
+    fn start(span: SpanId, parent: Option<SpanId>, name: &str) -> Arc<BuckEvent> {
+        Arc::new(BuckEvent::new(
+            SystemTime::UNIX_EPOCH + Duration::from_secs(10),
+            TraceId::null(),
+            Some(span),
+            parent,
+            buck2_data::buck_event::Data::SpanStart(SpanStartEvent {
+                data: Some(buck2_data::span_start_event::Data::Fake(FakeStart {
+                    caramba: name.to_owned(),
+                })),
+            }),
+        ))
+    }
+
+    fn end(span: SpanId, parent: Option<SpanId>, secs: u64) -> Arc<BuckEvent> {
+        Arc::new(BuckEvent::new(
+            SystemTime::UNIX_EPOCH + Duration::from_secs(secs),
+            TraceId::null(),
+            Some(span),
+            parent,
+            buck2_data::buck_event::Data::SpanEnd(SpanEndEvent {
+                data: Some(buck2_data::span_end_event::Data::Fake(
+                    buck2_data::FakeEnd::default(),
+                )),
+                ..Default::default()
+            }),
+        ))
+    }
+
+    #[test]
+    fn test_cursor_is_clamped_to_roots() {
+        let mut inspector = ActionInspector::new();
+        assert_eq!(inspector.selected(3), None);
+
+        inspector.activate();
+        assert_eq!(inspector.selected(0), None);
+        inspector.move_cursor(true, 3);
+        inspector.move_cursor(true, 3);
+        inspector.move_cursor(true, 3);
+        assert_eq!(inspector.selected(3), Some(2));
+
+        // Roots finished under the cursor: it stays on the last one, and moves up from there.
+        assert_eq!(inspector.selected(2), Some(1));
+        inspector.move_cursor(false, 2);
+        assert_eq!(inspector.selected(2), Some(0));
+        inspector.move_cursor(false, 2);
+        assert_eq!(inspector.selected(2), Some(0));
+    }
+
+    #[test]
+    fn test_escape_closes_pane_then_inspector() {
+        let root = SpanId::next();
+        let mut tracker = BuckEventSpanTracker::new();
+        tracker.handle_event(&start(root, None, "root")).unwrap();
+
+        let mut inspector = ActionInspector::new();
+        inspector.activate();
+        inspector.open(&tracker.iter_roots().next().unwrap());
+        assert!(inspector.pane.is_some());
+
+        inspector.escape();
+        assert!(inspector.active);
+        assert!(inspector.pane.is_none());
+        inspector.escape();
+        assert!(!inspector.active);
+    }
+
+    fn action_start(span: SpanId) -> Arc<BuckEvent> {
+        Arc::new(BuckEvent::new(
+            SystemTime::UNIX_EPOCH + Duration::from_secs(10),
+            TraceId::null(),
+            Some(span),
+            None,
+            buck2_data::buck_event::Data::SpanStart(SpanStartEvent {
+                data: Some(buck2_data::span_start_event::Data::ActionExecution(
+                    buck2_data::ActionExecutionStart::default(),
+                )),
+            }),
+        ))
+    }
+
+    fn action_end(span: SpanId, stderr: &str) -> Arc<BuckEvent> {
+        Arc::new(BuckEvent::new(
+            SystemTime::UNIX_EPOCH + Duration::from_secs(12),
+            TraceId::null(),
+            Some(span),
+            None,
+            buck2_data::buck_event::Data::SpanEnd(SpanEndEvent {
+                data: Some(buck2_data::span_end_event::Data::ActionExecution(Box::new(
+                    buck2_data::ActionExecutionEnd {
+                        commands: vec![buck2_data::CommandExecution {
+                            details: Some(buck2_data::CommandExecutionDetails {
+                                cmd_stderr: stderr.to_owned(),
+                                ..Default::default()
+                            }),
+                            ..Default::default()
+                        }],
+                        ..Default::default()
+                    },
+                ))),
+                ..Default::default()
+            }),
+        ))
+    }
+
+    fn stderr_tail(parent: SpanId, stderr: &str) -> Arc<BuckEvent> {
+        Arc::new(BuckEvent::new(
+            SystemTime::UNIX_EPOCH + Duration::from_secs(11),
+            TraceId::null(),
+            None,
+            Some(parent),
+            buck2_data::buck_event::Data::Instant(buck2_data::InstantEvent {
+                data: Some(
+                    buck2_data::ActionStderrTail {
+                        stderr: stderr.to_owned(),
+                    }
+                    .into(),
+                ),
+            }),
+        ))
+    }
+
+    #[test]
+    fn test_pane_tracks_phases() {
+        let root = SpanId::next();
+        let earlier = SpanId::next();
+        let seeded = SpanId::next();
+        let nested = SpanId::next();
+        let unrelated = SpanId::next();
+
+        let mut tracker = BuckEventSpanTracker::new();
+        let mut inspector = ActionInspector::new();
+        for event in [
+            start(root, None, "root"),
+            start(earlier, Some(root), "earlier"),
+            end(earlier, Some(root), 11),
+            start(seeded, Some(root), "seeded"),
+        ] {
+            tracker.handle_event(&event).unwrap();
+            inspector.observe(&event);
+        }
+
+        inspector.activate();
+        inspector.open(&tracker.iter_roots().next().unwrap());
+
+        inspector.observe(&start(nested, Some(seeded), "nested"));
+        inspector.observe(&start(unrelated, None, "unrelated"));
+        inspector.observe(&end(seeded, Some(root), 12));
+        inspector.observe(&end(root, None, 13));
+        inspector.observe(&end(unrelated, None, 13));
+
+        let pane = inspector.open_pane().unwrap();
+        let phases: Vec<(SpanId, bool)> = pane
+            .phases
+            .iter()
+            .map(|p| (p.span, p.end.is_some()))
+            .collect();
+        assert_eq!(
+            phases,
+            vec![(earlier, true), (seeded, true), (nested, false)]
+        );
+        assert_eq!(
+            duration_between_timestamps(pane.start.0, pane.end.unwrap().0),
+            Duration::from_secs(3)
+        );
+
+        // Finished roots are only kept while they are in the pane.
+        assert_eq!(inspector.actions.len(), 1);
+        inspector.escape();
+        assert!(inspector.actions.is_empty());
+    }
+
+    #[test]
+    fn test_cancel_and_stderr() {
+        let action = SpanId::next();
+        let other = SpanId::next();
+
+        let mut tracker = BuckEventSpanTracker::new();
+        let mut inspector = ActionInspector::new();
+        for event in [action_start(action), start(other, None, "other")] {
+            tracker.handle_event(&event).unwrap();
+            inspector.observe(&event);
+        }
+        inspector.activate();
+
+        // Only actions can be cancelled.
+        inspector.open(&tracker.iter_roots().nth(1).unwrap());
+        inspector.cancel();
+        assert!(inspector.take_cancellations().is_empty());
+
+        inspector.open(&tracker.iter_roots().next().unwrap());
+        inspector.cancel();
+        inspector.cancel();
+        assert_eq!(inspector.take_cancellations(), vec![action]);
+        assert!(inspector.take_cancellations().is_empty());
+
+        // The stderr is shown while the command runs, then replaced with the full one.
+        let stage = SpanId::next();
+        inspector.observe(&start(stage, Some(action), "stage"));
+        inspector.observe(&stderr_tail(stage, "warning"));
+        assert_eq!(
+            inspector.open_pane().unwrap().stderr.as_deref(),
+            Some("warning")
+        );
+
+        inspector.observe(&end(stage, Some(action), 12));
+        inspector.observe(&action_end(action, "warning\nerror: cancelled"));
+        let pane = inspector.open_pane().unwrap();
+        assert!(pane.end.is_some());
+        assert_eq!(pane.stderr.as_deref(), Some("warning\nerror: cancelled"));
+
+        // Finished actions can't be cancelled.
+        inspector.cancel();
+        assert!(inspector.take_cancellations().is_empty());
+    }
+}
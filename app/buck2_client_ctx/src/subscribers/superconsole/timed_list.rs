@@ -51,6 +51,7 @@ pub struct Cutoffs {
 struct TimedListBody<'c> {
     cutoffs: &'c Cutoffs,
     state: &'c SuperConsoleState,
+    selected: Option<usize>,
 }
 
 impl TimedListBody<'_> {
@@ -167,7 +168,12 @@ impl Component for TimedListBody<'_> {
         let mut visible_roots = 0;
 
         while let Some(root) = roots.next() {
-            let rows = self.draw_root(&root)?;
+            let mut rows = self.draw_root(&root)?;
+            if let Some(selected) = self.selected {
+                for (i, row) in rows.iter_mut().enumerate() {
+                    row.mark_selected(i == 0 && visible_roots == selected);
+                }
+            }
             let hidden_roots_after_this = roots.len();
             let reserved_summary_rows = if hidden_roots_after_this > 0 { 1 } else { 0 };
 
@@ -213,12 +219,23 @@ impl Component for TimedListHeader {
 pub struct TimedList<'a> {
     cutoffs: &'a Cutoffs,
     state: &'a SuperConsoleState,
+    selected: Option<usize>,
 }
 
 impl<'a> TimedList<'a> {
     /// * `cutoffs` determines durations for warnings, time-outs, and baseline notability.
     pub fn new(cutoffs: &'a Cutoffs, state: &'a SuperConsoleState) -> Self {
-        Self { cutoffs, state }
+        Self {
+            cutoffs,
+            state,
+            selected: None,
+        }
+    }
+
+    /// Mark the root at this index with a cursor, for the action inspector.
+    pub(crate) fn with_selected(mut self, selected: Option<usize>) -> Self {
+        self.selected = selected;
+        self
     }
 }
 
@@ -234,6 +251,7 @@ impl Component for TimedList<'_> {
                 let body = TimedListBody {
                     cutoffs: self.cutoffs,
                     state: self.state,
+                    selected: self.selected,
                 };
 
                 let mut draw = DrawVertical::new(dimensions);
@@ -386,6 +404,56 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_selected() -> buck2_error::Result<()> {
+        let tick = Tick::now();
+
+        let mut state = BuckEventSpanTracker::new();
+        for (secs, name) in [(3, "e1"), (1, "e2")] {
+            state.start_at(&Arc::new(BuckEvent::new(
+                fake_time(&tick, secs),
+                TraceId::new(),
+                Some(SpanId::next()),
+                None,
+                buck2_data::buck_event::Data::SpanStart(SpanStartEvent {
+                    data: Some(buck2_data::span_start_event::Data::Fake(FakeStart {
+                        caramba: name.to_owned(),
+                    })),
+                }),
+            )))?;
+        }
+
+        let output = TimedList::new(
+            &CUTOFFS,
+            &super_console_state_for_test(
+                state,
+                ActionStats::default(),
+                fake_timekeeper(tick),
+                SuperConsoleConfig::default(),
+            ),
+        )
+        .with_selected(Some(1))
+        .draw(
+            Dimensions {
+                width: 40,
+                height: 10,
+            },
+            DrawMode::Normal,
+        )?;
+        let expected = [
+            "────────────────────────────────────────",
+            "  e1 · speak of the devil           3.0s",
+            "<span bold>> </span>e2 · speak of the devil           1.0s",
+        ]
+        .iter()
+        .map(|l| format!("{l}\n"))
+        .join("");
+
+        pretty_assertions::assert_eq!(output.fmt_for_test().to_string(), expected);
+
+        Ok(())
+    }
+
     #[test]
     fn test_remaining() -> buck2_error::Result<()> {
         let tick = Tick::now();
@@ -241,6 +241,16 @@ impl TimedRow {
             aux,
         })
     }
+
+    /// Prefix the row with a gutter holding the cursor when it is selected.
+    pub(crate) fn mark_selected(&mut self, selected: bool) {
+        let gutter = if selected {
+            Span::new_styled_lossy("> ".to_owned().bold())
+        } else {
+            Span::padding(2)
+        };
+        self.primary.push_front(gutter);
+    }
 }
 
 struct DetailCell {
@@ -0,0 +1,621 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is dual-licensed under either the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree or the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree. You may select, at your option, one of the
+ * above-listed licenses.
+ */
+
+//! Writes the test results of `buck2 test` as JUnit XML and TAP reports for CI systems.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use buck2_data::TestStatus;
+use buck2_error::BuckErrorContext;
+use buck2_event_observer::display::TargetDisplayOptions;
+use buck2_event_observer::display::display_configured_target_label;
+use buck2_event_observer::unpack_event::UnpackedBuckEvent;
+use buck2_event_observer::unpack_event::unpack_event;
+use buck2_events::BuckEvent;
+use buck2_fs::async_fs_util;
+use buck2_fs::error::IoResultExt;
+use buck2_fs::paths::abs_path::AbsPathBuf;
+
+use crate::subscribers::subscriber::EventSubscriber;
+
+pub struct TestReportWriter {
+    junit_xml: Option<AbsPathBuf>,
+    tap: Option<AbsPathBuf>,
+    /// Results by configured target.
+    results: BTreeMap<String, Vec<TestCaseResult>>,
+}
+
+struct TestCaseResult {
+    name: String,
+    status: TestStatus,
+    message: Option<String>,
+    duration: Option<Duration>,
+    /// Combined stdout and stderr of the test.
+    details: String,
+    /// The stdout and stderr of the test, when the test runner reports them separately.
+    stdout: Option<String>,
+    stderr: Option<String>,
+}
+
+impl TestReportWriter {
+    pub fn new(junit_xml: Option<AbsPathBuf>, tap: Option<AbsPathBuf>) -> Self {
+        Self {
+            junit_xml,
+            tap,
+            results: BTreeMap::new(),
+        }
+    }
+
+    fn add(&mut self, result: &buck2_data::TestResult) -> buck2_error::Result<()> {
+        let status = result.status();
+        // Reruns are followed by the final result of the test, and listings are not test cases.
+        if matches!(
+            status,
+            TestStatus::Rerun | TestStatus::ListingSuccess | TestStatus::NotSetTestStatus
+        ) {
+            return Ok(());
+        }
+        let target = match &result.target_label {
+            Some(label) => display_configured_target_label(label, TargetDisplayOptions::for_log())?,
+            None => String::new(),
+        };
+        self.results
+            .entry(target)
+            .or_default()
+            .push(TestCaseResult {
+                name: result.name.clone(),
+                status,
+                message: result.msg.as_ref().map(|msg| msg.msg.clone()),
+                duration: result
+                    .duration
+                    .and_then(|duration| Duration::try_from(duration).ok()),
+                details: result.details.clone(),
+                stdout: result.stdout.clone(),
+                stderr: result.stderr.clone(),
+            });
+        Ok(())
+    }
+
+    fn sorted_results(&mut self) -> &BTreeMap<String, Vec<TestCaseResult>> {
+        // Tests finish in any order, sort them so that reports of identical runs are identical.
+        for cases in self.results.values_mut() {
+            cases.sort_by(|a, b| a.name.cmp(&b.name));
+        }
+        &self.results
+    }
+}
+
+#[async_trait]
+impl EventSubscriber for TestReportWriter {
+    async fn handle_events(&mut self, events: &[Arc<BuckEvent>]) -> buck2_error::Result<()> {
+        for event in events {
+            if let Ok(UnpackedBuckEvent::Instant(
+                _,
+                _,
+                buck2_data::instant_event::Data::TestResult(result),
+            )) = unpack_event(event)
+            {
+                self.add(result)?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn handle_command_result(
+        &mut self,
+        _result: &buck2_cli_proto::CommandResult,
+    ) -> buck2_error::Result<()> {
+        // Reports are written even if the command failed, with the tests that did run.
+        let junit_xml = self.junit_xml.clone();
+        let tap = self.tap.clone();
+        let results = self.sorted_results();
+        if let Some(path) = junit_xml {
+            async_fs_util::write(&path, render_junit_xml(results))
+                .await
+                .categorize_input()
+                .buck_error_context("Error writing JUnit XML test report")?;
+        }
+        if let Some(path) = tap {
+            async_fs_util::write(&path, render_tap(results))
+                .await
+                .categorize_input()
+                .buck_error_context("Error writing TAP test report")?;
+        }
+        Ok(())
+    }
+}
+
+/// How a test status is represented in JUnit XML.
+enum JUnitOutcome {
+    Passed,
+    Failure,
+    Error,
+    Skipped,
+}
+
+fn junit_outcome(status: TestStatus) -> JUnitOutcome {
+    match status {
+        TestStatus::Pass | TestStatus::Flaky => JUnitOutcome::Passed,
+        TestStatus::Fail => JUnitOutcome::Failure,
+        TestStatus::Skip | TestStatus::Omitted => JUnitOutcome::Skipped,
+        TestStatus::Fatal
+        | TestStatus::Timeout
+        | TestStatus::InfraFailure
+        | TestStatus::ListingFailed
+        | TestStatus::Unknown
+        | TestStatus::Rerun
+        | TestStatus::ListingSuccess
+        | TestStatus::NotSetTestStatus => JUnitOutcome::Error,
+    }
+}
+
+/// The name of the status in Buck2's test output, e.g. `TIMEOUT`.
+fn status_name(status: TestStatus) -> &'static str {
+    match status {
+        TestStatus::NotSetTestStatus => "NOT_SET",
+        TestStatus::Pass => "PASS",
+        TestStatus::Fail => "FAIL",
+        TestStatus::Skip => "SKIP",
+        TestStatus::Omitted => "OMITTED",
+        TestStatus::Fatal => "FATAL",
+        TestStatus::Timeout => "TIMEOUT",
+        TestStatus::Unknown => "UNKNOWN",
+        TestStatus::Rerun => "RERUN",
+        TestStatus::ListingSuccess => "LISTING_SUCCESS",
+        TestStatus::ListingFailed => "LISTING_FAILED",
+        TestStatus::InfraFailure => "INFRA_FAILURE",
+        TestStatus::Flaky => "FLAKY",
+    }
+}
+
+#[derive(Default)]
+struct JUnitCounts {
+    tests: usize,
+    failures: usize,
+    errors: usize,
+    skipped: usize,
+    time: Duration,
+}
+
+impl JUnitCounts {
+    fn add(&mut self, case: &TestCaseResult) {
+        self.tests += 1;
+        match junit_outcome(case.status) {
+            JUnitOutcome::Passed => {}
+            JUnitOutcome::Failure => self.failures += 1,
+            JUnitOutcome::Error => self.errors += 1,
+            JUnitOutcome::Skipped => self.skipped += 1,
+        }
+        self.time += case.duration.unwrap_or_default();
+    }
+
+    fn attributes(&self) -> String {
+        format!(
+            r#"tests="{}" failures="{}" errors="{}" skipped="{}" time="{:.3}""#,
+            self.tests,
+            self.failures,
+            self.errors,
+            self.skipped,
+            self.time.as_secs_f64()
+        )
+    }
+}
+
+/// One `<testsuite>` per configured target, one `<testcase>` per test case. The Buck2 status of
+/// each test case is kept as the `status` property, as JUnit has fewer statuses.
+fn render_junit_xml(results: &BTreeMap<String, Vec<TestCaseResult>>) -> String {
+    let mut total = JUnitCounts::default();
+    let mut suites = String::new();
+    for (target, cases) in results {
+        let mut counts = JUnitCounts::default();
+        let mut body = String::new();
+        for case in cases {
+            counts.add(case);
+            total.add(case);
+            write_junit_test_case(&mut body, target, case);
+        }
+        writeln!(
+            suites,
+            r#"  <testsuite name="{}" {}>"#,
+            xml_escape(target),
+            counts.attributes()
+        )
+        .unwrap();
+        suites.push_str(&body);
+        suites.push_str("  </testsuite>\n");
+    }
+
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    writeln!(
+        out,
+        r#"<testsuites name="buck2 test" {}>"#,
+        total.attributes()
+    )
+    .unwrap();
+    out.push_str(&suites);
+    out.push_str("</testsuites>\n");
+    out
+}
+
+fn write_junit_test_case(out: &mut String, target: &str, case: &TestCaseResult) {
+    writeln!(
+        out,
+        r#"    <testcase name="{}" classname="{}" time="{:.3}">"#,
+        xml_escape(&case.name),
+        xml_escape(target),
+        case.duration.unwrap_or_default().as_secs_f64()
+    )
+    .unwrap();
+    writeln!(
+        out,
+        r#"      <properties><property name="status" value="{}"/></properties>"#,
+        status_name(case.status)
+    )
+    .unwrap();
+    let message = xml_escape(case.message.as_deref().unwrap_or_default());
+    let status = status_name(case.status);
+    match junit_outcome(case.status) {
+        JUnitOutcome::Passed => {}
+        JUnitOutcome::Failure => writeln!(
+            out,
+            r#"      <failure message="{message}" type="{status}"/>"#
+        )
+        .unwrap(),
+        JUnitOutcome::Error => {
+            writeln!(out, r#"      <error message="{message}" type="{status}"/>"#).unwrap()
+        }
+        JUnitOutcome::Skipped => writeln!(out, r#"      <skipped message="{message}"/>"#).unwrap(),
+    }
+    if case.stdout.is_none() && case.stderr.is_none() {
+        write_junit_output(out, "system-out", &case.details);
+    } else {
+        write_junit_output(
+            out,
+            "system-out",
+            case.stdout.as_deref().unwrap_or_default(),
+        );
+        write_junit_output(
+            out,
+            "system-err",
+            case.stderr.as_deref().unwrap_or_default(),
+        );
+    }
+    out.push_str("    </testcase>\n");
+}
+
+fn write_junit_output(out: &mut String, element: &str, output: &str) {
+    if !output.is_empty() {
+        writeln!(out, "      <{element}>{}</{element}>", xml_escape(output)).unwrap();
+    }
+}
+
+/// Escapes text for XML, replacing characters that XML 1.0 does not allow at all, such as the
+/// escape sequences of colored test output.
+fn xml_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            '\t' | '\n' | '\r' => out.push(c),
+            c if c < ' ' || c == '\u{FFFE}' || c == '\u{FFFF}' => out.push('\u{FFFD}'),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// TAP version 13, with a YAML block for every test case that did not simply pass.
+fn render_tap(results: &BTreeMap<String, Vec<TestCaseResult>>) -> String {
+    let count: usize = results.values().map(|cases| cases.len()).sum();
+    let mut out = format!("TAP version 13\n1..{count}\n");
+    let mut number = 0;
+    for (target, cases) in results {
+        for case in cases {
+            number += 1;
+            let description = format!("{target} {}", case.name)
+                .replace('\\', "\\\\")
+                .replace('#', "\\#")
+                .replace('\n', " ");
+            let (ok, directive) = match junit_outcome(case.status) {
+                JUnitOutcome::Passed => ("ok", String::new()),
+                JUnitOutcome::Skipped => (
+                    "ok",
+                    format!(" # SKIP {}", case.message.as_deref().unwrap_or_default())
+                        .trim_end()
+                        .replace('\n', " "),
+                ),
+                JUnitOutcome::Failure | JUnitOutcome::Error => ("not ok", String::new()),
+            };
+            writeln!(out, "{ok} {number} - {description}{directive}").unwrap();
+
+            if case.status == TestStatus::Pass {
+                continue;
+            }
+            out.push_str("  ---\n");
+            writeln!(out, "  status: {}", status_name(case.status)).unwrap();
+            if let Some(duration) = case.duration {
+                writeln!(out, "  duration_ms: {}", duration.as_millis()).unwrap();
+            }
+            if let Some(message) = &case.message {
+                write_tap_block(&mut out, "message", message);
+            }
+            if case.stdout.is_none() && case.stderr.is_none() {
+                write_tap_block(&mut out, "details", &case.details);
+            } else {
+                write_tap_block(
+                    &mut out,
+                    "stdout",
+                    case.stdout.as_deref().unwrap_or_default(),
+                );
+                write_tap_block(
+                    &mut out,
+                    "stderr",
+                    case.stderr.as_deref().unwrap_or_default(),
+                );
+            }
+            out.push_str("  ...\n");
+        }
+    }
+    out
+}
+
+/// Writes a YAML literal block scalar, unless the value is empty.
+fn write_tap_block(out: &mut String, key: &str, value: &str) {
+    if value.is_empty() {
+        return;
+    }
+    writeln!(out, "  {key}: |").unwrap();
+    for line in value.lines() {
+        writeln!(out, "    {line}").unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn case(
+        name: &str,
+        status: TestStatus,
+        message: Option<&str>,
+        details: &str,
+    ) -> TestCaseResult {
+        TestCaseResult {
+            name: name.to_owned(),
+            status,
+            message: message.map(|m| m.to_owned()),
+            duration: Some(Duration::from_millis(1500)),
+            details: details.to_owned(),
+            stdout: None,
+            stderr: None,
+        }
+    }
+
+    fn results() -> BTreeMap<String, Vec<TestCaseResult>> {
+        BTreeMap::from([
+            (
+                "root//foo:a (cfg)".to_owned(),
+                vec![
+                    case("passes", TestStatus::Pass, None, ""),
+                    case(
+                        "fails",
+                        TestStatus::Fail,
+                        Some("expected <1>"),
+                        "boom\nline 2",
+                    ),
+                ],
+            ),
+            (
+                "root//foo:b (cfg)".to_owned(),
+                vec![
+                    case("times_out", TestStatus::Timeout, None, ""),
+                    case("omitted", TestStatus::Omitted, Some("Cancelled"), ""),
+                ],
+            ),
+        ])
+    }
+
+    #[test]
+    fn test_render_junit_xml() {
+        assert_eq!(
+            render_junit_xml(&results()),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="buck2 test" tests="4" failures="1" errors="1" skipped="1" time="6.000">
+  <testsuite name="root//foo:a (cfg)" tests="2" failures="1" errors="0" skipped="0" time="3.000">
+    <testcase name="passes" classname="root//foo:a (cfg)" time="1.500">
+      <properties><property name="status" value="PASS"/></properties>
+    </testcase>
+    <testcase name="fails" classname="root//foo:a (cfg)" time="1.500">
+      <properties><property name="status" value="FAIL"/></properties>
+      <failure message="expected &lt;1&gt;" type="FAIL"/>
+      <system-out>boom
+line 2</system-out>
+    </testcase>
+  </testsuite>
+  <testsuite name="root//foo:b (cfg)" tests="2" failures="0" errors="1" skipped="1" time="3.000">
+    <testcase name="times_out" classname="root//foo:b (cfg)" time="1.500">
+      <properties><property name="status" value="TIMEOUT"/></properties>
+      <error message="" type="TIMEOUT"/>
+    </testcase>
+    <testcase name="omitted" classname="root//foo:b (cfg)" time="1.500">
+      <properties><property name="status" value="OMITTED"/></properties>
+      <skipped message="Cancelled"/>
+    </testcase>
+  </testsuite>
+</testsuites>
+"#
+        );
+    }
+
+    #[test]
+    fn test_render_tap() {
+        assert_eq!(
+            render_tap(&results()),
+            "TAP version 13
+1..4
+ok 1 - root//foo:a (cfg) passes
+not ok 2 - root//foo:a (cfg) fails
+  ---
+  status: FAIL
+  duration_ms: 1500
+  message: |
+    expected <1>
+  details: |
+    boom
+    line 2
+  ...
+not ok 3 - root//foo:b (cfg) times_out
+  ---
+  status: TIMEOUT
+  duration_ms: 1500
+  ...
+ok 4 - root//foo:b (cfg) omitted # SKIP Cancelled
+  ---
+  status: OMITTED
+  duration_ms: 1500
+  message: |
+    Cancelled
+  ...
+"
+        );
+    }
+
+    #[test]
+    fn test_xml_escape() {
+        // Colored output contains escape sequences, which XML does not allow.
+        assert_eq!(
+            xml_escape("a<b & 'c'\t\"d\"\x1b[31m"),
+            "a&lt;b &amp; &apos;c&apos;\t&quot;d&quot;\u{FFFD}[31m"
+        );
+    }
+
+    #[test]
+    fn test_separate_outputs() {
+        let results = BTreeMap::from([(
+            "root//foo:a (cfg)".to_owned(),
+            vec![TestCaseResult {
+                stdout: Some("out".to_owned()),
+                stderr: Some("err <1>".to_owned()),
+                ..case("fails", TestStatus::Fail, None, "out\nerr <1>")
+            }],
+        )]);
+        let junit_xml = render_junit_xml(&results);
+        assert!(
+            junit_xml.contains(
+                "      <system-out>out</system-out>\n      <system-err>err &lt;1&gt;</system-err>\n"
+            ),
+            "{junit_xml}"
+        );
+        let tap = render_tap(&results);
+        assert!(
+            tap.contains("  stdout: |\n    out\n  stderr: |\n    err <1>\n"),
+            "{tap}"
+        );
+        assert!(!tap.contains("details"), "{tap}");
+    }
+
+    #[test]
+    fn test_status_mapping() {
+        let results = BTreeMap::from([(
+            "t".to_owned(),
+            vec![
+                case("flaky", TestStatus::Flaky, None, ""),
+                case("omitted", TestStatus::Omitted, None, ""),
+                case("skip", TestStatus::Skip, Some("Disabled"), ""),
+            ],
+        )]);
+        assert_eq!(
+            render_junit_xml(&results),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites name="buck2 test" tests="3" failures="0" errors="0" skipped="2" time="4.500">
+  <testsuite name="t" tests="3" failures="0" errors="0" skipped="2" time="4.500">
+    <testcase name="flaky" classname="t" time="1.500">
+      <properties><property name="status" value="FLAKY"/></properties>
+    </testcase>
+    <testcase name="omitted" classname="t" time="1.500">
+      <properties><property name="status" value="OMITTED"/></properties>
+      <skipped message=""/>
+    </testcase>
+    <testcase name="skip" classname="t" time="1.500">
+      <properties><property name="status" value="SKIP"/></properties>
+      <skipped message="Disabled"/>
+    </testcase>
+  </testsuite>
+</testsuites>
+"#
+        );
+        assert_eq!(
+            render_tap(&results),
+            "TAP version 13
+1..3
+ok 1 - t flaky
+  ---
+  status: FLAKY
+  duration_ms: 1500
+  ...
+ok 2 - t omitted # SKIP
+  ---
+  status: OMITTED
+  duration_ms: 1500
+  ...
+ok 3 - t skip # SKIP Disabled
+  ---
+  status: SKIP
+  duration_ms: 1500
+  message: |
+    Disabled
+  ...
+"
+        );
+    }
+
+    #[test]
+    fn test_escaping() {
+        let results = BTreeMap::from([(
+            "root//foo:\"a\" (cfg)".to_owned(),
+            vec![
+                case("x & y", TestStatus::Pass, None, ""),
+                case("a#b\\c\nd", TestStatus::Skip, Some("line 1\nline 2"), ""),
+            ],
+        )]);
+        let junit_xml = render_junit_xml(&results);
+        assert!(
+            junit_xml.contains(
+                r#"<testcase name="x &amp; y" classname="root//foo:&quot;a&quot; (cfg)""#
+            ),
+            "{junit_xml}"
+        );
+        assert!(
+            junit_xml.contains(
+                r#"<skipped message="line 1
+line 2"/>"#
+            ),
+            "{junit_xml}"
+        );
+        let tap = render_tap(&results);
+        assert!(
+            tap.contains("ok 1 - root//foo:\"a\" (cfg) x & y\n"),
+            "{tap}"
+        );
+        // `#` would start a directive, and a line break would end the test line.
+        assert!(
+            tap.contains("ok 2 - root//foo:\"a\" (cfg) a\\#b\\\\c d # SKIP line 1 line 2\n"),
+            "{tap}"
+        );
+    }
+}
@@ -23,6 +23,18 @@ pub struct AuditVisibilityCommand {
     #[clap(name = "TARGET_PATTERNS", help = "Target pattern(s) to analyze.")]
     pub patterns: Vec<String>,
 
+    /// Explain each dependency that is not visible: print the dependency edge, the visibility
+    /// patterns it was checked against (including the values inherited from `PACKAGE` files),
+    /// and the smallest pattern that would allow it.
+    ///
+    /// `within_view` is checked when build files are loaded, so its violations are load errors.
+    #[clap(long)]
+    pub explain: bool,
+
+    /// Print the explanations as JSON.
+    #[clap(long, requires = "explain")]
+    pub json: bool,
+
     /// Command doesn't need these flags, but they are used in mode files, so we need to keep them.
     #[clap(flatten)]
     _target_cfg: TargetCfgUnusedOptions,
@@ -8,24 +8,31 @@
  * above-listed licenses.
  */
 
+use std::io::Write;
+
 use async_trait::async_trait;
 use buck2_cli_proto::ClientContext;
 use buck2_cmd_audit_client::visibility::AuditVisibilityCommand;
 use buck2_common::pattern::parse_from_cli::parse_patterns_from_cli_args;
 use buck2_core::pattern::pattern_type::TargetPatternExtra;
+use buck2_core::target::label::label::TargetLabel;
 use buck2_node::load_patterns::MissingTargetBehavior;
 use buck2_node::load_patterns::load_patterns;
 use buck2_node::nodes::lookup::TargetNodeLookup;
 use buck2_node::nodes::unconfigured::TargetNode;
+use buck2_node::package_values_calculation::PACKAGE_VALUES_CALCULATION;
+use buck2_node::package_values_calculation::PackageValues;
 use buck2_query::query::environment::QueryTargetDepsSuccessors;
 use buck2_query::query::syntax::simple::eval::set::TargetSet;
 use buck2_query::query::traversal::async_depth_first_postorder_traversal;
 use buck2_server_ctx::ctx::ServerCommandContextTrait;
 use buck2_server_ctx::ctx::ServerCommandDiceContext;
 use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
+use dice::DiceComputations;
 use dice::DiceTransaction;
 use dupe::Dupe;
 use futures::FutureExt;
+use serde::Serialize;
 
 use crate::ServerAuditSubcommand;
 
@@ -38,9 +45,93 @@ enum VisibilityCommandError {
     DepNodeNotFound(String, String),
 }
 
+/// Why a dependency is not visible to the target depending on it.
+#[derive(Serialize)]
+struct VisibilityExplanation {
+    target: TargetLabel,
+    dependency: TargetLabel,
+    /// Evaluated `visibility` of the dependency.
+    visibility: serde_json::Value,
+    visibility_allows: bool,
+    /// Cap set by `enforce_visibility_intersection()` in the `PACKAGE` files of the
+    /// dependency, `["PUBLIC"]` when there is none.
+    visibility_cap: serde_json::Value,
+    visibility_cap_allows: bool,
+    /// Values of the dependency's package inherited from `PACKAGE` files: the default
+    /// `visibility` and `within_view`, the cap, and the package values.
+    package_values: PackageValues,
+    /// Smallest pattern which allows the dependency when added to each list which denies it.
+    suggested_pattern: String,
+}
+
+impl VisibilityExplanation {
+    async fn new(
+        ctx: &mut DiceComputations<'_>,
+        target: &TargetNode,
+        dependency: &TargetNode,
+    ) -> buck2_error::Result<Self> {
+        let visibility = dependency.visibility()?;
+        let visibility_cap = dependency.visibility_cap();
+        let package_values = PACKAGE_VALUES_CALCULATION
+            .get()?
+            .package_values(ctx, dependency.label().pkg())
+            .await?;
+        Ok(VisibilityExplanation {
+            target: target.label().dupe(),
+            dependency: dependency.label().dupe(),
+            visibility: visibility.to_json(),
+            visibility_allows: visibility.0.matches_target(target.label())?,
+            visibility_cap: visibility_cap.to_json(),
+            visibility_cap_allows: visibility_cap.matches_target(target.label())?,
+            package_values,
+            // Targets are the narrowest patterns.
+            suggested_pattern: target.label().to_string(),
+        })
+    }
+
+    fn write_text(&self, w: &mut dyn Write) -> buck2_error::Result<()> {
+        let verdict = |allows| if allows { "allows" } else { "denies" };
+        writeln!(w, "`{}` depends on `{}`", self.target, self.dependency)?;
+        writeln!(
+            w,
+            "  visibility: {} ({})",
+            self.visibility,
+            verdict(self.visibility_allows)
+        )?;
+        writeln!(
+            w,
+            "  visibility_cap: {} ({})",
+            self.visibility_cap,
+            verdict(self.visibility_cap_allows)
+        )?;
+        writeln!(
+            w,
+            "  PACKAGE visibility: {}",
+            self.package_values.visibility
+        )?;
+        if !self.visibility_allows {
+            writeln!(
+                w,
+                "  suggestion: add `\"{}\"` to the `visibility` of `{}`",
+                self.suggested_pattern, self.dependency
+            )?;
+        }
+        if !self.visibility_cap_allows {
+            writeln!(
+                w,
+                "  suggestion: add `\"{}\"` to `enforce_visibility_intersection()` in the `PACKAGE` files of `{}`",
+                self.suggested_pattern,
+                self.dependency.pkg()
+            )?;
+        }
+        Ok(())
+    }
+}
+
 async fn verify_visibility(
     ctx: DiceTransaction,
     targets: TargetSet<TargetNode>,
+    explain: Option<(&mut dyn Write, bool)>,
 ) -> buck2_error::Result<()> {
     let mut new_targets: TargetSet<TargetNode> = TargetSet::new();
 
@@ -68,6 +159,7 @@ async fn verify_visibility(
         .await?;
 
     let mut visibility_errors = Vec::new();
+    let mut explanations = Vec::new();
 
     for target in new_targets.iter() {
         for dep in target.deps() {
@@ -75,6 +167,11 @@ async fn verify_visibility(
                 Some(val) => {
                     if !val.is_visible_to(target.label())? {
                         visibility_errors.push(val.not_visible_to_error(target.label().dupe()));
+                        if explain.is_some() {
+                            explanations.push(
+                                VisibilityExplanation::new(&mut ctx.ctx(), target, val).await?,
+                            );
+                        }
                     }
                 }
                 None => {
@@ -89,8 +186,24 @@ async fn verify_visibility(
         }
     }
 
-    for err in &visibility_errors {
-        buck2_client_ctx::eprintln!("{}", err)?;
+    match explain {
+        Some((w, true)) => {
+            serde_json::to_writer_pretty(&mut *w, &explanations)?;
+            // Because serde does not write a trailing newline.
+            writeln!(w)?;
+            w.flush()?;
+        }
+        Some((w, false)) => {
+            for explanation in &explanations {
+                explanation.write_text(w)?;
+            }
+            w.flush()?;
+        }
+        None => {
+            for err in &visibility_errors {
+                buck2_client_ctx::eprintln!("{}", err)?;
+            }
+        }
     }
 
     if !visibility_errors.is_empty() {
@@ -110,7 +223,7 @@ impl ServerAuditSubcommand for AuditVisibilityCommand {
     async fn server_execute(
         &self,
         server_ctx: &dyn ServerCommandContextTrait,
-        _stdout: PartialResultDispatcher<buck2_cli_proto::StdoutBytes>,
+        mut stdout: PartialResultDispatcher<buck2_cli_proto::StdoutBytes>,
         _client_ctx: ClientContext,
     ) -> buck2_error::Result<()> {
         Ok(server_ctx
@@ -132,7 +245,11 @@ impl ServerAuditSubcommand for AuditVisibilityCommand {
                     nodes.extend(res.values().map(|n| n.to_owned()));
                 }
 
-                verify_visibility(ctx, nodes).await?;
+                let mut stdout = stdout.as_writer();
+                let explain = self
+                    .explain
+                    .then_some((&mut stdout as &mut dyn Write, self.json));
+                verify_visibility(ctx, nodes, explain).await?;
                 Ok(())
             })
             .await?)
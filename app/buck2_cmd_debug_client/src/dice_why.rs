@@ -0,0 +1,116 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is dual-licensed under either the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree or the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree. You may select, at your option, one of the
+ * above-listed licenses.
+ */
+
+use buck2_client_ctx::client_ctx::ClientCommandContext;
+use buck2_client_ctx::common::BuckArgMatches;
+use buck2_client_ctx::event_log_options::EventLogOptions;
+use buck2_client_ctx::exit_result::ExitResult;
+use buck2_event_log::stream_value::StreamValue;
+use tokio_stream::StreamExt;
+
+/// Explain why DICE keys were recomputed by a command, e.g. why analysis of a target reran.
+///
+/// This reads the command's event log, so the command must have been run with
+/// `-c buck2.dice_why_key_types=<types>`, where `<types>` is a comma-separated list of the DICE key
+/// types to record (e.g. `AnalysisKey`), or `*` for all of them.
+#[derive(Debug, clap::Parser)]
+pub struct DiceWhyCommand {
+    #[clap(flatten)]
+    event_log: EventLogOptions,
+
+    /// Only show keys of this type.
+    #[clap(long = "type", value_name = "TYPE")]
+    key_type: Option<String>,
+
+    /// Only show keys containing this string, e.g. a target label.
+    #[clap(value_name = "KEY")]
+    key: Option<String>,
+
+    /// Print one JSON object per recomputed key.
+    #[clap(long)]
+    json: bool,
+}
+
+impl DiceWhyCommand {
+    pub fn exec(self, _matches: BuckArgMatches<'_>, ctx: ClientCommandContext<'_>) -> ExitResult {
+        let Self {
+            event_log,
+            key_type,
+            key,
+            json,
+        } = self;
+
+        ctx.with_runtime(|ctx| async move {
+            let log_path = event_log.get(&ctx).await?;
+            let (invocation, mut events) = log_path.unpack_stream().await?;
+            buck2_client_ctx::eprintln!(
+                "Showing recomputed DICE keys from: {}",
+                invocation.display_command_line()
+            )?;
+
+            let mut found = 0;
+            while let Some(event) = events.try_next().await? {
+                if let StreamValue::Event(event) = event
+                    && let Some(buck2_data::buck_event::Data::Instant(instant)) = event.data
+                    && let Some(buck2_data::instant_event::Data::DiceKeyRecomputed(recomputed)) =
+                        instant.data
+                    && key_type.as_ref().is_none_or(|t| *t == recomputed.key_type)
+                    && key
+                        .as_ref()
+                        .is_none_or(|k| recomputed.key.contains(k.as_str()))
+                {
+                    found += 1;
+                    if json {
+                        buck2_client_ctx::println!("{}", serde_json::to_string(&recomputed)?)?;
+                    } else {
+                        print_recomputed(&recomputed)?;
+                    }
+                }
+            }
+
+            if found == 0 {
+                buck2_client_ctx::eprintln!(
+                    "No recomputed keys were recorded. Was `buck2.dice_why_key_types` set?"
+                )?;
+            }
+            buck2_error::Ok(())
+        })?;
+        ExitResult::success()
+    }
+}
+
+/// Prints the key, then the path back to the invalidation source, one key per line.
+fn print_recomputed(recomputed: &buck2_data::DiceKeyRecomputed) -> buck2_error::Result<()> {
+    buck2_client_ctx::println!("{} ({})", recomputed.key, recomputed.key_type)?;
+    match recomputed.invalidation_path.split_first() {
+        None => {
+            buck2_client_ctx::println!(
+                "  no invalidated dependency, e.g. it was not computed before"
+            )?;
+        }
+        Some((source, path)) => {
+            for entry in path.iter().rev() {
+                buck2_client_ctx::println!(
+                    "  <- {} ({}, {})",
+                    entry.key,
+                    entry.key_type,
+                    entry.version
+                )?;
+            }
+            buck2_client_ctx::println!(
+                "  <- {} ({}, changed at {})",
+                source.key,
+                source.key_type,
+                source.version
+            )?;
+        }
+    }
+    Ok(())
+}
@@ -21,6 +21,7 @@ use crate::allocator_stats::AllocatorStatsCommand;
 use crate::crash::CrashCommand;
 use crate::daemon_dir::DaemonDirCommand;
 use crate::dice_dump::DiceDumpCommand;
+use crate::dice_why::DiceWhyCommand;
 use crate::eval::EvalCommand;
 use crate::exe::ExeCommand;
 use crate::file_status::FileStatusCommand;
@@ -43,6 +44,7 @@ mod allocator_stats;
 mod crash;
 mod daemon_dir;
 mod dice_dump;
+mod dice_why;
 mod eval;
 mod exe;
 mod file_status;
@@ -70,6 +72,7 @@ pub enum DebugCommand {
     AllocatorStats(AllocatorStatsCommand),
     /// Dump the DICE graph to a file and saves it to disk.
     DiceDump(DiceDumpCommand),
+    DiceWhy(DiceWhyCommand),
     /// Prints the hash of the buck2 binary
     InternalVersion(InternalVersionCommand),
     /// Renders an event-log to a Chrome trace file for inspection with a browser.
@@ -115,6 +118,7 @@ impl DebugCommand {
         let matches = matches.unwrap_subcommand();
         match self {
             DebugCommand::DiceDump(cmd) => ctx.exec(cmd, matches, events_ctx),
+            DebugCommand::DiceWhy(cmd) => cmd.exec(matches, ctx),
             DebugCommand::Crash(cmd) => ctx.exec(cmd, matches, events_ctx),
             DebugCommand::HeapDump(cmd) => ctx.exec(cmd, matches, events_ctx),
             DebugCommand::AllocatorStats(cmd) => ctx.exec(cmd, matches, events_ctx),
@@ -23,6 +23,8 @@ use buck2_data::InstantEvent;
 use buck2_data::PersistEventLogSubprocess;
 use buck2_data::instant_event::Data;
 use buck2_error::BuckErrorContext;
+use buck2_event_log::index::INDEX_EXTENSION;
+use buck2_event_log::index::index_path;
 use buck2_event_log::ttl::manifold_event_log_ttl;
 use buck2_events::BuckEvent;
 use buck2_events::daemon_id::DaemonId;
@@ -109,7 +111,7 @@ impl PersistEventLogsCommand {
         stdin: impl io::AsyncBufRead + Unpin,
     ) -> (buck2_error::Result<()>, buck2_error::Result<()>) {
         let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
-        let file = match create_log_file(self.local_path).await {
+        let file = match create_log_file(&self.local_path).await {
             Ok(f) => Mutex::new(f),
             Err(e) => {
                 return (
@@ -122,7 +124,13 @@ impl PersistEventLogsCommand {
             }
         };
         let write = write_task(&file, tx, stdin);
-        let upload = upload_task(&file, rx, self.manifold_name, self.no_upload);
+        let upload = upload_task(
+            &file,
+            rx,
+            &self.local_path,
+            self.manifold_name,
+            self.no_upload,
+        );
 
         // Wait for both tasks to finish. If the upload fails we want to keep writing to disk
         let (write_result, upload_result) = tokio::join!(write, upload);
@@ -152,7 +160,7 @@ async fn write_task(
     Ok(())
 }
 
-async fn create_log_file(local_path: String) -> Result<tokio::fs::File, buck2_error::Error> {
+async fn create_log_file(local_path: &str) -> Result<tokio::fs::File, buck2_error::Error> {
     let local_path = AbsPathBuf::new(local_path)?;
 
     let file = OpenOptions::new()
@@ -174,6 +182,7 @@ async fn create_log_file(local_path: String) -> Result<tokio::fs::File, buck2_er
 async fn upload_task(
     file_mutex: &Mutex<File>,
     mut rx: tokio::sync::mpsc::UnboundedReceiver<u64>,
+    local_path: &str,
     manifold_name: String,
     no_upload: bool,
 ) -> buck2_error::Result<()> {
@@ -218,7 +227,35 @@ async fn upload_task(
     // Last chunk to upload is smaller than &reader
     uploader.upload_chunk().await?;
 
-    Ok(())
+    upload_index(&manifold_client, local_path, &manifold_path).await
+}
+
+/// Uploads the index of an indexed log next to the log. The index is written by the client
+/// rather than streamed to us, and the client closes it before closing our stdin, so it is
+/// complete by the time the log is.
+async fn upload_index(
+    manifold_client: &ManifoldClient,
+    local_path: &str,
+    manifold_path: &str,
+) -> buck2_error::Result<()> {
+    let index = index_path(&AbsPathBuf::new(local_path)?);
+    let mut file = match File::open(&index).await {
+        Ok(file) => file,
+        // The log is not indexed.
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => {
+            return Err(buck2_error::Error::from(e)
+                .context(format!("Failed to open event log index at `{index}`")));
+        }
+    };
+    manifold_client
+        .read_and_upload(
+            manifold::Bucket::EVENT_LOGS,
+            &format!("{manifold_path}{INDEX_EXTENSION}"),
+            manifold_event_log_ttl()?,
+            &mut file,
+        )
+        .await
 }
 
 /// Provides methods to:
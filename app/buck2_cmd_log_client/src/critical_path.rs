@@ -28,6 +28,8 @@ use tokio_stream::StreamExt;
 
 use crate::LogCommandOutputFormat;
 use crate::LogCommandOutputFormatWithWriter;
+use crate::critical_path_what_if::WhatIfOptions;
+use crate::critical_path_what_if::log_critical_path_what_if;
 use crate::transform_format;
 
 /// Show the critical path for a selected build.
@@ -50,12 +52,21 @@ use crate::transform_format;
 /// `<kind>[(reused)]\t<name>\t<category>\t<identifier>\t<execution_kind>\t<total_duration>\t<user_duration>\t<potential_improvement_duration>\t<non_critical_path_time>\t<start_offset>`
 ///
 /// All durations are in microseconds. Start offset is in microseconds from the beginning of the build.
+///
+/// With `--what-if`, this instead replays the build graph with some actions made faster or cache
+/// hits, or with a different number of local slots, and prints the number of actions, the number
+/// of actions the scenario changed, the observed and simulated number of local slots, the
+/// observed, replayed and what-if wall times, and the what-if critical path. The `tabulated`
+/// format prints them in that order on a single line. The build graph is only logged by builds
+/// run with `-c buck2.log_build_graph=true`.
 #[derive(Debug, clap::Parser)]
 pub struct CriticalPathCommand {
     #[clap(flatten)]
     event_log: EventLogOptions,
     #[clap(flatten)]
     format: LogCommandOutputFormat,
+    #[clap(flatten)]
+    what_if: WhatIfOptions,
 }
 
 impl BuckSubcommand for CriticalPathCommand {
@@ -67,7 +78,14 @@ impl BuckSubcommand for CriticalPathCommand {
         ctx: ClientCommandContext<'_>,
         _events_ctx: &mut EventsCtx,
     ) -> ExitResult {
-        let Self { event_log, format } = self;
+        let Self {
+            event_log,
+            format,
+            what_if,
+        } = self;
+        if what_if.what_if {
+            return log_critical_path_what_if(ctx, event_log, format, what_if).await;
+        }
         log_critical_path_command_exec(ctx, event_log, format, PathKind::Critical).await
     }
 }
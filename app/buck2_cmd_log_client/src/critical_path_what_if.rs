@@ -0,0 +1,629 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is dual-licensed under either the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree or the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree. You may select, at your option, one of the
+ * above-listed licenses.
+ */
+
+//! `buck2 log critical-path --what-if`: replay the actions of a build with changed durations or
+//! executor limits.
+//!
+//! The replay uses the build graph logged by the daemon when `buck2.log_build_graph` is set. Each
+//! node becomes ready once all of its dependencies have finished, plus the gap observed between
+//! them finishing and the node starting (e.g. materialization, or waiting on DICE). Local
+//! commands are then replayed on a limited number of slots.
+
+use std::io::Write;
+use std::time::Duration;
+
+use buck2_client_ctx::client_ctx::ClientCommandContext;
+use buck2_client_ctx::event_log_options::EventLogOptions;
+use buck2_client_ctx::exit_result::ClientIoError;
+use buck2_client_ctx::exit_result::ExitResult;
+use buck2_critical_path::GraphBuilder;
+use buck2_critical_path::SimulatedJob;
+use buck2_critical_path::compute_critical_path_potentials;
+use buck2_critical_path::simulate_schedule;
+use buck2_data::ActionExecutionKind;
+use buck2_error::conversion::clap::buck_error_clap_parser;
+use buck2_error::conversion::from_any_with_tag;
+use buck2_event_log::stream_value::StreamValue;
+use buck2_event_observer::display;
+use buck2_event_observer::display::TargetDisplayOptions;
+use buck2_hash::BuckMutMap;
+use buck2_hash::BuckMutSet;
+use serde::Serialize;
+use tokio_stream::StreamExt;
+
+use crate::LogCommandOutputFormat;
+use crate::LogCommandOutputFormatWithWriter;
+use crate::transform_format;
+
+#[derive(Debug, buck2_error::Error)]
+#[buck2(tag = Input)]
+enum WhatIfError {
+    #[error("Invalid speedup `{0}`, expected `CATEGORY=PERCENT`, e.g. `cxx_compile=30%`")]
+    InvalidSpeedup(String),
+    #[error("Invalid speedup `{0}`, the percentage must be between 0 and 100")]
+    SpeedupOutOfRange(String),
+    #[error(
+        "The selected log has no build graph to replay, rerun the build with \
+        `-c buck2.log_build_graph=true`"
+    )]
+    NoBuildGraph,
+    #[error("The selected log has no timestamped events")]
+    NoTimestamps,
+}
+
+/// Actions of `category` take `percent`% less time.
+#[derive(Debug, Clone)]
+pub(crate) struct Speedup {
+    category: String,
+    percent: f64,
+}
+
+fn parse_speedup(s: &str) -> buck2_error::Result<Speedup> {
+    let (category, percent) = s
+        .split_once('=')
+        .ok_or_else(|| WhatIfError::InvalidSpeedup(s.to_owned()))?;
+    let percent: f64 = percent
+        .strip_suffix('%')
+        .unwrap_or(percent)
+        .parse()
+        .map_err(|_| WhatIfError::InvalidSpeedup(s.to_owned()))?;
+    if category.is_empty() {
+        return Err(WhatIfError::InvalidSpeedup(s.to_owned()).into());
+    }
+    if !(0.0..=100.0).contains(&percent) {
+        return Err(WhatIfError::SpeedupOutOfRange(s.to_owned()).into());
+    }
+    Ok(Speedup {
+        category: category.to_owned(),
+        percent,
+    })
+}
+
+#[derive(Debug, clap::Parser)]
+pub(crate) struct WhatIfOptions {
+    /// Instead of printing the critical path, replay the build graph under the scenario given by
+    /// `--speedup`, `--cache-hit` and `--local-slots`, and print the resulting wall time.
+    ///
+    /// The build must have been run with `-c buck2.log_build_graph=true`, so that its graph is in
+    /// the event log. The replay is an estimate: compare the `what_if` wall time to the
+    /// `replayed` one, which uses the same model without any changes, rather than to the
+    /// observed one.
+    #[clap(long)]
+    pub(crate) what_if: bool,
+
+    /// Make actions of a category take a percentage less time, e.g. `cxx_compile=30%`.
+    /// Can be repeated.
+    #[clap(
+        long,
+        value_name = "CATEGORY=PERCENT",
+        requires = "what_if",
+        value_parser = buck_error_clap_parser(parse_speedup),
+    )]
+    speedup: Vec<Speedup>,
+
+    /// Make the actions of a target (e.g. `root//foo:bar`, without configuration) cache hits,
+    /// which take as long as the median cache hit of the build. Can be repeated.
+    #[clap(long, value_name = "TARGET", requires = "what_if")]
+    cache_hit: Vec<String>,
+
+    /// Number of local execution slots. Defaults to the largest number of local commands observed
+    /// running at the same time.
+    #[clap(long, value_name = "N", requires = "what_if")]
+    local_slots: Option<usize>,
+}
+
+fn timestamp_ns(timestamp: &prost_types::Timestamp) -> i128 {
+    timestamp.seconds as i128 * 1_000_000_000 + timestamp.nanos as i128
+}
+
+fn duration_ns(duration: Option<prost_types::Duration>) -> buck2_error::Result<u64> {
+    Ok(duration
+        .map(Duration::try_from)
+        .transpose()?
+        .map_or(0, |d| d.as_nanos() as u64))
+}
+
+fn micros(ns: u64) -> u64 {
+    ns / 1000
+}
+
+#[derive(Debug)]
+struct LoggedAction {
+    /// Owning target, without configuration.
+    target: Option<String>,
+    category: String,
+    kind: ActionExecutionKind,
+}
+
+impl LoggedAction {
+    fn is_local(&self) -> bool {
+        matches!(
+            self.kind,
+            ActionExecutionKind::Local | ActionExecutionKind::LocalWorker
+        )
+    }
+
+    fn is_cache_hit(&self) -> bool {
+        matches!(
+            self.kind,
+            ActionExecutionKind::ActionCache
+                | ActionExecutionKind::RemoteDepFileCache
+                | ActionExecutionKind::LocalActionCache
+                | ActionExecutionKind::LocalDepFile
+        )
+    }
+}
+
+/// A node of the logged build graph. Times are in nanoseconds from the start of the command.
+#[derive(Debug)]
+struct LoggedNode {
+    /// Indices of the nodes this node depends on, which come before it.
+    deps: Vec<u32>,
+    start: u64,
+    end: u64,
+    /// Time the node spent waiting for a local slot.
+    queue: u64,
+    /// The action this node executed, if any.
+    action: Option<LoggedAction>,
+}
+
+impl LoggedNode {
+    fn is_local(&self) -> bool {
+        self.action.as_ref().is_some_and(|a| a.is_local())
+    }
+
+    fn is_cache_hit(&self) -> bool {
+        self.action.as_ref().is_some_and(|a| a.is_cache_hit())
+    }
+
+    /// Time the node ran for, excluding time spent waiting for a local slot.
+    fn execution(&self) -> u64 {
+        (self.end - self.start).saturating_sub(self.queue)
+    }
+}
+
+#[derive(Default)]
+struct LoggedBuild {
+    first_event: Option<i128>,
+    last_event: Option<i128>,
+    command_start: Option<i128>,
+    command_end: Option<i128>,
+    /// Executed actions, by span id.
+    actions: BuckMutMap<u64, LoggedAction>,
+    graph: Vec<buck2_data::BuildGraphNode>,
+}
+
+impl LoggedBuild {
+    fn add_event(&mut self, event: &buck2_data::BuckEvent) -> buck2_error::Result<()> {
+        let Some(timestamp) = &event.timestamp else {
+            return Ok(());
+        };
+        let timestamp = timestamp_ns(timestamp);
+        self.first_event.get_or_insert(timestamp);
+        self.last_event = Some(timestamp);
+
+        match &event.data {
+            Some(buck2_data::buck_event::Data::SpanStart(start)) => {
+                if let Some(buck2_data::span_start_event::Data::Command(_)) = &start.data {
+                    self.command_start = Some(timestamp);
+                }
+            }
+            Some(buck2_data::buck_event::Data::SpanEnd(end)) => match &end.data {
+                Some(buck2_data::span_end_event::Data::Command(_)) => {
+                    self.command_end = Some(timestamp);
+                }
+                Some(buck2_data::span_end_event::Data::ActionExecution(action)) => {
+                    let target = action.key.as_ref().and_then(|key| {
+                        display::display_action_key(key, TargetDisplayOptions::for_chrome_trace())
+                            .ok()
+                    });
+                    self.actions.insert(
+                        event.span_id,
+                        LoggedAction {
+                            target,
+                            category: action
+                                .name
+                                .as_ref()
+                                .map(|n| n.category.clone())
+                                .unwrap_or_default(),
+                            kind: action.execution_kind(),
+                        },
+                    );
+                }
+                _ => {}
+            },
+            Some(buck2_data::buck_event::Data::Instant(instant)) => {
+                if let Some(buck2_data::instant_event::Data::BuildGraphInfo(info)) = &instant.data
+                    && !info.build_graph.is_empty()
+                {
+                    self.graph = info.build_graph.clone();
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn nodes(&mut self) -> buck2_error::Result<Vec<LoggedNode>> {
+        std::mem::take(&mut self.graph)
+            .into_iter()
+            .map(|node| -> buck2_error::Result<LoggedNode> {
+                let start = node.start_offset_ns;
+                Ok(LoggedNode {
+                    action: node
+                        .span_ids
+                        .iter()
+                        .find_map(|span_id| self.actions.remove(span_id)),
+                    deps: node.deps,
+                    start,
+                    end: start.saturating_add(duration_ns(node.duration)?),
+                    queue: duration_ns(node.queue_duration)?,
+                })
+            })
+            .collect()
+    }
+
+    fn what_if(mut self, options: &WhatIfOptions) -> buck2_error::Result<WhatIfResult> {
+        let nodes = self.nodes()?;
+        if nodes.is_empty() {
+            return Err(WhatIfError::NoBuildGraph.into());
+        }
+        let build_start = self
+            .command_start
+            .or(self.first_event)
+            .ok_or(WhatIfError::NoTimestamps)?;
+        let build_end = self
+            .command_end
+            .or(self.last_event)
+            .ok_or(WhatIfError::NoTimestamps)?;
+
+        let observed_local_slots = observed_local_slots(&nodes);
+        let local_slots = options.local_slots.unwrap_or(observed_local_slots.max(1));
+
+        let mut cache_hits: Vec<u64> = nodes
+            .iter()
+            .filter(|n| n.is_cache_hit())
+            .map(|n| n.execution())
+            .collect();
+        cache_hits.sort_unstable();
+        let cache_hit_duration = cache_hits.get(cache_hits.len() / 2).copied().unwrap_or(0);
+        let cache_hit_targets: BuckMutSet<&str> =
+            options.cache_hit.iter().map(|t| t.as_str()).collect();
+
+        let mut builder = GraphBuilder::new();
+        let mut affected_actions = 0;
+        for (index, node) in nodes.iter().enumerate() {
+            // Keep the time between the last dependency finishing and this node starting.
+            let deps_end = node
+                .deps
+                .iter()
+                .filter_map(|dep| nodes.get(*dep as usize))
+                .map(|dep| dep.end)
+                .max()
+                .unwrap_or(0);
+            let job = SimulatedJob {
+                delay: node.start.saturating_sub(deps_end),
+                duration: node.execution(),
+                local: node.is_local(),
+            };
+            let mut changed = job.clone();
+            if let Some(action) = &node.action {
+                if action
+                    .target
+                    .as_deref()
+                    .is_some_and(|t| cache_hit_targets.contains(t))
+                    && !action.is_cache_hit()
+                {
+                    changed.duration = cache_hit_duration;
+                    changed.local = false;
+                } else if let Some(speedup) = options
+                    .speedup
+                    .iter()
+                    .find(|s| s.category == action.category)
+                {
+                    changed.duration =
+                        (changed.duration as f64 * (1.0 - speedup.percent / 100.0)).round() as u64;
+                }
+            }
+            if changed != job {
+                affected_actions += 1;
+            }
+            builder.push(index as u32, node.deps.iter().copied(), (job, changed))?;
+        }
+        let (graph, _keys, jobs) = builder.finish();
+        let replayed = jobs.map_ref(|(job, _)| job.clone());
+        let what_if = jobs.map_ref(|(_, changed)| changed.clone());
+
+        let observed_wall_time = (build_end - build_start).max(0) as u64;
+        let last_node_end = nodes.iter().map(|n| n.end).max().unwrap_or(0);
+        // Time after the last node, e.g. reporting, which the scenarios don't change.
+        let tail = observed_wall_time.saturating_sub(last_node_end);
+
+        // With enough local slots, the what-if build takes as long as its critical path.
+        let (_, critical_path_cost, _, _) =
+            compute_critical_path_potentials(&graph, &what_if.map_ref(|j| j.delay + j.duration))?;
+
+        Ok(WhatIfResult {
+            actions: nodes.iter().filter(|n| n.action.is_some()).count(),
+            affected_actions,
+            observed_local_slots,
+            local_slots,
+            observed_wall_time: micros(observed_wall_time),
+            replayed_wall_time: micros(
+                simulate_schedule(&graph, &replayed, observed_local_slots.max(1))? + tail,
+            ),
+            what_if_wall_time: micros(simulate_schedule(&graph, &what_if, local_slots)? + tail),
+            what_if_critical_path: micros(critical_path_cost.runtime + tail),
+        })
+    }
+}
+
+/// Largest number of local commands running at the same time.
+fn observed_local_slots(nodes: &[LoggedNode]) -> usize {
+    let mut changes = Vec::new();
+    for node in nodes.iter().filter(|n| n.is_local()) {
+        changes.push((node.start + node.queue, 1));
+        changes.push((node.end, -1));
+    }
+    // At equal times, process commands finishing before those starting.
+    changes.sort_unstable();
+    let mut running: i64 = 0;
+    let mut max = 0;
+    for (_, change) in changes {
+        running += change;
+        max = max.max(running);
+    }
+    max as usize
+}
+
+/// All durations are in microseconds.
+#[derive(Serialize)]
+struct WhatIfResult {
+    actions: usize,
+    affected_actions: usize,
+    observed_local_slots: usize,
+    local_slots: usize,
+    observed_wall_time: u64,
+    replayed_wall_time: u64,
+    what_if_wall_time: u64,
+    /// What-if wall time with unlimited local slots.
+    what_if_critical_path: u64,
+}
+
+impl WhatIfResult {
+    fn change_percent(&self) -> f64 {
+        if self.replayed_wall_time == 0 {
+            0.0
+        } else {
+            (self.what_if_wall_time as f64 - self.replayed_wall_time as f64) * 100.0
+                / self.replayed_wall_time as f64
+        }
+    }
+}
+
+pub(crate) async fn log_critical_path_what_if(
+    ctx: ClientCommandContext<'_>,
+    event_log: EventLogOptions,
+    format: LogCommandOutputFormat,
+    options: WhatIfOptions,
+) -> ExitResult {
+    let log_path = event_log.get(&ctx).await?;
+
+    let (invocation, mut events) = log_path.unpack_stream().await?;
+    buck2_client_ctx::eprintln!(
+        "Replaying actions from: {}",
+        invocation.display_command_line()
+    )?;
+
+    let mut build = LoggedBuild::default();
+    while let Some(event) = events.try_next().await? {
+        if let StreamValue::Event(event) = event {
+            build.add_event(&event)?;
+        }
+    }
+    let result = build.what_if(&options)?;
+
+    buck2_client_ctx::stdio::print_with_writer::<buck2_error::Error, _>(async move |w| {
+        let res: Result<(), ClientIoError> = match &mut transform_format(format, w) {
+            LogCommandOutputFormatWithWriter::Readable(writer) => {
+                writeln!(
+                    writer,
+                    "actions              {} ({} affected)",
+                    result.actions, result.affected_actions
+                )?;
+                writeln!(
+                    writer,
+                    "local slots          {} (observed {})",
+                    result.local_slots, result.observed_local_slots
+                )?;
+                writeln!(writer, "observed wall time   {}", result.observed_wall_time)?;
+                writeln!(writer, "replayed wall time   {}", result.replayed_wall_time)?;
+                writeln!(
+                    writer,
+                    "what-if wall time    {} ({:+.1}% vs replayed)",
+                    result.what_if_wall_time,
+                    result.change_percent()
+                )?;
+                writeln!(
+                    writer,
+                    "what-if critical path {}",
+                    result.what_if_critical_path
+                )?;
+                Ok(())
+            }
+            LogCommandOutputFormatWithWriter::Tabulated(writer) => {
+                writeln!(
+                    writer,
+                    "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                    result.actions,
+                    result.affected_actions,
+                    result.observed_local_slots,
+                    result.local_slots,
+                    result.observed_wall_time,
+                    result.replayed_wall_time,
+                    result.what_if_wall_time,
+                    result.what_if_critical_path
+                )?;
+                Ok(())
+            }
+            LogCommandOutputFormatWithWriter::Json(writer) => {
+                serde_json::to_writer(writer.by_ref(), &result)?;
+                writer.write_all("\n".as_bytes())?;
+                Ok(())
+            }
+            LogCommandOutputFormatWithWriter::Csv(writer) => {
+                writer
+                    .serialize(&result)
+                    .map_err(|e| from_any_with_tag(e, buck2_error::ErrorTag::LogCmd))?;
+                Ok(())
+            }
+        };
+        res?;
+        Ok(())
+    })
+    .await?;
+
+    ExitResult::success()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(deps: Vec<u32>, start: u64, end: u64, kind: Option<ActionExecutionKind>) -> LoggedNode {
+        LoggedNode {
+            deps,
+            start,
+            end,
+            queue: 0,
+            action: kind.map(|kind| LoggedAction {
+                target: Some("root//foo:bar".to_owned()),
+                category: "cxx_compile".to_owned(),
+                kind,
+            }),
+        }
+    }
+
+    fn options() -> WhatIfOptions {
+        WhatIfOptions {
+            what_if: true,
+            speedup: Vec::new(),
+            cache_hit: Vec::new(),
+            local_slots: None,
+        }
+    }
+
+    fn build(nodes: &[LoggedNode]) -> LoggedBuild {
+        let mut build = LoggedBuild {
+            first_event: Some(0),
+            last_event: Some(4500),
+            command_start: Some(0),
+            command_end: Some(4500),
+            ..Default::default()
+        };
+        for (index, node) in nodes.iter().enumerate() {
+            let span_id = index as u64 + 1;
+            if let Some(action) = &node.action {
+                build.actions.insert(
+                    span_id,
+                    LoggedAction {
+                        target: action.target.clone(),
+                        category: action.category.clone(),
+                        kind: action.kind,
+                    },
+                );
+            }
+            build.graph.push(buck2_data::BuildGraphNode {
+                span_ids: vec![span_id],
+                deps: node.deps.clone(),
+                start_offset_ns: node.start,
+                duration: Some(
+                    Duration::from_nanos(node.end - node.start)
+                        .try_into()
+                        .unwrap(),
+                ),
+                queue_duration: Some(Duration::from_nanos(node.queue).try_into().unwrap()),
+            });
+        }
+        build
+    }
+
+    #[test]
+    fn test_parse_speedup() {
+        let speedup = parse_speedup("cxx_compile=30%").unwrap();
+        assert_eq!("cxx_compile", speedup.category);
+        assert_eq!(30.0, speedup.percent);
+        assert_eq!(12.5, parse_speedup("a=12.5").unwrap().percent);
+        assert!(parse_speedup("cxx_compile").is_err());
+        assert!(parse_speedup("=30").is_err());
+        assert!(parse_speedup("cxx_compile=130").is_err());
+    }
+
+    #[test]
+    fn test_observed_local_slots() {
+        let mut queued = node(vec![], 0, 20, Some(ActionExecutionKind::Local));
+        // Queued until the first action finished.
+        queued.queue = 10;
+        let nodes = [
+            node(vec![], 0, 10, Some(ActionExecutionKind::Local)),
+            queued,
+            node(vec![], 5, 15, Some(ActionExecutionKind::Remote)),
+        ];
+        assert_eq!(1, observed_local_slots(&nodes));
+    }
+
+    #[test]
+    fn test_no_graph() {
+        assert!(build(&[]).what_if(&options()).is_err());
+    }
+
+    #[test]
+    fn test_what_if() {
+        let nodes = || {
+            vec![
+                // Analysis.
+                node(vec![], 0, 500, None),
+                node(vec![0], 500, 1500, Some(ActionExecutionKind::Local)),
+                node(vec![0], 500, 1500, Some(ActionExecutionKind::Local)),
+                // Depends on both actions, not just the one which finished last.
+                node(vec![1, 2], 1500, 2500, Some(ActionExecutionKind::Remote)),
+                // Started after the third action, but only depends on the analysis.
+                node(vec![0], 2500, 4000, Some(ActionExecutionKind::Remote)),
+            ]
+        };
+
+        let result = build(&nodes()).what_if(&options()).unwrap();
+        assert_eq!(4, result.actions);
+        assert_eq!(2, result.observed_local_slots);
+        assert_eq!(4, result.observed_wall_time);
+        assert_eq!(4, result.replayed_wall_time);
+        assert_eq!(4, result.what_if_wall_time);
+        assert_eq!(4, result.what_if_critical_path);
+
+        let result = build(&nodes())
+            .what_if(&WhatIfOptions {
+                local_slots: Some(1),
+                ..options()
+            })
+            .unwrap();
+        // The local actions now run one after the other, but still finish before the last one.
+        assert_eq!(4, result.what_if_wall_time);
+
+        let result = build(&nodes())
+            .what_if(&WhatIfOptions {
+                speedup: vec![parse_speedup("cxx_compile=50").unwrap()],
+                ..options()
+            })
+            .unwrap();
+        assert_eq!(4, result.affected_actions);
+        assert_eq!(3, result.what_if_wall_time);
+        assert_eq!(3, result.what_if_critical_path);
+    }
+}
@@ -14,6 +14,7 @@ use buck2_client_ctx::events_ctx::EventsCtx;
 use buck2_client_ctx::exit_result::ExitResult;
 
 mod action_divergence;
+mod action_graph_diff;
 mod diff_options;
 mod external_config_diff;
 
@@ -21,6 +22,7 @@ mod external_config_diff;
 #[clap(about = "Subcommands for diff'ing two buck2 commands")]
 pub enum DiffCommand {
     ActionDivergence(action_divergence::ActionDivergenceCommand),
+    ActionGraph(action_graph_diff::ActionGraphDiffCommand),
     ExternalConfigs(external_config_diff::ExternalConfigDiffCommand),
 }
 
@@ -34,6 +36,7 @@ impl DiffCommand {
         match self {
             Self::ExternalConfigs(cmd) => ctx.exec(cmd, matches, events_ctx),
             Self::ActionDivergence(cmd) => ctx.exec(cmd, matches, events_ctx),
+            Self::ActionGraph(cmd) => ctx.exec(cmd, matches, events_ctx),
         }
     }
 }
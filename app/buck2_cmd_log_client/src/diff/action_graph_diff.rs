@@ -0,0 +1,490 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is dual-licensed under either the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree or the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree. You may select, at your option, one of the
+ * above-listed licenses.
+ */
+
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::time::Duration;
+
+use buck2_client_ctx::client_ctx::BuckSubcommand;
+use buck2_client_ctx::client_ctx::ClientCommandContext;
+use buck2_client_ctx::common::BuckArgMatches;
+use buck2_client_ctx::events_ctx::EventsCtx;
+use buck2_client_ctx::exit_result::ExitResult;
+use buck2_data::get_action_digest;
+use buck2_event_log::stream_value::StreamValue;
+use buck2_event_observer::display::TargetDisplayOptions;
+use buck2_event_observer::display::display_action_identity;
+use futures::Stream;
+use futures::TryStreamExt;
+use linked_hash_map::LinkedHashMap;
+use serde::Serialize;
+
+use crate::diff::diff_options::DiffEventLogOptions;
+
+/// Output format options for log diff action-graph.
+#[derive(Debug, Clone, clap::ValueEnum)]
+#[clap(rename_all = "snake_case")]
+enum ActionGraphDiffFormat {
+    /// Human-readable output (default).
+    Readable,
+    /// JSON format, one object per line.
+    Json,
+}
+
+/// Compares every action executed by two commands.
+///
+/// Actions are aligned by their identity (owning target plus category and identifier), and
+/// reported when they only ran in one of the commands, or when their action digest, execution
+/// kind (e.g. cache hit vs local vs remote) or duration changed.
+#[derive(Debug, clap::Parser)]
+pub struct ActionGraphDiffCommand {
+    #[clap(flatten)]
+    diff_event_log: DiffEventLogOptions,
+    #[clap(
+        long,
+        help = "Which output format to use for this command",
+        default_value = "readable",
+        ignore_case = true,
+        value_enum
+    )]
+    format: ActionGraphDiffFormat,
+    /// Report a duration change when the action got slower or faster by at least this
+    /// percentage.
+    #[clap(long, default_value = "50", value_name = "PERCENT")]
+    duration_threshold_percent: u64,
+    /// Ignore duration changes smaller than this many milliseconds, however large they are
+    /// relative to the original duration.
+    #[clap(long, default_value = "1000", value_name = "MILLISECONDS")]
+    min_duration_change_ms: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+struct ActionSummary {
+    category: String,
+    digest: Option<String>,
+    execution_kind: &'static str,
+    duration_ms: Option<u64>,
+    failed: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum ActionChange {
+    Digest,
+    ExecutionKind,
+    Duration,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum ActionGraphDiffEntry<'a> {
+    Appeared {
+        action: &'a str,
+        second: &'a ActionSummary,
+    },
+    Disappeared {
+        action: &'a str,
+        first: &'a ActionSummary,
+    },
+    Changed {
+        action: &'a str,
+        changes: Vec<ActionChange>,
+        first: &'a ActionSummary,
+        second: &'a ActionSummary,
+    },
+}
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+struct ActionGraphDiffSummary {
+    first_actions: usize,
+    second_actions: usize,
+    appeared: usize,
+    disappeared: usize,
+    digest_changed: usize,
+    execution_kind_changed: usize,
+    duration_changed: usize,
+    /// Execution kind transitions, e.g. `ACTION_EXECUTION_KIND_ACTION_CACHE -> ACTION_EXECUTION_KIND_REMOTE`.
+    execution_kind_transitions: BTreeMap<String, usize>,
+}
+
+struct DurationThreshold {
+    percent: u64,
+    min_change: Duration,
+}
+
+impl DurationThreshold {
+    fn is_significant(&self, first: Option<u64>, second: Option<u64>) -> bool {
+        let (Some(first), Some(second)) = (first, second) else {
+            return false;
+        };
+        let delta = first.abs_diff(second);
+        if delta < self.min_change.as_millis() as u64 {
+            return false;
+        }
+        // Compare against the faster of the two, so that a 2x slowdown and a 2x speedup are
+        // treated the same way.
+        let base = first.min(second);
+        base == 0 || delta.saturating_mul(100) >= base.saturating_mul(self.percent)
+    }
+}
+
+fn get_action_summary(event: &buck2_data::BuckEvent) -> Option<(String, ActionSummary)> {
+    let Some(buck2_data::buck_event::Data::SpanEnd(end)) = &event.data else {
+        return None;
+    };
+    let Some(buck2_data::span_end_event::Data::ActionExecution(action)) = &end.data else {
+        return None;
+    };
+    let identity = display_action_identity(
+        action.key.as_ref(),
+        action.name.as_ref(),
+        TargetDisplayOptions::for_log(),
+    )
+    .ok()?;
+
+    Some((
+        identity,
+        ActionSummary {
+            category: action
+                .name
+                .as_ref()
+                .map(|name| name.category.clone())
+                .unwrap_or_default(),
+            digest: get_action_digest(&action.commands),
+            execution_kind: buck2_data::ActionExecutionKind::try_from(action.execution_kind)
+                .unwrap_or(buck2_data::ActionExecutionKind::NotSet)
+                .as_str_name(),
+            duration_ms: action
+                .wall_time
+                .as_ref()
+                .map(|d| (d.seconds as u64) * 1000 + (d.nanos as u64) / 1_000_000),
+            failed: action.failed,
+        },
+    ))
+}
+
+async fn get_actions(
+    mut events: impl Stream<Item = buck2_error::Result<StreamValue>> + Unpin + Send,
+) -> buck2_error::Result<LinkedHashMap<String, ActionSummary>> {
+    let mut out = LinkedHashMap::new();
+    while let Some(event) = events.try_next().await? {
+        if let StreamValue::Event(event) = event {
+            if let Some((identity, summary)) = get_action_summary(&event) {
+                out.insert(identity, summary);
+            }
+        }
+    }
+    Ok(out)
+}
+
+fn diff_actions<'a>(
+    first: &'a LinkedHashMap<String, ActionSummary>,
+    second: &'a LinkedHashMap<String, ActionSummary>,
+    threshold: &DurationThreshold,
+) -> (Vec<ActionGraphDiffEntry<'a>>, ActionGraphDiffSummary) {
+    let mut entries = Vec::new();
+    let mut summary = ActionGraphDiffSummary {
+        first_actions: first.len(),
+        second_actions: second.len(),
+        ..Default::default()
+    };
+
+    for (action, s2) in second {
+        let Some(s1) = first.get(action) else {
+            summary.appeared += 1;
+            entries.push(ActionGraphDiffEntry::Appeared { action, second: s2 });
+            continue;
+        };
+
+        let mut changes = Vec::new();
+        if s1.digest != s2.digest {
+            summary.digest_changed += 1;
+            changes.push(ActionChange::Digest);
+        }
+        if s1.execution_kind != s2.execution_kind {
+            summary.execution_kind_changed += 1;
+            *summary
+                .execution_kind_transitions
+                .entry(format!("{} -> {}", s1.execution_kind, s2.execution_kind))
+                .or_default() += 1;
+            changes.push(ActionChange::ExecutionKind);
+        }
+        if threshold.is_significant(s1.duration_ms, s2.duration_ms) {
+            summary.duration_changed += 1;
+            changes.push(ActionChange::Duration);
+        }
+        if !changes.is_empty() {
+            entries.push(ActionGraphDiffEntry::Changed {
+                action,
+                changes,
+                first: s1,
+                second: s2,
+            });
+        }
+    }
+
+    for (action, s1) in first {
+        if !second.contains_key(action) {
+            summary.disappeared += 1;
+            entries.push(ActionGraphDiffEntry::Disappeared { action, first: s1 });
+        }
+    }
+
+    (entries, summary)
+}
+
+fn format_duration(duration_ms: Option<u64>) -> String {
+    match duration_ms {
+        Some(ms) => format!("{:.3}s", ms as f64 / 1000.0),
+        None => "_".to_owned(),
+    }
+}
+
+fn write_readable(
+    w: &mut dyn Write,
+    entries: &[ActionGraphDiffEntry<'_>],
+    summary: &ActionGraphDiffSummary,
+) -> buck2_error::Result<()> {
+    writeln!(w, "=== Summary ===")?;
+    writeln!(
+        w,
+        "actions: {} | {}",
+        summary.first_actions, summary.second_actions
+    )?;
+    writeln!(w, "appeared: {}", summary.appeared)?;
+    writeln!(w, "disappeared: {}", summary.disappeared)?;
+    writeln!(w, "digest changed: {}", summary.digest_changed)?;
+    writeln!(
+        w,
+        "execution kind changed: {}",
+        summary.execution_kind_changed
+    )?;
+    for (transition, count) in &summary.execution_kind_transitions {
+        writeln!(w, "  {transition}: {count}")?;
+    }
+    writeln!(w, "duration changed: {}", summary.duration_changed)?;
+
+    if entries.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(w, "=== Actions ===")?;
+    for entry in entries {
+        match entry {
+            ActionGraphDiffEntry::Appeared { action, .. } => writeln!(w, "+ {action}")?,
+            ActionGraphDiffEntry::Disappeared { action, .. } => writeln!(w, "- {action}")?,
+            ActionGraphDiffEntry::Changed {
+                action,
+                changes,
+                first,
+                second,
+            } => {
+                writeln!(w, "~ {action}")?;
+                for change in changes {
+                    match change {
+                        ActionChange::Digest => writeln!(
+                            w,
+                            "    digest: {} | {}",
+                            first.digest.as_deref().unwrap_or("_"),
+                            second.digest.as_deref().unwrap_or("_"),
+                        )?,
+                        ActionChange::ExecutionKind => writeln!(
+                            w,
+                            "    execution kind: {} | {}",
+                            first.execution_kind, second.execution_kind,
+                        )?,
+                        ActionChange::Duration => writeln!(
+                            w,
+                            "    duration: {} | {}",
+                            format_duration(first.duration_ms),
+                            format_duration(second.duration_ms),
+                        )?,
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+impl BuckSubcommand for ActionGraphDiffCommand {
+    const COMMAND_NAME: &'static str = "log-diff-action-graph";
+
+    async fn exec_impl(
+        self,
+        _matches: BuckArgMatches<'_>,
+        ctx: ClientCommandContext<'_>,
+        _events_ctx: &mut EventsCtx,
+    ) -> ExitResult {
+        let Self {
+            diff_event_log,
+            format,
+            duration_threshold_percent,
+            min_duration_change_ms,
+        } = self;
+
+        let (log_path1, log_path2) = diff_event_log.get(&ctx).await?;
+
+        let (invocation1, events1) = log_path1.unpack_stream().await?;
+        let (invocation2, events2) = log_path2.unpack_stream().await?;
+
+        let actions1 = get_actions(events1).await?;
+        let actions2 = get_actions(events2).await?;
+
+        let threshold = DurationThreshold {
+            percent: duration_threshold_percent,
+            min_change: Duration::from_millis(min_duration_change_ms),
+        };
+        let (entries, summary) = diff_actions(&actions1, &actions2, &threshold);
+
+        buck2_client_ctx::stdio::print_with_writer::<buck2_error::Error, _>(async move |w| {
+            match format {
+                ActionGraphDiffFormat::Readable => {
+                    writeln!(
+                        w,
+                        "Comparing actions between: \n{} and \n{}",
+                        invocation1.display_command_line(),
+                        invocation2.display_command_line()
+                    )?;
+                    write_readable(w, &entries, &summary)?;
+                }
+                ActionGraphDiffFormat::Json => {
+                    for entry in &entries {
+                        serde_json::to_writer(&mut *w, entry)?;
+                        writeln!(w)?;
+                    }
+                    serde_json::to_writer(
+                        &mut *w,
+                        &serde_json::json!({ "kind": "summary", "summary": summary }),
+                    )?;
+                    writeln!(w)?;
+                }
+            }
+            Ok(())
+        })
+        .await?;
+
+        ExitResult::success()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(digest: &str, execution_kind: &'static str, duration_ms: u64) -> ActionSummary {
+        ActionSummary {
+            category: "cxx_compile".to_owned(),
+            digest: Some(digest.to_owned()),
+            execution_kind,
+            duration_ms: Some(duration_ms),
+            failed: false,
+        }
+    }
+
+    fn threshold() -> DurationThreshold {
+        DurationThreshold {
+            percent: 50,
+            min_change: Duration::from_millis(1000),
+        }
+    }
+
+    #[test]
+    fn test_duration_threshold() {
+        let t = threshold();
+        assert!(!t.is_significant(Some(100), Some(900)));
+        assert!(t.is_significant(Some(2000), Some(4000)));
+        assert!(t.is_significant(Some(4000), Some(2000)));
+        assert!(!t.is_significant(Some(10000), Some(12000)));
+        assert!(!t.is_significant(None, Some(12000)));
+    }
+
+    #[test]
+    fn test_diff_actions() {
+        let mut first = LinkedHashMap::new();
+        first.insert(
+            "a".to_owned(),
+            summary("d1", "ACTION_EXECUTION_KIND_ACTION_CACHE", 10),
+        );
+        first.insert(
+            "b".to_owned(),
+            summary("d2", "ACTION_EXECUTION_KIND_LOCAL", 2000),
+        );
+        first.insert(
+            "gone".to_owned(),
+            summary("d3", "ACTION_EXECUTION_KIND_LOCAL", 10),
+        );
+
+        let mut second = LinkedHashMap::new();
+        second.insert(
+            "a".to_owned(),
+            summary("d1", "ACTION_EXECUTION_KIND_REMOTE", 10),
+        );
+        second.insert(
+            "b".to_owned(),
+            summary("d2x", "ACTION_EXECUTION_KIND_LOCAL", 5000),
+        );
+        second.insert(
+            "new".to_owned(),
+            summary("d4", "ACTION_EXECUTION_KIND_LOCAL", 10),
+        );
+
+        let (entries, summary) = diff_actions(&first, &second, &threshold());
+
+        assert_eq!(
+            entries,
+            vec![
+                ActionGraphDiffEntry::Changed {
+                    action: "a",
+                    changes: vec![ActionChange::ExecutionKind],
+                    first: &first["a"],
+                    second: &second["a"],
+                },
+                ActionGraphDiffEntry::Changed {
+                    action: "b",
+                    changes: vec![ActionChange::Digest, ActionChange::Duration],
+                    first: &first["b"],
+                    second: &second["b"],
+                },
+                ActionGraphDiffEntry::Appeared {
+                    action: "new",
+                    second: &second["new"],
+                },
+                ActionGraphDiffEntry::Disappeared {
+                    action: "gone",
+                    first: &first["gone"],
+                },
+            ]
+        );
+        assert_eq!(summary.appeared, 1);
+        assert_eq!(summary.disappeared, 1);
+        assert_eq!(summary.digest_changed, 1);
+        assert_eq!(summary.execution_kind_changed, 1);
+        assert_eq!(summary.duration_changed, 1);
+        assert_eq!(
+            summary.execution_kind_transitions["ACTION_EXECUTION_KIND_ACTION_CACHE -> ACTION_EXECUTION_KIND_REMOTE"],
+            1
+        );
+    }
+
+    #[test]
+    fn test_serialize_entry() -> buck2_error::Result<()> {
+        let s = summary("d1", "ACTION_EXECUTION_KIND_LOCAL", 10);
+        let entry = ActionGraphDiffEntry::Appeared {
+            action: "root//:t (cxx_compile a.cpp)",
+            second: &s,
+        };
+        assert_eq!(
+            serde_json::to_string(&entry)?,
+            r#"{"kind":"appeared","action":"root//:t (cxx_compile a.cpp)","second":{"category":"cxx_compile","digest":"d1","execution_kind":"ACTION_EXECUTION_KIND_LOCAL","duration_ms":10,"failed":false}}"#
+        );
+        Ok(())
+    }
+}
@@ -21,6 +21,7 @@ use buck2_log_common::chrome_trace::ChromeTraceCommand;
 use dupe::Dupe;
 
 mod critical_path;
+mod critical_path_what_if;
 mod diff;
 mod external_configs;
 pub(crate) mod path_log;
@@ -34,6 +35,7 @@ mod what_cmd;
 mod what_failed;
 mod what_materialized;
 pub(crate) mod what_ran;
+mod what_suspended;
 mod what_up;
 mod what_uploaded;
 
@@ -127,6 +129,7 @@ pub enum LogCommand {
     WhatUp(what_up::WhatUpCommand),
     WhatMaterialized(what_materialized::WhatMaterializedCommand),
     WhatUploaded(what_uploaded::WhatUploadedCommand),
+    WhatSuspended(what_suspended::WhatSuspendedCommand),
     CriticalPath(critical_path::CriticalPathCommand),
     SlowestPath(critical_path::SlowestPathCommand),
     Replay(replay::ReplayCommand),
@@ -157,6 +160,7 @@ impl LogCommand {
             Self::WhatUp(cmd) => ctx.exec(cmd, matches, events_ctx),
             Self::WhatMaterialized(cmd) => ctx.exec(cmd, matches, events_ctx),
             Self::WhatUploaded(cmd) => ctx.exec(cmd, matches, events_ctx),
+            Self::WhatSuspended(cmd) => ctx.exec(cmd, matches, events_ctx),
             Self::CriticalPath(cmd) => ctx.exec(cmd, matches, events_ctx),
             Self::SlowestPath(cmd) => ctx.exec(cmd, matches, events_ctx),
             Self::Replay(cmd) => ctx.exec(cmd, matches, events_ctx),
@@ -184,6 +188,7 @@ impl LogCommand {
             Self::WhatUp(cmd) => cmd.logging_name(),
             Self::WhatMaterialized(cmd) => cmd.logging_name(),
             Self::WhatUploaded(cmd) => cmd.logging_name(),
+            Self::WhatSuspended(cmd) => cmd.logging_name(),
             Self::CriticalPath(cmd) => cmd.logging_name(),
             Self::SlowestPath(cmd) => cmd.logging_name(),
             Self::Replay(cmd) => cmd.logging_name(),
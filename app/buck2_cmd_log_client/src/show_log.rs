@@ -15,13 +15,23 @@ use buck2_client_ctx::event_log_options::EventLogOptions;
 use buck2_client_ctx::events_ctx::EventsCtx;
 use buck2_client_ctx::exit_result::ExitResult;
 use buck2_client_ctx::stdio;
-use tokio_stream::StreamExt;
+use buck2_event_log::index::EventLogFilter;
+use futures::StreamExt;
+use futures::TryStreamExt;
 
 /// Outputs the log in JSON format from selected invocation.
 #[derive(Debug, clap::Parser)]
 pub struct ShowLogCommand {
     #[clap(flatten)]
     event_log: EventLogOptions,
+
+    /// Only output the events matching this filter, e.g. `kind=ActionExecution,target=root//foo:bar`.
+    /// Keys are `kind` (event type), `target` (unconfigured target label), `span` (span id),
+    /// `after` and `before` (Unix timestamps). Values of the same key are alternatives.
+    /// Logs written with `BUCK2_EVENT_LOG_INDEX=true` are indexed, and only the relevant parts of
+    /// them are read.
+    #[clap(long, value_name = "FILTER")]
+    filter: Option<String>,
 }
 
 impl BuckSubcommand for ShowLogCommand {
@@ -33,10 +43,20 @@ impl BuckSubcommand for ShowLogCommand {
         ctx: ClientCommandContext<'_>,
         _events_ctx: &mut EventsCtx,
     ) -> ExitResult {
-        let Self { event_log } = self;
+        let Self { event_log, filter } = self;
         let log_path = event_log.get(&ctx).await?;
 
-        let (invocation, mut events) = log_path.unpack_stream().await?;
+        let (invocation, mut events) = match filter {
+            Some(filter) => {
+                log_path
+                    .unpack_stream_filtered(filter.parse::<EventLogFilter>()?)
+                    .await?
+            }
+            None => {
+                let (invocation, events) = log_path.unpack_stream().await?;
+                (invocation, events.boxed())
+            }
+        };
 
         let mut buf = Vec::new();
 
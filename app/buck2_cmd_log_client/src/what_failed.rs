@@ -18,7 +18,8 @@ use crate::what_ran::WhatRanCommandCommon;
 
 /// Outputs every command that failed in the selected invocation.
 ///
-/// Look at the help for what-ran to understand the output format.
+/// Look at the help for what-ran to understand the output format. With `--format json`, commands
+/// whose action error handler found source locations also carry them as `diagnostics`.
 #[derive(Debug, clap::Parser)]
 pub struct WhatFailedCommand {
     #[clap(flatten)]
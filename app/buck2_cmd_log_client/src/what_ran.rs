@@ -25,6 +25,7 @@ use buck2_event_log::stream_value::StreamValue;
 use buck2_event_observer::fmt_duration;
 use buck2_event_observer::what_ran;
 use buck2_event_observer::what_ran::CommandReproducer;
+use buck2_event_observer::what_ran::WhatRanCommandOutcome;
 use buck2_event_observer::what_ran::WhatRanOptions;
 use buck2_event_observer::what_ran::WhatRanOutputCommand;
 use buck2_event_observer::what_ran::WhatRanOutputCommandExtra;
@@ -179,22 +180,12 @@ impl WhatRanEntry {
         self,
         output: &mut impl WhatRanOutputWriter,
         options: &WhatRanCommandOptions,
-        std_err: Option<&str>,
-        duration: Option<std::time::Duration>,
-        scheduling_mode: Option<SchedulingMode>,
+        outcome: WhatRanCommandOutcome<'_>,
     ) -> Result<(), ClientIoError> {
         let action = &self.action;
         let options_regex = what_ran::WhatRanOptionsRegex::from_options(&options.options)?;
         for repro in self.reproducers.into_iter() {
-            what_ran::emit_what_ran_entry(
-                Some(action),
-                repro,
-                output,
-                &options_regex,
-                std_err,
-                duration,
-                scheduling_mode,
-            )?;
+            what_ran::emit_what_ran_entry(Some(action), repro, output, &options_regex, outcome)?;
         }
         Ok(())
     }
@@ -240,7 +231,7 @@ impl WhatRanCommandState {
     ) -> buck2_error::Result<()> {
         for (_, entry) in self.known_actions.into_iter() {
             if should_emit_unfinished_action(options) {
-                entry.emit_what_ran_entry(output, options, None, None, None)?;
+                entry.emit_what_ran_entry(output, options, WhatRanCommandOutcome::default())?;
             }
         }
         Ok(())
@@ -287,25 +278,27 @@ impl WhatRanCommandState {
                 && should_emit_finished_action(&span.data, options)
             {
                 // Get extra data out of SpanEnd event
-                let (execution_kind, std_err, duration, scheduling_mode) =
-                    match &span.data {
-                        Some(buck2_data::span_end_event::Data::ActionExecution(action_exec)) => (
-                            Some(action_exec.execution_kind),
-                            action_exec.commands.iter().last().and_then(|cmd| {
+                let (execution_kind, outcome) = match &span.data {
+                    Some(buck2_data::span_end_event::Data::ActionExecution(action_exec)) => (
+                        Some(action_exec.execution_kind),
+                        WhatRanCommandOutcome {
+                            std_err: action_exec.commands.iter().last().and_then(|cmd| {
                                 cmd.details.as_ref().map(|d| d.cmd_stderr.as_ref())
                             }),
-                            action_exec.wall_time.as_ref().map(
+                            duration: action_exec.wall_time.as_ref().map(
                                 |prost_types::Duration { seconds, nanos }| {
                                     std::time::Duration::new(*seconds as u64, *nanos as u32)
                                 },
                             ),
-                            action_exec
+                            scheduling_mode: action_exec
                                 .scheduling_mode
                                 .as_ref()
                                 .and_then(|o| SchedulingMode::try_from(*o).ok()),
-                        ),
-                        _ => (None, None, None, None),
-                    };
+                            diagnostics: action_exec.diagnostics.as_slice(),
+                        },
+                    ),
+                    _ => (None, WhatRanCommandOutcome::default()),
+                };
 
                 if execution_kind == Some(buck2_data::ActionExecutionKind::LocalDepFile as i32) {
                     entry
@@ -313,7 +306,7 @@ impl WhatRanCommandState {
                         .push(CommandReproducer::LocalDepFileCacheHit);
                 }
 
-                entry.emit_what_ran_entry(output, options, std_err, duration, scheduling_mode)?;
+                entry.emit_what_ran_entry(output, options, outcome)?;
             }
         }
 
@@ -467,6 +460,11 @@ impl WhatRanOutputWriter for OutputFormatWithWriter<'_> {
                     extra: command.extra.map(Into::into),
                     std_err,
                     scheduling_mode: command.scheduling_mode,
+                    diagnostics: command
+                        .diagnostics
+                        .iter()
+                        .map(JsonDiagnostic::from)
+                        .collect(),
                 };
                 serde_json::to_writer(w.by_ref(), &command)?;
                 w.write_all("\n".as_bytes())?;
@@ -519,6 +517,38 @@ struct JsonCommand<'a> {
     std_err: Option<&'a str>,
     #[serde(skip_serializing_if = "Option::is_none")]
     scheduling_mode: Option<SchedulingMode>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    diagnostics: Vec<JsonDiagnostic<'a>>,
+}
+
+/// A diagnostic parsed from the output of a failed action by its error handler.
+#[derive(serde::Serialize)]
+struct JsonDiagnostic<'a> {
+    file: &'a str,
+    line: Option<u64>,
+    column: Option<u64>,
+    end_line: Option<u64>,
+    end_column: Option<u64>,
+    severity: String,
+    message: &'a str,
+    category: &'a str,
+    code: Option<u64>,
+}
+
+impl<'a> From<&'a buck2_data::ActionDiagnostic> for JsonDiagnostic<'a> {
+    fn from(diagnostic: &'a buck2_data::ActionDiagnostic) -> JsonDiagnostic<'a> {
+        JsonDiagnostic {
+            file: &diagnostic.file,
+            line: diagnostic.line,
+            column: diagnostic.column,
+            end_line: diagnostic.end_line,
+            end_column: diagnostic.end_column,
+            severity: diagnostic.severity().to_string(),
+            message: &diagnostic.message,
+            category: &diagnostic.category,
+            code: diagnostic.code,
+        }
+    }
 }
 
 mod json_reproducer {
@@ -603,6 +633,7 @@ mod tests {
             extra: None,
             std_err: None,
             scheduling_mode: None,
+            diagnostics: Vec::new(),
         }
     }
 
@@ -621,6 +652,7 @@ mod tests {
             extra: None,
             std_err: None,
             scheduling_mode: None,
+            diagnostics: Vec::new(),
         }
     }
 
@@ -698,6 +730,51 @@ mod tests {
     }
   },
   "duration": "1"
+}"#;
+        assert_eq!(expected, serde_json::to_string_pretty(&command)?);
+        Ok(())
+    }
+
+    #[test]
+    fn serialize_what_ran_command_with_diagnostics() -> buck2_error::Result<()> {
+        let diagnostic = buck2_data::ActionDiagnostic {
+            file: "src/lib.rs".to_owned(),
+            line: Some(3),
+            column: Some(7),
+            severity: buck2_data::DiagnosticSeverity::Error as i32,
+            message: "expected `;`".to_owned(),
+            category: "rustc".to_owned(),
+            ..Default::default()
+        };
+        let mut command = make_base_command_in_re();
+        command.diagnostics = vec![JsonDiagnostic::from(&diagnostic)];
+
+        let expected = r#"{
+  "reason": "test.run",
+  "identity": "some/target",
+  "reproducer": {
+    "executor": "Re",
+    "details": {
+      "digest": "placeholder",
+      "platform_properties": {
+        "platform": "linux-remote-execution"
+      }
+    }
+  },
+  "duration": "1",
+  "diagnostics": [
+    {
+      "file": "src/lib.rs",
+      "line": 3,
+      "column": 7,
+      "end_line": null,
+      "end_column": null,
+      "severity": "error",
+      "message": "expected `;`",
+      "category": "rustc",
+      "code": null
+    }
+  ]
 }"#;
         assert_eq!(expected, serde_json::to_string_pretty(&command)?);
         Ok(())
@@ -0,0 +1,152 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is dual-licensed under either the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree or the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree. You may select, at your option, one of the
+ * above-listed licenses.
+ */
+
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::io::Write;
+
+use buck2_client_ctx::client_ctx::BuckSubcommand;
+use buck2_client_ctx::client_ctx::ClientCommandContext;
+use buck2_client_ctx::common::BuckArgMatches;
+use buck2_client_ctx::event_log_options::EventLogOptions;
+use buck2_client_ctx::events_ctx::EventsCtx;
+use buck2_client_ctx::exit_result::ClientIoError;
+use buck2_client_ctx::exit_result::ExitResult;
+use buck2_event_log::stream_value::StreamValue;
+use tokio_stream::StreamExt;
+
+use crate::LogCommandOutputFormat;
+use crate::LogCommandOutputFormatWithWriter;
+use crate::transform_format;
+
+/// Outputs the decisions the resource control scheduler made to suspend, wake or hold back local
+/// actions under memory pressure, from the selected invocation.
+#[derive(Debug, clap::Parser)]
+pub struct WhatSuspendedCommand {
+    #[clap(flatten)]
+    event_log: EventLogOptions,
+    #[clap(flatten)]
+    output: LogCommandOutputFormat,
+}
+
+#[derive(serde::Serialize)]
+struct DecisionRecord {
+    kind: String,
+    reason: String,
+    policy: String,
+    action_digest: String,
+    action_category: String,
+    action_memory_current: u64,
+    candidates_count: u64,
+    category_memory_current: Option<u64>,
+    category_memory_budget: Option<u64>,
+    allprocs_memory_pressure: u64,
+}
+
+impl Display for DecisionRecord {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            self.kind,
+            self.reason,
+            self.policy,
+            self.action_digest,
+            self.action_category,
+            self.action_memory_current,
+            self.allprocs_memory_pressure,
+        )?;
+        if let (Some(current), Some(budget)) =
+            (self.category_memory_current, self.category_memory_budget)
+        {
+            write!(f, "\t{current}/{budget}")?;
+        }
+        Ok(())
+    }
+}
+
+fn get_decision_record(event: &buck2_data::ResourceControlEvent) -> Option<DecisionRecord> {
+    let decision = event.decision.as_ref()?;
+    Some(DecisionRecord {
+        kind: event.kind().as_str_name().to_owned(),
+        reason: decision.reason().as_str_name().to_owned(),
+        policy: decision.policy.clone(),
+        action_digest: event.action_digest.clone().unwrap_or_default(),
+        action_category: event.action_category.clone().unwrap_or_default(),
+        action_memory_current: event.action_cgroup_memory_current.unwrap_or_default(),
+        candidates_count: decision.candidates_count,
+        category_memory_current: decision.category_memory_current,
+        category_memory_budget: decision.category_memory_budget,
+        allprocs_memory_pressure: event.allprocs_memory_pressure,
+    })
+}
+
+fn print_decision(
+    output: &mut LogCommandOutputFormatWithWriter,
+    record: &DecisionRecord,
+) -> Result<(), ClientIoError> {
+    match output {
+        LogCommandOutputFormatWithWriter::Readable(w)
+        | LogCommandOutputFormatWithWriter::Tabulated(w) => Ok(writeln!(w, "{record}")?),
+        LogCommandOutputFormatWithWriter::Csv(writer) => Ok(writer.serialize(record)?),
+        LogCommandOutputFormatWithWriter::Json(w) => {
+            serde_json::to_writer(w.by_ref(), &record)?;
+            w.write_all("\n".as_bytes())?;
+            Ok(())
+        }
+    }
+}
+
+impl BuckSubcommand for WhatSuspendedCommand {
+    const COMMAND_NAME: &'static str = "log-what-suspended";
+
+    async fn exec_impl(
+        self,
+        _matches: BuckArgMatches<'_>,
+        ctx: ClientCommandContext<'_>,
+        _events_ctx: &mut EventsCtx,
+    ) -> ExitResult {
+        let Self { event_log, output } = self;
+
+        buck2_client_ctx::stdio::print_with_writer::<buck2_error::Error, _>(async move |w| {
+            let mut output = transform_format(output, w);
+            let log_path = event_log.get(&ctx).await?;
+
+            let (invocation, mut events) = log_path.unpack_stream().await?;
+            buck2_client_ctx::eprintln!(
+                "Showing resource control decisions from: {}",
+                invocation.display_command_line()
+            )?;
+
+            let mut decisions = 0;
+            while let Some(event) = events.try_next().await? {
+                match event {
+                    StreamValue::Event(event) => {
+                        if let Some(buck2_data::buck_event::Data::Instant(instant)) = &event.data
+                            && let Some(buck2_data::instant_event::Data::ResourceControlEvent(
+                                resource_control,
+                            )) = &instant.data
+                            && let Some(record) = get_decision_record(resource_control)
+                        {
+                            decisions += 1;
+                            print_decision(&mut output, &record)?;
+                        }
+                    }
+                    StreamValue::Result(..) | StreamValue::PartialResult(..) => {}
+                }
+            }
+            buck2_client_ctx::eprintln!("total: decisions: {}", decisions)?;
+
+            Ok(())
+        })
+        .await?;
+        ExitResult::success()
+    }
+}
@@ -0,0 +1,117 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is dual-licensed under either the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree or the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree. You may select, at your option, one of the
+ * above-listed licenses.
+ */
+
+//! Writes a [`DotDigraph`] as GraphML (see <http://graphml.graphdrawing.org/specification.html>).
+//!
+//! Only the query attributes of a node (the `extra` attrs) are written, as string `data`;
+//! the dot styling attrs mean nothing to GraphML consumers.
+
+use std::io::Write;
+
+use starlark_map::small_set::SmallSet;
+
+use crate::dot::DotDigraph;
+use crate::dot::DotNode;
+
+fn escape_xml(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+struct GraphMlNode {
+    id: String,
+    data: Vec<(String, String)>,
+    edges: Vec<String>,
+}
+
+pub(crate) struct GraphMl {}
+
+impl GraphMl {
+    pub(crate) fn render<'a, T: DotDigraph<'a>, W: Write>(
+        graph: &'a T,
+        mut w: W,
+    ) -> buck2_error::Result<()> {
+        // GraphML needs every `key` declared before the graph, so collect everything first.
+        let mut keys = SmallSet::new();
+        let mut nodes = Vec::new();
+        graph.for_each_node(|node| {
+            let data: Vec<_> = node.attrs()?.extra.into_iter().collect();
+            for (key, _) in &data {
+                keys.insert(key.clone());
+            }
+            let mut edges = Vec::new();
+            graph.for_each_edge(node, |edge| {
+                edges.push(edge.to.to_owned());
+                Ok(())
+            })?;
+            nodes.push(GraphMlNode {
+                id: node.id(),
+                data,
+                edges,
+            });
+            Ok(())
+        })?;
+
+        writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            w,
+            r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#
+        )?;
+        for key in &keys {
+            let key = escape_xml(key);
+            writeln!(
+                w,
+                r#"  <key id="{key}" for="node" attr.name="{key}" attr.type="string"/>"#
+            )?;
+        }
+        writeln!(
+            w,
+            r#"  <graph id="{}" edgedefault="directed">"#,
+            escape_xml(graph.name())
+        )?;
+        for node in nodes {
+            let id = escape_xml(&node.id);
+            if node.data.is_empty() {
+                writeln!(w, r#"    <node id="{id}"/>"#)?;
+            } else {
+                writeln!(w, r#"    <node id="{id}">"#)?;
+                for (key, value) in &node.data {
+                    writeln!(
+                        w,
+                        r#"      <data key="{}">{}</data>"#,
+                        escape_xml(key),
+                        escape_xml(value)
+                    )?;
+                }
+                writeln!(w, "    </node>")?;
+            }
+            for to in &node.edges {
+                writeln!(
+                    w,
+                    r#"    <edge source="{id}" target="{}"/>"#,
+                    escape_xml(to)
+                )?;
+            }
+        }
+        writeln!(w, "  </graph>")?;
+        writeln!(w, "</graphml>")?;
+        Ok(())
+    }
+}
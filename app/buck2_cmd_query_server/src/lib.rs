@@ -21,7 +21,9 @@ use buck2_server_ctx::late_bindings::QueryServerCommands;
 use buck2_server_ctx::partial_result_dispatcher::PartialResultDispatcher;
 
 pub(crate) mod dot;
+mod graphml;
 pub(crate) mod html;
+mod mermaid;
 pub(crate) mod query;
 mod query_output_format;
 
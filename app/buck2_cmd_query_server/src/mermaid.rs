@@ -0,0 +1,70 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is dual-licensed under either the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree or the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree. You may select, at your option, one of the
+ * above-listed licenses.
+ */
+
+//! Writes a [`DotDigraph`] as a Mermaid flowchart (see <https://mermaid.js.org/syntax/flowchart.html>).
+//!
+//! Target labels aren't valid Mermaid ids, so nodes are numbered (as in `DotCompact`) and
+//! the label is shown as the node text. Node attrs aren't written.
+
+use std::collections::hash_map::Entry::Occupied;
+use std::collections::hash_map::Entry::Vacant;
+use std::io::Write;
+
+use buck2_hash::BuckMutMap;
+
+use crate::dot::DotDigraph;
+use crate::dot::DotNode;
+
+/// Node text is double-quoted, so quotes have to be written as an entity. `#` starts an
+/// entity too, so it is escaped as well.
+fn escape_text(value: &str) -> String {
+    value.replace('#', "#35;").replace('"', "#quot;")
+}
+
+pub(crate) struct Mermaid {}
+
+impl Mermaid {
+    pub(crate) fn render<'a, T: DotDigraph<'a>, W: Write>(
+        graph: &'a T,
+        mut w: W,
+    ) -> buck2_error::Result<()> {
+        writeln!(w, "flowchart TD")?;
+
+        let mut next_id: u32 = 0;
+        let mut lookup_numeric_id: BuckMutMap<String, u32> = BuckMutMap::default();
+
+        let mut name_to_number = |node_name: &str| -> u32 {
+            match lookup_numeric_id.entry(node_name.to_owned()) {
+                Vacant(entry) => {
+                    next_id += 1;
+                    entry.insert(next_id);
+                    next_id
+                }
+                Occupied(entry) => *entry.get(),
+            }
+        };
+
+        graph.for_each_node(|node| {
+            let id = node.id();
+            writeln!(w, "  n{}[\"{}\"]", name_to_number(&id), escape_text(&id))?;
+            graph.for_each_edge(node, |edge| {
+                writeln!(
+                    w,
+                    "  n{} --> n{}",
+                    name_to_number(edge.from),
+                    name_to_number(edge.to)
+                )?;
+                Ok(())
+            })?;
+            Ok(())
+        })?;
+        Ok(())
+    }
+}
@@ -22,4 +22,8 @@ pub(crate) enum QueryCommandError {
         "query result was a set of files and one or more --output-attribute was requested, but files have not attributes"
     )]
     FileSetHasNoAttributes,
+    #[error(
+        "query result was a set of files, which has no edges to output as a graph (json_graph, graphml, mermaid)"
+    )]
+    FileSetHasNoGraph,
 }
@@ -42,7 +42,9 @@ use serde::ser::SerializeSeq;
 use crate::dot::Dot;
 use crate::dot::DotCompact;
 use crate::dot::targets::DotTargetGraph;
+use crate::graphml::GraphMl;
 use crate::html::Html;
+use crate::mermaid::Mermaid;
 use crate::query::QueryCommandError;
 use crate::query::query_target_ext::QueryCommandTarget;
 use crate::query_output_format::QueryOutputFormatInfo;
@@ -196,6 +198,73 @@ impl<T: QueryCommandTarget> Serialize for TargetSetJsonPrinter<'_, T> {
     }
 }
 
+/// The `json_graph` format: the targets, plus the edges between them.
+struct TargetGraphJsonPrinter<'a, T: QueryTarget> {
+    nodes: TargetSetJsonPrinter<'a, T>,
+    targets: &'a TargetSet<T>,
+}
+
+#[derive(Serialize)]
+struct JsonGraphEdge {
+    from: String,
+    to: String,
+}
+
+impl<T: QueryCommandTarget> Serialize for TargetGraphJsonPrinter<'_, T> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        struct Node<'a, 'b, T: QueryTarget> {
+            target: &'a PrintableQueryTarget<'b, T>,
+            with_attributes: bool,
+        }
+
+        impl<T: QueryCommandTarget> Serialize for Node<'_, '_, T> {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                let mut map = serializer.serialize_map(None)?;
+                map.serialize_entry("id", &self.target.label())?;
+                if self.with_attributes {
+                    map.serialize_entry("attributes", self.target)?;
+                }
+                map.end()
+            }
+        }
+
+        let nodes: Vec<_> = self
+            .nodes
+            .value
+            .iter()
+            .map(|target| Node {
+                target,
+                with_attributes: self.nodes.is_complex,
+            })
+            .collect();
+        // Only include edges to other nodes within the result, as for dot.
+        let edges: Vec<_> = self
+            .targets
+            .iter()
+            .flat_map(|target| {
+                target
+                    .deps()
+                    .filter(|dep| self.targets.contains(dep))
+                    .map(move |dep| JsonGraphEdge {
+                        from: target.node_key().to_string(),
+                        to: dep.to_string(),
+                    })
+            })
+            .collect();
+
+        let mut map = serializer.serialize_map(Some(2))?;
+        map.serialize_entry("nodes", &nodes)?;
+        map.serialize_entry("edges", &edges)?;
+        map.end()
+    }
+}
+
 struct FileSetJsonPrinter<'a> {
     value: &'a FileSet,
     resolver: &'a CellResolver,
@@ -416,6 +485,42 @@ impl<'a> QueryResultPrinter<'a> {
                         &mut output,
                     )?;
                 }
+                QueryOutputFormatInfo::JsonGraph => {
+                    {
+                        let mut ser = serde_json::Serializer::pretty(&mut output);
+                        TargetGraphJsonPrinter {
+                            nodes: TargetSetJsonPrinter::new(
+                                call_stack,
+                                print_providers,
+                                &self.attributes,
+                                &targets,
+                            )
+                            .await?,
+                            targets: &targets,
+                        }
+                        .serialize(&mut ser)?;
+                    }
+                    // need to add a newline to flush the output.
+                    writeln!(&mut output)?
+                }
+                QueryOutputFormatInfo::GraphMl => {
+                    GraphMl::render(
+                        &DotTargetGraph {
+                            targets,
+                            attributes: self.attributes.clone(),
+                        },
+                        &mut output,
+                    )?;
+                }
+                QueryOutputFormatInfo::Mermaid => {
+                    Mermaid::render(
+                        &DotTargetGraph {
+                            targets,
+                            attributes: None,
+                        },
+                        &mut output,
+                    )?;
+                }
             },
             QueryEvaluationValue::FileSet(files) => {
                 if self.attributes.is_some() {
@@ -461,6 +566,11 @@ impl<'a> QueryResultPrinter<'a> {
                             "html output for files not implemented yet"
                         ));
                     }
+                    QueryOutputFormatInfo::JsonGraph
+                    | QueryOutputFormatInfo::GraphMl
+                    | QueryOutputFormatInfo::Mermaid => {
+                        return Err(QueryCommandError::FileSetHasNoGraph.into());
+                    }
                 }
             }
         }
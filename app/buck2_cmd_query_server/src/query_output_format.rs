@@ -18,6 +18,9 @@ pub(crate) enum QueryOutputFormatInfo {
     DotCompact,
     Starlark,
     Html(String),
+    JsonGraph,
+    GraphMl,
+    Mermaid,
 }
 
 impl QueryOutputFormatInfo {
@@ -30,6 +33,9 @@ impl QueryOutputFormatInfo {
             QueryOutputFormat::DotCompact => Self::DotCompact,
             QueryOutputFormat::Starlark => Self::Starlark,
             QueryOutputFormat::Html => Self::Html(trace_id),
+            QueryOutputFormat::JsonGraph => Self::JsonGraph,
+            QueryOutputFormat::Graphml => Self::GraphMl,
+            QueryOutputFormat::Mermaid => Self::Mermaid,
         };
         Some(res)
     }
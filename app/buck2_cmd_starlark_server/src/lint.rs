@@ -97,6 +97,7 @@ async fn lint_file(
                 severity: EvalSeverity::Error,
                 problem: format!("{:#}", err.without_diagnostic()),
                 original: "".to_owned(),
+                fix: None,
             }])
         }
     }
@@ -10,12 +10,14 @@
 
 pub mod default;
 pub mod fmt;
+pub mod package_cache;
 pub mod resolve_alias;
 pub mod streaming;
 
 use std::fs::File;
 use std::io::BufWriter;
 use std::io::Write;
+use std::sync::Arc;
 
 use async_trait::async_trait;
 use buck2_cli_proto::TargetsRequest;
@@ -41,6 +43,7 @@ use zstd::stream::write as zstd;
 use crate::targets::default::TargetHashOptions;
 use crate::targets::default::targets_batch;
 use crate::targets::fmt::create_formatter;
+use crate::targets::package_cache::PackageCache;
 use crate::targets::resolve_alias::targets_resolve_aliases;
 use crate::targets::streaming::targets_streaming;
 
@@ -217,6 +220,18 @@ async fn targets_with_output(
                     _ => Some(other.target_hash_use_fast_hash),
                 };
 
+                if other.imports && other.package_cache.is_some() {
+                    // Self-check.
+                    return Err(internal_error!(
+                        "Package cache cannot be used when showing imports"
+                    ));
+                }
+                let package_cache = match other.package_cache.as_deref() {
+                    Some(dir) => Some(Arc::new(
+                        PackageCache::new(&mut dice.ctx(), dir, request, other).await?,
+                    )),
+                    None => None,
+                };
                 let res = targets_streaming(
                     server_ctx,
                     dice,
@@ -228,6 +243,7 @@ async fn targets_with_output(
                     other.imports,
                     hashing,
                     request.concurrency.as_ref().map(|x| x.concurrency as usize),
+                    package_cache,
                 )
                 .await?;
                 Ok(TargetsResponse {
@@ -0,0 +1,274 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is dual-licensed under either the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree or the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree. You may select, at your option, one of the
+ * above-listed licenses.
+ */
+
+//! On-disk cache of the per-package output of `buck2 targets --streaming --package-cache`.
+//!
+//! Entries are keyed by everything the output for a package depends on, computed without
+//! evaluating the package:
+//! * the buck2 binary, identified by its revision, or by its build id or hash for builds
+//!   without a revision,
+//! * the host `host_info()` describes, since the cache can be shared across machines,
+//! * the request options which affect how targets are formatted,
+//! * the package directory listing, which is what globs see,
+//! * the buckconfigs of the root cell, of the package's cell, and of every cell the loaded
+//!   files are in, since `read_config` in a `.bzl` file reads the config of that file's cell,
+//! * the contents of the build file, the `PACKAGE` files, and the files they load.
+//!
+//! So a daemon which has never evaluated a package can reuse the output
+//! cached by another daemon, as long as none of these changed.
+
+use std::path::Path;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+
+use buck2_cli_proto::TargetsRequest;
+use buck2_cli_proto::targets_request;
+use buck2_client_ctx::version::BuckVersion;
+use buck2_common::dice::cells::HasCellResolver;
+use buck2_common::file_ops::dice::DiceFileComputations;
+use buck2_common::legacy_configs::configs::LegacyBuckConfig;
+use buck2_common::legacy_configs::dice::HasLegacyConfigs;
+use buck2_common::package_listing::dice::DicePackageListingResolver;
+use buck2_core::package::PackageLabel;
+use buck2_core::package::package_relative_path::PackageRelativePath;
+use buck2_core::pattern::pattern::PackageSpec;
+use buck2_core::pattern::pattern_type::TargetPatternExtra;
+use buck2_error::BuckErrorContext;
+use buck2_error::BuckErrorOptionContext;
+use buck2_fs::fs_util;
+use buck2_fs::paths::abs_path::AbsPath;
+use buck2_fs::paths::abs_path::AbsPathBuf;
+use buck2_interpreter::load_module::INTERPRETER_CALCULATION_IMPL;
+use dice::DiceComputations;
+use dupe::Dupe;
+use starlark_map::small_set::SmallSet;
+
+/// Bump when the format of entries, or the output stored in them, changes.
+const FORMAT_VERSION: u64 = 1;
+
+pub(crate) struct PackageCache {
+    dir: AbsPathBuf,
+    /// Hash of the parts of the request which affect the output for a package.
+    request_hash: blake3::Hash,
+}
+
+/// Key of a cache entry, see the module documentation.
+pub(crate) struct PackageCacheKey(blake3::Hash);
+
+/// Output for a package which was loaded without errors.
+#[derive(Debug, PartialEq)]
+pub(crate) struct CachedPackage {
+    pub(crate) targets: u64,
+    pub(crate) stdout: String,
+}
+
+impl CachedPackage {
+    fn serialize(&self) -> String {
+        format!("{}\n{}", self.targets, self.stdout)
+    }
+
+    fn deserialize(entry: &str) -> Option<CachedPackage> {
+        let (targets, stdout) = entry.split_once('\n')?;
+        Some(CachedPackage {
+            targets: targets.parse().ok()?,
+            stdout: stdout.to_owned(),
+        })
+    }
+}
+
+fn update_str(hasher: &mut blake3::Hasher, s: &str) {
+    hasher.update(&(s.len() as u64).to_le_bytes());
+    hasher.update(s.as_bytes());
+}
+
+fn update_config(hasher: &mut blake3::Hasher, config: &LegacyBuckConfig) {
+    for (section, values) in config.iter() {
+        hasher.update(b"s");
+        update_str(hasher, section);
+        for (key, value) in values {
+            hasher.update(b"k");
+            update_str(hasher, key);
+            update_str(hasher, value);
+        }
+    }
+    hasher.update(b"e");
+}
+
+impl PackageCache {
+    pub(crate) async fn new(
+        ctx: &mut DiceComputations<'_>,
+        dir: &str,
+        request: &TargetsRequest,
+        other: &targets_request::Other,
+    ) -> buck2_error::Result<Self> {
+        let dir = AbsPath::new(Path::new(dir))?.to_owned();
+
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(&FORMAT_VERSION.to_le_bytes());
+        match buck2_build_info::revision() {
+            Some(revision) => update_str(&mut hasher, revision),
+            // Builds without a revision, e.g. local ones, must not share entries.
+            None => update_str(&mut hasher, BuckVersion::get_unique_id()?),
+        }
+        update_str(
+            &mut hasher,
+            &INTERPRETER_CALCULATION_IMPL.get()?.host_info(ctx).await?,
+        );
+        hasher.update(&request.output_format.to_le_bytes());
+        hasher.update(&[request.client_context()?.target_call_stacks as u8]);
+        for attribute in &other.output_attributes {
+            hasher.update(b"a");
+            update_str(&mut hasher, attribute);
+        }
+        for package_value in &other.package_values {
+            hasher.update(b"p");
+            update_str(&mut hasher, package_value);
+        }
+        hasher.update(&[other.include_default_attributes as u8]);
+        hasher.update(&other.target_hash_graph_type.to_le_bytes());
+        hasher.update(&[other.target_hash_use_fast_hash as u8]);
+
+        Ok(PackageCache {
+            dir,
+            request_hash: hasher.finalize(),
+        })
+    }
+
+    /// Compute the key of the output for a package. This parses, but does not evaluate,
+    /// the Starlark files the package depends on.
+    pub(crate) async fn key(
+        &self,
+        ctx: &mut DiceComputations<'_>,
+        package: PackageLabel,
+        spec: &PackageSpec<TargetPatternExtra>,
+    ) -> buck2_error::Result<PackageCacheKey> {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(self.request_hash.as_bytes());
+        update_str(&mut hasher, &package.to_string());
+        match spec {
+            PackageSpec::All() => {
+                hasher.update(b"*");
+            }
+            PackageSpec::Targets(targets) => {
+                // Output is in the order of the spec, so don't sort.
+                for (target, TargetPatternExtra) in targets {
+                    hasher.update(b"t");
+                    update_str(&mut hasher, target.as_str());
+                }
+            }
+        }
+
+        let listing = DicePackageListingResolver(ctx)
+            .resolve_package_listing(package.dupe())
+            .await?;
+        update_str(&mut hasher, listing.buildfile().as_str());
+        for file in listing.files().files() {
+            hasher.update(b"f");
+            update_str(&mut hasher, file.as_str());
+        }
+        for subpackage in listing.subpackages_within(PackageRelativePath::empty()) {
+            hasher.update(b"d");
+            update_str(&mut hasher, subpackage.as_str());
+        }
+
+        let files = INTERPRETER_CALCULATION_IMPL
+            .get()?
+            .get_package_source_files(ctx, package.dupe())
+            .await?;
+
+        let mut cells = SmallSet::new();
+        cells.insert(ctx.get_cell_resolver().await?.root_cell());
+        cells.insert(package.cell_name());
+        cells.extend(files.iter().map(|file| file.cell()));
+        for cell in cells {
+            hasher.update(b"c");
+            update_str(&mut hasher, cell.as_str());
+            update_config(&mut hasher, ctx.get_legacy_config_for_cell(cell).await?);
+        }
+
+        for file in files {
+            update_str(&mut hasher, &file.to_string());
+            match DiceFileComputations::read_file_if_exists(ctx, file.as_ref()).await? {
+                Some(content) => {
+                    hasher.update(b"+");
+                    update_str(&mut hasher, &content);
+                }
+                None => {
+                    hasher.update(b"-");
+                }
+            }
+        }
+
+        Ok(PackageCacheKey(hasher.finalize()))
+    }
+
+    fn entry_path(&self, key: &PackageCacheKey) -> AbsPathBuf {
+        let hex = key.0.to_hex();
+        self.dir.join(&hex[..2]).join(hex.as_str())
+    }
+
+    /// Return `None` if there is no entry for the key.
+    pub(crate) fn get(&self, key: &PackageCacheKey) -> buck2_error::Result<Option<CachedPackage>> {
+        let path = self.entry_path(key);
+        let Some(entry) = fs_util::read_to_string_if_exists(&path)
+            .with_buck_error_context(|| format!("Reading package cache entry `{path}`"))?
+        else {
+            return Ok(None);
+        };
+        // Treat entries which are truncated or from an incompatible buck2 as missing.
+        Ok(CachedPackage::deserialize(&entry))
+    }
+
+    pub(crate) fn put(
+        &self,
+        key: &PackageCacheKey,
+        package: &CachedPackage,
+    ) -> buck2_error::Result<()> {
+        static NEXT_TEMP_ID: AtomicU64 = AtomicU64::new(0);
+
+        let path = self.entry_path(key);
+        let dir = path
+            .parent()
+            .internal_error("Cache entry path has a parent")?;
+        fs_util::create_dir_all(dir)?;
+        // Write then rename, so that concurrent `targets` commands sharing the cache never see
+        // partially written entries.
+        let temp = dir.join(format!(
+            "{}.{}.{}.tmp",
+            key.0.to_hex(),
+            std::process::id(),
+            NEXT_TEMP_ID.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs_util::write(&temp, package.serialize())?;
+        fs_util::rename(&temp, &path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cached_package_round_trip() {
+        let package = CachedPackage {
+            targets: 2,
+            stdout: "root//foo:a\nroot//foo:b\n".to_owned(),
+        };
+        let entry = package.serialize();
+        assert_eq!(Some(package), CachedPackage::deserialize(&entry));
+    }
+
+    #[test]
+    fn cached_package_rejects_malformed_entries() {
+        assert_eq!(None, CachedPackage::deserialize(""));
+        assert_eq!(None, CachedPackage::deserialize("two\nroot//foo:a\n"));
+    }
+}
@@ -26,6 +26,7 @@ use buck2_core::pattern::pattern::ParsedPattern;
 use buck2_core::pattern::pattern_type::PatternType;
 use buck2_core::pattern::pattern_type::TargetPatternExtra;
 use buck2_core::target::name::TargetName;
+use buck2_events::dispatch::console_warning;
 use buck2_hash::BuckMutSet;
 use buck2_interpreter::load_module::INTERPRETER_CALCULATION_IMPL;
 use buck2_interpreter::load_module::InterpreterCalculation;
@@ -52,6 +53,8 @@ use crate::target_hash::TargetHashes;
 use crate::targets::fmt::Stats;
 use crate::targets::fmt::TargetFormatter;
 use crate::targets::fmt::TargetInfo;
+use crate::targets::package_cache::CachedPackage;
+use crate::targets::package_cache::PackageCache;
 
 fn write_str(outputter: &mut dyn Write, s: &mut String) -> buck2_error::Result<()> {
     outputter.write_all(s.as_bytes())?;
@@ -95,6 +98,8 @@ fn package_concurrency(threads: Option<usize>) -> buck2_error::Result<PackageCon
 ///   Passing from cli args `--keep-going` from `app/buck2_client/src/commands/targets.rs`.
 /// * `imports` - Show the imports of each package/import. Shows an additional output per package/import (not per target), including implicit dependencies (e.g. the prelude) but only direct dependencies (not the transitive closure)
 ///   Passing from cli args `--imports` from `app/buck2_client/src/commands/targets.rs`.
+/// * `package_cache` - Reuse the output for packages from an on-disk cache, and add to it.
+///   Passing from cli args `--package-cache` from `app/buck2_client/src/commands/targets.rs`.
 pub(crate) async fn targets_streaming(
     server_ctx: &dyn ServerCommandContextTrait,
     dice: DiceTransaction,
@@ -106,6 +111,7 @@ pub(crate) async fn targets_streaming(
     imports: bool,
     fast_hash: Option<bool>, // None = no hashing
     threads: Option<usize>,
+    package_cache: Option<Arc<PackageCache>>,
 ) -> buck2_error::Result<Stats> {
     let imported = Arc::new(Mutex::new(SmallSet::new()));
     let concurrency = package_concurrency(threads)?;
@@ -117,6 +123,7 @@ pub(crate) async fn targets_streaming(
             let formatter = formatter.dupe();
             let imported = imported.dupe();
             let threads = threads.dupe();
+            let package_cache = package_cache.dupe();
             let ctx = cloned_dice.dupe();
 
             spawn_dropcancel(
@@ -124,7 +131,7 @@ pub(crate) async fn targets_streaming(
                     {
                         async move {
                             let (package, spec) = x?;
-                            let res = process_package(
+                            process_package(
                                 &mut ctx.ctx(),
                                 cancellation,
                                 formatter,
@@ -136,9 +143,9 @@ pub(crate) async fn targets_streaming(
                                 fast_hash,
                                 threads,
                                 imported,
+                                package_cache,
                             )
-                            .await;
-                            buck2_error::Ok(res)
+                            .await
                         }
                     }
                     .boxed()
@@ -329,13 +336,32 @@ async fn process_package(
     fast_hash: Option<bool>,
     threads: Arc<Semaphore>,
     imported: Arc<Mutex<SmallSet<ImportPath>>>,
-) -> PreparePackageResult {
+    package_cache: Option<Arc<PackageCache>>,
+) -> buck2_error::Result<PreparePackageResult> {
     let mut result = PreparePackageResult::from_package(package.dupe());
-    let targets = {
-        // This bit of code is the heavy CPU stuff, so guard it with the threads
-        let _permit = threads.acquire().await.unwrap();
-        load_targets(ctx, cancellation, package.dupe(), spec, cached, keep_going).await
+    // This bit of code is the heavy CPU stuff, so guard it with the threads
+    let permit = threads.acquire().await.unwrap();
+    let cache_key = match &package_cache {
+        Some(package_cache) => Some(package_cache.key(ctx, package.dupe(), &spec).await),
+        None => None,
     };
+    if let (Some(package_cache), Some(Ok(cache_key))) = (&package_cache, &cache_key) {
+        // The cache is only an optimization, so treat failures to read it as a miss.
+        match package_cache.get(cache_key) {
+            Ok(Some(cached)) => {
+                result.stats.success += 1;
+                result.stats.targets += cached.targets;
+                result.stdout = cached.stdout;
+                return Ok(result);
+            }
+            Ok(None) => {}
+            Err(e) => console_warning(format!(
+                "Ignoring package cache entry for `{package}`: {e:#}"
+            )),
+        }
+    }
+    let targets = load_targets(ctx, cancellation, package.dupe(), spec, cached, keep_going).await;
+    drop(permit);
 
     match targets {
         Ok((eval_result, targets, err)) => {
@@ -354,7 +380,34 @@ async fn process_package(
         }
     }
 
-    result
+    // Only cache packages without errors, so that errors are always reported in full.
+    if let Some(package_cache) = &package_cache
+        && result.stderr.is_none()
+    {
+        match &cache_key {
+            Some(Ok(cache_key)) => {
+                if let Err(e) = package_cache.put(
+                    cache_key,
+                    &CachedPackage {
+                        targets: result.stats.targets,
+                        stdout: result.stdout.clone(),
+                    },
+                ) {
+                    console_warning(format!(
+                        "Failed to write package cache entry for `{package}`: {e:#}"
+                    ));
+                }
+            }
+            // If the package failed to load, the error it reported most likely explains why the
+            // key could not be computed too, so only warn for packages which loaded fine.
+            Some(Err(e)) => console_warning(format!(
+                "Not caching `{package}`, failed to compute its package cache key: {e:#}"
+            )),
+            None => {}
+        }
+    }
+
+    Ok(result)
 }
 
 /// Given the patterns, separate into those which have an explicit package, and those which are recursive
@@ -8,6 +8,7 @@
  * above-listed licenses.
  */
 
+use std::collections::BTreeMap;
 use std::str::FromStr;
 use std::time::Duration;
 
@@ -264,6 +265,19 @@ pub struct ResourceControlConfig {
     pub enable_suspension: bool,
     pub experimental_suspension_algo_variant: Option<u8>,
     pub preferred_action_suspend_strategy: ActionSuspendStrategy,
+    /// Which running actions to suspend first when memory pressure is high.
+    ///
+    /// The corresponding buckconfig is `buck2_resource_control.suspend_policy`.
+    #[serde(default)]
+    pub suspend_policy: ActionSuspendPolicy,
+    /// Memory budgets for the local actions of a category, e.g. `cxx_link=8000000000`.
+    ///
+    /// Actions of a category whose running actions are over budget are not started, and are the
+    /// first to be suspended under memory pressure.
+    ///
+    /// The corresponding buckconfig is `buck2_resource_control.category_memory_budgets`.
+    #[serde(default)]
+    pub category_memory_budgets: CategoryMemoryBudgets,
 }
 
 impl ResourceControlConfig {
@@ -293,6 +307,83 @@ impl FromStr for ActionSuspendStrategy {
     }
 }
 
+#[derive(
+    Allocative,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq
+)]
+pub enum ActionSuspendPolicy {
+    /// Suspend the most recently started action, using `preferred_action_suspend_strategy`.
+    #[default]
+    SuspendYoungest,
+    /// Like `SuspendYoungest`, but always kill and retry rather than freeze, for actions that allow
+    /// it.
+    KillAndRetryYoungest,
+    /// Suspend the action using the most memory.
+    SuspendLargestRss,
+}
+
+impl FromStr for ActionSuspendPolicy {
+    type Err = buck2_error::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "suspend_youngest" => Ok(Self::SuspendYoungest),
+            "kill_and_retry_youngest" => Ok(Self::KillAndRetryYoungest),
+            "suspend_largest_rss" => Ok(Self::SuspendLargestRss),
+            _ => Err(buck2_error::buck2_error!(
+                buck2_error::ErrorTag::Input,
+                "Invalid suspend policy: `{}`",
+                s
+            )),
+        }
+    }
+}
+
+/// Memory budgets in bytes, keyed by action category.
+#[derive(
+    Allocative,
+    Clone,
+    Debug,
+    Default,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq
+)]
+pub struct CategoryMemoryBudgets(pub BTreeMap<String, u64>);
+
+impl FromStr for CategoryMemoryBudgets {
+    type Err = buck2_error::Error;
+    /// Parses a comma-separated list of `category=bytes`. Budgets must be non-zero.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut budgets = BTreeMap::new();
+        for entry in s.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let budget = entry.split_once('=').and_then(|(category, bytes)| {
+                Some((category.trim(), bytes.trim().parse::<u64>().ok()?))
+            });
+            match budget {
+                Some((category, bytes)) if !category.is_empty() && bytes != 0 => {
+                    budgets.insert(category.to_owned(), bytes);
+                }
+                _ => {
+                    return Err(buck2_error::buck2_error!(
+                        buck2_error::ErrorTag::Input,
+                        "Invalid category memory budget: `{}`, expected `category=bytes` with non-zero bytes",
+                        entry
+                    ));
+                }
+            }
+        }
+        Ok(Self(budgets))
+    }
+}
+
 #[derive(
     Allocative,
     Clone,
@@ -459,6 +550,18 @@ impl ResourceControlConfig {
                     property: "preferred_action_suspend_strategy",
                 })?
                 .unwrap_or(ActionSuspendStrategy::KillAndRetry);
+            let suspend_policy = config
+                .parse(BuckconfigKeyRef {
+                    section: "buck2_resource_control",
+                    property: "suspend_policy",
+                })?
+                .unwrap_or_default();
+            let category_memory_budgets = config
+                .parse(BuckconfigKeyRef {
+                    section: "buck2_resource_control",
+                    property: "category_memory_budgets",
+                })?
+                .unwrap_or_default();
             Ok(Self {
                 status,
                 init,
@@ -472,6 +575,8 @@ impl ResourceControlConfig {
                 enable_suspension,
                 experimental_suspension_algo_variant,
                 preferred_action_suspend_strategy,
+                suspend_policy,
+                category_memory_budgets,
             })
         }
     }
@@ -528,8 +633,8 @@ impl HealthCheckConfig {
 }
 
 /// Pagable DICE storage settings, present (`Some`) only when paging is enabled —
-/// either `buck2_hydration.enable_paging` or `buck2_hydration.page_out_on_idle`
-/// (which implies it). When present, the daemon sets up on-disk storage during
+/// either `buck2_hydration.enable_paging`, or `buck2_hydration.page_out_on_idle` or
+/// `buck2_hydration.persist_across_restarts` (which imply it). When present, the daemon sets up on-disk storage during
 /// construction so `buck2 debug hydration` can page node values out to / in from
 /// disk. Read at startup because it gates that setup.
 #[derive(Allocative, Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
@@ -543,11 +648,21 @@ pub struct HydrationConfig {
     pub page_out_min_free_disk_gb: u64,
     /// Allow automatic idle page-out to run more than once per daemon.
     pub allow_multiple_idle_page_outs: bool,
+    /// Snapshot the DICE graph into pagable storage on shutdown and restore it on the next
+    /// start, when the file watcher can report what changed in between.
+    pub persist_across_restarts: bool,
+    /// Key types whose values (and everything they depend on) are persisted across restarts.
+    pub persist_key_types: Vec<String>,
 }
 
+/// Key types persisted across restarts when `buck2_hydration.persist_key_types` is unset: the
+/// interpreter and analysis results that dominate the cost of a cold daemon.
+const DEFAULT_PERSIST_KEY_TYPES: &[&str] =
+    &["InterpreterResultsKey", "EvalImportKey", "AnalysisKey"];
+
 impl HydrationConfig {
-    /// Returns `None` when neither `buck2_hydration.enable_paging` nor
-    /// `page_out_on_idle` is set.
+    /// Returns `None` when none of `buck2_hydration.enable_paging`, `page_out_on_idle` or
+    /// `persist_across_restarts` is set.
     fn from_config(config: &LegacyBuckConfig) -> buck2_error::Result<Option<Self>> {
         let page_out_on_idle = config
             .parse(BuckconfigKeyRef {
@@ -555,8 +670,16 @@ impl HydrationConfig {
                 property: "page_out_on_idle",
             })?
             .unwrap_or(false);
-        // `page_out_on_idle` implies pagable storage, so it enables it too.
+        let persist_across_restarts = config
+            .parse(BuckconfigKeyRef {
+                section: "buck2_hydration",
+                property: "persist_across_restarts",
+            })?
+            .unwrap_or(false);
+        // `page_out_on_idle` and `persist_across_restarts` imply pagable storage, so they
+        // enable it too.
         let enabled = page_out_on_idle
+            || persist_across_restarts
             || config
                 .parse(BuckconfigKeyRef {
                     section: "buck2_hydration",
@@ -586,6 +709,18 @@ impl HydrationConfig {
                     property: "allow_multiple_idle_page_outs",
                 })?
                 .unwrap_or(false),
+            persist_across_restarts,
+            persist_key_types: config
+                .parse_list(BuckconfigKeyRef {
+                    section: "buck2_hydration",
+                    property: "persist_key_types",
+                })?
+                .unwrap_or_else(|| {
+                    DEFAULT_PERSIST_KEY_TYPES
+                        .iter()
+                        .map(|s| (*s).to_owned())
+                        .collect()
+                }),
         }))
     }
 }
@@ -616,6 +751,8 @@ pub struct DaemonStartupConfig {
     pub retained_event_logs: usize,
     pub macos_qos_class: Option<String>,
     pub daemon_idle_timeout_s: Option<u64>,
+    /// Port on localhost where the daemon serves its metrics in the OpenMetrics format.
+    pub metrics_port: Option<u16>,
     /// Pagable DICE storage settings, or `None` when paging is disabled.
     pub hydration: Option<HydrationConfig>,
 }
@@ -741,6 +878,10 @@ impl DaemonStartupConfig {
                 section: "buck2",
                 property: "daemon_idle_timeout_s",
             })?,
+            metrics_port: config.parse(BuckconfigKeyRef {
+                section: "buck2",
+                property: "metrics_port",
+            })?,
             hydration: HydrationConfig::from_config(config)?,
         })
     }
@@ -774,6 +915,7 @@ impl DaemonStartupConfig {
             retained_event_logs: DEFAULT_RETAINED_EVENT_LOGS,
             macos_qos_class: None,
             daemon_idle_timeout_s: None,
+            metrics_port: None,
             hydration: None,
         }
     }
@@ -812,4 +954,61 @@ mod tests {
         assert_eq!(startup_config.daemon_idle_timeout_s, Some(10800));
         Ok(())
     }
+
+    #[test]
+    fn test_metrics_port_configured() -> buck2_error::Result<()> {
+        let config = parse(
+            &[(
+                "config",
+                indoc!(
+                    r#"
+                    [buck2]
+                    metrics_port = 9464
+                    "#
+                ),
+            )],
+            "config",
+        )?;
+        let startup_config = DaemonStartupConfig::new(&config, &BuckSettings::empty(), false)?;
+        assert_eq!(startup_config.metrics_port, Some(9464));
+        Ok(())
+    }
+
+    #[test]
+    fn test_resource_control_suspend_policy_configured() -> buck2_error::Result<()> {
+        let config = parse(
+            &[(
+                "config",
+                indoc!(
+                    r#"
+                    [buck2_resource_control]
+                    suspend_policy = suspend_largest_rss
+                    category_memory_budgets = cxx_link=8000, rust_compile = 4000
+                    "#
+                ),
+            )],
+            "config",
+        )?;
+        let resource_control = ResourceControlConfig::from_config(&config)?;
+        assert_eq!(
+            resource_control.suspend_policy,
+            ActionSuspendPolicy::SuspendLargestRss
+        );
+        assert_eq!(
+            resource_control.category_memory_budgets.0,
+            BTreeMap::from([
+                ("cxx_link".to_owned(), 8000),
+                ("rust_compile".to_owned(), 4000)
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_category_memory_budgets_invalid() {
+        assert!("cxx_link".parse::<CategoryMemoryBudgets>().is_err());
+        assert!("cxx_link=8GB".parse::<CategoryMemoryBudgets>().is_err());
+        assert!("=8000".parse::<CategoryMemoryBudgets>().is_err());
+        assert!("cxx_link=0".parse::<CategoryMemoryBudgets>().is_err());
+    }
 }
@@ -176,7 +176,9 @@ impl InvocationPaths {
     /// Deliberately absent from [`Self::valid_cache_dirs`], so it is wiped on
     /// daemon startup: the `DiceKey`->`DataKey` mapping needed to read it back
     /// lives only in the in-memory DICE graph, so a paged-out DB cannot outlive
-    /// the daemon that wrote it.
+    /// the daemon that wrote it. The exception is
+    /// `buck2_hydration.persist_across_restarts`, where the daemon writes that
+    /// mapping out as a snapshot on shutdown and keeps the directory.
     pub fn dice_state_path(&self) -> AbsNormPathBuf {
         self.cache_dir_path().join(self.dice_state_dir_name())
     }
@@ -201,7 +203,7 @@ impl InvocationPaths {
         FileName::unchecked_new("dep_file_state")
     }
 
-    fn dice_state_dir_name(&self) -> &FileName {
+    pub fn dice_state_dir_name(&self) -> &FileName {
         FileName::unchecked_new("dice_state")
     }
 
@@ -18,9 +18,11 @@ use buck2_core::cells::CellResolver;
 use buck2_core::cells::alias::NonEmptyCellAlias;
 use buck2_core::cells::cell_root_path::CellRootPath;
 use buck2_core::cells::cell_root_path::CellRootPathBuf;
+use buck2_core::cells::external::ArchiveType;
 use buck2_core::cells::external::ExternalCellOrigin;
 use buck2_core::cells::external::GitCellSetup;
 use buck2_core::cells::external::GitObjectFormat;
+use buck2_core::cells::external::HttpArchiveCellSetup;
 use buck2_core::cells::name::CellName;
 use buck2_core::fs::project::ProjectRoot;
 use buck2_core::fs::project_rel_path::ProjectRelativePath;
@@ -499,6 +501,12 @@ impl BuckConfigBasedCells {
             Unknown(String),
             #[error("Missing buckconfig `{0}.{1}` for external cell configuration")]
             MissingConfiguration(String, String),
+            #[error(
+                "Cannot infer archive type from url `{0}`, set `{1}.type` to one of `tar`, `tar.gz`, `tar.zst` or `zip`"
+            )]
+            UnknownArchiveType(String, String),
+            #[error("Invalid `{0}.sha256` `{1}`, expected 64 hex characters")]
+            InvalidSha256(String, String),
         }
 
         let get_config = |section: &str, property: &str| {
@@ -535,6 +543,49 @@ impl BuckConfigBasedCells {
                 commit: Arc::from(commit),
                 object_format,
             }))
+        } else if value == "http_archive" {
+            let section = &format!("external_cell_{}", cell.as_str());
+            let urls: Arc<[Arc<str>]> = get_config(section, "urls")?
+                .split(',')
+                .map(str::trim)
+                .filter(|url| !url.is_empty())
+                .map(Arc::from)
+                .collect();
+            let Some(first_url) = urls.first() else {
+                return Err(ExternalCellOriginParseError::MissingConfiguration(
+                    section.to_owned(),
+                    "urls".to_owned(),
+                )
+                .into());
+            };
+            let sha256 = get_config(section, "sha256")?.to_ascii_lowercase();
+            if sha256.len() != 64 || !sha256.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(ExternalCellOriginParseError::InvalidSha256(
+                    section.to_owned(),
+                    sha256,
+                )
+                .into());
+            }
+            let archive_type = match get_config(section, "type") {
+                Ok(s) => ArchiveType::from_str(s)?,
+                Err(_) => ArchiveType::from_url(first_url).ok_or_else(|| {
+                    ExternalCellOriginParseError::UnknownArchiveType(
+                        first_url.to_string(),
+                        section.to_owned(),
+                    )
+                })?,
+            };
+            let strip_prefix = get_config(section, "strip_prefix")
+                .ok()
+                .map(|s| s.trim_matches('/'))
+                .filter(|s| !s.is_empty())
+                .map(Arc::from);
+            Ok(ExternalCellOrigin::HttpArchive(HttpArchiveCellSetup {
+                urls,
+                sha256: Arc::from(sha256),
+                strip_prefix,
+                archive_type,
+            }))
         } else {
             Err(ExternalCellOriginParseError::Unknown(value.to_owned()).into())
         }
@@ -642,8 +693,10 @@ mod tests {
     use buck2_cli_proto::ConfigOverride;
     use buck2_core::cells::cell_root_path::CellRootPath;
     use buck2_core::cells::cell_root_path::CellRootPathBuf;
+    use buck2_core::cells::external::ArchiveType;
     use buck2_core::cells::external::ExternalCellOrigin;
     use buck2_core::cells::external::GitCellSetup;
+    use buck2_core::cells::external::HttpArchiveCellSetup;
     use buck2_core::cells::name::CellName;
     use dice::DiceComputations;
     use indoc::indoc;
@@ -1347,4 +1400,79 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_http_archive_external_cell() -> buck2_error::Result<()> {
+        initialize_external_cells_impl();
+
+        let mut file_ops = TestConfigParserFileOps::new(&[(
+            ".buckconfig",
+            indoc!(
+                r#"
+                    [cells]
+                        root = .
+                        libfoo = foo/
+                    [external_cells]
+                        libfoo = http_archive
+                    [external_cell_libfoo]
+                        urls = https://example.com/libfoo-1.0.tar.gz, https://mirror.example.com/libfoo-1.0.tar.gz
+                        sha256 = AAAAAAAABBBBBBBBCCCCCCCCDDDDDDDDEEEEEEEEFFFFFFFF0000000011111111
+                        strip_prefix = libfoo-1.0/
+                "#
+            ),
+        )])?;
+
+        let resolver = BuckConfigBasedCells::testing_parse_with_file_ops(&mut file_ops, &[])
+            .await?
+            .cell_resolver;
+
+        let instance = resolver.get(CellName::testing_new("libfoo")).unwrap();
+
+        assert_eq!(
+            instance.external(),
+            Some(&ExternalCellOrigin::HttpArchive(HttpArchiveCellSetup {
+                urls: vec![
+                    "https://example.com/libfoo-1.0.tar.gz".into(),
+                    "https://mirror.example.com/libfoo-1.0.tar.gz".into(),
+                ]
+                .into(),
+                sha256: "aaaaaaaabbbbbbbbccccccccddddddddeeeeeeeeffffffff0000000011111111".into(),
+                strip_prefix: Some("libfoo-1.0".into()),
+                archive_type: ArchiveType::TarGz,
+            })),
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_http_archive_external_cell_unknown_type() -> buck2_error::Result<()> {
+        initialize_external_cells_impl();
+
+        let mut file_ops = TestConfigParserFileOps::new(&[(
+            ".buckconfig",
+            indoc!(
+                r#"
+                    [cells]
+                        root = .
+                        libfoo = foo/
+                    [external_cells]
+                        libfoo = http_archive
+                    [external_cell_libfoo]
+                        urls = https://example.com/download?id=libfoo
+                        sha256 = aaaaaaaabbbbbbbbccccccccddddddddeeeeeeeeffffffff0000000011111111
+                "#
+            ),
+        )])?;
+
+        let e = BuckConfigBasedCells::testing_parse_with_file_ops(&mut file_ops, &[])
+            .await
+            .err()
+            .unwrap();
+
+        let e = format!("{e:?}");
+        assert!(e.contains("Cannot infer archive type"), "error: {e}");
+
+        Ok(())
+    }
 }
@@ -24,6 +24,7 @@ use crate::cells::name::CellName;
 pub enum ExternalCellOrigin {
     Bundled(CellName),
     Git(GitCellSetup),
+    HttpArchive(HttpArchiveCellSetup),
 }
 
 #[derive(
@@ -45,11 +46,84 @@ pub struct GitCellSetup {
     pub object_format: Option<GitObjectFormat>,
 }
 
+#[derive(
+    Debug,
+    derive_more::Display,
+    Clone,
+    Dupe,
+    allocative::Allocative,
+    PartialEq,
+    Eq,
+    Hash,
+    Pagable
+)]
+#[display("http_archive({})", sha256)]
+pub struct HttpArchiveCellSetup {
+    /// Mirrors to try in order. Guaranteed to be non-empty.
+    pub urls: Arc<[Arc<str>]>,
+    /// Guaranteed to be a valid, lowercase sha256 digest.
+    pub sha256: Arc<str>,
+    /// Leading directory to strip from every path in the archive.
+    pub strip_prefix: Option<Arc<str>>,
+    pub archive_type: ArchiveType,
+}
+
 impl fmt::Display for ExternalCellOrigin {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Bundled(cell) => write!(f, "bundled({cell})"),
             Self::Git(git) => write!(f, "{git}"),
+            Self::HttpArchive(archive) => write!(f, "{archive}"),
+        }
+    }
+}
+
+#[derive(
+    Debug, Display, Eq, PartialEq, Clone, Copy, Dupe, Hash, Allocative, Pagable
+)]
+pub enum ArchiveType {
+    #[display("tar")]
+    Tar,
+    #[display("tar.gz")]
+    TarGz,
+    #[display("tar.zst")]
+    TarZst,
+    #[display("zip")]
+    Zip,
+}
+
+impl FromStr for ArchiveType {
+    type Err = buck2_error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "tar" => Ok(ArchiveType::Tar),
+            "tar.gz" | "tgz" => Ok(ArchiveType::TarGz),
+            "tar.zst" | "tzst" => Ok(ArchiveType::TarZst),
+            "zip" => Ok(ArchiveType::Zip),
+            _ => Err(buck2_error!(
+                buck2_error::ErrorTag::Input,
+                "archive type must be one of `tar`, `tar.gz`, `tar.zst` or `zip` (got: {})",
+                &s,
+            )),
+        }
+    }
+}
+
+impl ArchiveType {
+    /// Guess the archive type from the extension of a URL, ignoring any query string.
+    pub fn from_url(url: &str) -> Option<Self> {
+        let path = url.split(['?', '#']).next().unwrap_or(url);
+        if path.ends_with(".tar.gz") || path.ends_with(".tgz") {
+            Some(ArchiveType::TarGz)
+        } else if path.ends_with(".tar.zst") || path.ends_with(".tzst") {
+            Some(ArchiveType::TarZst)
+        } else if path.ends_with(".tar") {
+            Some(ArchiveType::Tar)
+        } else if path.ends_with(".zip") {
+            Some(ArchiveType::Zip)
+        } else {
+            None
         }
     }
 }
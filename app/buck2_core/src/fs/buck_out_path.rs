@@ -336,6 +336,9 @@ impl BuckOutPathResolver {
             match origin {
                 ExternalCellOrigin::Bundled(_) => ForwardRelativePath::new("bundled").unwrap(),
                 ExternalCellOrigin::Git(_) => ForwardRelativePath::new("git").unwrap(),
+                ExternalCellOrigin::HttpArchive(_) => {
+                    ForwardRelativePath::new("http_archive").unwrap()
+                }
             },
             match &origin {
                 ExternalCellOrigin::Bundled(cell) => {
@@ -344,6 +347,9 @@ impl BuckOutPathResolver {
                 ExternalCellOrigin::Git(setup) => {
                     ForwardRelativePath::new(setup.commit.as_ref()).unwrap()
                 }
+                ExternalCellOrigin::HttpArchive(setup) => {
+                    ForwardRelativePath::new(setup.sha256.as_ref()).unwrap()
+                }
             },
             path.as_ref(),
         ]))
@@ -12,6 +12,7 @@ mod builder;
 mod critical_path_accessor;
 mod graph;
 mod potential;
+mod simulate;
 mod types;
 
 #[cfg(test)]
@@ -24,6 +25,9 @@ pub use graph::Graph;
 pub use graph::GraphVertex;
 pub use graph::TopoSortError;
 pub use potential::compute_critical_path_potentials;
+pub use simulate::SimulateError;
+pub use simulate::SimulatedJob;
+pub use simulate::simulate_schedule;
 pub use types::CriticalPathIndex;
 pub use types::CriticalPathVertexData;
 pub use types::OptionalVertexId;
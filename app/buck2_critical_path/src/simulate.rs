@@ -0,0 +1,207 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is dual-licensed under either the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree or the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree. You may select, at your option, one of the
+ * above-listed licenses.
+ */
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::graph::Graph;
+use crate::types::VertexData;
+use crate::types::VertexId;
+
+/// A job in a schedule replayed by [`simulate_schedule`]. Times are in arbitrary units
+/// (typically nanoseconds), like weights elsewhere in this crate.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SimulatedJob {
+    /// Time between the last dependency of this job finishing and this job becoming ready. Jobs
+    /// without dependencies are ready `delay` after the start of the schedule.
+    pub delay: u64,
+    /// Time this job runs for once it has started.
+    pub duration: u64,
+    /// Whether this job needs one of the local slots to run. Other jobs start as soon as
+    /// they are ready.
+    pub local: bool,
+}
+
+#[derive(buck2_error::Error, Debug)]
+#[buck2(tier0)]
+pub enum SimulateError {
+    #[error("{0} jobs never became ready, the graph has a cycle")]
+    Cycle(usize),
+
+    #[error("local jobs cannot run with zero local slots")]
+    NoLocalSlots,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Event {
+    // Finishes are ordered first, so that slots freed at some time are reused by jobs becoming
+    // ready at that same time.
+    Finish,
+    Ready,
+}
+
+/// Replay the jobs of a dependency graph and return the makespan, i.e. when the last job
+/// finishes. `deps` has an edge from each job to each of the jobs it depends on, like the graphs
+/// passed to [`compute_critical_path_potentials`](crate::compute_critical_path_potentials).
+///
+/// Local jobs wait for one of `local_slots` slots, which are handed out in the order jobs became
+/// ready.
+pub fn simulate_schedule(
+    deps: &Graph,
+    jobs: &VertexData<SimulatedJob>,
+    local_slots: usize,
+) -> Result<u64, SimulateError> {
+    if local_slots == 0 && jobs.values().any(|job| job.local) {
+        return Err(SimulateError::NoLocalSlots);
+    }
+
+    let rdeps = deps.reversed();
+    let mut pending_deps = deps.allocate_vertex_data(0usize);
+    let mut deps_finished = deps.allocate_vertex_data(0u64);
+    let mut events = BinaryHeap::new();
+    for job in deps.iter_vertices() {
+        pending_deps[job] = deps.iter_edges(job).count();
+        if pending_deps[job] == 0 {
+            events.push(Reverse((jobs[job].delay, Event::Ready, job)));
+        }
+    }
+
+    let mut free_slots = local_slots;
+    let mut waiting: BinaryHeap<Reverse<(u64, VertexId)>> = BinaryHeap::new();
+    let mut finished = 0;
+    let mut makespan = 0;
+
+    while let Some(Reverse((now, event, job))) = events.pop() {
+        let data = &jobs[job];
+        match event {
+            Event::Finish => {
+                finished += 1;
+                makespan = makespan.max(now);
+                if data.local {
+                    free_slots += 1;
+                }
+                // Edges are not deduplicated, so a dependent may show up more than once here,
+                // exactly as many times as it was counted in `pending_deps`.
+                for dependent in rdeps.iter_edges(job) {
+                    deps_finished[dependent] = deps_finished[dependent].max(now);
+                    pending_deps[dependent] -= 1;
+                    if pending_deps[dependent] == 0 {
+                        events.push(Reverse((
+                            deps_finished[dependent] + jobs[dependent].delay,
+                            Event::Ready,
+                            dependent,
+                        )));
+                    }
+                }
+            }
+            Event::Ready => {
+                if data.local {
+                    waiting.push(Reverse((now, job)));
+                } else {
+                    events.push(Reverse((now + data.duration, Event::Finish, job)));
+                }
+            }
+        }
+
+        while free_slots > 0
+            && let Some(Reverse((_, job))) = waiting.pop()
+        {
+            free_slots -= 1;
+            events.push(Reverse((now + jobs[job].duration, Event::Finish, job)));
+        }
+    }
+
+    if finished != jobs.len() {
+        return Err(SimulateError::Cycle(jobs.len() - finished));
+    }
+
+    Ok(makespan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::GraphBuilder;
+
+    fn job(delay: u64, duration: u64, local: bool) -> SimulatedJob {
+        SimulatedJob {
+            delay,
+            duration,
+            local,
+        }
+    }
+
+    /// Build a graph from jobs and the indices of the jobs they depend on, which must come
+    /// first.
+    fn graph(jobs: Vec<(Vec<u32>, SimulatedJob)>) -> (Graph, VertexData<SimulatedJob>) {
+        let mut builder = GraphBuilder::new();
+        for (index, (deps, job)) in jobs.into_iter().enumerate() {
+            builder.push(index as u32, deps, job).unwrap();
+        }
+        let (graph, _keys, jobs) = builder.finish();
+        (graph, jobs)
+    }
+
+    #[test]
+    fn test_chain() {
+        let (deps, jobs) = graph(vec![
+            (vec![], job(1, 10, false)),
+            (vec![0], job(2, 20, false)),
+            (vec![1], job(0, 5, false)),
+        ]);
+        assert_eq!(38, simulate_schedule(&deps, &jobs, 0).unwrap());
+    }
+
+    #[test]
+    fn test_waits_for_all_deps() {
+        let (deps, jobs) = graph(vec![
+            (vec![], job(0, 10, false)),
+            (vec![], job(0, 30, false)),
+            (vec![0, 1], job(1, 5, false)),
+            // Only depends on the short job.
+            (vec![0], job(0, 5, false)),
+        ]);
+        assert_eq!(36, simulate_schedule(&deps, &jobs, 0).unwrap());
+    }
+
+    #[test]
+    fn test_local_slots() {
+        let (deps, jobs) = graph(vec![
+            (vec![], job(0, 10, true)),
+            (vec![], job(0, 10, true)),
+            (vec![], job(0, 10, true)),
+            (vec![], job(0, 10, false)),
+        ]);
+        assert_eq!(30, simulate_schedule(&deps, &jobs, 1).unwrap());
+        assert_eq!(20, simulate_schedule(&deps, &jobs, 2).unwrap());
+        assert_eq!(10, simulate_schedule(&deps, &jobs, 3).unwrap());
+    }
+
+    #[test]
+    fn test_slots_are_handed_out_in_ready_order() {
+        let (deps, jobs) = graph(vec![
+            (vec![], job(0, 10, true)),
+            // Ready first, runs first when the slot frees up.
+            (vec![], job(1, 100, true)),
+            (vec![], job(2, 1, true)),
+            (vec![2], job(0, 1, false)),
+        ]);
+        assert_eq!(112, simulate_schedule(&deps, &jobs, 1).unwrap());
+    }
+
+    #[test]
+    fn test_no_local_slots() {
+        let (deps, jobs) = graph(vec![(vec![], job(0, 1, true))]);
+        assert!(matches!(
+            simulate_schedule(&deps, &jobs, 0),
+            Err(SimulateError::NoLocalSlots)
+        ));
+    }
+}
@@ -163,6 +163,18 @@ impl fmt::Display for DaemonShutdown {
     }
 }
 
+impl fmt::Display for DiagnosticSeverity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            DiagnosticSeverity::Unknown => "unknown",
+            DiagnosticSeverity::Error => "error",
+            DiagnosticSeverity::Warning => "warning",
+            DiagnosticSeverity::Info => "info",
+            DiagnosticSeverity::Note => "note",
+        })
+    }
+}
+
 pub mod serialize_duration_as_micros {
     use serde::Deserialize;
     use serde::Deserializer;
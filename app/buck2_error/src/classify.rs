@@ -444,6 +444,7 @@ fn tag_metadata(tag: ErrorTag) -> TagMetadata {
         ErrorTag::ActionMissingOutputs => rank!(input),
         ErrorTag::ActionWrongOutputType => rank!(input),
         ErrorTag::ActionCommandFailure => rank!(input),
+        ErrorTag::ActionDryRunWouldExecute => rank!(input),
         ErrorTag::ProjectMissingPath => rank!(input),
         ErrorTag::ArtifactMissingFilename => rank!(input),
         ErrorTag::MissingInputPath => rank!(input),
@@ -19,6 +19,8 @@ use buck2_wrapper_common::invocation_id::TraceId;
 use futures::StreamExt;
 use gazebo::prelude::VecExt;
 
+use crate::index::index_path;
+use crate::index::is_index_path;
 use crate::read::EventLogPathBuf;
 use crate::utils::Encoding;
 use crate::utils::EventLogErrors;
@@ -48,9 +50,16 @@ pub(crate) async fn remove_old_logs(logdir: &AbsNormPath, retained_event_logs: u
         // `retained_event_logs` counts the log about to be written, so one fewer
         // existing log is kept. Saturate rather than wrap: zero means retain none.
         let retained_existing_logs = retained_event_logs.saturating_sub(1);
-        futures::stream::iter(logfiles.into_iter().rev().skip(retained_existing_logs))
+        // Indexes don't count as logs, and are removed along with their logs.
+        let logfiles = logfiles
+            .into_iter()
+            .filter(|file| !is_index_path(file.as_abs_path()));
+        futures::stream::iter(logfiles.rev().skip(retained_existing_logs))
             .then(|file| async move {
                 // The oldest logs might be open from another concurrent build, so suppress error.
+                tokio::fs::remove_file(index_path(file.as_abs_path()))
+                    .await
+                    .ok();
                 tokio::fs::remove_file(file).await.ok()
             })
             .collect::<Vec<_>>()
@@ -0,0 +1,474 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is dual-licensed under either the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree or the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree. You may select, at your option, one of the
+ * above-listed licenses.
+ */
+
+//! Sidecar index for event logs, so reading a few events from a large log doesn't require
+//! decompressing all of it.
+//!
+//! An indexed log is a sequence of independently compressed blocks (concatenated gzip members
+//! or zstd frames, which regular readers decode as a single stream). The index is a JSON lines
+//! file next to the log: a header, then one line per block with the position of the block in
+//! the log and a summary of the events in it.
+
+use std::collections::BTreeSet;
+use std::str::FromStr;
+use std::time::Duration;
+
+use buck2_error::BuckErrorContext;
+use buck2_fs::paths::abs_path::AbsPath;
+use buck2_fs::paths::abs_path::AbsPathBuf;
+use gazebo::variants::VariantName;
+use serde::Deserialize;
+use serde::Serialize;
+use tokio::io::AsyncWriteExt;
+
+use crate::utils::timestamp;
+
+const INDEX_VERSION: u32 = 1;
+
+/// Suffix appended to the path of a log to get the path of its index.
+pub const INDEX_EXTENSION: &str = ".index";
+
+/// Uncompressed size after which a block is closed. The last block is closed when the log is
+/// shut down.
+pub(crate) const BLOCK_SIZE: usize = 1 << 20;
+
+/// Age after which a non-empty block is closed when the log is flushed, so that readers of a log
+/// being written, and readers after the writer is killed, don't miss more than this many seconds
+/// of events.
+pub(crate) const BLOCK_MAX_AGE: Duration = Duration::from_secs(1);
+
+#[derive(Debug, buck2_error::Error)]
+#[buck2(tag = Input)]
+enum EventLogFilterError {
+    #[error(
+        "Invalid event log filter `{0}`: expected comma-separated `kind=`, `target=`, `span=`, \
+        `after=` or `before=` entries"
+    )]
+    InvalidFilter(String),
+    #[error("Invalid span id `{0}` in event log filter")]
+    InvalidSpan(String),
+}
+
+pub fn index_path(log: &AbsPath) -> AbsPathBuf {
+    let mut path = log.as_os_str().to_owned();
+    path.push(INDEX_EXTENSION);
+    AbsPathBuf::new(path).expect("Appending to an absolute path keeps it absolute")
+}
+
+/// Whether `path` is the index of another file, rather than an event log.
+pub(crate) fn is_index_path(path: &AbsPath) -> bool {
+    path.as_os_str()
+        .to_str()
+        .is_some_and(|path| path.ends_with(INDEX_EXTENSION))
+}
+
+#[derive(Serialize, Deserialize)]
+struct IndexHeader {
+    version: u32,
+}
+
+/// A block of the log, and a summary of the events in it.
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq)]
+pub(crate) struct IndexedBlock {
+    /// Position of the compressed block in the log.
+    pub(crate) offset: u64,
+    /// Size of the compressed block.
+    pub(crate) length: u64,
+    /// Whether the block starts with the invocation header.
+    pub(crate) header: bool,
+    /// Time range of the events in the block, in milliseconds since the Unix epoch.
+    first_event_ms: Option<i64>,
+    last_event_ms: Option<i64>,
+    kinds: BTreeSet<String>,
+    targets: BTreeSet<String>,
+    /// Spans started or ended in this block, and spans of the events in it.
+    spans: BTreeSet<u64>,
+}
+
+impl IndexedBlock {
+    fn record(&mut self, event: &buck2_data::BuckEvent) {
+        if let Some(time) = event_time_ms(event) {
+            self.first_event_ms = Some(self.first_event_ms.map_or(time, |t| t.min(time)));
+            self.last_event_ms = Some(self.last_event_ms.map_or(time, |t| t.max(time)));
+        }
+        if let Some(kind) = event_kind(event) {
+            if !self.kinds.contains(kind) {
+                self.kinds.insert(kind.to_owned());
+            }
+        }
+        if let Some(target) = event_target(event) {
+            self.targets.insert(target);
+        }
+        self.spans.extend(
+            [event.span_id, event.parent_id]
+                .into_iter()
+                .filter(|id| *id != 0),
+        );
+    }
+}
+
+/// Summarizes the events of the block being written, and appends it to the index once the block
+/// is written.
+pub(crate) struct IndexWriter {
+    path: AbsPathBuf,
+    file: tokio::fs::File,
+    block: IndexedBlock,
+    /// Position of the next block in the log.
+    offset: u64,
+    /// Serialization buffer.
+    buf: Vec<u8>,
+}
+
+impl IndexWriter {
+    /// Creates the index of a log whose blocks are written from `offset` on, starting with the
+    /// invocation header.
+    pub(crate) async fn create(log: &AbsPath, offset: u64) -> buck2_error::Result<Self> {
+        let path = index_path(log);
+        let file = tokio::fs::File::create(&path)
+            .await
+            .with_buck_error_context(|| format!("Failed to create event log index at `{path}`"))?;
+        let mut writer = Self {
+            path,
+            file,
+            block: IndexedBlock {
+                header: true,
+                ..IndexedBlock::default()
+            },
+            offset,
+            buf: Vec::new(),
+        };
+        writer
+            .write_line(&IndexHeader {
+                version: INDEX_VERSION,
+            })
+            .await?;
+        Ok(writer)
+    }
+
+    pub(crate) fn record(&mut self, event: &buck2_data::BuckEvent) {
+        self.block.record(event);
+    }
+
+    /// Indexes the block written since the previous one.
+    pub(crate) async fn finish_block(&mut self, length: u64) -> buck2_error::Result<()> {
+        let block = IndexedBlock {
+            offset: self.offset,
+            length,
+            ..std::mem::take(&mut self.block)
+        };
+        self.offset += length;
+        self.write_line(&block).await
+    }
+
+    pub(crate) async fn flush(&mut self) -> buck2_error::Result<()> {
+        self.file.flush().await.with_buck_error_context(|| {
+            format!("Failed to flush event log index at `{}`", self.path)
+        })
+    }
+
+    pub(crate) async fn shutdown(&mut self) {
+        if let Err(e) = self.file.shutdown().await {
+            tracing::warn!(
+                "Failed to flush event log index at `{}`: {:#}",
+                self.path,
+                e
+            );
+        }
+    }
+
+    async fn write_line(&mut self, value: &impl Serialize) -> buck2_error::Result<()> {
+        self.buf.clear();
+        serde_json::to_writer(&mut self.buf, value)
+            .buck_error_context("Failed to serialize event log index")?;
+        self.buf.push(b'\n');
+        self.file
+            .write_all(&self.buf)
+            .await
+            .with_buck_error_context(|| {
+                format!("Failed to write event log index at `{}`", self.path)
+            })?;
+        Ok(())
+    }
+}
+
+/// Reads the index of `log`. Returns `None` if there is no usable index.
+pub(crate) async fn read_index(log: &AbsPath) -> buck2_error::Result<Option<Vec<IndexedBlock>>> {
+    let path = index_path(log);
+    let contents = match tokio::fs::read_to_string(&path).await {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => {
+            return Err(buck2_error::Error::from(e)
+                .context(format!("Failed to read event log index at `{path}`")));
+        }
+    };
+    let mut lines = contents.lines();
+    let header = lines
+        .next()
+        .and_then(|line| serde_json::from_str::<IndexHeader>(line).ok());
+    if header.is_none_or(|header| header.version != INDEX_VERSION) {
+        tracing::info!("Ignoring event log index at `{}` of unknown version", path);
+        return Ok(None);
+    }
+    let mut blocks = Vec::new();
+    for line in lines {
+        // The last line may be incomplete if the index is still being written.
+        match serde_json::from_str(line) {
+            Ok(block) => blocks.push(block),
+            Err(_) => break,
+        }
+    }
+    Ok(Some(blocks))
+}
+
+/// Selects events of an event log, e.g. `kind=ActionExecution,target=root//foo:bar`.
+///
+/// An event matches if it matches any of the values given for each key.
+#[derive(Clone, Debug, Default)]
+pub struct EventLogFilter {
+    /// Event data types, like `ActionExecution` or `TestResult`.
+    kinds: Vec<String>,
+    /// Unconfigured target labels, like `root//foo:bar`.
+    targets: Vec<String>,
+    /// Matches the start and end of a span, and the events directly in it.
+    spans: Vec<u64>,
+    /// Time range of the events, in milliseconds since the Unix epoch.
+    after_ms: Option<i64>,
+    before_ms: Option<i64>,
+}
+
+impl FromStr for EventLogFilter {
+    type Err = buck2_error::Error;
+
+    fn from_str(filter: &str) -> buck2_error::Result<Self> {
+        let mut result = Self::default();
+        for entry in filter.split(',') {
+            let (key, value) = entry
+                .split_once('=')
+                .ok_or_else(|| EventLogFilterError::InvalidFilter(filter.to_owned()))?;
+            match key {
+                "kind" => result.kinds.push(value.to_owned()),
+                "target" => result.targets.push(value.to_owned()),
+                "span" => result.spans.push(
+                    value
+                        .parse()
+                        .map_err(|_| EventLogFilterError::InvalidSpan(value.to_owned()))?,
+                ),
+                "after" => {
+                    result.after_ms = Some(timestamp::parse(value)?.as_millisecond());
+                }
+                "before" => {
+                    result.before_ms = Some(timestamp::parse(value)?.as_millisecond());
+                }
+                _ => return Err(EventLogFilterError::InvalidFilter(filter.to_owned()).into()),
+            }
+        }
+        Ok(result)
+    }
+}
+
+impl EventLogFilter {
+    pub fn matches(&self, event: &buck2_data::BuckEvent) -> bool {
+        let time = event_time_ms(event);
+        (self.kinds.is_empty()
+            || event_kind(event).is_some_and(|kind| self.kinds.iter().any(|k| k == kind)))
+            && (self.targets.is_empty()
+                || event_target(event).is_some_and(|target| self.targets.contains(&target)))
+            && (self.spans.is_empty()
+                || self
+                    .spans
+                    .iter()
+                    .any(|span| *span == event.span_id || *span == event.parent_id))
+            && self
+                .after_ms
+                .is_none_or(|after| time.is_some_and(|time| time >= after))
+            && self
+                .before_ms
+                .is_none_or(|before| time.is_some_and(|time| time <= before))
+    }
+
+    /// Whether the block may contain events matching the filter.
+    pub(crate) fn may_match(&self, block: &IndexedBlock) -> bool {
+        (self.kinds.is_empty() || self.kinds.iter().any(|kind| block.kinds.contains(kind)))
+            && (self.targets.is_empty()
+                || self
+                    .targets
+                    .iter()
+                    .any(|target| block.targets.contains(target)))
+            && (self.spans.is_empty() || self.spans.iter().any(|span| block.spans.contains(span)))
+            && self.after_ms.is_none_or(|after| {
+                block
+                    .last_event_ms
+                    .is_some_and(|last_event| last_event >= after)
+            })
+            && self.before_ms.is_none_or(|before| {
+                block
+                    .first_event_ms
+                    .is_some_and(|first_event| first_event <= before)
+            })
+    }
+}
+
+fn event_time_ms(event: &buck2_data::BuckEvent) -> Option<i64> {
+    let time = event.timestamp.as_ref()?;
+    Some(time.seconds * 1000 + i64::from(time.nanos) / 1_000_000)
+}
+
+fn event_kind(event: &buck2_data::BuckEvent) -> Option<&'static str> {
+    use buck2_data::buck_event::Data;
+
+    match event.data.as_ref()? {
+        Data::SpanStart(start) => start.data.as_ref().map(|data| data.variant_name()),
+        Data::SpanEnd(end) => end.data.as_ref().map(|data| data.variant_name()),
+        Data::Instant(instant) => instant.data.as_ref().map(|data| data.variant_name()),
+        Data::Record(record) => record.data.as_ref().map(|data| data.variant_name()),
+    }
+}
+
+/// The unconfigured label of the target an event is about, if any.
+fn event_target(event: &buck2_data::BuckEvent) -> Option<String> {
+    use buck2_data::buck_event::Data;
+
+    let action_target = |key: Option<&buck2_data::ActionKey>| match key?.owner.as_ref()? {
+        buck2_data::action_key::Owner::TargetLabel(label)
+        | buck2_data::action_key::Owner::TestTargetLabel(label)
+        | buck2_data::action_key::Owner::LocalResourceSetup(label) => Some(label),
+        _ => None,
+    };
+    let label = match event.data.as_ref()? {
+        Data::SpanStart(start) => match start.data.as_ref()? {
+            buck2_data::span_start_event::Data::ActionExecution(action) => {
+                action_target(action.key.as_ref())
+            }
+            buck2_data::span_start_event::Data::Analysis(analysis) => match &analysis.target {
+                Some(buck2_data::analysis_start::Target::StandardTarget(label)) => Some(label),
+                _ => None,
+            },
+            _ => None,
+        },
+        Data::SpanEnd(end) => match end.data.as_ref()? {
+            buck2_data::span_end_event::Data::ActionExecution(action) => {
+                action_target(action.key.as_ref())
+            }
+            buck2_data::span_end_event::Data::Analysis(analysis) => match &analysis.target {
+                Some(buck2_data::analysis_end::Target::StandardTarget(label)) => Some(label),
+                _ => None,
+            },
+            _ => None,
+        },
+        Data::Instant(instant) => match instant.data.as_ref()? {
+            buck2_data::instant_event::Data::TestResult(result) => result.target_label.as_ref(),
+            buck2_data::instant_event::Data::TopLevelTargetBuilt(built) => built.target.as_ref(),
+            _ => None,
+        },
+        Data::Record(_) => None,
+    }?;
+    let label = label.label.as_ref()?;
+    Some(format!("{}:{}", label.package, label.name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn action_end(target: &str, span_id: u64) -> buck2_data::BuckEvent {
+        let (package, name) = target.split_once(':').unwrap();
+        buck2_data::BuckEvent {
+            timestamp: Some(prost_types::Timestamp {
+                seconds: 1_700_000_000,
+                nanos: 500_000_000,
+            }),
+            span_id,
+            data: Some(
+                buck2_data::SpanEndEvent {
+                    data: Some(
+                        buck2_data::ActionExecutionEnd {
+                            key: Some(buck2_data::ActionKey {
+                                owner: Some(buck2_data::action_key::Owner::TargetLabel(
+                                    buck2_data::ConfiguredTargetLabel {
+                                        label: Some(buck2_data::TargetLabel {
+                                            package: package.to_owned(),
+                                            name: name.to_owned(),
+                                        }),
+                                        configuration: None,
+                                        execution_configuration: None,
+                                    },
+                                )),
+                                ..Default::default()
+                            }),
+                            ..Default::default()
+                        }
+                        .into(),
+                    ),
+                    ..Default::default()
+                }
+                .into(),
+            ),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_index_path() {
+        let log = AbsPathBuf::new(if cfg!(windows) {
+            "C:\\logs\\events.pb.zst"
+        } else {
+            "/logs/events.pb.zst"
+        })
+        .unwrap();
+        let index = index_path(&log);
+        assert!(index.to_str().unwrap().ends_with("events.pb.zst.index"));
+        assert!(is_index_path(&index));
+        assert!(!is_index_path(&log));
+    }
+
+    #[test]
+    fn test_filter_parse() {
+        for invalid in ["", "kind", "foo=bar", "span=abc", "after=yesterday"] {
+            assert!(EventLogFilter::from_str(invalid).is_err(), "{invalid}");
+        }
+        let filter =
+            EventLogFilter::from_str("kind=ActionExecution,kind=Analysis,target=root//foo:bar")
+                .unwrap();
+        assert_eq!(vec!["ActionExecution", "Analysis"], filter.kinds);
+        assert_eq!(vec!["root//foo:bar"], filter.targets);
+    }
+
+    #[test]
+    fn test_filter_matches_event() {
+        let event = action_end("root//foo:bar", 7);
+        let matches = |filter: &str| EventLogFilter::from_str(filter).unwrap().matches(&event);
+
+        assert!(matches("kind=ActionExecution"));
+        assert!(matches("kind=Analysis,kind=ActionExecution"));
+        assert!(!matches("kind=Analysis"));
+        assert!(matches("target=root//foo:bar"));
+        assert!(!matches("target=root//foo:baz"));
+        assert!(!matches("kind=ActionExecution,target=root//foo:baz"));
+        assert!(matches("span=7"));
+        assert!(!matches("span=8"));
+        assert!(matches("after=1700000000,before=1700000001"));
+        assert!(!matches("after=1700000001"));
+    }
+
+    #[test]
+    fn test_filter_matches_block() {
+        let mut block = IndexedBlock::default();
+        block.record(&action_end("root//foo:bar", 7));
+        block.record(&action_end("root//foo:baz", 8));
+        let may_match = |filter: &str| EventLogFilter::from_str(filter).unwrap().may_match(&block);
+
+        assert!(may_match("kind=ActionExecution,target=root//foo:baz"));
+        assert!(may_match("span=8"));
+        assert!(!may_match("kind=Analysis"));
+        assert!(!may_match("target=root//qux:bar"));
+        assert!(!may_match("before=1699999999"));
+    }
+}
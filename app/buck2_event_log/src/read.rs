@@ -38,11 +38,16 @@ use prost::Message;
 use regex::Regex;
 use tokio::io::AsyncBufReadExt;
 use tokio::io::AsyncRead;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncSeekExt;
 use tokio::io::BufReader;
 use tokio::io::ReadBuf;
 use tokio_stream::wrappers::LinesStream;
 use tokio_util::codec::FramedRead;
 
+use crate::index::EventLogFilter;
+use crate::index::index_path;
+use crate::index::read_index;
 use crate::stream_value::StreamValue;
 use crate::tail::TailOptions;
 use crate::tail::TailReader;
@@ -278,19 +283,9 @@ impl EventLogPathBuf {
         let invocation = Invocation::from_proto(invocation);
 
         let events = stream.and_then(|data| async move {
-            let val = buck2_cli_proto::CommandProgress::decode_length_delimited(data)
-                .buck_error_context("Invalid CommandProgress")?;
-            match val.progress {
-                Some(command_progress::Progress::Event(event)) => Ok(StreamValue::Event(event)),
-                Some(command_progress::Progress::Result(result)) => Ok(StreamValue::Result(result)),
-                Some(command_progress::Progress::PartialResult(result)) => {
-                    Ok(StreamValue::PartialResult(result))
-                }
-                None => Err(buck2_error::buck2_error!(
-                    buck2_error::ErrorTag::InvalidEvent,
-                    "Event type not recognized"
-                )),
-            }
+            decode_protobuf_stream_value(buck2_cli_proto::CommandProgress::decode_length_delimited(
+                data,
+            ))
         });
 
         // Wrap in tolerant_of_truncation to handle in-progress logs
@@ -347,6 +342,115 @@ impl EventLogPathBuf {
         self.unpack_stream_inner(None, Some(options)).await
     }
 
+    /// Like `unpack_stream`, but only streams the events matching `filter`. If the log has an
+    /// index, only the blocks of the log that may contain such events are read.
+    pub async fn unpack_stream_filtered(
+        &self,
+        filter: EventLogFilter,
+    ) -> buck2_error::Result<(
+        Invocation,
+        BoxStream<'static, buck2_error::Result<StreamValue>>,
+    )> {
+        let (invocation, events) = self.unpack_stream().await?;
+        let blocks = match read_index(&self.path).await? {
+            Some(blocks) if !blocks.is_empty() => blocks,
+            _ => {
+                let events = events.try_filter(move |value| {
+                    futures::future::ready(stream_value_matches(&filter, value))
+                });
+                return Ok((invocation, events.boxed()));
+            }
+        };
+
+        // Events after the last indexed block are in a block still being written.
+        let unindexed = blocks.last().map_or(0, |block| block.offset + block.length);
+        let blocks = blocks
+            .into_iter()
+            .filter(|block| filter.may_match(block))
+            .map(|block| (block.offset, Some(block.length), block.header))
+            .chain(std::iter::once((unindexed, None, false)))
+            .collect::<Vec<_>>();
+        let log = self.clone();
+        let events = futures::stream::iter(blocks)
+            .then(move |(offset, length, header)| {
+                let log = log.clone();
+                async move { log.read_block(offset, length, header).await }
+            })
+            .map_ok(|values| futures::stream::iter(values.into_iter().map(Ok)))
+            .try_flatten()
+            .try_filter(move |value| futures::future::ready(stream_value_matches(&filter, value)));
+        Ok((invocation, events.boxed()))
+    }
+
+    /// Reads the block of an indexed log at `offset`, or everything from `offset` on if
+    /// `length` is not set, in which case the block may be incomplete.
+    async fn read_block(
+        &self,
+        offset: u64,
+        length: Option<u64>,
+        header: bool,
+    ) -> buck2_error::Result<Vec<StreamValue>> {
+        let mut file = async_fs_util::open(&self.path)
+            .await
+            .categorize_internal()?;
+        file.seek(io::SeekFrom::Start(offset))
+            .await
+            .with_buck_error_context(|| format!("Error seeking in `{}`", self.path))?;
+        let mut compressed = Vec::new();
+        let read = match length {
+            Some(length) => file.take(length).read_to_end(&mut compressed).await,
+            None => file.read_to_end(&mut compressed).await,
+        };
+        read.with_buck_error_context(|| format!("Error reading `{}`", self.path))?;
+        if compressed.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut data = Vec::new();
+        let decoded = match self.encoding.compression {
+            Compression::None => {
+                data = compressed;
+                Ok(0)
+            }
+            Compression::Gzip => {
+                let mut decoder = GzipDecoder::new(compressed.as_slice());
+                decoder.multiple_members(true);
+                decoder.read_to_end(&mut data).await
+            }
+            Compression::Zstd => {
+                let mut decoder = ZstdDecoder::new(compressed.as_slice());
+                decoder.multiple_members(true);
+                decoder.read_to_end(&mut data).await
+            }
+        };
+        match decoded.map_err(buck2_error::Error::from) {
+            Ok(_) => {}
+            Err(e) if length.is_none() && is_truncated_stream_error(&e) => {}
+            Err(e) => {
+                return Err(e.context(format!("Error decompressing block of `{}`", self.path)));
+            }
+        }
+
+        let mut records = split_records(self.encoding.mode, &data, length.is_some())?;
+        if header {
+            records.next();
+        }
+        records
+            .map(|record| match self.encoding.mode {
+                LogMode::Json => serde_json::from_slice::<StreamValue>(record)
+                    .with_buck_error_context(|| {
+                        format!(
+                            "Invalid line: {}",
+                            String::from_utf8_lossy(record).trim_end()
+                        )
+                    }),
+                LogMode::Protobuf => {
+                    decode_protobuf_stream_value(buck2_cli_proto::CommandProgress::decode(record))
+                }
+            })
+            .collect()
+    }
+
     async fn open<'a>(
         &self,
         stats: Option<&'a ReaderStats>,
@@ -366,6 +470,11 @@ impl EventLogPathBuf {
             None => (None, None),
         };
 
+        // Indexed logs are a sequence of compressed blocks. When tailing, only expect more blocks
+        // after the first if the log is indexed, since otherwise its end marks the end of the log.
+        let multiple_members =
+            tail.is_none() || tokio::fs::try_exists(index_path(&self.path)).await?;
+
         let file = async_fs_util::open(&self.path)
             .await
             .categorize_internal()?;
@@ -378,14 +487,16 @@ impl EventLogPathBuf {
             Compression::None => {
                 Box::new(CountingReader::new(file, decompressed_bytes)) as EventLogReader
             }
-            Compression::Gzip => Box::new(CountingReader::new(
-                GzipDecoder::new(BufReader::new(file)),
-                decompressed_bytes,
-            )) as EventLogReader,
-            Compression::Zstd => Box::new(CountingReader::new(
-                ZstdDecoder::new(BufReader::new(file)),
-                decompressed_bytes,
-            )) as EventLogReader,
+            Compression::Gzip => {
+                let mut decoder = GzipDecoder::new(BufReader::new(file));
+                decoder.multiple_members(multiple_members);
+                Box::new(CountingReader::new(decoder, decompressed_bytes)) as EventLogReader
+            }
+            Compression::Zstd => {
+                let mut decoder = ZstdDecoder::new(BufReader::new(file));
+                decoder.multiple_members(multiple_members);
+                Box::new(CountingReader::new(decoder, decompressed_bytes)) as EventLogReader
+            }
         };
 
         Ok(file)
@@ -417,6 +528,69 @@ impl EventLogPathBuf {
     }
 }
 
+fn decode_protobuf_stream_value(
+    progress: Result<buck2_cli_proto::CommandProgress, prost::DecodeError>,
+) -> buck2_error::Result<StreamValue> {
+    let progress = progress.buck_error_context("Invalid CommandProgress")?;
+    match progress.progress {
+        Some(command_progress::Progress::Event(event)) => Ok(StreamValue::Event(event)),
+        Some(command_progress::Progress::Result(result)) => Ok(StreamValue::Result(result)),
+        Some(command_progress::Progress::PartialResult(result)) => {
+            Ok(StreamValue::PartialResult(result))
+        }
+        None => Err(buck2_error::buck2_error!(
+            buck2_error::ErrorTag::InvalidEvent,
+            "Event type not recognized"
+        )),
+    }
+}
+
+/// Splits decompressed log contents into serialized records: lines in JSON logs, and length
+/// delimited messages (without their length) in protobuf logs. If `complete` is not set, an
+/// incomplete last record is ignored.
+fn split_records(
+    mode: LogMode,
+    mut data: &[u8],
+    complete: bool,
+) -> buck2_error::Result<impl Iterator<Item = &[u8]>> {
+    let mut records = Vec::new();
+    match mode {
+        LogMode::Json => {
+            while let Some(end) = data.iter().position(|b| *b == b'\n') {
+                records.push(&data[..end]);
+                data = &data[end + 1..];
+            }
+        }
+        LogMode::Protobuf => {
+            while !data.is_empty() {
+                let mut rest = data;
+                let Ok(length) = prost::decode_length_delimiter(&mut rest) else {
+                    break;
+                };
+                if rest.len() < length {
+                    break;
+                }
+                records.push(&rest[..length]);
+                data = &rest[length..];
+            }
+        }
+    }
+    if complete && !data.is_empty() {
+        return Err(buck2_error::buck2_error!(
+            buck2_error::ErrorTag::InvalidEvent,
+            "Incomplete record at the end of an event log block"
+        ));
+    }
+    Ok(records.into_iter())
+}
+
+fn stream_value_matches(filter: &EventLogFilter, value: &StreamValue) -> bool {
+    match value {
+        StreamValue::Event(event) => filter.matches(event),
+        StreamValue::Result(_) | StreamValue::PartialResult(_) => false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use buck2_data::CommandStart;
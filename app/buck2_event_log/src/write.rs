@@ -16,6 +16,7 @@ use std::time::SystemTime;
 
 use buck2_cli_proto::*;
 use buck2_common::argv::SanitizedArgv;
+use buck2_core::buck2_env;
 use buck2_error::BuckErrorContext;
 use buck2_events::BuckEvent;
 use buck2_fs::paths::abs_norm_path::AbsNormPathBuf;
@@ -31,6 +32,7 @@ use tokio::fs::OpenOptions;
 use crate::FutureChildOutput;
 use crate::file_names::get_logfile_name;
 use crate::file_names::remove_old_logs;
+use crate::index::IndexWriter;
 use crate::read::EventLogPathBuf;
 use crate::should_block_on_log_upload;
 use crate::should_upload_log;
@@ -178,10 +180,12 @@ impl WriteEventLog {
             path: logdir.as_abs_path().join(file_name),
             encoding,
         };
+        let indexed = buck2_env!("BUCK2_EVENT_LOG_INDEX", bool)?;
         let writer = start_persist_event_log_subprocess(
             path,
             event.trace_id()?.clone(),
             self.log_size_counter_bytes.clone(),
+            indexed,
         )
         .await?;
         let mut writers = vec![writer];
@@ -196,6 +200,7 @@ impl WriteEventLog {
                     }),
                     self.log_size_counter_bytes.clone(),
                     EventLogType::System,
+                    indexed,
                 )
                 .await?,
             );
@@ -213,6 +218,7 @@ impl WriteEventLog {
                     }),
                     self.log_size_counter_bytes.clone(),
                     EventLogType::User,
+                    false,
                 )
                 .await?,
             );
@@ -257,6 +263,7 @@ async fn start_persist_event_log_subprocess(
     path: EventLogPathBuf,
     trace_id: TraceId,
     bytes_written: Option<Arc<AtomicU64>>,
+    indexed: bool,
 ) -> buck2_error::Result<NamedEventLogWriter> {
     let current_exe = std::env::current_exe().buck_error_context("No current_exe")?;
     let mut command = buck2_util::process::async_background_command(current_exe);
@@ -293,6 +300,13 @@ async fn start_persist_event_log_subprocess(
     })?;
     let pipe = child.stdin.take().expect("stdin was piped");
 
+    // The subprocess writes what it is sent to a new file.
+    let index = if indexed {
+        Some(IndexWriter::create(&path.path, 0).await?)
+    } else {
+        None
+    };
+
     // Only spawn this if we are going to wait.
     let process_to_wait_for = if block {
         Some(FutureChildOutput::new(child))
@@ -306,6 +320,7 @@ async fn start_persist_event_log_subprocess(
         bytes_written,
         EventLogType::System,
         process_to_wait_for,
+        index,
     ))
 }
 
@@ -313,6 +328,7 @@ async fn open_event_log_for_writing(
     path: EventLogPathBuf,
     bytes_written: Option<Arc<AtomicU64>>,
     event_log_type: EventLogType,
+    indexed: bool,
 ) -> buck2_error::Result<NamedEventLogWriter> {
     let file = OpenOptions::new()
         .create(true)
@@ -326,12 +342,25 @@ async fn open_event_log_for_writing(
             )
         })?;
 
+    let index = if indexed {
+        // The file is appended to, so its blocks start after its current contents.
+        let offset = file
+            .metadata()
+            .await
+            .with_buck_error_context(|| format!("Failed to stat event log at `{}`", path.path))?
+            .len();
+        Some(IndexWriter::create(&path.path, offset).await?)
+    } else {
+        None
+    };
+
     Ok(NamedEventLogWriter::new(
         path,
         file,
         bytes_written,
         event_log_type,
         None,
+        index,
     ))
 }
 
@@ -407,6 +436,10 @@ impl SerializeForLog for Invocation {
             .buck_error_context("Failed to serialize event")?;
         Ok(true)
     }
+
+    fn indexed_event(&self) -> Option<&buck2_data::BuckEvent> {
+        None
+    }
 }
 
 #[derive(Serialize)]
@@ -448,6 +481,13 @@ impl SerializeForLog for StreamValueForWrite<'_> {
 
         Ok(false)
     }
+
+    fn indexed_event(&self) -> Option<&buck2_data::BuckEvent> {
+        match self {
+            Self::Event(event) => Some(*event),
+            Self::Result(_) => None,
+        }
+    }
 }
 
 /// Read every event from `input`, drop those for which `keep` returns false, and write the
@@ -490,7 +530,8 @@ where
             )
         })?;
 
-    let mut writer = NamedEventLogWriter::new(output_log, file, None, EventLogType::System, None);
+    let mut writer =
+        NamedEventLogWriter::new(output_log, file, None, EventLogType::System, None, None);
 
     let mut buf = Vec::new();
     writer.write_events(&mut buf, &[&invocation]).await?;
@@ -549,6 +590,13 @@ impl SerializeForLog for OwnedStreamValueForWrite {
     fn maybe_serialize_user_event(&self, _buf: &mut Vec<u8>) -> buck2_error::Result<bool> {
         Ok(false)
     }
+
+    fn indexed_event(&self) -> Option<&buck2_data::BuckEvent> {
+        match &self.0 {
+            StreamValue::Event(event) => Some(&**event),
+            StreamValue::Result(_) | StreamValue::PartialResult(_) => None,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -565,16 +613,21 @@ mod tests {
     use tempfile::TempDir;
 
     use super::*;
+    use crate::index::BLOCK_MAX_AGE;
+    use crate::index::BLOCK_SIZE;
+    use crate::index::index_path;
+    use crate::index::read_index;
     use crate::tail::TailOptions;
     use crate::tail::WriterState;
     use crate::utils::Compression;
 
     impl WriteEventLog {
-        async fn new_test(log: EventLogPathBuf) -> buck2_error::Result<Self> {
+        async fn new_test(log: EventLogPathBuf, indexed: bool) -> buck2_error::Result<Self> {
             Ok(Self {
                 state: LogWriterState::Opened {
                     writers: vec![
-                        open_event_log_for_writing(log, None, EventLogType::System).await?,
+                        open_event_log_for_writing(log, None, EventLogType::System, indexed)
+                            .await?,
                     ],
                 },
                 sanitized_argv: Argv {
@@ -609,6 +662,33 @@ mod tests {
         )
     }
 
+    fn make_test_result(name: &str) -> BuckEvent {
+        BuckEvent::new(
+            SystemTime::now(),
+            TraceId::new(),
+            None,
+            None,
+            buck2_data::InstantEvent {
+                data: Some(
+                    buck2_data::TestResult {
+                        name: "test".to_owned(),
+                        target_label: Some(buck2_data::ConfiguredTargetLabel {
+                            label: Some(buck2_data::TargetLabel {
+                                package: "root//foo".to_owned(),
+                                name: name.to_owned(),
+                            }),
+                            configuration: None,
+                            execution_configuration: None,
+                        }),
+                        ..Default::default()
+                    }
+                    .into(),
+                ),
+            }
+            .into(),
+        )
+    }
+
     #[tokio::test]
     async fn test_protobuf_decoding_gzip() -> buck2_error::Result<()> {
         test_protobuf_decoding(Encoding::PROTO_GZIP).await
@@ -632,7 +712,7 @@ mod tests {
             encoding,
         };
 
-        let mut write_event_log = WriteEventLog::new_test(log.clone()).await?;
+        let mut write_event_log = WriteEventLog::new_test(log.clone(), false).await?;
 
         //Log event
         let value = StreamValueForWrite::Event(event.event());
@@ -685,7 +765,7 @@ mod tests {
             encoding,
         };
 
-        let mut write_event_log = WriteEventLog::new_test(log.clone()).await?;
+        let mut write_event_log = WriteEventLog::new_test(log.clone(), false).await?;
 
         let event = make_event();
         let value = StreamValueForWrite::Event(event.event());
@@ -749,7 +829,7 @@ mod tests {
             encoding: Encoding::PROTO_ZSTD,
         };
 
-        let mut write_event_log = WriteEventLog::new_test(log.clone()).await?;
+        let mut write_event_log = WriteEventLog::new_test(log.clone(), false).await?;
 
         let event1 = make_event();
         write_event_log.log_invocation(event1.trace_id()?).await?;
@@ -823,7 +903,7 @@ mod tests {
             encoding: Encoding::PROTO_ZSTD,
         };
 
-        let mut write_event_log = WriteEventLog::new_test(log.clone()).await?;
+        let mut write_event_log = WriteEventLog::new_test(log.clone(), false).await?;
 
         let event = make_event();
         write_event_log.log_invocation(event.trace_id()?).await?;
@@ -872,6 +952,112 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_indexed_log() -> buck2_error::Result<()> {
+        let tmp_dir = TempDir::new()?;
+
+        let log = EventLogPathBuf {
+            path: AbsPathBuf::try_from(tmp_dir.path().join("test_indexed_log.pb.zst")).unwrap(),
+            encoding: Encoding::PROTO_ZSTD,
+        };
+
+        let mut write_event_log = WriteEventLog::new_test(log.clone(), true).await?;
+
+        // Flushes right after a block starts don't close it, only the block size and shutting
+        // down do.
+        let load = make_event();
+        write_event_log.log_invocation(load.trace_id()?).await?;
+        write_event_log
+            .write_ln(&[StreamValueForWrite::Event(load.event())])
+            .await?;
+        write_event_log.flush_files().await?;
+        let foo = make_test_result("foo");
+        write_event_log
+            .write_ln(&[StreamValueForWrite::Event(foo.event())])
+            .await?;
+        write_event_log.flush_files().await?;
+        let large = make_test_result(&"x".repeat(BLOCK_SIZE));
+        write_event_log
+            .write_ln(&[StreamValueForWrite::Event(large.event())])
+            .await?;
+        let bar = make_test_result("bar");
+        write_event_log
+            .write_ln(&[StreamValueForWrite::Event(bar.event())])
+            .await?;
+        write_event_log.exit().await;
+
+        assert_eq!(2, read_index(&log.path).await?.unwrap().len());
+
+        // Readers unaware of the index see the events of every block.
+        let (_invocation, events) = log.unpack_stream().await?;
+        assert_eq!(4, events.try_collect::<Vec<_>>().await?.len());
+
+        for (filter, expected) in [
+            ("target=root//foo:foo", &foo),
+            ("kind=TestResult,target=root//foo:bar", &bar),
+            ("kind=Load", &load),
+        ] {
+            let (invocation, events) = log.unpack_stream_filtered(filter.parse()?).await?;
+            assert_eq!(load.trace_id()?, invocation.trace_id);
+            match events.try_collect::<Vec<_>>().await?.as_slice() {
+                [StreamValue::Event(event)] => assert_eq!(expected.event(), &**event, "{filter}"),
+                events => panic!("Expected one event for `{filter}`, got {}", events.len()),
+            }
+        }
+
+        // Filtering doesn't need the index.
+        tokio::fs::remove_file(index_path(&log.path)).await?;
+        let (_invocation, events) = log
+            .unpack_stream_filtered("kind=TestResult".parse()?)
+            .await?;
+        assert_eq!(3, events.try_collect::<Vec<_>>().await?.len());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_indexed_log_flush_closes_old_block() -> buck2_error::Result<()> {
+        tokio::time::pause();
+        let tmp_dir = TempDir::new()?;
+
+        let log = EventLogPathBuf {
+            path: AbsPathBuf::try_from(
+                tmp_dir
+                    .path()
+                    .join("test_indexed_log_flush_closes_old_block.pb.zst"),
+            )
+            .unwrap(),
+            encoding: Encoding::PROTO_ZSTD,
+        };
+
+        let mut write_event_log = WriteEventLog::new_test(log.clone(), true).await?;
+
+        let event = make_event();
+        write_event_log.log_invocation(event.trace_id()?).await?;
+        write_event_log
+            .write_ln(&[StreamValueForWrite::Event(event.event())])
+            .await?;
+        write_event_log.flush_files().await?;
+        assert!(
+            log.unpack_stream().await.is_err(),
+            "Sanity check: the block is too recent to be closed by a flush"
+        );
+
+        tokio::time::advance(BLOCK_MAX_AGE).await;
+        write_event_log.flush_files().await?;
+
+        // Do not close the log, and open it.
+        let (_invocation, events) = log.unpack_stream().await?;
+        match events.try_collect::<Vec<_>>().await?.as_slice() {
+            [StreamValue::Event(retrieved)] => assert_eq!(event.event(), &**retrieved),
+            events => panic!("Expected one event, got {}", events.len()),
+        }
+        assert_eq!(1, read_index(&log.path).await?.unwrap().len());
+
+        write_event_log.exit().await;
+        Ok(())
+    }
+
     #[test]
     fn test_stream_value_serialize_to_protobuf_length_delimited() {
         let event = make_event();
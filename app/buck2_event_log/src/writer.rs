@@ -18,18 +18,26 @@ use std::task::Poll;
 
 use async_compression::tokio::write::GzipEncoder;
 use async_compression::tokio::write::ZstdEncoder;
+use buck2_error::BuckErrorContext;
 use counting_reader::CountingReader;
 use pin_project::pin_project;
 use tokio::io::AsyncWrite;
 use tokio::io::AsyncWriteExt;
+use tokio::time::Instant;
 
 use crate::FutureChildOutput;
+use crate::index::BLOCK_MAX_AGE;
+use crate::index::BLOCK_SIZE;
+use crate::index::IndexWriter;
 use crate::read::EventLogPathBuf;
 use crate::utils::Compression;
 use crate::utils::LogMode;
 
 type EventLogWriter = Box<dyn AsyncWrite + Send + Sync + Unpin + 'static>;
 
+const GZIP_LEVEL: async_compression::Level = async_compression::Level::Fastest;
+const ZSTD_LEVEL: async_compression::Level = async_compression::Level::Precise(9);
+
 mod counting_reader {
     use super::*;
 
@@ -92,6 +100,17 @@ pub(crate) struct NamedEventLogWriter {
     /// If this writing is done by a subprocess, that process's output, assuming we intend to wait
     /// for it to exit.
     process_to_wait_for: Option<FutureChildOutput>,
+    /// Set if the log is indexed, in which case blocks are compressed before being written to
+    /// `file`.
+    blocks: Option<IndexedBlocks>,
+}
+
+struct IndexedBlocks {
+    index: IndexWriter,
+    /// Uncompressed contents of the block being written.
+    block: Vec<u8>,
+    /// When the first event of the block being written was added.
+    block_started: Option<Instant>,
 }
 
 impl NamedEventLogWriter {
@@ -101,18 +120,20 @@ impl NamedEventLogWriter {
         bytes_written: Option<Arc<AtomicU64>>,
         event_log_type: EventLogType,
         process_to_wait_for: Option<FutureChildOutput>,
+        index: Option<IndexWriter>,
     ) -> Self {
-        let file = match path.encoding.compression {
-            Compression::None => {
+        let file = match (&index, path.encoding.compression) {
+            // Blocks are compressed before being written.
+            (Some(_), _) | (None, Compression::None) => {
                 Box::new(CountingReader::new(file, bytes_written)) as EventLogWriter
             }
-            Compression::Gzip => Box::new(GzipEncoder::with_quality(
+            (None, Compression::Gzip) => Box::new(GzipEncoder::with_quality(
                 CountingReader::new(file, bytes_written),
-                async_compression::Level::Fastest,
+                GZIP_LEVEL,
             )) as EventLogWriter,
-            Compression::Zstd => Box::new(ZstdEncoder::with_quality(
+            (None, Compression::Zstd) => Box::new(ZstdEncoder::with_quality(
                 CountingReader::new(file, bytes_written),
-                async_compression::Level::Precise(9),
+                ZSTD_LEVEL,
             )) as EventLogWriter,
         };
         Self {
@@ -120,10 +141,28 @@ impl NamedEventLogWriter {
             file,
             event_log_type,
             process_to_wait_for,
+            blocks: index.map(|index| IndexedBlocks {
+                index,
+                block: Vec::new(),
+                block_started: None,
+            }),
         }
     }
 
     pub(crate) async fn flush(&mut self) -> buck2_error::Result<()> {
+        // Flushes happen several times a second, and closing a block each time would make blocks
+        // too small to compress well, so only blocks older than `BLOCK_MAX_AGE` are closed.
+        if self
+            .blocks
+            .as_ref()
+            .and_then(|blocks| blocks.block_started)
+            .is_some_and(|started| started.elapsed() >= BLOCK_MAX_AGE)
+        {
+            self.finish_block().await?;
+            if let Some(blocks) = &mut self.blocks {
+                blocks.index.flush().await?;
+            }
+        }
         match self.file.flush().await {
             Ok(_) => Ok(()),
             Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => {
@@ -139,6 +178,16 @@ impl NamedEventLogWriter {
     }
 
     pub(crate) async fn shutdown(&mut self) {
+        if let Err(e) = self.finish_block().await {
+            tracing::warn!(
+                "Failed to write last block of `{}`: {:#}",
+                self.path.path,
+                e
+            );
+        }
+        if let Some(blocks) = &mut self.blocks {
+            blocks.index.shutdown().await;
+        }
         if let Err(e) = self.file.shutdown().await {
             tracing::warn!("Failed to flush log file at `{}`: {:#}", self.path.path, e);
         }
@@ -172,15 +221,40 @@ impl NamedEventLogWriter {
     }
 
     async fn write_all(&mut self, buf: &[u8]) -> buck2_error::Result<()> {
-        match self.file.write_all(buf).await {
-            Ok(_) => Ok(()),
-            Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => {
-                // The subprocess exited with some kind of error. That is logged separately, so
-                // here we just ignore it.
+        match &mut self.blocks {
+            Some(blocks) => {
+                if blocks.block.is_empty() && !buf.is_empty() {
+                    blocks.block_started = Some(Instant::now());
+                }
+                blocks.block.extend_from_slice(buf);
+                if blocks.block.len() >= BLOCK_SIZE {
+                    self.finish_block().await?;
+                }
                 Ok(())
             }
-            Err(e) => Err(buck2_error::Error::from(e).context("Failed to write event")),
+            None => write_all_to_file(&mut self.file, buf).await,
+        }
+    }
+
+    /// Compresses and writes the current block of an indexed log, and indexes it.
+    async fn finish_block(&mut self) -> buck2_error::Result<()> {
+        let Some(blocks) = &mut self.blocks else {
+            return Ok(());
+        };
+        if blocks.block.is_empty() {
+            return Ok(());
+        }
+        let compressed = compress_block(self.path.encoding.compression, &blocks.block)
+            .await
+            .buck_error_context("Failed to compress event log block")?;
+        blocks.block.clear();
+        blocks.block_started = None;
+        if blocks.block.capacity() > 2 * BLOCK_SIZE {
+            // Make sure we don't keep too much memory if encountered one large event.
+            blocks.block = Vec::new();
         }
+        write_all_to_file(&mut self.file, &compressed).await?;
+        blocks.index.finish_block(compressed.len() as u64).await
     }
 
     pub(crate) async fn write_events<'b, T, I>(
@@ -194,13 +268,49 @@ impl NamedEventLogWriter {
     {
         for event in events.clone() {
             self.serialize_event(buf, event)?;
+            if let (Some(blocks), Some(event)) = (&mut self.blocks, event.indexed_event()) {
+                blocks.index.record(event);
+            }
         }
         self.write_all(buf).await
     }
 }
 
+async fn write_all_to_file(file: &mut EventLogWriter, buf: &[u8]) -> buck2_error::Result<()> {
+    match file.write_all(buf).await {
+        Ok(_) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => {
+            // The subprocess exited with some kind of error. That is logged separately, so
+            // here we just ignore it.
+            Ok(())
+        }
+        Err(e) => Err(buck2_error::Error::from(e).context("Failed to write event")),
+    }
+}
+
+/// Compresses a block of an indexed log on its own, so it can be decompressed on its own.
+async fn compress_block(compression: Compression, block: &[u8]) -> io::Result<Vec<u8>> {
+    match compression {
+        Compression::None => Ok(block.to_vec()),
+        Compression::Gzip => {
+            let mut encoder = GzipEncoder::with_quality(Vec::new(), GZIP_LEVEL);
+            encoder.write_all(block).await?;
+            encoder.shutdown().await?;
+            Ok(encoder.into_inner())
+        }
+        Compression::Zstd => {
+            let mut encoder = ZstdEncoder::with_quality(Vec::new(), ZSTD_LEVEL);
+            encoder.write_all(block).await?;
+            encoder.shutdown().await?;
+            Ok(encoder.into_inner())
+        }
+    }
+}
+
 pub(crate) trait SerializeForLog {
     fn serialize_to_json(&self, buf: &mut Vec<u8>) -> buck2_error::Result<()>;
     fn serialize_to_protobuf_length_delimited(&self, buf: &mut Vec<u8>) -> buck2_error::Result<()>;
     fn maybe_serialize_user_event(&self, buf: &mut Vec<u8>) -> buck2_error::Result<bool>;
+    /// The event to record in the index of indexed logs, if any.
+    fn indexed_event(&self) -> Option<&buck2_data::BuckEvent>;
 }
@@ -24,6 +24,7 @@ pub struct TestState {
     pub unknown: u64,
     pub listing_success: u64,
     pub listing_failed: u64,
+    pub flaky: u64,
 }
 
 impl TestState {
@@ -41,6 +42,7 @@ impl TestState {
             TestStatus::RERUN => &mut self.retry,
             TestStatus::LISTING_SUCCESS => &mut self.listing_success,
             TestStatus::LISTING_FAILED => &mut self.listing_failed,
+            TestStatus::FLAKY => &mut self.flaky,
         };
         *counter += 1;
 
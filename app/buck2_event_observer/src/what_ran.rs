@@ -102,6 +102,7 @@ pub struct WhatRanOutputCommand<'a> {
     pub std_err: Option<&'a str>,
     pub duration: Option<std::time::Duration>,
     pub scheduling_mode: Option<SchedulingMode>,
+    pub diagnostics: &'a [buck2_data::ActionDiagnostic],
 }
 
 impl WhatRanOutputCommand<'_> {
@@ -152,14 +153,21 @@ pub fn matches_category(action: Option<&WhatRanRelevantAction>, pattern: &Regex)
     }
 }
 
+/// How a command went, known once its action finished.
+#[derive(Default, Clone, Copy)]
+pub struct WhatRanCommandOutcome<'a> {
+    pub std_err: Option<&'a str>,
+    pub duration: Option<std::time::Duration>,
+    pub scheduling_mode: Option<SchedulingMode>,
+    pub diagnostics: &'a [buck2_data::ActionDiagnostic],
+}
+
 pub fn emit_what_ran_entry(
     action: Option<&WhatRanRelevantAction>,
     repro: CommandReproducer,
     output: &mut impl WhatRanOutputWriter,
     options: &WhatRanOptionsRegex,
-    std_err: Option<&str>,
-    duration: Option<std::time::Duration>,
-    scheduling_mode: Option<SchedulingMode>,
+    outcome: WhatRanCommandOutcome<'_>,
 ) -> buck2_error::Result<()> {
     let should_emit = options
         .filter_category_regex
@@ -212,9 +220,10 @@ pub fn emit_what_ran_entry(
         identity: &identity,
         repro,
         extra,
-        std_err,
-        duration,
-        scheduling_mode,
+        std_err: outcome.std_err,
+        duration: outcome.duration,
+        scheduling_mode: outcome.scheduling_mode,
+        diagnostics: outcome.diagnostics,
     })?;
 
     Ok(())
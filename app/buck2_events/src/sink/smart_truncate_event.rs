@@ -42,8 +42,14 @@ pub(crate) fn smart_truncate_event(d: &mut buck2_data::buck_event::Data) {
         }
         Data::Instant(inst) => {
             use buck2_data::instant_event::Data;
-            if let Some(Data::TargetPatterns(target_patterns)) = &mut inst.data {
-                truncate_target_patterns(&mut target_patterns.target_patterns);
+            match &mut inst.data {
+                Some(Data::TargetPatterns(target_patterns)) => {
+                    truncate_target_patterns(&mut target_patterns.target_patterns);
+                }
+                Some(Data::ActionError(action_error)) => {
+                    truncate_diagnostics(&mut action_error.diagnostics);
+                }
+                _ => {}
             }
         }
         Data::Record(rec) => {
@@ -121,6 +127,29 @@ fn truncate_action_execution_end(action_execution_end: &mut buck2_data::ActionEx
         // Save some bytes.
         truncate_cmd(last_command, !action_execution_end.failed);
     }
+
+    truncate_diagnostics(&mut action_execution_end.diagnostics);
+}
+
+fn truncate_diagnostics(diagnostics: &mut Vec<buck2_data::ActionDiagnostic>) {
+    // Tools can report a diagnostic per line of a large file, keep the first ones.
+    const MAX_DIAGNOSTICS_BYTES: usize = 100 * 1024;
+    const MAX_DIAGNOSTIC_MESSAGE_BYTES: usize = 4 * 1024;
+    let orig_len = diagnostics.len();
+    let mut bytes: usize = 0;
+    for (index, diagnostic) in diagnostics.iter_mut().enumerate() {
+        diagnostic.message = truncate(&diagnostic.message, MAX_DIAGNOSTIC_MESSAGE_BYTES);
+        bytes += diagnostic.file.len() + diagnostic.message.len() + diagnostic.category.len();
+        if bytes > MAX_DIAGNOSTICS_BYTES {
+            diagnostics.truncate(index);
+            let warn = format!("<<Truncated (reported {index} / {orig_len})>>");
+            diagnostics.push(buck2_data::ActionDiagnostic {
+                message: warn,
+                ..Default::default()
+            });
+            break;
+        }
+    }
 }
 
 fn truncate_command_end(command_end: &mut buck2_data::CommandEnd, clear_target_patterns: bool) {
@@ -711,4 +740,50 @@ mod tests {
 
         assert_eq!(event_data, event_data_expected);
     }
+
+    #[test]
+    fn smart_truncate_action_diagnostics() {
+        let diagnostic = |message: String| buck2_data::ActionDiagnostic {
+            file: "foo.c".to_owned(),
+            message,
+            ..Default::default()
+        };
+        let make_action_error = |diagnostics| {
+            buck2_data::buck_event::Data::Instant(buck2_data::InstantEvent {
+                data: Some(buck2_data::instant_event::Data::ActionError(
+                    buck2_data::ActionError {
+                        diagnostics,
+                        ..Default::default()
+                    },
+                )),
+            })
+        };
+
+        // Long messages are truncated, and only the diagnostics that fit are kept.
+        let mut event_data = make_action_error(vec![diagnostic("x".repeat(5 * 1024)); 100]);
+        smart_truncate_event(&mut event_data);
+        let Some(buck2_data::instant_event::Data::ActionError(action_error)) = (match event_data {
+            buck2_data::buck_event::Data::Instant(instant) => instant.data,
+            _ => None,
+        }) else {
+            panic!("Expected an action error");
+        };
+        let (truncated, kept) = action_error.diagnostics.split_last().unwrap();
+        assert_eq!(kept.len(), 24);
+        assert!(kept.iter().all(|d| d.message.len() <= 4 * 1024));
+        assert_eq!(truncated.message, "<<Truncated (reported 24 / 100)>>");
+
+        // Small diagnostics are left alone, also on action execution ends.
+        let diagnostics = vec![diagnostic("error: oops".to_owned())];
+        let mut event_data = make_action_execution_end(buck2_data::ActionExecutionEnd {
+            diagnostics: diagnostics.clone(),
+            ..Default::default()
+        });
+        let event_data_expected = make_action_execution_end(buck2_data::ActionExecutionEnd {
+            diagnostics,
+            ..Default::default()
+        });
+        smart_truncate_event(&mut event_data);
+        assert_eq!(event_data, event_data_expected);
+    }
 }
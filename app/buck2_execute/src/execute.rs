@@ -12,6 +12,7 @@ pub mod action_digest;
 pub mod action_digest_and_blobs;
 pub mod blobs;
 pub mod blocking;
+pub mod cache_probe;
 pub mod cache_uploader;
 pub mod claim;
 pub mod clean_output_paths;
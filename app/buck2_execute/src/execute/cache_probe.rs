@@ -0,0 +1,71 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is dual-licensed under either the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree or the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree. You may select, at your option, one of the
+ * above-listed licenses.
+ */
+
+//! Bookkeeping for `buck2 build --dry-run`, where actions are only looked up in the caches.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use buck2_cli_proto::dry_run_report;
+
+use crate::execute::action_digest::ActionDigest;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CacheProbeOutcome {
+    ActionCacheHit,
+    RemoteDepFileCacheHit,
+    WouldExecute,
+}
+
+impl CacheProbeOutcome {
+    fn to_proto(self) -> dry_run_report::Outcome {
+        match self {
+            CacheProbeOutcome::ActionCacheHit => dry_run_report::Outcome::ActionCacheHit,
+            CacheProbeOutcome::RemoteDepFileCacheHit => {
+                dry_run_report::Outcome::RemoteDepFileCacheHit
+            }
+            CacheProbeOutcome::WouldExecute => dry_run_report::Outcome::WouldExecute,
+        }
+    }
+}
+
+/// Records the outcome of the cache lookups of every action in a dry run. Shared by all the
+/// executors of a command.
+#[derive(Default)]
+pub struct CacheProbeTracker {
+    /// Keyed by the RE action key, so that an action that is looked up more than once (e.g. a
+    /// remote dep file cache hit that turns out to be unusable) keeps its final outcome.
+    actions: Mutex<BTreeMap<String, (ActionDigest, CacheProbeOutcome)>>,
+}
+
+impl CacheProbeTracker {
+    pub fn record(&self, action: String, digest: ActionDigest, outcome: CacheProbeOutcome) {
+        self.actions
+            .lock()
+            .unwrap()
+            .insert(action, (digest, outcome));
+    }
+
+    pub fn to_proto(&self) -> buck2_cli_proto::DryRunReport {
+        buck2_cli_proto::DryRunReport {
+            actions: self
+                .actions
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(action, (digest, outcome))| dry_run_report::Action {
+                    action: action.clone(),
+                    digest: digest.to_string(),
+                    outcome: outcome.to_proto() as i32,
+                })
+                .collect(),
+        }
+    }
+}
@@ -10,6 +10,7 @@
 
 pub mod action_cache;
 pub mod action_cache_upload_permission_checker;
+pub mod cache_probe;
 pub mod caching;
 pub(crate) mod empty_action_result;
 pub mod hybrid;
@@ -0,0 +1,100 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is dual-licensed under either the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree or the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree. You may select, at your option, one of the
+ * above-listed licenses.
+ */
+
+//! Executors for `buck2 build --dry-run`: the usual cache checkers (see
+//! [`crate::executors::action_cache`]) run as in any build, but what they find is recorded, and
+//! actions that miss are not executed.
+
+use std::ops::ControlFlow;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use buck2_execute::execute::action_digest::ActionDigest;
+use buck2_execute::execute::cache_probe::CacheProbeOutcome;
+use buck2_execute::execute::cache_probe::CacheProbeTracker;
+use buck2_execute::execute::manager::CommandExecutionManager;
+use buck2_execute::execute::manager::CommandExecutionManagerExt;
+use buck2_execute::execute::prepared::PreparedCommand;
+use buck2_execute::execute::prepared::PreparedCommandExecutor;
+use buck2_execute::execute::prepared::PreparedCommandOptionalExecutor;
+use buck2_execute::execute::request::ExecutorPreference;
+use buck2_execute::execute::result::CommandExecutionResult;
+use buck2_execute::execute::result::CommandExecutionStatus;
+use dice_futures::cancellation::CancellationContext;
+use dupe::Dupe;
+
+#[derive(buck2_error::Error, Debug)]
+#[buck2(tag = ActionDryRunWouldExecute)]
+#[error("Not executed: no cache has a result for action digest `{0}` (`--dry-run`)")]
+struct WouldExecuteError(ActionDigest);
+
+/// Wraps a cache checker to record its hits.
+pub struct CacheProbeChecker {
+    pub inner: Arc<dyn PreparedCommandOptionalExecutor>,
+    pub tracker: Arc<CacheProbeTracker>,
+    pub outcome_on_hit: CacheProbeOutcome,
+}
+
+#[async_trait]
+impl PreparedCommandOptionalExecutor for CacheProbeChecker {
+    async fn maybe_execute(
+        &self,
+        command: &PreparedCommand<'_, '_>,
+        manager: CommandExecutionManager,
+        cancellations: &CancellationContext,
+    ) -> ControlFlow<CommandExecutionResult, CommandExecutionManager> {
+        let res = self
+            .inner
+            .maybe_execute(command, manager, cancellations)
+            .await;
+        if let ControlFlow::Break(result) = &res
+            && matches!(result.report.status, CommandExecutionStatus::Success { .. })
+        {
+            self.tracker.record(
+                command.target.re_action_key(),
+                command.prepared_action.action_and_blobs.action.dupe(),
+                self.outcome_on_hit,
+            );
+        }
+        res
+    }
+}
+
+/// Used in place of the real executor: records that the action would execute, and fails it so
+/// that nothing depending on its outputs gets looked up.
+pub struct CacheProbeExecutor {
+    pub tracker: Arc<CacheProbeTracker>,
+}
+
+#[async_trait]
+impl PreparedCommandExecutor for CacheProbeExecutor {
+    async fn exec_cmd(
+        &self,
+        command: &PreparedCommand<'_, '_>,
+        manager: CommandExecutionManager,
+        _cancellations: &CancellationContext,
+    ) -> CommandExecutionResult {
+        let digest = command.prepared_action.action_and_blobs.action.dupe();
+        self.tracker.record(
+            command.target.re_action_key(),
+            digest.dupe(),
+            CacheProbeOutcome::WouldExecute,
+        );
+        manager.error("dry_run", WouldExecuteError(digest))
+    }
+
+    fn is_local_execution_possible(&self, _executor_preference: ExecutorPreference) -> bool {
+        false
+    }
+
+    fn is_full_hybrid_enabled(&self) -> bool {
+        false
+    }
+}
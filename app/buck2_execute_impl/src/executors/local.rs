@@ -75,7 +75,7 @@ use buck2_execute::materialize::materializer::Materializer;
 use buck2_execute_local::CommandResult;
 use buck2_execute_local::DefaultKillProcess;
 use buck2_execute_local::GatherOutputStatus;
-use buck2_execute_local::decode_command_event_stream;
+use buck2_execute_local::decode_command_event_stream_reporting_stderr;
 use buck2_execute_local::maybe_absolutize_exe;
 use buck2_execute_local::spawn_command_and_stream_events;
 use buck2_execute_local::status_decoder::DefaultStatusDecoder;
@@ -245,7 +245,7 @@ impl LocalExecutor {
                         freeze_rx,
                     )
                     .await?;
-                    decode_command_event_stream(stream).await
+                    decode_command_event_stream_reporting_stderr(stream, report_stderr_tail).await
                 }
                 .with_buck_error_context(|| format!("Failed to gather output from command: {exe}")),
             }?;
@@ -404,6 +404,7 @@ impl LocalExecutor {
         worker: Option<&WorkerHandle>,
         env: &[(&str, StrOrOsStr<'_>)],
         network_access: Option<NetworkAccess>,
+        action_category: &str,
     ) -> Result<
         (
             TimeSpan,
@@ -427,6 +428,7 @@ impl LocalExecutor {
                     self.memory_tracker.dupe(),
                     command_type,
                     Some(action_digest.to_string()),
+                    Some(action_category.to_owned()),
                     disable_kill_and_retry_suspend,
                 )
                 .await
@@ -534,6 +536,7 @@ impl LocalExecutor {
         digest_config: DigestConfig,
         local_resource_holders: &[LocalResourceHolder],
         network_access: Option<NetworkAccess>,
+        action_category: &str,
     ) -> CommandExecutionResult {
         let args = &request.all_args_vec();
         if args.is_empty() {
@@ -702,6 +705,7 @@ impl LocalExecutor {
                 worker.as_deref(),
                 &env,
                 network_access,
+                action_category,
             )
             .await
         {
@@ -1275,10 +1279,11 @@ impl PreparedCommandExecutor for LocalExecutor {
 
         let PreparedCommand {
             request,
-            target: _,
+            target,
             prepared_action,
             digest_config,
         } = command;
+        let action_category = target.as_proto_action_name().category;
 
         // `All` makes the forkserver skip the network namespace; see
         // `CommandExecutionRequest::disable_local_network_isolation`.
@@ -1334,6 +1339,7 @@ impl PreparedCommandExecutor for LocalExecutor {
                     *digest_config,
                     &local_resource_holders,
                     network_access,
+                    &action_category,
                 )
             })
             .await
@@ -1648,6 +1654,17 @@ pub async fn create_output_dirs(
     Ok(())
 }
 
+/// How much of the end of the stderr of a running command is sent in `ActionStderrTail` events.
+const STDERR_TAIL_BYTES: usize = 4 * 1024;
+
+/// Lets consoles show the stderr of a command before the action finishes.
+fn report_stderr_tail(stderr: &[u8]) {
+    let tail = &stderr[stderr.len().saturating_sub(STDERR_TAIL_BYTES)..];
+    buck2_events::dispatch::instant_event(buck2_data::ActionStderrTail {
+        stderr: String::from_utf8_lossy(tail).into_owned(),
+    });
+}
+
 pub fn apply_local_execution_environment(
     builder: &mut impl EnvironmentBuilder,
     working_directory: &AbsPath,
@@ -1752,6 +1769,7 @@ mod unix {
                 req,
                 async move { liveliness_observer.while_alive().await },
                 freeze_rx,
+                report_stderr_tail,
             )
             .await
     }
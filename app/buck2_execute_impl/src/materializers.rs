@@ -10,5 +10,6 @@
 
 pub mod artifact_type;
 pub mod deferred;
+pub mod dry_run;
 pub mod immediate;
 pub mod io;
@@ -0,0 +1,193 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is dual-licensed under either the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree or the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree. You may select, at your option, one of the
+ * above-listed licenses.
+ */
+
+use std::sync::Arc;
+
+use allocative::Allocative;
+use async_trait::async_trait;
+use buck2_common::file_ops::metadata::FileMetadata;
+use buck2_common::file_ops::metadata::TrackedFileDigest;
+use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
+use buck2_events::dispatch::EventDispatcher;
+use buck2_execute::artifact_value::ArtifactValue;
+use buck2_execute::digest_config::DigestConfig;
+use buck2_execute::directory::ActionDirectoryEntry;
+use buck2_execute::directory::ActionSharedDirectory;
+use buck2_execute::materialize::materializer::ArtifactNotMaterializedReason;
+use buck2_execute::materialize::materializer::CasDownloadInfo;
+use buck2_execute::materialize::materializer::CopiedArtifact;
+use buck2_execute::materialize::materializer::DeclareArtifactPayload;
+use buck2_execute::materialize::materializer::DeclareMatchOutcome;
+use buck2_execute::materialize::materializer::HttpDownloadInfo;
+use buck2_execute::materialize::materializer::MaterializationError;
+use buck2_execute::materialize::materializer::MaterializationPurpose;
+use buck2_execute::materialize::materializer::Materializer;
+use buck2_execute::materialize::materializer::WriteRequest;
+use futures::stream;
+use futures::stream::BoxStream;
+use futures::stream::StreamExt;
+
+/// Materializer used by `--dry-run` builds.
+///
+/// Actions still compute their output values (so that the build graph can be walked and cache
+/// probes can be made), but nothing is declared to, or written by, the daemon's materializer:
+/// declarations and invalidations are dropped and materialization requests succeed without doing
+/// anything. Queries are forwarded to the wrapped materializer so that state left by previous
+/// builds is still visible.
+#[derive(Allocative)]
+pub struct DryRunMaterializer {
+    inner: Arc<dyn Materializer>,
+    digest_config: DigestConfig,
+}
+
+impl DryRunMaterializer {
+    pub fn new(inner: Arc<dyn Materializer>, digest_config: DigestConfig) -> Self {
+        Self {
+            inner,
+            digest_config,
+        }
+    }
+}
+
+#[async_trait]
+impl Materializer for DryRunMaterializer {
+    fn name(&self) -> &str {
+        "dry_run"
+    }
+
+    async fn declare_existing(
+        &self,
+        _artifacts: Vec<DeclareArtifactPayload>,
+    ) -> buck2_error::Result<()> {
+        Ok(())
+    }
+
+    async fn declare_copy_impl(
+        &self,
+        _path: ProjectRelativePathBuf,
+        _value: ArtifactValue,
+        _srcs: Vec<CopiedArtifact>,
+        _configuration_path: Option<ProjectRelativePathBuf>,
+    ) -> buck2_error::Result<()> {
+        Ok(())
+    }
+
+    async fn declare_cas_many_impl<'a, 'b>(
+        &self,
+        _info: Arc<CasDownloadInfo>,
+        _artifacts: Vec<DeclareArtifactPayload>,
+    ) -> buck2_error::Result<()> {
+        Ok(())
+    }
+
+    async fn declare_http(
+        &self,
+        _path: ProjectRelativePathBuf,
+        _info: HttpDownloadInfo,
+        _configuration_path: Option<ProjectRelativePathBuf>,
+    ) -> buck2_error::Result<()> {
+        Ok(())
+    }
+
+    async fn declare_write<'a>(
+        &self,
+        generate: Box<dyn FnOnce() -> buck2_error::Result<Vec<WriteRequest>> + Send + 'a>,
+    ) -> buck2_error::Result<Vec<ArtifactValue>> {
+        Ok(generate()?
+            .into_iter()
+            .map(|request| {
+                let digest = TrackedFileDigest::from_content(
+                    &request.content,
+                    self.digest_config.cas_digest_config(),
+                );
+                ArtifactValue::file(FileMetadata {
+                    digest,
+                    is_executable: request.is_executable,
+                })
+            })
+            .collect())
+    }
+
+    async fn declare_match(
+        &self,
+        artifacts: Vec<(ProjectRelativePathBuf, ArtifactValue)>,
+    ) -> buck2_error::Result<DeclareMatchOutcome> {
+        self.inner.declare_match(artifacts).await
+    }
+
+    async fn has_artifact_at(&self, path: ProjectRelativePathBuf) -> buck2_error::Result<bool> {
+        self.inner.has_artifact_at(path).await
+    }
+
+    async fn invalidate_many(
+        &self,
+        _paths: Vec<ProjectRelativePathBuf>,
+    ) -> buck2_error::Result<()> {
+        Ok(())
+    }
+
+    async fn materialize_many(
+        &self,
+        artifact_paths: Vec<ProjectRelativePathBuf>,
+    ) -> buck2_error::Result<BoxStream<'static, Result<(), MaterializationError>>> {
+        Ok(stream::iter(artifact_paths.into_iter().map(|_| Ok(()))).boxed())
+    }
+
+    async fn ensure_materialized(
+        &self,
+        _artifact_paths: Vec<ProjectRelativePathBuf>,
+        _purpose: MaterializationPurpose,
+    ) -> buck2_error::Result<()> {
+        Ok(())
+    }
+
+    async fn try_materialize_final_artifact(
+        &self,
+        _artifact_path: ProjectRelativePathBuf,
+    ) -> buck2_error::Result<bool> {
+        Ok(false)
+    }
+
+    async fn get_materialized_file_paths(
+        &self,
+        file_paths: Vec<ProjectRelativePathBuf>,
+    ) -> buck2_error::Result<Vec<Result<ProjectRelativePathBuf, ArtifactNotMaterializedReason>>>
+    {
+        self.inner.get_materialized_file_paths(file_paths).await
+    }
+
+    fn log_materializer_state(&self, events: &EventDispatcher) {
+        self.inner.log_materializer_state(events)
+    }
+
+    fn add_snapshot_stats(&self, snapshot: &mut buck2_data::Snapshot) {
+        self.inner.add_snapshot_stats(snapshot)
+    }
+
+    async fn get_artifact_entries_for_materialized_paths(
+        &self,
+        paths: Vec<ProjectRelativePathBuf>,
+        fetch_root_artifact_entries_for_subpaths: bool,
+    ) -> buck2_error::Result<
+        Vec<
+            Option<(
+                ProjectRelativePathBuf,
+                ActionDirectoryEntry<ActionSharedDirectory>,
+            )>,
+        >,
+    > {
+        self.inner
+            .get_artifact_entries_for_materialized_paths(
+                paths,
+                fetch_root_artifact_entries_for_subpaths,
+            )
+            .await
+    }
+}
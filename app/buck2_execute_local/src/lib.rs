@@ -348,7 +348,24 @@ pub struct CommandResult {
     pub orphan_processes: Vec<OrphanProcessInfo>,
 }
 
+/// How often `decode_command_event_stream_reporting_stderr` reports the stderr of a command
+/// that keeps writing to it.
+const STDERR_REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
 pub async fn decode_command_event_stream<S>(stream: S) -> buck2_error::Result<CommandResult>
+where
+    S: Stream<Item = buck2_error::Result<CommandEvent>>,
+{
+    decode_command_event_stream_reporting_stderr(stream, |_| {}).await
+}
+
+/// Like `decode_command_event_stream`, but also passes the stderr written so far to `report`
+/// while the command runs: right away the first time, then at most once every
+/// `STDERR_REPORT_INTERVAL`.
+pub async fn decode_command_event_stream_reporting_stderr<S>(
+    stream: S,
+    mut report: impl FnMut(&[u8]),
+) -> buck2_error::Result<CommandResult>
 where
     S: Stream<Item = buck2_error::Result<CommandEvent>>,
 {
@@ -356,11 +373,28 @@ where
 
     let mut stdout = Vec::<u8>::new();
     let mut stderr = Vec::<u8>::new();
+    let mut report_interval = tokio::time::interval(STDERR_REPORT_INTERVAL);
+    report_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let mut unreported = false;
 
-    while let Some(event) = stream.try_next().await? {
+    loop {
+        let event = tokio::select! {
+            event = stream.try_next() => event?,
+            _ = report_interval.tick(), if unreported => {
+                report(&stderr);
+                unreported = false;
+                continue;
+            }
+        };
+        let Some(event) = event else {
+            break;
+        };
         match event {
             CommandEvent::Stdout(bytes) => stdout.extend(&bytes),
-            CommandEvent::Stderr(bytes) => stderr.extend(&bytes),
+            CommandEvent::Stderr(bytes) => {
+                stderr.extend(&bytes);
+                unreported = true;
+            }
             CommandEvent::Exit(exit, orphan_processes) => {
                 return Ok(CommandResult {
                     status: exit,
@@ -849,4 +883,33 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_decode_reports_stderr_while_running() -> buck2_error::Result<()> {
+        let stream = futures::stream::iter([
+            Ok(CommandEvent::Stderr(Bytes::from("compiling"))),
+            Ok(CommandEvent::Stdout(Bytes::from("out"))),
+        ])
+        .chain(futures::stream::once(async {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            Ok(CommandEvent::Exit(
+                GatherOutputStatus::Finished {
+                    exit_code: 0,
+                    execution_stats: None,
+                },
+                Vec::new(),
+            ))
+        }));
+
+        let mut reports = Vec::new();
+        let result = decode_command_event_stream_reporting_stderr(stream, |stderr| {
+            reports.push(str::from_utf8(stderr).unwrap().to_owned())
+        })
+        .await?;
+        assert_eq!(reports, vec!["compiling".to_owned()]);
+        assert_eq!(result.stderr, b"compiling");
+        assert_eq!(result.stdout, b"out");
+
+        Ok(())
+    }
 }
@@ -18,32 +18,17 @@ use std::sync::Mutex;
 use std::sync::OnceLock;
 
 use buck2_build_api::actions::artifact::get_artifact_fs::GetArtifactFs;
-use buck2_common::dice::data::HasIoProvider;
-use buck2_common::file_ops::delegate::FileOpsDelegate;
-use buck2_common::file_ops::metadata::FileDigestConfig;
-use buck2_common::file_ops::metadata::RawDirEntry;
-use buck2_common::file_ops::metadata::RawPathMetadata;
-use buck2_common::io::IoProvider;
 use buck2_common::io::fs::FsIoProvider;
-use buck2_core::cells::cell_path::CellPath;
 use buck2_core::cells::external::ExternalCellOrigin;
 use buck2_core::cells::external::GitCellSetup;
 use buck2_core::cells::name::CellName;
-use buck2_core::cells::paths::CellRelativePath;
-use buck2_core::fs::buck_out_path::BuckOutPathResolver;
 use buck2_core::fs::project_rel_path::ProjectRelativePath;
 use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
-use buck2_directory::directory::directory::Directory;
 use buck2_error::BuckErrorContext;
-use buck2_error::internal_error;
-use buck2_execute::artifact_value::ArtifactValue;
 use buck2_execute::digest_config::HasDigestConfig;
-use buck2_execute::directory::INTERNER;
-use buck2_execute::entry::build_entry_from_disk;
 use buck2_execute::execute::blocking::HasBlockingExecutor;
 use buck2_execute::execute::blocking::IoRequest;
 use buck2_execute::execute::clean_output_paths::CleanOutputPaths;
-use buck2_execute::materialize::materializer::DeclareArtifactPayload;
 use buck2_execute::materialize::materializer::HasMaterializer;
 use buck2_execute::materialize::materializer::Materializer;
 use buck2_fs::fs_util;
@@ -51,7 +36,6 @@ use buck2_fs::paths::abs_norm_path::AbsNormPath;
 use buck2_fs::paths::forward_rel_path::ForwardRelativePath;
 use buck2_hash::BuckMutMap;
 use buck2_util::process::background_command;
-use cmp_any::PartialEqAny;
 use dice::CancellationContext;
 use dice::DiceComputations;
 use dice::EqualityBehavior;
@@ -63,6 +47,9 @@ use pagable::Pagable;
 use pagable::pagable_typetag;
 use tokio::sync::Semaphore;
 
+use crate::materialized::MaterializedCellFileOpsDelegate;
+use crate::materialized::declare_fetched_directory;
+
 #[derive(buck2_error::Error, Debug)]
 #[buck2(tag = Tier0)]
 enum GitError {
@@ -71,8 +58,6 @@ enum GitError {
         exit_code: ExitStatus,
         stderr: String,
     },
-    #[error("Expected git to create a directory at the checkout location")]
-    NoDirectory,
 }
 
 struct GitFetchIoRequest {
@@ -207,32 +192,7 @@ async fn download_impl(
     )
     .await?;
 
-    // Read and hash the contents. We have to do this because the materializer requires an artifact
-    // value. This work is kind of duplicated with the reading in the fileops, but only the first
-    // time the contents are downloaded. On subsequent invocations of the daemon, we won't rerun
-    // this however, so that case will still avoid doing unnecessary work.
-    let io_prov = ctx.global_data().get_io_provider();
-    let proj_root = io_prov.project_root().root();
-    let abs_path = proj_root.join(path);
-    let digest_config = ctx.global_data().get_digest_config();
-    let file_digest_config = FileDigestConfig::build(digest_config.cas_digest_config());
-    let entry = build_entry_from_disk(abs_path, file_digest_config, io, proj_root)
-        .await?
-        .0
-        .ok_or(GitError::NoDirectory)?;
-    let entry = entry.map_dir(|d| {
-        d.to_builder()
-            .fingerprint(digest_config.as_directory_serializer())
-            .shared(&*INTERNER)
-    });
-
-    materializer
-        .declare_existing(vec![DeclareArtifactPayload {
-            path: path.to_owned(),
-            artifact: ArtifactValue::new(entry, None),
-            configuration_path: None,
-        }])
-        .await?;
+    declare_fetched_directory(ctx, path, materializer).await?;
 
     Ok(())
 }
@@ -309,95 +269,11 @@ async fn download_and_materialize(
     res
 }
 
-#[derive(allocative::Allocative, Pagable)]
-pub(crate) struct GitFileOpsDelegate {
-    buck_out_resolver: BuckOutPathResolver,
-    cell: CellName,
-    setup: GitCellSetup,
-    // The fs accesses in this code are sort of a mix between source file accesses and buck-out
-    // accesses. Unconditionally using an `FsIoProvider` turns out to give all the right behavior
-    io: FsIoProvider,
-}
-
-impl GitFileOpsDelegate {
-    fn resolve(&self, path: &CellRelativePath) -> ProjectRelativePathBuf {
-        self.buck_out_resolver
-            .resolve_external_cell_source(path, ExternalCellOrigin::Git(self.setup.dupe()))
-    }
-
-    fn get_base_path(&self) -> ProjectRelativePathBuf {
-        self.resolve(CellRelativePath::empty())
-    }
-}
-
-#[pagable_typetag]
-#[async_trait::async_trait]
-impl FileOpsDelegate for GitFileOpsDelegate {
-    async fn read_file_if_exists(
-        &self,
-        _ctx: &mut DiceComputations<'_>,
-        path: &'async_trait CellRelativePath,
-    ) -> buck2_error::Result<Option<String>> {
-        let project_path = self.resolve(path);
-        (&self.io as &dyn IoProvider)
-            .read_file_if_exists(project_path)
-            .await
-    }
-
-    async fn read_dir(
-        &self,
-        _ctx: &mut DiceComputations<'_>,
-        path: &'async_trait CellRelativePath,
-    ) -> buck2_error::Result<Arc<[RawDirEntry]>> {
-        let project_path = self.resolve(path);
-        let mut entries = (&self.io as &dyn IoProvider)
-            .read_dir(project_path)
-            .await
-            .with_buck_error_context(|| format!("Error listing dir `{path}`"))?
-            .into_entries();
-
-        // Make sure entries are deterministic, since read_dir isn't.
-        entries.sort_by(|a, b| a.file_name.cmp(&b.file_name));
-
-        Ok(entries.into())
-    }
-
-    async fn read_path_metadata_if_exists(
-        &self,
-        _ctx: &mut DiceComputations<'_>,
-        path: &'async_trait CellRelativePath,
-    ) -> buck2_error::Result<Option<RawPathMetadata>> {
-        let project_path = self.resolve(path);
-
-        let Some(metadata) = (&self.io as &dyn IoProvider)
-            .read_path_metadata_if_exists(project_path)
-            .await
-            .with_buck_error_context(|| format!("Error accessing metadata for path `{path}`"))?
-        else {
-            return Ok(None);
-        };
-        Ok(Some(metadata.try_map(
-            |path| match path.strip_prefix_opt(self.get_base_path()) {
-                Some(path) => Ok(Arc::new(CellPath::new(self.cell, path.to_owned().into()))),
-                None => Err(internal_error!(
-                    "Non-cell internal symlink at `{}` in cell `{}`",
-                    path,
-                    self.cell
-                )),
-            },
-        )?))
-    }
-
-    fn eq_token(&self) -> PartialEqAny<'_> {
-        PartialEqAny::always_false()
-    }
-}
-
 pub(crate) async fn get_file_ops_delegate(
     ctx: &mut DiceComputations<'_>,
     cell: CellName,
     setup: GitCellSetup,
-) -> buck2_error::Result<Arc<GitFileOpsDelegate>> {
+) -> buck2_error::Result<Arc<MaterializedCellFileOpsDelegate>> {
     #[derive(
         dupe::Dupe,
         Clone,
@@ -415,7 +291,7 @@ pub(crate) async fn get_file_ops_delegate(
 
     #[async_trait::async_trait]
     impl Key for GitFileOpsDelegateKey {
-        type Value = buck2_error::Result<Arc<GitFileOpsDelegate>>;
+        type Value = buck2_error::Result<Arc<MaterializedCellFileOpsDelegate>>;
 
         async fn compute(
             &self,
@@ -423,16 +299,16 @@ pub(crate) async fn get_file_ops_delegate(
             cancellations: &CancellationContext,
         ) -> Self::Value {
             let artifact_fs = ctx.get_artifact_fs().await?;
-            let ops = GitFileOpsDelegate {
-                buck_out_resolver: artifact_fs.buck_out_path_resolver().clone(),
-                cell: self.0,
-                setup: self.1.dupe(),
-                io: FsIoProvider::new(
+            let ops = MaterializedCellFileOpsDelegate::new(
+                artifact_fs.buck_out_path_resolver().clone(),
+                self.0,
+                ExternalCellOrigin::Git(self.1.dupe()),
+                FsIoProvider::new(
                     artifact_fs.fs().dupe(),
                     ctx.global_data().get_digest_config().cas_digest_config(),
                     false,
                 ),
-            };
+            );
             download_and_materialize(ctx, &ops.get_base_path(), &self.1, cancellations).await?;
             Ok(Arc::new(ops))
         }
@@ -456,7 +332,7 @@ pub(crate) async fn materialize_all(
     cell: CellName,
     setup: GitCellSetup,
 ) -> buck2_error::Result<ProjectRelativePathBuf> {
-    // Get the `GitFileOpsDelegate` instance to make sure all the data is materialized.
+    // Get the file ops delegate to make sure all the data is materialized.
     let ops = get_file_ops_delegate(ctx, cell, setup.dupe()).await?;
     Ok(ops.get_base_path())
 }
@@ -0,0 +1,901 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is dual-licensed under either the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree or the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree. You may select, at your option, one of the
+ * above-listed licenses.
+ */
+
+use std::collections::hash_map;
+use std::io::Read;
+use std::io::Seek;
+use std::io::Write;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::OnceLock;
+
+use buck2_build_api::actions::artifact::get_artifact_fs::GetArtifactFs;
+use buck2_common::dice::data::HasIoProvider;
+use buck2_common::http::HasHttpClient;
+use buck2_common::io::fs::FsIoProvider;
+use buck2_core::cells::external::ArchiveType;
+use buck2_core::cells::external::ExternalCellOrigin;
+use buck2_core::cells::external::HttpArchiveCellSetup;
+use buck2_core::cells::name::CellName;
+use buck2_core::fs::project::ProjectRoot;
+use buck2_core::fs::project_rel_path::ProjectRelativePath;
+use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
+use buck2_error::BuckErrorContext;
+use buck2_error::BuckErrorOptionContext;
+use buck2_execute::digest_config::HasDigestConfig;
+use buck2_execute::execute::blocking::HasBlockingExecutor;
+use buck2_execute::execute::blocking::IoRequest;
+use buck2_execute::execute::clean_output_paths::CleanOutputPaths;
+use buck2_execute::materialize::http::Checksum;
+use buck2_execute::materialize::http::http_download;
+use buck2_execute::materialize::materializer::HasMaterializer;
+use buck2_execute::materialize::materializer::Materializer;
+use buck2_fs::error::IoResultExt;
+use buck2_fs::fs_util;
+use buck2_fs::paths::abs_path::AbsPathBuf;
+use buck2_hash::BuckMutMap;
+use dice::CancellationContext;
+use dice::DiceComputations;
+use dice::EqualityBehavior;
+use dice::Key;
+use dice::OkPagableValueSerialize;
+use dice::ValueSerialize;
+use dupe::Dupe;
+use pagable::Pagable;
+use pagable::pagable_typetag;
+use sha2::Digest;
+use sha2::Sha256;
+use tokio::sync::Semaphore;
+
+use crate::materialized::MaterializedCellFileOpsDelegate;
+use crate::materialized::declare_fetched_directory;
+
+#[derive(buck2_error::Error, Debug)]
+#[buck2(tag = Input)]
+enum HttpArchiveError {
+    #[error("Invalid sha256 digest for `{url}`. Expected {expected}, got {obtained}")]
+    InvalidChecksum {
+        url: String,
+        expected: String,
+        obtained: String,
+    },
+    #[error("Unsupported url `{0}` for http_archive external cell, expected http(s):// or file://")]
+    UnsupportedUrl(String),
+    #[error("Archive entry `{0}` escapes the external cell root")]
+    UnsafeEntryPath(String),
+    #[error("Archive hard link `{0}` does not point to a file inside the external cell")]
+    InvalidHardLink(String),
+    #[error("Archive symlink `{0}` points to `{1}`, outside of the external cell")]
+    UnsafeSymlink(String, String),
+    #[error("Archive entry `{0}` would be unpacked through a symlink")]
+    EntryThroughSymlink(String),
+    #[error("`strip_prefix = {0}` did not match any entry in the archive")]
+    StripPrefixNotFound(String),
+}
+
+/// Copy an archive from a `file://` url into buck-out, verifying its sha256 on the way.
+struct LocalArchiveFetchIoRequest {
+    url: Arc<str>,
+    source: AbsPathBuf,
+    sha256: Arc<str>,
+    dest: ProjectRelativePathBuf,
+}
+
+impl IoRequest for LocalArchiveFetchIoRequest {
+    fn execute(self: Box<Self>, project_fs: &ProjectRoot) -> buck2_error::Result<()> {
+        let dest = project_fs.resolve(&self.dest);
+        if let Some(parent) = dest.parent() {
+            fs_util::create_dir_all(parent)?;
+        }
+
+        let mut reader = fs_util::open_file(&self.source).categorize_input()?;
+        let mut writer = fs_util::create_file(&dest).categorize_internal()?;
+        let mut hasher = Sha256::new();
+        let mut buf = vec![0; 64 * 1024];
+        loop {
+            let n = reader
+                .read(&mut buf)
+                .with_buck_error_context(|| format!("read({})", self.source))?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buf[..n]);
+            writer
+                .write_all(&buf[..n])
+                .with_buck_error_context(|| format!("write({dest})"))?;
+        }
+        writer
+            .flush()
+            .with_buck_error_context(|| format!("flush({dest})"))?;
+
+        let obtained = hex::encode(hasher.finalize());
+        if obtained != *self.sha256 {
+            return Err(HttpArchiveError::InvalidChecksum {
+                url: self.url.to_string(),
+                expected: self.sha256.to_string(),
+                obtained,
+            }
+            .into());
+        }
+
+        Ok(())
+    }
+}
+
+/// Unpack a downloaded archive into the external cell's directory in buck-out.
+struct ArchiveExtractIoRequest {
+    archive: ProjectRelativePathBuf,
+    archive_type: ArchiveType,
+    strip_prefix: Option<Arc<str>>,
+    path: ProjectRelativePathBuf,
+}
+
+/// Compute where an archive entry should be unpacked relative to the cell root, or `None` if it
+/// lies outside of `strip_prefix` (or is the prefix directory itself).
+fn entry_destination(
+    entry_path: &Path,
+    strip_prefix: Option<&Path>,
+) -> buck2_error::Result<Option<PathBuf>> {
+    let mut normalized = PathBuf::new();
+    for component in entry_path.components() {
+        match component {
+            Component::Normal(c) => normalized.push(c),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                return Err(
+                    HttpArchiveError::UnsafeEntryPath(entry_path.display().to_string()).into(),
+                );
+            }
+        }
+    }
+
+    let relative = match strip_prefix {
+        None => normalized,
+        Some(prefix) => match normalized.strip_prefix(prefix) {
+            Ok(rest) => rest.to_owned(),
+            Err(_) => return Ok(None),
+        },
+    };
+
+    if relative.as_os_str().is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(relative))
+    }
+}
+
+/// Error if a symlink unpacked at `relative` and pointing to `link_target` would point outside of
+/// the cell.
+fn check_symlink_target(relative: &Path, link_target: &Path) -> buck2_error::Result<()> {
+    let unsafe_symlink = || {
+        HttpArchiveError::UnsafeSymlink(
+            relative.display().to_string(),
+            link_target.display().to_string(),
+        )
+    };
+    // Number of directories between the cell root and the symlink's target so far.
+    let mut depth = relative.components().count().saturating_sub(1);
+    for component in link_target.components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => {}
+            Component::ParentDir => depth = depth.checked_sub(1).ok_or_else(unsafe_symlink)?,
+            Component::RootDir | Component::Prefix(_) => return Err(unsafe_symlink().into()),
+        }
+    }
+    Ok(())
+}
+
+/// State shared by the extraction of every entry of an archive.
+struct Extraction<'a> {
+    dest: AbsPathBuf,
+    strip_prefix: Option<&'a Path>,
+    matched_prefix: bool,
+}
+
+impl<'a> Extraction<'a> {
+    fn new(
+        request: &'a ArchiveExtractIoRequest,
+        project_fs: &ProjectRoot,
+    ) -> buck2_error::Result<Self> {
+        let dest = project_fs.resolve(&request.path).into_abs_path_buf();
+        fs_util::create_dir_all(&dest)?;
+        Ok(Self {
+            dest,
+            strip_prefix: request.strip_prefix.as_deref().map(Path::new),
+            matched_prefix: false,
+        })
+    }
+
+    /// Compute where an entry should be unpacked, relative to the cell root and absolute, and
+    /// create its parent directory. Returns `None` for entries outside of `strip_prefix`.
+    fn destination(
+        &mut self,
+        entry_path: &Path,
+    ) -> buck2_error::Result<Option<(PathBuf, AbsPathBuf)>> {
+        let Some(relative) = entry_destination(entry_path, self.strip_prefix)? else {
+            return Ok(None);
+        };
+        self.matched_prefix = true;
+        self.check_no_symlinks(&relative)?;
+
+        let target = self.dest.join(&relative);
+        if let Some(parent) = target.parent() {
+            fs_util::create_dir_all(parent)?;
+        }
+        Ok(Some((relative, target)))
+    }
+
+    /// Error if `relative` goes through a symlink that was already unpacked, since the symlink
+    /// could be used to write (or, for hard links, read) files outside of the cell.
+    fn check_no_symlinks(&self, relative: &Path) -> buck2_error::Result<()> {
+        let mut path = self.dest.clone();
+        for component in relative.components() {
+            path.push(component);
+            match fs_util::symlink_metadata(&path) {
+                Ok(metadata) if metadata.file_type().is_symlink() => {
+                    return Err(HttpArchiveError::EntryThroughSymlink(
+                        relative.display().to_string(),
+                    )
+                    .into());
+                }
+                Ok(_) => {}
+                // Nothing was unpacked there yet, nor below it.
+                Err(_) => break,
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(self, strip_prefix: Option<&Arc<str>>) -> buck2_error::Result<()> {
+        if let Some(prefix) = strip_prefix {
+            if !self.matched_prefix {
+                return Err(HttpArchiveError::StripPrefixNotFound(prefix.to_string()).into());
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ArchiveExtractIoRequest {
+    fn extract_tar(&self, reader: impl Read, project_fs: &ProjectRoot) -> buck2_error::Result<()> {
+        let mut extraction = Extraction::new(self, project_fs)?;
+
+        let mut archive = tar::Archive::new(reader);
+        for entry in archive
+            .entries()
+            .buck_error_context("Error reading archive for external cell")?
+        {
+            let mut entry = entry.buck_error_context("Error reading archive entry")?;
+            let entry_path = entry
+                .path()
+                .buck_error_context("Archive entry has an invalid path")?
+                .into_owned();
+            let Some((relative, target)) = extraction.destination(&entry_path)? else {
+                continue;
+            };
+
+            let entry_type = entry.header().entry_type();
+            if entry_type.is_hard_link() {
+                // Hard links are relative to the archive root, so they need the same prefix
+                // handling as regular entries. We copy rather than link so that the cell
+                // contents remain independent files.
+                let invalid_hard_link =
+                    || HttpArchiveError::InvalidHardLink(entry_path.display().to_string());
+                let link_name = entry
+                    .link_name()
+                    .buck_error_context("Archive hard link has an invalid target")?
+                    .ok_or_else(invalid_hard_link)?;
+                let link_relative = entry_destination(&link_name, extraction.strip_prefix)?
+                    .ok_or_else(invalid_hard_link)?;
+                extraction.check_no_symlinks(&link_relative)?;
+                fs_util::copy(extraction.dest.join(link_relative), &target)
+                    .categorize_internal()?;
+                continue;
+            }
+
+            if entry_type.is_symlink() {
+                let link_name = entry
+                    .link_name()
+                    .buck_error_context("Archive symlink has an invalid target")?
+                    .with_buck_error_context(|| {
+                        format!("Archive symlink `{}` has no target", entry_path.display())
+                    })?;
+                check_symlink_target(&relative, &link_name)?;
+            }
+
+            entry
+                .unpack(&target)
+                .with_buck_error_context(|| format!("Error unpacking `{}`", relative.display()))?;
+        }
+
+        extraction.finish(self.strip_prefix.as_ref())
+    }
+
+    fn extract_zip(
+        &self,
+        reader: impl Read + Seek,
+        project_fs: &ProjectRoot,
+    ) -> buck2_error::Result<()> {
+        let mut extraction = Extraction::new(self, project_fs)?;
+
+        let mut archive = zip::ZipArchive::new(reader)
+            .buck_error_context("Error reading archive for external cell")?;
+        for i in 0..archive.len() {
+            let mut entry = archive
+                .by_index(i)
+                .buck_error_context("Error reading archive entry")?;
+            let entry_path = PathBuf::from(entry.name());
+            let Some((relative, target)) = extraction.destination(&entry_path)? else {
+                continue;
+            };
+
+            if entry.is_dir() {
+                fs_util::create_dir_all(&target)?;
+            } else if entry.is_symlink() {
+                let mut link_name = String::new();
+                entry
+                    .read_to_string(&mut link_name)
+                    .with_buck_error_context(|| {
+                        format!("Error reading symlink `{}`", relative.display())
+                    })?;
+                check_symlink_target(&relative, Path::new(&link_name))?;
+                fs_util::symlink(&link_name, &target).categorize_internal()?;
+            } else {
+                let mut file = fs_util::create_file(&target).categorize_internal()?;
+                std::io::copy(&mut entry, &mut file).with_buck_error_context(|| {
+                    format!("Error unpacking `{}`", relative.display())
+                })?;
+                if entry.unix_mode().is_some_and(|mode| mode & 0o111 != 0) {
+                    fs_util::set_executable(&target, true).categorize_internal()?;
+                }
+            }
+        }
+
+        extraction.finish(self.strip_prefix.as_ref())
+    }
+}
+
+impl IoRequest for ArchiveExtractIoRequest {
+    fn execute(self: Box<Self>, project_fs: &ProjectRoot) -> buck2_error::Result<()> {
+        let file = std::io::BufReader::new(
+            fs_util::open_file(project_fs.resolve(&self.archive)).categorize_internal()?,
+        );
+        match self.archive_type {
+            ArchiveType::Tar => self.extract_tar(file, project_fs),
+            ArchiveType::TarGz => self.extract_tar(flate2::read::GzDecoder::new(file), project_fs),
+            ArchiveType::TarZst => self.extract_tar(
+                zstd::stream::read::Decoder::with_buffer(file)
+                    .buck_error_context("Error initializing zstd decoder")?,
+                project_fs,
+            ),
+            ArchiveType::Zip => self.extract_zip(file, project_fs),
+        }
+    }
+}
+
+fn archive_download_path(path: &ProjectRelativePath) -> ProjectRelativePathBuf {
+    ProjectRelativePathBuf::unchecked_new(format!("{path}.archive"))
+}
+
+async fn fetch_archive(
+    ctx: &mut DiceComputations<'_>,
+    setup: &HttpArchiveCellSetup,
+    archive: &ProjectRelativePath,
+    cancellations: &CancellationContext,
+) -> buck2_error::Result<()> {
+    let io = ctx.get_blocking_executor();
+    let http_client = ctx.per_transaction_data().get_http_client().dupe();
+    let io_prov = ctx.global_data().get_io_provider();
+    let digest_config = ctx.global_data().get_digest_config();
+    let checksum = Checksum::Sha256(setup.sha256.dupe());
+
+    // Try each mirror in turn, reporting the last error if none of them work.
+    let mut last_error = None;
+    for url in setup.urls.iter() {
+        let res = if let Some(local) = url.strip_prefix("file://") {
+            async {
+                io.execute_io(
+                    Box::new(LocalArchiveFetchIoRequest {
+                        url: url.dupe(),
+                        source: AbsPathBuf::new(local)?,
+                        sha256: setup.sha256.dupe(),
+                        dest: archive.to_owned(),
+                    }),
+                    cancellations,
+                )
+                .await
+            }
+            .await
+        } else if url.starts_with("http://") || url.starts_with("https://") {
+            http_download(
+                &http_client,
+                io_prov.project_root(),
+                digest_config,
+                archive,
+                url,
+                &checksum,
+                false,
+            )
+            .await
+            .map(|_digest| ())
+        } else {
+            Err(HttpArchiveError::UnsupportedUrl(url.to_string()).into())
+        };
+
+        match res {
+            Ok(()) => return Ok(()),
+            Err(e) => last_error = Some(e),
+        }
+    }
+
+    // `urls` is guaranteed to be non-empty by the buckconfig parser.
+    let last_error = last_error.internal_error("http_archive external cell has no urls")?;
+    Err(last_error.context(format!("Error fetching external cell {setup}")))
+}
+
+async fn download_impl(
+    ctx: &mut DiceComputations<'_>,
+    setup: &HttpArchiveCellSetup,
+    path: &ProjectRelativePath,
+    materializer: &dyn Materializer,
+    cancellations: &CancellationContext,
+) -> buck2_error::Result<()> {
+    let io = ctx.get_blocking_executor();
+    let archive = archive_download_path(path);
+    io.execute_io(
+        Box::new(CleanOutputPaths {
+            paths: vec![path.to_owned(), archive.clone()],
+        }),
+        cancellations,
+    )
+    .await?;
+
+    fetch_archive(ctx, setup, &archive, cancellations).await?;
+
+    io.execute_io(
+        Box::new(ArchiveExtractIoRequest {
+            archive: archive.clone(),
+            archive_type: setup.archive_type,
+            strip_prefix: setup.strip_prefix.dupe(),
+            path: path.to_owned(),
+        }),
+        cancellations,
+    )
+    .await?;
+
+    // The archive itself is not part of the cell, and there's no reason to keep it around once
+    // it's been unpacked.
+    io.execute_io(
+        Box::new(CleanOutputPaths {
+            paths: vec![archive],
+        }),
+        cancellations,
+    )
+    .await?;
+
+    declare_fetched_directory(ctx, path, materializer).await?;
+
+    Ok(())
+}
+
+struct DirectoryLicense {
+    semaphore: Semaphore,
+    /// The result of populating the directory, set before `semaphore` is released.
+    result: OnceLock<buck2_error::Result<()>>,
+}
+
+async fn download_and_materialize(
+    ctx: &mut DiceComputations<'_>,
+    path: &ProjectRelativePath,
+    setup: &HttpArchiveCellSetup,
+    cancellations: &CancellationContext,
+) -> buck2_error::Result<()> {
+    let materializer = ctx.per_transaction_data().get_materializer();
+
+    if materializer.has_artifact_at(path.to_owned()).await? {
+        return Ok(());
+    }
+
+    // A map of archive digests to semaphores that are actually condvars which protect access to
+    // the directory associated with that archive. See the equivalent in `git.rs`.
+    static DIRECTORY_LICENSES: OnceLock<Mutex<BuckMutMap<Arc<str>, Arc<DirectoryLicense>>>> =
+        OnceLock::new();
+
+    let license;
+    let semaphore_guard;
+    'populate: {
+        'wait: {
+            let mut map_guard = DIRECTORY_LICENSES
+                .get_or_init(Default::default)
+                .lock()
+                .unwrap();
+            let entry = map_guard.entry(setup.sha256.dupe());
+
+            match entry {
+                hash_map::Entry::Occupied(entry) => {
+                    // Someone else is populating this directory; the sha256 uniquely identifies
+                    // the contents, so we just need to wait for them.
+                    license = entry.get().dupe();
+                    break 'wait;
+                }
+                hash_map::Entry::Vacant(entry) => {
+                    license = Arc::new(DirectoryLicense {
+                        semaphore: Semaphore::new(1),
+                        result: OnceLock::new(),
+                    });
+                    semaphore_guard = license.semaphore.try_acquire().unwrap(); // we know there's a permit available
+                    entry.insert(license.dupe());
+                    break 'populate;
+                }
+            }
+        }
+
+        drop(license.semaphore.acquire().await.unwrap());
+        // Report the failure of whoever populated the directory, since we didn't retry.
+        return license
+            .result
+            .get()
+            .cloned()
+            .internal_error("External cell download finished without a result")?;
+    }
+
+    // Like for git, don't allow the download to be cancelled part way through.
+    let res = cancellations
+        .critical_section(|| download_impl(ctx, setup, path, &*materializer, cancellations))
+        .await;
+
+    // Set before releasing the semaphore, for the waiters to see.
+    let _ignored = license.result.set(res.clone());
+    drop(semaphore_guard);
+    DIRECTORY_LICENSES
+        .get()
+        .unwrap()
+        .lock()
+        .unwrap()
+        .remove(&setup.sha256)
+        .unwrap();
+
+    res
+}
+
+pub(crate) async fn get_file_ops_delegate(
+    ctx: &mut DiceComputations<'_>,
+    cell: CellName,
+    setup: HttpArchiveCellSetup,
+) -> buck2_error::Result<Arc<MaterializedCellFileOpsDelegate>> {
+    #[derive(
+        dupe::Dupe,
+        Clone,
+        Debug,
+        derive_more::Display,
+        PartialEq,
+        Eq,
+        Hash,
+        allocative::Allocative,
+        Pagable
+    )]
+    #[display("({}, {})", _0, _1)]
+    #[pagable_typetag(dice::DiceKeyDyn)]
+    struct HttpArchiveFileOpsDelegateKey(CellName, HttpArchiveCellSetup);
+
+    #[async_trait::async_trait]
+    impl Key for HttpArchiveFileOpsDelegateKey {
+        type Value = buck2_error::Result<Arc<MaterializedCellFileOpsDelegate>>;
+
+        async fn compute(
+            &self,
+            ctx: &mut DiceComputations,
+            cancellations: &CancellationContext,
+        ) -> Self::Value {
+            let artifact_fs = ctx.get_artifact_fs().await?;
+            let ops = MaterializedCellFileOpsDelegate::new(
+                artifact_fs.buck_out_path_resolver().clone(),
+                self.0,
+                ExternalCellOrigin::HttpArchive(self.1.dupe()),
+                FsIoProvider::new(
+                    artifact_fs.fs().dupe(),
+                    ctx.global_data().get_digest_config().cas_digest_config(),
+                    false,
+                ),
+            );
+            download_and_materialize(ctx, &ops.get_base_path(), &self.1, cancellations).await?;
+            Ok(Arc::new(ops))
+        }
+
+        fn equality_behavior() -> EqualityBehavior<Self::Value> {
+            EqualityBehavior::AlwaysUnequal
+        }
+
+        fn value_serialize() -> impl ValueSerialize<Value = Self::Value> {
+            OkPagableValueSerialize::<Self::Value>::new()
+        }
+    }
+
+    ctx.compute(&HttpArchiveFileOpsDelegateKey(cell, setup))
+        .await?
+        .dupe()
+}
+
+pub(crate) async fn materialize_all(
+    ctx: &mut DiceComputations<'_>,
+    cell: CellName,
+    setup: HttpArchiveCellSetup,
+) -> buck2_error::Result<ProjectRelativePathBuf> {
+    // Get the file ops delegate to make sure all the data is materialized.
+    let ops = get_file_ops_delegate(ctx, cell, setup).await?;
+    Ok(ops.get_base_path())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::path::Path;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use buck2_core::cells::external::ArchiveType;
+    use buck2_core::fs::project::ProjectRootTemp;
+    use buck2_core::fs::project_rel_path::ProjectRelativePath;
+    use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
+    use buck2_execute::execute::blocking::IoRequest;
+    use buck2_fs::fs_util;
+    use buck2_fs::paths::forward_rel_path::ForwardRelativePath;
+    use sha2::Digest;
+    use sha2::Sha256;
+
+    use super::ArchiveExtractIoRequest;
+    use super::LocalArchiveFetchIoRequest;
+    use super::entry_destination;
+
+    fn write_tar_gz(path: &Path, entries: &[(&str, &str)]) {
+        let file = std::fs::File::create(path).unwrap();
+        let encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+        for (name, contents) in entries {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, name, contents.as_bytes())
+                .unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap();
+    }
+
+    #[test]
+    fn test_entry_destination() {
+        let prefix = Some(Path::new("libfoo-1.0"));
+        assert_eq!(
+            entry_destination(Path::new("./libfoo-1.0/src/lib.rs"), prefix).unwrap(),
+            Some(PathBuf::from("src/lib.rs"))
+        );
+        assert_eq!(
+            entry_destination(Path::new("libfoo-1.0/"), prefix).unwrap(),
+            None
+        );
+        assert_eq!(
+            entry_destination(Path::new("other/BUCK"), prefix).unwrap(),
+            None
+        );
+        assert_eq!(
+            entry_destination(Path::new("BUCK"), None).unwrap(),
+            Some(PathBuf::from("BUCK"))
+        );
+        assert!(entry_destination(Path::new("../escape"), None).is_err());
+        assert!(entry_destination(Path::new("/etc/passwd"), None).is_err());
+    }
+
+    #[test]
+    fn test_fetch_and_extract_local_archive() {
+        let fs = ProjectRootTemp::new().unwrap();
+        let source = fs
+            .path()
+            .resolve(ProjectRelativePath::new("src.tar.gz").unwrap());
+        write_tar_gz(
+            source.as_path(),
+            &[
+                ("libfoo-1.0/BUCK", "# buck"),
+                ("libfoo-1.0/src/lib.rs", "fn main() {}"),
+            ],
+        );
+        let sha256: Arc<str> = hex::encode(Sha256::digest(fs_util::read(&source).unwrap())).into();
+
+        let archive = ProjectRelativePathBuf::unchecked_new("out/cell.archive".to_owned());
+        let cell = ProjectRelativePathBuf::unchecked_new("out/cell".to_owned());
+
+        Box::new(LocalArchiveFetchIoRequest {
+            url: format!("file://{source}").into(),
+            source: source.clone().into_abs_path_buf(),
+            sha256,
+            dest: archive.clone(),
+        })
+        .execute(fs.path())
+        .unwrap();
+
+        Box::new(ArchiveExtractIoRequest {
+            archive,
+            archive_type: ArchiveType::TarGz,
+            strip_prefix: Some("libfoo-1.0".into()),
+            path: cell.clone(),
+        })
+        .execute(fs.path())
+        .unwrap();
+
+        assert_eq!(
+            fs_util::read_to_string(
+                fs.path()
+                    .resolve(cell.join(ForwardRelativePath::new("src/lib.rs").unwrap()))
+            )
+            .unwrap(),
+            "fn main() {}"
+        );
+    }
+
+    fn extract(
+        fs: &ProjectRootTemp,
+        archive_type: ArchiveType,
+        archive: &str,
+    ) -> buck2_error::Result<()> {
+        Box::new(ArchiveExtractIoRequest {
+            archive: ProjectRelativePathBuf::unchecked_new(archive.to_owned()),
+            archive_type,
+            strip_prefix: None,
+            path: ProjectRelativePathBuf::unchecked_new("out/cell".to_owned()),
+        })
+        .execute(fs.path())
+    }
+
+    fn write_tar_with_symlink(path: &Path, link: &str, link_target: &str, through_link: &str) {
+        let mut builder = tar::Builder::new(std::fs::File::create(path).unwrap());
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_size(0);
+        builder.append_link(&mut header, link, link_target).unwrap();
+        let mut header = tar::Header::new_gnu();
+        header.set_size(5);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, through_link, "owned".as_bytes())
+            .unwrap();
+        builder.into_inner().unwrap();
+    }
+
+    #[test]
+    fn test_extract_rejects_absolute_symlink() {
+        let fs = ProjectRootTemp::new().unwrap();
+        let outside = fs
+            .path()
+            .resolve(ProjectRelativePath::new("outside").unwrap());
+        fs_util::create_dir_all(&outside).unwrap();
+        write_tar_with_symlink(
+            fs.path()
+                .resolve(ProjectRelativePath::new("cell.tar").unwrap())
+                .as_path(),
+            "a",
+            &outside.to_string(),
+            "a/passwd",
+        );
+
+        let err = extract(&fs, ArchiveType::Tar, "cell.tar").unwrap_err();
+        assert!(
+            format!("{err:?}").contains("outside of the external cell"),
+            "error: {err:?}"
+        );
+        assert!(
+            !fs_util::try_exists(
+                fs.path()
+                    .resolve(ProjectRelativePath::new("outside/passwd").unwrap())
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_extract_rejects_relative_symlink_escape() {
+        let fs = ProjectRootTemp::new().unwrap();
+        write_tar_with_symlink(
+            fs.path()
+                .resolve(ProjectRelativePath::new("cell.tar").unwrap())
+                .as_path(),
+            "dir/a",
+            "../../escape",
+            "dir/a/passwd",
+        );
+
+        let err = extract(&fs, ArchiveType::Tar, "cell.tar").unwrap_err();
+        assert!(
+            format!("{err:?}").contains("outside of the external cell"),
+            "error: {err:?}"
+        );
+        assert!(
+            !fs_util::try_exists(
+                fs.path()
+                    .resolve(ProjectRelativePath::new("out/escape/passwd").unwrap())
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_extract_rejects_entry_through_symlink() {
+        let fs = ProjectRootTemp::new().unwrap();
+        // The symlink itself stays inside the cell, but nothing may be unpacked through it.
+        write_tar_with_symlink(
+            fs.path()
+                .resolve(ProjectRelativePath::new("cell.tar").unwrap())
+                .as_path(),
+            "a",
+            "b",
+            "a/passwd",
+        );
+
+        let err = extract(&fs, ArchiveType::Tar, "cell.tar").unwrap_err();
+        assert!(
+            format!("{err:?}").contains("through a symlink"),
+            "error: {err:?}"
+        );
+    }
+
+    #[test]
+    fn test_extract_zip() {
+        let fs = ProjectRootTemp::new().unwrap();
+        let archive = fs
+            .path()
+            .resolve(ProjectRelativePath::new("cell.zip").unwrap());
+        let mut writer = zip::ZipWriter::new(std::fs::File::create(archive.as_path()).unwrap());
+        writer
+            .add_directory("src/", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        writer
+            .start_file(
+                "src/lib.rs",
+                zip::write::SimpleFileOptions::default().unix_permissions(0o755),
+            )
+            .unwrap();
+        writer.write_all(b"fn main() {}").unwrap();
+        writer.finish().unwrap();
+
+        extract(&fs, ArchiveType::Zip, "cell.zip").unwrap();
+
+        let lib = fs
+            .path()
+            .resolve(ProjectRelativePath::new("out/cell/src/lib.rs").unwrap());
+        assert_eq!(fs_util::read_to_string(&lib).unwrap(), "fn main() {}");
+    }
+
+    #[test]
+    fn test_fetch_local_archive_bad_checksum() {
+        let fs = ProjectRootTemp::new().unwrap();
+        fs.write_file("src.tar.gz", "not really an archive");
+        let source = fs
+            .path()
+            .resolve(ProjectRelativePath::new("src.tar.gz").unwrap());
+
+        let err = Box::new(LocalArchiveFetchIoRequest {
+            url: "file:///src.tar.gz".into(),
+            source: source.into_abs_path_buf(),
+            sha256: "0".repeat(64).into(),
+            dest: ProjectRelativePathBuf::unchecked_new("out/cell.archive".to_owned()),
+        })
+        .execute(fs.path())
+        .unwrap_err();
+
+        assert!(
+            format!("{err:?}").contains("Invalid sha256 digest"),
+            "error: {err:?}"
+        );
+    }
+}
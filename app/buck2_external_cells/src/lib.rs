@@ -27,6 +27,8 @@ use dice::DiceComputations;
 
 mod bundled;
 mod git;
+mod http_archive;
+mod materialized;
 
 struct ConcreteExternalCellsImpl;
 
@@ -52,6 +54,9 @@ impl buck2_common::external_cells::ExternalCellsImpl for ConcreteExternalCellsIm
             ExternalCellOrigin::Git(setup) => {
                 Ok(git::get_file_ops_delegate(ctx, cell_name, setup).await? as _)
             }
+            ExternalCellOrigin::HttpArchive(setup) => {
+                Ok(http_archive::get_file_ops_delegate(ctx, cell_name, setup).await? as _)
+            }
         }
     }
 
@@ -92,6 +97,9 @@ impl buck2_common::external_cells::ExternalCellsImpl for ConcreteExternalCellsIm
         let materialized_path = match origin {
             ExternalCellOrigin::Bundled(cell) => bundled::materialize_all(ctx, cell).await?,
             ExternalCellOrigin::Git(setup) => git::materialize_all(ctx, cell, setup).await?,
+            ExternalCellOrigin::HttpArchive(setup) => {
+                http_archive::materialize_all(ctx, cell, setup).await?
+            }
         };
 
         Ok(io.project_root().copy(&materialized_path, &dest_path)?)
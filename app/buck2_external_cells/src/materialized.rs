@@ -0,0 +1,187 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is dual-licensed under either the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree or the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree. You may select, at your option, one of the
+ * above-listed licenses.
+ */
+
+//! Shared pieces for external cells whose contents are fetched into buck-out and then served
+//! directly from disk.
+
+use std::sync::Arc;
+
+use buck2_common::dice::data::HasIoProvider;
+use buck2_common::file_ops::delegate::FileOpsDelegate;
+use buck2_common::file_ops::metadata::FileDigestConfig;
+use buck2_common::file_ops::metadata::RawDirEntry;
+use buck2_common::file_ops::metadata::RawPathMetadata;
+use buck2_common::io::IoProvider;
+use buck2_common::io::fs::FsIoProvider;
+use buck2_core::cells::cell_path::CellPath;
+use buck2_core::cells::external::ExternalCellOrigin;
+use buck2_core::cells::name::CellName;
+use buck2_core::cells::paths::CellRelativePath;
+use buck2_core::fs::buck_out_path::BuckOutPathResolver;
+use buck2_core::fs::project_rel_path::ProjectRelativePath;
+use buck2_core::fs::project_rel_path::ProjectRelativePathBuf;
+use buck2_directory::directory::directory::Directory;
+use buck2_error::BuckErrorContext;
+use buck2_error::internal_error;
+use buck2_execute::artifact_value::ArtifactValue;
+use buck2_execute::digest_config::HasDigestConfig;
+use buck2_execute::directory::INTERNER;
+use buck2_execute::entry::build_entry_from_disk;
+use buck2_execute::execute::blocking::HasBlockingExecutor;
+use buck2_execute::materialize::materializer::DeclareArtifactPayload;
+use buck2_execute::materialize::materializer::Materializer;
+use cmp_any::PartialEqAny;
+use dice::DiceComputations;
+use pagable::Pagable;
+use pagable::pagable_typetag;
+
+#[derive(buck2_error::Error, Debug)]
+#[buck2(tag = Tier0)]
+enum MaterializedCellError {
+    #[error("Expected a directory at `{0}` after fetching external cell")]
+    NoDirectory(ProjectRelativePathBuf),
+}
+
+/// Hash the directory that was just written at `path` and tell the materializer about it.
+///
+/// We have to do this because the materializer requires an artifact value. This work is kind of
+/// duplicated with the reading in the fileops, but only the first time the contents are
+/// downloaded. On subsequent invocations of the daemon, we won't rerun this however, so that case
+/// will still avoid doing unnecessary work.
+pub(crate) async fn declare_fetched_directory(
+    ctx: &mut DiceComputations<'_>,
+    path: &ProjectRelativePath,
+    materializer: &dyn Materializer,
+) -> buck2_error::Result<()> {
+    let io = ctx.get_blocking_executor();
+    let io_prov = ctx.global_data().get_io_provider();
+    let proj_root = io_prov.project_root().root();
+    let abs_path = proj_root.join(path);
+    let digest_config = ctx.global_data().get_digest_config();
+    let file_digest_config = FileDigestConfig::build(digest_config.cas_digest_config());
+    let entry = build_entry_from_disk(abs_path, file_digest_config, io, proj_root)
+        .await?
+        .0
+        .ok_or_else(|| MaterializedCellError::NoDirectory(path.to_owned()))?;
+    let entry = entry.map_dir(|d| {
+        d.to_builder()
+            .fingerprint(digest_config.as_directory_serializer())
+            .shared(&*INTERNER)
+    });
+
+    materializer
+        .declare_existing(vec![DeclareArtifactPayload {
+            path: path.to_owned(),
+            artifact: ArtifactValue::new(entry, None),
+            configuration_path: None,
+        }])
+        .await?;
+
+    Ok(())
+}
+
+/// File ops for an external cell that has been fully materialized into buck-out.
+#[derive(allocative::Allocative, Pagable)]
+pub(crate) struct MaterializedCellFileOpsDelegate {
+    buck_out_resolver: BuckOutPathResolver,
+    cell: CellName,
+    origin: ExternalCellOrigin,
+    // The fs accesses in this code are sort of a mix between source file accesses and buck-out
+    // accesses. Unconditionally using an `FsIoProvider` turns out to give all the right behavior
+    io: FsIoProvider,
+}
+
+impl MaterializedCellFileOpsDelegate {
+    pub(crate) fn new(
+        buck_out_resolver: BuckOutPathResolver,
+        cell: CellName,
+        origin: ExternalCellOrigin,
+        io: FsIoProvider,
+    ) -> Self {
+        Self {
+            buck_out_resolver,
+            cell,
+            origin,
+            io,
+        }
+    }
+
+    fn resolve(&self, path: &CellRelativePath) -> ProjectRelativePathBuf {
+        self.buck_out_resolver
+            .resolve_external_cell_source(path, self.origin.clone())
+    }
+
+    pub(crate) fn get_base_path(&self) -> ProjectRelativePathBuf {
+        self.resolve(CellRelativePath::empty())
+    }
+}
+
+#[pagable_typetag]
+#[async_trait::async_trait]
+impl FileOpsDelegate for MaterializedCellFileOpsDelegate {
+    async fn read_file_if_exists(
+        &self,
+        _ctx: &mut DiceComputations<'_>,
+        path: &'async_trait CellRelativePath,
+    ) -> buck2_error::Result<Option<String>> {
+        let project_path = self.resolve(path);
+        (&self.io as &dyn IoProvider)
+            .read_file_if_exists(project_path)
+            .await
+    }
+
+    async fn read_dir(
+        &self,
+        _ctx: &mut DiceComputations<'_>,
+        path: &'async_trait CellRelativePath,
+    ) -> buck2_error::Result<Arc<[RawDirEntry]>> {
+        let project_path = self.resolve(path);
+        let mut entries = (&self.io as &dyn IoProvider)
+            .read_dir(project_path)
+            .await
+            .with_buck_error_context(|| format!("Error listing dir `{path}`"))?
+            .into_entries();
+
+        // Make sure entries are deterministic, since read_dir isn't.
+        entries.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+
+        Ok(entries.into())
+    }
+
+    async fn read_path_metadata_if_exists(
+        &self,
+        _ctx: &mut DiceComputations<'_>,
+        path: &'async_trait CellRelativePath,
+    ) -> buck2_error::Result<Option<RawPathMetadata>> {
+        let project_path = self.resolve(path);
+
+        let Some(metadata) = (&self.io as &dyn IoProvider)
+            .read_path_metadata_if_exists(project_path)
+            .await
+            .with_buck_error_context(|| format!("Error accessing metadata for path `{path}`"))?
+        else {
+            return Ok(None);
+        };
+        Ok(Some(metadata.try_map(
+            |path| match path.strip_prefix_opt(self.get_base_path()) {
+                Some(path) => Ok(Arc::new(CellPath::new(self.cell, path.to_owned().into()))),
+                None => Err(internal_error!(
+                    "Non-cell internal symlink at `{}` in cell `{}`",
+                    path,
+                    self.cell
+                )),
+            },
+        )?))
+    }
+
+    fn eq_token(&self) -> PartialEqAny<'_> {
+        PartialEqAny::always_false()
+    }
+}
@@ -34,12 +34,36 @@ use crate::mergebase::Mergebase;
 use crate::notify::NotifyFileWatcher;
 use crate::watchman::interface::WatchmanFileWatcher;
 
+/// A position in a file watcher's change stream. A watcher started later over the same project
+/// can resume from it and report every change made since, including changes made while no
+/// watcher was running.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct FileWatcherCheckpoint {
+    /// Which watcher produced the checkpoint; only that kind of watcher can resume from it.
+    pub provider: String,
+    pub clock: String,
+    pub mergebase: Option<String>,
+}
+
 #[async_trait]
 pub trait FileWatcher: Allocative + Send + Sync + 'static {
     async fn sync(
         &self,
         dice: DiceTransactionUpdater,
     ) -> buck2_error::Result<(DiceTransactionUpdater, Mergebase)>;
+
+    /// The position of the last sync, if this watcher supports resuming and has synced.
+    async fn checkpoint(&self) -> buck2_error::Result<Option<FileWatcherCheckpoint>> {
+        Ok(None)
+    }
+
+    /// Resume from a checkpoint taken by an earlier watcher, before this one first syncs. When
+    /// this returns true, the first sync invalidates everything that changed since the
+    /// checkpoint (or clears DICE if it can't tell), so state computed as of the checkpoint is
+    /// safe to reuse.
+    async fn resume(&self, _checkpoint: &FileWatcherCheckpoint) -> buck2_error::Result<bool> {
+        Ok(false)
+    }
 }
 
 /// Parse the `dice_clear_on_mergebase_change` config, honoring both the buckconfig
@@ -322,6 +322,8 @@ pub(crate) trait SyncableQueryProcessor: Send + Sync {
 /// commands to be sent to the SyncableQueryHandler.
 enum SyncableQueryCommand<T, P> {
     Sync(P, oneshot::Sender<buck2_error::Result<(T, P)>>),
+    Checkpoint(oneshot::Sender<Option<(String, Option<String>)>>),
+    Resume(String, Option<String>, oneshot::Sender<bool>),
 }
 
 /// A SyncableQuery is similar to a subscription. When created, it accepts a query expression
@@ -358,6 +360,7 @@ enum WatchmanSyncResult {
 /// processing will happen in a linear order.
 ///
 /// The SyncableQueryHandler maintains the clock and last mergebase and updates them with each request.
+/// Until the first sync, they can instead be seeded from an earlier handler's checkpoint.
 struct SyncableQueryHandler<T, P> {
     connector: Connector,
     path: CanonicalPath,
@@ -367,6 +370,9 @@ struct SyncableQueryHandler<T, P> {
     last_mergebase: Option<String>,
     mergebase_with: Option<String>,
     dice_clear_on_mergebase_change: bool,
+    /// Whether a sync has completed. Once it has, the clock can be checkpointed but no longer
+    /// resumed.
+    synced: bool,
     control_rx: UnboundedReceiver<SyncableQueryCommand<T, P>>,
 }
 
@@ -393,6 +399,12 @@ where
                     // job. That's fine.
                     let _ignore = sync_tx.send(res);
                 }
+                Some(SyncableQueryCommand::Checkpoint(checkpoint_tx)) => {
+                    let _ignore = checkpoint_tx.send(self.checkpoint());
+                }
+                Some(SyncableQueryCommand::Resume(clock, mergebase, resume_tx)) => {
+                    let _ignore = resume_tx.send(self.resume(clock, mergebase));
+                }
                 None => {
                     // This indicates the controlling SyncableQuery has been dropped.
                     return;
@@ -460,10 +472,32 @@ where
 
         self.last_mergebase = new_mergebase;
         self.last_clock = clock;
+        self.synced = true;
 
         Ok(res)
     }
 
+    fn checkpoint(&self) -> Option<(String, Option<String>)> {
+        if !self.synced {
+            return None;
+        }
+        match &self.last_clock {
+            ClockSpec::StringClock(clock) => Some((clock.clone(), self.last_mergebase.clone())),
+            _ => None,
+        }
+    }
+
+    /// Seed the clock so that the first sync reports changes since `clock`. If watchman no longer
+    /// knows that clock (e.g. it restarted), that sync reports a fresh instance as usual.
+    fn resume(&mut self, clock: String, mergebase: Option<String>) -> bool {
+        if self.synced {
+            return false;
+        }
+        self.last_clock = ClockSpec::StringClock(clock);
+        self.last_mergebase = mergebase;
+        true
+    }
+
     async fn reconnect(&mut self, client: &mut Option<WatchmanClient>) -> buck2_error::Result<()> {
         self.last_clock = Default::default();
         self.last_mergebase = None;
@@ -623,6 +657,33 @@ where
         }
     }
 
+    /// Returns the clock and mergebase as of the last successful sync, if there was one.
+    pub(crate) async fn checkpoint(&self) -> buck2_error::Result<Option<(String, Option<String>)>> {
+        let (tx, rx) = oneshot::channel();
+        self.control_tx
+            .send(SyncableQueryCommand::Checkpoint(tx))
+            .ok()
+            .internal_error("SyncableQueryHandler has exited")?;
+        rx.await
+            .buck_error_context("SyncableQueryHandler did not return a response for checkpoint")
+    }
+
+    /// Makes the next sync report changes since a clock obtained from `checkpoint()` on an earlier
+    /// query. Returns false if this query has already synced.
+    pub(crate) async fn resume(
+        &self,
+        clock: String,
+        mergebase: Option<String>,
+    ) -> buck2_error::Result<bool> {
+        let (tx, rx) = oneshot::channel();
+        self.control_tx
+            .send(SyncableQueryCommand::Resume(clock, mergebase, tx))
+            .ok()
+            .internal_error("SyncableQueryHandler has exited")?;
+        rx.await
+            .buck_error_context("SyncableQueryHandler did not return a response for resume")
+    }
+
     pub(crate) fn new(
         connector: Connector,
         path: impl AsRef<Path>,
@@ -663,6 +724,7 @@ where
                 last_mergebase: None,
                 mergebase_with,
                 dice_clear_on_mergebase_change,
+                synced: false,
                 processor,
                 control_rx,
             };
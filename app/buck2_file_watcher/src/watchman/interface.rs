@@ -33,6 +33,7 @@ use watchman_client::prelude::Connector;
 use watchman_client::prelude::FileType;
 
 use crate::file_watcher::FileWatcher;
+use crate::file_watcher::FileWatcherCheckpoint;
 use crate::mergebase::Mergebase;
 use crate::stats::FileWatcherStats;
 use crate::watchman::core::SyncableQuery;
@@ -341,6 +342,8 @@ impl SyncableQueryProcessor for WatchmanQueryProcessor {
     }
 }
 
+const WATCHMAN_PROVIDER: &str = "watchman";
+
 #[derive(Allocative)]
 pub(crate) struct WatchmanFileWatcher {
     #[allocative(skip)]
@@ -442,4 +445,25 @@ impl FileWatcher for WatchmanFileWatcher {
         )
         .await
     }
+
+    async fn checkpoint(&self) -> buck2_error::Result<Option<FileWatcherCheckpoint>> {
+        Ok(self
+            .query
+            .checkpoint()
+            .await?
+            .map(|(clock, mergebase)| FileWatcherCheckpoint {
+                provider: WATCHMAN_PROVIDER.to_owned(),
+                clock,
+                mergebase,
+            }))
+    }
+
+    async fn resume(&self, checkpoint: &FileWatcherCheckpoint) -> buck2_error::Result<bool> {
+        if checkpoint.provider != WATCHMAN_PROVIDER {
+            return Ok(false);
+        }
+        self.query
+            .resume(checkpoint.clock.clone(), checkpoint.mergebase.clone())
+            .await
+    }
 }
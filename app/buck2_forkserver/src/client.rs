@@ -17,7 +17,7 @@ use arc_swap::ArcSwapOption;
 use buck2_core::tag_error;
 use buck2_error::BuckErrorContext;
 use buck2_execute_local::CommandResult;
-use buck2_execute_local::decode_command_event_stream;
+use buck2_execute_local::decode_command_event_stream_reporting_stderr;
 use buck2_resource_control::ActionFreezeEvent;
 use buck2_resource_control::ActionFreezeEventReceiver;
 use dupe::Dupe;
@@ -90,6 +90,7 @@ impl ForkserverClient {
         req: buck2_forkserver_proto::CommandRequest,
         cancel: C,
         freeze_rx: impl ActionFreezeEventReceiver,
+        report_stderr: impl FnMut(&[u8]) + Send,
     ) -> buck2_error::Result<CommandResult>
     where
         C: Future<Output = ()> + Send + 'static,
@@ -130,7 +131,7 @@ impl ForkserverClient {
             .into_inner();
         let stream = decode_event_stream(stream);
 
-        decode_command_event_stream(stream).await
+        decode_command_event_stream_reporting_stderr(stream, report_stderr).await
     }
 
     pub async fn set_log_filter(&self, log_filter: String) -> buck2_error::Result<()> {
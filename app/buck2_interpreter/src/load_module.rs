@@ -10,6 +10,7 @@
 
 use async_trait::async_trait;
 use buck2_core::bzl::ImportPath;
+use buck2_core::cells::cell_path::CellPath;
 use buck2_core::package::PackageLabel;
 use buck2_util::late_binding::LateBinding;
 use dice::DiceComputations;
@@ -46,12 +47,25 @@ pub trait InterpreterCalculationImpl: Send + Sync + 'static {
         package: PackageLabel,
     ) -> buck2_error::Result<Option<(PackageFilePath, Vec<ImportPath>)>>;
 
+    /// Every Starlark, JSON or TOML file the evaluation of the package's build file reads:
+    /// the build file, the `PACKAGE` files of the package and its parents, and all the files
+    /// these load, transitively. Files are found by parsing only, nothing is evaluated.
+    async fn get_package_source_files(
+        &self,
+        ctx: &mut DiceComputations<'_>,
+        package: PackageLabel,
+    ) -> buck2_error::Result<Vec<CellPath>>;
+
     async fn global_env(&self, ctx: &mut DiceComputations<'_>) -> buck2_error::Result<Globals>;
 
     async fn prelude_import(
         &self,
         ctx: &mut DiceComputations<'_>,
     ) -> buck2_error::Result<Option<PreludePath>>;
+
+    /// Identifies what `host_info()` returns to build files, including the host platform and
+    /// architecture overrides.
+    async fn host_info(&self, ctx: &mut DiceComputations<'_>) -> buck2_error::Result<String>;
 }
 
 pub static INTERPRETER_CALCULATION_IMPL: LateBinding<&'static dyn InterpreterCalculationImpl> =
@@ -10,13 +10,16 @@
 
 //! Interpreter related Dice calculations
 
+use std::collections::HashSet;
 use std::sync::Arc;
 
 use allocative::Allocative;
 use async_trait::async_trait;
+use buck2_common::dice::cells::HasCellResolver;
 use buck2_common::package_listing::dice::DicePackageListingResolver;
 use buck2_core::build_file_path::BuildFilePath;
 use buck2_core::bzl::ImportPath;
+use buck2_core::cells::cell_path::CellPath;
 use buck2_core::package::PackageLabel;
 use buck2_events::dispatch::async_record_root_spans;
 use buck2_events::span::SpanId;
@@ -203,6 +206,47 @@ impl Key for EvalImportKey {
     }
 }
 
+/// Modules loaded by a module, found by parsing it without evaluating anything.
+///
+/// Unlike the imports of `EvalImportKey`, these are available without evaluating
+/// the module's dependencies.
+#[derive(Clone, Display, Debug, Eq, Hash, PartialEq, Allocative, Pagable)]
+#[pagable_typetag(dice::DiceKeyDyn)]
+struct ParsedImportsKey(OwnedStarlarkModulePath);
+
+#[async_trait]
+impl Key for ParsedImportsKey {
+    type Value = buck2_error::Result<Arc<Vec<OwnedStarlarkModulePath>>>;
+    async fn compute(
+        &self,
+        ctx: &mut DiceComputations,
+        _cancellation: &CancellationContext,
+    ) -> Self::Value {
+        let starlark_path = self.0.clone().into_starlark_path();
+        Ok(Arc::new(
+            ctx.get_interpreter_calculator(starlark_path.clone())
+                .await?
+                .parse_imports(starlark_path.borrow())
+                .await?,
+        ))
+    }
+
+    fn equality_behavior() -> EqualityBehavior<Self::Value> {
+        EqualityBehavior::Compare(|x, y| match (x, y) {
+            (Ok(x), Ok(y)) => x == y,
+            _ => false,
+        })
+    }
+
+    fn validity(x: &Self::Value) -> bool {
+        x.is_ok()
+    }
+
+    fn value_serialize() -> impl ValueSerialize<Value = Self::Value> {
+        OkPagableValueSerialize::<Self::Value>::new()
+    }
+}
+
 #[async_trait]
 impl InterpreterCalculationImpl for InterpreterCalculationInstance {
     fn get_loaded_module<'a, 'd>(
@@ -268,6 +312,75 @@ impl InterpreterCalculationImpl for InterpreterCalculationInstance {
         )))
     }
 
+    async fn get_package_source_files(
+        &self,
+        ctx: &mut DiceComputations<'_>,
+        package: PackageLabel,
+    ) -> buck2_error::Result<Vec<CellPath>> {
+        let build_file_name = DicePackageListingResolver(ctx)
+            .resolve_package_listing(package.dupe())
+            .await?
+            .buildfile()
+            .to_owned();
+        let build_file_path = BuildFilePath::new(package.dupe(), build_file_name);
+
+        let mut files = vec![build_file_path.path()];
+        let mut todo = ctx
+            .get_interpreter_calculator(OwnedStarlarkPath::BuildFile(build_file_path.clone()))
+            .await?
+            .parse_imports(StarlarkPath::BuildFile(&build_file_path))
+            .await?;
+
+        // `PACKAGE` files are looked up in parent directories across cell boundaries,
+        // the same way they are when evaluating the build file.
+        let cell_resolver = ctx.get_cell_resolver().await?;
+        let mut dir = Some(package);
+        while let Some(package) = dir {
+            let mut calc = ctx
+                .get_interpreter_calculator(OwnedStarlarkPath::PackageFile(
+                    PackageFilePath::package_file_for_dir(package.as_cell_path()),
+                ))
+                .await?;
+            if let Some(package_file_path) = calc.lookup_package_file(package.dupe()).await? {
+                todo.extend(
+                    calc.parse_imports(StarlarkPath::PackageFile(&package_file_path))
+                        .await?,
+                );
+                files.push(package_file_path.path().clone());
+            }
+            dir = match cell_resolver.resolve_path(package.as_cell_path())?.parent() {
+                None => None,
+                Some(parent) => Some(PackageLabel::from_cell_path(
+                    cell_resolver.get_cell_path(parent).as_ref(),
+                )?),
+            };
+        }
+
+        let mut seen = HashSet::new();
+        while let Some(import) = todo.pop() {
+            if !seen.insert(import.clone()) {
+                continue;
+            }
+            files.push(import.path().to_owned());
+            match import {
+                // Data files don't load anything.
+                OwnedStarlarkModulePath::JsonFile(_) | OwnedStarlarkModulePath::TomlFile(_) => {}
+                import => {
+                    todo.extend(
+                        ctx.compute(&ParsedImportsKey(import))
+                            .await??
+                            .iter()
+                            .cloned(),
+                    );
+                }
+            }
+        }
+
+        files.sort();
+        files.dedup();
+        Ok(files)
+    }
+
     async fn global_env(&self, ctx: &mut DiceComputations<'_>) -> buck2_error::Result<Globals> {
         Ok(ctx.get_global_interpreter_state().await?.globals().dupe())
     }
@@ -283,6 +396,15 @@ impl InterpreterCalculationImpl for InterpreterCalculationInstance {
             .prelude_import()
             .cloned())
     }
+
+    async fn host_info(&self, ctx: &mut DiceComputations<'_>) -> buck2_error::Result<String> {
+        Ok(ctx
+            .get_global_interpreter_state()
+            .await?
+            .configuror
+            .host_info()
+            .describe())
+    }
 }
 
 #[async_trait]
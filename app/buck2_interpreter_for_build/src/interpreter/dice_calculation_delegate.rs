@@ -260,6 +260,19 @@ impl<'c, 'd: 'c> DiceCalculationDelegate<'c, 'd> {
         Ok((ast, deps))
     }
 
+    /// Modules loaded by a file, including the implicit imports, found by parsing the file
+    /// without evaluating it or its dependencies.
+    pub(crate) async fn parse_imports(
+        &mut self,
+        starlark_file: StarlarkPath<'_>,
+    ) -> buck2_error::Result<Vec<OwnedStarlarkModulePath>> {
+        let ParseData(_ast, imports) = self.parse_file(starlark_file).await??;
+        Ok(imports
+            .iter()
+            .map(|(_span, import)| import.clone())
+            .collect())
+    }
+
     pub fn prepare_eval_with_content(
         &self,
         starlark_file: StarlarkPath<'_>,
@@ -404,10 +417,10 @@ impl<'c, 'd: 'c> DiceCalculationDelegate<'c, 'd> {
     }
 
     /// Return `None` if there's no `PACKAGE` file in the directory.
-    pub async fn prepare_package_file_eval(
+    pub(crate) async fn lookup_package_file(
         &mut self,
         package: PackageLabel,
-    ) -> buck2_error::Result<Option<(PackageFilePath, AstModule, ModuleDeps)>> {
+    ) -> buck2_error::Result<Option<PackageFilePath>> {
         // Note:
         /// To avoid paying the cost of read_dir when computing if any specific file has changed (e.g. PACKAGE),
         /// we depend on directory_sublisting_matching_any_case_key to invalidate all files that match (regardless of case).
@@ -465,18 +478,25 @@ impl<'c, 'd: 'c> DiceCalculationDelegate<'c, 'd> {
             }
         }
 
-        match self
-            .ctx
-            .compute(&PackageFileLookupKey(package.dupe()))
+        self.ctx
+            .compute(&PackageFileLookupKey(package))
             .await?
             .as_ref()
-            .duped_err()?
-        {
+            .duped_err()
+            .cloned()
+    }
+
+    /// Return `None` if there's no `PACKAGE` file in the directory.
+    pub async fn prepare_package_file_eval(
+        &mut self,
+        package: PackageLabel,
+    ) -> buck2_error::Result<Option<(PackageFilePath, AstModule, ModuleDeps)>> {
+        match self.lookup_package_file(package).await? {
             Some(package_file_path) => {
                 let (module, deps) = self
-                    .prepare_eval(StarlarkPath::PackageFile(package_file_path))
+                    .prepare_eval(StarlarkPath::PackageFile(&package_file_path))
                     .await?;
-                Ok(Some((package_file_path.clone(), module, deps)))
+                Ok(Some((package_file_path, module, deps)))
             }
             None => Ok(None),
         }
@@ -179,4 +179,9 @@ impl HostInfo {
             value,
         }
     }
+
+    /// Identifies what `host_info()` returns, for caches of evaluation results.
+    pub fn describe(&self) -> String {
+        format!("{:?} {:?} {:?}", self.platform, self.arch, self.xcode)
+    }
 }
@@ -90,6 +90,7 @@ impl ActionCgroupSession {
         tracker: Option<MemoryTrackerHandle>,
         command_type: CommandType,
         action_digest: Option<String>,
+        action_category: Option<String>,
         disable_kill_and_retry_suspend: bool,
     ) -> buck2_error::Result<Option<(Self, RetryFuture)>> {
         let Some(tracker) = tracker else {
@@ -112,6 +113,7 @@ impl ActionCgroupSession {
             SceneDescription {
                 action_digest,
                 command_type,
+                category: action_category,
             },
             disable_kill_and_retry_suspend,
         );
@@ -165,6 +165,7 @@ pub mod action_scene {
             _tracker: Option<MemoryTrackerHandle>,
             _command_type: CommandType,
             _action_digest: Option<String>,
+            _action_category: Option<String>,
             _disable_kill_and_retry_suspend: bool,
         ) -> buck2_error::Result<Option<(Self, RetryFuture)>> {
             Ok(None)
@@ -13,6 +13,7 @@ use std::time::Duration;
 use std::time::Instant;
 
 use buck2_common::init::ActionSuspendStrategy;
+use buck2_common::init::CategoryMemoryBudgets;
 use buck2_common::init::ResourceControlConfig;
 use buck2_events::daemon_id::DaemonId;
 use buck2_hash::BuckMutMap;
@@ -28,8 +29,13 @@ use crate::cgroup::EffectiveResourceConstraints;
 use crate::memory_tracker::MemoryReading;
 use crate::scheduler::event::EventSenderState;
 use crate::scheduler::event::ResourceControlEventMostly;
+use crate::scheduler::event::SchedulerDecision;
 use crate::scheduler::forecast::InflatedCurrentPressureForecast;
 use crate::scheduler::forecast::PressureForecast;
+use crate::scheduler::suspend_policy::SuspendPolicy;
+use crate::scheduler::suspend_policy::SuspendYoungestPolicy;
+use crate::scheduler::suspend_policy::VictimCandidate;
+use crate::scheduler::suspend_policy::suspend_policy_from_config;
 use crate::scheduler::suspend_timing::SpreadHalfSuspendTiming;
 use crate::scheduler::suspend_timing::SuspendCandidate;
 use crate::scheduler::suspend_timing::SuspendTiming;
@@ -37,6 +43,7 @@ use crate::scheduler::timeseries::Timeseries;
 
 mod event;
 mod forecast;
+mod suspend_policy;
 mod suspend_timing;
 mod timeseries;
 
@@ -60,6 +67,8 @@ impl ExperimentalAlgoVariant {
 pub(crate) struct SceneDescription {
     pub(crate) action_digest: Option<String>,
     pub(crate) command_type: CommandType,
+    /// Action category, which is what memory budgets are keyed on.
+    pub(crate) category: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -170,12 +179,15 @@ pub(crate) struct Scheduler {
     preferred_action_suspend_strategy: ActionSuspendStrategy,
     pressure_forecast: Box<dyn PressureForecast>,
     suspend_timing: Box<dyn SuspendTiming>,
+    suspend_policy: Box<dyn SuspendPolicy>,
+    category_memory_budgets: CategoryMemoryBudgets,
     /// Currently running and suspended scenes
     ///
     /// A scene is guaranteed to exist in exactly one of the two lists. Once completed a scene is
-    /// removed from the lists entirely. Across these two lists, scenes always appear in the order
-    /// in which they were originally started; so, if we have scenes S1, S2, and S3, originally
-    /// started in that order, the list might look like:
+    /// removed from the lists entirely. With the default suspend policy and no category memory
+    /// budgets, across these two lists, scenes always appear in the order in which they were
+    /// originally started; so, if we have scenes S1, S2, and S3, originally started in that order,
+    /// the list might look like:
     ///
     /// ```rust,ignore
     /// running_scenes: [S1],
@@ -183,11 +195,11 @@ pub(crate) struct Scheduler {
     /// ```
     ///
     /// When suspending/waking scenes, we move them between the end of the running list and the
-    /// beginning of the suspended list to maintain this property. The restriction this places on
-    /// the choice of which scenes we suspend/wake is important, as it guarantees forward progress;
-    /// specifically, the very first scene in the running list is guaranteed to never be suspended
-    /// before it finishes. Without this kind of a guarantee, we risk creating a deadlock where no
-    /// scene ever finishes before it's killed and retried.
+    /// beginning of the suspended list to maintain this property. Other policies may suspend any
+    /// running scene and budgets may skip over suspended scenes, but what matters is preserved
+    /// either way: the very first scene in the running list is guaranteed to never be suspended
+    /// before it finishes, which guarantees forward progress. Without this kind of a guarantee, we
+    /// risk creating a deadlock where no scene ever finishes before it's killed and retried.
     ///
     /// The high-level structure of our scheduling algorithm is as follows: By default, we hold the
     /// number of running scenes constant, scheduling an additional one whenever another finishes.
@@ -249,6 +261,8 @@ impl Scheduler {
             resource_control_config.preferred_action_suspend_strategy,
             Box::new(InflatedCurrentPressureForecast),
             Box::new(SpreadHalfSuspendTiming::new()),
+            suspend_policy_from_config(resource_control_config.suspend_policy),
+            resource_control_config.category_memory_budgets.clone(),
             effective_resource_constraints,
             system_memory_max,
             daemon_id,
@@ -262,6 +276,8 @@ impl Scheduler {
         preferred_action_suspend_strategy: ActionSuspendStrategy,
         pressure_forecast: Box<dyn PressureForecast>,
         suspend_timing: Box<dyn SuspendTiming>,
+        suspend_policy: Box<dyn SuspendPolicy>,
+        category_memory_budgets: CategoryMemoryBudgets,
         effective_resource_constraints: EffectiveResourceConstraints,
         system_memory_max: u64,
         daemon_id: &DaemonId,
@@ -277,6 +293,7 @@ impl Scheduler {
         } else {
             base_tags.push("suspension:disabled".to_owned());
         }
+        base_tags.push(format!("suspend_policy:{}", suspend_policy.name()));
 
         let mut event_sender_state = EventSenderState::new(daemon_id, estimated_memory_cap);
         event_sender_state.set_tags(base_tags.clone());
@@ -284,9 +301,12 @@ impl Scheduler {
         Self {
             enable_suspension,
             _experimental_algo_variant: experimental_algo_variant,
-            preferred_action_suspend_strategy,
+            preferred_action_suspend_strategy: suspend_policy
+                .suspend_strategy(preferred_action_suspend_strategy),
             pressure_forecast,
             suspend_timing,
+            suspend_policy,
+            category_memory_budgets,
             running_scenes: Vec::new(),
             suspended_scenes: VecDeque::new(),
             current_intent: CurrentIntent::Increase,
@@ -302,12 +322,27 @@ impl Scheduler {
 
     #[cfg(test)]
     pub(crate) fn testing_new(now: Instant) -> Self {
+        Self::testing_new_with_policy(
+            now,
+            Box::new(SuspendYoungestPolicy),
+            CategoryMemoryBudgets::default(),
+        )
+    }
+
+    #[cfg(test)]
+    pub(crate) fn testing_new_with_policy(
+        now: Instant,
+        suspend_policy: Box<dyn SuspendPolicy>,
+        category_memory_budgets: CategoryMemoryBudgets,
+    ) -> Self {
         Self::new(
             true,
             ExperimentalAlgoVariant::new(None),
             ActionSuspendStrategy::KillAndRetry,
             Box::new(InflatedCurrentPressureForecast),
             Box::new(SpreadHalfSuspendTiming::new()),
+            suspend_policy,
+            category_memory_budgets,
             EffectiveResourceConstraints::default(),
             1_000_000, // System memory max
             &DaemonId::new(),
@@ -360,7 +395,9 @@ impl Scheduler {
         let start_future = RetryFuture(start_rx);
         let start_wake_implemenation = WakeImplementation::UnblockStart(start_tx);
 
-        if self.suspended_scenes.is_empty() {
+        let held_by_category_budget = self.is_held_by_category_budget(&scene_cgroup.description);
+        // Scenes held back by their category's budget don't stop other scenes from starting.
+        if self.next_to_wake().is_none() && !held_by_category_budget {
             let running_scene_cgroup = wake_scene(scene_cgroup, start_wake_implemenation).0;
 
             self.running_scenes.push(running_scene_cgroup);
@@ -373,6 +410,22 @@ impl Scheduler {
                 wake_implementation: start_wake_implemenation,
             };
             self.suspended_scenes.push_back(suspended_scene_cgroup);
+
+            if held_by_category_budget {
+                let held_cgroup = self.suspended_scenes.back().unwrap();
+                let decision = self.decision(
+                    buck2_data::ResourceControlDecisionReason::CategoryMemoryBudget,
+                    0,
+                    &held_cgroup.scene.description,
+                );
+                self.event_sender_state.send_event(
+                    buck2_data::ResourceControlEventKind::HoldCategoryMemoryBudget,
+                    Some(&held_cgroup.scene),
+                    Some(decision),
+                    self.running_scenes.len() as u64,
+                    self.suspended_scenes.len() as u64,
+                );
+            }
         }
 
         (scene_id, start_future)
@@ -385,7 +438,10 @@ impl Scheduler {
             .position(|cg| cg.scene.scene_id == scene_id.as_ref())
         {
             let cgroup = self.running_scenes.remove(i);
-            self.wake(now);
+            self.wake(
+                now,
+                buck2_data::ResourceControlDecisionReason::ActionFinished,
+            );
             SceneResult::from_info(&cgroup.scene)
         } else if let Some(i) = self
             .suspended_scenes
@@ -540,12 +596,10 @@ impl Scheduler {
         // running and what we recently killed, we need to decide how many things to suspend now, if
         // any.
         let suspend_candidates = self
-            .running_scenes
-            .iter()
-            .skip(1)
-            .rev()
-            .map(|scene| SuspendCandidate {
-                memory_current: scene.scene.memory_current,
+            .suspension_order()
+            .into_iter()
+            .map(|(i, _)| SuspendCandidate {
+                memory_current: self.running_scenes[i].scene.memory_current,
             })
             .collect::<Vec<_>>();
         let suspends_to_issue = self.suspend_timing.suspends_to_issue(
@@ -558,7 +612,21 @@ impl Scheduler {
         }
 
         for _ in 0..suspends_to_issue {
-            let cgroup = self.running_scenes.pop().unwrap();
+            let suspension_order = self.suspension_order();
+            let Some(&(i, over_category_budget)) = suspension_order.first() else {
+                return;
+            };
+            let reason = if over_category_budget {
+                buck2_data::ResourceControlDecisionReason::CategoryMemoryBudget
+            } else {
+                buck2_data::ResourceControlDecisionReason::MemoryPressure
+            };
+            let decision = self.decision(
+                reason,
+                suspension_order.len(),
+                &self.running_scenes[i].scene.description,
+            );
+            let cgroup = self.running_scenes.remove(i);
 
             let (suspended_cgroup, event_kind) = suspend_scene(cgroup, now);
 
@@ -570,12 +638,93 @@ impl Scheduler {
             self.event_sender_state.send_event(
                 event_kind,
                 Some(&suspended_cgroup.scene),
+                Some(decision),
                 self.running_scenes.len() as u64,
                 self.suspended_scenes.len() as u64,
             );
         }
     }
 
+    /// Indices into `running_scenes` in the order in which they should be suspended, and whether
+    /// the scene's category is over its memory budget. Scenes over budget go first, then the
+    /// suspend policy decides. The first running scene is never a candidate.
+    fn suspension_order(&self) -> Vec<(usize, bool)> {
+        let candidates = self
+            .running_scenes
+            .iter()
+            .skip(1)
+            .map(|scene| VictimCandidate {
+                memory_current: scene.scene.memory_current,
+            })
+            .collect::<Vec<_>>();
+        let mut order = self
+            .suspend_policy
+            .suspension_order(&candidates)
+            .into_iter()
+            .map(|i| {
+                let i = i + 1;
+                let over_category_budget = self
+                    .category_memory(&self.running_scenes[i].scene.description)
+                    .is_some_and(|(current, budget)| current > budget);
+                (i, over_category_budget)
+            })
+            .collect::<Vec<_>>();
+        // Stable, so the policy's order is kept otherwise.
+        order.sort_by_key(|(_, over_category_budget)| !over_category_budget);
+        order
+    }
+
+    /// Memory in use by the running scenes of the scene's category, and the budget of that
+    /// category, if it has one.
+    fn category_memory(&self, description: &SceneDescription) -> Option<(u64, u64)> {
+        let category = description.category.as_ref()?;
+        let budget = *self.category_memory_budgets.0.get(category)?;
+        let current = self
+            .running_scenes
+            .iter()
+            .filter(|scene| scene.scene.description.category.as_ref() == Some(category))
+            .map(|scene| scene.scene.memory_current)
+            .sum();
+        Some((current, budget))
+    }
+
+    /// Whether a scene can't be started (or woken) because the running scenes of its category
+    /// already use up the category's budget. A scene is never held if no scene of its category is
+    /// running, since then nothing would ever release it.
+    fn is_held_by_category_budget(&self, description: &SceneDescription) -> bool {
+        let category_running = || {
+            self.running_scenes
+                .iter()
+                .any(|scene| scene.scene.description.category == description.category)
+        };
+        self.category_memory(description)
+            .is_some_and(|(current, budget)| current >= budget)
+            && category_running()
+    }
+
+    fn decision(
+        &self,
+        reason: buck2_data::ResourceControlDecisionReason,
+        candidates_count: usize,
+        description: &SceneDescription,
+    ) -> SchedulerDecision {
+        let category_memory = self.category_memory(description);
+        SchedulerDecision {
+            policy: self.suspend_policy.name(),
+            reason,
+            candidates_count: candidates_count as u64,
+            category_memory_budget: category_memory.map(|(_, budget)| budget),
+            category_memory_current: category_memory.map(|(current, _)| current),
+        }
+    }
+
+    /// The first suspended scene that isn't held back by its category's memory budget.
+    fn next_to_wake(&self) -> Option<usize> {
+        self.suspended_scenes
+            .iter()
+            .position(|scene| !self.is_held_by_category_budget(&scene.scene.description))
+    }
+
     fn maybe_increase_running_count(&mut self, now: Instant) {
         // We increase the running count at most once every 3 seconds since memory changes of
         // previous suspensions will take several seconds to take effect. This ensures that we don't
@@ -584,9 +733,10 @@ impl Scheduler {
             return;
         }
 
-        let Some(suspended_cgroup) = self.suspended_scenes.front() else {
+        let Some(i) = self.next_to_wake() else {
             return;
         };
+        let suspended_cgroup = &self.suspended_scenes[i];
 
         let required_memory_headroom = match suspended_cgroup.memory_current_when_suspended {
             Some(memory_current_when_suspended) => {
@@ -611,13 +761,22 @@ impl Scheduler {
         }
 
         self.last_parallelism_increase_time = now;
-        self.wake(now)
+        self.wake(
+            now,
+            buck2_data::ResourceControlDecisionReason::MemoryAvailable,
+        )
     }
 
-    fn wake(&mut self, now: Instant) {
-        let Some(mut suspended_cgroup) = self.suspended_scenes.pop_front() else {
+    fn wake(&mut self, now: Instant, reason: buck2_data::ResourceControlDecisionReason) {
+        let Some(i) = self.next_to_wake() else {
             return;
         };
+        let decision = self.decision(
+            reason,
+            self.suspended_scenes.len(),
+            &self.suspended_scenes[i].scene.description,
+        );
+        let mut suspended_cgroup = self.suspended_scenes.remove(i).unwrap();
 
         if let Some(suspend_start) = suspended_cgroup.suspend_start {
             let suspend_elapsed = now - suspend_start;
@@ -641,6 +800,7 @@ impl Scheduler {
         self.event_sender_state.send_event(
             event_kind,
             Some(&running_cgroup.scene),
+            Some(decision),
             self.running_scenes.len() as u64,
             self.suspended_scenes.len() as u64,
         );
@@ -788,6 +948,7 @@ mod tests {
                 SceneDescription {
                     command_type: CommandType::Build,
                     action_digest: Some("action_1".to_owned()),
+                    category: None,
                 },
                 false,
             )
@@ -797,6 +958,7 @@ mod tests {
                 SceneDescription {
                     command_type: CommandType::Build,
                     action_digest: Some("action_2".to_owned()),
+                    category: None,
                 },
                 false,
             )
@@ -868,6 +1030,7 @@ mod tests {
                 SceneDescription {
                     command_type: CommandType::Build,
                     action_digest: None,
+                    category: None,
                 },
                 false,
             )
@@ -877,6 +1040,7 @@ mod tests {
                 SceneDescription {
                     command_type: CommandType::Build,
                     action_digest: None,
+                    category: None,
                 },
                 false,
             )
@@ -950,6 +1114,7 @@ mod tests {
             SceneDescription {
                 command_type: CommandType::Build,
                 action_digest: None,
+                category: None,
             },
             false,
         );
@@ -958,6 +1123,7 @@ mod tests {
             SceneDescription {
                 command_type: CommandType::Build,
                 action_digest: None,
+                category: None,
             },
             false,
         );
@@ -1036,4 +1202,118 @@ mod tests {
         let scenario = &[(60, 0.0), (60, 80.0)];
         multi_pressure_level_test(&[], scenario, true);
     }
+
+    fn start_scene(scheduler: &mut Scheduler, category: &str) -> (SceneId, RetryFuture) {
+        scheduler.scene_started(
+            SceneDescription {
+                command_type: CommandType::Build,
+                action_digest: None,
+                category: Some(category.to_owned()),
+            },
+            false,
+        )
+    }
+
+    fn pressure_reading(allprocs_memory_pressure: f64) -> MemoryReading {
+        MemoryReading {
+            allprocs_memory_pressure,
+            allprocs_memory_current: 0,
+            allprocs_swap_current: 0,
+            daemon_memory_current: 0,
+            daemon_swap_current: 0,
+            time_collected: SystemTime::now(),
+        }
+    }
+
+    #[test]
+    fn test_largest_rss_policy_suspends_largest_scene() {
+        let now = Instant::now();
+        let mut scheduler = Scheduler::testing_new_with_policy(
+            now,
+            Box::new(crate::scheduler::suspend_policy::SuspendLargestRssPolicy),
+            CategoryMemoryBudgets::default(),
+        );
+        let timeline = TestTimeline(now);
+
+        let (scene1, mut scene1_start) = start_scene(&mut scheduler, "a");
+        let (scene2, mut scene2_start) = start_scene(&mut scheduler, "b");
+        let (scene3, mut scene3_start) = start_scene(&mut scheduler, "c");
+        let mut scene1_kill = scene1_start.0.try_recv().unwrap().0;
+        let mut scene2_kill = scene2_start.0.try_recv().unwrap().0;
+        let mut scene3_kill = scene3_start.0.try_recv().unwrap().0;
+
+        let readings = || {
+            UpdateBuilder::new()
+                .add(scene1.as_ref(), 1)
+                .add(scene2.as_ref(), 50)
+                .add(scene3.as_ref(), 10)
+                .build()
+        };
+        for i in 0..60 {
+            scheduler.update(pressure_reading(0.0), readings(), timeline.secs(i));
+        }
+        let mut killed = false;
+        for i in 60..160 {
+            scheduler.update(pressure_reading(80.0), readings(), timeline.secs(i));
+            if scene2_kill.0.try_recv().is_ok() {
+                killed = true;
+                break;
+            }
+        }
+
+        // The youngest scene is smaller, so it is still running.
+        assert!(killed, "largest scene was not killed");
+        assert!(scene1_kill.0.try_recv().is_err());
+        assert!(scene3_kill.0.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_category_budget_holds_start() {
+        let now = Instant::now();
+        let mut scheduler = Scheduler::testing_new_with_policy(
+            now,
+            Box::new(SuspendYoungestPolicy),
+            CategoryMemoryBudgets([("link".to_owned(), 100)].into_iter().collect()),
+        );
+        let timeline = TestTimeline(now);
+
+        let (scene1, mut scene1_start) = start_scene(&mut scheduler, "link");
+        assert!(scene1_start.0.try_recv().is_ok());
+        scheduler.update(
+            pressure_reading(0.0),
+            UpdateBuilder::new().add(scene1.as_ref(), 150).build(),
+            timeline.secs(1),
+        );
+
+        // The link budget is used up, so another link waits...
+        let (_scene2, mut scene2_start) = start_scene(&mut scheduler, "link");
+        assert!(scene2_start.0.try_recv().is_err());
+        // ...but that doesn't hold back other categories.
+        let (_scene3, mut scene3_start) = start_scene(&mut scheduler, "compile");
+        assert!(scene3_start.0.try_recv().is_ok());
+
+        scheduler.scene_finished(scene1, timeline.secs(2));
+        assert!(scene2_start.0.try_recv().is_ok());
+    }
+
+    #[test]
+    fn test_category_budget_never_holds_first_scene() {
+        let now = Instant::now();
+        let mut scheduler = Scheduler::testing_new_with_policy(
+            now,
+            Box::new(SuspendYoungestPolicy),
+            CategoryMemoryBudgets([("link".to_owned(), 0)].into_iter().collect()),
+        );
+        let timeline = TestTimeline(now);
+
+        // Nothing of the category is running, so even a used up budget lets a scene start...
+        let (scene1, mut scene1_start) = start_scene(&mut scheduler, "link");
+        assert!(scene1_start.0.try_recv().is_ok());
+
+        // ...and holds the next one until the first finishes.
+        let (_scene2, mut scene2_start) = start_scene(&mut scheduler, "link");
+        assert!(scene2_start.0.try_recv().is_err());
+        scheduler.scene_finished(scene1, timeline.secs(1));
+        assert!(scene2_start.0.try_recv().is_ok());
+    }
 }
@@ -21,6 +21,27 @@ use crate::CommandType;
 use crate::memory_tracker::MemoryReading;
 use crate::scheduler::Scene;
 
+/// Why the scheduler suspended, woke or held back a scene, recorded in its event.
+pub(crate) struct SchedulerDecision {
+    pub(crate) policy: &'static str,
+    pub(crate) reason: buck2_data::ResourceControlDecisionReason,
+    pub(crate) candidates_count: u64,
+    pub(crate) category_memory_budget: Option<u64>,
+    pub(crate) category_memory_current: Option<u64>,
+}
+
+impl SchedulerDecision {
+    fn to_proto(&self) -> buck2_data::ResourceControlDecision {
+        buck2_data::ResourceControlDecision {
+            policy: self.policy.to_owned(),
+            reason: self.reason.into(),
+            candidates_count: self.candidates_count,
+            category_memory_budget: self.category_memory_budget,
+            category_memory_current: self.category_memory_current,
+        }
+    }
+}
+
 pub(crate) struct EventSenderState {
     metadata: IntentionallyStdHashMap<String, String>,
     estimated_memory_cap: u64,
@@ -83,6 +104,7 @@ impl EventSenderState {
             self.send_event(
                 buck2_data::ResourceControlEventKind::Scheduled,
                 None,
+                None,
                 actions_running,
                 actions_suspended,
             );
@@ -93,10 +115,11 @@ impl EventSenderState {
         &mut self,
         kind: buck2_data::ResourceControlEventKind,
         cgroup: Option<&Scene>,
+        decision: Option<SchedulerDecision>,
         actions_running: u64,
         actions_suspended: u64,
     ) {
-        let e = self.make_event(kind, cgroup, actions_running, actions_suspended);
+        let e = self.make_event(kind, cgroup, decision, actions_running, actions_suspended);
         self.txs.retain_mut(|tx| tx.send(e.clone()).is_ok());
     }
 
@@ -104,6 +127,7 @@ impl EventSenderState {
         &self,
         kind: buck2_data::ResourceControlEventKind,
         cgroup: Option<&Scene>,
+        decision: Option<SchedulerDecision>,
         actions_running: u64,
         actions_suspended: u64,
     ) -> ResourceControlEventMostly {
@@ -124,6 +148,8 @@ impl EventSenderState {
             action_cgroup_memory_peak: cgroup.map(|cgroup| cgroup.memory_peak),
             action_cgroup_swap_current: cgroup.map(|cgroup| cgroup.swap_current),
             action_cgroup_swap_peak: cgroup.map(|cgroup| cgroup.swap_peak),
+            action_category: cgroup.and_then(|cgroup| cgroup.description.category.clone()),
+            decision: decision.map(|decision| decision.to_proto()),
             tags: self.tags.clone(),
         }
     }
@@ -146,6 +172,8 @@ pub(crate) struct ResourceControlEventMostly {
     action_cgroup_memory_peak: Option<u64>,
     action_cgroup_swap_current: Option<u64>,
     action_cgroup_swap_peak: Option<u64>,
+    action_category: Option<String>,
+    decision: Option<buck2_data::ResourceControlDecision>,
     tags: Vec<String>,
 }
 
@@ -187,6 +215,9 @@ impl ResourceControlEventMostly {
             }),
 
             tags: self.tags,
+
+            action_category: self.action_category,
+            decision: self.decision,
         }
     }
 }
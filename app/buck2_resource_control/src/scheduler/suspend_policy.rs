@@ -0,0 +1,140 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is dual-licensed under either the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree or the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree. You may select, at your option, one of the
+ * above-listed licenses.
+ */
+
+use buck2_common::init::ActionSuspendPolicy;
+use buck2_common::init::ActionSuspendStrategy;
+
+/// A running scene that may be suspended.
+pub(crate) struct VictimCandidate {
+    pub(crate) memory_current: u64,
+}
+
+/// Decides which running scenes are suspended when the scheduler decides to suspend some.
+pub(crate) trait SuspendPolicy: Send + Sync {
+    /// Name used in event log records.
+    fn name(&self) -> &'static str;
+
+    /// Orders the candidates (given in start order, oldest first) by the order in which they
+    /// should be suspended, returning their indices.
+    fn suspension_order(&self, candidates: &[VictimCandidate]) -> Vec<usize>;
+
+    /// The suspend strategy used for scenes that don't require freezing.
+    fn suspend_strategy(&self, preferred: ActionSuspendStrategy) -> ActionSuspendStrategy {
+        preferred
+    }
+}
+
+pub(crate) fn suspend_policy_from_config(policy: ActionSuspendPolicy) -> Box<dyn SuspendPolicy> {
+    match policy {
+        ActionSuspendPolicy::SuspendYoungest => Box::new(SuspendYoungestPolicy),
+        ActionSuspendPolicy::KillAndRetryYoungest => Box::new(KillAndRetryYoungestPolicy),
+        ActionSuspendPolicy::SuspendLargestRss => Box::new(SuspendLargestRssPolicy),
+    }
+}
+
+fn youngest_first(candidates: &[VictimCandidate]) -> Vec<usize> {
+    (0..candidates.len()).rev().collect()
+}
+
+/// Suspend the most recently started scenes first.
+///
+/// This keeps scenes in start order across the running and suspended lists.
+pub(crate) struct SuspendYoungestPolicy;
+
+impl SuspendPolicy for SuspendYoungestPolicy {
+    fn name(&self) -> &'static str {
+        "suspend_youngest"
+    }
+
+    fn suspension_order(&self, candidates: &[VictimCandidate]) -> Vec<usize> {
+        youngest_first(candidates)
+    }
+}
+
+/// Like [`SuspendYoungestPolicy`], but kill and retry scenes rather than freezing them whenever
+/// the scene allows it, so that their memory is released immediately.
+pub(crate) struct KillAndRetryYoungestPolicy;
+
+impl SuspendPolicy for KillAndRetryYoungestPolicy {
+    fn name(&self) -> &'static str {
+        "kill_and_retry_youngest"
+    }
+
+    fn suspension_order(&self, candidates: &[VictimCandidate]) -> Vec<usize> {
+        youngest_first(candidates)
+    }
+
+    fn suspend_strategy(&self, _preferred: ActionSuspendStrategy) -> ActionSuspendStrategy {
+        ActionSuspendStrategy::KillAndRetry
+    }
+}
+
+/// Suspend the scenes using the most memory first, so that as few scenes as possible are
+/// suspended. Ties go to the youngest scene.
+pub(crate) struct SuspendLargestRssPolicy;
+
+impl SuspendPolicy for SuspendLargestRssPolicy {
+    fn name(&self) -> &'static str {
+        "suspend_largest_rss"
+    }
+
+    fn suspension_order(&self, candidates: &[VictimCandidate]) -> Vec<usize> {
+        let mut order = youngest_first(candidates);
+        // Stable, so ties stay youngest first.
+        order.sort_by_key(|i| std::cmp::Reverse(candidates[*i].memory_current));
+        order
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidates(memory: &[u64]) -> Vec<VictimCandidate> {
+        memory
+            .iter()
+            .map(|memory_current| VictimCandidate {
+                memory_current: *memory_current,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_youngest_first() {
+        assert_eq!(
+            SuspendYoungestPolicy.suspension_order(&candidates(&[3, 1, 2])),
+            vec![2, 1, 0]
+        );
+    }
+
+    #[test]
+    fn test_largest_rss_first() {
+        assert_eq!(
+            SuspendLargestRssPolicy.suspension_order(&candidates(&[3, 1, 2])),
+            vec![0, 2, 1]
+        );
+    }
+
+    #[test]
+    fn test_largest_rss_ties_youngest_first() {
+        assert_eq!(
+            SuspendLargestRssPolicy.suspension_order(&candidates(&[2, 2, 1])),
+            vec![1, 0, 2]
+        );
+    }
+
+    #[test]
+    fn test_kill_and_retry_youngest_overrides_strategy() {
+        assert_eq!(
+            KillAndRetryYoungestPolicy.suspend_strategy(ActionSuspendStrategy::CgroupFreeze),
+            ActionSuspendStrategy::KillAndRetry
+        );
+    }
+}
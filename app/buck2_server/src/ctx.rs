@@ -19,6 +19,7 @@ use std::time::Instant;
 
 use allocative::Allocative;
 use async_trait::async_trait;
+use buck2_build_api::actions::execute::dice_data::SetCacheProbeTracker;
 use buck2_build_api::actions::execute::dice_data::SetCommandExecutor;
 use buck2_build_api::actions::execute::dice_data::SetReClient;
 use buck2_build_api::actions::execute::dice_data::set_fallback_executor_config;
@@ -76,6 +77,7 @@ use buck2_events::metadata;
 use buck2_events::schedule_type::SandcastleScheduleType;
 use buck2_execute::dep_file_state::DEP_FILE_STORE;
 use buck2_execute::execute::blocking::SetBlockingExecutor;
+use buck2_execute::execute::cache_probe::CacheProbeTracker;
 use buck2_execute::knobs::ExecutorGlobalKnobs;
 use buck2_execute::materialize::materializer::Materializer;
 use buck2_execute::materialize::materializer::SetMaterializer;
@@ -142,6 +144,7 @@ use crate::daemon::common::CommandExecutorFactory;
 use crate::daemon::common::get_default_executor_config;
 use crate::daemon::state::DaemonStateData;
 use crate::dice_tracker::BuckDiceTracker;
+use crate::dice_why::DiceWhyRecorder;
 use crate::heartbeat_guard::HeartbeatGuard;
 use crate::host_info;
 use crate::paging::PagingManager;
@@ -447,6 +450,12 @@ impl<'a> ServerCommandContext<'a> {
             .as_ref()
             .is_some_and(|opts| opts.upload_all_actions);
 
+        let cache_probe_tracker = if self.build_options.as_ref().is_some_and(|opts| opts.dry_run) {
+            Some(Arc::new(CacheProbeTracker::default()))
+        } else {
+            None
+        };
+
         let (interpreter_platform, interpreter_architecture, interpreter_xcode_version) =
             host_info::get_host_info(
                 self.host_platform_override,
@@ -463,6 +472,7 @@ impl<'a> ServerCommandContext<'a> {
             re_connection,
             build_signals,
             upload_all_actions,
+            cache_probe_tracker,
             skip_cache_read,
             skip_cache_write,
             keep_going: self
@@ -638,6 +648,7 @@ struct DiceCommandUpdater<'s, 'a: 's> {
     profile_event_listener: Option<Arc<FileWritingProfileEventListener>>,
     build_signals: BuildSignalsInstaller,
     upload_all_actions: bool,
+    cache_probe_tracker: Option<Arc<CacheProbeTracker>>,
     run_action_knobs: RunActionKnobs,
     skip_cache_read: bool,
     skip_cache_write: bool,
@@ -941,6 +952,10 @@ impl DiceCommandUpdater<'_, '_> {
             )?),
             cycle_detector,
             activation_tracker: Some(self.build_signals.activation_tracker.dupe()),
+            invalidation_path_recorder: DiceWhyRecorder::from_config(
+                root_config,
+                self.cmd_ctx.events().dupe(),
+            )?,
             ..Default::default()
         };
         data.set_detailed_aggregated_metrics_events_holder();
@@ -953,6 +968,12 @@ impl DiceCommandUpdater<'_, '_> {
                 property: "critical_path_backend2",
             })?
             .unwrap_or(CriticalPathBackendName::LongestPathGraph);
+        let log_build_graph = root_config
+            .parse::<bool>(BuckconfigKeyRef {
+                section: "buck2",
+                property: "log_build_graph",
+            })?
+            .unwrap_or(false);
 
         let override_use_case = root_config.parse::<RemoteExecutorUseCase>(BuckconfigKeyRef {
             section: "buck2_re_client",
@@ -967,11 +988,17 @@ impl DiceCommandUpdater<'_, '_> {
         if let Some(v) = &self.profile_event_listener {
             SetProfileEventListener::set(&mut data, v.clone());
         }
+        // Dry runs must not leave anything in buck-out, whichever kind of action would write it.
+        let materializer = if self.cache_probe_tracker.is_some() {
+            self.cmd_ctx.base_context.daemon.dry_run_materializer.dupe()
+        } else {
+            self.cmd_ctx.base_context.daemon.materializer.dupe()
+        };
         data.set_command_executor(Box::new(CommandExecutorFactory::new(
             self.re_connection.dupe(),
             host_sharing_broker,
             low_pass_filter,
-            self.cmd_ctx.base_context.daemon.materializer.dupe(),
+            materializer.dupe(),
             self.cmd_ctx.base_context.daemon.blocking_executor.dupe(),
             self.execution_strategy,
             executor_global_knobs,
@@ -990,10 +1017,14 @@ impl DiceCommandUpdater<'_, '_> {
             run_action_knobs.deduplicate_get_digests_ttl_calls,
             output_trees_download_config.dupe(),
             self.cmd_ctx.base_context.daemon.daemon_id.dupe(),
+            self.cache_probe_tracker.dupe(),
         )));
+        if let Some(tracker) = &self.cache_probe_tracker {
+            data.set_cache_probe_tracker(tracker.dupe());
+        }
         data.set_blocking_executor(self.cmd_ctx.base_context.daemon.blocking_executor.dupe());
         data.set_http_client(self.cmd_ctx.base_context.daemon.http_client.dupe());
-        data.set_materializer(self.cmd_ctx.base_context.daemon.materializer.dupe());
+        data.set_materializer(materializer);
         data.init_materialization_queue_tracker();
         data.set_build_signals(self.build_signals.build_signals.dupe());
         data.set_run_action_knobs(run_action_knobs);
@@ -1012,6 +1043,7 @@ impl DiceCommandUpdater<'_, '_> {
         );
         data.set_keep_going(self.keep_going);
         data.set_critical_path_backend(critical_path_backend);
+        data.set_log_build_graph(log_build_graph);
         data.init_local_resource_registry();
         data.init_bxl_streaming_tracker();
         initialize_read_dir_cache(&mut data);
@@ -35,6 +35,8 @@ use buck2_core::fs::artifact_path_resolver::ArtifactFs;
 use buck2_core::fs::project::ProjectRoot;
 use buck2_events::daemon_id::DaemonId;
 use buck2_execute::execute::blocking::BlockingExecutor;
+use buck2_execute::execute::cache_probe::CacheProbeOutcome;
+use buck2_execute::execute::cache_probe::CacheProbeTracker;
 use buck2_execute::execute::cache_uploader::NoOpCacheUploader;
 use buck2_execute::execute::cache_uploader::force_cache_upload;
 use buck2_execute::execute::prepared::NoOpCommandOptionalExecutor;
@@ -49,6 +51,8 @@ use buck2_execute::re::output_trees_download_config::OutputTreesDownloadConfig;
 use buck2_execute_impl::executors::action_cache::ActionCacheChecker;
 use buck2_execute_impl::executors::action_cache::RemoteDepFileCacheChecker;
 use buck2_execute_impl::executors::action_cache_upload_permission_checker::ActionCacheUploadPermissionChecker;
+use buck2_execute_impl::executors::cache_probe::CacheProbeChecker;
+use buck2_execute_impl::executors::cache_probe::CacheProbeExecutor;
 use buck2_execute_impl::executors::caching::CacheUploader;
 use buck2_execute_impl::executors::hybrid::FallbackTracker;
 use buck2_execute_impl::executors::hybrid::HybridExecutor;
@@ -97,6 +101,8 @@ pub struct CommandExecutorFactory {
     deduplicate_get_digests_ttl_calls: bool,
     output_trees_download_config: OutputTreesDownloadConfig,
     daemon_id: DaemonId,
+    /// Set for `--dry-run`: actions are looked up in the caches, but never executed.
+    cache_probe_tracker: Option<Arc<CacheProbeTracker>>,
 }
 
 impl CommandExecutorFactory {
@@ -123,6 +129,7 @@ impl CommandExecutorFactory {
         deduplicate_get_digests_ttl_calls: bool,
         output_trees_download_config: OutputTreesDownloadConfig,
         daemon_id: DaemonId,
+        cache_probe_tracker: Option<Arc<CacheProbeTracker>>,
     ) -> Self {
         let cache_upload_permission_checker = Arc::new(ActionCacheUploadPermissionChecker::new());
 
@@ -134,7 +141,8 @@ impl CommandExecutorFactory {
             blocking_executor,
             strategy,
             executor_global_knobs,
-            upload_all_actions,
+            // A dry run never executes anything, so there is nothing to upload.
+            upload_all_actions: upload_all_actions && cache_probe_tracker.is_none(),
             forkserver,
             skip_cache_read,
             skip_cache_write,
@@ -151,6 +159,7 @@ impl CommandExecutorFactory {
             deduplicate_get_digests_ttl_calls,
             output_trees_download_config,
             daemon_id,
+            cache_probe_tracker,
         }
     }
 
@@ -161,6 +170,31 @@ impl CommandExecutorFactory {
         let use_case = self.re_use_case_override.unwrap_or(use_case);
         self.re_connection.get_client().with_use_case(use_case)
     }
+
+    /// In a dry run, record what the cache checkers find and replace the executor.
+    fn with_cache_probe(&self, response: CommandExecutorResponse) -> CommandExecutorResponse {
+        let Some(tracker) = &self.cache_probe_tracker else {
+            return response;
+        };
+        CommandExecutorResponse {
+            executor: Arc::new(CacheProbeExecutor {
+                tracker: tracker.dupe(),
+            }),
+            platform: response.platform,
+            action_cache_checker: Arc::new(CacheProbeChecker {
+                inner: response.action_cache_checker,
+                tracker: tracker.dupe(),
+                outcome_on_hit: CacheProbeOutcome::ActionCacheHit,
+            }),
+            remote_dep_file_cache_checker: Arc::new(CacheProbeChecker {
+                inner: response.remote_dep_file_cache_checker,
+                tracker: tracker.dupe(),
+                outcome_on_hit: CacheProbeOutcome::RemoteDepFileCacheHit,
+            }),
+            cache_uploader: Arc::new(NoOpCacheUploader {}),
+            output_trees_download_config: response.output_trees_download_config,
+        }
+    }
 }
 
 #[allow(clippy::large_enum_variant)]
@@ -215,14 +249,14 @@ impl HasCommandExecutor for CommandExecutorFactory {
                 return Err(ExecutorCompatibilityError::LocalIncompatible(self.strategy).into());
             }
 
-            return Ok(CommandExecutorResponse {
+            return Ok(self.with_cache_probe(CommandExecutorResponse {
                 executor: Arc::new(local_executor_new(&LocalExecutorOptions::default())),
                 platform: Default::default(),
                 action_cache_checker: Arc::new(NoOpCommandOptionalExecutor {}),
                 remote_dep_file_cache_checker: Arc::new(NoOpCommandOptionalExecutor {}),
                 cache_uploader: Arc::new(NoOpCacheUploader {}),
                 output_trees_download_config: self.output_trees_download_config.dupe(),
-            });
+            }));
         }
 
         let remote_executor_new = |options: &RemoteExecutorOptions,
@@ -465,7 +499,7 @@ impl HasCommandExecutor for CommandExecutorFactory {
         let response = response.ok_or_else(|| {
             ExecutorCompatibilityError::SelectedConfig(self.strategy, executor_config.clone())
         })?;
-        Ok(response)
+        Ok(self.with_cache_probe(response))
     }
 }
 
@@ -23,6 +23,7 @@ use std::time::SystemTime;
 
 use allocative::Allocative;
 use async_trait::async_trait;
+use buck2_build_api::actions::execute::user_cancellation::cancel_action;
 use buck2_build_api::configure_dice::configure_dice_for_buck;
 use buck2_build_api::spawner::BuckSpawner;
 use buck2_certs::validate::CertState;
@@ -52,6 +53,7 @@ use buck2_events::Event;
 use buck2_events::daemon_id::DaemonId;
 use buck2_events::dispatch::EventDispatcher;
 use buck2_events::source::ChannelEventSource;
+use buck2_events::span::SpanId;
 use buck2_execute::digest_config::DigestConfig;
 use buck2_execute::materialize::materializer::MaterializationMethod;
 use buck2_execute_impl::executors::local::ForkserverAccess;
@@ -120,9 +122,11 @@ use crate::daemon::multi_event_stream::MultiEventStream;
 use crate::daemon::server_allocative::spawn_allocative;
 use crate::daemon::state::DaemonState;
 use crate::daemon::state::DaemonStateData;
+use crate::dice_persistence::DicePersistence;
 use crate::file_status::file_status_command;
 use crate::hydration::hydration_command;
 use crate::lsp::run_lsp_server_command;
+use crate::metrics_endpoint::spawn_metrics_endpoint;
 use crate::new_generic::new_generic_command;
 use crate::paging::cancel_active_page_out;
 use crate::profile::profile_command;
@@ -319,6 +323,17 @@ impl BuckdServer {
         certs_validation_background_job(cert_state.dupe()).await;
 
         let daemon_idle_timeout_s = init_ctx.daemon_startup_config.daemon_idle_timeout_s;
+        let metrics_port = init_ctx.daemon_startup_config.metrics_port;
+
+        let dice_persistence = DicePersistence::new(
+            &paths,
+            init_ctx.daemon_startup_config.hydration.as_ref(),
+            &process_info.version,
+        );
+        let restart_manifest = match &dice_persistence {
+            Some(persistence) => persistence.take_manifest()?,
+            None => None,
+        };
 
         let daemon_state = Arc::new(
             DaemonState::new(
@@ -333,7 +348,36 @@ impl BuckdServer {
             )
             .await?,
         );
-        let dice = daemon_state.data().dice_manager.unsafe_dice().dupe();
+        let daemon_data = daemon_state.data();
+        let dice = daemon_data.dice_manager.unsafe_dice().dupe();
+
+        if let (Some(persistence), Some(manifest)) = (&dice_persistence, restart_manifest) {
+            // Before serving, so that no sync happens before the file watcher is resumed.
+            if let Err(e) = persistence.restore(&daemon_data, manifest).await {
+                tracing::warn!("Failed to restore DICE from the previous daemon: {:#}", e);
+            }
+        }
+
+        let metrics_endpoint = match metrics_port {
+            Some(port) => {
+                let collector = SnapshotCollector::new(
+                    daemon_data.dupe(),
+                    daemon_state.paths.buck_out_path(),
+                    rt.clone(),
+                );
+                match spawn_metrics_endpoint(port, collector).await {
+                    Ok((addr, handle)) => {
+                        tracing::info!("Serving metrics on http://{}/metrics", addr);
+                        Some(handle)
+                    }
+                    Err(e) => {
+                        tracing::warn!("Failed to start the metrics endpoint: {:#}", e);
+                        None
+                    }
+                }
+            }
+            None => None,
+        };
 
         #[cfg(fbcode_build)]
         {
@@ -392,6 +436,9 @@ impl BuckdServer {
         }
 
         let server_result = server.await;
+        if let Some(metrics_endpoint) = metrics_endpoint {
+            metrics_endpoint.abort();
+        }
 
         let shutdown_deadline = shutdown_deadline
             .await
@@ -401,6 +448,15 @@ impl BuckdServer {
             .is_err()
         {
             tracing::warn!("timed out waiting for DICE tasks to finish during shutdown");
+        } else if let Some(persistence) = &dice_persistence {
+            match timeout_at(shutdown_deadline, persistence.persist(&daemon_data)).await {
+                Ok(Ok(Some(nodes))) => {
+                    tracing::info!("Persisted {} DICE nodes for the next daemon", nodes)
+                }
+                Ok(Ok(None)) => {}
+                Ok(Err(e)) => tracing::warn!("Failed to persist DICE: {:#}", e),
+                Err(_) => tracing::warn!("timed out persisting DICE during shutdown"),
+            }
         }
 
         server_result?;
@@ -843,6 +899,7 @@ fn pump_events(
             }
             Event::Buck(buck_event) => {
                 state.peek_event(&buck_event);
+                crate::subscription::events::notify(&buck_event);
 
                 let _ignore = output_send.send(Ok(CommandProgress {
                     progress: Some(command_progress::Progress::Event(buck_event.into())),
@@ -1723,6 +1780,17 @@ impl DaemonApi for BuckdServer {
         Ok(Response::new(SetLogFilterResponse {}))
     }
 
+    async fn cancel_action(
+        &self,
+        req: Request<CancelActionRequest>,
+    ) -> Result<Response<CancelActionResponse>, Status> {
+        let span_id = SpanId::from_u64(req.into_inner().span_id)
+            .map_err(|e| Status::invalid_argument(format!("{e:#}")))?;
+        Ok(Response::new(CancelActionResponse {
+            cancelled: cancel_action(span_id),
+        }))
+    }
+
     type TraceIoStream = ResponseStream;
     async fn trace_io(
         &self,
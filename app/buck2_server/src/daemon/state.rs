@@ -64,6 +64,7 @@ use buck2_execute_impl::materializers::deferred::DeferredMaterializer;
 use buck2_execute_impl::materializers::deferred::DeferredMaterializerConfigs;
 use buck2_execute_impl::materializers::deferred::TtlRefreshConfiguration;
 use buck2_execute_impl::materializers::deferred::clean_stale::CleanStaleConfig;
+use buck2_execute_impl::materializers::dry_run::DryRunMaterializer;
 use buck2_execute_impl::re::paranoid_download::ParanoidDownloader;
 use buck2_execute_impl::sqlite::dep_file_state_db::PersistedDepFileStore;
 use buck2_execute_impl::sqlite::incremental_state_db::IncrementalDbState;
@@ -153,6 +154,9 @@ pub struct DaemonStateData {
     /// materializations to work properly between distinct build commands.
     pub(crate) materializer: Arc<dyn Materializer>,
 
+    /// Wraps `materializer` for `--dry-run` builds, so that no action writes to buck-out.
+    pub(crate) dry_run_materializer: Arc<dyn Materializer>,
+
     pub(crate) forkserver: ForkserverAccess,
 
     #[allocative(skip)]
@@ -436,7 +440,17 @@ impl DaemonState {
                 };
 
             let cache_dir_path = paths.cache_dir_path();
-            let valid_cache_dirs = paths.valid_cache_dirs();
+            let mut valid_cache_dirs = paths.valid_cache_dirs();
+            // With persistence, `dice_state` was already validated (and wiped if stale) by
+            // `DicePersistence::take_manifest` before we got here.
+            if init_ctx
+                .daemon_startup_config
+                .hydration
+                .as_ref()
+                .is_some_and(|h| h.persist_across_restarts)
+            {
+                valid_cache_dirs.push(paths.dice_state_dir_name());
+            }
 
             let deferred_materializer_configs = {
                 let defer_write_actions = root_config
@@ -645,6 +659,8 @@ impl DaemonState {
                 http_client.dupe(),
                 daemon_dispatcher.dupe(),
             )?;
+            let dry_run_materializer: Arc<dyn Materializer> =
+                Arc::new(DryRunMaterializer::new(materializer.dupe(), digest_config));
 
             tracing::info!("Creating memory tracker...");
             let memory_tracker = memory_tracker::create_memory_tracker(
@@ -787,6 +803,7 @@ impl DaemonState {
                 re_client_manager,
                 blocking_executor,
                 materializer,
+                dry_run_materializer,
                 forkserver,
                 scribe_sink,
                 use_network_action_output_cache,
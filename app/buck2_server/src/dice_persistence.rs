@@ -0,0 +1,179 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is dual-licensed under either the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree or the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree. You may select, at your option, one of the
+ * above-listed licenses.
+ */
+
+//! Keeping the DICE graph across daemon restarts (`buck2_hydration.persist_across_restarts`).
+//!
+//! On graceful shutdown the daemon writes a DICE snapshot (see [`dice::Dice::persist_snapshot`]) of
+//! the configured key types into the pagable storage under `dice_state`, and next to it a small
+//! manifest recording the snapshot id, the daemon binary that wrote it and the file watcher's
+//! checkpoint (clock and mergebase) as of the last sync.
+//!
+//! The next daemon consumes the manifest before DICE opens the storage: if it is missing or was
+//! written by a different binary, `dice_state` is wiped as it would be without persistence.
+//! Otherwise, once DICE and the file watcher exist, the watcher is resumed from the checkpoint
+//! and only then is the snapshot restored. The first sync then invalidates whatever changed
+//! while no daemon was running, or clears DICE entirely if the watcher can't tell (a restarted
+//! watchman, a new mergebase), so restored values are never trusted blindly. Watchers that
+//! can't resume (e.g. `notify`) produce no checkpoint, so nothing is persisted with them.
+
+use buck2_common::init::HydrationConfig;
+use buck2_common::invocation_paths::InvocationPaths;
+use buck2_error::BuckErrorContext;
+use buck2_error::ErrorTag;
+use buck2_error::conversion::from_any_with_tag;
+use buck2_file_watcher::file_watcher::FileWatcherCheckpoint;
+use buck2_fs::error::IoResultExt;
+use buck2_fs::fs_util;
+use buck2_fs::paths::abs_norm_path::AbsNormPathBuf;
+use buck2_fs::paths::file_name::FileName;
+use dice::DiceSnapshotId;
+
+use crate::daemon::state::DaemonStateData;
+use crate::paging::cancel_active_page_out;
+use crate::paging::wait_for_idle_page_out;
+
+/// Written last (via a rename), so its presence means the snapshot it names is complete.
+const MANIFEST_FILE_NAME: &str = "restart_manifest.json";
+const MANIFEST_TMP_FILE_NAME: &str = "restart_manifest.json.tmp";
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub(crate) struct RestartManifest {
+    /// Unique id of the daemon binary that wrote the snapshot. Serialized values are only
+    /// guaranteed to be readable by the same binary.
+    daemon_version: String,
+    snapshot: String,
+    watcher: FileWatcherCheckpoint,
+}
+
+pub(crate) struct DicePersistence {
+    state_dir: AbsNormPathBuf,
+    daemon_version: String,
+    key_types: Vec<String>,
+}
+
+impl DicePersistence {
+    /// Returns `None` unless `buck2_hydration.persist_across_restarts` is set.
+    pub(crate) fn new(
+        paths: &InvocationPaths,
+        hydration: Option<&HydrationConfig>,
+        daemon_version: &str,
+    ) -> Option<Self> {
+        let hydration = hydration.filter(|h| h.persist_across_restarts)?;
+        Some(Self {
+            state_dir: paths.dice_state_path(),
+            daemon_version: daemon_version.to_owned(),
+            key_types: hydration.persist_key_types.clone(),
+        })
+    }
+
+    fn manifest_path(&self) -> AbsNormPathBuf {
+        self.state_dir
+            .join(FileName::unchecked_new(MANIFEST_FILE_NAME))
+    }
+
+    /// Read and remove the manifest left by the previous daemon. Must run before DICE opens
+    /// its storage. When there is no usable manifest, the whole state dir is removed so that
+    /// snapshots nobody can restore don't accumulate.
+    pub(crate) fn take_manifest(&self) -> buck2_error::Result<Option<RestartManifest>> {
+        let manifest_path = self.manifest_path();
+        let manifest = fs_util::read_to_string_if_exists(&manifest_path)?
+            .and_then(
+                |contents| match serde_json::from_str::<RestartManifest>(&contents) {
+                    Ok(manifest) => Some(manifest),
+                    Err(e) => {
+                        tracing::warn!("Ignoring unreadable `{}`: {}", manifest_path, e);
+                        None
+                    }
+                },
+            )
+            .filter(|manifest| manifest.daemon_version == self.daemon_version);
+
+        if manifest.is_some() {
+            // Consumed: if this daemon doesn't shut down cleanly, the next one starts empty.
+            fs_util::remove_file(&manifest_path).categorize_internal()?;
+        } else {
+            fs_util::remove_all(&self.state_dir).categorize_internal()?;
+        }
+        Ok(manifest)
+    }
+
+    /// Restore the previous daemon's snapshot, provided the file watcher can resume from where
+    /// that daemon stopped. Must run before the first file watcher sync. Returns the number of
+    /// restored nodes.
+    pub(crate) async fn restore(
+        &self,
+        data: &DaemonStateData,
+        manifest: RestartManifest,
+    ) -> buck2_error::Result<usize> {
+        let id: DiceSnapshotId = manifest
+            .snapshot
+            .parse()
+            .map_err(|e| from_any_with_tag(e, ErrorTag::Tier0))
+            .buck_error_context("Invalid DICE snapshot id in restart manifest")?;
+
+        if !data.file_watcher.resume(&manifest.watcher).await? {
+            tracing::info!(
+                "File watcher cannot resume from the previous daemon, not restoring DICE"
+            );
+            return Ok(0);
+        }
+
+        let restored = data
+            .dice_manager
+            .unsafe_dice()
+            .restore_snapshot(id)
+            .await
+            .map_err(|e| from_any_with_tag(e, ErrorTag::Environment))?;
+        tracing::info!("Restored {} DICE nodes from the previous daemon", restored);
+        Ok(restored)
+    }
+
+    /// Snapshot DICE for the next daemon. DICE must be idle. Returns the number of persisted
+    /// nodes, or `None` if nothing was persisted.
+    pub(crate) async fn persist(
+        &self,
+        data: &DaemonStateData,
+    ) -> buck2_error::Result<Option<usize>> {
+        // An idle page-out would race the snapshot for the state thread and the storage.
+        cancel_active_page_out();
+        wait_for_idle_page_out().await;
+
+        let Some(watcher) = data.file_watcher.checkpoint().await? else {
+            return Ok(None);
+        };
+        let Some(snapshot) = data
+            .dice_manager
+            .unsafe_dice()
+            .persist_snapshot(self.key_types.iter().cloned())
+            .await
+            .map_err(|e| from_any_with_tag(e, ErrorTag::Environment))?
+        else {
+            return Ok(None);
+        };
+
+        let manifest = RestartManifest {
+            daemon_version: self.daemon_version.clone(),
+            snapshot: snapshot.id.to_string(),
+            watcher,
+        };
+        let manifest_path = self.manifest_path();
+        let tmp_path = self
+            .state_dir
+            .join(FileName::unchecked_new(MANIFEST_TMP_FILE_NAME));
+        fs_util::write(
+            &tmp_path,
+            serde_json::to_vec(&manifest).buck_error_context("Serializing restart manifest")?,
+        )
+        .categorize_internal()?;
+        fs_util::rename(&tmp_path, &manifest_path).categorize_internal()?;
+
+        Ok(Some(snapshot.node_count))
+    }
+}
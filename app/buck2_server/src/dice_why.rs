@@ -0,0 +1,87 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is dual-licensed under either the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree or the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree. You may select, at your option, one of the
+ * above-listed licenses.
+ */
+
+use std::sync::Arc;
+
+use buck2_common::legacy_configs::configs::LegacyBuckConfig;
+use buck2_common::legacy_configs::key::BuckconfigKeyRef;
+use buck2_events::dispatch::EventDispatcher;
+use buck2_hash::BuckMutSet;
+use dice::DiceKeyTrackedInvalidationPaths;
+use dice::DiceTrackedInvalidationPath;
+use dice::DynKey;
+use dice::InvalidationPathRecorder;
+
+enum RecordedKeyTypes {
+    All,
+    Only(BuckMutSet<String>),
+}
+
+/// Sends a `DiceKeyRecomputed` event, for `buck2 debug dice-why`, for every DICE key of the
+/// types in `buck2.dice_why_key_types` that the command recomputes. `*` records all key types,
+/// which is expensive on large builds.
+pub(crate) struct DiceWhyRecorder {
+    events: EventDispatcher,
+    key_types: RecordedKeyTypes,
+}
+
+impl DiceWhyRecorder {
+    pub(crate) fn from_config(
+        root_config: &LegacyBuckConfig,
+        events: EventDispatcher,
+    ) -> buck2_error::Result<Option<Arc<dyn InvalidationPathRecorder>>> {
+        let Some(key_types) = root_config.parse_list::<String>(BuckconfigKeyRef {
+            section: "buck2",
+            property: "dice_why_key_types",
+        })?
+        else {
+            return Ok(None);
+        };
+        let key_types = if key_types.iter().any(|t| t == "*") {
+            RecordedKeyTypes::All
+        } else if key_types.is_empty() {
+            return Ok(None);
+        } else {
+            RecordedKeyTypes::Only(key_types.into_iter().collect())
+        };
+        Ok(Some(Arc::new(DiceWhyRecorder { events, key_types })))
+    }
+}
+
+impl InvalidationPathRecorder for DiceWhyRecorder {
+    fn should_record(&self, key: &DynKey) -> bool {
+        match &self.key_types {
+            RecordedKeyTypes::All => true,
+            RecordedKeyTypes::Only(types) => types.contains(key.key_type_name()),
+        }
+    }
+
+    fn key_recomputed(&self, key: &DynKey, paths: DiceKeyTrackedInvalidationPaths) {
+        // Normal priority paths follow the most recent change of any kind, which is what caused
+        // the recompute. High priority ones only follow file changes.
+        let invalidation_path = match &paths.normal_priority_path {
+            DiceTrackedInvalidationPath::Invalidated(path) => path
+                .get_invalidation_path()
+                .into_iter()
+                .map(|entry| buck2_data::DiceInvalidationPathEntry {
+                    key_type: entry.key.key_type_name().to_owned(),
+                    key: entry.key.to_string(),
+                    version: entry.version.to_string(),
+                })
+                .collect(),
+            DiceTrackedInvalidationPath::Clean | DiceTrackedInvalidationPath::Unknown => Vec::new(),
+        };
+        self.events.instant_event(buck2_data::DiceKeyRecomputed {
+            key_type: key.key_type_name().to_owned(),
+            key: key.to_string(),
+            invalidation_path,
+        });
+    }
+}
@@ -18,7 +18,9 @@ mod clean_stale;
 mod cpu_usage_collector;
 mod ctx;
 pub mod daemon;
+mod dice_persistence;
 mod dice_tracker;
+mod dice_why;
 mod file_status;
 mod heartbeat_guard;
 mod host_info;
@@ -26,6 +28,7 @@ mod hydration;
 mod jemalloc_stats;
 pub mod lsp;
 mod materialize;
+mod metrics_endpoint;
 mod net_io;
 pub(crate) mod new_generic;
 mod paging;
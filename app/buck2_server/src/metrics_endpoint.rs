@@ -0,0 +1,525 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is dual-licensed under either the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree or the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree. You may select, at your option, one of the
+ * above-listed licenses.
+ */
+
+//! Serves the daemon's state as OpenMetrics on a local HTTP endpoint, so that long-lived daemons
+//! can be scraped between commands. Enabled with `[buck2] metrics_port`.
+
+use std::fmt::Write;
+use std::net::Ipv4Addr;
+use std::net::SocketAddr;
+
+use buck2_error::BuckErrorContext;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpListener;
+use tokio::net::TcpStream;
+use tokio::task::JoinHandle;
+
+use crate::snapshot::SnapshotCollector;
+
+const CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+/// Scrapes send small requests, anything larger is not a scrape.
+const MAX_REQUEST_SIZE: usize = 8192;
+
+/// Binds the endpoint on localhost and serves it until the returned task is aborted.
+pub(crate) async fn spawn_metrics_endpoint(
+    port: u16,
+    collector: SnapshotCollector,
+) -> buck2_error::Result<(SocketAddr, JoinHandle<()>)> {
+    let listener = TcpListener::bind(SocketAddr::new(Ipv4Addr::LOCALHOST.into(), port))
+        .await
+        .with_buck_error_context(|| format!("Error binding metrics endpoint to port {port}"))?;
+    let addr = listener.local_addr()?;
+    let handle = tokio::spawn(async move {
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    tracing::warn!("Error accepting metrics endpoint connection: {}", e);
+                    continue;
+                }
+            };
+            let collector = collector.clone();
+            tokio::spawn(async move {
+                let metrics = async { render_open_metrics(&collector.create_snapshot().await) };
+                if let Err(e) = serve(stream, metrics).await {
+                    tracing::debug!("Error serving metrics endpoint request: {:#}", e);
+                }
+            });
+        }
+    });
+    Ok((addr, handle))
+}
+
+/// Answers a single request, computing the metrics only if they are requested.
+async fn serve(
+    mut stream: TcpStream,
+    metrics: impl Future<Output = String>,
+) -> buck2_error::Result<()> {
+    let mut request = Vec::new();
+    let mut buf = [0; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+        let read = stream.read(&mut buf).await?;
+        if read == 0 || request.len() + read > MAX_REQUEST_SIZE {
+            return Ok(());
+        }
+        request.extend_from_slice(&buf[..read]);
+    }
+
+    let request_line = String::from_utf8_lossy(&request);
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next();
+    // Scrapers may add query parameters, which this endpoint ignores.
+    let path = parts
+        .next()
+        .map(|target| target.split_once('?').map_or(target, |(path, _)| path));
+    let (status, content_type, body) = match (method, path) {
+        (Some("GET"), Some("/metrics")) => ("200 OK", CONTENT_TYPE, metrics.await),
+        (Some("GET"), _) => ("404 Not Found", "text/plain", "Not found\n".to_owned()),
+        _ => (
+            "405 Method Not Allowed",
+            "text/plain",
+            "Method not allowed\n".to_owned(),
+        ),
+    };
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+#[derive(Clone, Copy)]
+enum MetricType {
+    Gauge,
+    Counter,
+}
+
+/// Writes metric families in the OpenMetrics text format.
+struct OpenMetricsWriter {
+    out: String,
+}
+
+impl OpenMetricsWriter {
+    fn family(
+        &mut self,
+        name: &str,
+        metric_type: MetricType,
+        help: &str,
+        samples: &[(&[(&str, &str)], f64)],
+    ) {
+        let (type_name, suffix) = match metric_type {
+            MetricType::Gauge => ("gauge", ""),
+            MetricType::Counter => ("counter", "_total"),
+        };
+        writeln!(self.out, "# TYPE buck2_{name} {type_name}").unwrap();
+        writeln!(self.out, "# HELP buck2_{name} {help}").unwrap();
+        for (labels, value) in samples {
+            write!(self.out, "buck2_{name}{suffix}").unwrap();
+            if !labels.is_empty() {
+                let labels: Vec<_> = labels
+                    .iter()
+                    .map(|(key, value)| format!("{key}=\"{}\"", escape_label_value(value)))
+                    .collect();
+                write!(self.out, "{{{}}}", labels.join(",")).unwrap();
+            }
+            writeln!(self.out, " {value}").unwrap();
+        }
+    }
+
+    fn gauge(&mut self, name: &str, help: &str, value: impl Into<f64>) {
+        self.family(name, MetricType::Gauge, help, &[(&[], value.into())]);
+    }
+
+    fn counter(&mut self, name: &str, help: &str, value: impl Into<f64>) {
+        self.family(name, MetricType::Counter, help, &[(&[], value.into())]);
+    }
+
+    fn finish(mut self) -> String {
+        self.out.push_str("# EOF\n");
+        self.out
+    }
+}
+
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn render_open_metrics(snapshot: &buck2_data::Snapshot) -> String {
+    let mut w = OpenMetricsWriter { out: String::new() };
+
+    w.gauge(
+        "daemon_uptime_seconds",
+        "Time since the daemon started.",
+        snapshot.daemon_uptime_s as f64,
+    );
+
+    // Memory.
+    if let Some(rss) = snapshot.buck2_rss {
+        w.gauge("rss_bytes", "Resident set size of the daemon.", rss as f64);
+    }
+    w.gauge(
+        "max_rss_bytes",
+        "Peak resident set size of the daemon.",
+        snapshot.buck2_max_rss as f64,
+    );
+    if let Some(active) = snapshot.malloc_bytes_active {
+        w.gauge(
+            "malloc_active_bytes",
+            "Bytes in active pages allocated by the allocator.",
+            active as f64,
+        );
+    }
+    if let Some(allocated) = snapshot.malloc_bytes_allocated {
+        w.gauge(
+            "malloc_allocated_bytes",
+            "Bytes allocated by the application.",
+            allocated as f64,
+        );
+    }
+
+    // CPU.
+    w.counter(
+        "cpu_user_seconds",
+        "User CPU time of the daemon.",
+        snapshot.buck2_user_cpu_us as f64 / 1e6,
+    );
+    w.counter(
+        "cpu_system_seconds",
+        "System CPU time of the daemon.",
+        snapshot.buck2_system_cpu_us as f64 / 1e6,
+    );
+    if let (Some(user), Some(system)) = (
+        snapshot.host_cpu_usage_user_ms,
+        snapshot.host_cpu_usage_system_ms,
+    ) {
+        w.family(
+            "host_cpu_seconds",
+            MetricType::Counter,
+            "CPU time of the host since the metrics endpoint started.",
+            &[
+                (&[("mode", "user")], user as f64 / 1e3),
+                (&[("mode", "system")], system as f64 / 1e3),
+            ],
+        );
+    }
+
+    // Queues.
+    w.gauge(
+        "blocking_executor_queue_size",
+        "IO operations waiting for the blocking executor.",
+        snapshot.blocking_executor_io_queue_size as f64,
+    );
+    w.gauge(
+        "tokio_blocking_queue_depth",
+        "Tasks waiting for a tokio blocking thread.",
+        snapshot.tokio_blocking_queue_depth as f64,
+    );
+
+    // DICE.
+    w.gauge(
+        "dice_keys",
+        "Keys stored in the DICE graph.",
+        snapshot.dice_key_count as f64,
+    );
+    w.gauge(
+        "dice_active_transactions",
+        "Active DICE transactions.",
+        snapshot.dice_active_transaction_count,
+    );
+
+    // Remote execution.
+    w.family(
+        "re_bytes",
+        MetricType::Counter,
+        "Bytes transferred to and from remote execution.",
+        &[
+            (
+                &[("direction", "download")],
+                snapshot.re_download_bytes as f64,
+            ),
+            (&[("direction", "upload")], snapshot.re_upload_bytes as f64),
+        ],
+    );
+    let re_requests = [
+        (
+            "upload",
+            snapshot.re_uploads_started,
+            snapshot.re_uploads_finished_successfully,
+            snapshot.re_uploads_finished_with_error,
+        ),
+        (
+            "download",
+            snapshot.re_downloads_started,
+            snapshot.re_downloads_finished_successfully,
+            snapshot.re_downloads_finished_with_error,
+        ),
+        (
+            "action_cache",
+            snapshot.re_action_cache_started,
+            snapshot.re_action_cache_finished_successfully,
+            snapshot.re_action_cache_finished_with_error,
+        ),
+        (
+            "execute",
+            snapshot.re_executes_started,
+            snapshot.re_executes_finished_successfully,
+            snapshot.re_executes_finished_with_error,
+        ),
+        (
+            "materialize",
+            snapshot.re_materializes_started,
+            snapshot.re_materializes_finished_successfully,
+            snapshot.re_materializes_finished_with_error,
+        ),
+        (
+            "write_action_result",
+            snapshot.re_write_action_results_started,
+            snapshot.re_write_action_results_finished_successfully,
+            snapshot.re_write_action_results_finished_with_error,
+        ),
+        (
+            "get_digest_expiration",
+            snapshot.re_get_digest_expirations_started,
+            snapshot.re_get_digest_expirations_finished_successfully,
+            snapshot.re_get_digest_expirations_finished_with_error,
+        ),
+    ];
+    let labels: Vec<_> = re_requests
+        .iter()
+        .map(|(operation, ..)| {
+            [
+                [("operation", *operation), ("result", "success")],
+                [("operation", *operation), ("result", "error")],
+            ]
+        })
+        .collect();
+    let finished: Vec<_> = re_requests
+        .iter()
+        .zip(&labels)
+        .flat_map(|((_, _, success, error), [success_labels, error_labels])| {
+            [
+                (success_labels.as_slice(), *success as f64),
+                (error_labels.as_slice(), *error as f64),
+            ]
+        })
+        .collect();
+    w.family(
+        "re_requests_finished",
+        MetricType::Counter,
+        "Finished remote execution requests.",
+        &finished,
+    );
+    let operation_labels: Vec<_> = re_requests
+        .iter()
+        .map(|(operation, ..)| [("operation", *operation)])
+        .collect();
+    let in_flight: Vec<_> = re_requests
+        .iter()
+        .zip(&operation_labels)
+        .map(|((_, started, success, error), labels)| {
+            (
+                labels.as_slice(),
+                started.saturating_sub(*success).saturating_sub(*error) as f64,
+            )
+        })
+        .collect();
+    w.family(
+        "re_requests_in_flight",
+        MetricType::Gauge,
+        "Remote execution requests started and not finished.",
+        &in_flight,
+    );
+    w.counter(
+        "http_download_bytes",
+        "Bytes downloaded over HTTP.",
+        snapshot.http_download_bytes as f64,
+    );
+
+    // Materializer.
+    w.gauge(
+        "materializer_queue_size",
+        "Commands waiting for the deferred materializer.",
+        snapshot.deferred_materializer_queue_size as f64,
+    );
+    w.counter(
+        "materializer_declares",
+        "Artifacts declared to the deferred materializer.",
+        snapshot.deferred_materializer_declares as f64,
+    );
+    w.counter(
+        "materializer_declares_reused",
+        "Artifact declarations that reused an existing artifact.",
+        snapshot.deferred_materializer_declares_reused as f64,
+    );
+    w.family(
+        "materializer_logical_bytes",
+        MetricType::Gauge,
+        "Logical size of the artifacts tracked by the deferred materializer.",
+        &[
+            (
+                &[("output", "final")],
+                snapshot.deferred_materializer_final_output_logical_bytes as f64,
+            ),
+            (
+                &[("output", "intermediate")],
+                snapshot.deferred_materializer_intermediate_only_logical_bytes as f64,
+            ),
+        ],
+    );
+
+    // Network.
+    let mut interfaces: Vec<_> = snapshot.network_interface_stats.iter().collect();
+    interfaces.sort_by(|a, b| a.0.cmp(b.0));
+    let network_labels: Vec<_> = interfaces
+        .iter()
+        .map(|(name, _)| {
+            [
+                [("interface", name.as_str()), ("direction", "receive")],
+                [("interface", name.as_str()), ("direction", "transmit")],
+            ]
+        })
+        .collect();
+    let network: Vec<_> = interfaces
+        .iter()
+        .zip(&network_labels)
+        .flat_map(|((_, stats), [rx_labels, tx_labels])| {
+            [
+                (rx_labels.as_slice(), stats.rx_bytes as f64),
+                (tx_labels.as_slice(), stats.tx_bytes as f64),
+            ]
+        })
+        .collect();
+    w.family(
+        "network_bytes",
+        MetricType::Counter,
+        "Bytes transferred by the host on its network interfaces.",
+        &network,
+    );
+
+    w.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample<'a>(metrics: &'a str, name: &str) -> Option<&'a str> {
+        metrics
+            .lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(' '))
+    }
+
+    #[test]
+    fn test_render_open_metrics() {
+        let mut snapshot = buck2_data::Snapshot {
+            buck2_rss: Some(1024),
+            buck2_user_cpu_us: 1_500_000,
+            dice_key_count: 42,
+            deferred_materializer_queue_size: 3,
+            re_uploads_started: 10,
+            re_uploads_finished_successfully: 6,
+            re_uploads_finished_with_error: 1,
+            ..Default::default()
+        };
+        snapshot.network_interface_stats.insert(
+            "eth0".to_owned(),
+            buck2_data::NetworkInterfaceStats {
+                tx_bytes: 5,
+                rx_bytes: 7,
+                ..Default::default()
+            },
+        );
+        let metrics = render_open_metrics(&snapshot);
+
+        assert!(metrics.contains("# TYPE buck2_rss_bytes gauge\n"));
+        assert_eq!(Some("1024"), sample(&metrics, "buck2_rss_bytes"));
+        assert_eq!(
+            Some("1.5"),
+            sample(&metrics, "buck2_cpu_user_seconds_total")
+        );
+        assert_eq!(Some("42"), sample(&metrics, "buck2_dice_keys"));
+        assert_eq!(Some("3"), sample(&metrics, "buck2_materializer_queue_size"));
+        assert_eq!(
+            Some("3"),
+            sample(
+                &metrics,
+                "buck2_re_requests_in_flight{operation=\"upload\"}"
+            )
+        );
+        assert_eq!(
+            Some("1"),
+            sample(
+                &metrics,
+                "buck2_re_requests_finished_total{operation=\"upload\",result=\"error\"}"
+            )
+        );
+        assert_eq!(
+            Some("7"),
+            sample(
+                &metrics,
+                "buck2_network_bytes_total{interface=\"eth0\",direction=\"receive\"}"
+            )
+        );
+        // Unknown values are left out rather than reported as zero.
+        assert!(!metrics.contains("buck2_malloc_active_bytes"));
+        assert!(metrics.ends_with("# EOF\n"));
+    }
+
+    async fn request(request: &str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            serve(stream, async { "buck2_dice_keys 1\n# EOF\n".to_owned() })
+                .await
+                .unwrap();
+        });
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        server.await.unwrap();
+        response
+    }
+
+    #[tokio::test]
+    async fn test_serve() {
+        let response = request("GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n").await;
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{response}");
+        assert!(response.contains(&format!("Content-Type: {CONTENT_TYPE}\r\n")));
+        assert!(response.ends_with("\r\n\r\nbuck2_dice_keys 1\n# EOF\n"));
+
+        let response = request("GET /metrics?format=openmetrics HTTP/1.1\r\n\r\n").await;
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{response}");
+
+        let response = request("GET / HTTP/1.1\r\n\r\n").await;
+        assert!(
+            response.starts_with("HTTP/1.1 404 Not Found\r\n"),
+            "{response}"
+        );
+
+        let response = request("POST /metrics HTTP/1.1\r\n\r\n").await;
+        assert!(
+            response.starts_with("HTTP/1.1 405 Method Not Allowed\r\n"),
+            "{response}"
+        );
+    }
+
+    #[test]
+    fn test_escape_label_value() {
+        assert_eq!("a\\\"b\\\\c\\nd", escape_label_value("a\"b\\c\nd"));
+    }
+}
@@ -8,6 +8,8 @@
  * above-listed licenses.
  */
 
+pub(crate) mod events;
+
 use std::time::Duration;
 
 use buck2_error::BuckErrorContext;
@@ -22,6 +24,8 @@ use gazebo::prelude::*;
 use tokio::time::MissedTickBehavior;
 
 use crate::active_commands;
+use crate::subscription::events::EventFilter;
+use crate::subscription::events::EventSubscription;
 
 pub(crate) async fn run_subscription_server_command(
     ctx: &dyn ServerCommandContextTrait,
@@ -50,6 +54,7 @@ pub(crate) async fn run_subscription_server_command(
                 .buck_error_context("Error creating a materializer subscription")?;
 
             let mut wants_active_commands = false;
+            let mut event_subscription: Option<EventSubscription> = None;
 
             let mut ticker = tokio::time::interval(Duration::from_millis(100));
             ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
@@ -76,6 +81,12 @@ pub(crate) async fn run_subscription_server_command(
                             Request::SubscribeToActiveCommands(buck2_subscription_proto::SubscribeToActiveCommands {}) => {
                                 wants_active_commands = true;
                             }
+                            Request::SubscribeToEvents(subscribe) => {
+                                event_subscription = Some(EventSubscription::new(EventFilter::new(subscribe)?));
+                            }
+                            Request::UnsubscribeFromEvents(buck2_subscription_proto::UnsubscribeFromEvents {}) => {
+                                event_subscription = None;
+                            }
                         }
                     }
                     path = materializer_subscription.next_materialization().fuse() => {
@@ -86,6 +97,19 @@ pub(crate) async fn run_subscription_server_command(
                             })
                         });
                     }
+                    response = async {
+                        match &mut event_subscription {
+                            Some(subscription) => subscription.next().await,
+                            None => futures::future::pending().await,
+                        }
+                    }.fuse() => {
+                        let response = response.internal_error("Event subscription hung up")?;
+                        partial_result_dispatcher.emit(buck2_cli_proto::SubscriptionResponseWrapper {
+                            response: Some(buck2_subscription_proto::SubscriptionResponse {
+                                response: Some(response)
+                            })
+                        });
+                    }
                     _ = ticker.tick().fuse() => {
                         if wants_active_commands {
                             let snapshot = buck2_subscription_proto::ActiveCommandsSnapshot {
@@ -0,0 +1,529 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is dual-licensed under either the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree or the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree. You may select, at your option, one of the
+ * above-listed licenses.
+ */
+
+//! Notifies subscriptions of the events of every command the daemon runs.
+
+use std::sync::Arc;
+use std::sync::LazyLock;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use buck2_events::BuckEvent;
+use buck2_hash::BuckMutMap;
+use buck2_subscription_proto::EventKind;
+use buck2_subscription_proto::subscription_response::Response;
+use parking_lot::Mutex;
+use tokio::sync::mpsc;
+
+static SUBSCRIPTIONS: LazyLock<Mutex<BuckMutMap<u64, Subscriber>>> =
+    LazyLock::new(|| Mutex::new(BuckMutMap::default()));
+
+/// Number of entries in `SUBSCRIPTIONS`, checked for every event without taking the lock.
+static SUBSCRIPTION_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Notifications buffered per subscription. Past that, notifications are dropped rather than
+/// buffered without bound for a client which doesn't keep up.
+const CHANNEL_CAPACITY: usize = 10000;
+
+#[derive(Debug, buck2_error::Error)]
+#[buck2(tag = Input)]
+enum EventSubscriptionError {
+    #[error(
+        "Invalid target pattern `{0}`: expected a target pattern fully qualified with its cell, \
+        like `cell//pkg:name`, `cell//pkg:` or `cell//pkg/...`"
+    )]
+    InvalidTargetPattern(String),
+    #[error("Unknown event kind: {0}")]
+    UnknownEventKind(i32),
+}
+
+struct Subscriber {
+    filter: EventFilter,
+    sender: mpsc::Sender<Response>,
+    /// Notifications dropped because the channel was full, and not reported yet.
+    dropped: Arc<AtomicU64>,
+}
+
+/// Receives the notifications matching a filter until dropped.
+pub(crate) struct EventSubscription {
+    id: u64,
+    receiver: mpsc::Receiver<Response>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl EventSubscription {
+    pub(crate) fn new(filter: EventFilter) -> Self {
+        Self::with_capacity(filter, CHANNEL_CAPACITY)
+    }
+
+    fn with_capacity(filter: EventFilter, capacity: usize) -> Self {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let (sender, receiver) = mpsc::channel(capacity);
+        let dropped = Arc::new(AtomicU64::new(0));
+        SUBSCRIPTIONS.lock().insert(
+            id,
+            Subscriber {
+                filter,
+                sender,
+                dropped: dropped.clone(),
+            },
+        );
+        SUBSCRIPTION_COUNT.fetch_add(1, Ordering::Relaxed);
+        Self {
+            id,
+            receiver,
+            dropped,
+        }
+    }
+
+    /// Returns the next notification, or an `EventsDropped` notification first if notifications
+    /// were dropped since the previous call.
+    pub(crate) async fn next(&mut self) -> Option<Response> {
+        let count = self.dropped.swap(0, Ordering::Relaxed);
+        if count > 0 {
+            return Some(buck2_subscription_proto::EventsDropped { count }.into());
+        }
+        self.receiver.recv().await
+    }
+}
+
+impl Drop for EventSubscription {
+    fn drop(&mut self) {
+        if SUBSCRIPTIONS.lock().remove(&self.id).is_some() {
+            SUBSCRIPTION_COUNT.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Which events a subscription wants to be notified of.
+pub(crate) struct EventFilter {
+    kinds: Vec<EventKind>,
+    patterns: Vec<TargetPatternFilter>,
+}
+
+impl EventFilter {
+    pub(crate) fn new(
+        request: buck2_subscription_proto::SubscribeToEvents,
+    ) -> buck2_error::Result<Self> {
+        let kinds = request
+            .kinds
+            .into_iter()
+            .map(|kind| match EventKind::try_from(kind) {
+                Ok(kind) if kind != EventKind::Unspecified => Ok(kind),
+                _ => Err(EventSubscriptionError::UnknownEventKind(kind).into()),
+            })
+            .collect::<buck2_error::Result<_>>()?;
+        let patterns = request
+            .target_patterns
+            .iter()
+            .map(|pattern| TargetPatternFilter::parse(pattern))
+            .collect::<buck2_error::Result<_>>()?;
+        Ok(Self { kinds, patterns })
+    }
+
+    fn matches(&self, kind: EventKind, target: Option<&buck2_data::TargetLabel>) -> bool {
+        if !self.kinds.contains(&kind) {
+            return false;
+        }
+        if self.patterns.is_empty() || kind == EventKind::FilesChanged {
+            return true;
+        }
+        target.is_some_and(|target| {
+            self.patterns
+                .iter()
+                .any(|pattern| pattern.matches(&target.package, &target.name))
+        })
+    }
+}
+
+enum TargetPatternFilter {
+    /// `cell//pkg:name`
+    Target { package: String, name: String },
+    /// `cell//pkg:`
+    Package(String),
+    /// `cell//pkg/...`, or `cell//...` stored as `cell//`.
+    Recursive(String),
+}
+
+impl TargetPatternFilter {
+    fn parse(pattern: &str) -> buck2_error::Result<Self> {
+        let invalid = || EventSubscriptionError::InvalidTargetPattern(pattern.to_owned());
+        let filter = if let Some(cell) = pattern.strip_suffix("//...") {
+            Self::Recursive(format!("{cell}//"))
+        } else if let Some(package) = pattern.strip_suffix("/...") {
+            Self::Recursive(package.to_owned())
+        } else {
+            let (package, name) = pattern.rsplit_once(':').ok_or_else(invalid)?;
+            if name.is_empty() {
+                Self::Package(package.to_owned())
+            } else {
+                Self::Target {
+                    package: package.to_owned(),
+                    name: name.to_owned(),
+                }
+            }
+        };
+        let package = match &filter {
+            Self::Target { package, .. } | Self::Package(package) | Self::Recursive(package) => {
+                package
+            }
+        };
+        match package.split_once("//") {
+            Some((cell, _)) if !cell.is_empty() && !package.contains(':') => Ok(filter),
+            _ => Err(invalid().into()),
+        }
+    }
+
+    fn matches(&self, target_package: &str, target_name: &str) -> bool {
+        match self {
+            Self::Target { package, name } => package == target_package && name == target_name,
+            Self::Package(package) => package == target_package,
+            Self::Recursive(package) if package.ends_with("//") => {
+                target_package.starts_with(package.as_str())
+            }
+            Self::Recursive(package) => target_package
+                .strip_prefix(package.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/')),
+        }
+    }
+}
+
+/// Notifies the subscriptions this event is relevant to.
+pub(crate) fn notify(event: &BuckEvent) {
+    if SUBSCRIPTION_COUNT.load(Ordering::Relaxed) == 0 {
+        return;
+    }
+    let Some((kind, target, response)) = notification(event) else {
+        return;
+    };
+    for subscriber in SUBSCRIPTIONS.lock().values() {
+        if subscriber.filter.matches(kind, target) {
+            match subscriber.sender.try_send(response.clone()) {
+                Ok(()) => {}
+                Err(mpsc::error::TrySendError::Full(_)) => {
+                    subscriber.dropped.fetch_add(1, Ordering::Relaxed);
+                }
+                // The subscription is going away, nothing to do then.
+                Err(mpsc::error::TrySendError::Closed(_)) => {}
+            }
+        }
+    }
+}
+
+/// Converts an event into a notification, if subscriptions can be notified of it.
+fn notification(
+    event: &BuckEvent,
+) -> Option<(EventKind, Option<&buck2_data::TargetLabel>, Response)> {
+    use buck2_data::buck_event::Data;
+
+    let trace_id = event.event().trace_id.clone();
+    match event.data() {
+        Data::Instant(instant) => match instant.data.as_ref()? {
+            buck2_data::instant_event::Data::TopLevelTargetBuilt(built) => {
+                let (target, label, configuration) = configured_target(built.target.as_ref());
+                Some((
+                    EventKind::TargetBuilt,
+                    target,
+                    buck2_subscription_proto::TargetBuilt {
+                        trace_id,
+                        target: label,
+                        configuration,
+                        provider: built.provider.clone(),
+                        success: built.success,
+                    }
+                    .into(),
+                ))
+            }
+            buck2_data::instant_event::Data::TestResult(result) => {
+                let (target, label, configuration) =
+                    configured_target(result.target_label.as_ref());
+                Some((
+                    EventKind::TestResult,
+                    target,
+                    buck2_subscription_proto::TestResult {
+                        trace_id,
+                        target: label,
+                        configuration,
+                        name: result.name.clone(),
+                        status: result.status().as_str_name().to_owned(),
+                        message: result.msg.as_ref().map(|msg| msg.msg.clone()),
+                        duration_ms: result
+                            .duration
+                            .and_then(|duration| Duration::try_from(duration).ok())
+                            .map(|duration| duration.as_millis() as u64),
+                    }
+                    .into(),
+                ))
+            }
+            _ => None,
+        },
+        Data::SpanEnd(end) => match end.data.as_ref()? {
+            buck2_data::span_end_event::Data::ActionExecution(action) if action.failed => {
+                let owner = match action.key.as_ref().and_then(|key| key.owner.as_ref()) {
+                    Some(buck2_data::action_key::Owner::TargetLabel(label)) => Some(label),
+                    _ => None,
+                };
+                let (target, label, configuration) = configured_target(owner);
+                let error = match &action.error {
+                    Some(buck2_data::action_execution_end::Error::Unknown(message)) => {
+                        Some(message.clone())
+                    }
+                    Some(buck2_data::action_execution_end::Error::MissingOutputs(missing)) => {
+                        Some(missing.message.clone())
+                    }
+                    _ => None,
+                };
+                let name = action.name.clone().unwrap_or_default();
+                Some((
+                    EventKind::ActionFailed,
+                    target,
+                    buck2_subscription_proto::ActionFailed {
+                        trace_id,
+                        target: label,
+                        configuration,
+                        category: name.category,
+                        identifier: name.identifier,
+                        error,
+                    }
+                    .into(),
+                ))
+            }
+            buck2_data::span_end_event::Data::FileWatcher(file_watcher) => {
+                let stats = file_watcher.stats.as_ref()?;
+                Some((
+                    EventKind::FilesChanged,
+                    None,
+                    buck2_subscription_proto::FilesChanged {
+                        trace_id,
+                        paths: stats
+                            .events
+                            .iter()
+                            .map(|event| event.path.clone())
+                            .collect(),
+                        changes: stats.events_processed,
+                        incomplete_reason: stats.incomplete_events_reason.clone(),
+                        fresh_instance: stats.fresh_instance,
+                    }
+                    .into(),
+                ))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The target label, its display and the display of its configuration.
+fn configured_target(
+    label: Option<&buck2_data::ConfiguredTargetLabel>,
+) -> (Option<&buck2_data::TargetLabel>, String, String) {
+    let target = label.and_then(|label| label.label.as_ref());
+    (
+        target,
+        target
+            .map(|target| format!("{}:{}", target.package, target.name))
+            .unwrap_or_default(),
+        label
+            .and_then(|label| label.configuration.as_ref())
+            .map(|configuration| configuration.full_name.clone())
+            .unwrap_or_default(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::SystemTime;
+
+    use buck2_wrapper_common::invocation_id::TraceId;
+
+    use super::*;
+
+    fn filter(kinds: &[EventKind], patterns: &[&str]) -> EventFilter {
+        EventFilter::new(buck2_subscription_proto::SubscribeToEvents {
+            kinds: kinds.iter().map(|kind| *kind as i32).collect(),
+            target_patterns: patterns.iter().map(|p| (*p).to_owned()).collect(),
+        })
+        .unwrap()
+    }
+
+    fn label(package: &str, name: &str) -> buck2_data::TargetLabel {
+        buck2_data::TargetLabel {
+            package: package.to_owned(),
+            name: name.to_owned(),
+        }
+    }
+
+    fn target_built(package: &str, name: &str) -> BuckEvent {
+        BuckEvent::new(
+            SystemTime::now(),
+            TraceId::null(),
+            None,
+            None,
+            buck2_data::InstantEvent {
+                data: Some(
+                    buck2_data::TopLevelTargetBuilt {
+                        target: Some(buck2_data::ConfiguredTargetLabel {
+                            label: Some(label(package, name)),
+                            configuration: Some(buck2_data::Configuration {
+                                full_name: "cfg".to_owned(),
+                            }),
+                            execution_configuration: None,
+                        }),
+                        provider: None,
+                        success: true,
+                    }
+                    .into(),
+                ),
+            }
+            .into(),
+        )
+    }
+
+    #[test]
+    fn test_target_patterns() {
+        let pattern = |p| TargetPatternFilter::parse(p).unwrap();
+        assert!(pattern("root//foo:bar").matches("root//foo", "bar"));
+        assert!(!pattern("root//foo:bar").matches("root//foo", "baz"));
+        assert!(pattern("root//foo:").matches("root//foo", "baz"));
+        assert!(!pattern("root//foo:").matches("root//foo/bar", "baz"));
+        assert!(pattern("root//foo/...").matches("root//foo", "bar"));
+        assert!(pattern("root//foo/...").matches("root//foo/bar", "baz"));
+        assert!(!pattern("root//foo/...").matches("root//foobar", "baz"));
+        assert!(pattern("root//...").matches("root//foo", "bar"));
+        assert!(!pattern("root//...").matches("other//foo", "bar"));
+
+        for invalid in ["//foo:bar", "foo:bar", "root//foo", ":bar"] {
+            assert!(TargetPatternFilter::parse(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_filter() {
+        let target = label("root//foo", "bar");
+        let filter = filter(
+            &[EventKind::TargetBuilt, EventKind::FilesChanged],
+            &["root//foo:"],
+        );
+        assert!(filter.matches(EventKind::TargetBuilt, Some(&target)));
+        assert!(!filter.matches(EventKind::TargetBuilt, Some(&label("root//baz", "bar"))));
+        assert!(!filter.matches(EventKind::TargetBuilt, None));
+        assert!(!filter.matches(EventKind::TestResult, Some(&target)));
+        // File changes are not associated with targets.
+        assert!(filter.matches(EventKind::FilesChanged, None));
+
+        assert!(
+            EventFilter::new(buck2_subscription_proto::SubscribeToEvents {
+                kinds: vec![EventKind::Unspecified as i32],
+                target_patterns: Vec::new(),
+            })
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_notification() {
+        let event = BuckEvent::new(
+            SystemTime::now(),
+            TraceId::null(),
+            None,
+            None,
+            buck2_data::SpanEndEvent {
+                data: Some(
+                    buck2_data::ActionExecutionEnd {
+                        key: Some(buck2_data::ActionKey {
+                            owner: Some(buck2_data::action_key::Owner::TargetLabel(
+                                buck2_data::ConfiguredTargetLabel {
+                                    label: Some(label("root//foo", "bar")),
+                                    configuration: None,
+                                    execution_configuration: None,
+                                },
+                            )),
+                            ..Default::default()
+                        }),
+                        name: Some(buck2_data::ActionName {
+                            category: "cxx_compile".to_owned(),
+                            identifier: "main.cpp".to_owned(),
+                        }),
+                        failed: true,
+                        error: Some(buck2_data::action_execution_end::Error::Unknown(
+                            "boom".to_owned(),
+                        )),
+                        ..Default::default()
+                    }
+                    .into(),
+                ),
+                ..Default::default()
+            }
+            .into(),
+        );
+        let (kind, target, response) = notification(&event).unwrap();
+        assert_eq!(EventKind::ActionFailed, kind);
+        assert_eq!(Some(&label("root//foo", "bar")), target);
+        let Response::ActionFailed(failed) = response else {
+            panic!("Unexpected notification: {response:?}");
+        };
+        assert_eq!("root//foo:bar", failed.target);
+        assert_eq!("cxx_compile", failed.category);
+        assert_eq!(Some("boom"), failed.error.as_deref());
+    }
+
+    #[tokio::test]
+    async fn test_notify() {
+        let mut subscription =
+            EventSubscription::new(filter(&[EventKind::TargetBuilt], &["root//test_notify:"]));
+        notify(&target_built("root//other", "bar"));
+        notify(&target_built("root//test_notify", "bar"));
+
+        let Some(Response::TargetBuilt(built)) = subscription.next().await else {
+            panic!("Expected a TargetBuilt notification");
+        };
+        assert_eq!("root//test_notify:bar", built.target);
+        assert_eq!("cfg", built.configuration);
+        assert!(built.success);
+        assert!(subscription.receiver.try_recv().is_err());
+
+        let id = subscription.id;
+        drop(subscription);
+        assert!(!SUBSCRIPTIONS.lock().contains_key(&id));
+    }
+
+    #[tokio::test]
+    async fn test_notify_drops_when_full() {
+        let mut subscription = EventSubscription::with_capacity(
+            filter(
+                &[EventKind::TargetBuilt],
+                &["root//test_notify_drops_when_full:"],
+            ),
+            2,
+        );
+        for name in ["a", "b", "c", "d"] {
+            notify(&target_built("root//test_notify_drops_when_full", name));
+        }
+
+        let Some(Response::EventsDropped(dropped)) = subscription.next().await else {
+            panic!("Expected an EventsDropped notification");
+        };
+        assert_eq!(2, dropped.count);
+        for expected in ["a", "b"] {
+            let Some(Response::TargetBuilt(built)) = subscription.next().await else {
+                panic!("Expected a TargetBuilt notification");
+            };
+            assert_eq!(
+                format!("root//test_notify_drops_when_full:{expected}"),
+                built.target
+            );
+        }
+        assert!(subscription.receiver.try_recv().is_err());
+    }
+}
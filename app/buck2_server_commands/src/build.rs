@@ -13,6 +13,7 @@ use std::time::Instant;
 
 use async_trait::async_trait;
 use buck2_build_api::actions::artifact::get_artifact_fs::GetArtifactFs;
+use buck2_build_api::actions::execute::dice_data::GetCacheProbeTracker;
 use buck2_build_api::build;
 use buck2_build_api::build::AsyncBuildTargetResultBuilder;
 use buck2_build_api::build::BuildEvent;
@@ -62,6 +63,7 @@ use buck2_data::BuildResult;
 use buck2_data::ToProtoMessage;
 use buck2_error::BuckErrorContext;
 use buck2_error::BuckErrorOptionContext;
+use buck2_error::ErrorTag;
 use buck2_error::internal_error;
 use buck2_events::dispatch::console_message;
 use buck2_events::dispatch::instant_event;
@@ -229,13 +231,18 @@ async fn build(
 
     let build_providers = Arc::new(request.build_providers.unwrap());
 
-    let final_artifact_materializations =
+    let mut final_artifact_materializations =
         Materializations::try_from(request.final_artifact_materializations)
             .with_buck_error_context(|| "Invalid final_artifact_materializations")
             .unwrap();
-    let final_artifact_uploads = Uploads::try_from(request.final_artifact_uploads)
+    let mut final_artifact_uploads = Uploads::try_from(request.final_artifact_uploads)
         .with_buck_error_context(|| "Invalid final_artifact_uploads")
         .unwrap();
+    if build_opts.dry_run {
+        // Only the cache lookups happen in a dry run: nothing is downloaded or uploaded.
+        final_artifact_materializations = Materializations::Skip;
+        final_artifact_uploads = Uploads::Never;
+    }
 
     let want_configured_graph_size = ctx
         .ctx()
@@ -720,6 +727,7 @@ async fn process_build_result(
             detailed_metrics,
             action_graph_sketch_result,
             artifact_path_sketch_result,
+            None,
         )?
     } else {
         None
@@ -766,17 +774,25 @@ async fn process_build_result(
         .build_errors
         .errors
         .iter()
+        // Actions that would execute are listed in the dry run report instead.
+        .filter(|e| !e.has_tag(ErrorTag::ActionDryRunWouldExecute))
         .map(buck2_data::ErrorReport::from)
         .unique_by(|e| e.message.clone())
         .collect();
 
     let project_root = server_ctx.project_root().to_string();
 
+    let dry_run_report = ctx
+        .per_transaction_data()
+        .get_cache_probe_tracker()
+        .map(|tracker| tracker.to_proto());
+
     Ok(buck2_cli_proto::BuildResponse {
         build_targets,
         project_root,
         serialized_build_report,
         errors,
+        dry_run_report,
     })
 }
 
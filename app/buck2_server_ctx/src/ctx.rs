@@ -331,6 +331,9 @@ impl ServerCommandDiceContext for dyn ServerCommandContextTrait + '_ {
                                                         .to_owned(),
                                                     early_command_timing: early_command_timing
                                                         .finish_early_command_timing(),
+                                                    log_build_graph: dice
+                                                        .per_transaction_data()
+                                                        .get_log_build_graph(),
                                                 },
                                                 || exec(self, dice),
                                             )
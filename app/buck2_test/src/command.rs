@@ -8,6 +8,7 @@
  * above-listed licenses.
  */
 
+use std::collections::BTreeMap;
 use std::ops::ControlFlow;
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -25,6 +26,7 @@ use buck2_build_api::build::BuildTargetResultBuilder;
 use buck2_build_api::build::ConfiguredBuildEventVariant;
 use buck2_build_api::build::ProvidersToBuild;
 use buck2_build_api::build::build_configured_label;
+use buck2_build_api::build::build_report::BuildReportTestCounts;
 use buck2_build_api::build::build_report::build_report_opts;
 use buck2_build_api::build::build_report::write_build_report;
 use buck2_build_api::interpreter::rule_defs::provider::builtin::internal_runner_test_info::FrozenInternalRunnerTestInfo;
@@ -123,6 +125,8 @@ use crate::orchestrator::BuckTestOrchestrator;
 use crate::orchestrator::ExecutorMessage;
 use crate::session::TestSession;
 use crate::session::TestSessionOptions;
+use crate::shard::TestSharding;
+use crate::shard::test_case_key;
 use crate::translations::build_configured_target_handle;
 
 struct TestOutcome {
@@ -208,13 +212,32 @@ struct TestStatuses {
     fatals: CounterWithExamples,
     listing_success: CounterWithExamples,
     listing_failed: CounterWithExamples,
+    flaky: CounterWithExamples,
+    /// Seconds spent running each sharding unit, see `buck2 test --shard`.
+    durations: BTreeMap<String, f64>,
 }
 impl TestStatuses {
     fn ingest(&mut self, result: &TestResult, session: &TestSession) {
-        let display_name: std::borrow::Cow<'_, str> = match session.get(result.target) {
+        let label = session.get(result.target);
+        let display_name: std::borrow::Cow<'_, str> = match &label {
             Ok(label) => std::borrow::Cow::Owned(format!("{} ({})", result.name, label.cfg())),
             Err(_) => std::borrow::Cow::Borrowed(result.name.as_str()),
         };
+        if let (Ok(label), Some(duration)) = (&label, result.duration) {
+            let target = label.target().unconfigured();
+            let unit = if session.is_sharded_by_test_case(result.target) {
+                test_case_key(target, &result.name)
+            } else {
+                target.to_string()
+            };
+            let is_listing = matches!(
+                result.status,
+                TestStatus::LISTING_SUCCESS | TestStatus::LISTING_FAILED
+            );
+            if !is_listing {
+                *self.durations.entry(unit).or_default() += duration.as_secs_f64();
+            }
+        }
         let name = display_name.as_ref();
         match result.status {
             TestStatus::PASS => self.passed.add(name),
@@ -228,6 +251,21 @@ impl TestStatuses {
             TestStatus::RERUN => {}
             TestStatus::LISTING_SUCCESS => self.listing_success.add(name),
             TestStatus::LISTING_FAILED => self.listing_failed.add(name),
+            TestStatus::FLAKY => self.flaky.add(name),
+        }
+    }
+
+    fn build_report_counts(&self) -> BuildReportTestCounts {
+        BuildReportTestCounts {
+            passed: self.passed.count,
+            failed: self.failed.count,
+            fatal: self.fatals.count,
+            timed_out: self.timed_out.count,
+            skipped: self.skipped.count,
+            omitted: self.omitted.count,
+            infra_failure: self.infra_failure.count,
+            flaky: self.flaky.count,
+            durations: self.durations.clone(),
         }
     }
 }
@@ -273,6 +311,18 @@ impl InternalRunnerConfig {
     }
 }
 
+/// Whether the target's tests are run in-process, which lists its test cases in Buck2.
+fn uses_internal_runner(
+    providers: &FrozenProviderCollectionValue,
+    internal_runner_config: &InternalRunnerConfig,
+) -> bool {
+    providers
+        .builtin_provider_value::<FrozenInternalRunnerTestInfo>()
+        .is_some_and(|provider| {
+            internal_runner_config.should_use(provider.as_ref().value().as_ref().test_type())
+        })
+}
+
 async fn test_command(
     ctx: &dyn ServerCommandContextTrait,
     partial_result_dispatcher: PartialResultDispatcher<NoPartialResult>,
@@ -360,6 +410,11 @@ async fn test(
         };
     let build_default_info = request.build_default_info.unwrap_or(test_builds_targets);
     let build_run_info = request.build_run_info.unwrap_or(test_builds_targets);
+    let sharding = request
+        .shard
+        .as_ref()
+        .map(TestSharding::from_proto)
+        .transpose()?;
 
     let client_ctx = request.client_context()?;
     let global_cfg_options = global_cfg_options_from_client_context(
@@ -503,6 +558,7 @@ async fn test(
         build_default_info,
         build_run_info,
         tpx_experiments,
+        sharding.as_ref(),
     )
     .await?;
 
@@ -526,6 +582,8 @@ async fn test(
         .count()
         .try_into()?;
 
+    let build_report_test_counts = test_outcome.executor_report.statuses.build_report_counts();
+
     let test_statuses = buck2_cli_proto::test_response::TestStatuses {
         passed: Some(
             test_outcome
@@ -590,6 +648,13 @@ async fn test(
                 .timed_out
                 .into_cli_proto_counter(),
         ),
+        flaky: Some(
+            test_outcome
+                .executor_report
+                .statuses
+                .flaky
+                .into_cli_proto_counter(),
+        ),
         build_errors: build_errors_count,
     };
 
@@ -618,6 +683,7 @@ async fn test(
             None,
             None,
             None,
+            Some(build_report_test_counts),
         )?
     } else {
         None
@@ -655,6 +721,7 @@ async fn test_targets(
     build_default_info: bool,
     build_run_info: bool,
     tpx_experiments: BuckMutSet<String>,
+    sharding: Option<&TestSharding>,
 ) -> buck2_error::Result<TestOutcome> {
     let session = Arc::new(session);
 
@@ -785,6 +852,7 @@ async fn test_targets(
                     ignore_tests_attribute,
                     build_default_info,
                     build_run_info,
+                    sharding,
                 });
 
                 driver.push_pattern(
@@ -969,6 +1037,7 @@ struct TestDriverState<'a, 'e> {
     ignore_tests_attribute: bool,
     build_default_info: bool,
     build_run_info: bool,
+    sharding: Option<&'a TestSharding>,
 }
 
 /// Maintains the state of an ongoing test execution.
@@ -1375,6 +1444,8 @@ impl<'a, 'e> TestDriver<'a, 'e> {
                             modifiers_dupe,
                             state.build_default_info,
                             state.build_run_info,
+                            state.sharding,
+                            state.internal_runner_config,
                         )
                         .await
                     }
@@ -1460,6 +1531,8 @@ async fn build_target_result(
     modifiers: Modifiers,
     build_default_info: bool,
     build_run_info: bool,
+    sharding: Option<&TestSharding>,
+    internal_runner_config: &InternalRunnerConfig,
 ) -> buck2_error::Result<(BuildTargetResult, FrozenProviderCollectionValue)> {
     // NOTE: We fail if we hit an incompatible target here. This can happen if we reach an
     // incompatible target via `tests = [...]`. This should perhaps change, but that's how it works
@@ -1476,6 +1549,14 @@ async fn build_target_result(
     // 2. --build-default-info is requested
     // 3. --build-run-info is requested and the target produces a RunInfo
     if let Some(test_info) = test_provider_from_collection(collections) {
+        // Targets sharded by test case are built on every shard, the others only on their own.
+        if let Some(sharding) = sharding {
+            if !uses_internal_runner(&providers, internal_runner_config)
+                && !sharding.includes_target(label.target().unconfigured())
+            {
+                return Ok((BuildTargetResult::new(), providers));
+            }
+        }
         let skip_build_based_on_labels = !label_filtering.build_filtered_targets
             && label_filtering.is_excluded(test_info.labels());
         if skip_build_based_on_labels {
@@ -1570,6 +1651,11 @@ async fn test_target<'a, 'e>(
                 test_config_unification_rollout,
                 oncall,
             )?;
+            if driver_state.sharding.is_some() {
+                driver_state
+                    .session
+                    .mark_sharded_by_test_case(handle.handle);
+            }
 
             let orchestrator = driver_state
                 .internal_orchestrator
@@ -1614,12 +1700,19 @@ async fn test_target<'a, 'e>(
                 );
                 (spec, listing_spec)
             };
+            let unconfigured = target.target().unconfigured();
+            let include_test_case = |test_case: &str| {
+                driver_state
+                    .sharding
+                    .is_none_or(|sharding| sharding.includes_test_case(unconfigured, test_case))
+            };
             crate::internal_runner::run_internal_test(
                 orchestrator.as_ref(),
                 spec,
                 listing_spec,
                 &internal_provider,
                 internal_test_timeout,
+                &include_test_case,
             )
             .await?;
             return Ok(Some(target));
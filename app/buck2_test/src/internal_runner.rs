@@ -47,6 +47,7 @@ pub async fn run_internal_test(
     listing_spec: ExternalRunnerSpec,
     provider: &OwnedInternalRunnerTestInfo,
     timeout: Duration,
+    include_test_case: &(dyn Fn(&str) -> bool + Sync),
 ) -> buck2_error::Result<()> {
     let target_handle = spec.target.handle;
     let suite = spec.target.target.clone();
@@ -122,6 +123,11 @@ pub async fn run_internal_test(
         .as_ref()
         .parse_test_listing_output(&listing_output)
         .buck_error_context("Failed to parse test listing output")?;
+    // Test cases belonging to other shards are neither reported nor run.
+    let discovered_tests: Vec<_> = discovered_tests
+        .into_iter()
+        .filter(|t| include_test_case(&t.name))
+        .collect();
 
     // Step 3: Report discovered tests
     let test_names: Vec<String> = discovered_tests.iter().map(|t| t.name.clone()).collect();
@@ -177,6 +183,13 @@ pub async fn run_internal_test(
     while let Some((entry_name, test_response)) = futures.next().await {
         match test_response {
             Ok(ExecuteResponse::Result(result)) => {
+                let stdout_str = match &result.stdout {
+                    ExecutionStream::Inline(bytes) => String::from_utf8_lossy(bytes).to_string(),
+                };
+                let stderr_str = match &result.stderr {
+                    ExecutionStream::Inline(bytes) => String::from_utf8_lossy(bytes).to_string(),
+                };
+
                 let exit_code = match result.status {
                     ExecutionStatus::Finished { exitcode } => exitcode,
                     ExecutionStatus::TimedOut { .. } => {
@@ -189,6 +202,8 @@ pub async fn run_internal_test(
                             duration: Some(result.execution_time),
                             details: format_execution_output(&result.stdout, &result.stderr),
                             max_memory_used_bytes: result.max_memory_used_bytes,
+                            stdout: Some(stdout_str),
+                            stderr: Some(stderr_str),
                         };
                         orchestrator
                             .report_test_result(test_result)
@@ -198,13 +213,6 @@ pub async fn run_internal_test(
                     }
                 };
 
-                let stdout_str = match &result.stdout {
-                    ExecutionStream::Inline(bytes) => String::from_utf8_lossy(bytes).to_string(),
-                };
-                let stderr_str = match &result.stderr {
-                    ExecutionStream::Inline(bytes) => String::from_utf8_lossy(bytes).to_string(),
-                };
-
                 let result_entries = provider
                     .as_ref()
                     .value()
@@ -231,6 +239,8 @@ pub async fn run_internal_test(
                         duration: Some(result.execution_time),
                         details: format_execution_output(&result.stdout, &result.stderr),
                         max_memory_used_bytes: result.max_memory_used_bytes,
+                        stdout: Some(stdout_str),
+                        stderr: Some(stderr_str),
                     };
                     orchestrator
                         .report_test_result(test_result)
@@ -246,6 +256,10 @@ pub async fn run_internal_test(
                             duration: res.duration,
                             details: res.details.unwrap_or_default(),
                             max_memory_used_bytes: result.max_memory_used_bytes,
+                            // The parser picks the output of each test case out of the
+                            // output of the whole execution.
+                            stdout: None,
+                            stderr: None,
                         };
                         orchestrator
                             .report_test_result(test_result)
@@ -263,6 +277,8 @@ pub async fn run_internal_test(
                     duration: None,
                     details: String::new(),
                     max_memory_used_bytes: None,
+                    stdout: None,
+                    stderr: None,
                 };
                 orchestrator
                     .report_test_result(test_result)
@@ -278,6 +294,8 @@ pub async fn run_internal_test(
                     duration: None,
                     details: String::new(),
                     max_memory_used_bytes: None,
+                    stdout: None,
+                    stderr: None,
                 };
                 orchestrator
                     .report_test_result(test_result)
@@ -22,6 +22,7 @@ pub(crate) mod local_resource_setup;
 pub mod orchestrator;
 pub(crate) mod remote_storage;
 pub mod session;
+pub(crate) mod shard;
 pub(crate) mod tcp;
 pub mod translations;
 #[cfg(unix)]
@@ -2654,6 +2654,8 @@ mod tests {
                     duration: Some(Duration::from_micros(1)),
                     details: "1".to_owned(),
                     max_memory_used_bytes: None,
+                    stdout: None,
+                    stderr: None,
                 })
                 .await?;
 
@@ -2666,6 +2668,8 @@ mod tests {
                     duration: Some(Duration::from_micros(2)),
                     details: "2".to_owned(),
                     max_memory_used_bytes: None,
+                    stdout: None,
+                    stderr: None,
                 })
                 .await?;
 
@@ -2688,6 +2692,8 @@ mod tests {
                     duration: Some(Duration::from_micros(1)),
                     details: "1".to_owned(),
                     max_memory_used_bytes: None,
+                    stdout: None,
+                    stderr: None,
                 }),
                 ExecutorMessage::TestResult(TestResult {
                     target,
@@ -2698,6 +2704,8 @@ mod tests {
                     duration: Some(Duration::from_micros(2)),
                     details: "2".to_owned(),
                     max_memory_used_bytes: None,
+                    stdout: None,
+                    stderr: None,
                 }),
                 ExecutorMessage::ExitCode(0),
             ]
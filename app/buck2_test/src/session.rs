@@ -16,6 +16,7 @@ use allocative::Allocative;
 use buck2_core::provider::label::ConfiguredProvidersLabel;
 use buck2_error::BuckErrorOptionContext;
 use buck2_hash::BuckDashMap;
+use buck2_hash::BuckDashSet;
 use buck2_test_api::data::ConfiguredTargetHandle;
 use dupe::Dupe;
 use pagable::Pagable;
@@ -47,6 +48,8 @@ pub struct TestSession {
     /// A mapping of ConfiguredTargetHandle (which Tpx can use with) to the underlying provider in
     /// Buck2.
     labels: BuckDashMap<ConfiguredTargetHandle, ConfiguredProvidersLabel>,
+    /// Targets sharded by test case rather than as a whole, see `buck2 test --shard`.
+    sharded_by_test_case: BuckDashSet<ConfiguredTargetHandle>,
     /// Options overriding the behavior of tests executed in this session. This is primarily
     /// intended for unstable or debugging features.
     options: TestSessionOptions,
@@ -57,6 +60,7 @@ impl TestSession {
         Self {
             next_id: AtomicU64::new(0),
             labels: BuckDashMap::default(),
+            sharded_by_test_case: BuckDashSet::default(),
             options,
         }
     }
@@ -82,4 +86,12 @@ impl TestSession {
 
         Ok(res.clone())
     }
+
+    pub fn mark_sharded_by_test_case(&self, id: ConfiguredTargetHandle) {
+        self.sharded_by_test_case.insert(id);
+    }
+
+    pub fn is_sharded_by_test_case(&self, id: ConfiguredTargetHandle) -> bool {
+        self.sharded_by_test_case.contains(&id)
+    }
 }
@@ -0,0 +1,197 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is dual-licensed under either the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree or the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree. You may select, at your option, one of the
+ * above-listed licenses.
+ */
+
+//! Deterministic sharding of a test run across machines, for `buck2 test --shard`.
+//!
+//! The unit of sharding is a target, or a test case for targets whose test cases Buck2 lists
+//! itself. Every shard independently decides which units are its own, so all shards must agree on
+//! the inputs: the shard count and the timings files.
+
+use std::collections::BTreeMap;
+
+use buck2_core::target::label::label::TargetLabel;
+use buck2_error::BuckErrorContext;
+use buck2_fs::fs_util;
+use buck2_fs::paths::abs_path::AbsPath;
+use buck2_hash::BuckMutMap;
+use serde::Deserialize;
+
+/// The key of a test case unit, also used for its duration in the build report.
+pub(crate) fn test_case_key(target: &TargetLabel, test_case: &str) -> String {
+    format!("{target} {test_case}")
+}
+
+pub(crate) struct TestSharding {
+    index: u32,
+    count: u32,
+    /// Shards for the units in the timings files, assigned up front to balance their durations.
+    assigned: BuckMutMap<String, u32>,
+}
+
+impl TestSharding {
+    pub(crate) fn from_proto(shard: &buck2_cli_proto::TestShard) -> buck2_error::Result<Self> {
+        if shard.index >= shard.count {
+            return Err(ShardError::Invalid(shard.index, shard.count).into());
+        }
+        let mut durations = BTreeMap::new();
+        for path in &shard.timings_paths {
+            durations.extend(read_durations(path)?);
+        }
+        Ok(Self::new(shard.index, shard.count, durations))
+    }
+
+    fn new(index: u32, count: u32, durations: BTreeMap<String, f64>) -> Self {
+        // Longest processing time first: hand out units from longest to shortest, each to the
+        // least loaded shard. Ties are broken by key and by shard index, so every shard computes
+        // the same assignment.
+        let mut units: Vec<(String, f64)> = durations.into_iter().collect();
+        units.sort_by(|(a_key, a), (b_key, b)| b.total_cmp(a).then_with(|| a_key.cmp(b_key)));
+
+        let mut loads = vec![0.0f64; count as usize];
+        let mut assigned = BuckMutMap::default();
+        for (key, duration) in units {
+            let (shard, load) = loads
+                .iter_mut()
+                .enumerate()
+                .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                .expect("at least one shard");
+            *load += duration;
+            assigned.insert(key, shard as u32);
+        }
+
+        Self {
+            index,
+            count,
+            assigned,
+        }
+    }
+
+    fn includes(&self, key: &str) -> bool {
+        let shard = match self.assigned.get(key) {
+            Some(shard) => *shard,
+            None => (stable_hash(key) % u64::from(self.count)) as u32,
+        };
+        shard == self.index
+    }
+
+    pub(crate) fn includes_target(&self, target: &TargetLabel) -> bool {
+        self.includes(&target.to_string())
+    }
+
+    pub(crate) fn includes_test_case(&self, target: &TargetLabel, test_case: &str) -> bool {
+        self.includes(&test_case_key(target, test_case))
+    }
+}
+
+/// FNV-1a. Shards may run different builds of Buck2, so this must never change.
+fn stable_hash(key: &str) -> u64 {
+    key.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[derive(Deserialize)]
+struct TimingsReport {
+    tests: Option<TimingsReportTests>,
+}
+
+#[derive(Deserialize)]
+struct TimingsReportTests {
+    #[serde(default)]
+    durations: BTreeMap<String, f64>,
+}
+
+fn read_durations(path: &str) -> buck2_error::Result<BTreeMap<String, f64>> {
+    let contents = fs_util::read_to_string(AbsPath::new(path)?)?;
+    let report: TimingsReport = serde_json::from_str(&contents)
+        .with_buck_error_context(|| format!("Error parsing shard timings `{path}`"))?;
+    match report.tests {
+        Some(tests) => Ok(tests.durations),
+        None => Err(ShardError::NoTestDurations(path.to_owned()).into()),
+    }
+}
+
+#[derive(Debug, buck2_error::Error)]
+#[buck2(tag = Input)]
+enum ShardError {
+    #[error("Invalid shard index {0} for {1} shards")]
+    Invalid(u32, u32),
+    #[error("`{0}` has no test durations, it must be a build report written by `buck2 test`")]
+    NoTestDurations(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shards_of(count: u32, durations: &[(&str, f64)], keys: &[&str]) -> Vec<u32> {
+        let shards: Vec<TestSharding> = (0..count)
+            .map(|index| {
+                TestSharding::new(
+                    index,
+                    count,
+                    durations
+                        .iter()
+                        .map(|(k, d)| ((*k).to_owned(), *d))
+                        .collect(),
+                )
+            })
+            .collect();
+        keys.iter()
+            .map(|key| {
+                let owners: Vec<u32> = shards
+                    .iter()
+                    .filter(|s| s.includes(key))
+                    .map(|s| s.index)
+                    .collect();
+                assert_eq!(owners.len(), 1, "`{key}` is in shards {owners:?}");
+                owners[0]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_every_unit_is_in_exactly_one_shard() {
+        let keys: Vec<String> = (0..100).map(|i| format!("root//foo:test_{i}")).collect();
+        let keys: Vec<&str> = keys.iter().map(|k| k.as_str()).collect();
+        let shards = shards_of(7, &[("root//foo:test_3", 10.0)], &keys);
+        // Hashing spreads the units over all the shards.
+        for shard in 0..7 {
+            assert!(shards.contains(&shard), "shard {shard} is empty");
+        }
+    }
+
+    #[test]
+    fn test_timings_balance_shards() {
+        let durations = [
+            ("root//:a", 8.0),
+            ("root//:b", 5.0),
+            ("root//:c", 4.0),
+            ("root//:d", 3.0),
+        ];
+        // `a` + `d` on one shard, `b` + `c` on the other
+        assert_eq!(
+            shards_of(
+                2,
+                &durations,
+                &["root//:a", "root//:b", "root//:c", "root//:d"]
+            ),
+            vec![0, 1, 1, 0]
+        );
+    }
+
+    #[test]
+    fn test_stable_hash() {
+        // Pin the hash: changing it would reshuffle tests between shards running different
+        // versions of Buck2.
+        assert_eq!(stable_hash(""), 0xcbf29ce484222325);
+        assert_eq!(stable_hash("a"), 0xaf63dc4c8601ec8c);
+    }
+}
@@ -68,6 +68,8 @@ pub(crate) fn convert_test_result(
         details,
         target: test_target,
         max_memory_used_bytes,
+        stdout,
+        stderr,
     } = test_result;
 
     let test_target = session.get(test_target)?;
@@ -80,6 +82,8 @@ pub(crate) fn convert_test_result(
         details,
         target_label: Some(test_target.target().as_proto()),
         max_memory_used_bytes,
+        stdout,
+        stderr,
     })
 }
 
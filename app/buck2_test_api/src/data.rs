@@ -127,6 +127,9 @@ pub struct TestResult {
     pub max_memory_used_bytes: Option<u64>,
     // the output of the test execution (combining stdout and stderr)
     pub details: String,
+    // the stdout and stderr combined in `details`, when they are known separately
+    pub stdout: Option<String>,
+    pub stderr: Option<String>,
 }
 
 /// different possible test results
@@ -147,6 +150,8 @@ pub enum TestStatus {
     RERUN,
     LISTING_SUCCESS,
     LISTING_FAILED,
+    // Passed, but only after failing at least once, e.g. on a retry.
+    FLAKY,
 }
 
 impl TestStatus {
@@ -164,6 +169,7 @@ impl TestStatus {
             "RERUN" => Ok(TestStatus::RERUN),
             "LISTING_SUCCESS" => Ok(TestStatus::LISTING_SUCCESS),
             "LISTING_FAILED" => Ok(TestStatus::LISTING_FAILED),
+            "FLAKY" => Ok(TestStatus::FLAKY),
             _ => Err(buck2_error!(
                 ErrorTag::Input,
                 "Unknown test status: `{}`",
@@ -171,6 +177,11 @@ impl TestStatus {
             )),
         }
     }
+
+    /// Whether the test passed, possibly only after being retried.
+    pub fn is_success(&self) -> bool {
+        matches!(self, TestStatus::PASS | TestStatus::FLAKY)
+    }
 }
 
 /// Entry from a parsed test listing (returned by parse_test_listing callback).
@@ -546,6 +557,7 @@ mod tests {
             TestStatus::parse("LISTING_FAILED").unwrap(),
             TestStatus::LISTING_FAILED
         );
+        assert_eq!(TestStatus::parse("FLAKY").unwrap(), TestStatus::FLAKY);
     }
 
     #[test]
@@ -353,6 +353,7 @@ impl TryFrom<i32> for TestStatus {
             buck2_test_proto::TestStatus::Rerun => TestStatus::RERUN,
             buck2_test_proto::TestStatus::ListingSuccess => TestStatus::LISTING_SUCCESS,
             buck2_test_proto::TestStatus::ListingFailed => TestStatus::LISTING_FAILED,
+            buck2_test_proto::TestStatus::Flaky => TestStatus::FLAKY,
         })
     }
 }
@@ -373,6 +374,7 @@ impl TryInto<i32> for TestStatus {
             TestStatus::RERUN => buck2_test_proto::TestStatus::Rerun,
             TestStatus::LISTING_SUCCESS => buck2_test_proto::TestStatus::ListingSuccess,
             TestStatus::LISTING_FAILED => buck2_test_proto::TestStatus::ListingFailed,
+            TestStatus::FLAKY => buck2_test_proto::TestStatus::Flaky,
         } as i32)
     }
 }
@@ -389,6 +391,8 @@ impl TryFrom<buck2_test_proto::TestResult> for TestResult {
             duration,
             details,
             max_memory_used_bytes,
+            stdout,
+            stderr,
         } = s;
 
         let duration = duration
@@ -407,6 +411,8 @@ impl TryFrom<buck2_test_proto::TestResult> for TestResult {
             duration,
             max_memory_used_bytes,
             details,
+            stdout,
+            stderr,
         })
     }
 }
@@ -432,6 +438,8 @@ impl TryInto<buck2_test_proto::TestResult> for TestResult {
             msg: self.msg.map(|msg| OptionalMsg { msg }),
             duration: self.duration.try_map(|d| d.try_into())?,
             max_memory_used_bytes: self.max_memory_used_bytes,
+            stdout: self.stdout,
+            stderr: self.stderr,
         })
     }
 }
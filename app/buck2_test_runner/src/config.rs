@@ -30,6 +30,16 @@ pub struct Config {
     /// Available as a workaround for when test features are available.
     #[clap(long, num_args=1.., allow_hyphen_values = true)]
     pub test_arg: Vec<String>,
+
+    /// Run a failing test up to this many more times. A test that passes on a retry is reported
+    /// as flaky. Targets can override this with a `test_retries=<N>` label.
+    #[clap(long, default_value = "0")]
+    pub retries: u32,
+
+    /// File listing quarantined test targets, one `cell//package:target` per line. Quarantined
+    /// tests still run, but their failures do not fail the test run.
+    #[clap(long)]
+    pub quarantine_file: Option<String>,
 }
 
 /// Uiltity that can be used to parse Env values from CLI arguments.
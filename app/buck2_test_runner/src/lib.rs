@@ -10,6 +10,7 @@
 
 mod config;
 mod executor;
+mod policy;
 mod runner;
 mod service;
 pub mod tcp;
@@ -0,0 +1,116 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is dual-licensed under either the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree or the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree. You may select, at your option, one of the
+ * above-listed licenses.
+ */
+
+//! Retry and quarantine policy for the tests run by `Buck2TestRunner`.
+
+use std::collections::HashSet;
+
+use buck2_error::BuckErrorContext;
+
+use crate::config::Config;
+
+/// A target label of the form `test_retries=<N>` overrides `--retries` for that target.
+const RETRIES_LABEL_PREFIX: &str = "test_retries=";
+
+pub(crate) struct TestPolicy {
+    retries: u32,
+    quarantined: HashSet<String>,
+}
+
+impl TestPolicy {
+    pub(crate) fn new(config: &Config) -> buck2_error::Result<Self> {
+        let quarantined = match &config.quarantine_file {
+            Some(path) => {
+                let contents = std::fs::read_to_string(path).with_buck_error_context(|| {
+                    format!("Error reading quarantine file `{path}`")
+                })?;
+                parse_quarantine_list(&contents)
+            }
+            None => HashSet::new(),
+        };
+        Ok(Self {
+            retries: config.retries,
+            quarantined,
+        })
+    }
+
+    /// How many times a failing test of a target with these labels is retried.
+    pub(crate) fn retries(&self, labels: &[String]) -> buck2_error::Result<u32> {
+        match labels
+            .iter()
+            .find_map(|label| label.strip_prefix(RETRIES_LABEL_PREFIX))
+        {
+            Some(retries) => retries.parse().map_err(|_| {
+                PolicyError::InvalidRetriesLabel(format!("{RETRIES_LABEL_PREFIX}{retries}")).into()
+            }),
+            None => Ok(self.retries),
+        }
+    }
+
+    /// Quarantined tests still run and report their results, but failures do not fail the run.
+    pub(crate) fn is_quarantined(&self, target: &str) -> bool {
+        self.quarantined.contains(target)
+    }
+}
+
+/// One `cell//package:target` per line, e.g. `root//foo:bar_test`. Blank lines and `#` comments
+/// are ignored.
+fn parse_quarantine_list(contents: &str) -> HashSet<String> {
+    contents
+        .lines()
+        .map(|line| line.split_once('#').map_or(line, |(line, _)| line).trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| line.to_owned())
+        .collect()
+}
+
+#[derive(Debug, buck2_error::Error)]
+#[buck2(tag = Input)]
+enum PolicyError {
+    #[error("Invalid label `{0}`, expected `test_retries=<number>`")]
+    InvalidRetriesLabel(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(retries: u32) -> TestPolicy {
+        TestPolicy {
+            retries,
+            quarantined: HashSet::new(),
+        }
+    }
+
+    #[test]
+    fn test_retries_label_overrides_default() {
+        let policy = policy(1);
+        assert_eq!(policy.retries(&[]).unwrap(), 1);
+        assert_eq!(
+            policy
+                .retries(&["slow".to_owned(), "test_retries=3".to_owned()])
+                .unwrap(),
+            3
+        );
+        assert_eq!(policy.retries(&["test_retries=0".to_owned()]).unwrap(), 0);
+        assert!(policy.retries(&["test_retries=many".to_owned()]).is_err());
+    }
+
+    #[test]
+    fn test_parse_quarantine_list() {
+        let quarantined = parse_quarantine_list(
+            "# Flaky since the last toolchain update\nroot//foo:a_test\n\n  root//bar:b_test # T123\n",
+        );
+        assert_eq!(
+            quarantined,
+            HashSet::from(["root//foo:a_test".to_owned(), "root//bar:b_test".to_owned()])
+        );
+    }
+}
@@ -34,6 +34,7 @@ use parking_lot::Mutex;
 
 use crate::config::Config;
 use crate::config::EnvValue;
+use crate::policy::TestPolicy;
 
 pub type SpecReceiver = UnboundedReceiver<ExternalRunnerSpec>;
 
@@ -48,6 +49,7 @@ pub struct Buck2TestRunner {
     orchestrator_client: TestOrchestratorClient,
     spec_receiver: Mutex<Option<SpecReceiver>>,
     config: Config,
+    policy: TestPolicy,
 }
 
 impl Buck2TestRunner {
@@ -58,10 +60,12 @@ impl Buck2TestRunner {
     ) -> buck2_error::Result<Self> {
         let config = Config::try_parse_from(args)
             .buck_error_context("Error parsing test runner arguments")?;
+        let policy = TestPolicy::new(&config)?;
         Ok(Self {
             orchestrator_client,
             spec_receiver: Mutex::new(Some(spec_receiver)),
             config,
+            policy,
         })
     }
 
@@ -81,34 +85,70 @@ impl Buck2TestRunner {
                     spec.target.cell, spec.target.package, spec.target.target
                 );
                 let target_handle = spec.target.handle.to_owned();
+                let retries = self.policy.retries(&spec.labels)?;
+                let quarantined = self.policy.is_quarantined(&name);
 
-                let execution_response = self
-                    .execute_test_from_spec(spec)
-                    .await
-                    .buck_error_context("Test execution request failed")?;
+                let mut failed_attempts = 0;
+                let mut test_result = loop {
+                    let execution_response = self
+                        .execute_test_from_spec(&spec)
+                        .await
+                        .buck_error_context("Test execution request failed")?;
+
+                    let execution_result = match execution_response {
+                        ExecuteResponse::Result(r) => r,
+                        ExecuteResponse::Cancelled(_) => {
+                            return Ok((TestStatus::OMITTED, quarantined));
+                        }
+                    };
 
-                let execution_result = match execution_response {
-                    ExecuteResponse::Result(r) => r,
-                    ExecuteResponse::Cancelled(_) => return Ok(TestStatus::OMITTED),
+                    let mut test_result =
+                        get_test_result(name.clone(), target_handle, execution_result);
+                    if test_result.status == TestStatus::PASS {
+                        if failed_attempts > 0 {
+                            test_result.status = TestStatus::FLAKY;
+                            test_result.msg =
+                                Some(format!("Passed after {failed_attempts} failed attempt(s)"));
+                        }
+                        break test_result;
+                    }
+                    if failed_attempts == retries {
+                        break test_result;
+                    }
+
+                    // Report the failed attempt, so its output isn't lost, then try again.
+                    failed_attempts += 1;
+                    test_result.msg = Some(format!(
+                        "Attempt {failed_attempts} failed with {:?}, retrying",
+                        test_result.status
+                    ));
+                    test_result.status = TestStatus::RERUN;
+                    self.report_test_result(test_result)
+                        .await
+                        .buck_error_context("Test result reporting failed")?;
                 };
 
-                let test_result = get_test_result(name, target_handle, execution_result);
                 let test_status = test_result.status.clone();
+                if quarantined && !test_status.is_success() {
+                    test_result.msg =
+                        Some("Quarantined: this result does not fail the test run".to_owned());
+                }
 
                 self.report_test_result(test_result)
                     .await
                     .buck_error_context("Test result reporting failed")?;
 
-                Ok(test_status)
+                Ok((test_status, quarantined))
             })
             // Use an arbitrarily large buffer -- execution throttling will be handled by the Buck2
             // executor, so no need to hold back on requests here.
             .buffer_unordered(10000)
-            // If any individual test failed, consider the entire run to have failed.
+            // If any individual test failed, consider the entire run to have failed, unless the
+            // test is quarantined.
             .try_fold(
                 RunVerdict::Pass,
-                |mut run_verdict, test_status| async move {
-                    if test_status != TestStatus::PASS {
+                |mut run_verdict, (test_status, quarantined)| async move {
+                    if !test_status.is_success() && !quarantined {
                         run_verdict = RunVerdict::Fail;
                     }
                     buck2_error::Ok(run_verdict)
@@ -123,10 +163,10 @@ impl Buck2TestRunner {
 
     async fn execute_test_from_spec(
         &self,
-        spec: ExternalRunnerSpec,
+        spec: &ExternalRunnerSpec,
     ) -> buck2_error::Result<ExecuteResponse> {
         let stage = TestStage::Testing {
-            suite: spec.target.target,
+            suite: spec.target.target.clone(),
             testcases: Vec::new(),
             variant: None,
             repeat_count: None,
@@ -141,9 +181,9 @@ impl Buck2TestRunner {
 
         let command = spec
             .command
-            .into_iter()
+            .iter()
             .map(|spec_value| ArgValue {
-                content: ArgValueContent::ExternalRunnerSpecValue(spec_value),
+                content: ArgValueContent::ExternalRunnerSpecValue(spec_value.clone()),
                 format: None,
             })
             .chain(config_args)
@@ -169,12 +209,12 @@ impl Buck2TestRunner {
 
         let env = spec
             .env
-            .into_iter()
+            .iter()
             .map(|(key, value)| {
                 (
-                    key,
+                    key.clone(),
                     ArgValue {
-                        content: ArgValueContent::ExternalRunnerSpecValue(value),
+                        content: ArgValueContent::ExternalRunnerSpecValue(value.clone()),
                         format: None,
                     },
                 )
@@ -233,6 +273,8 @@ fn get_test_result(
             execution_result.stdout, execution_result.stderr
         ),
         max_memory_used_bytes: execution_result.max_memory_used_bytes,
+        stdout: Some(format!("{:?}", execution_result.stdout)),
+        stderr: Some(format!("{:?}", execution_result.stderr)),
     }
 }
 
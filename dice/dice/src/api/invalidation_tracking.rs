@@ -72,6 +72,19 @@ impl std::fmt::Debug for DiceInvalidationPath {
     }
 }
 
+/// An InvalidationPathRecorder receives the invalidation paths of the keys recomputed in a
+/// transaction, to explain why they were recomputed.
+pub trait InvalidationPathRecorder: Send + Sync + 'static {
+    /// Whether to record the invalidation paths of this key. This is checked before the paths are
+    /// handed over, so recording can be limited to the key types of interest.
+    fn should_record(&self, key: &DynKey) -> bool;
+
+    /// Receives the invalidation paths of a key that was recomputed. The paths end at the
+    /// dependency of `key` through which the invalidation reached it, and are clean or unknown if
+    /// no invalidated data flowed into `key` (e.g. because it had not been computed before).
+    fn key_recomputed(&self, key: &DynKey, paths: DiceKeyTrackedInvalidationPaths);
+}
+
 /// A node in the invalidation path.
 pub struct InvalidationPathEntry {
     pub key: DynKey,
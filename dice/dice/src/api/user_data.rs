@@ -20,6 +20,7 @@ use crate::api::activation_tracker::ActivationTracker;
 use crate::api::data::DiceData;
 use crate::api::events::DiceEvent;
 use crate::api::events::DiceEventListener;
+use crate::api::invalidation_tracking::InvalidationPathRecorder;
 
 /// Includes all user related computation-specific data.
 #[derive(Allocative)]
@@ -39,6 +40,9 @@ pub struct UserComputationData {
     #[allocative(skip)]
     pub activation_tracker: Option<Arc<dyn ActivationTracker>>,
 
+    #[allocative(skip)]
+    pub invalidation_path_recorder: Option<Arc<dyn InvalidationPathRecorder>>,
+
     /// We require that UserComputationData always be constructed with `..Default::default()`
     pub _requires_default: RequireDefault,
 }
@@ -89,6 +93,7 @@ impl Default for UserComputationData {
             spawner: Arc::new(TokioSpawner),
             cycle_detector: None,
             activation_tracker: None,
+            invalidation_path_recorder: None,
             _requires_default: RequireDefault(()),
         }
     }
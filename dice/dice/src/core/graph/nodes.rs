@@ -604,6 +604,28 @@ impl OccupiedGraphNode {
         }
     }
 
+    /// A node whose value lives only in pagable storage at `data_key`, e.g. one restored
+    /// from a persisted snapshot. It hydrates on first lookup like any paged-out node.
+    pub(crate) fn new_paged_out(
+        key: DiceKey,
+        data_key: DataKey,
+        deps: Arc<SeriesParallelDeps>,
+        verified_ranges: VersionRanges,
+        invalidation_paths: TrackedInvalidationPaths,
+    ) -> Self {
+        Self {
+            key,
+            res: PagableNodeValue::PagedOut(data_key),
+            metadata: NodeMetadata {
+                deps,
+                rdeps: LazyDepsSet::new(),
+                verified_ranges: verified_ranges.into_arc(),
+                dirtied_history: ForceDirtyHistory::new(),
+            },
+            invalidation_paths,
+        }
+    }
+
     pub(crate) fn mark_unchanged(
         &mut self,
         version: VersionNumber,
@@ -846,6 +868,12 @@ pub(crate) struct InjectedNodeData {
     valid_versions: Arc<VersionRanges>,
 }
 
+impl InjectedNodeData {
+    pub(crate) fn value(&self) -> &DiceValidValue {
+        &self.value
+    }
+}
+
 impl InjectedGraphNode {
     /// Returns a list of rdeps to invalidate and a bool indicating if the value changed. Should only ever be called at increasing version numbers.
     pub(crate) fn on_injected(
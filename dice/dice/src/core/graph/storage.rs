@@ -169,6 +169,7 @@
 
 use allocative::Allocative;
 use bit_set::BitSet;
+use pagable::DataKey;
 
 use self::store::NodeEntry;
 use self::store::NodeMut;
@@ -648,6 +649,64 @@ impl VersionedGraph {
         true
     }
 
+    /// Inserts a node restored from a persisted snapshot, verified from `key.v` on (bounded
+    /// by its deps). Returns false without touching the graph if the key is already present or
+    /// one of its deps is missing or not valid at `key.v`: a restored value is only trusted
+    /// when everything it was computed from has been restored alongside it.
+    pub(crate) fn restore(
+        &mut self,
+        key: VersionedGraphKey,
+        value: PersistedValue,
+        deps: Arc<SeriesParallelDeps>,
+    ) -> bool {
+        if self.nodes.nodes().contains_key(&key.k) {
+            return false;
+        }
+
+        let mut verified_ranges = VersionRange::begins_with(key.v).into_ranges();
+        for dep in deps.iter_keys() {
+            match self.nodes.nodes().get(&dep) {
+                Some(
+                    node @ (VersionedGraphNode::Occupied(_) | VersionedGraphNode::Injected(_)),
+                ) => node.intersect_valid_versions_at(key.v, &mut verified_ranges),
+                Some(VersionedGraphNode::Vacant(_)) | None => return false,
+            }
+        }
+        if verified_ranges.is_empty() {
+            return false;
+        }
+
+        for dep in deps.iter_keys() {
+            self.nodes
+                .node_mut(dep)
+                .expect("dependency checked above")
+                .add_rdep_at(key.v, key.k);
+        }
+
+        let node = match value {
+            PersistedValue::Resident(value) => OccupiedGraphNode::new(
+                key.k,
+                value,
+                deps,
+                verified_ranges,
+                ForceDirtyHistory::new(),
+                TrackedInvalidationPaths::clean(),
+            ),
+            PersistedValue::PagedOut(data_key) => OccupiedGraphNode::new_paged_out(
+                key.k,
+                data_key,
+                deps,
+                verified_ranges,
+                TrackedInvalidationPaths::clean(),
+            ),
+        };
+        match self.nodes.node_entry(key.k) {
+            NodeEntry::Vacant(slot) => slot.insert(VersionedGraphNode::Occupied(node)),
+            NodeEntry::Occupied(_) => unreachable!("checked absent above"),
+        }
+        true
+    }
+
     // -----------------------------------------------------------------------------
     // ------------------------- Implementation functions below --------------------
     // -----------------------------------------------------------------------------
@@ -738,6 +797,15 @@ pub(crate) enum InvalidateKind {
     Update(DiceValidValue, StorageType),
 }
 
+/// The value of a node captured for, or restored from, a persisted snapshot.
+pub(crate) enum PersistedValue {
+    /// In memory: not yet written to pagable storage, or recomputed during restore (e.g. a
+    /// projection derived from its restored base).
+    Resident(DiceValidValue),
+    /// In pagable storage only; a restored node hydrates it on first lookup.
+    PagedOut(DataKey),
+}
+
 #[cfg(test)]
 pub(crate) mod testing {
 
@@ -17,6 +17,7 @@ use crate::arc::Arc;
 use crate::core::graph::introspection::VersionedGraphIntrospectable;
 use crate::core::graph::nodes::VersionedGraphNode;
 use crate::core::graph::storage::InvalidateKind;
+use crate::core::graph::storage::PersistedValue;
 use crate::core::graph::storage::ValueReusable;
 use crate::core::graph::storage::VersionedGraph;
 use crate::core::graph::types::VersionedGraphKey;
@@ -57,6 +58,29 @@ pub(crate) struct PagableStatusRaw {
     pub(crate) paged_out: Vec<DiceKey>,
 }
 
+/// A node with a committed value at the current version, collected by
+/// `CoreState::snapshot_nodes` for persisting across daemon restarts.
+pub(crate) struct SnapshotNode {
+    pub(crate) key: DiceKey,
+    pub(crate) value: PersistedValue,
+    pub(crate) deps: Vec<DiceKey>,
+    pub(crate) injected: bool,
+}
+
+/// A node to re-insert into the graph with `CoreState::restore_nodes`. Entries must be
+/// ordered so that every node comes after its deps.
+pub(crate) enum RestoredNode {
+    Injected {
+        key: DiceKey,
+        value: DiceValidValue,
+    },
+    Computed {
+        key: DiceKey,
+        value: PersistedValue,
+        deps: Vec<DiceKey>,
+    },
+}
+
 impl CoreState {
     pub(super) fn new() -> Self {
         Self {
@@ -254,6 +278,75 @@ impl CoreState {
         }
     }
 
+    /// Collects every node with a committed value at the current version: occupied nodes
+    /// verified at it and the latest value of each injected node.
+    pub(super) fn snapshot_nodes(&self) -> Vec<SnapshotNode> {
+        let v = self.version_tracker.current();
+        self.graph
+            .nodes()
+            .iter()
+            .filter_map(|(key, node)| match node {
+                VersionedGraphNode::Occupied(occ) if occ.is_verified_at(v) => {
+                    let value = match occ.val().as_hydrated() {
+                        Some(value) => PersistedValue::Resident(value.dupe()),
+                        None => PersistedValue::PagedOut(occ.val().data_key()?),
+                    };
+                    Some(SnapshotNode {
+                        key: *key,
+                        value,
+                        deps: occ.deps().iter_keys().collect(),
+                        injected: false,
+                    })
+                }
+                VersionedGraphNode::Injected(inj) => Some(SnapshotNode {
+                    key: *key,
+                    value: PersistedValue::Resident(inj.latest().value().dupe()),
+                    deps: Vec::new(),
+                    injected: true,
+                }),
+                VersionedGraphNode::Occupied(_) | VersionedGraphNode::Vacant(_) => None,
+            })
+            .collect()
+    }
+
+    /// Re-inserts nodes from a persisted snapshot at a new version. Injected values are
+    /// injected as usual; computed nodes are restored only where the graph has no entry yet
+    /// and all their deps were restored. Returns the number of computed nodes restored.
+    pub(super) fn restore_nodes(&mut self, nodes: Vec<RestoredNode>) -> usize {
+        let version_update = self.version_tracker.write();
+        let v = version_update.version();
+
+        let mut changes_recorded = false;
+        let mut restored = 0;
+        for node in nodes {
+            match node {
+                RestoredNode::Injected { key, value } => {
+                    changes_recorded |= self.graph.invalidate(
+                        VersionedGraphKey::new(v, key),
+                        InvalidateKind::Update(value, StorageType::Injected),
+                        InvalidationSourcePriority::Ignored,
+                    );
+                }
+                RestoredNode::Computed { key, value, deps } => {
+                    if self.graph.restore(
+                        VersionedGraphKey::new(v, key),
+                        value,
+                        Arc::new(SeriesParallelDeps::serial_from_vec(deps)),
+                    ) {
+                        changes_recorded = true;
+                        restored += 1;
+                    }
+                }
+            }
+        }
+        if changes_recorded {
+            version_update.commit();
+        } else {
+            version_update.undo();
+        }
+        restored
+    }
+
     /// Returns some metrics about the current state of DICE. Don't do expensive things here.
     pub(super) fn metrics(&self) -> Metrics {
         let mut active_transaction_count = 0;
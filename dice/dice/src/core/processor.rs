@@ -149,6 +149,12 @@ impl StateProcessor {
             StateRequest::Rehydrate { key, value } => {
                 self.state.rehydrate(key, value);
             }
+            StateRequest::SnapshotNodes { resp } => {
+                drop(resp.send(self.state.snapshot_nodes()));
+            }
+            StateRequest::RestoreNodes { nodes, resp } => {
+                drop(resp.send(self.state.restore_nodes(nodes)));
+            }
             StateRequest::Metrics { resp } => {
                 let _ignored = resp.send(self.state.metrics());
             }
@@ -31,6 +31,8 @@ use crate::core::graph::types::VersionedGraphResult;
 use crate::core::graph::types::VersionedGraphResultMismatch;
 use crate::core::internals::CoreState;
 use crate::core::internals::PagableStatusRaw;
+use crate::core::internals::RestoredNode;
+use crate::core::internals::SnapshotNode;
 use crate::core::processor::StateProcessor;
 use crate::core::versions::VersionEpoch;
 use crate::core::versions::introspection::VersionIntrospectable;
@@ -298,6 +300,22 @@ impl CoreStateHandle {
         self.request(StateRequest::Rehydrate { key, value })
     }
 
+    /// Nodes with a committed value at the current version, for persisting a snapshot.
+    pub(crate) fn snapshot_nodes(&self) -> impl Future<Output = Vec<SnapshotNode>> + use<> {
+        let (resp, recv) = oneshot::channel();
+        self.call(StateRequest::SnapshotNodes { resp }, recv)
+    }
+
+    /// Re-insert nodes from a persisted snapshot at a new version. Returns the number of
+    /// computed nodes restored.
+    pub(crate) fn restore_nodes(
+        &self,
+        nodes: Vec<RestoredNode>,
+    ) -> impl Future<Output = usize> + use<> {
+        let (resp, recv) = oneshot::channel();
+        self.call(StateRequest::RestoreNodes { nodes, resp }, recv)
+    }
+
     /// Collect metrics
     pub(crate) fn metrics(&self) -> Metrics {
         let (resp, recv) = oneshot::channel();
@@ -419,6 +437,13 @@ pub(super) enum StateRequest {
     MarkNonPageable { keys: Vec<DiceKey> },
     /// Replace the paged-out value at `key` with its hydrated form.
     Rehydrate { key: DiceKey, value: DiceValidValue },
+    /// Collect nodes with a committed value at the current version.
+    SnapshotNodes { resp: Sender<Vec<SnapshotNode>> },
+    /// Re-insert nodes from a persisted snapshot at a new version.
+    RestoreNodes {
+        nodes: Vec<RestoredNode>,
+        resp: Sender<usize>,
+    },
     /// Collect metrics
     Metrics { resp: Sender<Metrics> },
     /// Collects the introspectable dice state
@@ -29,6 +29,9 @@ use crate::introspection::graph::GraphIntrospectable;
 use crate::key_index::DiceKeyIndex;
 use crate::metrics::Metrics;
 use crate::metrics::PageInKeyTypeMetrics;
+use crate::snapshot;
+use crate::snapshot::DiceSnapshotId;
+use crate::snapshot::PersistedSnapshot;
 use crate::storage::DiceStorage;
 use crate::updater::TransactionUpdater;
 
@@ -257,6 +260,48 @@ impl Dice {
         Ok(())
     }
 
+    /// Persist the committed graph to the configured `DiceStorage` so a later process can
+    /// [`Dice::restore_snapshot`] it. Only the dependency closure of nodes whose key type
+    /// (see `Key::key_type_name`) is in `root_key_types` is written, minus anything that
+    /// can't be persisted or depends on something that can't. Values already paged out are
+    /// referenced rather than rewritten.
+    ///
+    /// **Caller must ensure DICE is idle** before calling this, as for [`Dice::page_out`].
+    ///
+    /// Returns `None` if pagable storage is not configured or there was nothing to persist.
+    pub async fn persist_snapshot(
+        self: &StdArc<Self>,
+        root_key_types: impl IntoIterator<Item = String>,
+    ) -> anyhow::Result<Option<PersistedSnapshot>> {
+        if !self.is_idle().await {
+            return Err(anyhow::anyhow!(
+                "Dice::persist_snapshot called while DICE is not idle"
+            ));
+        }
+        let Some(storage) = self.pagable_storage.as_ref() else {
+            return Ok(None);
+        };
+        snapshot::persist(self, storage, root_key_types.into_iter().collect()).await
+    }
+
+    /// Restore a snapshot written by [`Dice::persist_snapshot`] (typically by a previous
+    /// daemon using the same storage) as a new version. Nodes already in the graph are kept
+    /// as they are. Restored values are only as fresh as the snapshot: the caller must
+    /// invalidate whatever changed since it was taken.
+    ///
+    /// Returns the number of computed nodes restored.
+    pub async fn restore_snapshot(
+        self: &StdArc<Self>,
+        id: DiceSnapshotId,
+    ) -> anyhow::Result<usize> {
+        let Some(storage) = self.pagable_storage.as_ref() else {
+            return Err(anyhow::anyhow!(
+                "No storage available to restore a snapshot"
+            ));
+        };
+        snapshot::restore(self, storage, id).await
+    }
+
     /// Summarize pagable status: counts of resident vs paged-out node values
     /// with a per-key-type breakdown.
     ///
@@ -0,0 +1,257 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is dual-licensed under either the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree or the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree. You may select, at your option, one of the
+ * above-listed licenses.
+ */
+
+//! End-to-end tests for `Dice::persist_snapshot` / `Dice::restore_snapshot` across two DICE
+//! instances sharing one on-disk store, as with a daemon restart.
+
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use allocative::Allocative;
+use async_trait::async_trait;
+use derive_more::Display;
+use dice_futures::cancellation::CancellationContext;
+use dupe::Dupe;
+use pagable::Pagable;
+use pagable::pagable_typetag;
+use tempfile::tempdir;
+
+use crate::DiceKeyDyn;
+use crate::DiceSnapshotId;
+use crate::DiceStorage;
+use crate::PagableStorageBackend;
+use crate::api::computations::DiceComputations;
+use crate::api::cycles::DetectCycles;
+use crate::api::key::EqualityBehavior;
+use crate::api::key::Key;
+use crate::api::key::NoValueSerialize;
+use crate::api::key::PagableValueSerialize;
+use crate::api::key::ValueSerialize;
+use crate::api::user_data::UserComputationData;
+use crate::dice::Dice;
+
+/// Per-test compute counters, injected via `UserComputationData`.
+#[derive(Clone, Dupe)]
+struct Computes(Arc<[AtomicUsize; 2]>);
+
+impl Computes {
+    const LEAF: usize = 0;
+    const ROOT: usize = 1;
+
+    fn new() -> Self {
+        Self(Arc::new(std::array::from_fn(|_| AtomicUsize::new(0))))
+    }
+
+    fn record(ctx: &DiceComputations, which: usize) {
+        if let Ok(computes) = ctx.per_transaction_data().data.get::<Computes>() {
+            computes.0[which].fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn count(&self, which: usize) -> usize {
+        self.0[which].load(Ordering::SeqCst)
+    }
+
+    fn user_data(&self) -> UserComputationData {
+        let mut data = UserComputationData::new();
+        data.data.set(self.dupe());
+        data
+    }
+}
+
+#[derive(Allocative, Clone, Dupe, Debug, Display, PartialEq, Eq, Hash, Pagable)]
+#[pagable_typetag(DiceKeyDyn)]
+struct SnapshotLeaf(u32);
+
+#[async_trait]
+impl Key for SnapshotLeaf {
+    type Value = u64;
+
+    async fn compute(
+        &self,
+        ctx: &mut DiceComputations,
+        _cancellations: &CancellationContext,
+    ) -> Self::Value {
+        Computes::record(ctx, Computes::LEAF);
+        u64::from(self.0) * 10
+    }
+
+    fn equality_behavior() -> EqualityBehavior<Self::Value> {
+        EqualityBehavior::Compare(|x, y| x == y)
+    }
+
+    fn value_serialize() -> impl ValueSerialize<Value = Self::Value> {
+        PagableValueSerialize::<Self::Value>::new()
+    }
+}
+
+#[derive(Allocative, Clone, Dupe, Debug, Display, PartialEq, Eq, Hash, Pagable)]
+#[pagable_typetag(DiceKeyDyn)]
+struct SnapshotOpaqueLeaf(u32);
+
+#[async_trait]
+impl Key for SnapshotOpaqueLeaf {
+    type Value = u64;
+
+    async fn compute(
+        &self,
+        _ctx: &mut DiceComputations,
+        _cancellations: &CancellationContext,
+    ) -> Self::Value {
+        u64::from(self.0) * 10
+    }
+
+    fn equality_behavior() -> EqualityBehavior<Self::Value> {
+        EqualityBehavior::Compare(|x, y| x == y)
+    }
+
+    fn value_serialize() -> impl ValueSerialize<Value = Self::Value> {
+        NoValueSerialize::<Self::Value>::new()
+    }
+}
+
+/// Depends on `SnapshotLeaf`, or on `SnapshotOpaqueLeaf` (which can't be persisted) if
+/// `opaque` is set.
+#[derive(Allocative, Clone, Dupe, Debug, Display, PartialEq, Eq, Hash, Pagable)]
+#[display("{:?}", self)]
+#[pagable_typetag(DiceKeyDyn)]
+struct SnapshotRoot {
+    leaf: u32,
+    opaque: bool,
+}
+
+#[async_trait]
+impl Key for SnapshotRoot {
+    type Value = u64;
+
+    async fn compute(
+        &self,
+        ctx: &mut DiceComputations,
+        _cancellations: &CancellationContext,
+    ) -> Self::Value {
+        Computes::record(ctx, Computes::ROOT);
+        let leaf = if self.opaque {
+            ctx.compute(&SnapshotOpaqueLeaf(self.leaf)).await
+        } else {
+            ctx.compute(&SnapshotLeaf(self.leaf)).await
+        };
+        leaf.expect("leaf should compute") + 1
+    }
+
+    fn equality_behavior() -> EqualityBehavior<Self::Value> {
+        EqualityBehavior::Compare(|x, y| x == y)
+    }
+
+    fn value_serialize() -> impl ValueSerialize<Value = Self::Value> {
+        PagableValueSerialize::<Self::Value>::new()
+    }
+}
+
+fn make_dice(path: &Path) -> anyhow::Result<Arc<Dice>> {
+    let mut builder = Dice::builder();
+    builder.set_pagable_storage(DiceStorage::open(path, PagableStorageBackend::Sqlite)?);
+    Ok(builder.build(DetectCycles::Disabled))
+}
+
+/// Compute `root` in a fresh DICE over `path` and persist a snapshot rooted at
+/// `SnapshotRoot`, dropping that DICE afterwards.
+async fn persist_root(path: &Path, root: SnapshotRoot) -> anyhow::Result<Option<DiceSnapshotId>> {
+    let dice = make_dice(path)?;
+    let tx = dice.updater().commit().await;
+    tx.compute(&root).await?;
+    drop(tx);
+    dice.wait_for_idle().await;
+    let snapshot = dice
+        .persist_snapshot([SnapshotRoot::key_type_name().to_owned()])
+        .await?;
+    Ok(snapshot.map(|snapshot| snapshot.id))
+}
+
+#[tokio::test]
+async fn restored_values_are_reused_without_recompute() -> anyhow::Result<()> {
+    let tmp = tempdir()?;
+    let root = SnapshotRoot {
+        leaf: 4,
+        opaque: false,
+    };
+    let id = persist_root(tmp.path(), root.dupe())
+        .await?
+        .expect("root and leaf are persistable");
+
+    let dice = make_dice(tmp.path())?;
+    assert_eq!(dice.restore_snapshot(id).await?, 2);
+
+    let computes = Computes::new();
+    let tx = dice.updater_with_data(computes.user_data()).commit().await;
+    assert_eq!(*tx.compute(&root).await?, 41);
+    assert_eq!(*tx.compute(&SnapshotLeaf(4)).await?, 40);
+    assert_eq!(computes.count(Computes::ROOT), 0);
+    assert_eq!(computes.count(Computes::LEAF), 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn restored_nodes_are_invalidated_through_their_deps() -> anyhow::Result<()> {
+    let tmp = tempdir()?;
+    let root = SnapshotRoot {
+        leaf: 4,
+        opaque: false,
+    };
+    let id = persist_root(tmp.path(), root.dupe())
+        .await?
+        .expect("root and leaf are persistable");
+
+    let dice = make_dice(tmp.path())?;
+    dice.restore_snapshot(id).await?;
+
+    let computes = Computes::new();
+    let mut updater = dice.updater_with_data(computes.user_data());
+    updater.changed([SnapshotLeaf(4)])?;
+    let tx = updater.commit().await;
+    assert_eq!(*tx.compute(&root).await?, 41);
+    assert_eq!(
+        computes.count(Computes::LEAF),
+        1,
+        "the restored leaf should be dirtied like any other node"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn nodes_depending_on_unpersistable_values_are_not_persisted() -> anyhow::Result<()> {
+    let tmp = tempdir()?;
+    let root = SnapshotRoot {
+        leaf: 4,
+        opaque: true,
+    };
+    assert_eq!(persist_root(tmp.path(), root).await?, None);
+    Ok(())
+}
+
+#[tokio::test]
+async fn snapshot_id_round_trips_through_its_string_form() -> anyhow::Result<()> {
+    let tmp = tempdir()?;
+    let id = persist_root(
+        tmp.path(),
+        SnapshotRoot {
+            leaf: 1,
+            opaque: false,
+        },
+    )
+    .await?
+    .expect("root and leaf are persistable");
+    assert_eq!(id.to_string().parse::<DiceSnapshotId>()?, id);
+    assert!("not hex".parse::<DiceSnapshotId>().is_err());
+    Ok(())
+}
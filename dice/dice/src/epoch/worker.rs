@@ -26,6 +26,7 @@ use itertools::Either;
 use crate::DynKey;
 use crate::api::activation_tracker::ActivationData;
 use crate::api::activation_tracker::PageInPhase;
+use crate::api::invalidation_tracking::DiceKeyTrackedInvalidationPaths;
 use crate::arc::Arc;
 use crate::core::graph::types::PagedOutMismatch;
 use crate::core::graph::types::VersionedGraphKey;
@@ -332,6 +333,7 @@ impl DiceTaskWorker {
         drop(deps_check_continuables);
 
         let activation_info = self.activation_info(result.deps.iter_keys(), activation_data);
+        self.record_invalidation_paths(&result.invalidation_paths);
 
         let res = {
             match result.value.into_valid_value() {
@@ -413,6 +415,22 @@ impl DiceTaskWorker {
         )
     }
 
+    fn record_invalidation_paths(&self, paths: &TrackedInvalidationPaths) {
+        if let Some(recorder) = self.eval.user_data.invalidation_path_recorder.as_ref() {
+            let key = DynKey::ref_cast(self.eval.dice.key_index.get(self.k));
+            if recorder.should_record(key) {
+                recorder.key_recomputed(
+                    key,
+                    DiceKeyTrackedInvalidationPaths::new(
+                        self.eval.dice.dupe(),
+                        paths.get_normal(),
+                        paths.get_high(),
+                    ),
+                );
+            }
+        }
+    }
+
     /// Deserialize a paged-out value via `DiceStorage`, then send a `Rehydrate` request
     /// so the graph node returns to the `Hydrated` state for subsequent lookups. The
     /// returned value is the worker's local copy.
@@ -86,6 +86,10 @@ impl DiceKeyErased {
         })
     }
 
+    pub(crate) fn proj_dyn(base: DiceKey, proj: StdArc<dyn DiceProjectionDyn>) -> Self {
+        Self::Projection(ProjectionWithBase { base, proj })
+    }
+
     pub(crate) fn key_type_name(&self) -> &'static str {
         match self {
             DiceKeyErased::Key(k) => k.key_type_name(),
@@ -290,6 +294,14 @@ impl<'a> CowDiceKeyHashed<'a> {
         }
     }
 
+    pub(crate) fn erased(key: DiceKeyErased) -> CowDiceKeyHashed<'static> {
+        let hash = key.hash();
+        CowDiceKeyHashed {
+            cow: CowDiceKey::Owned(key),
+            hash,
+        }
+    }
+
     pub(crate) fn hash(&self) -> u64 {
         self.hash
     }
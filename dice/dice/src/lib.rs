@@ -204,6 +204,7 @@ pub(crate) mod key;
 mod key_index;
 pub(crate) mod metrics;
 pub(crate) mod opaque;
+mod snapshot;
 pub(crate) mod storage;
 pub(crate) mod updater;
 pub(crate) mod user_cycle;
@@ -234,6 +235,7 @@ pub use crate::api::invalidation_tracking::DiceInvalidationPath;
 pub use crate::api::invalidation_tracking::DiceKeyTrackedInvalidationPaths;
 pub use crate::api::invalidation_tracking::DiceTrackedInvalidationPath;
 pub use crate::api::invalidation_tracking::InvalidationPathEntry;
+pub use crate::api::invalidation_tracking::InvalidationPathRecorder;
 pub use crate::api::key::EqualityBehavior;
 pub use crate::api::key::InvalidationSourcePriority;
 pub use crate::api::key::Key;
@@ -261,6 +263,8 @@ pub use crate::introspection::serialize_graph;
 pub use crate::key::DiceKeyDyn;
 pub use crate::key::DiceProjectionDyn;
 pub use crate::opaque::OpaqueValue;
+pub use crate::snapshot::DiceSnapshotId;
+pub use crate::snapshot::PersistedSnapshot;
 pub use crate::storage::DiceStorage;
 pub use crate::storage::PagableStorageBackend;
 pub use crate::value::DiceValueDyn;
@@ -0,0 +1,441 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is dual-licensed under either the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree or the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree. You may select, at your option, one of the
+ * above-listed licenses.
+ */
+
+//! Persisting committed DICE state across daemon restarts.
+//!
+//! A snapshot is a manifest item in pagable storage listing nodes in dependency order:
+//! every entry comes after its deps, which are referenced by position. Keys are written
+//! with their type tag; values are referenced by the `DataKey` they were paged out to, so a
+//! snapshot of an already paged-out graph writes little more than its keys.
+//!
+//! Only the dependency closure of the requested root key types is persisted, and a node is
+//! dropped (with everything depending on it) if its value or any of its deps can't be
+//! persisted: a restored node is trusted only because everything it was computed from is
+//! restored with it. Projections are persisted without their value and recomputed from their
+//! restored base.
+//!
+//! Restoring re-inserts computed nodes as paged out, so values load lazily on first use;
+//! injected values are hydrated eagerly since the graph holds them resident. The caller is
+//! responsible for then invalidating whatever changed while no daemon was running (the file
+//! watcher does this for buck2).
+
+use std::fmt;
+use std::fmt::Display;
+use std::str::FromStr;
+use std::sync::Arc as StdArc;
+
+use dupe::Dupe;
+use pagable::DataKey;
+use pagable::PagableBoxDeserialize;
+use pagable::PagableDeserialize;
+use pagable::PagableDeserializer;
+use pagable::PagableSerialize;
+use pagable::PagableSerializer;
+use pagable::storage::traits::ArcSerCache;
+
+use crate::HashMap;
+use crate::HashSet;
+use crate::api::projection::DiceProjectionComputations;
+use crate::api::user_data::UserComputationData;
+use crate::core::graph::storage::PersistedValue;
+use crate::core::internals::RestoredNode;
+use crate::core::internals::SnapshotNode;
+use crate::dice::Dice;
+use crate::key::CowDiceKeyHashed;
+use crate::key::DiceKey;
+use crate::key::DiceKeyDyn;
+use crate::key::DiceKeyErased;
+use crate::key::DiceProjectionDyn;
+use crate::storage::DiceStorage;
+use crate::value::DiceValidValue;
+use crate::value::MaybeValidDiceValue;
+
+/// Bumped whenever the manifest layout changes; older manifests are rejected on restore.
+const MANIFEST_FORMAT_VERSION: u32 = 1;
+
+/// Identifies a persisted snapshot in pagable storage. Rendered as 32 hex digits so it can be
+/// recorded outside of DICE (e.g. next to the daemon's other state) and parsed back.
+#[derive(Clone, Copy, Dupe, Debug, PartialEq, Eq)]
+pub struct DiceSnapshotId(DataKey);
+
+impl Display for DiceSnapshotId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:032x}", self.0.get())
+    }
+}
+
+impl FromStr for DiceSnapshotId {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let v = u128::from_str_radix(s, 16)
+            .map_err(|e| anyhow::anyhow!("invalid DICE snapshot id `{s}`: {e}"))?;
+        Ok(DiceSnapshotId(data_key_from_u128(v)?))
+    }
+}
+
+/// A snapshot written by [`Dice::persist_snapshot`].
+#[derive(Debug)]
+pub struct PersistedSnapshot {
+    pub id: DiceSnapshotId,
+    /// Nodes written to the manifest, injected and computed.
+    pub node_count: usize,
+}
+
+fn data_key_from_u128(v: u128) -> anyhow::Result<DataKey> {
+    let mut bytes = [0; 16];
+    bytes[..8].copy_from_slice(&(v as u64).to_ne_bytes());
+    bytes[8..].copy_from_slice(&((v >> 64) as u64).to_ne_bytes());
+    DataKey::from_stored_bytes(bytes)
+}
+
+enum ManifestEntry {
+    Injected {
+        key: StdArc<dyn DiceKeyDyn>,
+        value: DataKey,
+    },
+    Computed {
+        key: StdArc<dyn DiceKeyDyn>,
+        value: DataKey,
+        deps: Vec<u32>,
+    },
+    Projection {
+        base: u32,
+        proj: StdArc<dyn DiceProjectionDyn>,
+        deps: Vec<u32>,
+    },
+}
+
+impl ManifestEntry {
+    const INJECTED: u8 = 0;
+    const COMPUTED: u8 = 1;
+    const PROJECTION: u8 = 2;
+
+    fn serialize(&self, ser: &mut dyn PagableSerializer) -> anyhow::Result<()> {
+        match self {
+            ManifestEntry::Injected { key, value } => {
+                Self::INJECTED.pagable_serialize(ser)?;
+                (**key).pagable_serialize(ser)?;
+                value.get().pagable_serialize(ser)?;
+            }
+            ManifestEntry::Computed { key, value, deps } => {
+                Self::COMPUTED.pagable_serialize(ser)?;
+                (**key).pagable_serialize(ser)?;
+                value.get().pagable_serialize(ser)?;
+                deps.pagable_serialize(ser)?;
+            }
+            ManifestEntry::Projection { base, proj, deps } => {
+                Self::PROJECTION.pagable_serialize(ser)?;
+                base.pagable_serialize(ser)?;
+                (**proj).pagable_serialize(ser)?;
+                deps.pagable_serialize(ser)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn deserialize<'de>(deser: &mut dyn PagableDeserializer<'de>) -> anyhow::Result<Self> {
+        let kind = u8::pagable_deserialize(deser)?;
+        Ok(match kind {
+            Self::INJECTED => ManifestEntry::Injected {
+                key: <dyn DiceKeyDyn>::deserialize_box(deser)?.into(),
+                value: data_key_from_u128(u128::pagable_deserialize(deser)?)?,
+            },
+            Self::COMPUTED => ManifestEntry::Computed {
+                key: <dyn DiceKeyDyn>::deserialize_box(deser)?.into(),
+                value: data_key_from_u128(u128::pagable_deserialize(deser)?)?,
+                deps: Vec::pagable_deserialize(deser)?,
+            },
+            Self::PROJECTION => ManifestEntry::Projection {
+                base: u32::pagable_deserialize(deser)?,
+                proj: <dyn DiceProjectionDyn>::deserialize_box(deser)?.into(),
+                deps: Vec::pagable_deserialize(deser)?,
+            },
+            _ => return Err(anyhow::anyhow!("unknown DICE snapshot entry kind {kind}")),
+        })
+    }
+}
+
+fn serialize_manifest(
+    entries: &[ManifestEntry],
+    ser: &mut dyn PagableSerializer,
+) -> anyhow::Result<()> {
+    MANIFEST_FORMAT_VERSION.pagable_serialize(ser)?;
+    entries.len().pagable_serialize(ser)?;
+    for entry in entries {
+        entry.serialize(ser)?;
+    }
+    Ok(())
+}
+
+fn deserialize_manifest<'de>(
+    deser: &mut dyn PagableDeserializer<'de>,
+) -> anyhow::Result<Vec<ManifestEntry>> {
+    let version = u32::pagable_deserialize(deser)?;
+    if version != MANIFEST_FORMAT_VERSION {
+        return Err(anyhow::anyhow!(
+            "DICE snapshot has format version {version}, expected {MANIFEST_FORMAT_VERSION}"
+        ));
+    }
+    let len = usize::pagable_deserialize(deser)?;
+    (0..len)
+        .map(|_| ManifestEntry::deserialize(deser))
+        .collect()
+}
+
+/// Progress of a node through the deps-first walk in `plan_manifest`.
+#[derive(Clone, Copy)]
+enum Visit {
+    /// Deps are being visited.
+    InProgress,
+    /// Written as the manifest entry at this position.
+    Persisted(u32),
+    /// Not persistable itself, or depends on something that isn't.
+    Skipped,
+}
+
+/// Walk the deps of every node whose key type is in `root_key_types` and build the manifest
+/// entries in dependency order, writing out resident values as they are reached.
+fn plan_manifest(
+    dice: &Dice,
+    storage: &DiceStorage,
+    nodes: &[SnapshotNode],
+    root_key_types: &HashSet<String>,
+) -> anyhow::Result<Vec<ManifestEntry>> {
+    let by_key: HashMap<DiceKey, usize> = nodes
+        .iter()
+        .enumerate()
+        .map(|(i, node)| (node.key, i))
+        .collect();
+    // A projection's base is needed to recompute it on restore, so it is walked like a dep.
+    let walk_deps = |i: usize| -> Vec<DiceKey> {
+        let node = &nodes[i];
+        let mut deps = node.deps.clone();
+        if let DiceKeyErased::Projection(proj) = dice.key_index.get(node.key) {
+            deps.push(proj.base());
+        }
+        deps
+    };
+
+    let finished = ArcSerCache::new();
+    let mut visits: Vec<Option<Visit>> = vec![None; nodes.len()];
+    let mut entries = Vec::new();
+    let roots = nodes
+        .iter()
+        .enumerate()
+        .filter(|(_, node)| root_key_types.contains(dice.key_index.get(node.key).key_type_name()));
+    for (root, _) in roots {
+        let mut stack = vec![(root, false)];
+        while let Some((i, deps_visited)) = stack.pop() {
+            if !deps_visited {
+                if visits[i].is_some() {
+                    continue;
+                }
+                visits[i] = Some(Visit::InProgress);
+                stack.push((i, true));
+                for dep in walk_deps(i) {
+                    if let Some(&j) = by_key.get(&dep) {
+                        if visits[j].is_none() {
+                            stack.push((j, false));
+                        }
+                    }
+                }
+                continue;
+            }
+
+            // A dep that was never collected (not committed at the current version), or that
+            // is still in progress (only possible on a cycle), makes this node unpersistable.
+            let persisted_deps: Option<Vec<u32>> = nodes[i]
+                .deps
+                .iter()
+                .map(|dep| match visits[*by_key.get(dep)?] {
+                    Some(Visit::Persisted(idx)) => Some(idx),
+                    _ => None,
+                })
+                .collect();
+            let entry = match persisted_deps {
+                Some(deps) => {
+                    plan_entry(
+                        dice,
+                        storage,
+                        &finished,
+                        &nodes[i],
+                        deps,
+                        |key| match visits[*by_key.get(&key)?] {
+                            Some(Visit::Persisted(idx)) => Some(idx),
+                            _ => None,
+                        },
+                    )?
+                }
+                None => None,
+            };
+            visits[i] = Some(match entry {
+                Some(entry) => {
+                    entries.push(entry);
+                    Visit::Persisted((entries.len() - 1) as u32)
+                }
+                None => Visit::Skipped,
+            });
+        }
+    }
+    Ok(entries)
+}
+
+/// The manifest entry for `node`, or `None` if it can't be persisted.
+fn plan_entry(
+    dice: &Dice,
+    storage: &DiceStorage,
+    finished: &ArcSerCache,
+    node: &SnapshotNode,
+    deps: Vec<u32>,
+    persisted_index: impl Fn(DiceKey) -> Option<u32>,
+) -> anyhow::Result<Option<ManifestEntry>> {
+    let key_dyn = dice.key_index.get(node.key);
+    let key = match key_dyn {
+        DiceKeyErased::Key(key) => key.dupe(),
+        DiceKeyErased::Projection(proj) => {
+            return Ok(
+                persisted_index(proj.base()).map(|base| ManifestEntry::Projection {
+                    base,
+                    proj: proj.proj().clone_arc(),
+                    deps,
+                }),
+            );
+        }
+    };
+    let value = match &node.value {
+        PersistedValue::PagedOut(data_key) => *data_key,
+        PersistedValue::Resident(value) => {
+            match storage.page_out_value(key_dyn, value.dupe(), finished)? {
+                Some(data_key) => data_key,
+                None => return Ok(None),
+            }
+        }
+    };
+    Ok(Some(if node.injected {
+        ManifestEntry::Injected { key, value }
+    } else {
+        ManifestEntry::Computed { key, value, deps }
+    }))
+}
+
+pub(crate) async fn persist(
+    dice: &StdArc<Dice>,
+    storage: &DiceStorage,
+    root_key_types: HashSet<String>,
+) -> anyhow::Result<Option<PersistedSnapshot>> {
+    let nodes = dice.state_handle.snapshot_nodes().await;
+    let entries = {
+        let dice = dice.dupe();
+        let storage = storage.dupe();
+        // Serializing resident values is CPU-bound; keep it off the async workers.
+        tokio::task::spawn_blocking(move || plan_manifest(&dice, &storage, &nodes, &root_key_types))
+            .await??
+    };
+    if entries.is_empty() {
+        return Ok(None);
+    }
+    let root = storage.page_out_root(|ser| serialize_manifest(&entries, ser))?;
+    storage.flush().await?;
+    Ok(Some(PersistedSnapshot {
+        id: DiceSnapshotId(root),
+        node_count: entries.len(),
+    }))
+}
+
+pub(crate) async fn restore(
+    dice: &StdArc<Dice>,
+    storage: &DiceStorage,
+    id: DiceSnapshotId,
+) -> anyhow::Result<usize> {
+    let entries = storage.fetch_root(id.0, deserialize_manifest).await?;
+
+    // By entry position: the restored `DiceKey` and where its value lives. Projection bases
+    // are hydrated on demand, so a value may move from on-disk to resident.
+    let mut keys: Vec<DiceKey> = Vec::with_capacity(entries.len());
+    let mut values: Vec<PersistedValue> = Vec::with_capacity(entries.len());
+    let mut nodes = Vec::with_capacity(entries.len());
+    for entry in entries {
+        match entry {
+            ManifestEntry::Injected { key, value } => {
+                let key_erased = DiceKeyErased::Key(key);
+                let value = storage.hydrate(&key_erased, value).await?;
+                let key = dice.key_index.index(CowDiceKeyHashed::erased(key_erased));
+                keys.push(key);
+                values.push(PersistedValue::Resident(value.dupe()));
+                nodes.push(RestoredNode::Injected { key, value });
+            }
+            ManifestEntry::Computed { key, value, deps } => {
+                let deps = resolve_entries(&keys, &deps)?;
+                let key = dice
+                    .key_index
+                    .index(CowDiceKeyHashed::erased(DiceKeyErased::Key(key)));
+                keys.push(key);
+                values.push(PersistedValue::PagedOut(value));
+                nodes.push(RestoredNode::Computed {
+                    key,
+                    value: PersistedValue::PagedOut(value),
+                    deps,
+                });
+            }
+            ManifestEntry::Projection { base, proj, deps } => {
+                let deps = resolve_entries(&keys, &deps)?;
+                let base_key = resolve_entries(&keys, &[base])?[0];
+                let base_value = match &values[base as usize] {
+                    PersistedValue::Resident(value) => value.dupe(),
+                    PersistedValue::PagedOut(data_key) => {
+                        let data_key = *data_key;
+                        let value = storage
+                            .hydrate(dice.key_index.get(base_key), data_key)
+                            .await?;
+                        values[base as usize] = PersistedValue::Resident(value.dupe());
+                        value
+                    }
+                };
+                let value = DiceValidValue::from_arc(proj.compute(
+                    &MaybeValidDiceValue::valid(base_value),
+                    &DiceProjectionComputations {
+                        data: &dice.global_data,
+                        user_data: &UserComputationData::new(),
+                    },
+                ));
+                let key = dice
+                    .key_index
+                    .index(CowDiceKeyHashed::erased(DiceKeyErased::proj_dyn(
+                        base_key, proj,
+                    )));
+                keys.push(key);
+                values.push(PersistedValue::Resident(value.dupe()));
+                nodes.push(RestoredNode::Computed {
+                    key,
+                    value: PersistedValue::Resident(value),
+                    deps,
+                });
+            }
+        }
+    }
+    Ok(dice.state_handle.restore_nodes(nodes).await)
+}
+
+/// Map manifest positions to the keys already restored for them. Entries only refer to
+/// earlier entries, so anything else means the manifest is corrupt.
+fn resolve_entries(keys: &[DiceKey], positions: &[u32]) -> anyhow::Result<Vec<DiceKey>> {
+    positions
+        .iter()
+        .map(|&p| {
+            keys.get(p as usize).copied().ok_or_else(|| {
+                anyhow::anyhow!(
+                    "corrupt DICE snapshot: entry {} refers forward to {p}",
+                    keys.len()
+                )
+            })
+        })
+        .collect()
+}
@@ -30,6 +30,8 @@ use dashmap::DashMap;
 use dice_error::storage::PagableStorageBackendParseError;
 use dupe::Dupe;
 use pagable::DataKey;
+use pagable::PagableDeserializer;
+use pagable::PagableSerializer;
 use pagable::StorageContext;
 use pagable::storage::handle::PagableStorageHandle;
 use pagable::storage::noop::NoopPagableStorage;
@@ -316,7 +318,10 @@ impl DiceStorage {
         Ok(())
     }
 
-    fn page_out_value(
+    /// Serialize `value` via `key_dyn`'s `ValueSerialize` and write it to storage.
+    /// `None` if the key has no value serializer (or an earlier write of a shared
+    /// sub-value failed), in which case nothing is written.
+    pub(crate) fn page_out_value(
         &self,
         key_dyn: &DiceKeyErased,
         value: DiceValidValue,
@@ -353,6 +358,47 @@ impl DiceStorage {
         }
     }
 
+    /// Write a standalone item (one not tied to a DICE key) to storage, returning its
+    /// `DataKey`. Used for persisted snapshot manifests.
+    pub(crate) fn page_out_root(
+        &self,
+        serialize: impl FnOnce(&mut dyn PagableSerializer) -> anyhow::Result<()>,
+    ) -> anyhow::Result<DataKey> {
+        let storage_context = self.storage.storage_context();
+        let mut serializer = SerializerForPaging::new(storage_context);
+        serialize(&mut serializer)?;
+        let (data, arcs) = serializer.finish();
+        match self
+            .storage
+            .page_out_item(data, arcs, &ArcSerCache::new(), storage_context)
+        {
+            Ok(key) => Ok(key),
+            Err(PageOutError::Failed(e)) => Err(e),
+            Err(PageOutError::AlreadyFailed) => {
+                Err(anyhow::anyhow!("pagable storage rejected standalone item"))
+            }
+        }
+    }
+
+    /// Read back an item written by [`DiceStorage::page_out_root`].
+    pub(crate) async fn fetch_root<T>(
+        &self,
+        data_key: DataKey,
+        deserialize: impl for<'de> FnOnce(&mut dyn PagableDeserializer<'de>) -> anyhow::Result<T>,
+    ) -> anyhow::Result<T> {
+        let data = self.storage.fetch_data(&data_key).await?;
+        let handle = self.storage_handle();
+        let mut deserializer = handle.root_deserializer(data_key, &data);
+        deserialize(&mut deserializer)
+    }
+
+    /// Flush writes made outside of `page_out` (e.g. a persisted snapshot) to disk.
+    pub(crate) async fn flush(&self) -> anyhow::Result<()> {
+        self.storage.flush()?;
+        self.refresh_db_size_bytes().await;
+        Ok(())
+    }
+
     /// Rehydrate all paged-out values in parallel, sending rehydrate messages
     /// back to the core state thread.
     pub(crate) async fn page_in(
@@ -10,6 +10,7 @@
 
 use std::hash::Hash;
 use std::sync::Arc;
+use std::sync::Mutex;
 
 use allocative::Allocative;
 use async_trait::async_trait;
@@ -20,10 +21,13 @@ use dice::DiceComputations;
 use dice::DiceKeyDyn;
 use dice::DiceKeyTrackedInvalidationPaths;
 use dice::DiceTrackedInvalidationPath;
+use dice::DynKey;
 use dice::EqualityBehavior;
 use dice::InjectedKey;
+use dice::InvalidationPathRecorder;
 use dice::InvalidationSourcePriority;
 use dice::Key;
+use dice::UserComputationData;
 use dice_futures::cancellation::CancellationContext;
 use dupe::Dupe;
 use gazebo::prelude::*;
@@ -416,3 +420,67 @@ fn test_compute_tracks_invalidations_over_versions() -> anyhow::Result<()> {
         Ok(())
     })
 }
+
+#[test]
+fn test_invalidation_path_recorder() -> anyhow::Result<()> {
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .unhandled_panic(tokio::runtime::UnhandledPanic::ShutdownRuntime)
+        .build()
+        .unwrap();
+
+    rt.block_on(async {
+        let dice = {
+            let builder = Dice::builder();
+            builder.build(DetectCycles::Enabled)
+        };
+
+        /// Records the keys of one type.
+        struct Recorder {
+            key_type: &'static str,
+            recorded: Mutex<Vec<(String, CapturedInvalidationPaths)>>,
+        }
+
+        impl InvalidationPathRecorder for Recorder {
+            fn should_record(&self, key: &DynKey) -> bool {
+                key.key_type_name() == self.key_type
+            }
+
+            fn key_recomputed(&self, key: &DynKey, paths: DiceKeyTrackedInvalidationPaths) {
+                self.recorded
+                    .lock()
+                    .unwrap()
+                    .push((key.to_string(), CapturedInvalidationPaths::new(paths)));
+            }
+        }
+
+        let recorder = Arc::new(Recorder {
+            key_type: HighChanged::key_type_name(),
+            recorded: Mutex::new(Vec::new()),
+        });
+
+        let mut updater = dice.updater_with_data(UserComputationData {
+            invalidation_path_recorder: Some(recorder.dupe()),
+            ..Default::default()
+        });
+        updater.changed_to([(NormalInjected(0), 0)])?;
+        updater.changed_to([(HighInjected(0), 0)])?;
+        let ctx = updater.commit().await;
+
+        ctx.compute(&NormalChanged(0)).await?;
+        ctx.compute(&HighChanged(0)).await?;
+
+        let recorded = recorder.recorded.lock().unwrap();
+        assert_eq!(
+            recorded.map(|(key, _)| key.as_str()),
+            vec![HighChanged(0).to_string().as_str()]
+        );
+        assert_invalidations(
+            ExpectedInvalidation::Invalidated(vec![ExpectedInvalidation::item(HighInjected(0), 2)]),
+            ExpectedInvalidation::Invalidated(vec![ExpectedInvalidation::item(HighInjected(0), 2)]),
+            &recorded[0].1,
+        );
+
+        Ok(())
+    })
+}
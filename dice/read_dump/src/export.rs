@@ -0,0 +1,91 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is dual-licensed under either the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree or the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree. You may select, at your option, one of the
+ * above-listed licenses.
+ */
+
+//! Export a subgraph of a DICE dump, for viewing with Graphviz or processing with other tools.
+
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use dice::introspection::graph::short_type_name;
+
+use crate::graph::Direction;
+use crate::graph::DumpGraph;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub(crate) enum ExportFormat {
+    Dot,
+    Json,
+}
+
+/// Write the `visited` keys, with their distance from the roots of the walk, and the dependency
+/// edges between them. Edges always point from a key to the keys it depends on.
+pub(crate) fn export(
+    out: &mut dyn Write,
+    graph: &DumpGraph,
+    visited: &[(usize, usize)],
+    format: ExportFormat,
+) -> anyhow::Result<()> {
+    let included: BTreeMap<usize, usize> = visited.iter().copied().collect();
+    let edges: Vec<(usize, usize)> = included
+        .keys()
+        .flat_map(|from| {
+            graph
+                .edges(*from, Direction::Deps)
+                .iter()
+                .filter(|to| included.contains_key(to))
+                .map(|to| (*from, *to))
+        })
+        .collect();
+
+    match format {
+        ExportFormat::Dot => {
+            writeln!(out, "digraph dice {{")?;
+            for i in included.keys() {
+                let node = graph.node(*i);
+                let label = format!("{}\n{}", short_type_name(&node.type_name), node.key);
+                // Debug formatting gives a quoted, escaped string, which is what DOT wants.
+                writeln!(out, "  n{} [label={:?}];", node.id.0, label)?;
+            }
+            for (from, to) in edges {
+                writeln!(
+                    out,
+                    "  n{} -> n{};",
+                    graph.node(from).id.0,
+                    graph.node(to).id.0
+                )?;
+            }
+            writeln!(out, "}}")?;
+        }
+        ExportFormat::Json => {
+            let nodes: Vec<serde_json::Value> = included
+                .iter()
+                .map(|(i, distance)| {
+                    let node = graph.node(*i);
+                    serde_json::json!({
+                        "id": node.id.0,
+                        "key": node.key,
+                        "type_name": node.type_name,
+                        "distance": distance,
+                    })
+                })
+                .collect();
+            let edges: Vec<[usize; 2]> = edges
+                .into_iter()
+                .map(|(from, to)| [graph.node(from).id.0, graph.node(to).id.0])
+                .collect();
+            serde_json::to_writer_pretty(
+                &mut *out,
+                &serde_json::json!({ "nodes": nodes, "edges": edges }),
+            )?;
+            writeln!(out)?;
+        }
+    }
+    Ok(())
+}
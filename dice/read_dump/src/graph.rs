@@ -0,0 +1,275 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is dual-licensed under either the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree or the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree. You may select, at your option, one of the
+ * above-listed licenses.
+ */
+
+//! An indexed, in-memory view of a DICE dump.
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::BufReader;
+use std::io::Read;
+use std::io::Seek;
+use std::path::Path;
+
+use anyhow::Context;
+use dice::introspection::graph::SerializedGraphNodeForKey;
+use dice::introspection::graph::short_type_name;
+use regex::Regex;
+
+/// Which edges to follow when walking the graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub(crate) enum Direction {
+    /// The keys a key was computed from.
+    Deps,
+    /// The keys computed from a key, which are invalidated when it changes.
+    Rdeps,
+}
+
+pub(crate) struct DumpGraph {
+    nodes: Vec<SerializedGraphNodeForKey>,
+    /// Node index by `KeyID`.
+    by_id: HashMap<usize, usize>,
+    /// Node index by the key's `Display` output. Keys that print the same are all kept.
+    by_key: HashMap<String, Vec<usize>>,
+    deps: Vec<Vec<usize>>,
+    rdeps: Vec<Vec<usize>>,
+}
+
+impl DumpGraph {
+    /// Load a dump written by `buck2 debug dice-dump --serde` or `--serde-pretty`.
+    pub(crate) fn load(path: &Path) -> anyhow::Result<Self> {
+        let mut file = BufReader::new(
+            File::open(path).with_context(|| format!("Failed to open `{}`", path.display()))?,
+        );
+
+        let mut first = [0u8; 1];
+        let is_json = file.read(&mut first)? == 1 && first[0] == b'[';
+        file.rewind()?;
+
+        let nodes: Vec<SerializedGraphNodeForKey> = if is_json {
+            serde_json::from_reader(file)?
+        } else {
+            bincode::serde::decode_from_std_read(&mut file, bincode::config::legacy())?
+        };
+        Ok(Self::new(nodes))
+    }
+
+    pub(crate) fn new(nodes: Vec<SerializedGraphNodeForKey>) -> Self {
+        let by_id: HashMap<usize, usize> =
+            nodes.iter().enumerate().map(|(i, n)| (n.id.0, i)).collect();
+
+        let mut by_key: HashMap<String, Vec<usize>> = HashMap::new();
+        for (i, n) in nodes.iter().enumerate() {
+            by_key.entry(n.key.clone()).or_default().push(i);
+        }
+
+        // Nodes record edges in both directions, and the two are not always in agreement (e.g.
+        // injected keys have no deps), so take the union of both.
+        let mut deps: Vec<BTreeSet<usize>> = vec![BTreeSet::new(); nodes.len()];
+        let mut rdeps: Vec<BTreeSet<usize>> = vec![BTreeSet::new(); nodes.len()];
+        for (i, n) in nodes.iter().enumerate() {
+            for d in n.node.deps.iter().filter_map(|d| by_id.get(&d.0)) {
+                deps[i].insert(*d);
+                rdeps[*d].insert(i);
+            }
+            for r in n.node.rdeps.iter().filter_map(|r| by_id.get(&r.0)) {
+                rdeps[i].insert(*r);
+                deps[*r].insert(i);
+            }
+        }
+
+        Self {
+            nodes,
+            by_id,
+            by_key,
+            deps: deps.into_iter().map(|x| x.into_iter().collect()).collect(),
+            rdeps: rdeps.into_iter().map(|x| x.into_iter().collect()).collect(),
+        }
+    }
+
+    pub(crate) fn nodes(&self) -> &[SerializedGraphNodeForKey] {
+        &self.nodes
+    }
+
+    pub(crate) fn node(&self, index: usize) -> &SerializedGraphNodeForKey {
+        &self.nodes[index]
+    }
+
+    pub(crate) fn edges(&self, index: usize, direction: Direction) -> &[usize] {
+        match direction {
+            Direction::Deps => &self.deps[index],
+            Direction::Rdeps => &self.rdeps[index],
+        }
+    }
+
+    /// Find the keys whose `Display` output matches `pattern`, optionally restricted to keys of
+    /// type `type_name`, which may be the full or the short type name.
+    pub(crate) fn find(&self, type_name: Option<&str>, pattern: Option<&Regex>) -> Vec<usize> {
+        (0..self.nodes.len())
+            .filter(|i| {
+                self.has_type(*i, type_name)
+                    && pattern.is_none_or(|p| p.is_match(&self.nodes[*i].key))
+            })
+            .collect()
+    }
+
+    fn has_type(&self, index: usize, type_name: Option<&str>) -> bool {
+        let actual = &self.nodes[index].type_name;
+        type_name.is_none_or(|t| actual == t || short_type_name(actual) == t)
+    }
+
+    /// Resolve a key given on the command line: `#<id>` for a `KeyID`, the exact `Display`
+    /// output of a key, or else a regex matched against the keys.
+    pub(crate) fn select(
+        &self,
+        selector: &str,
+        type_name: Option<&str>,
+    ) -> anyhow::Result<Vec<usize>> {
+        if let Some(Ok(id)) = selector.strip_prefix('#').map(|id| id.parse::<usize>()) {
+            return match self.by_id.get(&id) {
+                Some(i) => Ok(vec![*i]),
+                None => Err(anyhow::anyhow!("No key with id `{id}` in the dump")),
+            };
+        }
+
+        let selected = match self.by_key.get(selector) {
+            Some(exact) => exact
+                .iter()
+                .copied()
+                .filter(|i| self.has_type(*i, type_name))
+                .collect(),
+            None => {
+                let pattern = Regex::new(selector)
+                    .with_context(|| format!("`{selector}` is neither a key nor a regex"))?;
+                self.find(type_name, Some(&pattern))
+            }
+        };
+        if selected.is_empty() {
+            return Err(anyhow::anyhow!("No key in the dump matches `{selector}`"));
+        }
+        Ok(selected)
+    }
+
+    /// Breadth-first walk from `roots`, returning every reachable key with its distance from the
+    /// nearest root, in the order visited. Roots are at distance 0.
+    pub(crate) fn walk(
+        &self,
+        roots: &[usize],
+        direction: Direction,
+        max_depth: Option<usize>,
+    ) -> Vec<(usize, usize)> {
+        let mut distance: BTreeMap<usize, usize> = BTreeMap::new();
+        let mut visited = Vec::new();
+        let mut queue = VecDeque::new();
+        for r in roots {
+            if distance.insert(*r, 0).is_none() {
+                visited.push((*r, 0));
+                queue.push_back(*r);
+            }
+        }
+
+        while let Some(i) = queue.pop_front() {
+            let d = distance[&i];
+            if max_depth.is_some_and(|max| d >= max) {
+                continue;
+            }
+            for next in self.edges(i, direction) {
+                if !distance.contains_key(next) {
+                    distance.insert(*next, d + 1);
+                    visited.push((*next, d + 1));
+                    queue.push_back(*next);
+                }
+            }
+        }
+        visited
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dice::introspection::graph::CellHistory;
+    use dice::introspection::graph::GraphNodeKind;
+    use dice::introspection::graph::KeyID;
+    use dice::introspection::graph::SerializedGraphNode;
+
+    use super::*;
+
+    fn node(id: usize, key: &str, deps: &[usize]) -> SerializedGraphNodeForKey {
+        SerializedGraphNodeForKey {
+            id: KeyID(id),
+            key: key.to_owned(),
+            type_name: format!("my_crate::{}", key.split(':').next().unwrap()),
+            node: SerializedGraphNode {
+                node_id: KeyID(id),
+                kind: GraphNodeKind::Occupied,
+                history: CellHistory {
+                    valid_ranges: Vec::new(),
+                    force_dirtied_at: Vec::new(),
+                },
+                deps: deps.iter().map(|d| KeyID(*d)).collect(),
+                rdeps: Vec::new(),
+            },
+        }
+    }
+
+    /// `file:a` <- `parse:a` <- `analysis:x` -> `parse:b` -> `file:b`
+    fn graph() -> DumpGraph {
+        DumpGraph::new(vec![
+            node(10, "file:a", &[]),
+            node(11, "file:b", &[]),
+            node(20, "parse:a", &[10]),
+            node(21, "parse:b", &[11]),
+            node(30, "analysis:x", &[20, 21]),
+        ])
+    }
+
+    fn keys(graph: &DumpGraph, visited: &[(usize, usize)]) -> Vec<(String, usize)> {
+        visited
+            .iter()
+            .map(|(i, d)| (graph.node(*i).key.clone(), *d))
+            .collect()
+    }
+
+    #[test]
+    fn test_select() -> anyhow::Result<()> {
+        let graph = graph();
+        assert_eq!(graph.select("#20", None)?, vec![2]);
+        assert_eq!(graph.select("file:b", None)?, vec![1]);
+        assert_eq!(graph.select("^parse:", None)?, vec![2, 3]);
+        assert_eq!(graph.select(":a$", Some("parse"))?, vec![2]);
+        assert_eq!(graph.select(":a$", Some("my_crate::file"))?, vec![0]);
+        assert!(graph.select("#99", None).is_err());
+        assert!(graph.select("nothing", None).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_walk() {
+        let graph = graph();
+        assert_eq!(
+            keys(&graph, &graph.walk(&[0], Direction::Rdeps, None)),
+            vec![
+                ("file:a".to_owned(), 0),
+                ("parse:a".to_owned(), 1),
+                ("analysis:x".to_owned(), 2)
+            ]
+        );
+        assert_eq!(
+            keys(&graph, &graph.walk(&[4], Direction::Deps, Some(1))),
+            vec![
+                ("analysis:x".to_owned(), 0),
+                ("parse:a".to_owned(), 1),
+                ("parse:b".to_owned(), 1)
+            ]
+        );
+    }
+}
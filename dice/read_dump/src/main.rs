@@ -8,12 +8,25 @@
  * above-listed licenses.
  */
 
+//! Reads a DICE dump written by `buck2 debug dice-dump --serde`, and either converts it to JSON
+//! or answers queries about it, e.g. why a key is recomputed.
+
 use std::fs::File;
+use std::io::BufRead;
+use std::io::IsTerminal;
+use std::io::Write;
 use std::path::PathBuf;
 
 use clap::CommandFactory;
 use clap::FromArgMatches;
-use dice::introspection::graph::SerializedGraphNodeForKey;
+use clap::Parser;
+
+use crate::graph::DumpGraph;
+use crate::query::Query;
+
+mod export;
+mod graph;
+mod query;
 
 #[derive(Debug, clap::Parser)]
 #[clap(name = "read_dump", about = "dice dump reader")]
@@ -22,6 +35,24 @@ pub(crate) struct Opt {
     file: PathBuf,
     #[clap(long = "out", help = "Copy the output to this path")]
     out: Option<PathBuf>,
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, clap::Subcommand)]
+enum Command {
+    #[clap(flatten)]
+    Query(Query),
+    /// Read queries from stdin, one per line, e.g. `rdeps --transitive #123`.
+    Shell,
+}
+
+/// A single line read by `shell`.
+#[derive(Debug, clap::Parser)]
+#[clap(no_binary_name = true)]
+struct ShellLine {
+    #[clap(subcommand)]
+    query: Query,
 }
 
 fn main() -> anyhow::Result<()> {
@@ -29,19 +60,50 @@ fn main() -> anyhow::Result<()> {
     let matches = clap.get_matches_from(std::env::args().collect::<Vec<String>>());
     let opt = Opt::from_arg_matches(&matches)?;
 
-    let mut file = File::open(opt.file)?;
+    let mut out: Box<dyn Write> = match &opt.out {
+        Some(path) => Box::new(File::create(path)?),
+        None => Box::new(std::io::stdout().lock()),
+    };
 
-    let out: Vec<SerializedGraphNodeForKey> =
-        bincode::serde::decode_from_std_read(&mut file, bincode::config::legacy())?;
+    let graph = DumpGraph::load(&opt.file)?;
+    match opt.command {
+        None => serde_json::to_writer_pretty(&mut out, graph.nodes())?,
+        Some(Command::Query(query)) => query.run(&graph, &mut out)?,
+        Some(Command::Shell) => shell(&graph, &mut out)?,
+    }
+    out.flush()?;
 
-    match opt.out {
-        Some(path) => {
-            serde_json::to_writer_pretty(File::create(path)?, &out)?;
-        }
-        None => {
-            serde_json::to_writer_pretty(std::io::stdout(), &out)?;
+    Ok(())
+}
+
+fn shell(graph: &DumpGraph, out: &mut dyn Write) -> anyhow::Result<()> {
+    let stdin = std::io::stdin();
+    let interactive = stdin.is_terminal();
+    let prompt = || -> anyhow::Result<()> {
+        if interactive {
+            eprint!("> ");
+            std::io::stderr().flush()?;
         }
+        Ok(())
     };
 
+    prompt()?;
+    for line in stdin.lock().lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            let res = shlex::split(&line)
+                .ok_or_else(|| anyhow::anyhow!("Unbalanced quotes"))
+                .and_then(|words| Ok(ShellLine::try_parse_from(words)?))
+                .and_then(|l| l.query.run(graph, out));
+            match res {
+                Ok(()) => {}
+                // Carry on when the user made a mistake, but a script should stop at the first one.
+                Err(e) if interactive => eprintln!("{e:#}"),
+                Err(e) => return Err(e.context(format!("Failed to run `{line}`"))),
+            }
+            out.flush()?;
+        }
+        prompt()?;
+    }
     Ok(())
 }
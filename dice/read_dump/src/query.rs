@@ -0,0 +1,224 @@
+/*
+ * Copyright (c) Meta Platforms, Inc. and affiliates.
+ *
+ * This source code is dual-licensed under either the MIT license found in the
+ * LICENSE-MIT file in the root directory of this source tree or the Apache
+ * License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+ * of this source tree. You may select, at your option, one of the
+ * above-listed licenses.
+ */
+
+//! Queries over a loaded DICE dump.
+//!
+//! Keys are selected by `#<id>`, by the exact `Display` output of the key, or by a regex
+//! matched against it, and can be narrowed down with `--type`.
+
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use dice::introspection::graph::GraphNodeKind;
+use dice::introspection::graph::SerializedGraphNodeForKey;
+use dice::introspection::graph::short_type_name;
+use regex::Regex;
+
+use crate::export::ExportFormat;
+use crate::export::export;
+use crate::graph::Direction;
+use crate::graph::DumpGraph;
+
+#[derive(Debug, clap::Args)]
+pub(crate) struct KeyArgs {
+    /// Only select keys of this type, given as the full or the short type name.
+    #[clap(long = "type", value_name = "TYPE")]
+    type_name: Option<String>,
+    /// The keys to start from.
+    #[clap(value_name = "KEY", required = true)]
+    keys: Vec<String>,
+}
+
+impl KeyArgs {
+    fn select(&self, graph: &DumpGraph) -> anyhow::Result<Vec<usize>> {
+        let mut selected = Vec::new();
+        for key in &self.keys {
+            selected.extend(graph.select(key, self.type_name.as_deref())?);
+        }
+        selected.sort_unstable();
+        selected.dedup();
+        Ok(selected)
+    }
+}
+
+#[derive(Debug, clap::Args)]
+pub(crate) struct WalkArgs {
+    #[clap(flatten)]
+    keys: KeyArgs,
+    /// How many edges to follow.
+    #[clap(long, default_value_t = 1, conflicts_with = "transitive")]
+    depth: usize,
+    /// Follow edges all the way.
+    #[clap(long)]
+    transitive: bool,
+}
+
+impl WalkArgs {
+    fn max_depth(&self) -> Option<usize> {
+        if self.transitive {
+            None
+        } else {
+            Some(self.depth)
+        }
+    }
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub(crate) enum Query {
+    /// List the keys of a type, or matching a regex.
+    Find {
+        #[clap(long = "type", value_name = "TYPE")]
+        type_name: Option<String>,
+        #[clap(value_name = "REGEX")]
+        pattern: Option<String>,
+    },
+    /// Count the keys of each type.
+    Types,
+    /// Show the keys that the given keys were computed from.
+    Deps(WalkArgs),
+    /// Show the keys that were computed from the given keys.
+    Rdeps(WalkArgs),
+    /// Show every key invalidated when the given keys change, e.g. the keys for a changed file.
+    Invalidate {
+        #[clap(flatten)]
+        keys: KeyArgs,
+        /// Only print how many keys of each type are invalidated.
+        #[clap(long)]
+        summary: bool,
+    },
+    /// Show the versions at which the given keys were valid, and when they were dirtied.
+    History(KeyArgs),
+    /// Export the subgraph around the given keys.
+    Export {
+        #[clap(flatten)]
+        walk: WalkArgs,
+        #[clap(long, value_enum, default_value_t = Direction::Deps)]
+        direction: Direction,
+        #[clap(long, value_enum, default_value_t = ExportFormat::Dot)]
+        format: ExportFormat,
+    },
+}
+
+impl Query {
+    pub(crate) fn run(&self, graph: &DumpGraph, out: &mut dyn Write) -> anyhow::Result<()> {
+        match self {
+            Query::Find { type_name, pattern } => {
+                let pattern = pattern.as_deref().map(Regex::new).transpose()?;
+                for i in graph.find(type_name.as_deref(), pattern.as_ref()) {
+                    write_key(out, graph.node(i))?;
+                }
+            }
+            Query::Types => {
+                let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+                for n in graph.nodes() {
+                    *counts.entry(&n.type_name).or_default() += 1;
+                }
+                write_counts(out, counts)?;
+            }
+            Query::Deps(walk) => write_walk(out, graph, walk, Direction::Deps)?,
+            Query::Rdeps(walk) => write_walk(out, graph, walk, Direction::Rdeps)?,
+            Query::Invalidate { keys, summary } => {
+                let cone = graph.walk(&keys.select(graph)?, Direction::Rdeps, None);
+                if *summary {
+                    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+                    for (i, _) in &cone {
+                        *counts.entry(&graph.node(*i).type_name).or_default() += 1;
+                    }
+                    write_counts(out, counts)?;
+                    writeln!(out, "{}\ttotal", cone.len())?;
+                } else {
+                    for (i, distance) in cone {
+                        write!(out, "{distance}\t")?;
+                        write_key(out, graph.node(i))?;
+                    }
+                }
+            }
+            Query::History(keys) => {
+                for i in keys.select(graph)? {
+                    write_history(out, graph.node(i))?;
+                }
+            }
+            Query::Export {
+                walk,
+                direction,
+                format,
+            } => {
+                let visited = graph.walk(&walk.keys.select(graph)?, *direction, walk.max_depth());
+                export(out, graph, &visited, *format)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn write_key(out: &mut dyn Write, node: &SerializedGraphNodeForKey) -> anyhow::Result<()> {
+    writeln!(
+        out,
+        "#{}\t{}\t{}",
+        node.id.0,
+        short_type_name(&node.type_name),
+        node.key
+    )?;
+    Ok(())
+}
+
+fn write_counts(out: &mut dyn Write, counts: BTreeMap<&str, usize>) -> anyhow::Result<()> {
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort_by(|(_, a), (_, b)| b.cmp(a));
+    for (type_name, count) in counts {
+        writeln!(out, "{count}\t{type_name}")?;
+    }
+    Ok(())
+}
+
+fn write_walk(
+    out: &mut dyn Write,
+    graph: &DumpGraph,
+    walk: &WalkArgs,
+    direction: Direction,
+) -> anyhow::Result<()> {
+    for (i, distance) in graph.walk(&walk.keys.select(graph)?, direction, walk.max_depth()) {
+        if distance > 0 {
+            write!(out, "{distance}\t")?;
+            write_key(out, graph.node(i))?;
+        }
+    }
+    Ok(())
+}
+
+fn write_history(out: &mut dyn Write, node: &SerializedGraphNodeForKey) -> anyhow::Result<()> {
+    write_key(out, node)?;
+    let kind = match node.node.kind {
+        GraphNodeKind::Occupied => "occupied",
+        GraphNodeKind::Transient => "transient",
+        GraphNodeKind::Vacant => "vacant",
+    };
+    writeln!(out, "  kind: {kind}")?;
+
+    let history = &node.node.history;
+    let valid: Vec<String> = history
+        .valid_ranges
+        .iter()
+        .map(|(begin, end)| match end {
+            Some(end) => format!("v{begin}..v{end}"),
+            None => format!("v{begin}.."),
+        })
+        .collect();
+    writeln!(out, "  valid at: {}", valid.join(", "))?;
+    if !history.force_dirtied_at.is_empty() {
+        let dirtied: Vec<String> = history
+            .force_dirtied_at
+            .iter()
+            .map(|v| format!("v{v}"))
+            .collect();
+        writeln!(out, "  force dirtied at: {}", dirtied.join(", "))?;
+    }
+    Ok(())
+}
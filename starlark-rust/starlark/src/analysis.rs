@@ -19,6 +19,9 @@
 
 use std::collections::HashSet;
 
+pub use fix::LintEdit;
+pub use fix::LintFix;
+pub use fix::apply_lint_fixes;
 pub use lint_message::LintMessage;
 pub use types::EvalMessage;
 pub use types::EvalSeverity;
@@ -30,6 +33,7 @@ use crate::syntax::AstModule;
 
 mod dubious;
 pub mod find_call_name;
+mod fix;
 mod flow;
 mod incompatible;
 mod lint_message;
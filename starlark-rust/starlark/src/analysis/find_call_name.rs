@@ -21,11 +21,52 @@ use starlark_syntax::syntax::ast::Argument;
 use starlark_syntax::syntax::ast::AstExpr;
 use starlark_syntax::syntax::ast::AstLiteral;
 use starlark_syntax::syntax::ast::Expr;
+use starlark_syntax::syntax::module::AstModuleFields;
 
 use crate::codemap::Span;
 use crate::codemap::Spanned;
 use crate::syntax::AstModule;
 
+/// An argument of a [`FunctionCall`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionCallArgument {
+    /// The name of a keyword argument, or `None` for a positional argument.
+    pub name: Option<String>,
+    /// The value, if it is a string literal.
+    pub string_value: Option<String>,
+    /// The source code of the value.
+    pub source: String,
+}
+
+/// A top level function call, as found by [`AstModuleFindCallName::find_function_calls`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionCall {
+    /// The source code of the called function, e.g. `cc_library` or `native.cc_library`.
+    pub function: String,
+    /// The location of the called function.
+    pub span: Span,
+    /// Positional and keyword arguments, in order. `*args` and `**kwargs` are skipped.
+    pub arguments: Vec<FunctionCallArgument>,
+}
+
+impl FunctionCall {
+    /// The value of the keyword argument `name`, if it is a string literal.
+    pub fn named_string(&self, name: &str) -> Option<&str> {
+        self.arguments
+            .iter()
+            .find(|argument| argument.name.as_deref() == Some(name))
+            .and_then(|argument| argument.string_value.as_deref())
+    }
+
+    /// The string literal positional arguments.
+    pub fn positional_strings(&self) -> impl Iterator<Item = &str> {
+        self.arguments
+            .iter()
+            .filter(|argument| argument.name.is_none())
+            .filter_map(|argument| argument.string_value.as_deref())
+    }
+}
+
 /// Find the location of a top level function call that has a kwarg "name", and a string value
 /// matching `name`.
 pub trait AstModuleFindCallName {
@@ -35,6 +76,11 @@ pub trait AstModuleFindCallName {
     /// NOTE: If the AST is exposed in the future, this function may be removed and implemented
     ///       by specific programs instead.
     fn find_function_call_with_name(&self, name: &str) -> Option<Span>;
+
+    /// Find all top level function calls, e.g. the targets of a `BUILD` file or the
+    /// dependencies declared in a `MODULE.bazel` file. Calls nested in the arguments of
+    /// another call are not included.
+    fn find_function_calls(&self) -> Vec<FunctionCall>;
 }
 
 impl AstModuleFindCallName for AstModule {
@@ -81,6 +127,49 @@ impl AstModuleFindCallName for AstModule {
             .visit_expr(|x| visit_expr(&mut ret, name, x));
         ret
     }
+
+    fn find_function_calls(&self) -> Vec<FunctionCall> {
+        let mut ret = Vec::new();
+
+        fn visit_expr(ret: &mut Vec<FunctionCall>, module: &AstModule, node: &AstExpr) {
+            match node {
+                Spanned {
+                    node: Expr::Call(identifier, arguments),
+                    ..
+                } if matches!(&identifier.node, Expr::Identifier(_) | Expr::Dot(_, _)) => {
+                    let codemap = module.codemap();
+                    let argument = |name: Option<&str>, value: &AstExpr| FunctionCallArgument {
+                        name: name.map(str::to_owned),
+                        string_value: match &value.node {
+                            Expr::Literal(AstLiteral::String(s)) => Some(s.node.clone()),
+                            _ => None,
+                        },
+                        source: codemap.source_span(value.span).to_owned(),
+                    };
+                    ret.push(FunctionCall {
+                        function: codemap.source_span(identifier.span).to_owned(),
+                        span: identifier.span,
+                        arguments: arguments
+                            .args
+                            .iter()
+                            .filter_map(|x| match &x.node {
+                                Argument::Positional(value) => Some(argument(None, value)),
+                                Argument::Named(name, value) => {
+                                    Some(argument(Some(&name.node), value))
+                                }
+                                Argument::Args(_) | Argument::KwArgs(_) => None,
+                            })
+                            .collect(),
+                    });
+                }
+                _ => node.visit_expr(|x| visit_expr(ret, module, x)),
+            }
+        }
+
+        self.statement()
+            .visit_expr(|x| visit_expr(&mut ret, self, x));
+        ret
+    }
 }
 
 #[cfg(test)]
@@ -134,4 +223,40 @@ def x(name = "foo_name"):
         );
         Ok(())
     }
+
+    #[test]
+    fn finds_function_calls() -> anyhow::Result<()> {
+        let contents = r#"
+load(":defs.bzl", "foo")
+foo(name = "foo_name", srcs = ["a.c"], *args)
+utils.bar("bar_name", deps = [baz(name = "nested")], **kwargs)
+"#;
+
+        let module =
+            AstModule::parse("BUILD", contents.to_owned(), &Dialect::AllOptionsInternal).unwrap();
+
+        let calls = module.find_function_calls();
+        assert_eq!(
+            vec!["foo", "utils.bar"],
+            calls
+                .iter()
+                .map(|c| c.function.as_str())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(Some("foo_name"), calls[0].named_string("name"));
+        assert_eq!(None, calls[0].named_string("srcs"));
+        assert_eq!(
+            vec![(Some("name"), "\"foo_name\""), (Some("srcs"), "[\"a.c\"]")],
+            calls[0]
+                .arguments
+                .iter()
+                .map(|a| (a.name.as_deref(), a.source.as_str()))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec!["bar_name"],
+            calls[1].positional_strings().collect::<Vec<_>>()
+        );
+        Ok(())
+    }
 }
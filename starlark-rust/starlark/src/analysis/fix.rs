@@ -0,0 +1,175 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Machine-applicable fixes attached to lints.
+
+use crate::codemap::CodeMap;
+use crate::codemap::Pos;
+use crate::codemap::Span;
+
+/// A single replacement of text in the linted file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintEdit {
+    /// The text to replace. An empty span is an insertion.
+    pub span: Span,
+    /// The text to put in its place.
+    pub replacement: String,
+}
+
+/// A mechanical fix for a [`Lint`](crate::analysis::Lint),
+/// made up of non-overlapping edits to the linted file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintFix {
+    /// A short imperative description, e.g. `Remove unused load`.
+    pub title: String,
+    /// The edits, sorted by position.
+    pub edits: Vec<LintEdit>,
+}
+
+impl LintEdit {
+    pub(crate) fn replace(span: Span, replacement: impl Into<String>) -> Self {
+        Self {
+            span,
+            replacement: replacement.into(),
+        }
+    }
+
+    pub(crate) fn insert(pos: Pos, text: impl Into<String>) -> Self {
+        Self::replace(Span::new(pos, pos), text)
+    }
+
+    /// Delete the statement at `span`. If nothing else is on its line, the whole line
+    /// goes too, otherwise a trailing comment is left where the statement was.
+    pub(crate) fn delete_statement(codemap: &CodeMap, span: Span) -> Self {
+        let source = codemap.source();
+        let begin = span.begin().get() as usize;
+        let end = span.end().get() as usize;
+        let line_begin = source[..begin].rfind('\n').map_or(0, |x| x + 1);
+        let line_end = source[end..].find('\n').map_or(source.len(), |x| end + x);
+        let before = &source[line_begin..begin];
+        let after = &source[end..line_end];
+
+        let is_blank = |x: &str| x.chars().all(|c| c == ' ' || c == '\t' || c == '\r');
+        let span = if !is_blank(before) {
+            span
+        } else if is_blank(after) {
+            let end = if line_end < source.len() {
+                line_end + 1
+            } else {
+                line_end
+            };
+            Span::new(Pos::new(line_begin as u32), Pos::new(end as u32))
+        } else if after.trim_start().starts_with('#') {
+            let comment = line_end - after.trim_start().len();
+            Span::new(span.begin(), Pos::new(comment as u32))
+        } else {
+            span
+        };
+        Self::replace(span, "")
+    }
+}
+
+impl LintFix {
+    pub(crate) fn new(title: impl Into<String>, mut edits: Vec<LintEdit>) -> Self {
+        edits.sort_by_key(|x| (x.span.begin(), x.span.end()));
+        Self {
+            title: title.into(),
+            edits,
+        }
+    }
+}
+
+/// Apply as many of the `fixes` to `source` as possible, returning the new source and the
+/// number of fixes applied. A fix which overlaps one applied earlier is skipped entirely;
+/// linting the result again will usually produce it again.
+pub fn apply_lint_fixes<'a>(
+    source: &str,
+    fixes: impl IntoIterator<Item = &'a LintFix>,
+) -> (String, usize) {
+    let mut accepted: Vec<&LintEdit> = Vec::new();
+    let mut applied = 0;
+    for fix in fixes {
+        let overlaps = fix.edits.iter().any(|edit| {
+            accepted.iter().any(|prev| {
+                edit.span.begin() < prev.span.end() && prev.span.begin() < edit.span.end()
+                    || edit.span == prev.span
+            })
+        });
+        if !overlaps {
+            accepted.extend(&fix.edits);
+            applied += 1;
+        }
+    }
+    accepted.sort_by_key(|x| (x.span.begin(), x.span.end()));
+
+    let mut res = String::with_capacity(source.len());
+    let mut pos = 0;
+    for edit in accepted {
+        res.push_str(&source[pos..edit.span.begin().get() as usize]);
+        res.push_str(&edit.replacement);
+        pos = edit.span.end().get() as usize;
+    }
+    res.push_str(&source[pos..]);
+    (res, applied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(begin: u32, end: u32) -> Span {
+        Span::new(Pos::new(begin), Pos::new(end))
+    }
+
+    fn delete(source: &str, stmt: &str) -> String {
+        let codemap = CodeMap::new("X".to_owned(), source.to_owned());
+        let begin = source.find(stmt).unwrap() as u32;
+        let fix = LintFix::new(
+            "Delete",
+            vec![LintEdit::delete_statement(
+                &codemap,
+                span(begin, begin + stmt.len() as u32),
+            )],
+        );
+        apply_lint_fixes(source, [&fix]).0
+    }
+
+    #[test]
+    fn test_delete_statement() {
+        assert_eq!("a\nc\n", delete("a\n  b\nc\n", "b"));
+        assert_eq!("a\n", delete("a\nb", "b"));
+        assert_eq!("a\n  # Why.\n", delete("a\n  b  # Why.\n", "b"));
+        assert_eq!("a; \n", delete("a; b\n", "b"));
+    }
+
+    #[test]
+    fn test_apply_skips_overlapping() {
+        let fix1 = LintFix::new("1", vec![LintEdit::replace(span(0, 2), "x")]);
+        let fix2 = LintFix::new("2", vec![LintEdit::replace(span(1, 3), "y")]);
+        let fix3 = LintFix::new(
+            "3",
+            vec![
+                LintEdit::insert(Pos::new(5), "!"),
+                LintEdit::replace(span(3, 4), ""),
+            ],
+        );
+        assert_eq!(
+            ("x2b!".to_owned(), 2),
+            apply_lint_fixes("ab2ab", [&fix1, &fix2, &fix3])
+        );
+    }
+}
@@ -15,6 +15,9 @@
  * limitations under the License.
  */
 
+use std::cmp;
+use std::collections::HashSet;
+
 use starlark_syntax::syntax::ast::AstExpr;
 use starlark_syntax::syntax::ast::AstLiteral;
 use starlark_syntax::syntax::ast::AstStmt;
@@ -27,6 +30,8 @@ use starlark_syntax::syntax::module::AstModuleFields;
 use thiserror::Error;
 
 use crate::analysis::EvalSeverity;
+use crate::analysis::fix::LintEdit;
+use crate::analysis::fix::LintFix;
 use crate::analysis::types::LintT;
 use crate::analysis::types::LintWarning;
 use crate::codemap::CodeMap;
@@ -237,22 +242,52 @@ fn reachable(codemap: &CodeMap, x: &AstStmt, res: &mut Vec<LintT<FlowIssue>>) ->
 // If you have a definition which ends with return, or a loop which ends with continue
 // that is a useless statement that just
 fn redundant(codemap: &CodeMap, x: &AstStmt, res: &mut Vec<LintT<FlowIssue>>) {
-    fn check(is_loop: bool, codemap: &CodeMap, x: &AstStmt, res: &mut Vec<LintT<FlowIssue>>) {
+    // Remove the statement, leaving `pass` if it is the only one in its block.
+    fn remove(codemap: &CodeMap, span: Span, alone: bool, title: &str) -> LintFix {
+        let edit = if alone {
+            LintEdit::replace(span, "pass")
+        } else {
+            LintEdit::delete_statement(codemap, span)
+        };
+        LintFix::new(title, vec![edit])
+    }
+
+    fn check(
+        is_loop: bool,
+        alone: bool,
+        codemap: &CodeMap,
+        x: &AstStmt,
+        res: &mut Vec<LintT<FlowIssue>>,
+    ) {
         match &**x {
-            Stmt::Continue if is_loop => {
-                res.push(LintT::new(codemap, x.span, FlowIssue::RedundantContinue))
-            }
-            Stmt::Return(None) if !is_loop => {
-                res.push(LintT::new(codemap, x.span, FlowIssue::RedundantReturn))
-            }
-            Stmt::Statements(xs) if !xs.is_empty() => {
-                check(is_loop, codemap, xs.last().unwrap(), res)
-            }
-            Stmt::If(_, x) => check(is_loop, codemap, x, res),
+            Stmt::Continue if is_loop => res.push(
+                LintT::new(codemap, x.span, FlowIssue::RedundantContinue).with_fix(remove(
+                    codemap,
+                    x.span,
+                    alone,
+                    "Remove redundant `continue`",
+                )),
+            ),
+            Stmt::Return(None) if !is_loop => res.push(
+                LintT::new(codemap, x.span, FlowIssue::RedundantReturn).with_fix(remove(
+                    codemap,
+                    x.span,
+                    alone,
+                    "Remove redundant `return`",
+                )),
+            ),
+            Stmt::Statements(xs) if !xs.is_empty() => check(
+                is_loop,
+                alone && xs.len() == 1,
+                codemap,
+                xs.last().unwrap(),
+                res,
+            ),
+            Stmt::If(_, x) => check(is_loop, true, codemap, x, res),
             Stmt::IfElse(_, x_y) => {
                 let (x, y) = &**x_y;
-                check(is_loop, codemap, x, res);
-                check(is_loop, codemap, y, res);
+                check(is_loop, true, codemap, x, res);
+                check(is_loop, true, codemap, y, res);
             }
             _ => {}
         }
@@ -260,8 +295,8 @@ fn redundant(codemap: &CodeMap, x: &AstStmt, res: &mut Vec<LintT<FlowIssue>>) {
 
     fn f(codemap: &CodeMap, x: &AstStmt, res: &mut Vec<LintT<FlowIssue>>) {
         match &**x {
-            Stmt::For(ForP { body, .. }) => check(true, codemap, body, res),
-            Stmt::Def(DefP { body, .. }) => check(false, codemap, body, res),
+            Stmt::For(ForP { body, .. }) => check(true, true, codemap, body, res),
+            Stmt::Def(DefP { body, .. }) => check(false, true, codemap, body, res),
             _ => {}
         }
         // We always want to look inside everything for other types of violation
@@ -284,26 +319,89 @@ fn misplaced_load(codemap: &CodeMap, x: &AstStmt, res: &mut Vec<LintT<FlowIssue>
         }
     }
 
+    // Names bound at the top level by a statement, i.e. not inside a `def`.
+    fn bound_names<'a>(x: &'a AstStmt, names: &mut HashSet<&'a str>) {
+        match &**x {
+            Stmt::Assign(assign) => assign.lhs.visit_lvalue(|x| {
+                names.insert(x.ident.as_str());
+            }),
+            Stmt::AssignModify(lhs, _, _) => lhs.visit_lvalue(|x| {
+                names.insert(x.ident.as_str());
+            }),
+            Stmt::For(ForP { var, body, .. }) => {
+                var.visit_lvalue(|x| {
+                    names.insert(x.ident.as_str());
+                });
+                bound_names(body, names);
+            }
+            Stmt::Def(DefP { name, .. }) => {
+                names.insert(name.ident.as_str());
+            }
+            Stmt::Load(load) => {
+                for arg in &load.args {
+                    names.insert(arg.local.ident.as_str());
+                }
+            }
+            _ => x.visit_stmt(|x| bound_names(x, names)),
+        }
+    }
+
     let mut stmts = Vec::new();
     top_statements(x, &mut stmts);
 
-    // We allow loads or documentation strings, but after that, no loads
-    let mut allow_loads = true;
+    // We allow loads or documentation strings, but after that, no loads.
+    // Misplaced loads are moved to the line after the last allowed statement,
+    // or to the first line of code if there were none. That would change which
+    // value a name refers to if it was already bound, so there is no fix then.
+    let mut last_allowed: Option<Span> = None;
+    let mut insert_at = None;
+    let mut bound = HashSet::new();
     for x in stmts {
         match &**x {
-            Stmt::Load(..) => {
-                if !allow_loads {
-                    res.push(LintT::new(codemap, x.span, FlowIssue::MisplacedLoad))
+            Stmt::Load(load) => match insert_at {
+                None => last_allowed = Some(x.span),
+                Some(pos) => {
+                    let lint = LintT::new(codemap, x.span, FlowIssue::MisplacedLoad);
+                    if load
+                        .args
+                        .iter()
+                        .any(|arg| bound.contains(arg.local.ident.as_str()))
+                    {
+                        res.push(lint);
+                    } else {
+                        let load = codemap.source_span(x.span);
+                        let fix = LintFix::new(
+                            "Move `load` to the top of the file",
+                            vec![
+                                LintEdit::insert(pos, format!("{load}\n")),
+                                LintEdit::delete_statement(codemap, x.span),
+                            ],
+                        );
+                        res.push(lint.with_fix(fix))
+                    }
                 }
-            }
+            },
             Stmt::Expression(Spanned {
                 node: Expr::Literal(AstLiteral::String(_)),
                 ..
             }) => {
                 // Still allow loads after a literal string (probably documentation)
+                if insert_at.is_none() {
+                    last_allowed = Some(x.span);
+                }
+            }
+            _ => {
+                if insert_at.is_none() {
+                    let line = codemap.find_line(x.span.begin());
+                    let line = match last_allowed {
+                        Some(span) => cmp::min(codemap.find_line(span.end()) + 1, line),
+                        None => line,
+                    };
+                    insert_at = Some(codemap.line_span(line).begin());
+                }
             }
-            _ => allow_loads = false,
         }
+        bound_names(x, &mut bound);
     }
 }
 
@@ -331,6 +429,7 @@ mod tests {
     use starlark_syntax::slice_vec_ext::SliceExt;
 
     use super::*;
+    use crate::analysis::fix::apply_lint_fixes;
     use crate::syntax::Dialect;
 
     fn module(x: &str) -> AstModule {
@@ -499,6 +598,92 @@ load("c", "b")
         assert_eq!(res.len(), 1);
     }
 
+    #[test]
+    fn test_lint_redundant_fix() {
+        let m = module(
+            r#"
+def test():
+    foo
+    return # Why.
+def test2():
+    for x in xs:
+        foo
+        continue
+    if x:
+        return
+    else:
+        foo
+"#,
+        );
+        let mut res = Vec::new();
+        redundant(m.codemap(), m.statement(), &mut res);
+        let fixes = res.iter().filter_map(|x| x.fix.as_ref());
+        assert_eq!(
+            r#"
+def test():
+    foo
+    # Why.
+def test2():
+    for x in xs:
+        foo
+    if x:
+        pass
+    else:
+        foo
+"#,
+            apply_lint_fixes(m.codemap().source(), fixes).0
+        );
+    }
+
+    #[test]
+    fn test_lint_misplaced_load_fix() {
+        let m = module(
+            r#"
+"""Docs."""
+load("a", "a")
+
+x = 1
+load("b", "b")
+y = 2
+"#,
+        );
+        let mut res = Vec::new();
+        misplaced_load(m.codemap(), m.statement(), &mut res);
+        let fixes = res.iter().filter_map(|x| x.fix.as_ref());
+        assert_eq!(
+            r#"
+"""Docs."""
+load("a", "a")
+load("b", "b")
+
+x = 1
+y = 2
+"#,
+            apply_lint_fixes(m.codemap().source(), fixes).0
+        );
+    }
+
+    #[test]
+    fn test_lint_misplaced_load_no_fix_if_bound() {
+        let m = module(
+            r#"
+load("a", "a")
+b = 1
+for c in []:
+    pass
+def d():
+    e = 1
+load("b", "b")
+load("c", "c")
+load("d", d2 = "d")
+load("e", "e")
+"#,
+        );
+        let mut res = Vec::new();
+        misplaced_load(m.codemap(), m.statement(), &mut res);
+        assert_eq!(res.map(|x| x.fix.is_some()), vec![false, false, true, true]);
+    }
+
     #[test]
     fn test_lint_no_effect() {
         let src = r#"
@@ -33,6 +33,8 @@ use starlark_syntax::syntax::module::AstModuleFields;
 use thiserror::Error;
 
 use crate::analysis::EvalSeverity;
+use crate::analysis::fix::LintEdit;
+use crate::analysis::fix::LintFix;
 use crate::analysis::types::LintT;
 use crate::analysis::types::LintWarning;
 use crate::codemap::CodeMap;
@@ -101,14 +103,21 @@ fn match_bad_type_equality(
             if (*op == BinOp::Equal || *op == BinOp::NotEqual) && is_type_call(lhs) =>
         {
             if let Some(replacement) = lookup_type(rhs, types) {
-                res.push(LintT::new(
-                    codemap,
-                    x.span,
-                    Incompatibility::IncompatibleTypeCheck(
-                        x.to_string(),
-                        format!("{}{}type({})", lhs.node, op, replacement),
-                    ),
-                ))
+                let fix = LintFix::new(
+                    format!("Compare with `type({replacement})`"),
+                    vec![LintEdit::replace(rhs.span, format!("type({replacement})"))],
+                );
+                res.push(
+                    LintT::new(
+                        codemap,
+                        x.span,
+                        Incompatibility::IncompatibleTypeCheck(
+                            x.to_string(),
+                            format!("{}{}type({})", lhs.node, op, replacement),
+                        ),
+                    )
+                    .with_fix(fix),
+                )
             }
         }
         _ => {}
@@ -213,6 +222,7 @@ mod tests {
     use starlark_syntax::slice_vec_ext::SliceExt;
 
     use super::*;
+    use crate::analysis::fix::apply_lint_fixes;
     use crate::syntax::Dialect;
 
     fn module(x: &str) -> AstModule {
@@ -240,6 +250,18 @@ def foo():
         );
     }
 
+    #[test]
+    fn test_lint_incompatible_fix() {
+        let m = module("x = type(a) == list or type(b) != int\n");
+        let mut res = Vec::new();
+        bad_type_equality(&m, &mut res);
+        let fixes = res.iter().filter_map(|x| x.fix.as_ref());
+        assert_eq!(
+            "x = type(a) == type([]) or type(b) != type(0)\n",
+            apply_lint_fixes(m.codemap().source(), fixes).0
+        );
+    }
+
     #[test]
     fn test_lint_duplicate_top_level_assign() {
         let m = module(
@@ -44,9 +44,12 @@ use starlark_syntax::syntax::ast::ForP;
 use starlark_syntax::syntax::ast::LoadArgP;
 use starlark_syntax::syntax::ast::Stmt;
 use starlark_syntax::syntax::module::AstModuleFields;
+use starlark_syntax::syntax::top_level_stmts::top_level_stmts;
 use thiserror::Error;
 
 use crate::analysis::EvalSeverity;
+use crate::analysis::fix::LintEdit;
+use crate::analysis::fix::LintFix;
 use crate::analysis::types::LintT;
 use crate::analysis::types::LintWarning;
 use crate::codemap::CodeMap;
@@ -555,7 +558,59 @@ pub(crate) fn lint(
         loop_depth: 0,
     };
     state.module(module);
-    state.warnings
+    let mut warnings = state.warnings;
+    fix_unused_loads(module, &mut warnings);
+    warnings
+}
+
+/// Attach fixes to the `UnusedLoad` warnings, removing the whole `load` if none of it is used.
+fn fix_unused_loads(module: &AstModule, warnings: &mut [LintT<NameWarning>]) {
+    let codemap = module.codemap();
+    let unused: HashMap<Span, usize> = warnings
+        .iter()
+        .enumerate()
+        .filter(|(_, x)| matches!(x.problem, NameWarning::UnusedLoad(_)))
+        .map(|(i, x)| (x.location.span, i))
+        .collect();
+
+    for stmt in top_level_stmts(module.statement()) {
+        let Stmt::Load(load) = &**stmt else {
+            continue;
+        };
+        let args: Vec<(usize, usize)> = load
+            .args
+            .iter()
+            .enumerate()
+            .filter_map(|(j, arg)| Some((j, *unused.get(&arg.local.span)?)))
+            .collect();
+        if args.len() == load.args.len() {
+            for (_, i) in args {
+                warnings[i].fix = Some(LintFix::new(
+                    "Remove unused `load`",
+                    vec![LintEdit::delete_statement(codemap, stmt.span)],
+                ));
+            }
+            continue;
+        }
+        for (j, i) in args {
+            let arg = &load.args[j];
+            let span = match (&arg.comma, j.checked_sub(1).map(|j| &load.args[j].comma)) {
+                // Remove the trailing comma and any spaces after it.
+                (Some(comma), _) => {
+                    let rest = &codemap.source()[comma.span.end().get() as usize..];
+                    let spaces = rest.len() - rest.trim_start_matches(' ').len();
+                    Span::new(arg.span().begin(), comma.span.end() + spaces as u32)
+                }
+                // The last argument, so remove the comma before it.
+                (None, Some(Some(comma))) => Span::new(comma.span.begin(), arg.span().end()),
+                (None, _) => arg.span(),
+            };
+            warnings[i].fix = Some(LintFix::new(
+                format!("Remove unused `{}`", arg.local.ident),
+                vec![LintEdit::delete_statement(codemap, span)],
+            ));
+        }
+    }
 }
 
 #[cfg(test)]
@@ -563,6 +618,7 @@ mod tests {
     use starlark_syntax::slice_vec_ext::SliceExt;
 
     use super::*;
+    use crate::analysis::fix::apply_lint_fixes;
     use crate::syntax::Dialect;
 
     impl NameWarning {
@@ -813,4 +869,35 @@ def _bar():
         let res = lint(&m, Some(&HashSet::new()));
         assert_eq!(res.len(), 0);
     }
+
+    #[test]
+    fn test_unused_load_fix() {
+        let m = module(
+            r#"
+load("a", "no1")
+load("b", "b1", "no2", "b2")
+load("c", "c1", "no3")
+load(
+    "d",
+    "no4",
+    "d1",
+)
+print(b1, b2, c1, d1)
+"#,
+        );
+        let res = lint(&m, None);
+        let fixes = res.iter().filter_map(|x| x.fix.as_ref());
+        assert_eq!(
+            r#"
+load("b", "b1", "b2")
+load("c", "c1")
+load(
+    "d",
+    "d1",
+)
+print(b1, b2, c1, d1)
+"#,
+            apply_lint_fixes(m.codemap().source(), fixes).0
+        );
+    }
 }
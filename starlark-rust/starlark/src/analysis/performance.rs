@@ -22,6 +22,8 @@ use starlark_syntax::syntax::module::AstModuleFields;
 use thiserror::Error;
 
 use crate::analysis::EvalSeverity;
+use crate::analysis::fix::LintEdit;
+use crate::analysis::fix::LintFix;
 use crate::analysis::types::LintT;
 use crate::analysis::types::LintWarning;
 use crate::codemap::CodeMap;
@@ -60,11 +62,24 @@ fn match_dict_copy(codemap: &CodeMap, x: &AstExpr, res: &mut Vec<LintT<Performan
     match &**x {
         Expr::Call(fun, args) if args.args.len() == 1 => match (&***fun, &*args.args[0]) {
             (Expr::Identifier(f), Argument::KwArgs(arg)) if f.node.ident == "dict" => {
-                res.push(LintT::new(
-                    codemap,
-                    x.span,
-                    Performance::DictWithoutStarStar(x.to_string(), format!("dict({})", arg.node)),
-                ))
+                let fix = LintFix::new(
+                    "Remove `**`",
+                    vec![LintEdit::replace(
+                        args.args[0].span,
+                        codemap.source_span(arg.span),
+                    )],
+                );
+                res.push(
+                    LintT::new(
+                        codemap,
+                        x.span,
+                        Performance::DictWithoutStarStar(
+                            x.to_string(),
+                            format!("dict({})", arg.node),
+                        ),
+                    )
+                    .with_fix(fix),
+                )
             }
             _ => {}
         },
@@ -133,6 +148,7 @@ mod tests {
     use starlark_syntax::slice_vec_ext::SliceExt;
 
     use super::*;
+    use crate::analysis::fix::apply_lint_fixes;
     use crate::syntax::Dialect;
 
     fn module(x: &str) -> AstModule {
@@ -159,6 +175,18 @@ def foo(extra, **kwargs):
         );
     }
 
+    #[test]
+    fn test_lint_fixes_dict_issue() {
+        let m = module("x = dict(** kwargs)\n");
+        let mut res = Vec::new();
+        check_call_expr(&m, &mut res);
+        let fixes = res.iter().filter_map(|x| x.fix.as_ref());
+        assert_eq!(
+            "x = dict(kwargs)\n",
+            apply_lint_fixes(m.codemap().source(), fixes).0
+        );
+    }
+
     #[test]
     fn test_lint_matches_any_function() {
         let mut res = Vec::new();
@@ -22,6 +22,7 @@ use std::path::Path;
 use dupe::Dupe;
 use serde::Serialize;
 
+use crate::analysis::fix::LintFix;
 use crate::codemap::CodeMap;
 use crate::codemap::FileSpan;
 use crate::codemap::ResolvedSpan;
@@ -39,6 +40,7 @@ pub(crate) struct LintT<T> {
     pub location: FileSpan,
     pub original: String,
     pub problem: T,
+    pub fix: Option<LintFix>,
 }
 
 /// A lint produced by `AstModule::lint`.
@@ -55,6 +57,8 @@ pub struct Lint {
     pub problem: String,
     /// The source code at [`location`](Lint::location).
     pub original: String,
+    /// A mechanical fix for the problem, if there is one.
+    pub fix: Option<LintFix>,
 }
 
 impl Display for Lint {
@@ -76,9 +80,16 @@ impl<T: LintWarning> LintT<T> {
             original: location.file.source_span(span).to_owned(),
             location,
             problem,
+            fix: None,
         }
     }
 
+    /// Attach a fix, whose edits are in the same file as the lint.
+    pub(crate) fn with_fix(mut self, fix: LintFix) -> Self {
+        self.fix = Some(fix);
+        self
+    }
+
     pub(crate) fn erase(self) -> Lint {
         Lint {
             location: self.location,
@@ -86,6 +97,7 @@ impl<T: LintWarning> LintT<T> {
             severity: self.problem.severity(),
             problem: self.problem.to_string(),
             original: self.original,
+            fix: self.fix,
         }
     }
 }
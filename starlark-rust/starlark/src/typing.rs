@@ -43,6 +43,7 @@ pub(crate) mod structs;
 pub(crate) mod tuple;
 pub(crate) mod ty;
 pub(crate) mod typecheck;
+pub(crate) mod typed_dict;
 pub(crate) mod user;
 
 pub mod macro_support;
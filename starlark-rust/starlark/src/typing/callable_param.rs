@@ -210,6 +210,24 @@ impl ParamSpec {
         &self.params
     }
 
+    /// Type of the parameter the `index`-th positional argument is bound to.
+    pub(crate) fn pos_arg_param_ty(&self, index: usize) -> Option<&Ty> {
+        self.params
+            .iter()
+            .filter(|p| p.allows_pos())
+            .enumerate()
+            .find(|(i, p)| *i == index || p.mode == ParamMode::Args)
+            .map(|(_, p)| &p.ty)
+    }
+
+    /// Type of the parameter the named argument `name` is bound to.
+    pub(crate) fn named_arg_param_ty(&self, name: &str) -> Option<&Ty> {
+        self.params
+            .iter()
+            .find(|p| p.name() == Some(name) || p.mode == ParamMode::Kwargs)
+            .map(|p| &p.ty)
+    }
+
     /// Create a new parameter specification from different parameter kinds in order.
     pub fn new_parts(
         pos_only: impl IntoIterator<Item = (ParamIsRequired, Ty)>,
@@ -18,6 +18,7 @@
 use std::cell::RefCell;
 use std::fmt::Debug;
 
+use dupe::Dupe;
 use starlark_map::unordered_map::UnorderedMap;
 use starlark_syntax::slice_vec_ext::SliceExt;
 use starlark_syntax::syntax::ast::AssignOp;
@@ -51,8 +52,15 @@ use crate::typing::oracle::traits::TypingBinOp;
 use crate::typing::oracle::traits::TypingUnOp;
 use crate::typing::ty::Approximation;
 use crate::typing::ty::Ty;
+use crate::typing::typed_dict::TyTypedDict;
 use crate::values::types::bytes::value::StarlarkBytes;
 
+/// How an argument is passed, to find the parameter it is bound to.
+enum ArgBinding<'a> {
+    Pos(usize),
+    Named(&'a str),
+}
+
 pub(crate) struct TypingContext<'a> {
     pub(crate) oracle: TypingOracleCtx<'a>,
     // We'd prefer this to be a &mut self,
@@ -146,8 +154,20 @@ impl TypingContext<'_> {
             }
         }
 
+        let key = match &index.node {
+            ExprP::Literal(AstLiteral::String(key)) => Some(key.node.as_str()),
+            _ => None,
+        };
         let index = self.expression_type_spanned(index)?;
-        self.result_to_ty_with_internal_error(self.oracle.expr_index(span, array_ty, index))
+        match key {
+            Some(key) => self.result_to_ty_with_internal_error(
+                self.oracle
+                    .expr_index_str_literal(span, array_ty, index, key),
+            ),
+            None => {
+                self.result_to_ty_with_internal_error(self.oracle.expr_index(span, array_ty, index))
+            }
+        }
     }
 
     fn expression_un_op(
@@ -299,6 +319,96 @@ impl TypingContext<'_> {
         self.result_to_ty_with_internal_error(self.oracle.expr_bin_op(span, lhs, op, rhs))
     }
 
+    /// Type of a dict literal, and the type of each entry if every key is a string literal.
+    fn dict_literal_type<'x>(
+        &self,
+        xs: &'x [(CstExpr, CstExpr)],
+    ) -> Result<(Ty, Option<Vec<(&'x str, Ty)>>), InternalError> {
+        let entries =
+            xs.try_map(|(k, v)| Ok((self.expression_type(k)?, self.expression_type(v)?)))?;
+        let fields = xs
+            .iter()
+            .zip(&entries)
+            .map(|((k, _), (_, v))| match &k.node {
+                ExprP::Literal(AstLiteral::String(k)) => Some((k.node.as_str(), v.clone())),
+                _ => None,
+            })
+            .collect();
+        let (ks, vs) = entries.into_iter().unzip();
+        Ok((Ty::dict(Ty::unions(ks), Ty::unions(vs)), fields))
+    }
+
+    /// Type of an expression, and the entries of the expression if it is a dict literal
+    /// with string literal keys.
+    fn expression_type_with_dict_literal<'x>(
+        &self,
+        x: &'x CstExpr,
+    ) -> Result<(Ty, Option<Vec<(&'x str, Ty)>>), InternalError> {
+        match &**x {
+            ExprP::Dict(xs) => self.dict_literal_type(xs),
+            _ => Ok((self.expression_type(x)?, None)),
+        }
+    }
+
+    /// Check an expression against the type it is required to have.
+    ///
+    /// Unlike [`validate_type`](Self::validate_type), dict literals are checked key by key
+    /// against `typing.TypedDict` types, which otherwise only see `dict[str, V]`.
+    pub(crate) fn validate_expr_type(
+        &self,
+        span: Span,
+        x: &CstExpr,
+        require: &Ty,
+    ) -> Result<(), InternalError> {
+        let (ty, fields) = self.expression_type_with_dict_literal(x)?;
+        self.validate_type(Spanned { span, node: &ty }, require)?;
+        if let Some(fields) = fields {
+            self.validate_dict_literal(span, &ty, &fields, require)?;
+        }
+        Ok(())
+    }
+
+    fn validate_dict_literal(
+        &self,
+        span: Span,
+        ty: &Ty,
+        fields: &[(&str, Ty)],
+        require: &Ty,
+    ) -> Result<(), InternalError> {
+        if !self.oracle.intersects(ty, require)? {
+            // Already reported by `validate_type`.
+            return Ok(());
+        }
+        let mut mismatch = None;
+        for basic in require.iter_union() {
+            let typed_dict = match basic {
+                TyBasic::Custom(c) => c.0.as_any().downcast_ref::<TyTypedDict>(),
+                _ => None,
+            };
+            match typed_dict {
+                Some(typed_dict) => match typed_dict.check_literal(fields, &self.oracle)? {
+                    None => return Ok(()),
+                    Some(reason) => {
+                        mismatch.get_or_insert(reason);
+                    }
+                },
+                None => {
+                    if self.oracle.intersects(ty, &Ty::basic(basic.dupe()))? {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+        if let Some(reason) = mismatch {
+            self.errors.borrow_mut().push(TypingError::msg(
+                format!("Dict literal does not match `{require}`: {reason}"),
+                span,
+                self.oracle.codemap,
+            ));
+        }
+        Ok(())
+    }
+
     fn expr_call(
         &self,
         span: Span,
@@ -315,11 +425,18 @@ impl TypingContext<'_> {
             star_star,
         } = args;
 
+        // Dict literal arguments, checked against their parameters once the call is validated.
+        let mut dict_literals = Vec::new();
+
         let mut pos_ty: Vec<Spanned<Ty>> = Vec::new();
-        for pos in pos {
+        for (i, pos) in pos.into_iter().enumerate() {
+            let (ty, fields) = self.expression_type_with_dict_literal(pos.node.expr())?;
+            if let Some(fields) = fields {
+                dict_literals.push((ArgBinding::Pos(i), pos.span, ty.clone(), fields));
+            }
             pos_ty.push(Spanned {
                 span: pos.span,
-                node: self.expression_type(pos.node.expr())?,
+                node: ty,
             });
         }
 
@@ -332,9 +449,13 @@ impl TypingContext<'_> {
                     self.oracle.codemap,
                 ));
             };
+            let (ty, fields) = self.expression_type_with_dict_literal(named.node.expr())?;
+            if let Some(fields) = fields {
+                dict_literals.push((ArgBinding::Named(name), named.span, ty.clone(), fields));
+            }
             named_ty.push(Spanned {
                 span: named.span,
-                node: (name, self.expression_type(named.node.expr())?),
+                node: (name, ty),
             });
         }
 
@@ -364,7 +485,26 @@ impl TypingContext<'_> {
         let f_ty = self.expression_type(f)?;
         // If we can't resolve the types of the arguments, we can't validate the call,
         // but we still know the type of the result since the args don't impact that
-        self.validate_call(&f_ty, &args_ty, span)
+        let result = self.validate_call(&f_ty, &args_ty, span)?;
+
+        let callable = match f_ty.iter_union() {
+            [TyBasic::Callable(c)] => Some(c.dupe()),
+            [TyBasic::Custom(c)] => c.0.as_callable_dyn(),
+            _ => None,
+        };
+        if let Some(callable) = callable {
+            for (binding, span, ty, fields) in dict_literals {
+                let param_ty = match binding {
+                    ArgBinding::Pos(i) => callable.params().pos_arg_param_ty(i),
+                    ArgBinding::Named(name) => callable.params().named_arg_param_ty(name),
+                };
+                if let Some(param_ty) = param_ty {
+                    self.validate_dict_literal(span, &ty, &fields, param_ty)?;
+                }
+            }
+        }
+
+        Ok(result)
     }
 
     fn expr_slice(
@@ -466,13 +606,7 @@ impl TypingContext<'_> {
                 let ts = xs.try_map(|x| self.expression_type(x))?;
                 Ok(Ty::list(Ty::unions(ts)))
             }
-            ExprP::Dict(xs) => {
-                let (ks, vs) = xs
-                    .try_map(|(k, v)| Ok((self.expression_type(k)?, self.expression_type(v)?)))?
-                    .into_iter()
-                    .unzip();
-                Ok(Ty::dict(Ty::unions(ks), Ty::unions(vs)))
-            }
+            ExprP::Dict(xs) => Ok(self.dict_literal_type(xs)?.0),
             ExprP::ListComprehension(a, b, c) => {
                 self.check_comprehension(b, c)?;
                 Ok(Ty::list(self.expression_type(a)?))
@@ -76,6 +76,13 @@ pub trait TyCustomImpl:
     fn as_function(&self) -> Option<&TyFunction> {
         None
     }
+    /// The `dict[K, V]` type this type refines, if it is a dict.
+    ///
+    /// Methods, and intersection with types other than this custom type,
+    /// are typechecked as for that dict type.
+    fn as_dict(&self) -> Option<TyBasic> {
+        None
+    }
     /// Result type of `self <op> rhs`.
     fn bin_op(
         &self,
@@ -99,6 +106,12 @@ pub trait TyCustomImpl:
         let _unused = (item, ctx);
         Err(TypingNoContextOrInternalError::Typing)
     }
+    /// Result type of `self["key"]` where the key is a string literal,
+    /// or `None` if this type does not know its keys.
+    fn index_str_literal(&self, key: &str) -> Option<Result<Ty, TypingNoContextError>> {
+        let _unused = key;
+        None
+    }
     /// Type of `self.attr`.
     fn attribute(&self, attr: &str) -> Result<Ty, TypingNoContextError>;
     /// Merge `x | other` into a single custom type, or fail.
@@ -110,6 +123,12 @@ pub trait TyCustomImpl:
         let _ignore = (x, y);
         true
     }
+    /// Like [`intersects`](Self::intersects), for types which contain other types
+    /// and need the oracle to intersect them.
+    fn intersects_in_ctx(x: &Self, y: &Self, ctx: &TypingOracleCtx) -> Result<bool, InternalError> {
+        let _unused = ctx;
+        Ok(Self::intersects(x, y))
+    }
     /// Additional types that this type intersects with.
     fn intersects_with(&self, _other: &TyBasic) -> bool {
         false
@@ -138,12 +157,14 @@ pub(crate) trait TyCustomDyn:
     fn is_intersects_with_dyn(&self, other: &TyBasic) -> bool;
     fn as_callable_dyn(&self) -> Option<TyCallable>;
     fn as_function_dyn(&self) -> Option<&TyFunction>;
+    fn as_dict_dyn(&self) -> Option<TyBasic>;
     fn iter_item_dyn(&self) -> Result<Ty, TypingNoContextError>;
     fn index_dyn(
         &self,
         index: &TyBasic,
         ctx: &TypingOracleCtx,
     ) -> Result<Ty, TypingNoContextOrInternalError>;
+    fn index_str_literal_dyn(&self, key: &str) -> Option<Result<Ty, TypingNoContextError>>;
     fn attribute_dyn(&self, attr: &str) -> Result<Ty, TypingNoContextError>;
     fn bin_op_dyn(
         &self,
@@ -155,7 +176,11 @@ pub(crate) trait TyCustomDyn:
         self: Arc<Self>,
         other: Arc<dyn TyCustomDyn>,
     ) -> Result<Arc<dyn TyCustomDyn>, (Arc<dyn TyCustomDyn>, Arc<dyn TyCustomDyn>)>;
-    fn intersects_dyn(&self, other: &dyn TyCustomDyn) -> bool;
+    fn intersects_dyn(
+        &self,
+        other: &dyn TyCustomDyn,
+        ctx: &TypingOracleCtx,
+    ) -> Result<bool, InternalError>;
 
     fn matcher_with_type_compiled_factory_dyn<'v>(
         &self,
@@ -213,6 +238,10 @@ impl<T: TyCustomImpl> TyCustomDyn for T {
         self.as_function()
     }
 
+    fn as_dict_dyn(&self) -> Option<TyBasic> {
+        self.as_dict()
+    }
+
     fn attribute_dyn(&self, attr: &str) -> Result<Ty, TypingNoContextError> {
         self.attribute(attr)
     }
@@ -229,6 +258,10 @@ impl<T: TyCustomImpl> TyCustomDyn for T {
         self.index(index, ctx)
     }
 
+    fn index_str_literal_dyn(&self, key: &str) -> Option<Result<Ty, TypingNoContextError>> {
+        self.index_str_literal(key)
+    }
+
     fn bin_op_dyn(
         &self,
         bin_op: TypingBinOp,
@@ -252,11 +285,15 @@ impl<T: TyCustomImpl> TyCustomDyn for T {
         }
     }
 
-    fn intersects_dyn(&self, other: &dyn TyCustomDyn) -> bool {
+    fn intersects_dyn(
+        &self,
+        other: &dyn TyCustomDyn,
+        ctx: &TypingOracleCtx,
+    ) -> Result<bool, InternalError> {
         if let Some(other) = other.as_any().downcast_ref::<Self>() {
-            T::intersects(self, other)
+            T::intersects_in_ctx(self, other, ctx)
         } else {
-            false
+            Ok(false)
         }
     }
 
@@ -290,8 +327,12 @@ impl TyCustom {
             .map_err(|(x, y)| (TyCustom(x), TyCustom(y)))
     }
 
-    pub(crate) fn intersects(x: &TyCustom, y: &TyCustom) -> bool {
-        x.0.intersects_dyn(&*y.0)
+    pub(crate) fn intersects(
+        x: &TyCustom,
+        y: &TyCustom,
+        ctx: TypingOracleCtx,
+    ) -> Result<bool, InternalError> {
+        x.0.intersects_dyn(&*y.0, &ctx)
     }
 
     pub(crate) fn intersects_with(
@@ -303,12 +344,15 @@ impl TyCustom {
             return Ok(true);
         }
         match other {
-            TyBasic::Custom(other) => Ok(Self::intersects(self, other)),
+            TyBasic::Custom(other) => Self::intersects(self, other, ctx),
             TyBasic::Callable(c) => match self.0.as_callable_dyn() {
                 Some(this) => ctx.callables_intersect(&this, c),
                 _ => Ok(false),
             },
-            _ => Ok(false),
+            other => match self.0.as_dict_dyn() {
+                Some(this) => ctx.intersects_basic(&this, other),
+                None => Ok(false),
+            },
         }
     }
 
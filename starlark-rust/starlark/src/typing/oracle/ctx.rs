@@ -63,6 +63,8 @@ enum TypingOracleCtxError {
     CallArgumentsIncompatible { fun: Ty },
     #[error("Type `{ty}` does not have [] operator or [] cannot accept `{index}`")]
     MissingIndexOperator { ty: Ty, index: Ty },
+    #[error("Type `{ty}` does not have key `{key}`")]
+    MissingKey { ty: Ty, key: String },
     #[error("Type `{ty}` does not have [::] operator")]
     MissingSliceOperator { ty: Ty },
     #[error("The attribute `{attr}` is not available on the type `{ty}`")]
@@ -482,6 +484,59 @@ impl<'a> TypingOracleCtx<'a> {
         }
     }
 
+    /// `array["key"]`: like [`expr_index`](Self::expr_index),
+    /// but types which know their keys check the key itself.
+    pub(crate) fn expr_index_str_literal(
+        &self,
+        span: Span,
+        array: Ty,
+        index: Spanned<Ty>,
+        key: &str,
+    ) -> Result<Ty, TypingOrInternalError> {
+        if array.is_any() || array.is_never() {
+            return Ok(array);
+        }
+
+        let mut good = Vec::new();
+        let mut other = Vec::new();
+        for basic in array.iter_union() {
+            let literal = match basic {
+                TyBasic::Custom(c) => c.0.index_str_literal_dyn(key),
+                _ => None,
+            };
+            match literal {
+                Some(Ok(ty)) => good.push(ty),
+                Some(Err(TypingNoContextError)) => {}
+                None => other.push(Ty::basic(basic.dupe())),
+            }
+        }
+
+        if !other.is_empty() {
+            match self.expr_index(span, Ty::unions(other), index) {
+                Ok(ty) => good.push(ty),
+                Err(TypingOrInternalError::Typing(e)) if good.is_empty() => {
+                    return Err(TypingOrInternalError::Typing(e));
+                }
+                Err(TypingOrInternalError::Typing(_)) => {}
+                Err(TypingOrInternalError::Internal(e)) => {
+                    return Err(TypingOrInternalError::Internal(e));
+                }
+            }
+        }
+
+        if good.is_empty() {
+            Err(self.mk_error_as_maybe_internal(
+                span,
+                TypingOracleCtxError::MissingKey {
+                    ty: array,
+                    key: key.to_owned(),
+                },
+            ))
+        } else {
+            Ok(Ty::unions(good))
+        }
+    }
+
     fn expr_slice_basic(&self, array: &TyBasic) -> Result<Ty, TypingNoContextError> {
         if let TyBasic::StarlarkValue(v) = array {
             v.slice()
@@ -551,7 +606,10 @@ impl<'a> TypingOracleCtx<'a> {
                     attr => TyStarlarkValue::new::<MutableDict>().attr(attr),
                 }
             }
-            TyBasic::Custom(custom) => custom.0.attribute_dyn(attr),
+            TyBasic::Custom(custom) => match custom.0.as_dict_dyn() {
+                Some(dict) => self.expr_dot_basic(&dict, attr),
+                None => custom.0.attribute_dyn(attr),
+            },
             //TODO(romanp) add match on attr similar to Dict
             TyBasic::Set(_) => TyStarlarkValue::new::<MutableSet>().attr(attr),
         }
@@ -108,17 +108,16 @@ pub(crate) fn solve_bindings(
         ctx.expression_type(x)?;
     }
     for (span, e, require) in &bindings.check_type {
-        let ty = match e {
-            None => Ty::none(),
-            Some(x) => ctx.expression_type(x)?,
-        };
-        ctx.validate_type(
-            Spanned {
-                node: &ty,
-                span: *span,
-            },
-            require,
-        )?;
+        match e {
+            None => ctx.validate_type(
+                Spanned {
+                    node: &Ty::none(),
+                    span: *span,
+                },
+                require,
+            )?,
+            Some(x) => ctx.validate_expr_type(*span, x, require)?,
+        }
     }
     Ok((
         ctx.errors.into_inner(),
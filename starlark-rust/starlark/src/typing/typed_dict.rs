@@ -0,0 +1,252 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::fmt;
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+use allocative::Allocative;
+use dupe::Dupe;
+use pagable::Pagable;
+use pagable::pagable_typetag;
+use starlark_derive::type_matcher;
+use starlark_map::sorted_map::SortedMap;
+
+use crate as starlark;
+use crate::typing::Ty;
+use crate::typing::TyBasic;
+use crate::typing::TypingBinOp;
+use crate::typing::TypingOracleCtx;
+use crate::typing::custom::TyCustomDyn;
+use crate::typing::custom::TyCustomImpl;
+use crate::typing::error::InternalError;
+use crate::typing::error::TypingNoContextError;
+use crate::typing::error::TypingNoContextOrInternalError;
+use crate::util::arc_str::ArcStr;
+use crate::values::Value;
+use crate::values::dict::DictRef;
+use crate::values::typing::type_compiled::alloc::TypeMatcherAlloc;
+use crate::values::typing::type_compiled::matcher::TypeMatcher;
+use crate::values::typing::type_compiled::matcher::TypeMatcherBox;
+use crate::values::typing::type_compiled::matcher::TypeMatcherBoxAlloc;
+use crate::values::typing::type_compiled::matcher::TypeMatcherDyn;
+
+/// Value type of a key of a [`TyTypedDict`].
+#[derive(
+    Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Allocative, Pagable
+)]
+pub(crate) struct TyTypedDictField {
+    pub(crate) ty: Ty,
+    /// [`false`] if the key may be absent.
+    pub(crate) required: bool,
+}
+
+#[derive(Clone, Allocative, Debug, Pagable)]
+struct TypedDictFieldMatcher {
+    matcher: TypeMatcherBox,
+    required: bool,
+}
+
+#[derive(Clone, Allocative, Debug, Pagable)]
+#[pagable_typetag(TypeMatcherDyn)]
+struct TypedDictMatcher {
+    fields: SortedMap<ArcStr, TypedDictFieldMatcher>,
+    required_count: usize,
+}
+
+#[type_matcher]
+impl TypeMatcher for TypedDictMatcher {
+    fn matches(&self, value: Value) -> bool {
+        let Some(dict) = DictRef::from_value(value) else {
+            return false;
+        };
+        let mut required_count = 0;
+        for (k, v) in dict.iter() {
+            let Some(field) = k.unpack_str().and_then(|k| self.fields.get(k)) else {
+                return false;
+            };
+            if !field.matcher.0.matches_dyn(v) {
+                return false;
+            }
+            if field.required {
+                required_count += 1;
+            }
+        }
+        required_count == self.required_count
+    }
+}
+
+/// Dict with a fixed set of string keys, each with its own value type,
+/// created with `typing.TypedDict`.
+#[derive(
+    Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Allocative, Pagable
+)]
+#[pagable_typetag(TyCustomDyn)]
+pub(crate) struct TyTypedDict {
+    pub(crate) fields: SortedMap<ArcStr, TyTypedDictField>,
+}
+
+impl TyTypedDict {
+    fn value_ty(&self) -> Ty {
+        Ty::unions(self.fields.values().map(|f| f.ty.dupe()).collect())
+    }
+
+    fn required_keys(&self) -> impl Iterator<Item = &ArcStr> {
+        self.fields
+            .iter()
+            .filter(|(_, f)| f.required)
+            .map(|(k, _)| k)
+    }
+
+    /// Check the entries of a dict literal whose keys are all string literals,
+    /// returning why the literal does not match this type.
+    pub(crate) fn check_literal(
+        &self,
+        entries: &[(&str, Ty)],
+        ctx: &TypingOracleCtx,
+    ) -> Result<Option<String>, InternalError> {
+        for (key, ty) in entries {
+            let Some(field) = self.fields.get(*key) else {
+                return Ok(Some(format!("unknown key `{key}`")));
+            };
+            if !ctx.intersects(ty, &field.ty)? {
+                return Ok(Some(format!(
+                    "key `{key}` expects `{}`, got `{ty}`",
+                    field.ty
+                )));
+            }
+        }
+        if let Some(key) = self
+            .required_keys()
+            .find(|k| !entries.iter().any(|(key, _)| *key == &***k))
+        {
+            return Ok(Some(format!("missing required key `{}`", &**key)));
+        }
+        Ok(None)
+    }
+}
+
+impl TyCustomImpl for TyTypedDict {
+    fn as_name(&self) -> Option<&str> {
+        Some("dict")
+    }
+
+    fn as_dict(&self) -> Option<TyBasic> {
+        Some(TyBasic::dict(Ty::string(), self.value_ty()))
+    }
+
+    fn bin_op(
+        &self,
+        bin_op: TypingBinOp,
+        rhs: &TyBasic,
+        ctx: &TypingOracleCtx,
+    ) -> Result<Ty, TypingNoContextOrInternalError> {
+        match bin_op {
+            TypingBinOp::In => {
+                if ctx.intersects_basic(rhs, &TyBasic::string())? {
+                    Ok(Ty::bool())
+                } else {
+                    Err(TypingNoContextOrInternalError::Typing)
+                }
+            }
+            _ => Err(TypingNoContextOrInternalError::Typing),
+        }
+    }
+
+    fn iter_item(&self) -> Result<Ty, TypingNoContextError> {
+        Ok(Ty::string())
+    }
+
+    fn index(
+        &self,
+        item: &TyBasic,
+        ctx: &TypingOracleCtx,
+    ) -> Result<Ty, TypingNoContextOrInternalError> {
+        if ctx.intersects_basic(item, &TyBasic::string())? {
+            Ok(self.value_ty())
+        } else {
+            Err(TypingNoContextOrInternalError::Typing)
+        }
+    }
+
+    fn index_str_literal(&self, key: &str) -> Option<Result<Ty, TypingNoContextError>> {
+        Some(match self.fields.get(key) {
+            Some(field) => Ok(field.ty.dupe()),
+            None => Err(TypingNoContextError),
+        })
+    }
+
+    fn attribute(&self, _attr: &str) -> Result<Ty, TypingNoContextError> {
+        // Methods are typechecked as methods of `as_dict()`.
+        Err(TypingNoContextError)
+    }
+
+    fn intersects_in_ctx(x: &Self, y: &Self, ctx: &TypingOracleCtx) -> Result<bool, InternalError> {
+        if !(x.required_keys().all(|k| y.fields.contains_key(k))
+            && y.required_keys().all(|k| x.fields.contains_key(k)))
+        {
+            return Ok(false);
+        }
+        // A key required by either type is present, so its value must match both types.
+        for (k, x_field) in x.fields.iter() {
+            if let Some(y_field) = y.fields.get(k) {
+                if (x_field.required || y_field.required)
+                    && !ctx.intersects(&x_field.ty, &y_field.ty)?
+                {
+                    return Ok(false);
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    fn matcher<T: TypeMatcherAlloc>(&self, factory: T) -> T::Result {
+        factory.alloc(TypedDictMatcher {
+            fields: self
+                .fields
+                .iter()
+                .map(|(k, f)| {
+                    (
+                        k.dupe(),
+                        TypedDictFieldMatcher {
+                            matcher: TypeMatcherBoxAlloc.ty(&f.ty),
+                            required: f.required,
+                        },
+                    )
+                })
+                .collect(),
+            required_count: self.required_keys().count(),
+        })
+    }
+}
+
+impl Display for TyTypedDict {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let fields = |required: bool| {
+            self.fields
+                .iter()
+                .filter(move |(_, field)| field.required == required)
+                .map(|(k, field)| format!("{:?}: {}", &**k, field.ty))
+        };
+        write!(f, "typing.TypedDict(")?;
+        display_container::fmt_container(f, "{", "}", fields(true))?;
+        if self.fields.values().any(|field| !field.required) {
+            display_container::fmt_container(f, ", optional = {", "}", fields(false))?;
+        }
+        write!(f, ")")
+    }
+}
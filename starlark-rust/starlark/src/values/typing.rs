@@ -26,6 +26,7 @@ pub(crate) mod never;
 pub(crate) mod ty;
 pub(crate) mod type_compiled;
 pub(crate) mod type_type;
+pub(crate) mod typed_dict;
 
 pub use crate::values::types::type_instance_id::StarlarkTypeIdDomain;
 pub use crate::values::types::type_instance_id::TypeIdDomain;
@@ -0,0 +1,236 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Implementation of `typing.TypedDict` function.
+
+use dupe::Dupe;
+use starlark_derive::starlark_module;
+
+use crate as starlark;
+use crate::collections::SmallMap;
+use crate::environment::GlobalsBuilder;
+use crate::eval::Evaluator;
+use crate::typing::Ty;
+use crate::typing::typed_dict::TyTypedDict;
+use crate::typing::typed_dict::TyTypedDictField;
+use crate::util::arc_str::ArcStr;
+use crate::values::Value;
+use crate::values::dict::UnpackDictEntries;
+use crate::values::typing::type_compiled::compiled::TypeCompiled;
+
+#[derive(Debug, thiserror::Error)]
+enum TypedDictError {
+    #[error("Key `{0}` is declared both required and optional in `typing.TypedDict`")]
+    DuplicateKey(String),
+}
+
+#[starlark_module]
+pub(crate) fn register_typed_dict(globals: &mut GlobalsBuilder) {
+    /// Type of a dict with a fixed set of string keys, each with its own value type.
+    ///
+    /// For example:
+    ///
+    /// ```python
+    /// Dep = typing.TypedDict({"name": str, "version": int}, optional = {"features": list[str]})
+    ///
+    /// def dep_name(dep: Dep) -> str:
+    ///     return dep["name"]
+    /// ```
+    ///
+    /// Values of this type are plain dicts, so existing dict-taking APIs can be typed without
+    /// changing their callers. A dict matches the type if it has all the required keys,
+    /// no keys other than the declared ones, and every value matches the type of its key.
+    ///
+    /// Accessing a key with a string literal (e.g. `dep["nme"]`) is checked by the typechecker,
+    /// and the result has the declared type of that key.
+    fn TypedDict<'v>(
+        #[starlark(require = pos)] required: UnpackDictEntries<String, Value<'v>>,
+        #[starlark(require = named, default = UnpackDictEntries::default())]
+        optional: UnpackDictEntries<String, Value<'v>>,
+        eval: &mut Evaluator<'v, '_, '_>,
+    ) -> anyhow::Result<TypeCompiled<Value<'v>>> {
+        let mut fields = SmallMap::with_capacity(required.entries.len() + optional.entries.len());
+        for (is_required, entries) in [(true, required.entries), (false, optional.entries)] {
+            for (key, ty) in entries {
+                let ty = TypeCompiled::new(ty, eval.heap())?.as_ty().dupe();
+                let field = TyTypedDictField {
+                    ty,
+                    required: is_required,
+                };
+                if fields.contains_key(&key) {
+                    return Err(TypedDictError::DuplicateKey(key).into());
+                }
+                fields.insert(key, field);
+            }
+        }
+        let ty = Ty::custom(TyTypedDict {
+            fields: fields
+                .into_iter()
+                .map(|(key, field)| (ArcStr::from(key.as_str()), field))
+                .collect(),
+        });
+        Ok(TypeCompiled::from_ty(&ty, eval.heap()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::assert;
+
+    #[test]
+    fn test_typed_dict_matches() {
+        assert::pass(
+            r#"
+Dep = typing.TypedDict({"name": str, "version": int}, optional = {"features": list[str]})
+assert_true(isinstance({"name": "a", "version": 1}, Dep))
+assert_true(isinstance({"name": "a", "version": 1, "features": ["x"]}, Dep))
+assert_false(isinstance({"name": "a"}, Dep))
+assert_false(isinstance({"name": "a", "version": "1"}, Dep))
+assert_false(isinstance({"name": "a", "version": 1, "other": 2}, Dep))
+assert_false(isinstance(["name"], Dep))
+"#,
+        );
+    }
+
+    #[test]
+    fn test_typed_dict_key_access() {
+        assert::pass(
+            r#"
+Dep = typing.TypedDict({"name": str}, optional = {"version": int})
+
+def dep_name(dep: Dep) -> str:
+    return dep["name"]
+
+def dep_version(dep: Dep) -> int:
+    return dep.get("version", 0)
+
+assert_eq(dep_name({"name": "a"}), "a")
+assert_eq(dep_version({"name": "a"}), 0)
+"#,
+        );
+    }
+
+    #[test]
+    fn test_typed_dict_unknown_key_compile_time() {
+        assert::fail(
+            r#"
+Dep = typing.TypedDict({"name": str})
+
+def test(dep: Dep):
+    return dep["nme"]
+"#,
+            "does not have key `nme`",
+        );
+    }
+
+    #[test]
+    fn test_typed_dict_key_type_compile_time() {
+        assert::fail(
+            r#"
+Dep = typing.TypedDict({"name": str, "version": int})
+
+def test(dep: Dep) -> str:
+    return dep["version"]
+"#,
+            "Expected type `str` but got `int`",
+        );
+    }
+
+    #[test]
+    fn test_typed_dict_runtime_mismatch() {
+        assert::fail(
+            r#"
+Dep = typing.TypedDict({"name": str})
+
+def dep_name(dep: Dep) -> str:
+    return dep["name"]
+
+dep = {"name": "a", "version": 1}
+dep_name(dep)
+"#,
+            "does not match the type annotation",
+        );
+    }
+
+    #[test]
+    fn test_typed_dict_literal_missing_key() {
+        assert::fail(
+            r#"
+Dep = typing.TypedDict({"name": str, "version": int})
+
+def dep_name(dep: Dep) -> str:
+    return dep["name"]
+
+def test():
+    dep_name({"name": "a"})
+"#,
+            "missing required key `version`",
+        );
+    }
+
+    #[test]
+    fn test_typed_dict_literal_unknown_key() {
+        assert::fail(
+            r#"
+Dep = typing.TypedDict({"name": str})
+
+def test() -> Dep:
+    return {"name": "a", "nme": "b"}
+"#,
+            "unknown key `nme`",
+        );
+    }
+
+    #[test]
+    fn test_typed_dict_literal_wrong_value_type() {
+        assert::fail(
+            r#"
+Dep = typing.TypedDict({"name": str, "version": int})
+
+def test():
+    dep: Dep = {"name": "a", "version": "1"}
+    return dep
+"#,
+            "key `version` expects `int`, got `str`",
+        );
+    }
+
+    #[test]
+    fn test_typed_dict_value_types_intersect() {
+        assert::fail(
+            r#"
+Dep = typing.TypedDict({"name": str})
+Other = typing.TypedDict({"name": int})
+
+def dep_name(dep: Dep) -> str:
+    return dep["name"]
+
+def test(other: Other):
+    dep_name(other)
+"#,
+            "Expected type",
+        );
+    }
+
+    #[test]
+    fn test_typed_dict_duplicate_key() {
+        assert::fail(
+            r#"typing.TypedDict({"name": str}, optional = {"name": str})"#,
+            "declared both required and optional",
+        );
+    }
+}
@@ -24,6 +24,7 @@
 //! module will be removed, and extracted to its own project.
 
 mod label;
+mod repositories;
 
 use std::borrow::Cow;
 use std::collections::HashMap;
@@ -41,7 +42,9 @@ use lsp_types::CompletionItemKind;
 use lsp_types::Uri;
 use starlark::StarlarkResultExt;
 use starlark::analysis::AstModuleLint;
+use starlark::analysis::Lint;
 use starlark::analysis::find_call_name::AstModuleFindCallName;
+use starlark::analysis::find_call_name::FunctionCall;
 use starlark::docs::DocModule;
 use starlark::environment::FrozenModule;
 use starlark::environment::Globals;
@@ -61,6 +64,8 @@ use starlark_lsp::server::StringLiteralResult;
 use tower_lsp_server::UriExt as _;
 
 use self::label::Label;
+use self::label::LabelRepo;
+use self::repositories::RepositoryMapping;
 use crate::eval::ContextMode;
 use crate::eval::EvalResult;
 
@@ -165,6 +170,7 @@ pub(crate) fn main(
 pub(crate) struct BazelContext<'v> {
     pub(crate) workspace_name: Option<String>,
     pub(crate) external_output_base: Option<PathBuf>,
+    pub(crate) repositories: RepositoryMapping,
     pub(crate) mode: ContextMode,
     pub(crate) print_non_none: bool,
     pub(crate) prelude: Vec<FrozenModule>,
@@ -230,16 +236,22 @@ impl<'v> BazelContext<'v> {
         let output = String::from_utf8(output.stdout)?;
         let mut execroot = None;
         let mut output_base = None;
+        let mut workspace = None;
         for line in output.lines() {
             if let Some((key, value)) = line.split_once(": ") {
                 match key {
                     "execution_root" => execroot = Some(value),
                     "output_base" => output_base = Some(value),
+                    "workspace" => workspace = Some(value),
                     _ => {}
                 }
             }
         }
 
+        let repositories = workspace
+            .map(|workspace| RepositoryMapping::load(Path::new(workspace), &dialect))
+            .unwrap_or_default();
+
         Ok(Self {
             mode,
             print_non_none,
@@ -249,18 +261,22 @@ impl<'v> BazelContext<'v> {
             globals,
             builtin_docs,
             builtin_symbols,
-            workspace_name: execroot.and_then(|execroot| {
-                match PathBuf::from(execroot)
-                    .file_name()?
-                    .to_string_lossy()
-                    .to_string()
-                {
-                    name if name == Self::DEFAULT_WORKSPACE_NAME => None,
-                    name => Some(name),
-                }
-            }),
+            workspace_name: repositories
+                .main_repository_name()
+                .map(str::to_owned)
+                .or_else(|| {
+                    match PathBuf::from(execroot?)
+                        .file_name()?
+                        .to_string_lossy()
+                        .to_string()
+                    {
+                        name if name == Self::DEFAULT_WORKSPACE_NAME => None,
+                        name => Some(name),
+                    }
+                }),
             external_output_base: output_base
                 .map(|output_base| PathBuf::from(output_base).join("external")),
+            repositories,
         })
     }
 
@@ -343,6 +359,11 @@ impl<'v> BazelContext<'v> {
     }
 
     fn check(&self, module: &AstModule) -> impl Iterator<Item = EvalMessage> + use<> {
+        self.lints(module).into_iter().map(EvalMessage::from)
+    }
+
+    /// The lints for a module, checked against the globals of the prelude and the builtins.
+    fn lints(&self, module: &AstModule) -> Vec<Lint> {
         let globals = if self.prelude.is_empty() {
             None
         } else {
@@ -360,10 +381,7 @@ impl<'v> BazelContext<'v> {
             Some(globals)
         };
 
-        module
-            .lint(globals.as_ref())
-            .into_iter()
-            .map(EvalMessage::from)
+        module.lint(globals.as_ref())
     }
     pub(crate) fn file_with_contents(
         &self,
@@ -378,6 +396,15 @@ impl<'v> BazelContext<'v> {
     }
 
     fn get_repository_for_path<'a>(&'a self, path: &'a Path) -> Option<(Cow<'a, str>, &'a Path)> {
+        // Prefer the apparent name from the repository mapping, as that is what can be used
+        // in labels in the workspace.
+        if let Some((repository_name, repository_path)) = self
+            .repositories
+            .find_repository_for_path(path, self.external_output_base.as_deref())
+        {
+            return Some((Cow::Borrowed(repository_name), repository_path));
+        }
+
         self.external_output_base
             .as_ref()
             .and_then(|external_output_base| path.strip_prefix(external_output_base).ok())
@@ -391,10 +418,19 @@ impl<'v> BazelContext<'v> {
             })
     }
 
-    fn get_repository_path(&self, repository_name: &str) -> Option<PathBuf> {
+    fn get_repository_path(&self, repository: &LabelRepo) -> Option<PathBuf> {
+        if !repository.is_canonical {
+            if let Some(path) = self
+                .repositories
+                .resolve(&repository.name, self.external_output_base.as_deref())
+            {
+                return Some(path);
+            }
+        }
+
         self.external_output_base
             .as_ref()
-            .map(|external_output_base| external_output_base.join(repository_name))
+            .map(|external_output_base| external_output_base.join(&repository.name))
     }
 
     /// Finds the directory that is the root of a package, given a label
@@ -437,7 +473,7 @@ impl<'v> BazelContext<'v> {
                 if matches!(self.workspace_name.as_ref(), Some(name) if name == &repository.name) {
                     workspace_root.map(Cow::Borrowed)
                 } else if let Some(remote_repository_root) =
-                    self.get_repository_path(&repository.name).map(Cow::Owned)
+                    self.get_repository_path(repository).map(Cow::Owned)
                 {
                     Some(remote_repository_root)
                 } else {
@@ -480,6 +516,7 @@ impl<'v> BazelContext<'v> {
         if let Some(workspace_name) = &self.workspace_name {
             names.push(Cow::Borrowed(workspace_name.as_str()));
         }
+        names.extend(self.repositories.names().map(Cow::Borrowed));
 
         if let Some(external_output_base) = self.external_output_base.as_ref() {
             // Look for existing folders in `external_output_base`.
@@ -488,7 +525,9 @@ impl<'v> BazelContext<'v> {
                     if let Ok(file_type) = entry.file_type() {
                         if file_type.is_dir() {
                             if let Some(name) = entry.file_name().to_str() {
-                                names.push(Cow::Owned(name.to_owned()));
+                                if !names.iter().any(|existing| existing == name) {
+                                    names.push(Cow::Owned(name.to_owned()));
+                                }
                             }
                         }
                     }
@@ -543,13 +582,16 @@ impl<'v> BazelContext<'v> {
             } else if path.is_file() {
                 if Self::BUILD_FILE_NAMES.contains(&file_name.as_ref()) {
                     if options.targets {
-                        if let Some(targets) = self.query_buildable_targets(
-                            &format!(
-                                "{render_base}{}",
-                                if render_base.ends_with(':') { "" } else { ":" }
-                            ),
-                            workspace_root,
-                        ) {
+                        let targets = self.parse_build_file_targets(&path).or_else(|| {
+                            self.query_buildable_targets(
+                                &format!(
+                                    "{render_base}{}",
+                                    if render_base.ends_with(':') { "" } else { ":" }
+                                ),
+                                workspace_root,
+                            )
+                        });
+                        if let Some(targets) = targets {
                             results.extend(targets.into_iter().map(|target| {
                                 StringCompletionResult {
                                     value: target.to_owned(),
@@ -602,6 +644,37 @@ impl<'v> BazelContext<'v> {
         Ok(())
     }
 
+    /// Parse a BUILD file and return the names of the targets declared in it. Targets created
+    /// by macros with computed names are not found this way.
+    fn parse_build_file_targets(&self, build_file: &Path) -> Option<Vec<String>> {
+        let content = fs::read_to_string(build_file).ok()?;
+        let module =
+            AstModule::parse(&build_file.to_string_lossy(), content, &self.dialect).ok()?;
+        Some(
+            module
+                .find_function_calls()
+                .iter()
+                .filter_map(|call| call.named_string("name").map(str::to_owned))
+                .collect(),
+        )
+    }
+
+    /// Render a target declaration for hover: the rule name and its attributes.
+    fn render_target_hover(call: &FunctionCall) -> String {
+        let mut rendered = format!("```python\n{}(\n", call.function);
+        for argument in &call.arguments {
+            rendered.push_str("    ");
+            if let Some(name) = &argument.name {
+                rendered.push_str(name);
+                rendered.push_str(" = ");
+            }
+            rendered.push_str(&argument.source);
+            rendered.push_str(",\n");
+        }
+        rendered.push_str(")\n```");
+        rendered
+    }
+
     fn query_buildable_targets(
         &self,
         module: &str,
@@ -767,6 +840,40 @@ impl<'v> LspContext for BazelContext<'v> {
             })
     }
 
+    fn render_string_literal_hover(
+        &self,
+        literal: &str,
+        current_file: &LspUri,
+        workspace_root: Option<&Path>,
+        target: &LspUri,
+        module: &AstModule,
+    ) -> Result<Option<String>, String> {
+        let Ok(label) = Label::parse(literal) else {
+            return Ok(None);
+        };
+        // Only the BUILD file of the label's package declares the target, other files can
+        // declare targets with the same name.
+        let LspUri::File(target_path) = target else {
+            return Ok(None);
+        };
+        let Ok(package) = self.resolve_folder(&label, current_file, workspace_root) else {
+            return Ok(None);
+        };
+        let is_package_build_file = target_path.parent() == Some(package.as_path())
+            && target_path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| Self::BUILD_FILE_NAMES.contains(&name));
+        if !is_package_build_file {
+            return Ok(None);
+        }
+        Ok(module
+            .find_function_calls()
+            .iter()
+            .find(|call| call.named_string("name") == Some(label.name.as_str()))
+            .map(Self::render_target_hover))
+    }
+
     fn get_load_contents(&self, uri: &LspUri) -> Result<Option<String>, String> {
         match uri {
             LspUri::File(path) => match path.is_absolute() {
@@ -782,6 +889,13 @@ impl<'v> LspContext for BazelContext<'v> {
         }
     }
 
+    fn get_lints(&self, uri: &LspUri, module: &AstModule) -> Vec<Lint> {
+        match uri {
+            LspUri::File(_) => self.lints(module),
+            _ => Vec::new(),
+        }
+    }
+
     fn get_environment(&self, _uri: &LspUri) -> DocModule {
         DocModule::default()
     }
@@ -886,3 +1000,117 @@ impl<'v> LspContext for BazelContext<'v> {
         Ok(names)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::fs;
+    use std::path::Path;
+    use std::path::PathBuf;
+
+    use lsp_types::CompletionItemKind;
+    use starlark::environment::Globals;
+    use starlark::syntax::AstModule;
+    use starlark::syntax::Dialect;
+    use starlark_lsp::completion::StringCompletionType;
+    use starlark_lsp::server::LspContext;
+    use starlark_lsp::server::LspUri;
+
+    use crate::bazel::BazelContext;
+    use crate::bazel::repositories::RepositoryMapping;
+    use crate::eval::ContextMode;
+
+    const BUILD: &str = r#"
+load(":defs.bzl", "my_macro")
+
+cc_library(
+    name = "foo",
+    srcs = glob(["*.c"]),
+)
+
+cc_binary(name = "bar", deps = [":foo"])
+
+my_macro(name = NAME)
+"#;
+
+    fn context() -> BazelContext<'static> {
+        BazelContext {
+            workspace_name: None,
+            external_output_base: None,
+            repositories: RepositoryMapping::default(),
+            mode: ContextMode::Check,
+            print_non_none: false,
+            prelude: Vec::new(),
+            module: None,
+            dialect: Dialect::Extended,
+            globals: Globals::extended_internal(),
+            builtin_docs: HashMap::new(),
+            builtin_symbols: HashMap::new(),
+        }
+    }
+
+    fn file(root: &Path, path: &str) -> LspUri {
+        LspUri::File(root.join(path))
+    }
+
+    #[test]
+    fn test_hover_matches_package_and_name() {
+        let context = context();
+        let root = Path::new("/ws");
+        let build = AstModule::parse("BUILD", BUILD.to_owned(), &Dialect::Extended).unwrap();
+        let hover = |literal: &str, current_file: &str| {
+            context
+                .render_string_literal_hover(
+                    literal,
+                    &file(root, current_file),
+                    Some(root),
+                    &file(root, "pkg/BUILD"),
+                    &build,
+                )
+                .unwrap()
+        };
+
+        let expected =
+            "```python\ncc_library(\n    name = \"foo\",\n    srcs = glob([\"*.c\"]),\n)\n```";
+        assert_eq!(hover("//pkg:foo", "other/BUILD").as_deref(), Some(expected));
+        assert_eq!(hover(":foo", "pkg/BUILD").as_deref(), Some(expected));
+
+        // Same name, but in another package.
+        assert_eq!(hover("//other:foo", "pkg/BUILD"), None);
+        assert_eq!(hover(":foo", "other/BUILD"), None);
+        // Same package, but no such target.
+        assert_eq!(hover("//pkg:baz", "other/BUILD"), None);
+    }
+
+    #[test]
+    fn test_completes_targets_from_build_file() {
+        let root: PathBuf =
+            std::env::temp_dir().join(format!("starlark_bazel_completion_{}", std::process::id()));
+        fs::create_dir_all(root.join("pkg")).unwrap();
+        fs::write(root.join("pkg/BUILD"), BUILD).unwrap();
+        fs::write(root.join("pkg/foo.c"), "").unwrap();
+
+        let completions = context()
+            .get_string_completion_options(
+                &file(&root, "other/BUILD"),
+                StringCompletionType::String,
+                "//pkg:",
+                Some(&root),
+            )
+            .unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        let mut targets: Vec<(&str, Option<&str>)> = completions
+            .iter()
+            .filter(|completion| completion.kind == CompletionItemKind::PROPERTY)
+            .map(|completion| (completion.value.as_str(), completion.insert_text.as_deref()))
+            .collect();
+        targets.sort();
+        assert_eq!(targets, vec![("bar", Some("bar")), ("foo", Some("foo"))]);
+        assert!(
+            completions
+                .iter()
+                .any(|completion| completion.value == "foo.c")
+        );
+    }
+}
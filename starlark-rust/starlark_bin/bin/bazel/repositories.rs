@@ -0,0 +1,360 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Module for mapping apparent repository names (as used in `@repo//pkg:target` labels) to
+//! the directories of the repositories, based on `MODULE.bazel` and `WORKSPACE` files.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+use starlark::analysis::find_call_name::AstModuleFindCallName;
+use starlark::analysis::find_call_name::FunctionCall;
+use starlark::syntax::AstModule;
+use starlark::syntax::Dialect;
+
+/// Where a repository mapping points to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Repository {
+    /// A repository fetched into the `external` directory, named after a Bazel module (with
+    /// bzlmod) or the repository rule call (with `WORKSPACE`). With bzlmod the directory name
+    /// gets a version dependent suffix, e.g. `rules_cc~` or `rules_cc+`.
+    External(String),
+    /// A repository generated by a module extension and imported with `use_repo`. Its
+    /// directory name is prefixed with the module and extension, e.g. `rules_go++go_sdk+go_sdk`.
+    ExtensionRepo(String),
+    /// A repository on the local filesystem, e.g. a `local_path_override` or `local_repository`.
+    Local(PathBuf),
+}
+
+/// The repository mapping of the main repository.
+#[derive(Debug, Default)]
+pub(crate) struct RepositoryMapping {
+    /// The apparent name of the main repository itself, from `module()`.
+    main_repository_name: Option<String>,
+    repositories: BTreeMap<String, Repository>,
+}
+
+/// Separators between the parts of canonical repository names, before and after Bazel 8.
+const CANONICAL_NAME_SEPARATORS: [char; 2] = ['+', '~'];
+
+impl RepositoryMapping {
+    const MODULE_FILE_NAMES: [&'static str; 1] = ["MODULE.bazel"];
+    const WORKSPACE_FILE_NAMES: [&'static str; 2] = ["WORKSPACE.bazel", "WORKSPACE"];
+
+    /// Read the repository mapping from the files in `workspace_root`. Files that are
+    /// missing or fail to parse are skipped, as the mapping only serves to improve the
+    /// editor experience.
+    pub(crate) fn load(workspace_root: &Path, dialect: &Dialect) -> Self {
+        let mut mapping = Self::default();
+        let parse = |name: &str| {
+            let path = workspace_root.join(name);
+            let content = fs::read_to_string(&path).ok()?;
+            AstModule::parse(&path.to_string_lossy(), content, dialect).ok()
+        };
+
+        // With both, `WORKSPACE` repositories are only visible if not overridden by modules.
+        for name in Self::WORKSPACE_FILE_NAMES {
+            if let Some(module) = parse(name) {
+                mapping.add_workspace_calls(workspace_root, &module.find_function_calls());
+                break;
+            }
+        }
+        for name in Self::MODULE_FILE_NAMES {
+            if let Some(module) = parse(name) {
+                mapping.add_module_calls(workspace_root, &module.find_function_calls());
+            }
+        }
+        mapping
+    }
+
+    fn add_module_calls(&mut self, workspace_root: &Path, calls: &[FunctionCall]) {
+        let mut local_overrides = BTreeMap::new();
+        for call in calls {
+            if call.function == "local_path_override" {
+                if let (Some(module_name), Some(path)) =
+                    (call.named_string("module_name"), call.named_string("path"))
+                {
+                    local_overrides.insert(module_name, workspace_root.join(path));
+                }
+            }
+        }
+
+        for call in calls {
+            match call.function.as_str() {
+                "module" => {
+                    self.main_repository_name = call
+                        .named_string("repo_name")
+                        .or_else(|| call.named_string("name"))
+                        .map(str::to_owned);
+                }
+                "bazel_dep" => {
+                    if let Some(name) = call.named_string("name") {
+                        let repository = match local_overrides.get(name) {
+                            Some(path) => Repository::Local(path.clone()),
+                            None => Repository::External(name.to_owned()),
+                        };
+                        let apparent_name = call.named_string("repo_name").unwrap_or(name);
+                        self.repositories
+                            .insert(apparent_name.to_owned(), repository);
+                    }
+                }
+                "use_repo" => {
+                    // `use_repo(extension, "name", apparent_name = "name")`
+                    for argument in call.arguments.iter().skip(1) {
+                        if let Some(name) = &argument.string_value {
+                            let apparent_name = argument.name.as_ref().unwrap_or(name);
+                            self.repositories.insert(
+                                apparent_name.clone(),
+                                Repository::ExtensionRepo(name.clone()),
+                            );
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn add_workspace_calls(&mut self, workspace_root: &Path, calls: &[FunctionCall]) {
+        for call in calls {
+            if call.function == "workspace" {
+                continue;
+            }
+            let Some(name) = call.named_string("name") else {
+                continue;
+            };
+            let repository = match (call.function.as_str(), call.named_string("path")) {
+                ("local_repository" | "new_local_repository", Some(path)) => {
+                    Repository::Local(workspace_root.join(path))
+                }
+                _ => Repository::External(name.to_owned()),
+            };
+            self.repositories.insert(name.to_owned(), repository);
+        }
+    }
+
+    /// The apparent name of the main repository, if it declares one.
+    pub(crate) fn main_repository_name(&self) -> Option<&str> {
+        self.main_repository_name.as_deref()
+    }
+
+    /// All apparent repository names, except the main repository.
+    pub(crate) fn names(&self) -> impl Iterator<Item = &str> {
+        self.repositories.keys().map(String::as_str)
+    }
+
+    /// Find the directory of the repository with the given apparent name. External
+    /// repositories are only found once Bazel has fetched them.
+    pub(crate) fn resolve(
+        &self,
+        apparent_name: &str,
+        external_output_base: Option<&Path>,
+    ) -> Option<PathBuf> {
+        match self.repositories.get(apparent_name)? {
+            Repository::Local(path) => Some(path.clone()),
+            Repository::External(name) => {
+                let external_output_base = external_output_base?;
+                let exact = external_output_base.join(name);
+                if exact.is_dir() {
+                    return Some(exact);
+                }
+                // `name+`, `name~` or `name~1.2.3`, but not extension repositories of the
+                // module, like `name++ext+repo`.
+                Self::find_external_dir(external_output_base, |dir_name| {
+                    dir_name
+                        .strip_prefix(name.as_str())
+                        .and_then(|rest| rest.strip_prefix(CANONICAL_NAME_SEPARATORS))
+                        .is_some_and(|version| !version.contains(CANONICAL_NAME_SEPARATORS))
+                })
+            }
+            Repository::ExtensionRepo(name) => {
+                Self::find_external_dir(external_output_base?, |dir_name| {
+                    dir_name
+                        .strip_suffix(name.as_str())
+                        .is_some_and(|rest| rest.ends_with(CANONICAL_NAME_SEPARATORS))
+                })
+            }
+        }
+    }
+
+    fn find_external_dir(
+        external_output_base: &Path,
+        matches: impl Fn(&str) -> bool,
+    ) -> Option<PathBuf> {
+        let mut candidates: Vec<_> = fs::read_dir(external_output_base)
+            .ok()?
+            .flatten()
+            .filter(|entry| entry.file_type().is_ok_and(|file_type| file_type.is_dir()))
+            .filter(|entry| entry.file_name().to_str().is_some_and(&matches))
+            .map(|entry| entry.path())
+            .collect();
+        // Be deterministic if stale directories of several versions are around.
+        candidates.sort();
+        candidates.pop()
+    }
+
+    /// Find the apparent name of the repository containing `path`, and the path relative
+    /// to the root of that repository. Repositories nested in others (e.g. a local
+    /// override inside the workspace) take precedence.
+    pub(crate) fn find_repository_for_path<'a>(
+        &'a self,
+        path: &'a Path,
+        external_output_base: Option<&Path>,
+    ) -> Option<(&'a str, &'a Path)> {
+        self.repositories
+            .keys()
+            .filter_map(|name| {
+                let root = self.resolve(name, external_output_base)?;
+                let relative = path.strip_prefix(&root).ok()?;
+                Some((root.components().count(), name.as_str(), relative))
+            })
+            .max_by_key(|(depth, _, _)| *depth)
+            .map(|(_, name, relative)| (name, relative))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+    use std::path::PathBuf;
+
+    use starlark::analysis::find_call_name::AstModuleFindCallName;
+    use starlark::syntax::AstModule;
+    use starlark::syntax::Dialect;
+
+    use crate::bazel::repositories::Repository;
+    use crate::bazel::repositories::RepositoryMapping;
+
+    fn mapping(module: &str, workspace: &str) -> RepositoryMapping {
+        let mut mapping = RepositoryMapping::default();
+        let calls = |content: &str| {
+            AstModule::parse("test.bazel", content.to_owned(), &Dialect::Extended)
+                .unwrap()
+                .find_function_calls()
+        };
+        mapping.add_workspace_calls(Path::new("/ws"), &calls(workspace));
+        mapping.add_module_calls(Path::new("/ws"), &calls(module));
+        mapping
+    }
+
+    #[test]
+    fn test_module_mapping() {
+        let mapping = mapping(
+            r#"
+module(name = "main", version = "1.0")
+bazel_dep(name = "rules_cc", version = "0.0.9")
+bazel_dep(name = "rules_python", version = "0.31.0", repo_name = "py")
+bazel_dep(name = "local_lib")
+local_path_override(module_name = "local_lib", path = "third_party/local_lib")
+ext = use_extension("@rules_python//python/extensions:pip.bzl", "pip")
+use_repo(ext, "pip_deps", pip = "pip_deps_311")
+"#,
+            "",
+        );
+
+        assert_eq!(
+            vec!["local_lib", "pip", "pip_deps", "py", "rules_cc"],
+            mapping.names().collect::<Vec<_>>()
+        );
+        assert_eq!(Some("main"), mapping.main_repository_name());
+        assert_eq!(
+            Some(&Repository::External("rules_python".to_owned())),
+            mapping.repositories.get("py")
+        );
+        assert_eq!(
+            Some(PathBuf::from("/ws/third_party/local_lib")),
+            mapping.resolve("local_lib", None)
+        );
+        assert_eq!(
+            Some(&Repository::ExtensionRepo("pip_deps_311".to_owned())),
+            mapping.repositories.get("pip")
+        );
+        assert_eq!(None, mapping.resolve("rules_cc", None));
+    }
+
+    #[test]
+    fn test_workspace_mapping() {
+        let mapping = mapping(
+            "",
+            r#"
+workspace(name = "main")
+local_repository(name = "local", path = "/abs/local")
+new_local_repository(name = "rel", path = "rel", build_file = "BUILD.rel")
+http_archive(name = "zlib", urls = ["https://example.com/zlib.tar.gz"])
+"#,
+        );
+
+        assert_eq!(
+            vec!["local", "rel", "zlib"],
+            mapping.names().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            Some(PathBuf::from("/abs/local")),
+            mapping.resolve("local", None)
+        );
+        assert_eq!(Some(PathBuf::from("/ws/rel")), mapping.resolve("rel", None));
+        assert_eq!(
+            Some(&Repository::External("zlib".to_owned())),
+            mapping.repositories.get("zlib")
+        );
+    }
+
+    #[test]
+    fn test_resolve_external() {
+        let external = std::env::temp_dir().join(format!(
+            "starlark_bazel_repositories_{}",
+            std::process::id()
+        ));
+        for dir in [
+            "rules_cc+",
+            "rules_cc++cc_configure+local_config_cc",
+            "zlib",
+        ] {
+            fs::create_dir_all(external.join(dir)).unwrap();
+        }
+        let mapping = mapping(
+            r#"
+bazel_dep(name = "rules_cc")
+use_repo(ext, "local_config_cc")
+"#,
+            r#"http_archive(name = "zlib")"#,
+        );
+
+        assert_eq!(
+            Some(external.join("rules_cc+")),
+            mapping.resolve("rules_cc", Some(&external))
+        );
+        assert_eq!(
+            Some(external.join("rules_cc++cc_configure+local_config_cc")),
+            mapping.resolve("local_config_cc", Some(&external))
+        );
+        assert_eq!(
+            Some(external.join("zlib")),
+            mapping.resolve("zlib", Some(&external))
+        );
+        let path = external.join("rules_cc+/cc/defs.bzl");
+        assert_eq!(
+            Some(("rules_cc", Path::new("cc/defs.bzl"))),
+            mapping.find_repository_for_path(&path, Some(&external))
+        );
+
+        fs::remove_dir_all(&external).unwrap();
+    }
+}
@@ -28,6 +28,7 @@ use itertools::Either;
 use lsp_types::Uri;
 use starlark::StarlarkResultExt;
 use starlark::analysis::AstModuleLint;
+use starlark::analysis::Lint;
 use starlark::docs::DocModule;
 use starlark::environment::FrozenModule;
 use starlark::environment::Globals;
@@ -285,6 +286,11 @@ impl<'v> Context<'v> {
     }
 
     fn check(&self, file: &str, module: &AstModule) -> impl Iterator<Item = EvalMessage> + use<> {
+        self.lints(file, module).into_iter().map(EvalMessage::from)
+    }
+
+    /// The lints for a module, excluding suppressed ones.
+    pub(crate) fn lints(&self, file: &str, module: &AstModule) -> Vec<Lint> {
         let globals = if self.prelude.is_empty() {
             None
         } else {
@@ -304,7 +310,7 @@ impl<'v> Context<'v> {
 
         let mut lints = module.lint(globals.as_ref());
         lints.retain(|issue| !self.is_suppressed(file, &issue.short_name));
-        lints.into_iter().map(EvalMessage::from)
+        lints
     }
 }
 
@@ -397,6 +403,13 @@ impl<'v> LspContext for Context<'v> {
         Err("Not yet implemented, render_as_load".to_owned())
     }
 
+    fn get_lints(&self, uri: &LspUri, module: &AstModule) -> Vec<Lint> {
+        match uri {
+            LspUri::File(path) => self.lints(&path.to_string_lossy(), module),
+            _ => Vec::new(),
+        }
+    }
+
     fn get_environment(&self, _uri: &LspUri) -> DocModule {
         DocModule::default()
     }
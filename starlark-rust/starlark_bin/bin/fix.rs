@@ -0,0 +1,72 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Implementation of `--fix`.
+
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::Context as _;
+use starlark::analysis::apply_lint_fixes;
+use starlark::syntax::AstModule;
+
+use crate::eval::Context;
+
+/// Fixes which overlap are applied over several passes, re-linting in between.
+/// Bound the passes in case two fixes keep undoing each other.
+const MAX_PASSES: usize = 10;
+
+/// Apply the fixes for all the lints in every file, rewriting them in place.
+/// Fails if any file does not parse.
+pub(crate) fn fix_files(ctx: &Context, files: impl Iterator<Item = PathBuf>) -> anyhow::Result<()> {
+    let mut errors = 0;
+    'files: for file in files {
+        let filename = file.to_string_lossy();
+        let original =
+            fs::read_to_string(&file).with_context(|| format!("Reading `{}`", file.display()))?;
+        let mut content = original.clone();
+        let mut applied = 0;
+        for _ in 0..MAX_PASSES {
+            let ast = match AstModule::parse(&filename, content.clone(), &ctx.dialect) {
+                Ok(ast) => ast,
+                Err(e) => {
+                    eprintln!("{e}");
+                    errors += 1;
+                    continue 'files;
+                }
+            };
+            let lints = ctx.lints(&filename, &ast);
+            let (fixed, count) =
+                apply_lint_fixes(&content, lints.iter().filter_map(|x| x.fix.as_ref()));
+            if count == 0 {
+                break;
+            }
+            content = fixed;
+            applied += count;
+        }
+
+        if content != original {
+            fs::write(&file, content).with_context(|| format!("Writing `{}`", file.display()))?;
+            println!("{}: applied {applied} fixes", file.display());
+        }
+    }
+
+    if errors > 0 {
+        return Err(anyhow::anyhow!("Failed to parse {errors} files"));
+    }
+    Ok(())
+}
@@ -0,0 +1,67 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Implementation of `--format` and `--check-format`.
+
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::Context as _;
+use starlark::syntax::AstModule;
+use starlark::syntax::Dialect;
+
+/// Format every file in place, or with `check`, only report the files
+/// which are not formatted. Fails if any file does not parse, or if `check`
+/// is set and any file is not formatted.
+pub(crate) fn format_files(
+    files: impl Iterator<Item = PathBuf>,
+    dialect: &Dialect,
+    check: bool,
+) -> anyhow::Result<()> {
+    let mut errors = 0;
+    let mut unformatted = 0;
+    for file in files {
+        let content =
+            fs::read_to_string(&file).with_context(|| format!("Reading `{}`", file.display()))?;
+        let ast = match AstModule::parse(&file.to_string_lossy(), content.clone(), dialect) {
+            Ok(ast) => ast,
+            Err(e) => {
+                eprintln!("{e}");
+                errors += 1;
+                continue;
+            }
+        };
+        let formatted = ast.format();
+        if formatted == content {
+            continue;
+        }
+        unformatted += 1;
+        if check {
+            println!("{}: not formatted", file.display());
+        } else {
+            fs::write(&file, formatted).with_context(|| format!("Writing `{}`", file.display()))?;
+        }
+    }
+
+    if errors > 0 {
+        return Err(anyhow::anyhow!("Failed to parse {errors} files"));
+    }
+    if check && unformatted > 0 {
+        return Err(anyhow::anyhow!("{unformatted} files are not formatted"));
+    }
+    Ok(())
+}
@@ -32,6 +32,7 @@ use clap::builder::TypedValueParser;
 use dupe::Dupe;
 use eval::Context;
 use itertools::Either;
+use sarif::Sarif;
 use starlark::analysis::LintMessage;
 use starlark::docs::DocItem;
 use starlark::docs::markdown::render_doc_item_no_link;
@@ -49,6 +50,9 @@ use crate::eval::ContextMode;
 mod bazel;
 mod dap;
 mod eval;
+mod fix;
+mod format;
+mod sarif;
 mod suppression;
 
 #[derive(Debug, Parser)]
@@ -92,6 +96,30 @@ struct Args {
     )]
     check: bool,
 
+    #[arg(
+        long = "format",
+        help = "Rewrite files in the canonical format.",
+        requires = "files",
+        conflicts_with_all = &["lsp", "dap", "check", "check_format", "evaluate"],
+    )]
+    format: bool,
+
+    #[arg(
+        long = "check-format",
+        help = "Check that files are in the canonical format, without modifying them.",
+        requires = "files",
+        conflicts_with_all = &["lsp", "dap", "check", "format", "evaluate"],
+    )]
+    check_format: bool,
+
+    #[arg(
+        long = "fix",
+        help = "Apply the mechanical fixes for lints, rewriting files in place.",
+        requires = "files",
+        conflicts_with_all = &["lsp", "dap", "json", "format", "check_format", "evaluate"],
+    )]
+    fix: bool,
+
     #[arg(
         long = "json",
         help = "Show output as JSON lines.",
@@ -99,6 +127,13 @@ struct Args {
     )]
     json: bool,
 
+    #[arg(
+        long = "sarif",
+        help = "Show output as a SARIF log, with a rule per lint.",
+        conflicts_with_all = &["lsp", "dap", "json"],
+    )]
+    sarif: bool,
+
     #[arg(
         long = "docs",
         help = "Generate documentation output.",
@@ -227,11 +262,14 @@ impl Stats {
 fn drain(
     xs: impl Iterator<Item = EvalMessage>,
     json: bool,
+    mut sarif: Option<&mut Sarif>,
     stats: &mut Stats,
 ) -> anyhow::Result<()> {
     for x in xs {
         stats.increment(x.severity);
-        if json {
+        if let Some(sarif) = sarif.as_deref_mut() {
+            sarif.add(x);
+        } else if json {
             println!(
                 "{}",
                 serde_json::to_string(&LintMessage::new(x))
@@ -256,7 +294,7 @@ fn interactive(ctx: &Context) -> anyhow::Result<()> {
         match rl.read_line("$> ")? {
             Some(line) => {
                 let mut stats = Stats::default();
-                drain(ctx.expression(line).messages, false, &mut stats)?;
+                drain(ctx.expression(line).messages, false, None, &mut stats)?;
             }
             // User pressed EOF - disconnected terminal, or similar
             None => return Ok(()),
@@ -297,6 +335,10 @@ fn main() -> anyhow::Result<()> {
         let prelude = expand_dirs(ext, args.prelude).collect::<Vec<_>>();
         let print_non_none = !args.evaluate.is_empty() || is_interactive;
 
+        if args.format || args.check_format {
+            return format::format_files(expand_dirs(ext, args.files), &dialect, args.check_format);
+        }
+
         // TODO: Remove this when extracting the Bazel binary to its own
         // repository, after the LspContext interface stabilizes.
         if args.bazel {
@@ -323,6 +365,10 @@ fn main() -> anyhow::Result<()> {
                 args.suppression,
             )?;
 
+            if args.fix {
+                return fix::fix_files(&ctx, expand_dirs(ext, args.files.clone()));
+            }
+
             if args.lsp {
                 ctx.mode = ContextMode::Check;
                 starlark_lsp::server::stdio_server(ctx)?;
@@ -346,21 +392,35 @@ fn main() -> anyhow::Result<()> {
                 interactive(&ctx)?;
             } else {
                 let mut stats = Stats::default();
+                let mut sarif = args.sarif.then(Sarif::default);
                 for e in args.evaluate.clone() {
                     stats.increment_file();
-                    drain(ctx.expression(e).messages, args.json, &mut stats)?;
+                    drain(
+                        ctx.expression(e).messages,
+                        args.json,
+                        sarif.as_mut(),
+                        &mut stats,
+                    )?;
                 }
 
                 for file in expand_dirs(ext, args.files.clone()) {
                     stats.increment_file();
-                    drain(ctx.file(&file).messages, args.json, &mut stats)?;
+                    drain(
+                        ctx.file(&file).messages,
+                        args.json,
+                        sarif.as_mut(),
+                        &mut stats,
+                    )?;
                 }
 
-                if !args.json {
+                let is_sarif = sarif.is_some();
+                if let Some(sarif) = sarif {
+                    sarif.print()?;
+                } else if !args.json {
                     println!("{stats}");
-                    if stats.error > 0 {
-                        return Err(anyhow::anyhow!("Failed with {} errors", stats.error));
-                    }
+                }
+                if (is_sarif || !args.json) && stats.error > 0 {
+                    return Err(anyhow::anyhow!("Failed with {} errors", stats.error));
                 }
             }
             Ok(())
@@ -0,0 +1,360 @@
+/*
+ * Copyright 2019 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Implementation of `--sarif`, which reports the messages as a
+//! [SARIF 2.1.0](https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html) log.
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use serde::Serialize;
+use starlark::errors::EvalMessage;
+use starlark::errors::EvalSeverity;
+use url::Url;
+
+/// Collects messages, grouping them by the name of the lint that produced them.
+#[derive(Default)]
+pub(crate) struct Sarif {
+    messages: Vec<EvalMessage>,
+}
+
+impl Sarif {
+    pub(crate) fn add(&mut self, x: EvalMessage) {
+        self.messages.push(x);
+    }
+
+    /// The log of the messages, whose relative paths are relative to `cwd`.
+    fn log(self, cwd: &Path) -> SarifLog {
+        let results = self
+            .messages
+            .into_iter()
+            .map(|x| SarifResult {
+                rule_id: x.name,
+                rule_index: 0,
+                level: match x.severity {
+                    EvalSeverity::Error => "error",
+                    EvalSeverity::Warning => "warning",
+                    EvalSeverity::Advice => "note",
+                    EvalSeverity::Disabled => "none",
+                },
+                message: SarifMessage {
+                    text: x.description,
+                },
+                locations: vec![SarifLocation {
+                    physical_location: Some(SarifPhysicalLocation {
+                        artifact_location: SarifArtifactLocation::new(cwd, &x.path),
+                        region: x.span.map(|span| SarifRegion {
+                            start_line: span.begin.line as u64 + 1,
+                            start_column: Some(span.begin.column as u64 + 1),
+                            end_line: Some(span.end.line as u64 + 1),
+                            end_column: Some(span.end.column as u64 + 1),
+                        }),
+                    }),
+                    logical_locations: Vec::new(),
+                }],
+                properties: BTreeMap::new(),
+            })
+            .collect();
+        SarifLog::single_run(
+            "starlark",
+            "https://github.com/facebook/starlark-rust",
+            None,
+            cwd,
+            results,
+        )
+    }
+
+    pub(crate) fn print(self) -> anyhow::Result<()> {
+        let log = self.log(&std::env::current_dir()?);
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&log)
+                .map_err(|e| anyhow::anyhow!("Failed to serialize SARIF: {e}"))?
+        );
+        Ok(())
+    }
+}
+
+// The SARIF types and helpers from here to the tests are the same in buck2's build report
+// (`app/buck2_build_api/src/build/sarif.rs`) and in `starlark --sarif`
+// (`starlark-rust/starlark_bin/bin/sarif.rs`). Keep them identical.
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const SARIF_VERSION: &str = "2.1.0";
+
+/// The base id of the paths relative to the root.
+const SRCROOT: &str = "%SRCROOT%";
+
+#[derive(Debug, Serialize)]
+pub(crate) struct SarifLog {
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    version: &'static str,
+    runs: Vec<SarifRun>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifRun {
+    tool: SarifTool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    automation_details: Option<SarifAutomationDetails>,
+    original_uri_base_ids: BTreeMap<&'static str, SarifArtifactLocation>,
+    results: Vec<SarifResult>,
+}
+
+#[derive(Debug, Serialize)]
+struct SarifTool {
+    driver: SarifDriver,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifDriver {
+    name: &'static str,
+    information_uri: &'static str,
+    rules: Vec<SarifRule>,
+}
+
+#[derive(Debug, Serialize)]
+struct SarifRule {
+    id: String,
+}
+
+#[derive(Debug, Serialize)]
+struct SarifAutomationDetails {
+    id: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifResult {
+    rule_id: String,
+    rule_index: usize,
+    level: &'static str,
+    message: SarifMessage,
+    locations: Vec<SarifLocation>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    properties: BTreeMap<&'static str, serde_json::Value>,
+}
+
+#[derive(Debug, Serialize)]
+struct SarifMessage {
+    text: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifLocation {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    physical_location: Option<SarifPhysicalLocation>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    logical_locations: Vec<SarifLogicalLocation>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifPhysicalLocation {
+    artifact_location: SarifArtifactLocation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    region: Option<SarifRegion>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifArtifactLocation {
+    uri: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    uri_base_id: Option<&'static str>,
+}
+
+/// Lines and columns are 1-based, and the end column is exclusive.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifRegion {
+    start_line: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    start_column: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    end_line: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    end_column: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+struct SarifLogicalLocation {
+    fully_qualified_name: String,
+    kind: &'static str,
+}
+
+impl SarifLog {
+    /// A log with a single run of `driver_name`, whose rules are the rules of the results.
+    fn single_run(
+        driver_name: &'static str,
+        information_uri: &'static str,
+        automation_details: Option<SarifAutomationDetails>,
+        root: &Path,
+        mut results: Vec<SarifResult>,
+    ) -> Self {
+        let rules: BTreeSet<&str> = results.iter().map(|r| r.rule_id.as_str()).collect();
+        let rules: Vec<String> = rules.into_iter().map(str::to_owned).collect();
+        for result in &mut results {
+            result.rule_index = rules.binary_search(&result.rule_id).unwrap_or_default();
+        }
+        SarifLog {
+            schema: SARIF_SCHEMA,
+            version: SARIF_VERSION,
+            runs: vec![SarifRun {
+                tool: SarifTool {
+                    driver: SarifDriver {
+                        name: driver_name,
+                        information_uri,
+                        rules: rules.into_iter().map(|id| SarifRule { id }).collect(),
+                    },
+                },
+                automation_details,
+                original_uri_base_ids: BTreeMap::from([(
+                    SRCROOT,
+                    SarifArtifactLocation {
+                        uri: Url::from_directory_path(root)
+                            .map_or_else(|()| root.display().to_string(), String::from),
+                        uri_base_id: None,
+                    },
+                )]),
+                results,
+            }],
+        }
+    }
+}
+
+impl SarifArtifactLocation {
+    /// The location of `path`. Relative paths are resolved against `root`, and are reported
+    /// relative to the `%SRCROOT%` base id.
+    fn new(root: &Path, path: &str) -> Self {
+        if Path::new(path).is_relative() {
+            let relative = match (
+                Url::from_directory_path(root),
+                Url::from_file_path(root.join(path)),
+            ) {
+                (Ok(root), Ok(url)) => root.make_relative(&url),
+                _ => None,
+            };
+            if let Some(uri) = relative {
+                return SarifArtifactLocation {
+                    uri,
+                    uri_base_id: Some(SRCROOT),
+                };
+            }
+        }
+        SarifArtifactLocation {
+            uri: Url::from_file_path(path).map_or_else(|()| path.to_owned(), String::from),
+            uri_base_id: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use starlark::codemap::ResolvedPos;
+    use starlark::codemap::ResolvedSpan;
+
+    use super::*;
+
+    fn message(path: &str, name: &str, severity: EvalSeverity) -> EvalMessage {
+        EvalMessage {
+            path: path.to_owned(),
+            span: Some(ResolvedSpan {
+                begin: ResolvedPos { line: 0, column: 4 },
+                end: ResolvedPos { line: 1, column: 0 },
+            }),
+            severity,
+            name: name.to_owned(),
+            description: format!("{name} found"),
+            full_error_with_span: None,
+            original: None,
+        }
+    }
+
+    #[test]
+    fn test_sarif_log() {
+        let (cwd, cwd_uri, absolute, absolute_uri) = if cfg!(windows) {
+            (
+                "C:\\work",
+                "file:///C:/work/",
+                "C:\\lib\\x y.bzl",
+                "file:///C:/lib/x%20y.bzl",
+            )
+        } else {
+            (
+                "/work",
+                "file:///work/",
+                "/lib/x y.bzl",
+                "file:///lib/x%20y.bzl",
+            )
+        };
+        let mut sarif = Sarif::default();
+        sarif.add(message("pkg/BUILD", "unused-load", EvalSeverity::Warning));
+        sarif.add(message(absolute, "error", EvalSeverity::Error));
+
+        let location = |artifact_location| {
+            json!([{
+                "physicalLocation": {
+                    "artifactLocation": artifact_location,
+                    "region": {"startLine": 1, "startColumn": 5, "endLine": 2, "endColumn": 1},
+                },
+            }])
+        };
+        assert_eq!(
+            json!({
+                "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+                "version": "2.1.0",
+                "runs": [{
+                    "tool": {
+                        "driver": {
+                            "name": "starlark",
+                            "informationUri": "https://github.com/facebook/starlark-rust",
+                            "rules": [{"id": "error"}, {"id": "unused-load"}],
+                        },
+                    },
+                    "originalUriBaseIds": {"%SRCROOT%": {"uri": cwd_uri}},
+                    "results": [
+                        {
+                            "ruleId": "unused-load",
+                            "ruleIndex": 1,
+                            "level": "warning",
+                            "message": {"text": "unused-load found"},
+                            "locations": location(
+                                json!({"uri": "pkg/BUILD", "uriBaseId": "%SRCROOT%"})
+                            ),
+                        },
+                        {
+                            "ruleId": "error",
+                            "ruleIndex": 0,
+                            "level": "error",
+                            "message": {"text": "error found"},
+                            "locations": location(json!({"uri": absolute_uri})),
+                        },
+                    ],
+                }],
+            }),
+            serde_json::to_value(sarif.log(Path::new(cwd))).unwrap()
+        );
+    }
+}
@@ -40,6 +40,12 @@ use lsp_server::Request;
 use lsp_server::RequestId;
 use lsp_server::Response;
 use lsp_server::ResponseError;
+use lsp_types::CodeAction;
+use lsp_types::CodeActionKind;
+use lsp_types::CodeActionOrCommand;
+use lsp_types::CodeActionParams;
+use lsp_types::CodeActionProviderCapability;
+use lsp_types::CodeActionResponse;
 use lsp_types::CompletionItem;
 use lsp_types::CompletionItemKind;
 use lsp_types::CompletionOptions;
@@ -50,6 +56,7 @@ use lsp_types::Diagnostic;
 use lsp_types::DidChangeTextDocumentParams;
 use lsp_types::DidCloseTextDocumentParams;
 use lsp_types::DidOpenTextDocumentParams;
+use lsp_types::DocumentFormattingParams;
 use lsp_types::Documentation;
 use lsp_types::GotoDefinitionParams;
 use lsp_types::GotoDefinitionResponse;
@@ -65,6 +72,7 @@ use lsp_types::MarkedString;
 use lsp_types::MarkupContent;
 use lsp_types::MarkupKind;
 use lsp_types::MessageType;
+use lsp_types::NumberOrString;
 use lsp_types::OneOf;
 use lsp_types::Position;
 use lsp_types::PublishDiagnosticsParams;
@@ -75,13 +83,16 @@ use lsp_types::TextDocumentSyncKind;
 use lsp_types::TextEdit;
 use lsp_types::Uri;
 use lsp_types::WorkDoneProgressOptions;
+use lsp_types::WorkspaceEdit;
 use lsp_types::WorkspaceFolder;
 use lsp_types::notification::DidChangeTextDocument;
 use lsp_types::notification::DidCloseTextDocument;
 use lsp_types::notification::DidOpenTextDocument;
 use lsp_types::notification::LogMessage;
 use lsp_types::notification::PublishDiagnostics;
+use lsp_types::request::CodeActionRequest;
 use lsp_types::request::Completion;
+use lsp_types::request::Formatting;
 use lsp_types::request::GotoDefinition;
 use lsp_types::request::HoverRequest;
 use serde::Deserialize;
@@ -89,6 +100,7 @@ use serde::Deserializer;
 use serde::Serialize;
 use serde::Serializer;
 use serde::de::DeserializeOwned;
+use starlark::analysis::Lint;
 use starlark::codemap::ResolvedSpan;
 use starlark::codemap::Span;
 use starlark::docs::DocItem;
@@ -377,6 +389,13 @@ pub trait LspContext {
     /// Get the preloaded environment for a particular file.
     fn get_environment(&self, uri: &LspUri) -> DocModule;
 
+    /// Get the lints of a file, whose fixes are offered as code actions. These should be the
+    /// lints reported by `parse_file_with_contents`, e.g. checked against the same globals.
+    fn get_lints(&self, uri: &LspUri, module: &AstModule) -> Vec<Lint> {
+        let _unused = (uri, module);
+        Vec::new()
+    }
+
     /// Get the LspUri for a global symbol if possible.
     ///
     /// The current file is provided in case different files have different global symbols
@@ -400,6 +419,25 @@ pub trait LspContext {
         let _unused = (document_uri, kind, current_value, workspace_root);
         Ok(Vec::new())
     }
+
+    /// Render the hover text (as markdown) for a string literal that was resolved by
+    /// [`resolve_string_literal`](LspContext::resolve_string_literal), e.g. to describe the
+    /// build target a label refers to.
+    ///
+    /// `target` is the file the literal resolved to and `module` its contents. If `None` is
+    /// returned, the hover shows the source code at the location found by the
+    /// `location_finder` instead.
+    fn render_string_literal_hover(
+        &self,
+        literal: &str,
+        current_file: &LspUri,
+        workspace_root: Option<&Path>,
+        target: &LspUri,
+        module: &AstModule,
+    ) -> Result<Option<String>, String> {
+        let _unused = (literal, current_file, workspace_root, target, module);
+        Ok(None)
+    }
 }
 
 pub(crate) struct Backend<T: LspContext> {
@@ -408,6 +446,9 @@ pub(crate) struct Backend<T: LspContext> {
     /// The `AstModule` from the last time that a file was opened / changed and parsed successfully.
     /// Entries are evicted when the file is closed.
     pub(crate) last_valid_parse: RwLock<HashMap<LspUri, Arc<LspModule>>>,
+    /// Files whose latest contents failed to parse, so their entry in `last_valid_parse`
+    /// is stale. Formatting them is refused, as it would revert the newer contents.
+    pub(crate) failed_parse: RwLock<HashSet<LspUri>>,
 }
 
 /// The logic implementations of stuff
@@ -425,6 +466,8 @@ impl<T: LspContext> Backend<T> {
             definition_provider,
             completion_provider: Some(CompletionOptions::default()),
             hover_provider: Some(HoverProviderCapability::Simple(true)),
+            document_formatting_provider: Some(OneOf::Left(true)),
+            code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
             ..ServerCapabilities::default()
         }
     }
@@ -454,8 +497,11 @@ impl<T: LspContext> Backend<T> {
         let eval_result = self.context.parse_file_with_contents(&lsp_uri, text);
         if let Some(ast) = eval_result.ast {
             let module = Arc::new(LspModule::new(ast));
+            self.failed_parse.write().unwrap().remove(&lsp_uri);
             let mut last_valid_parse = self.last_valid_parse.write().unwrap();
             last_valid_parse.insert(lsp_uri, module);
+        } else {
+            self.failed_parse.write().unwrap().insert(lsp_uri);
         }
         self.publish_diagnostics(uri, eval_result.diagnostics, version);
         Ok(())
@@ -481,8 +527,10 @@ impl<T: LspContext> Backend<T> {
 
     fn did_close(&self, params: DidCloseTextDocumentParams) -> Result<(), LspOpError> {
         {
+            let lsp_uri: LspUri = params.text_document.uri.clone().try_into()?;
+            self.failed_parse.write().unwrap().remove(&lsp_uri);
             let mut last_valid_parse = self.last_valid_parse.write().unwrap();
-            last_valid_parse.remove(&params.text_document.uri.clone().try_into()?);
+            last_valid_parse.remove(&lsp_uri);
         }
         self.publish_diagnostics(params.text_document.uri, Vec::new(), None);
         Ok(())
@@ -524,6 +572,16 @@ impl<T: LspContext> Backend<T> {
         self.send_response(new_response(id, self.hover_info(params, initialize_params)));
     }
 
+    /// Formats the whole file in the canonical style.
+    fn formatting(&self, id: RequestId, params: DocumentFormattingParams) {
+        self.send_response(new_response(id, self.format_edits(params)));
+    }
+
+    /// Offers the fixes for lints in the requested range.
+    fn code_action(&self, id: RequestId, params: CodeActionParams) {
+        self.send_response(new_response(id, self.code_actions(params)));
+    }
+
     /// Get the file contents of a starlark: URI.
     fn get_starlark_file_contents(&self, id: RequestId, params: StarlarkFileContentsParams) {
         let response = match params.uri {
@@ -540,6 +598,85 @@ impl<T: LspContext> Backend<T> {
         self.send_response(new_response(id, response));
     }
 
+    fn format_edits(
+        &self,
+        params: DocumentFormattingParams,
+    ) -> Result<Option<Vec<TextEdit>>, LspOpError> {
+        let uri: LspUri = params.text_document.uri.try_into()?;
+        if self.failed_parse.read().unwrap().contains(&uri) {
+            return Err(LspOpError::Other(format!(
+                "Cannot format `{uri}` because it does not parse"
+            )));
+        }
+        let Some(module) = self.get_ast(&uri) else {
+            return Ok(None);
+        };
+        let source = module.ast.codemap().source();
+        let formatted = module.ast.format();
+        if formatted == source {
+            return Ok(Some(Vec::new()));
+        }
+        // Replace the whole document, ending after the last character of the last line.
+        let last_line = source.rsplit('\n').next().unwrap_or_default();
+        let end = Position::new(
+            source.matches('\n').count() as u32,
+            last_line.encode_utf16().count() as u32,
+        );
+        Ok(Some(vec![TextEdit::new(
+            Range::new(Position::new(0, 0), end),
+            formatted,
+        )]))
+    }
+
+    fn code_actions(
+        &self,
+        params: CodeActionParams,
+    ) -> Result<Option<CodeActionResponse>, LspOpError> {
+        let uri: LspUri = params.text_document.uri.clone().try_into()?;
+        // Edits computed against a stale parse would corrupt the newer contents.
+        if self.failed_parse.read().unwrap().contains(&uri) {
+            return Ok(None);
+        }
+        let Some(module) = self.get_ast(&uri) else {
+            return Ok(None);
+        };
+        let codemap = module.ast.codemap();
+        let mut actions = Vec::new();
+        for lint in self.context.get_lints(&uri, &module.ast) {
+            let Some(fix) = lint.fix else {
+                continue;
+            };
+            let range: Range = lint.location.resolve_span().into();
+            if range.end < params.range.start || params.range.end < range.start {
+                continue;
+            }
+            let code = NumberOrString::String(lint.short_name);
+            let diagnostics = params
+                .context
+                .diagnostics
+                .iter()
+                .filter(|x| x.range == range && x.code.as_ref() == Some(&code))
+                .cloned()
+                .collect::<Vec<_>>();
+            let edits = fix
+                .edits
+                .into_iter()
+                .map(|x| TextEdit::new(codemap.resolve_span(x.span).into(), x.replacement))
+                .collect();
+            actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                title: fix.title,
+                kind: Some(CodeActionKind::QUICKFIX),
+                diagnostics: (!diagnostics.is_empty()).then_some(diagnostics),
+                edit: Some(WorkspaceEdit {
+                    changes: Some(HashMap::from([(params.text_document.uri.clone(), edits)])),
+                    ..WorkspaceEdit::default()
+                }),
+                ..CodeAction::default()
+            }));
+        }
+        Ok(Some(actions))
+    }
+
     pub(crate) fn resolve_load_path(
         &self,
         path: &str,
@@ -1168,6 +1305,22 @@ impl<T: LspContext> Backend<T> {
                                 return Ok(None);
                             }
                         };
+                        if let Some(hover) = self
+                            .context
+                            .render_string_literal_hover(
+                                &literal,
+                                document_uri,
+                                workspace_root,
+                                &uri,
+                                &module.ast,
+                            )
+                            .map_err(LspOpError::FromContext)?
+                        {
+                            return Ok(Some(Hover {
+                                contents: HoverContents::Array(vec![MarkedString::String(hover)]),
+                                range: Some(source.into()),
+                            }));
+                        }
                         let result =
                             location_finder(&module.ast).map_err(LspOpError::FromContext)?;
 
@@ -1266,6 +1419,10 @@ impl<T: LspContext> Backend<T> {
                         self.completion(req.id, params, &initialize_params);
                     } else if let Some(params) = as_request::<HoverRequest>(&req) {
                         self.hover(req.id, params, &initialize_params);
+                    } else if let Some(params) = as_request::<Formatting>(&req) {
+                        self.formatting(req.id, params);
+                    } else if let Some(params) = as_request::<CodeActionRequest>(&req) {
+                        self.code_action(req.id, params);
                     } else if self.connection.handle_shutdown(&req)? {
                         return Ok(());
                     }
@@ -1340,6 +1497,7 @@ pub fn server_with_connection<T: LspContext>(
         connection,
         context,
         last_valid_parse: RwLock::default(),
+        failed_parse: RwLock::default(),
     }
     .main_loop(initialization_params)?;
 
@@ -1424,15 +1582,30 @@ mod tests {
     use anyhow::Context;
     use lsp_server::Request;
     use lsp_server::RequestId;
+    use lsp_types::CodeActionContext;
+    use lsp_types::CodeActionOrCommand;
+    use lsp_types::CodeActionParams;
+    use lsp_types::CodeActionResponse;
+    use lsp_types::DocumentFormattingParams;
+    use lsp_types::FormattingOptions;
     use lsp_types::GotoDefinitionParams;
     use lsp_types::GotoDefinitionResponse;
+    use lsp_types::Hover;
+    use lsp_types::HoverContents;
+    use lsp_types::HoverParams;
+    use lsp_types::LanguageString;
     use lsp_types::LocationLink;
+    use lsp_types::MarkedString;
     use lsp_types::Position;
     use lsp_types::Range;
     use lsp_types::TextDocumentIdentifier;
     use lsp_types::TextDocumentPositionParams;
+    use lsp_types::TextEdit;
     use lsp_types::Uri;
+    use lsp_types::request::CodeActionRequest;
+    use lsp_types::request::Formatting;
     use lsp_types::request::GotoDefinition;
+    use lsp_types::request::HoverRequest;
     use starlark::codemap::ResolvedSpan;
     use starlark::wasm::is_wasm;
     use textwrap::dedent;
@@ -2320,6 +2493,64 @@ mod tests {
         Ok(())
     }
 
+    fn hover_request(server: &mut TestServer, uri: Uri, line: u32, character: u32) -> Request {
+        server.new_request::<HoverRequest>(HoverParams {
+            text_document_position_params: TextDocumentPositionParams {
+                text_document: TextDocumentIdentifier { uri },
+                position: Position { line, character },
+            },
+            work_done_progress_params: Default::default(),
+        })
+    }
+
+    #[test]
+    fn hovers_string_literals() -> anyhow::Result<()> {
+        if is_wasm() {
+            return Ok(());
+        }
+
+        let foo_uri = temp_file_uri("foo.star");
+        let bar_uri = temp_file_uri("bar.star");
+        let baz_uri = temp_file_uri("baz.star");
+
+        let mut server = TestServer::new()?;
+        server.set_file_contents(&bar_uri, "rule(name = \"bar\")\n".to_owned())?;
+        server.set_file_contents(&baz_uri, "rule(name = \"other\")\n".to_owned())?;
+        server.open_file(
+            foo_uri.clone(),
+            "\"bar.star--0:4\"\n\"baz.star--0:4\"\n".to_owned(),
+        )?;
+
+        // The context describes the target declared in the file.
+        let req = hover_request(&mut server, foo_uri.clone(), 0, 3);
+        let request_id = server.send_request(req)?;
+        let hover = server.get_response::<Hover>(request_id)?;
+        assert_eq!(
+            HoverContents::Array(vec![MarkedString::String(
+                "`bar` is declared by `rule`".to_owned()
+            )]),
+            hover.contents
+        );
+        assert_eq!(
+            Some(Range::new(Position::new(0, 0), Position::new(0, 15))),
+            hover.range
+        );
+
+        // Otherwise, the source at the location found for the literal is shown.
+        let req = hover_request(&mut server, foo_uri, 1, 3);
+        let request_id = server.send_request(req)?;
+        let hover = server.get_response::<Hover>(request_id)?;
+        assert_eq!(
+            HoverContents::Array(vec![MarkedString::LanguageString(LanguageString {
+                language: "python".to_owned(),
+                value: "rule".to_owned(),
+            })]),
+            hover.contents
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn returns_starlark_file_contents() -> anyhow::Result<()> {
         if is_wasm() {
@@ -2349,6 +2580,109 @@ mod tests {
         Ok(())
     }
 
+    fn formatting_request(server: &mut TestServer, uri: Uri) -> Request {
+        server.new_request::<Formatting>(DocumentFormattingParams {
+            text_document: TextDocumentIdentifier { uri },
+            options: FormattingOptions::default(),
+            work_done_progress_params: Default::default(),
+        })
+    }
+
+    #[test]
+    fn formats_document() -> anyhow::Result<()> {
+        if is_wasm() {
+            return Ok(());
+        }
+
+        let uri = temp_file_uri("file.star");
+        let mut server = TestServer::new()?;
+        server.open_file(uri.clone(), "x=[1,\n  2]  # Two.\ny = 1".to_owned())?;
+
+        let req = formatting_request(&mut server, uri.clone());
+        let request_id = server.send_request(req)?;
+        let edits = server.get_response::<Vec<TextEdit>>(request_id)?;
+        let expected = vec![TextEdit::new(
+            Range::new(Position::new(0, 0), Position::new(2, 5)),
+            "x = [\n    1,\n    2,  # Two.\n]\ny = 1\n".to_owned(),
+        )];
+        assert_eq!(expected, edits);
+
+        server.change_file(uri.clone(), "y = 1\n".to_owned())?;
+        let req = formatting_request(&mut server, uri.clone());
+        let request_id = server.send_request(req)?;
+        let edits = server.get_response::<Vec<TextEdit>>(request_id)?;
+        assert!(edits.is_empty());
+
+        server.change_file(uri.clone(), "\"invalid parse".to_owned())?;
+        let req = formatting_request(&mut server, uri);
+        let request_id = server.send_request(req)?;
+        assert!(server.get_response::<Vec<TextEdit>>(request_id).is_err());
+        Ok(())
+    }
+
+    fn code_action_request(server: &mut TestServer, uri: Uri, range: Range) -> Request {
+        server.new_request::<CodeActionRequest>(CodeActionParams {
+            text_document: TextDocumentIdentifier { uri },
+            range,
+            context: CodeActionContext::default(),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        })
+    }
+
+    #[test]
+    fn offers_lint_fixes_as_code_actions() -> anyhow::Result<()> {
+        if is_wasm() {
+            return Ok(());
+        }
+
+        let uri = temp_file_uri("file.star");
+        let mut server = TestServer::new()?;
+        server.open_file(uri.clone(), String::new())?;
+        server.change_file(
+            uri.clone(),
+            "load(\"a.star\", \"a\", \"b\")\nx = dict(**a)\n".to_owned(),
+        )?;
+
+        // Only the unused load of `b` is on the first line.
+        let req = code_action_request(
+            &mut server,
+            uri.clone(),
+            Range::new(Position::new(0, 0), Position::new(1, 0)),
+        );
+        let request_id = server.send_request(req)?;
+        let actions = server.get_response::<CodeActionResponse>(request_id)?;
+        let [CodeActionOrCommand::CodeAction(action)] = actions.as_slice() else {
+            panic!("Expected one code action, got {actions:?}");
+        };
+        assert_eq!("Remove unused `b`", action.title);
+        let changes = action.edit.as_ref().unwrap().changes.as_ref().unwrap();
+        assert_eq!(
+            &vec![TextEdit::new(
+                Range::new(Position::new(0, 18), Position::new(0, 23)),
+                String::new(),
+            )],
+            changes.get(&uri).unwrap()
+        );
+
+        let req = code_action_request(
+            &mut server,
+            uri.clone(),
+            Range::new(Position::new(1, 6), Position::new(1, 6)),
+        );
+        let request_id = server.send_request(req)?;
+        let actions = server.get_response::<CodeActionResponse>(request_id)?;
+        let titles = actions
+            .iter()
+            .map(|x| match x {
+                CodeActionOrCommand::CodeAction(x) => x.title.as_str(),
+                CodeActionOrCommand::Command(x) => x.title.as_str(),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["Remove `**`"], titles);
+        Ok(())
+    }
+
     fn resolve_range_in_string(s: &str, r: Range) -> &str {
         let byte_of_pos = |p: Position| {
             let l = if p.line == 0 {
@@ -58,6 +58,8 @@ use lsp_types::request::Shutdown;
 use maplit::hashmap;
 use serde::de::DeserializeOwned;
 use starlark::analysis::AstModuleLint;
+use starlark::analysis::Lint;
+use starlark::analysis::find_call_name::AstModuleFindCallName;
 use starlark::codemap::Pos;
 use starlark::codemap::Span;
 use starlark::docs::DocFunction;
@@ -266,6 +268,27 @@ impl LspContext for TestServerContext {
             })
     }
 
+    fn render_string_literal_hover(
+        &self,
+        literal: &str,
+        _current_file: &LspUri,
+        _workspace_root: Option<&Path>,
+        _target: &LspUri,
+        module: &AstModule,
+    ) -> Result<Option<String>, String> {
+        // Describe the call declaring the target named after the file, e.g. `bar` for
+        // `bar.star--0:3`.
+        let path = literal.split("--").next().unwrap_or(literal);
+        let Some(name) = Path::new(path).file_stem().and_then(|stem| stem.to_str()) else {
+            return Ok(None);
+        };
+        Ok(module
+            .find_function_calls()
+            .into_iter()
+            .find(|call| call.named_string("name") == Some(name))
+            .map(|call| format!("`{name}` is declared by `{}`", call.function)))
+    }
+
     fn get_load_contents(&self, uri: &LspUri) -> Result<Option<String>, String> {
         match uri {
             LspUri::File(u) => {
@@ -290,6 +313,10 @@ impl LspContext for TestServerContext {
         Ok(self.builtin_symbols.get(symbol).cloned())
     }
 
+    fn get_lints(&self, _uri: &LspUri, module: &AstModule) -> Vec<Lint> {
+        module.lint(None)
+    }
+
     fn get_environment(&self, _uri: &LspUri) -> DocModule {
         DocModule {
             docs: None,
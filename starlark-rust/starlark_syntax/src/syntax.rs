@@ -27,6 +27,9 @@ pub mod ast;
 pub mod ast_load;
 pub mod call;
 pub mod def;
+mod format;
+#[cfg(test)]
+mod format_tests;
 #[cfg(test)]
 mod grammar_tests;
 pub mod grammar_util;
@@ -0,0 +1,888 @@
+/*
+ * Copyright 2018 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Canonical formatter for Starlark modules.
+//!
+//! The AST does not contain comments, so they are attached while printing:
+//! the comment spans recorded by the parser are consumed in source order,
+//! each one emitted before the first statement or collection item which
+//! follows it, or at the end of the line it trails.
+
+use crate::codemap::Pos;
+use crate::codemap::Span;
+use crate::syntax::AstModule;
+use crate::syntax::ast::ArgumentP;
+use crate::syntax::ast::AssignP;
+use crate::syntax::ast::AssignTargetP;
+use crate::syntax::ast::AstArgument;
+use crate::syntax::ast::AstAssignTarget;
+use crate::syntax::ast::AstExpr;
+use crate::syntax::ast::AstLiteral;
+use crate::syntax::ast::AstNoPayload;
+use crate::syntax::ast::AstParameter;
+use crate::syntax::ast::AstStmt;
+use crate::syntax::ast::AstTypeExpr;
+use crate::syntax::ast::BinOp;
+use crate::syntax::ast::Clause;
+use crate::syntax::ast::ClauseP;
+use crate::syntax::ast::DefP;
+use crate::syntax::ast::ExprP;
+use crate::syntax::ast::ForClause;
+use crate::syntax::ast::ForP;
+use crate::syntax::ast::LambdaP;
+use crate::syntax::ast::LoadArgP;
+use crate::syntax::ast::LoadP;
+use crate::syntax::ast::ParameterP;
+use crate::syntax::ast::StmtP;
+
+const INDENT: &str = "    ";
+
+/// Precedence of `lambda` and conditional expressions.
+const PREC_TEST: u8 = 0;
+/// Precedence of the operand of a conditional expression or comprehension clause.
+const PREC_OR_TEST: u8 = 1;
+/// Precedence of prefix `not`, same as comparisons.
+const PREC_NOT: u8 = 5;
+/// Precedence of unary `-`, `+` and `~`.
+const PREC_UNARY: u8 = 19;
+/// Precedence of attribute access, calls, indexing and slicing.
+const PREC_POSTFIX: u8 = 21;
+/// Precedence of atoms, which never need parentheses.
+const PREC_ATOM: u8 = 22;
+
+fn bin_op_precedence(op: BinOp) -> u8 {
+    // Matches the binding powers used by the parser.
+    match op {
+        BinOp::Or => 1,
+        BinOp::And => 3,
+        BinOp::Equal
+        | BinOp::NotEqual
+        | BinOp::Less
+        | BinOp::Greater
+        | BinOp::LessOrEqual
+        | BinOp::GreaterOrEqual
+        | BinOp::In
+        | BinOp::NotIn => 5,
+        BinOp::BitOr => 7,
+        BinOp::BitXor => 9,
+        BinOp::BitAnd => 11,
+        BinOp::LeftShift | BinOp::RightShift => 13,
+        BinOp::Add | BinOp::Subtract => 15,
+        BinOp::Multiply | BinOp::Percent | BinOp::Divide | BinOp::FloorDivide => 17,
+    }
+}
+
+fn expr_precedence(expr: &AstExpr) -> u8 {
+    match &expr.node {
+        ExprP::Lambda(_) | ExprP::If(_) => PREC_TEST,
+        ExprP::Op(_, op, _) => bin_op_precedence(*op),
+        ExprP::Not(_) => PREC_NOT,
+        ExprP::Minus(_) | ExprP::Plus(_) | ExprP::BitNot(_) => PREC_UNARY,
+        ExprP::Dot(..)
+        | ExprP::Call(..)
+        | ExprP::Index(_)
+        | ExprP::Index2(_)
+        | ExprP::Slice(..) => PREC_POSTFIX,
+        ExprP::Tuple(_)
+        | ExprP::Identifier(_)
+        | ExprP::Literal(_)
+        | ExprP::List(_)
+        | ExprP::Dict(_)
+        | ExprP::ListComprehension(..)
+        | ExprP::DictComprehension(..)
+        | ExprP::FString(_) => PREC_ATOM,
+    }
+}
+
+fn pos(pos: Pos) -> usize {
+    pos.get() as usize
+}
+
+impl AstModule {
+    /// Print the module in the canonical Starlark style.
+    ///
+    /// Indentation is normalized to four spaces, operators and commas are spaced
+    /// uniformly and redundant parentheses are dropped. Comments and single blank
+    /// lines between statements are preserved. Collections, calls and signatures
+    /// which span several lines in the source are printed one item per line with
+    /// a trailing comma, the rest stay on one line. Literals are copied verbatim.
+    ///
+    /// Formatting is idempotent: formatting the output again produces the same text.
+    pub fn format(&self) -> String {
+        let mut formatter = Formatter {
+            source: self.codemap.source(),
+            comments: self.comments(),
+            next_comment: 0,
+            out: String::new(),
+            indent: 0,
+            last_end: 0,
+            block_start: true,
+        };
+        formatter.stmts(&self.statement);
+        while let Some(comment) = formatter.peek_comment() {
+            formatter.separate(comment.begin());
+            formatter.comment_line(comment);
+        }
+        formatter.out
+    }
+}
+
+struct Formatter<'a> {
+    source: &'a str,
+    comments: &'a [Span],
+    /// Index of the first comment not yet written.
+    next_comment: usize,
+    out: String,
+    indent: usize,
+    /// End of the last line-level element (statement, item or comment) written,
+    /// used to find blank lines in the source.
+    last_end: usize,
+    /// Nothing has been written in the current block or multi-line collection yet.
+    block_start: bool,
+}
+
+impl<'a> Formatter<'a> {
+    fn text(&self, span: Span) -> &'a str {
+        &self.source[pos(span.begin())..pos(span.end())]
+    }
+
+    fn has_newline(&self, from: Pos, to: Pos) -> bool {
+        from < to && self.source[pos(from)..pos(to)].contains('\n')
+    }
+
+    fn is_multiline(&self, span: Span) -> bool {
+        self.has_newline(span.begin(), span.end())
+    }
+
+    fn line_start(&self, at: usize) -> usize {
+        self.source[..at].rfind('\n').map_or(0, |i| i + 1)
+    }
+
+    fn column(&self, at: Pos) -> usize {
+        pos(at) - self.line_start(pos(at))
+    }
+
+    /// Whether nothing but whitespace precedes the comment on its line.
+    fn is_own_line(&self, comment: Span) -> bool {
+        let begin = pos(comment.begin());
+        self.source[self.line_start(begin)..begin].trim().is_empty()
+    }
+
+    /// Whether the expression is preceded by an opening parenthesis in the source.
+    /// Tuple spans do not include their parentheses.
+    fn is_parenthesized(&self, span: Span) -> bool {
+        self.source[..pos(span.begin())].trim_end().ends_with('(')
+    }
+
+    fn peek_comment(&self) -> Option<Span> {
+        self.comments.get(self.next_comment).copied()
+    }
+
+    fn write(&mut self, s: &str) {
+        self.out.push_str(s);
+    }
+
+    fn write_indent(&mut self) {
+        for _ in 0..self.indent {
+            self.out.push_str(INDENT);
+        }
+    }
+
+    /// Emit a blank line if the source had at least one between the previous
+    /// element and `at`. Runs of blank lines are collapsed into one.
+    fn separate(&mut self, at: Pos) {
+        let at = pos(at);
+        if !self.block_start
+            && self.last_end < at
+            && self.source[self.last_end..at].matches('\n').count() >= 2
+        {
+            self.out.push('\n');
+        }
+    }
+
+    fn comment_line(&mut self, comment: Span) {
+        self.write_indent();
+        self.write(self.text(comment).trim_end());
+        self.out.push('\n');
+        self.next_comment += 1;
+        self.last_end = pos(comment.end());
+        self.block_start = false;
+    }
+
+    /// Write the comments which start before `before`, each on its own line.
+    fn leading_comments(&mut self, before: Pos) {
+        while let Some(comment) = self.peek_comment()
+            && comment.begin() < before
+        {
+            self.separate(comment.begin());
+            self.comment_line(comment);
+        }
+    }
+
+    /// Append the next comment to the current line if it is on the source line
+    /// where `after` is.
+    fn trailing_comment(&mut self, after: Pos) {
+        if let Some(comment) = self.peek_comment()
+            && comment.begin() >= after
+            && !self.has_newline(after, comment.begin())
+        {
+            self.write("  ");
+            self.write(self.text(comment).trim_end());
+            self.next_comment += 1;
+            self.last_end = pos(comment.end());
+        }
+    }
+
+    /// Finish the line of a simple statement ending at `end`.
+    fn end_line(&mut self, end: Pos) {
+        // Comments inside the statement not attached to any collection item,
+        // e.g. between operands of a multi-line binary expression.
+        let mut inner = Vec::new();
+        while let Some(comment) = self.peek_comment()
+            && comment.begin() < end
+        {
+            inner.push(comment);
+            self.next_comment += 1;
+        }
+        self.last_end = pos(end);
+        self.trailing_comment(end);
+        self.out.push('\n');
+        for comment in inner {
+            self.write_indent();
+            self.write(self.text(comment).trim_end());
+            self.out.push('\n');
+        }
+    }
+
+    fn stmts(&mut self, stmt: &AstStmt) {
+        match &stmt.node {
+            StmtP::Statements(stmts) => {
+                for stmt in stmts {
+                    self.stmts(stmt);
+                }
+            }
+            _ => self.stmt(stmt),
+        }
+    }
+
+    fn stmt(&mut self, stmt: &AstStmt) {
+        self.leading_comments(stmt.span.begin());
+        self.separate(stmt.span.begin());
+        self.block_start = false;
+        self.write_indent();
+        match &stmt.node {
+            StmtP::Break => {
+                self.write("break");
+                self.end_line(stmt.span.end());
+            }
+            StmtP::Continue => {
+                self.write("continue");
+                self.end_line(stmt.span.end());
+            }
+            StmtP::Pass => {
+                self.write("pass");
+                self.end_line(stmt.span.end());
+            }
+            StmtP::Return(value) => {
+                self.write("return");
+                if let Some(value) = value {
+                    self.write(" ");
+                    self.expr_top(value);
+                }
+                self.end_line(stmt.span.end());
+            }
+            StmtP::Expression(expr) => {
+                self.expr_top(expr);
+                self.end_line(stmt.span.end());
+            }
+            StmtP::Assign(AssignP { lhs, ty, rhs }) => {
+                self.target_top(lhs);
+                if let Some(ty) = ty {
+                    self.write(": ");
+                    self.type_expr(ty);
+                }
+                self.write(" = ");
+                self.expr_top(rhs);
+                self.end_line(stmt.span.end());
+            }
+            StmtP::AssignModify(lhs, op, rhs) => {
+                self.target(lhs);
+                self.write(&op.to_string());
+                self.expr_top(rhs);
+                self.end_line(stmt.span.end());
+            }
+            StmtP::Load(load) => {
+                self.load(load, self.is_multiline(stmt.span), stmt.span.end());
+                self.end_line(stmt.span.end());
+            }
+            StmtP::If(..) | StmtP::IfElse(..) => self.if_stmt("if", stmt),
+            StmtP::For(ForP { var, over, body }) => {
+                self.write("for ");
+                self.target_top(var);
+                self.write(" in ");
+                self.expr(over, PREC_TEST);
+                self.write(":");
+                self.block(body, stmt.span.begin());
+            }
+            StmtP::Def(def) => self.def(def, stmt.span.begin()),
+            StmtP::Statements(_) => unreachable!("flattened by `stmts`"),
+        }
+    }
+
+    /// Write the indented body of a compound statement, after its header.
+    fn block(&mut self, body: &AstStmt, parent: Pos) {
+        // A comment after the colon of the header.
+        if let Some(comment) = self.peek_comment()
+            && comment.begin() < body.span.begin()
+            && !self.is_own_line(comment)
+        {
+            self.write("  ");
+            self.write(self.text(comment).trim_end());
+            self.next_comment += 1;
+        }
+        self.out.push('\n');
+        self.indent += 1;
+        self.block_start = true;
+        self.stmts(body);
+        // Comments indented under the block which follow its last statement.
+        let parent_column = self.column(parent);
+        while let Some(comment) = self.peek_comment()
+            && self.is_own_line(comment)
+            && self.column(comment.begin()) > parent_column
+            && self.source[self.last_end..pos(comment.begin())]
+                .trim_matches(|c: char| c.is_whitespace() || c == ';')
+                .is_empty()
+        {
+            self.separate(comment.begin());
+            self.comment_line(comment);
+        }
+        self.indent -= 1;
+    }
+
+    /// Write an `if` statement, or an `elif` branch when `keyword` is `elif`.
+    fn if_stmt(&mut self, keyword: &str, stmt: &AstStmt) {
+        let (cond, then_block, else_block) = match &stmt.node {
+            StmtP::If(cond, then_block) => (cond, &**then_block, None),
+            StmtP::IfElse(cond, then_else) => (cond, &then_else.0, Some(&then_else.1)),
+            _ => unreachable!("not an `if` statement"),
+        };
+        self.write(keyword);
+        self.write(" ");
+        self.expr(cond, PREC_TEST);
+        self.write(":");
+        self.block(then_block, stmt.span.begin());
+        let Some(else_block) = else_block else {
+            return;
+        };
+        // The parser gives an `elif` branch the span starting at the keyword.
+        let is_elif = self.source[pos(else_block.span.begin())..].starts_with("elif");
+        let parent_column = self.column(stmt.span.begin());
+        while let Some(comment) = self.peek_comment()
+            && comment.begin() < else_block.span.begin()
+            && (is_elif || self.column(comment.begin()) <= parent_column)
+        {
+            self.comment_line(comment);
+        }
+        self.write_indent();
+        if is_elif {
+            self.if_stmt("elif", else_block);
+        } else {
+            self.write("else:");
+            self.block(else_block, stmt.span.begin());
+        }
+    }
+
+    fn def(&mut self, def: &DefP<AstNoPayload>, begin: Pos) {
+        let DefP {
+            name,
+            params,
+            return_type,
+            body,
+            payload: _,
+        } = def;
+        self.write("def ");
+        self.write(&name.node.ident);
+        let multiline = params
+            .last()
+            .is_some_and(|last| self.has_newline(name.span.end(), last.span.end()));
+        let end = params
+            .last()
+            .map_or(name.span.end(), |last| last.span.end());
+        self.write("(");
+        self.sequence(params, multiline, end, |p| p.span, |this, p| this.param(p));
+        self.write(")");
+        if let Some(return_type) = return_type {
+            self.write(" -> ");
+            self.type_expr(return_type);
+        }
+        self.write(":");
+        self.block(body, begin);
+    }
+
+    fn load(&mut self, load: &LoadP<AstNoPayload>, multiline: bool, end: Pos) {
+        self.write("load(");
+        if multiline {
+            self.indent += 1;
+            self.block_start = true;
+            self.out.push('\n');
+            self.write_indent();
+            self.write(self.text(load.module.span));
+            self.write(",");
+            self.last_end = pos(load.module.span.end());
+            self.trailing_comment(load.module.span.end());
+            self.out.push('\n');
+            self.block_start = false;
+            for arg in &load.args {
+                self.leading_comments(arg.span().begin());
+                self.separate(arg.span().begin());
+                self.write_indent();
+                self.load_arg(arg);
+                self.write(",");
+                self.last_end = pos(arg.span_with_trailing_comma().end());
+                self.trailing_comment(arg.span_with_trailing_comma().end());
+                self.out.push('\n');
+            }
+            self.leading_comments(end);
+            self.indent -= 1;
+            self.write_indent();
+        } else {
+            self.write(self.text(load.module.span));
+            for arg in &load.args {
+                self.write(", ");
+                self.load_arg(arg);
+            }
+        }
+        self.write(")");
+    }
+
+    fn load_arg(&mut self, arg: &LoadArgP<AstNoPayload>) {
+        if arg.local.node.ident != arg.their.node {
+            self.write(&arg.local.node.ident);
+            self.write(" = ");
+        }
+        self.write(self.text(arg.their.span));
+    }
+
+    /// Write comma-separated items between brackets written by the caller.
+    ///
+    /// Multi-line lists put each item on its own line followed by a comma,
+    /// with comments kept next to the items. `end` bounds the comments which
+    /// belong before the closing bracket.
+    fn sequence<T>(
+        &mut self,
+        items: &[T],
+        multiline: bool,
+        end: Pos,
+        span: impl Fn(&T) -> Span,
+        item: impl Fn(&mut Self, &T),
+    ) {
+        if multiline {
+            self.indent += 1;
+            self.block_start = true;
+            self.out.push('\n');
+            for x in items {
+                let span = span(x);
+                self.leading_comments(span.begin());
+                self.separate(span.begin());
+                self.block_start = false;
+                self.write_indent();
+                item(self, x);
+                self.write(",");
+                self.last_end = pos(span.end());
+                self.trailing_comment(span.end());
+                self.out.push('\n');
+            }
+            self.leading_comments(end);
+            self.block_start = false;
+            self.indent -= 1;
+            self.write_indent();
+        } else {
+            for (i, x) in items.iter().enumerate() {
+                if i != 0 {
+                    self.write(", ");
+                }
+                item(self, x);
+            }
+        }
+    }
+
+    /// Write an expression in statement position, where a tuple may appear
+    /// without parentheses.
+    fn expr_top(&mut self, expr: &AstExpr) {
+        match &expr.node {
+            ExprP::Tuple(items)
+                if items.len() > 1
+                    && !self.is_multiline(expr.span)
+                    && !self.is_parenthesized(expr.span) =>
+            {
+                for (i, x) in items.iter().enumerate() {
+                    if i != 0 {
+                        self.write(", ");
+                    }
+                    self.expr(x, PREC_TEST);
+                }
+            }
+            _ => self.expr(expr, PREC_TEST),
+        }
+    }
+
+    /// Write an expression, parenthesized if it binds weaker than `precedence`.
+    fn expr(&mut self, expr: &AstExpr, precedence: u8) {
+        if expr_precedence(expr) < precedence {
+            self.write("(");
+            self.expr_unparenthesized(expr);
+            self.write(")");
+        } else {
+            self.expr_unparenthesized(expr);
+        }
+    }
+
+    fn expr_unparenthesized(&mut self, expr: &AstExpr) {
+        match &expr.node {
+            ExprP::Tuple(items) => {
+                let multiline = self.is_multiline(expr.span);
+                self.write("(");
+                self.sequence(
+                    items,
+                    multiline,
+                    expr.span.end(),
+                    |x| x.span,
+                    |this, x| this.expr(x, PREC_TEST),
+                );
+                if items.len() == 1 && !multiline {
+                    self.write(",");
+                }
+                self.write(")");
+            }
+            ExprP::Dot(object, attr) => {
+                self.postfix_receiver(object);
+                self.write(".");
+                self.write(&attr.node);
+            }
+            ExprP::Call(f, args) => {
+                self.expr(f, PREC_POSTFIX);
+                let multiline = self.has_newline(f.span.end(), expr.span.end());
+                self.write("(");
+                self.sequence(
+                    &args.args,
+                    multiline,
+                    expr.span.end(),
+                    |x| x.span,
+                    |this, x| this.argument(x),
+                );
+                self.write(")");
+            }
+            ExprP::Index(array_index) => {
+                let (array, index) = &**array_index;
+                self.postfix_receiver(array);
+                self.write("[");
+                self.expr(index, PREC_TEST);
+                self.write("]");
+            }
+            ExprP::Index2(array_indices) => {
+                let (array, index0, index1) = &**array_indices;
+                self.postfix_receiver(array);
+                self.write("[");
+                self.expr(index0, PREC_TEST);
+                self.write(", ");
+                self.expr(index1, PREC_TEST);
+                self.write("]");
+            }
+            ExprP::Slice(array, start, stop, step) => {
+                self.postfix_receiver(array);
+                self.write("[");
+                if let Some(start) = start {
+                    self.expr(start, PREC_TEST);
+                }
+                self.write(":");
+                if let Some(stop) = stop {
+                    self.expr(stop, PREC_TEST);
+                }
+                if let Some(step) = step {
+                    self.write(":");
+                    self.expr(step, PREC_TEST);
+                }
+                self.write("]");
+            }
+            ExprP::Identifier(ident) => self.write(&ident.node.ident),
+            ExprP::Lambda(LambdaP {
+                params,
+                body,
+                payload: _,
+            }) => {
+                self.write("lambda");
+                for (i, param) in params.iter().enumerate() {
+                    self.write(if i == 0 { " " } else { ", " });
+                    self.param(param);
+                }
+                self.write(": ");
+                self.expr(body, PREC_TEST);
+            }
+            ExprP::Literal(AstLiteral::Ellipsis) => self.write("..."),
+            ExprP::Literal(_) | ExprP::FString(_) => self.write(self.text(expr.span)),
+            ExprP::Not(x) => {
+                self.write("not ");
+                self.expr(x, PREC_NOT);
+            }
+            ExprP::Minus(x) => {
+                self.write("-");
+                self.expr(x, PREC_UNARY);
+            }
+            ExprP::Plus(x) => {
+                self.write("+");
+                self.expr(x, PREC_UNARY);
+            }
+            ExprP::BitNot(x) => {
+                self.write("~");
+                self.expr(x, PREC_UNARY);
+            }
+            ExprP::Op(lhs, op, rhs) => {
+                let precedence = bin_op_precedence(*op);
+                // Comparisons are not associative, so neither operand may be one.
+                let lhs_precedence = if precedence == bin_op_precedence(BinOp::Equal) {
+                    precedence + 1
+                } else {
+                    precedence
+                };
+                self.expr(lhs, lhs_precedence);
+                self.write(&op.to_string());
+                self.expr(rhs, precedence + 1);
+            }
+            ExprP::If(cond_then_else) => {
+                let (cond, then_expr, else_expr) = &**cond_then_else;
+                self.expr(then_expr, PREC_OR_TEST);
+                self.write(" if ");
+                self.expr(cond, PREC_OR_TEST);
+                self.write(" else ");
+                self.expr(else_expr, PREC_TEST);
+            }
+            ExprP::List(items) => {
+                self.write("[");
+                self.sequence(
+                    items,
+                    self.is_multiline(expr.span),
+                    expr.span.end(),
+                    |x| x.span,
+                    |this, x| this.expr(x, PREC_TEST),
+                );
+                self.write("]");
+            }
+            ExprP::Dict(entries) => {
+                self.write("{");
+                self.sequence(
+                    entries,
+                    self.is_multiline(expr.span),
+                    expr.span.end(),
+                    |(k, v)| k.span.merge(v.span),
+                    |this, (k, v)| {
+                        this.expr(k, PREC_TEST);
+                        this.write(": ");
+                        this.expr(v, PREC_TEST);
+                    },
+                );
+                self.write("}");
+            }
+            ExprP::ListComprehension(item, for_clause, clauses) => {
+                self.write("[");
+                self.comprehension(expr.span, item.span, for_clause, clauses, |this| {
+                    this.expr(item, PREC_TEST)
+                });
+                self.write("]");
+            }
+            ExprP::DictComprehension(entry, for_clause, clauses) => {
+                let (k, v) = &**entry;
+                self.write("{");
+                self.comprehension(expr.span, k.span, for_clause, clauses, |this| {
+                    this.expr(k, PREC_TEST);
+                    this.write(": ");
+                    this.expr(v, PREC_TEST);
+                });
+                self.write("}");
+            }
+        }
+    }
+
+    fn postfix_receiver(&mut self, object: &AstExpr) {
+        // `1.x` would be lexed as a float.
+        if matches!(&object.node, ExprP::Literal(AstLiteral::Int(_))) {
+            self.write("(");
+            self.expr(object, PREC_TEST);
+            self.write(")");
+        } else {
+            self.expr(object, PREC_POSTFIX);
+        }
+    }
+
+    /// Write a comprehension between brackets written by the caller.
+    /// A multi-line comprehension puts the item and each clause on its own line.
+    fn comprehension(
+        &mut self,
+        span: Span,
+        item_span: Span,
+        for_clause: &ForClause,
+        clauses: &[Clause],
+        item: impl Fn(&mut Self),
+    ) {
+        let multiline = self.is_multiline(span);
+        if multiline {
+            self.indent += 1;
+            self.block_start = true;
+            self.out.push('\n');
+            self.leading_comments(item_span.begin());
+            self.write_indent();
+            item(self);
+            self.out.push('\n');
+            self.block_start = false;
+        } else {
+            item(self);
+        }
+        self.comprehension_for(for_clause, multiline);
+        for clause in clauses {
+            match clause {
+                ClauseP::For(for_clause) => self.comprehension_for(for_clause, multiline),
+                ClauseP::If(cond) => {
+                    if multiline {
+                        self.leading_comments(cond.span.begin());
+                        self.write_indent();
+                        self.write("if ");
+                    } else {
+                        self.write(" if ");
+                    }
+                    self.expr(cond, PREC_OR_TEST);
+                    if multiline {
+                        self.out.push('\n');
+                    }
+                }
+            }
+        }
+        if multiline {
+            self.leading_comments(span.end());
+            self.indent -= 1;
+            self.write_indent();
+        }
+    }
+
+    fn comprehension_for(&mut self, for_clause: &ForClause, multiline: bool) {
+        if multiline {
+            self.leading_comments(for_clause.var.span.begin());
+            self.write_indent();
+            self.write("for ");
+        } else {
+            self.write(" for ");
+        }
+        self.target_top(&for_clause.var);
+        self.write(" in ");
+        self.expr(&for_clause.over, PREC_OR_TEST);
+        if multiline {
+            self.out.push('\n');
+        }
+    }
+
+    fn argument(&mut self, arg: &AstArgument) {
+        match &arg.node {
+            ArgumentP::Positional(x) => self.expr(x, PREC_TEST),
+            ArgumentP::Named(name, x) => {
+                self.write(&name.node);
+                self.write(" = ");
+                self.expr(x, PREC_TEST);
+            }
+            ArgumentP::Args(x) => {
+                self.write("*");
+                self.expr(x, PREC_TEST);
+            }
+            ArgumentP::KwArgs(x) => {
+                self.write("**");
+                self.expr(x, PREC_TEST);
+            }
+        }
+    }
+
+    fn param(&mut self, param: &AstParameter) {
+        let (prefix, name, ty, default) = match &param.node {
+            ParameterP::Slash => return self.write("/"),
+            ParameterP::NoArgs => return self.write("*"),
+            ParameterP::Normal(name, ty, default) => ("", name, ty, default.as_deref()),
+            ParameterP::Args(name, ty) => ("*", name, ty, None),
+            ParameterP::KwArgs(name, ty) => ("**", name, ty, None),
+        };
+        self.write(prefix);
+        self.write(&name.node.ident);
+        if let Some(ty) = ty {
+            self.write(": ");
+            self.type_expr(ty);
+        }
+        if let Some(default) = default {
+            self.write(" = ");
+            self.expr(default, PREC_TEST);
+        }
+    }
+
+    fn type_expr(&mut self, ty: &AstTypeExpr) {
+        self.expr(&ty.node.expr, PREC_TEST);
+    }
+
+    /// Write an assignment target in statement position, where a tuple may
+    /// appear without parentheses.
+    fn target_top(&mut self, target: &AstAssignTarget) {
+        match &target.node {
+            AssignTargetP::Tuple(items)
+                if items.len() > 1
+                    && !self.is_multiline(target.span)
+                    && !self.is_parenthesized(target.span) =>
+            {
+                for (i, x) in items.iter().enumerate() {
+                    if i != 0 {
+                        self.write(", ");
+                    }
+                    self.target(x);
+                }
+            }
+            _ => self.target(target),
+        }
+    }
+
+    fn target(&mut self, target: &AstAssignTarget) {
+        match &target.node {
+            AssignTargetP::Tuple(items) => {
+                self.write("(");
+                for (i, x) in items.iter().enumerate() {
+                    if i != 0 {
+                        self.write(", ");
+                    }
+                    self.target(x);
+                }
+                if items.len() == 1 {
+                    self.write(",");
+                }
+                self.write(")");
+            }
+            AssignTargetP::Index(array_index) => {
+                let (array, index) = &**array_index;
+                self.postfix_receiver(array);
+                self.write("[");
+                self.expr(index, PREC_TEST);
+                self.write("]");
+            }
+            AssignTargetP::Dot(object, attr) => {
+                self.postfix_receiver(object);
+                self.write(".");
+                self.write(&attr.node);
+            }
+            AssignTargetP::Identifier(ident) => self.write(&ident.node.ident),
+        }
+    }
+}
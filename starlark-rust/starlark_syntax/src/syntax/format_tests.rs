@@ -0,0 +1,225 @@
+/*
+ * Copyright 2018 The Starlark in Rust Authors.
+ * Copyright (c) Facebook, Inc. and its affiliates.
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     https://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::fmt::Write;
+
+use crate::golden_test_template::golden_test_template;
+use crate::syntax::AstModule;
+use crate::syntax::Dialect;
+
+fn format(program: &str) -> String {
+    AstModule::parse(
+        "format.bzl",
+        program.to_owned(),
+        &Dialect::AllOptionsInternal,
+    )
+    .unwrap_or_else(|e| panic!("Failed to parse:\n{program}\nError: {e}"))
+    .format()
+}
+
+fn format_golden_test(name: &str, program: &str) {
+    let program = program.trim();
+    let formatted = format(program);
+    assert_eq!(
+        formatted,
+        format(&formatted),
+        "Formatting is not idempotent for:\n{program}"
+    );
+
+    let mut out = String::new();
+    writeln!(out, "Program:").unwrap();
+    writeln!(out, "{program}").unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "Formatted:").unwrap();
+    write!(out, "{formatted}").unwrap();
+
+    golden_test_template(&format!("src/syntax/format_tests/{name}.golden"), &out);
+}
+
+#[test]
+fn test_simple_statements() {
+    format_golden_test(
+        "simple_statements",
+        r#"
+x=1
+y ,z = 2,3
+(a, b) = (4, 5)
+x+=1; y-=1
+print( x,y )
+def f():
+  pass
+  return
+"#,
+    );
+}
+
+#[test]
+fn test_comments() {
+    format_golden_test(
+        "comments",
+        r#"
+# Leading comment.
+load("//:a.bzl", "a")  # Trailing comment.
+
+
+# After two blank lines.
+def f(x):  # Header comment.
+  # Inside the body.
+  if x:
+    return 1  # Returns one.
+    # End of the if body.
+  # Before else.
+  else:
+    return 2
+  # End of the function body.
+
+# End of file.
+"#,
+    );
+}
+
+#[test]
+fn test_collections() {
+    format_golden_test(
+        "collections",
+        r#"
+srcs = ["a.c", "b.c"]
+deps = [
+  "//foo:bar", # The bar.
+  # Comment before baz.
+  "//foo:baz"
+
+  ,"//foo:qux",
+  # Comment at the end.
+]
+d = {"a":1,
+  "b" : 2}
+t = (1,)
+empty = [  ]
+"#,
+    );
+}
+
+#[test]
+fn test_calls() {
+    format_golden_test(
+        "calls",
+        r#"
+cc_library(name="foo", srcs=glob(["*.c"]), **kwargs)
+cc_library(
+  name="bar",
+  deps=[":foo"],  # Depends on foo.
+  *args
+)
+x = a.b(1)[2].c
+"#,
+    );
+}
+
+#[test]
+fn test_precedence() {
+    format_golden_test(
+        "precedence",
+        r#"
+x = (a + b) * c
+y = a + (b * c)
+z = (a - b) - (c - d)
+w = not (a and b) or (c if d else e)
+v = -(a + 1)
+u = (lambda x: x)(1)
+s = (1).real
+t = (a < b) == c
+"#,
+    );
+}
+
+#[test]
+fn test_def() {
+    format_golden_test(
+        "def",
+        r#"
+def f(a,b:int=1,*args,c,**kwargs)->str:
+    """Docstring."""
+    return a
+def g(
+  x,
+  # Comment on y.
+  y = None):
+  pass
+"#,
+    );
+}
+
+#[test]
+fn test_control_flow() {
+    format_golden_test(
+        "control_flow",
+        r#"
+for k , v in d.items():
+  if k: continue
+  elif v:
+     break
+  else:
+     pass
+x = [i for i in range(10) if i % 2]
+y = {
+  k: v
+  for k, v in d.items()
+  if v
+}
+"#,
+    );
+}
+
+#[test]
+fn test_load() {
+    format_golden_test(
+        "load",
+        r#"
+load("//:a.bzl", "a", b="c")
+load(
+    "//:d.bzl",
+    # The e symbol.
+    "e",
+    f = "g",
+)
+"#,
+    );
+}
+
+#[test]
+fn test_literals_verbatim() {
+    format_golden_test(
+        "literals_verbatim",
+        r#"
+a = 'single'
+b = r"raw\d"
+c = 0x10 + 1e3
+d = f"x={x}"
+e = b"\x00"
+f = """
+  triple
+"""
+"#,
+    );
+}
+
+#[test]
+fn test_empty() {
+    assert_eq!("", format(""));
+    assert_eq!("# Only a comment.\n", format("\n\n# Only a comment.\n\n"));
+}
@@ -8,6 +8,7 @@
 
 # pyre-strict
 
+import json
 
 import pytest
 from buck2.tests.e2e_util.api.buck import Buck
@@ -40,3 +41,30 @@ async def test_audit_visibility(buck: Buck, rule: str, passes: bool) -> None:
             buck.audit_visibility(rule),
             stderr_regex=f"not visible to `{rule}`",
         )
+
+
+@buck_test()
+async def test_audit_visibility_explain_json(buck: Buck) -> None:
+    failure = await expect_failure(
+        buck.audit_visibility("self//:fail1", "--explain", "--json"),
+    )
+    [explanation] = json.loads(failure.stdout)
+    assert explanation["target"] == "self//:fail1"
+    assert explanation["dependency"] == "self//subdir:badtarget"
+    assert explanation["visibility"] == ["self//:nothing"]
+    assert explanation["visibility_allows"] is False
+    assert explanation["visibility_cap_allows"] is True
+    assert "visibility" in explanation["package_values"]
+    assert explanation["suggested_pattern"] == "self//:fail1"
+
+
+@buck_test()
+async def test_audit_visibility_explain(buck: Buck) -> None:
+    failure = await expect_failure(
+        buck.audit_visibility("self//:fail1", "--explain"),
+    )
+    assert "`self//:fail1` depends on `self//subdir:badtarget`" in failure.stdout
+    assert (
+        'suggestion: add `"self//:fail1"` to the `visibility` of `self//subdir:badtarget`'
+        in failure.stdout
+    )
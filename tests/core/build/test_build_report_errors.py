@@ -405,3 +405,44 @@ if not running_on_windows() and not running_on_mac():
             ),
             rel_path="fixtures/test_truncate_error_content.golden.json",
         )
+
+    @buck_test()
+    async def test_build_report_sarif(buck: Buck, tmp_path: Path) -> None:
+        report = tmp_path / "build-report.sarif"
+        await expect_failure(
+            buck.build(
+                "--build-report",
+                str(report),
+                "--build-report-options",
+                "sarif",
+                "//fail_action:fail_many_with_error_handler",
+            )
+        )
+        with open(report) as f:
+            sarif = json.loads(f.read())
+
+        assert sarif["version"] == "2.1.0"
+        [run] = sarif["runs"]
+        rules = [rule["id"] for rule in run["tool"]["driver"]["rules"]]
+        assert "syntax" in rules
+
+        results = {r["ruleId"]: r for r in run["results"]}
+        syntax = results["syntax"]
+        assert syntax["level"] == "error"
+        assert syntax["message"]["text"] == "Syntax error!"
+        assert rules[syntax["ruleIndex"]] == "syntax"
+        [location] = syntax["locations"]
+        assert location["physicalLocation"] == {
+            "artifactLocation": {
+                "uri": "not_really_the_right_file",
+                "uriBaseId": "%SRCROOT%",
+            },
+            "region": {"startLine": 1},
+        }
+        [owner] = location["logicalLocations"]
+        assert owner["fullyQualifiedName"].startswith(
+            "root//fail_action:fail_many_with_error_handler "
+        )
+
+        # The action whose error handler found no location is still reported.
+        assert len(run["results"]) == 2
@@ -0,0 +1,60 @@
+# Copyright (c) Meta Platforms, Inc. and affiliates.
+#
+# This source code is dual-licensed under either the MIT license found in the
+# LICENSE-MIT file in the root directory of this source tree or the Apache
+# License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+# of this source tree. You may select, at your option, one of the
+# above-listed licenses.
+
+# pyre-strict
+
+
+from buck2.tests.e2e_util.api.buck import Buck
+from buck2.tests.e2e_util.buck_workspace import buck_test
+from buck2.tests.e2e_util.helper.utils import random_string
+
+
+def dry_run_outcomes(stdout: str) -> list[str]:
+    return [line.split("\t")[0] for line in stdout.splitlines()]
+
+
+@buck_test()
+async def test_dry_run_would_execute(buck: Buck) -> None:
+    # Make sure the action is not cached.
+    with open(buck.cwd / "input.txt", "w") as f:
+        f.write(random_string())
+
+    result = await buck.build("root//:simple", "--remote-only", "--dry-run")
+
+    assert dry_run_outcomes(result.stdout) == ["would_execute"]
+    assert "0 cached, 1 would execute" in result.stderr
+    # Nothing ran, so there is nothing to materialize.
+    assert list((buck.cwd / "buck-out").rglob("output")) == []
+
+
+@buck_test()
+async def test_dry_run_cache_hit(buck: Buck) -> None:
+    with open(buck.cwd / "input.txt", "w") as f:
+        f.write(random_string())
+
+    await buck.build("root//:simple", "--remote-only")
+    # Restart so that the action isn't already known to the daemon.
+    await buck.kill()
+
+    result = await buck.build("root//:simple", "--remote-only", "--dry-run")
+
+    assert dry_run_outcomes(result.stdout) == ["cache_hit"]
+    assert "1 cached, 0 would execute" in result.stderr
+
+
+@buck_test()
+async def test_dry_run_does_not_write(buck: Buck) -> None:
+    await buck.build("root//:written", "--dry-run")
+
+    assert list((buck.cwd / "buck-out").rglob("written.txt")) == []
+
+    # The dry run must not leave anything behind that a real build would reuse.
+    await buck.build("root//:written")
+
+    [output] = list((buck.cwd / "buck-out").rglob("written.txt"))
+    assert output.read_text() == "written"
@@ -68,6 +68,19 @@ async def test_debug_flush_pgo_profile(buck: Buck) -> None:
     assert "was not flushed" in result.stderr
 
 
+@buck_test()
+async def test_debug_dice_why(buck: Buck) -> None:
+    config = ["-c", "buck2.dice_why_key_types=InterpreterResultsKey"]
+    await buck.uquery("root//:", *config)
+    (buck.cwd / "TARGETS.fixture").write_text("# Changed\n")
+    await buck.uquery("root//:", *config)
+
+    output = await buck.debug("dice-why", "--type", "InterpreterResultsKey")
+    assert "(InterpreterResultsKey)" in output.stdout
+    assert "TARGETS.fixture" in output.stdout
+    assert "changed at" in output.stdout
+
+
 @buck_test(skip_for_os=["windows", "darwin"])
 async def test_thread_dump(buck: Buck) -> None:
     # Make sure we don't start a daemon if there isn't one
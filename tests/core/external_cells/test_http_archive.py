@@ -0,0 +1,105 @@
+# Copyright (c) Meta Platforms, Inc. and affiliates.
+#
+# This source code is dual-licensed under either the MIT license found in the
+# LICENSE-MIT file in the root directory of this source tree or the Apache
+# License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+# of this source tree. You may select, at your option, one of the
+# above-listed licenses.
+
+# pyre-strict
+
+import functools
+import hashlib
+import http.server
+import tarfile
+import threading
+import typing
+from pathlib import Path
+
+from buck2.tests.e2e_util.api.buck import Buck
+from buck2.tests.e2e_util.asserts import expect_failure
+from buck2.tests.e2e_util.buck_workspace import buck_test
+
+
+def _make_archive(cwd: Path) -> Path:
+    """Pack `template` into a tarball with a leading `libfoo-1.0/` directory."""
+    dist = (cwd.parent / "dist").absolute()
+    dist.mkdir(parents=True, exist_ok=True)
+    archive = dist / "libfoo-1.0.tar.gz"
+    with tarfile.open(archive, "w:gz") as tar:
+        tar.add(cwd / "template", arcname="libfoo-1.0")
+    return archive
+
+
+def _sha256(path: Path) -> str:
+    return hashlib.sha256(path.read_bytes()).hexdigest()
+
+
+def _set_archive(urls: list[str], sha256: str, cwd: Path) -> None:
+    p = cwd / ".buckconfig"
+    data = p.read_text().splitlines()[:-2]
+    data.append(f"  urls = {', '.join(urls)}")
+    data.append(f"  sha256 = {sha256}")
+    p.write_text("\n".join(data))
+
+
+def _serve(directory: Path) -> typing.Tuple[http.server.HTTPServer, str]:
+    handler = functools.partial(
+        http.server.SimpleHTTPRequestHandler, directory=str(directory)
+    )
+    server = http.server.HTTPServer(("127.0.0.1", 0), handler)
+    threading.Thread(target=server.serve_forever, daemon=True).start()
+    return server, f"http://127.0.0.1:{server.server_port}"
+
+
+@buck_test()
+async def test_expand_external_file_url(buck: Buck) -> None:
+    archive = _make_archive(buck.cwd)
+    _set_archive([f"file://{archive}"], _sha256(archive), cwd=buck.cwd)
+
+    await buck.expand_external_cell("libfoo")
+    assert (buck.cwd / "libfoo" / "src.txt").read_text().strip() == (
+        "hello from archive"
+    )
+    assert "buildfile" in (buck.cwd / "libfoo" / ".buckconfig").read_text()
+
+
+@buck_test()
+async def test_build_from_http(buck: Buck) -> None:
+    archive = _make_archive(buck.cwd)
+    server, base = _serve(archive.parent)
+    try:
+        _set_archive(
+            [f"{base}/missing.tar.gz", f"{base}/{archive.name}"],
+            _sha256(archive),
+            cwd=buck.cwd,
+        )
+        res = await buck.build_without_report(
+            "libfoo//:t", "--show-full-simple-output"
+        )
+        assert Path(res.stdout.strip()).read_text().strip() == "hello from archive"
+    finally:
+        server.shutdown()
+
+
+@buck_test()
+async def test_no_refetch_on_restart(buck: Buck) -> None:
+    archive = _make_archive(buck.cwd)
+    _set_archive([f"file://{archive}"], _sha256(archive), cwd=buck.cwd)
+
+    await buck.build("libfoo//:t")
+    await buck.kill()
+
+    archive.unlink()
+    await buck.build("libfoo//:t")
+
+
+@buck_test()
+async def test_checksum_mismatch(buck: Buck) -> None:
+    archive = _make_archive(buck.cwd)
+    _set_archive([f"file://{archive}"], "0" * 64, cwd=buck.cwd)
+
+    await expect_failure(
+        buck.build("libfoo//:t"),
+        stderr_regex="Invalid sha256 digest",
+    )
@@ -66,6 +66,51 @@ async def test_action_divergence_command(buck: Buck) -> None:
     )
 
 
+@buck_test()
+async def test_no_action_graph_diff_command(buck: Buck) -> None:
+    await buck.build("//:simple", *with_buck2_output("foo"))
+    await buck.build("//:simple", *with_buck2_output("foo"))
+    out = await buck.log(
+        "diff", "action-graph", "--recent1", "1", "--recent2", "0", "--format=json"
+    )
+    lines = [json.loads(line) for line in out.stdout.splitlines() if line.strip()]
+    assert lines == [
+        {
+            "kind": "summary",
+            "summary": {
+                "first_actions": lines[0]["summary"]["first_actions"],
+                "second_actions": lines[0]["summary"]["second_actions"],
+                "appeared": 0,
+                "disappeared": 0,
+                "digest_changed": 0,
+                "execution_kind_changed": 0,
+                "duration_changed": 0,
+                "execution_kind_transitions": {},
+            },
+        }
+    ]
+
+
+@buck_test()
+async def test_action_graph_diff_command(buck: Buck) -> None:
+    await buck.build("//:non_det", *with_buck2_output("foo"))
+    await buck.build("//:non_det", *with_buck2_output("bar"))
+    out = await buck.log(
+        "diff", "action-graph", "--recent1", "1", "--recent2", "0", "--format=json"
+    )
+    lines = [json.loads(line) for line in out.stdout.splitlines() if line.strip()]
+    kinds = {(line["kind"], line.get("action")) for line in lines}
+    assert ("appeared", "prelude//:non_det (<unspecified>) (write bar.txt)") in kinds
+    assert (
+        "disappeared",
+        "prelude//:non_det (<unspecified>) (write foo.txt)",
+    ) in kinds
+    summary = lines[-1]
+    assert summary["kind"] == "summary"
+    assert summary["summary"]["appeared"] == 1
+    assert summary["summary"]["disappeared"] == 1
+
+
 @buck_test()
 async def test_no_config_diff_command(buck: Buck) -> None:
     await buck.build("//:simple", *with_buck2_output("foo"))
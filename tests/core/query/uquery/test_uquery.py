@@ -447,6 +447,25 @@ async def test_dot_compact(buck: Buck) -> None:
     )
 
 
+@buck_test(data_dir="bxl_simple")
+async def test_graph_output_formats(buck: Buck) -> None:
+    for output_format in ["json_graph", "graphml", "mermaid"]:
+        out = await buck.uquery(
+            "--output-format",
+            output_format,
+            "deps(root//bin:the_binary, 100, target_deps())",
+        )
+        golden(
+            output=out.stdout,
+            rel_path=f"bxl_simple/expected/graph_formats/deps.{output_format}.golden",
+        )
+
+    await expect_failure(
+        buck.uquery("--output-format", "mermaid", "buildfile(root//bin:the_binary)"),
+        stderr_regex="no edges to output as a graph",
+    )
+
+
 @buck_test(data_dir="bxl_simple")
 async def test_html(buck: Buck) -> None:
     uuid = "aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee"
@@ -0,0 +1,60 @@
+# Copyright (c) Meta Platforms, Inc. and affiliates.
+#
+# This source code is dual-licensed under either the MIT license found in the
+# LICENSE-MIT file in the root directory of this source tree or the Apache
+# License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+# of this source tree. You may select, at your option, one of the
+# above-listed licenses.
+
+# pyre-strict
+
+
+from pathlib import Path
+
+from buck2.tests.e2e_util.api.buck import Buck
+from buck2.tests.e2e_util.buck_workspace import buck_test
+
+
+async def targets_with_cache(buck: Buck, cache: Path) -> tuple[list[str], str]:
+    result = await buck.targets(
+        "//...", "--streaming", "--package-cache", str(cache)
+    )
+    return sorted(result.stdout.splitlines()), result.stderr
+
+
+@buck_test()
+async def test_package_cache_reused_by_cold_daemon(buck: Buck, tmp_path: Path) -> None:
+    cache = tmp_path / "cache"
+
+    targets, stderr = await targets_with_cache(buck, cache)
+    assert targets == ["root//a:target1", "root//a:target2", "root//b:target3"]
+    assert "EVALUATING a" in stderr
+    assert "EVALUATING b" in stderr
+
+    # A new daemon has nothing evaluated, so unchanged packages come from the cache.
+    await buck.kill()
+    cached_targets, stderr = await targets_with_cache(buck, cache)
+    assert cached_targets == targets
+    assert "EVALUATING" not in stderr
+
+
+@buck_test()
+async def test_package_cache_invalidated_by_loaded_file(
+    buck: Buck, tmp_path: Path
+) -> None:
+    cache = tmp_path / "cache"
+    await targets_with_cache(buck, cache)
+
+    with open(buck.cwd / "defs.bzl", "w") as f:
+        f.write('NAMES = ["target1", "target2", "target4"]\n')
+    await buck.kill()
+
+    targets, stderr = await targets_with_cache(buck, cache)
+    assert targets == [
+        "root//a:target1",
+        "root//a:target2",
+        "root//a:target4",
+        "root//b:target3",
+    ]
+    assert "EVALUATING a" in stderr
+    assert "EVALUATING b" not in stderr
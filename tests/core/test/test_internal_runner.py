@@ -10,6 +10,10 @@
 # pyre-strict
 
 
+import json
+import xml.etree.ElementTree as ET
+from pathlib import Path
+
 from buck2.tests.e2e_util.api.buck import Buck
 from buck2.tests.e2e_util.asserts import expect_failure
 from buck2.tests.e2e_util.buck_workspace import buck_test, env
@@ -53,3 +57,154 @@ async def test_internal_test_executor_timeout(buck: Buck) -> None:
         ),
         stderr_regex="Timeout: ",
     )
+
+
+@buck_test()
+@env("BUCK2_ALLOW_INTERNAL_TEST_RUNNER_DO_NOT_USE", "1")
+async def test_internal_test_executor_flaky_without_retries(buck: Buck) -> None:
+    await expect_failure(
+        buck.test(
+            ":flaky",
+            test_executor=INTERNAL_TEST_EXECUTOR,
+        ),
+        stderr_regex="Fail 1",
+    )
+
+
+@buck_test()
+@env("BUCK2_ALLOW_INTERNAL_TEST_RUNNER_DO_NOT_USE", "1")
+async def test_internal_test_executor_retries(buck: Buck) -> None:
+    result = await buck.test(
+        ":flaky",
+        "--",
+        "--retries",
+        "2",
+        test_executor=INTERNAL_TEST_EXECUTOR,
+    )
+    assert "Flaky 1" in result.stderr
+    assert "1 TESTS FLAKY" in result.stderr
+
+
+@buck_test()
+@env("BUCK2_ALLOW_INTERNAL_TEST_RUNNER_DO_NOT_USE", "1")
+async def test_internal_test_executor_retries_label(buck: Buck) -> None:
+    result = await buck.test(
+        ":flaky_with_retries_label",
+        test_executor=INTERNAL_TEST_EXECUTOR,
+    )
+    assert "Flaky 1" in result.stderr
+
+
+@buck_test()
+@env("BUCK2_ALLOW_INTERNAL_TEST_RUNNER_DO_NOT_USE", "1")
+async def test_internal_test_executor_retries_exhausted(buck: Buck) -> None:
+    await expect_failure(
+        buck.test(
+            ":always_fail",
+            "--",
+            "--retries",
+            "2",
+            test_executor=INTERNAL_TEST_EXECUTOR,
+        ),
+        stderr_regex="Fail 1",
+    )
+
+
+@buck_test()
+@env("BUCK2_ALLOW_INTERNAL_TEST_RUNNER_DO_NOT_USE", "1")
+async def test_internal_test_executor_quarantine(buck: Buck, tmp_path: Path) -> None:
+    quarantine = tmp_path / "quarantine.txt"
+    quarantine.write_text("# Known to be broken\nroot//:always_fail\n")
+    result = await buck.test(
+        ":always_fail",
+        ":trivial_pass",
+        "--",
+        "--quarantine-file",
+        str(quarantine),
+        test_executor=INTERNAL_TEST_EXECUTOR,
+    )
+    assert "Pass 1" in result.stderr
+    assert "Fail 1" in result.stderr
+
+
+SHARD_TARGETS = [f"root//:shard_{i}" for i in range(8)]
+
+
+async def run_shards(buck: Buck, reports: list[Path], *args: str) -> list[set[str]]:
+    """Runs every shard, returning the targets each one ran."""
+    shards = []
+    for index, report in enumerate(reports):
+        await buck.test(
+            "--shard",
+            f"{index + 1}/{len(reports)}",
+            "--build-report",
+            str(report),
+            *args,
+            *SHARD_TARGETS,
+            test_executor=INTERNAL_TEST_EXECUTOR,
+        )
+        durations = json.loads(report.read_text())["tests"].get("durations", {})
+        shards.append(set(durations))
+    return shards
+
+
+@buck_test()
+@env("BUCK2_ALLOW_INTERNAL_TEST_RUNNER_DO_NOT_USE", "1")
+async def test_internal_test_executor_shard(buck: Buck, tmp_path: Path) -> None:
+    reports = [tmp_path / "shard-1.json", tmp_path / "shard-2.json"]
+    shards = await run_shards(buck, reports)
+    assert shards[0].isdisjoint(shards[1])
+    assert shards[0] | shards[1] == set(SHARD_TARGETS)
+
+    # Balancing by the timings of the previous run still runs every target exactly once.
+    timings = [arg for report in reports for arg in ["--shard-timings", str(report)]]
+    shards = await run_shards(
+        buck, [tmp_path / "balanced-1.json", tmp_path / "balanced-2.json"], *timings
+    )
+    assert shards[0].isdisjoint(shards[1])
+    assert shards[0] | shards[1] == set(SHARD_TARGETS)
+
+
+@buck_test()
+async def test_shard_invalid(buck: Buck) -> None:
+    await expect_failure(
+        buck.test(":trivial_pass", "--shard", "3/2"),
+        stderr_regex="Invalid shard `3/2`",
+    )
+
+
+@buck_test()
+@env("BUCK2_ALLOW_INTERNAL_TEST_RUNNER_DO_NOT_USE", "1")
+async def test_internal_test_executor_junit_xml_and_tap(
+    buck: Buck, tmp_path: Path
+) -> None:
+    junit_xml = tmp_path / "junit.xml"
+    tap = tmp_path / "results.tap"
+    await expect_failure(
+        buck.test(
+            ":always_fail",
+            ":trivial_pass",
+            "--junit-xml",
+            str(junit_xml),
+            "--tap",
+            str(tap),
+            test_executor=INTERNAL_TEST_EXECUTOR,
+        ),
+    )
+
+    testsuites = ET.parse(junit_xml).getroot()
+    assert testsuites.get("tests") == "2"
+    assert testsuites.get("failures") == "1"
+    suites = {suite.get("name", "").split(" ")[0]: suite for suite in testsuites}
+    assert set(suites) == {"root//:always_fail", "root//:trivial_pass"}
+    assert suites["root//:always_fail"].find("testcase/failure") is not None
+    assert suites["root//:trivial_pass"].find("testcase/failure") is None
+
+    lines = tap.read_text().splitlines()
+    assert lines[:2] == ["TAP version 13", "1..2"]
+    assert any(
+        line.startswith("not ok ") and "root//:always_fail" in line for line in lines
+    )
+    assert any(
+        line.startswith("ok ") and "root//:trivial_pass" in line for line in lines
+    )